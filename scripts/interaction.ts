@@ -8,8 +8,8 @@
  * 
  * Commands:
  *   init <contract> <owner> <treasury>  - Initialize the contract
- *   commit <contract> <amount> <salt>   - Create a swap commitment
- *   reveal <contract> <token0> <token1> <amount> <salt> <minOut> - Execute swap
+ *   commit <contract> <tokenA> <tokenB> <zeroForOne> <amount> <salt> <deadlineBlock> - Create a swap commitment
+ *   reveal <contract> <tokenA> <tokenB> <zeroForOne> <amount> <salt> <minOut> <deadlineBlock> - Execute swap
  *   addLiquidity <contract> <token0> <token1> <amount0> <amount1> - Add liquidity
  */
 
@@ -24,30 +24,42 @@ const CHAIN_ID = 421614; // Arbitrum Sepolia
 const OAK_PROTOCOL_ABI = [
     "function init(address initialOwner, address treasury) external",
     "function commitSwap(bytes32 hash) external",
-    "function revealSwap(address token0, address token1, uint256 amountIn, uint256 salt, uint256 minAmountOut) external",
-    "function addLiquidity(address token0, address token1, uint256 amount0, uint256 amount1, uint256 amount0Min, uint256 amount1Min) external",
+    "function revealSwap(address tokenA, address tokenB, bool zeroForOne, uint256 amountIn, uint256 salt, uint256 minAmountOut, uint256 deadline) external",
+    "function addLiquidity(address token0, address token1, uint256 amount0, uint256 amount1, uint256 amount0Min, uint256 amount1Min, uint256 deadline) external",
     "function paused() external view returns (bool)",
     "event CommitSwap(address indexed user, bytes32 hash, uint256 blockNumber)",
     "event RevealSwap(address indexed user, uint256 amountIn, uint256 amountOut, uint256 treasuryFee, uint256 lpFee)",
 ];
 
 /**
- * Generate a commitment hash from amount and salt.
+ * Generate the pool-bound commitment hash stored by commitSwap.
  * 
- * @param amountIn - Input token amount (as BigNumber or string)
- * @param salt - Random salt (as BigNumber or string)
- * @returns keccak256 hash of abi.encode(amountIn, salt)
+ * @param tokenA - One pool token (any order)
+ * @param tokenB - The other pool token
+ * @param zeroForOne - True to sell the lower-address token
+ * @param amountIn - Input token amount
+ * @param deadline - Last block number at which the reveal may execute (block number, not timestamp)
+ * @param salt - Random salt
+ * @returns keccak256(pairKey ++ keccak256(abi.encode(amountIn, zeroForOne, deadline, salt))),
+ *          where pairKey = keccak256(abi.encode(token0, token1)) with token0 < token1
  */
-function generateCommitHash(amountIn: ethers.BigNumber, salt: ethers.BigNumber): string {
-    // ABI encode: (uint256, uint256)
+function generateCommitHash(
+    tokenA: string,
+    tokenB: string,
+    zeroForOne: boolean,
+    amountIn: ethers.BigNumber,
+    deadline: ethers.BigNumber,
+    salt: ethers.BigNumber
+): string {
     const encoder = new ethers.utils.AbiCoder();
-    const encoded = encoder.encode(
-        ["uint256", "uint256"],
-        [amountIn, salt]
+    const inner = ethers.utils.keccak256(
+        encoder.encode(["uint256", "bool", "uint256", "uint256"], [amountIn, zeroForOne, deadline, salt])
     );
     
-    // keccak256 hash
-    return ethers.utils.keccak256(encoded);
+    const [token0, token1] = tokenA.toLowerCase() < tokenB.toLowerCase() ? [tokenA, tokenB] : [tokenB, tokenA];
+    const pairKey = ethers.utils.keccak256(encoder.encode(["address", "address"], [token0, token1]));
+    
+    return ethers.utils.keccak256(ethers.utils.concat([pairKey, inner]));
 }
 
 /**
@@ -114,19 +126,29 @@ async function initializeContract(
  */
 async function commitSwap(
     contractAddress: string,
+    tokenA: string,
+    tokenB: string,
+    zeroForOne: boolean,
     amountIn: string,
     salt: string,
+    deadline: string,
     signer: ethers.Signer
 ): Promise<{ hash: string; blockNumber: number }> {
     console.log("\n🔒 Creating swap commitment...");
+    console.log(`   Pool: ${tokenA} / ${tokenB}, zeroForOne: ${zeroForOne}`);
     console.log(`   Amount In: ${amountIn}`);
     console.log(`   Salt: ${salt}`);
-    
-    const amountBn = ethers.BigNumber.from(amountIn);
-    const saltBn = ethers.BigNumber.from(salt);
+    console.log(`   Deadline Block: ${deadline}`);
     
     // Generate commitment hash
-    const commitHash = generateCommitHash(amountBn, saltBn);
+    const commitHash = generateCommitHash(
+        tokenA,
+        tokenB,
+        zeroForOne,
+        ethers.BigNumber.from(amountIn),
+        ethers.BigNumber.from(deadline),
+        ethers.BigNumber.from(salt)
+    );
     console.log(`   Commitment Hash: ${commitHash}`);
     
     const contract = new ethers.Contract(contractAddress, OAK_PROTOCOL_ABI, signer);
@@ -159,29 +181,34 @@ async function commitSwap(
  */
 async function revealSwap(
     contractAddress: string,
-    token0: string,
-    token1: string,
+    tokenA: string,
+    tokenB: string,
+    zeroForOne: boolean,
     amountIn: string,
     salt: string,
     minAmountOut: string,
+    deadline: string,
     signer: ethers.Signer
 ): Promise<void> {
     console.log("\n🔓 Revealing and executing swap...");
-    console.log(`   Token0: ${token0}`);
-    console.log(`   Token1: ${token1}`);
+    console.log(`   Pool: ${tokenA} / ${tokenB}, zeroForOne: ${zeroForOne}`);
     console.log(`   Amount In: ${amountIn}`);
     console.log(`   Salt: ${salt}`);
     console.log(`   Min Amount Out: ${minAmountOut}`);
+    console.log(`   Deadline Block: ${deadline}`);
     
     const contract = new ethers.Contract(contractAddress, OAK_PROTOCOL_ABI, signer);
     
     try {
+        // Must match the committed preimage exactly, including the deadline block.
         const tx = await contract.revealSwap(
-            token0,
-            token1,
+            tokenA,
+            tokenB,
+            zeroForOne,
             amountIn,
             salt,
-            minAmountOut
+            minAmountOut,
+            deadline
         );
         console.log(`📤 Transaction sent: ${tx.hash}`);
         
@@ -221,7 +248,9 @@ async function addLiquidity(
     const contract = new ethers.Contract(contractAddress, OAK_PROTOCOL_ABI, signer);
     
     try {
-        const tx = await contract.addLiquidity(token0, token1, amount0, amount1, amount0Min, amount1Min);
+        // Deadline is a unix timestamp: revert rather than execute at a stale ratio if the tx sits in the mempool.
+        const deadline = Math.floor(Date.now() / 1000) + 300;
+        const tx = await contract.addLiquidity(token0, token1, amount0, amount1, amount0Min, amount1Min, deadline);
         console.log(`📤 Transaction sent: ${tx.hash}`);
         
         const receipt = await tx.wait();
@@ -237,8 +266,8 @@ async function addLiquidity(
  */
 async function completeSwapFlow(
     contractAddress: string,
-    token0: string,
-    token1: string,
+    tokenIn: string,
+    tokenOut: string,
     amountIn: string,
     minAmountOut: string,
     signer: ethers.Signer,
@@ -248,15 +277,21 @@ async function completeSwapFlow(
     console.log("🌳 Oak Protocol: Complete Commit-Reveal Swap Flow");
     console.log("=".repeat(60));
     
-    // Step 1: Generate salt and commitment hash
+    // Step 1: Generate salt, direction and reveal deadline (a block number, bound into the hash)
     const salt = generateSalt();
-    console.log(`\n1️⃣  Generated salt: ${salt.toString()}`);
+    const zeroForOne = tokenIn.toLowerCase() < tokenOut.toLowerCase();
+    const deadline = (await provider.getBlockNumber()) + 100;
+    console.log(`\n1️⃣  Generated salt: ${salt.toString()}, reveal deadline block: ${deadline}`);
     
     // Step 2: Commit swap
-    const { blockNumber: commitBlock } = await commitSwap(
+    await commitSwap(
         contractAddress,
+        tokenIn,
+        tokenOut,
+        zeroForOne,
         amountIn,
         salt.toString(),
+        deadline.toString(),
         signer
     );
     
@@ -266,11 +301,13 @@ async function completeSwapFlow(
     // Step 4: Reveal and execute swap
     await revealSwap(
         contractAddress,
-        token0,
-        token1,
+        tokenIn,
+        tokenOut,
+        zeroForOne,
         amountIn,
         salt.toString(),
         minAmountOut,
+        deadline.toString(),
         signer
     );
     
@@ -291,13 +328,13 @@ Commands:
   init <contract> <owner> <treasury>
     Initialize the contract
     
-  commit <contract> <amount> <salt>
-    Create a swap commitment
+  commit <contract> <tokenA> <tokenB> <zeroForOne> <amount> <salt> <deadlineBlock>
+    Create a swap commitment (deadlineBlock is the last block the reveal may execute in)
     
-  reveal <contract> <token0> <token1> <amount> <salt> <minOut>
-    Execute a swap after revealing
+  reveal <contract> <tokenA> <tokenB> <zeroForOne> <amount> <salt> <minOut> <deadlineBlock>
+    Execute a swap after revealing (same zeroForOne, amount, salt and deadlineBlock as the commit)
     
-  swap <contract> <token0> <token1> <amount> <minOut>
+  swap <contract> <tokenIn> <tokenOut> <amount> <minOut>
     Complete commit-reveal flow (generates salt automatically)
     
   addLiquidity <contract> <token0> <token1> <amount0> <amount1>
@@ -336,24 +373,24 @@ Environment Variables:
                 break;
                 
             case "commit":
-                if (args.length !== 4) {
-                    console.error("Usage: commit <contract> <amount> <salt>");
+                if (args.length !== 8) {
+                    console.error("Usage: commit <contract> <tokenA> <tokenB> <zeroForOne> <amount> <salt> <deadlineBlock>");
                     process.exit(1);
                 }
-                await commitSwap(args[1], args[2], args[3], signer);
+                await commitSwap(args[1], args[2], args[3], args[4] === "true", args[5], args[6], args[7], signer);
                 break;
                 
             case "reveal":
-                if (args.length !== 7) {
-                    console.error("Usage: reveal <contract> <token0> <token1> <amount> <salt> <minOut>");
+                if (args.length !== 9) {
+                    console.error("Usage: reveal <contract> <tokenA> <tokenB> <zeroForOne> <amount> <salt> <minOut> <deadlineBlock>");
                     process.exit(1);
                 }
-                await revealSwap(args[1], args[2], args[3], args[4], args[5], args[6], signer);
+                await revealSwap(args[1], args[2], args[3], args[4] === "true", args[5], args[6], args[7], args[8], signer);
                 break;
                 
             case "swap":
                 if (args.length !== 6) {
-                    console.error("Usage: swap <contract> <token0> <token1> <amount> <minOut>");
+                    console.error("Usage: swap <contract> <tokenIn> <tokenOut> <amount> <minOut>");
                     process.exit(1);
                 }
                 await completeSwapFlow(args[1], args[2], args[3], args[4], args[5], signer, provider);
@@ -1,54 +1,131 @@
-//! Access Control by roles (DEFAULT_ADMIN_ROLE, PAUSER_ROLE, UPGRADER_ROLE).
+//! Access Control by roles (ADMIN, PAUSER, UPGRADER, GUARDIAN, FEE_SETTER, TIMELOCK_ADMIN).
 //!
 //! No_std compatible. Caller is identified via `msg::sender()` (EVM predecessor).
-//! Roles stored in `sol_storage!` as role_hash -> account -> bool.
+//! Roles are stored in `sol_storage!` as one bitmask per account (`role_bits`: account -> U256),
+//! so a role check is a single slot read and an account's roles are one view call.
+//!
+//! ROLE_ADMIN replaces the single owner: it gates every governance setter and manages who holds
+//! which role. Narrow roles split those powers: a guardian can only pause (never unpause), a fee
+//! setter can only call `set_fee`.
 
-use alloc::vec::Vec;
-use stylus_sdk::{alloy_primitives::{Address, FixedBytes}, crypto, msg};
+use stylus_sdk::{
+    alloy_primitives::{Address, FixedBytes, U256},
+    crypto, msg,
+};
 
-use crate::{errors::*, state::OakDEX};
+use crate::{
+    audit::{word, AdminAuditLog},
+    constants::{AUDIT_ACTION_GRANT_ROLE, AUDIT_ACTION_REVOKE_ROLE},
+    errors::*,
+    events::{emit_roles_granted, emit_roles_revoked},
+    state::OakDEX,
+};
 
-/// Role identifiers (keccak256 of role name), as bytes32.
-pub fn default_admin_role() -> FixedBytes<32> {
-    crypto::keccak(b"DEFAULT_ADMIN_ROLE")
-}
-pub fn pauser_role() -> FixedBytes<32> {
-    crypto::keccak(b"PAUSER_ROLE")
-}
-pub fn upgrader_role() -> FixedBytes<32> {
-    crypto::keccak(b"UPGRADER_ROLE")
-}
+/// Role bits. A role argument may combine several (e.g. `ROLE_ADMIN | ROLE_FEE_SETTER`).
+pub const ROLE_ADMIN: U256 = U256::from_limbs([1, 0, 0, 0]);
+pub const ROLE_PAUSER: U256 = U256::from_limbs([1 << 1, 0, 0, 0]);
+pub const ROLE_UPGRADER: U256 = U256::from_limbs([1 << 2, 0, 0, 0]);
+/// Emergency-only role: may pause, may not unpause.
+pub const ROLE_GUARDIAN: U256 = U256::from_limbs([1 << 3, 0, 0, 0]);
+/// May change the protocol swap fee (`set_fee`) and nothing else.
+pub const ROLE_FEE_SETTER: U256 = U256::from_limbs([1 << 4, 0, 0, 0]);
+/// May queue timelock operations.
+pub const ROLE_TIMELOCK_ADMIN: U256 = U256::from_limbs([1 << 5, 0, 0, 0]);
+/// Every defined role bit.
+pub const ROLE_ALL: U256 = U256::from_limbs([(1 << 6) - 1, 0, 0, 0]);
 
-/// Returns true if `account` has `role`. Uses getter for read-only access.
+/// True if `held` contains at least one of the bits in `required`.
 #[inline]
-pub fn has_role(dex: &OakDEX, role: FixedBytes<32>, account: Address) -> bool {
-    dex.roles.getter(role).getter(account).get()
+pub fn holds_any(held: U256, required: U256) -> bool {
+    !(held & required).is_zero()
 }
 
-/// Requires that `msg::sender()` has `role`; otherwise returns `ERR_MISSING_ROLE`.
-pub fn require_role(dex: &OakDEX, role: FixedBytes<32>) -> Result<(), Vec<u8>> {
-    let caller = msg::sender();
-    if has_role(dex, role, caller) {
+/// Reverts with `ERR_MISSING_ROLE` unless `held` contains one of the bits in `required`.
+pub fn check_roles(held: U256, required: U256) -> OakResult<()> {
+    if holds_any(held, required) {
         Ok(())
     } else {
         Err(err(ERR_MISSING_ROLE))
     }
 }
 
-/// Grants `role` to `account`. Caller must have DEFAULT_ADMIN_ROLE (or same role for renounce).
+/// Role bits after granting (`grant`) or revoking `roles` on `held`. Reverts with
+/// `ERR_INVALID_ROLE` for an empty mask or undefined bits.
+pub fn updated_roles(held: U256, roles: U256, grant: bool) -> OakResult<U256> {
+    if roles.is_zero() || roles & !ROLE_ALL != U256::ZERO {
+        return Err(err(ERR_INVALID_ROLE));
+    }
+    Ok(if grant { held | roles } else { held & !roles })
+}
+
+/// Role bitmask held by `account`.
+#[inline]
+pub fn roles_of(dex: &OakDEX, account: Address) -> U256 {
+    dex.role_bits.get(account)
+}
+
+/// Returns true if `account` holds every bit of `role`.
+#[inline]
+pub fn has_role(dex: &OakDEX, role: U256, account: Address) -> bool {
+    !role.is_zero() && roles_of(dex, account) & role == role
+}
+
+/// Requires that `msg::sender()` holds one of the roles in `roles`; otherwise `ERR_MISSING_ROLE`.
+pub fn require_role(dex: &OakDEX, roles: U256) -> OakResult<()> {
+    check_roles(roles_of(dex, msg::sender()), roles)
+}
+
+/// Grants `roles` to `account`. Caller must have ROLE_ADMIN.
 /// CEI: effects (storage) before no external calls.
-pub fn grant_role(dex: &mut OakDEX, role: FixedBytes<32>, account: Address) -> Result<(), Vec<u8>> {
+pub fn grant_role(dex: &mut OakDEX, roles: U256, account: Address) -> OakResult<()> {
     if account == Address::ZERO {
         return Err(err(ERR_GRANT_ZERO));
     }
-    require_role(dex, default_admin_role())?;
-    dex.roles.setter(role).setter(account).set(true);
+    require_role(dex, ROLE_ADMIN)?;
+    let updated = updated_roles(roles_of(dex, account), roles, true)?;
+    AdminAuditLog::record(dex, AUDIT_ACTION_GRANT_ROLE, &[word(roles), account.into_word()])?;
+    dex.role_bits.setter(account).set(updated);
+    emit_roles_granted(account, roles, msg::sender());
     Ok(())
 }
 
-/// Revokes `role` from `account`. Caller must have DEFAULT_ADMIN_ROLE.
-pub fn revoke_role(dex: &mut OakDEX, role: FixedBytes<32>, account: Address) -> Result<(), Vec<u8>> {
-    require_role(dex, default_admin_role())?;
-    dex.roles.setter(role).setter(account).set(false);
+/// Revokes `roles` from `account`. Caller must have ROLE_ADMIN.
+pub fn revoke_role(dex: &mut OakDEX, roles: U256, account: Address) -> OakResult<()> {
+    require_role(dex, ROLE_ADMIN)?;
+    let updated = updated_roles(roles_of(dex, account), roles, false)?;
+    AdminAuditLog::record(dex, AUDIT_ACTION_REVOKE_ROLE, &[word(roles), account.into_word()])?;
+    dex.role_bits.setter(account).set(updated);
+    emit_roles_revoked(account, roles, msg::sender());
     Ok(())
 }
+
+/// Moves `account`'s grants from the legacy role_hash -> account -> bool map into its bitmask.
+///
+/// @dev Only copies what governance already granted, so anyone may call it; the legacy entries
+///      are cleared so a later revoke cannot be undone by migrating again.
+pub fn migrate_legacy_roles(dex: &mut OakDEX, account: Address) -> U256 {
+    let legacy: [(&[u8], U256); 6] = [
+        (b"DEFAULT_ADMIN_ROLE", ROLE_ADMIN),
+        (b"PAUSER_ROLE", ROLE_PAUSER),
+        (b"UPGRADER_ROLE", ROLE_UPGRADER),
+        (b"GUARDIAN_ROLE", ROLE_GUARDIAN),
+        (b"FEE_SETTER_ROLE", ROLE_FEE_SETTER),
+        (b"TIMELOCK_ADMIN_ROLE", ROLE_TIMELOCK_ADMIN),
+    ];
+    let mut migrated = U256::ZERO;
+    for (name, bit) in legacy {
+        let role: FixedBytes<32> = crypto::keccak(name);
+        let mut entry = dex.roles.setter(role);
+        let mut held = entry.setter(account);
+        if held.get() {
+            held.set(false);
+            migrated |= bit;
+        }
+    }
+    if !migrated.is_zero() {
+        let updated = roles_of(dex, account) | migrated;
+        dex.role_bits.setter(account).set(updated);
+        emit_roles_granted(account, migrated, msg::sender());
+    }
+    migrated
+}
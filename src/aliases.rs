@@ -0,0 +1,97 @@
+//! Token aliases: canonical and bridged representations of one asset (e.g. USDC vs USDC.e).
+//!
+//! Governance maps each alias to its canonical token; the mapping is one level deep (a
+//! canonical token is never itself an alias). Pools stay keyed by the real token addresses,
+//! so a USDC/USDC.e pool is still an ordinary pair. The router resolves every path token to its
+//! canonical asset and rejects paths that come back to an asset they already left, whichever
+//! representation they return through; converting between representations is one direct hop.
+
+use alloc::vec::Vec;
+
+use stylus_sdk::alloy_primitives::{Address, U256};
+
+use crate::errors::{err, OakResult, ERR_ALIAS_NOT_FOUND, ERR_INVALID_ALIAS, ERR_OVERFLOW, ERR_PATH_REVISITS_ASSET};
+use crate::events::{emit_token_alias_removed, emit_token_alias_set};
+use crate::state::OakDEX;
+
+/// True if an asset in `assets` reappears after at least one other asset in between.
+pub fn revisits_asset(assets: &[Address]) -> bool {
+    assets
+        .iter()
+        .enumerate()
+        .any(|(i, asset)| assets.iter().skip(i + 2).any(|later| later == asset))
+}
+
+/// Token alias registry (uses OakDEX token_alias* storage).
+pub struct TokenAliases;
+
+impl TokenAliases {
+    /// Canonical token of `token` (itself when it is not an alias).
+    pub fn canonical(dex: &OakDEX, token: Address) -> Address {
+        if dex.token_alias_set.get(token) {
+            dex.token_alias.get(token)
+        } else {
+            token
+        }
+    }
+
+    /// Whether `token` is registered as an alias.
+    pub fn is_alias(dex: &OakDEX, token: Address) -> bool {
+        dex.token_alias_set.get(token)
+    }
+
+    /// Map `alias` to `canonical`, replacing any previous mapping of `alias`.
+    ///
+    /// @dev `canonical` may be native ETH (address(0)). Rejects self-aliases, a `canonical`
+    ///      that is an alias, and an `alias` that other tokens already point to.
+    pub fn set(dex: &mut OakDEX, alias: Address, canonical: Address) -> OakResult<()> {
+        if alias == canonical || Self::is_alias(dex, canonical) || !dex.token_alias_count.get(alias).is_zero() {
+            return Err(err(ERR_INVALID_ALIAS));
+        }
+        if Self::is_alias(dex, alias) {
+            Self::release(dex, alias);
+        }
+        let count = dex
+            .token_alias_count
+            .get(canonical)
+            .checked_add(U256::from(1u64))
+            .ok_or_else(|| err(ERR_OVERFLOW))?;
+        dex.token_alias_count.setter(canonical).set(count);
+        dex.token_alias.setter(alias).set(canonical);
+        dex.token_alias_set.setter(alias).set(true);
+        emit_token_alias_set(alias, canonical);
+        Ok(())
+    }
+
+    /// Remove the mapping of `alias`; it is treated as its own asset again.
+    pub fn remove(dex: &mut OakDEX, alias: Address) -> OakResult<()> {
+        if !Self::is_alias(dex, alias) {
+            return Err(err(ERR_ALIAS_NOT_FOUND));
+        }
+        let canonical = Self::release(dex, alias);
+        emit_token_alias_removed(alias, canonical);
+        Ok(())
+    }
+
+    /// Clear `alias` and decrement its canonical token's alias count; returns the canonical.
+    fn release(dex: &mut OakDEX, alias: Address) -> Address {
+        let canonical = dex.token_alias.get(alias);
+        let count = dex.token_alias_count.get(canonical).saturating_sub(U256::from(1u64));
+        dex.token_alias_count.setter(canonical).set(count);
+        dex.token_alias.setter(alias).set(Address::ZERO);
+        dex.token_alias_set.setter(alias).set(false);
+        canonical
+    }
+
+    /// Revert if `path` returns to an asset it already left (canonical tokens compared).
+    ///
+    /// @notice Adjacent tokens may share an asset (a direct alias conversion hop); identical
+    ///         adjacent tokens are left to the caller's own hop checks.
+    pub fn require_acyclic_path(dex: &OakDEX, path: &[Address]) -> OakResult<()> {
+        let assets: Vec<Address> = path.iter().map(|&token| Self::canonical(dex, token)).collect();
+        if revisits_asset(&assets) {
+            return Err(err(ERR_PATH_REVISITS_ASSET));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,419 @@
+//! Batch auction: reveals collected over a window settle together at one uniform clearing price.
+//!
+//! With a batch window configured, `reveal_swap_to_batch` verifies the caller's commitment,
+//! takes the input into the contract and queues the order in the pool's current batch instead
+//! of swapping. Once the window has closed anyone may call `settle_batch`. Every order in the
+//! batch then trades at the same price, so the order of reveals inside a window is irrelevant.
+//!
+//! Clearing (CowSwap style, against the CPMM curve): opposite flows are crossed with each other
+//! and only the net ("residual") flow of the heavier side goes through the pool as one swap. The
+//! clearing price `p` (token1 per token0) is the one at which the residual trade itself
+//! executes at `p` on the fee-adjusted curve. For net token0 flow `x` in with `y` of token1
+//! offered against it (fee factor `g = 1 - fee`):
+//!
+//! ```text
+//! p = g * (reserve1 + y) / (reserve0 + g * x)      residual = x - y / p
+//! ```
+//!
+//! Orders whose limit is worse than `p` are left out and refunded, and `p` is recomputed until
+//! every remaining order is satisfied. If the residual would exceed the MAX_TRADE_RESERVE_BPS
+//! trade cap, the heavy side is filled only up to the cap, earliest commitment first
+//! (`allocate_fills_by_priority`); unfilled input is refunded. Proceeds and refunds are credited
+//! to the owners' claimable balances (`claim_output`), so one failing recipient cannot block a
+//! settlement. Rounding dust goes to the treasury.
+
+use alloc::vec::Vec;
+
+use stylus_sdk::{alloy_primitives::{Address, U256}, block, contract};
+
+use crate::constants::{as_u256, BATCH_RECLAIM_DELAY_BLOCKS, BPS, MAX_BATCH_ORDERS, MAX_BATCH_WINDOW_BLOCKS, MAX_TRADE_RESERVE_BPS};
+use crate::errors::{
+    err, MathResult, OakResult, ERR_BATCH_FULL, ERR_BATCH_MODE_DISABLED, ERR_BATCH_NOT_FOUND,
+    ERR_BATCH_ORDER_NOT_RECLAIMABLE, ERR_BATCH_SETTLED, ERR_BATCH_WINDOW_OPEN, ERR_DIVISION_BY_ZERO,
+    ERR_INSUFFICIENT_LIQUIDITY, ERR_INVALID_BATCH_WINDOW, ERR_INVALID_TOKEN, ERR_OVERFLOW, ERR_POOL_PAUSED,
+};
+use crate::escrow::OutputEscrow;
+use crate::events::{emit_batch_order_reclaimed, emit_batch_order_revealed, emit_batch_settled, emit_batch_window_set};
+use crate::logic::{allocate_fills_by_priority, process_swap_from_to_with_fee, pull_received};
+use crate::state::OakDEX;
+use crate::token::safe_transfer;
+
+/// (zero_for_one, amount_in, min_amount_out, priority) of one batch order.
+pub type BatchOrderInput = (bool, U256, U256, U256);
+
+/// Result of clearing a batch against the pool.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BatchClearing {
+    /// Input used per order (input order); the rest is refunded.
+    pub fills: Vec<U256>,
+    /// Output paid per order.
+    pub payouts: Vec<U256>,
+    /// Uniform clearing price, token1 per token0 = price_num / price_den (0 / 0 if nothing cleared).
+    pub price_num: U256,
+    pub price_den: U256,
+    /// Direction of the net flow routed through the pool (true = token0 in).
+    pub residual_zero_for_one: bool,
+    /// Net input swapped on the curve.
+    pub residual_in: U256,
+    /// Curve output needed, on top of the crossed volume, to pay the heavy side.
+    pub residual_out_needed: U256,
+}
+
+impl BatchClearing {
+    fn empty(n: usize) -> Self {
+        Self {
+            fills: alloc::vec![U256::ZERO; n],
+            payouts: alloc::vec![U256::ZERO; n],
+            price_num: U256::ZERO,
+            price_den: U256::ZERO,
+            residual_zero_for_one: true,
+            residual_in: U256::ZERO,
+            residual_out_needed: U256::ZERO,
+        }
+    }
+}
+
+fn mul(a: U256, b: U256) -> MathResult<U256> {
+    a.checked_mul(b).ok_or(ERR_OVERFLOW)
+}
+
+fn add(a: U256, b: U256) -> MathResult<U256> {
+    a.checked_add(b).ok_or(ERR_OVERFLOW)
+}
+
+fn div(a: U256, b: U256) -> MathResult<U256> {
+    a.checked_div(b).ok_or(ERR_DIVISION_BY_ZERO)
+}
+
+/// Clearing price for net flow of `x` into the pool against `y` offered by the other side:
+/// y-per-x as (num, den) with fee factor `g / BPS`.
+fn heavy_price(x: U256, y: U256, reserve_x: U256, reserve_y: U256, g: U256) -> MathResult<(U256, U256)> {
+    let num = mul(g, add(reserve_y, y)?)?;
+    let den = add(mul(as_u256(BPS), reserve_x)?, mul(g, x)?)?;
+    Ok((num, den))
+}
+
+/// Heavy-side input left for the curve once `y` is crossed at num/den (crossed x rounds down).
+fn residual_of(x: U256, y: U256, num: U256, den: U256) -> MathResult<U256> {
+    Ok(x.saturating_sub(div(mul(y, den)?, num)?))
+}
+
+/// Clear a batch at one uniform price (see module docs).
+///
+/// @param orders (zero_for_one, amount_in, min_amount_out, priority); zero amounts are skipped.
+/// @param fee_bps Swap fee of the pool; `max_trade_bps` caps the residual as a share of its reserve.
+pub fn clear_batch(
+    orders: &[BatchOrderInput],
+    reserve0: U256,
+    reserve1: U256,
+    fee_bps: U256,
+    max_trade_bps: U256,
+) -> MathResult<BatchClearing> {
+    if reserve0.is_zero() || reserve1.is_zero() {
+        return Err(ERR_INSUFFICIENT_LIQUIDITY);
+    }
+    let bps = as_u256(BPS);
+    let g = bps.checked_sub(fee_bps).ok_or(ERR_OVERFLOW)?;
+    if g.is_zero() {
+        return Err(ERR_DIVISION_BY_ZERO);
+    }
+    let n = orders.len();
+    let mut included: Vec<bool> = orders.iter().map(|o| !o.1.is_zero()).collect();
+
+    // Each pass either clears or permanently drops at least one order, so n + 1 passes suffice.
+    for _ in 0..=n {
+        let (mut total0, mut total1) = (U256::ZERO, U256::ZERO);
+        for (o, _) in orders.iter().zip(&included).filter(|(_, inc)| **inc) {
+            if o.0 {
+                total0 = add(total0, o.1)?;
+            } else {
+                total1 = add(total1, o.1)?;
+            }
+        }
+        if total0.is_zero() && total1.is_zero() {
+            return Ok(BatchClearing::empty(n));
+        }
+
+        // Heavy side: the one worth more than the other at the fee-adjusted spot price.
+        let zero_heavy = mul(mul(g, total0)?, reserve1)? >= mul(mul(bps, total1)?, reserve0)?;
+        let (x, y, reserve_x, reserve_y) =
+            if zero_heavy { (total0, total1, reserve0, reserve1) } else { (total1, total0, reserve1, reserve0) };
+        let (mut num, mut den) = heavy_price(x, y, reserve_x, reserve_y, g)?;
+        let mut x_fill = x;
+        let mut residual = residual_of(x, y, num, den)?;
+        let cap = div(mul(reserve_x, max_trade_bps)?, bps)?;
+        if residual > cap {
+            // Largest heavy fill whose residual stays within the cap (one wei of rounding slack):
+            // x_fill = (cap * (reserve_y + y) + y * reserve_x / g) / reserve_y.
+            let target = cap.saturating_sub(U256::from(1u64));
+            let crossed_term = div(mul(mul(y, reserve_x)?, bps)?, g)?;
+            x_fill = div(add(mul(target, add(reserve_y, y)?)?, crossed_term)?, reserve_y)?.min(x);
+            (num, den) = heavy_price(x_fill, y, reserve_x, reserve_y, g)?;
+            residual = residual_of(x_fill, y, num, den)?;
+        }
+
+        // Limits: heavy orders receive num/den per unit, light orders den/num.
+        let mut dropped = false;
+        for (i, o) in orders.iter().enumerate() {
+            if !included[i] {
+                continue;
+            }
+            let ok = if o.0 == zero_heavy {
+                mul(o.1, num)? >= mul(o.2, den)?
+            } else {
+                mul(o.1, den)? >= mul(o.2, num)?
+            };
+            if !ok {
+                included[i] = false;
+                dropped = true;
+            }
+        }
+        if dropped {
+            continue;
+        }
+
+        // Heavy side filled up to x_fill in commitment order; light side in full.
+        let queue: Vec<(U256, U256)> = orders
+            .iter()
+            .zip(&included)
+            .map(|(o, inc)| if *inc && o.0 == zero_heavy { (o.3, o.1) } else { (o.3, U256::ZERO) })
+            .collect();
+        let mut fills = allocate_fills_by_priority(&queue, x_fill);
+        let mut payouts = alloc::vec![U256::ZERO; n];
+        let mut heavy_paid = U256::ZERO;
+        for (i, o) in orders.iter().enumerate() {
+            if !included[i] {
+                continue;
+            }
+            if o.0 == zero_heavy {
+                payouts[i] = div(mul(fills[i], num)?, den)?;
+                heavy_paid = add(heavy_paid, payouts[i])?;
+            } else {
+                fills[i] = o.1;
+                payouts[i] = div(mul(o.1, den)?, num)?;
+            }
+        }
+        let (price_num, price_den) = if zero_heavy { (num, den) } else { (den, num) };
+        return Ok(BatchClearing {
+            fills,
+            payouts,
+            price_num,
+            price_den,
+            residual_zero_for_one: zero_heavy,
+            residual_in: residual,
+            residual_out_needed: heavy_paid.saturating_sub(y),
+        });
+    }
+    Ok(BatchClearing::empty(n))
+}
+
+/// Batch auction (uses OakDEX batch_* / swap_batches storage).
+pub struct BatchAuction;
+
+impl BatchAuction {
+    /// Owner sets the batch window in blocks (0 turns batch mode off; open batches still settle).
+    pub fn set_window(dex: &mut OakDEX, blocks: U256) -> OakResult<()> {
+        if blocks > as_u256(MAX_BATCH_WINDOW_BLOCKS) {
+            return Err(err(ERR_INVALID_BATCH_WINDOW));
+        }
+        dex.batch_window_blocks.set(blocks);
+        emit_batch_window_set(blocks);
+        Ok(())
+    }
+
+    /// Queue a verified reveal into the pool's current batch, pulling `amount_in` from `owner`.
+    ///
+    /// @notice The order and the escrow record the amount that actually arrived (fee-on-transfer
+    ///         tokens deliver less than `amount_in`).
+    /// @return The batch id (the block at which its window closes).
+    #[allow(clippy::too_many_arguments)]
+    pub fn enqueue(
+        dex: &mut OakDEX,
+        owner: Address,
+        token0: Address,
+        token1: Address,
+        zero_for_one: bool,
+        amount_in: U256,
+        min_amount_out: U256,
+        priority: U256,
+    ) -> OakResult<U256> {
+        let window = dex.batch_window_blocks.get();
+        if window.is_zero() {
+            return Err(err(ERR_BATCH_MODE_DISABLED));
+        }
+        {
+            let outer = dex.pools.getter(token0);
+            let pool = outer.getter(token1);
+            if !pool.initialized.get() {
+                return Err(err(ERR_INVALID_TOKEN));
+            }
+            if pool.paused.get() {
+                return Err(err(ERR_POOL_PAUSED));
+            }
+        }
+        // Keyed by the closing block: a new window always closes after any settled one.
+        let now = U256::from(block::number());
+        let batch_id = (now / window + U256::from(1u64)) * window;
+
+        let token_in = if zero_for_one { token0 } else { token1 };
+        // Fee-on-transfer tokens: the order is for what actually arrived.
+        let amount_in = pull_received(dex, (token0, token1), token_in, owner, amount_in)?;
+        let escrowed = dex
+            .batch_escrowed
+            .get(token_in)
+            .checked_add(amount_in)
+            .ok_or_else(|| err(ERR_OVERFLOW))?;
+        dex.batch_escrowed.setter(token_in).set(escrowed);
+
+        let mut outer = dex.swap_batches.setter(token0);
+        let mut middle = outer.setter(token1);
+        let mut batch = middle.setter(batch_id);
+        let index = batch.count.get();
+        if index >= as_u256(MAX_BATCH_ORDERS) {
+            return Err(err(ERR_BATCH_FULL));
+        }
+        batch.count.set(index + U256::from(1u64));
+        let mut order = batch.orders.setter(index);
+        order.owner.set(owner);
+        order.zero_for_one.set(zero_for_one);
+        order.amount_in.set(amount_in);
+        order.min_amount_out.set(min_amount_out);
+        order.priority.set(priority);
+
+        emit_batch_order_revealed(owner, token0, token1, batch_id, index, zero_for_one, amount_in);
+        Ok(batch_id)
+    }
+
+    /// Settle a closed batch at its uniform clearing price (anyone may call).
+    ///
+    /// @return The clearing price as (price_num, price_den), token1 per token0.
+    pub fn settle(dex: &mut OakDEX, token0: Address, token1: Address, batch_id: U256) -> OakResult<(U256, U256)> {
+        let mut owners: Vec<Address> = Vec::new();
+        let mut orders: Vec<BatchOrderInput> = Vec::new();
+        {
+            let mut outer = dex.swap_batches.setter(token0);
+            let mut middle = outer.setter(token1);
+            let mut batch = middle.setter(batch_id);
+            let count = batch.count.get();
+            if count.is_zero() {
+                return Err(err(ERR_BATCH_NOT_FOUND));
+            }
+            if batch.settled.get() {
+                return Err(err(ERR_BATCH_SETTLED));
+            }
+            if U256::from(block::number()) < batch_id {
+                return Err(err(ERR_BATCH_WINDOW_OPEN));
+            }
+            batch.settled.set(true);
+            let n: usize = count.to::<usize>();
+            for i in 0..n {
+                let order = batch.orders.getter(U256::from(i));
+                owners.push(order.owner.get());
+                orders.push((
+                    order.zero_for_one.get(),
+                    order.amount_in.get(),
+                    order.min_amount_out.get(),
+                    order.priority.get(),
+                ));
+            }
+        }
+
+        let (reserve0, reserve1) = {
+            let outer = dex.pools.getter(token0);
+            let pool = outer.getter(token1);
+            (pool.reserve0.get(), pool.reserve1.get())
+        };
+        let fee_bps = crate::logic::pool_fee_bps(dex, token0, token1);
+        let clearing =
+            clear_batch(&orders, reserve0, reserve1, fee_bps, as_u256(MAX_TRADE_RESERVE_BPS)).map_err(err)?;
+
+        // Net flow through the curve; the output stays in the contract for distribution.
+        let (residual_in_token, residual_out_token) =
+            if clearing.residual_zero_for_one { (token0, token1) } else { (token1, token0) };
+        let mut residual_out = U256::ZERO;
+        if !clearing.residual_in.is_zero() {
+            let contract_addr = contract::address();
+            residual_out = process_swap_from_to_with_fee(
+                dex,
+                contract_addr,
+                contract_addr,
+                residual_in_token,
+                residual_out_token,
+                clearing.residual_in,
+                clearing.residual_out_needed.max(U256::from(1u64)),
+                fee_bps,
+            )?;
+        }
+
+        // Per-token conservation: everything received minus everything paid out is dust.
+        let mut received = [U256::ZERO; 2];
+        let mut paid = [U256::ZERO; 2];
+        for (i, o) in orders.iter().enumerate() {
+            let (side_in, side_out, token_in, token_out) =
+                if o.0 { (0, 1, token0, token1) } else { (1, 0, token1, token0) };
+            let refund = o.1.saturating_sub(clearing.fills[i]);
+            received[side_in] = received[side_in].checked_add(o.1).ok_or_else(|| err(ERR_OVERFLOW))?;
+            paid[side_in] = paid[side_in].checked_add(refund).ok_or_else(|| err(ERR_OVERFLOW))?;
+            paid[side_out] = paid[side_out].checked_add(clearing.payouts[i]).ok_or_else(|| err(ERR_OVERFLOW))?;
+            OutputEscrow::credit(dex, owners[i], token_in, refund)?;
+            OutputEscrow::credit(dex, owners[i], token_out, clearing.payouts[i])?;
+        }
+        let (side_in, side_out) = if clearing.residual_zero_for_one { (0, 1) } else { (1, 0) };
+        paid[side_in] = paid[side_in].checked_add(clearing.residual_in).ok_or_else(|| err(ERR_OVERFLOW))?;
+        received[side_out] = received[side_out].checked_add(residual_out).ok_or_else(|| err(ERR_OVERFLOW))?;
+
+        for (side, token) in [(0, token0), (1, token1)] {
+            let escrowed = dex.batch_escrowed.get(token);
+            let order_input = orders
+                .iter()
+                .filter(|o| o.0 == (side == 0))
+                .try_fold(U256::ZERO, |acc, o| acc.checked_add(o.1))
+                .ok_or_else(|| err(ERR_OVERFLOW))?;
+            dex.batch_escrowed.setter(token).set(escrowed.saturating_sub(order_input));
+            let dust = received[side].checked_sub(paid[side]).ok_or_else(|| err(ERR_OVERFLOW))?;
+            if !dust.is_zero() {
+                let treasury = dex.treasury_balance.get(token).checked_add(dust).ok_or_else(|| err(ERR_OVERFLOW))?;
+                dex.treasury_balance.setter(token).set(treasury);
+            }
+        }
+
+        emit_batch_settled(token0, token1, batch_id, clearing.price_num, clearing.price_den, clearing.residual_in);
+        Ok((clearing.price_num, clearing.price_den))
+    }
+
+    /// Return an order's input when its batch was not settled within BATCH_RECLAIM_DELAY_BLOCKS.
+    pub fn reclaim(dex: &mut OakDEX, token0: Address, token1: Address, batch_id: U256, index: U256) -> OakResult<U256> {
+        let caller = stylus_sdk::msg::sender();
+        let reclaim_after = batch_id
+            .checked_add(as_u256(BATCH_RECLAIM_DELAY_BLOCKS))
+            .ok_or_else(|| err(ERR_OVERFLOW))?;
+        let (token_in, amount) = {
+            let mut outer = dex.swap_batches.setter(token0);
+            let mut middle = outer.setter(token1);
+            let mut batch = middle.setter(batch_id);
+            if batch.settled.get() || index >= batch.count.get() || U256::from(block::number()) <= reclaim_after {
+                return Err(err(ERR_BATCH_ORDER_NOT_RECLAIMABLE));
+            }
+            let mut order = batch.orders.setter(index);
+            let amount = order.amount_in.get();
+            if order.owner.get() != caller || amount.is_zero() {
+                return Err(err(ERR_BATCH_ORDER_NOT_RECLAIMABLE));
+            }
+            order.amount_in.set(U256::ZERO);
+            (if order.zero_for_one.get() { token0 } else { token1 }, amount)
+        };
+        let escrowed = dex.batch_escrowed.get(token_in);
+        dex.batch_escrowed.setter(token_in).set(escrowed.saturating_sub(amount));
+        safe_transfer(token_in, caller, amount)?;
+        emit_batch_order_reclaimed(caller, batch_id, index, amount);
+        Ok(amount)
+    }
+
+    /// View: (order count, settled) of a batch.
+    pub fn info(dex: &OakDEX, token0: Address, token1: Address, batch_id: U256) -> (U256, bool) {
+        let outer = dex.swap_batches.getter(token0);
+        let middle = outer.getter(token1);
+        let batch = middle.getter(batch_id);
+        (batch.count.get(), batch.settled.get())
+    }
+}
@@ -0,0 +1,94 @@
+//! Owner audit log: append-only on-chain record of every privileged action.
+//!
+//! Events are enough while the history is indexed, but nodes prune logs and indexers come and
+//! go. Each fee change, pause, withdrawal, role change and configuration update therefore also
+//! appends (action code, actor, parameters hash, block) to storage. Entries are never modified
+//! or deleted, so governance behavior can be replayed from state alone; the parameters hash is
+//! checked against the calldata (or the matching event) of the block it names.
+
+use stylus_sdk::{
+    alloy_primitives::{Address, FixedBytes, U256},
+    block, crypto, msg,
+};
+
+use alloc::vec::Vec;
+
+use crate::constants::{as_u256, MAX_PAGE_SIZE};
+use crate::errors::{err, OakResult, ERR_AUDIT_RECORD_NOT_FOUND, ERR_OVERFLOW, ERR_TOO_MANY_ITEMS};
+use crate::state::OakDEX;
+
+/// (action, actor, params_hash, block_number).
+pub type AuditRecordView = (U256, Address, FixedBytes<32>, U256);
+
+/// ABI word of a `uint256` parameter.
+pub fn word(value: U256) -> FixedBytes<32> {
+    FixedBytes::from(value.to_be_bytes::<32>())
+}
+
+/// ABI word of a `bool` parameter.
+pub fn bool_word(value: bool) -> FixedBytes<32> {
+    word(U256::from(value as u64))
+}
+
+/// `keccak256(abi.encode(params...))` for static parameters given as words.
+pub fn compute_params_hash(params: &[FixedBytes<32>]) -> FixedBytes<32> {
+    let mut encoded = Vec::with_capacity(params.len() * 32);
+    for param in params {
+        encoded.extend_from_slice(param.as_slice());
+    }
+    crypto::keccak(encoded)
+}
+
+/// Owner audit log (uses OakDEX audit_log* storage).
+pub struct AdminAuditLog;
+
+impl AdminAuditLog {
+    /// Append `action` by msg.sender with its parameters. Call after the authorization check.
+    pub fn record(dex: &mut OakDEX, action: u64, params: &[FixedBytes<32>]) -> OakResult<()> {
+        let index = dex.audit_log_len.get();
+        let next = index.checked_add(U256::from(1u64)).ok_or_else(|| err(ERR_OVERFLOW))?;
+        let mut entry = dex.audit_log.setter(index);
+        entry.action.set(as_u256(action));
+        entry.actor.set(msg::sender());
+        entry.params_hash.set(U256::from_be_bytes(compute_params_hash(params).0));
+        entry.block_number.set(U256::from(block::number()));
+        dex.audit_log_len.set(next);
+        Ok(())
+    }
+
+    /// Number of entries in the log.
+    pub fn len(dex: &OakDEX) -> U256 {
+        dex.audit_log_len.get()
+    }
+
+    /// Up to `limit` entries starting at `offset` (oldest first).
+    ///
+    /// @dev `limit` at most MAX_PAGE_SIZE; an offset past the end returns an empty page.
+    pub fn page(dex: &OakDEX, offset: U256, limit: U256) -> OakResult<Vec<AuditRecordView>> {
+        if limit > as_u256(MAX_PAGE_SIZE) {
+            return Err(err(ERR_TOO_MANY_ITEMS));
+        }
+        let end = Self::len(dex).min(offset.saturating_add(limit));
+        let mut entries = Vec::new();
+        let mut index = offset;
+        while index < end {
+            entries.push(Self::entry(dex, index)?);
+            index += U256::from(1u64);
+        }
+        Ok(entries)
+    }
+
+    /// Entry `index` of the log.
+    pub fn entry(dex: &OakDEX, index: U256) -> OakResult<AuditRecordView> {
+        if index >= Self::len(dex) {
+            return Err(err(ERR_AUDIT_RECORD_NOT_FOUND));
+        }
+        let entry = dex.audit_log.getter(index);
+        Ok((
+            entry.action.get(),
+            entry.actor.get(),
+            FixedBytes::from(entry.params_hash.get().to_be_bytes::<32>()),
+            entry.block_number.get(),
+        ))
+    }
+}
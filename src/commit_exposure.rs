@@ -0,0 +1,74 @@
+//! Pending commitment exposure: the worst-case flow unrevealed commitments can send into a pool.
+//!
+//! A commitment is only a hash, so its size is invisible until the reveal. A trader may opt in
+//! with `commit_swap_with_exposure` and declare the pool, the direction and an upper bound on the
+//! input. Declarations are summed per pool and direction while the commitment is pending and
+//! released when it is revealed, cancelled, slashed or replaced after expiry. Reveals are held to
+//! the declaration, so the totals bound the declared flow that can still hit the pool. Expired
+//! commitments stay counted until cleared (`slash_expired_commitment`), which keeps the figure
+//! an upper bound. Undeclared commitments are not included.
+
+use stylus_sdk::alloy_primitives::{Address, FixedBytes, U256};
+
+use crate::errors::{err, OakResult, ERR_EXCEEDS_DECLARED_EXPOSURE, ERR_OVERFLOW};
+use crate::events::emit_commit_exposure_declared;
+use crate::state::OakDEX;
+
+/// Revert with EXCEEDS_DECLARED_EXPOSURE unless a reveal of `amount_in` in direction
+/// `zero_for_one` stays within the declaration.
+///
+/// @dev `declared_in` = 0 means nothing was declared: any reveal is within it.
+pub fn check_declared(declared_in: U256, declared_zero_for_one: bool, zero_for_one: bool, amount_in: U256) -> OakResult<()> {
+    if declared_in.is_zero() || (declared_zero_for_one == zero_for_one && amount_in <= declared_in) {
+        return Ok(());
+    }
+    Err(err(ERR_EXCEEDS_DECLARED_EXPOSURE))
+}
+
+/// Pending commitment exposure ledger (uses CommitmentSlot exposure_* and OakDEX pending_exposure).
+pub struct CommitExposure;
+
+impl CommitExposure {
+    /// Record `user`'s (just stored) commitment as sending up to `max_amount_in` into `pool`.
+    pub fn declare(dex: &mut OakDEX, user: Address, pool: FixedBytes<32>, zero_for_one: bool, max_amount_in: U256) -> OakResult<()> {
+        {
+            let mut commitment = dex.commitments.setter(user);
+            commitment.exposure_in.set(max_amount_in);
+            commitment.exposure_zero_for_one.set(zero_for_one);
+        }
+        let mut totals = dex.pending_exposure.setter(pool);
+        let slot = if zero_for_one { &mut totals.amount0_in } else { &mut totals.amount1_in };
+        let next = slot.get().checked_add(max_amount_in).ok_or_else(|| err(ERR_OVERFLOW))?;
+        slot.set(next);
+        let count = totals.count.get().checked_add(U256::from(1u64)).ok_or_else(|| err(ERR_OVERFLOW))?;
+        totals.count.set(count);
+        emit_commit_exposure_declared(user, pool, zero_for_one, max_amount_in);
+        Ok(())
+    }
+
+    /// Drop `user`'s declaration from its pool's totals; no-op when none is recorded.
+    ///
+    /// @dev Call whenever the commitment leaves the pending set (reveal, cancel, slash, replace).
+    pub fn release(dex: &mut OakDEX, user: Address) {
+        let (declared, zero_for_one, pool) = {
+            let commitment = dex.commitments.getter(user);
+            (commitment.exposure_in.get(), commitment.exposure_zero_for_one.get(), commitment.pool.get())
+        };
+        if declared.is_zero() {
+            return;
+        }
+        dex.commitments.setter(user).exposure_in.set(U256::ZERO);
+        let mut totals = dex.pending_exposure.setter(FixedBytes::from(pool.to_be_bytes::<32>()));
+        let slot = if zero_for_one { &mut totals.amount0_in } else { &mut totals.amount1_in };
+        let next = slot.get().saturating_sub(declared);
+        slot.set(next);
+        let count = totals.count.get().saturating_sub(U256::from(1u64));
+        totals.count.set(count);
+    }
+
+    /// (amount0_in, amount1_in, count) declared by `pool`'s pending commitments.
+    pub fn pending(dex: &OakDEX, pool: FixedBytes<32>) -> (U256, U256, U256) {
+        let totals = dex.pending_exposure.getter(pool);
+        (totals.amount0_in.get(), totals.amount1_in.get(), totals.count.get())
+    }
+}
@@ -9,6 +9,11 @@ pub const INITIAL_FEE: u64 = 50;
 /// @dev Intended long‑term default after the initial launch phase.
 pub const DEFAULT_FEE_BPS: u64 = 30;
 
+/// Pool fee tiers enabled at `init`: stable pairs, standard pairs, volatile pairs (bps).
+pub const FEE_TIER_STABLE_BPS: u64 = 5;
+pub const FEE_TIER_STANDARD_BPS: u64 = 30;
+pub const FEE_TIER_VOLATILE_BPS: u64 = 100;
+
 /// Basis points denominator (10000 = 100%).
 pub const FEE_DENOMINATOR: u64 = 10_000;
 
@@ -22,24 +27,148 @@ pub const COMMIT_REVEAL_DELAY: u64 = 5;
 /// @dev Prevents storage bloat from abandoned commitments.
 pub const MAX_COMMITMENT_AGE: u64 = 1_000_000; // ~277 hours at 1 block/second
 
+/// Bounds for the owner-configurable reveal delay (blocks). Defaults to COMMIT_REVEAL_DELAY.
+pub const MIN_COMMIT_REVEAL_DELAY: u64 = 1;
+pub const MAX_COMMIT_REVEAL_DELAY: u64 = 300;
+
+/// Bounds for the owner-configurable commitment age (blocks). Defaults to MAX_COMMITMENT_AGE.
+/// @dev The lower bound keeps a usable reveal window above the longest allowed delay.
+pub const MIN_COMMITMENT_AGE: u64 = 600;
+pub const MAX_COMMITMENT_AGE_LIMIT: u64 = 2_592_000; // ~30 days at 1 block/second
+
+/// Commitment status codes returned by `can_reveal` (see `logic::commitment_status`).
+pub const COMMIT_STATUS_NONE: u8 = 0;
+pub const COMMIT_STATUS_TOO_EARLY: u8 = 1;
+pub const COMMIT_STATUS_READY: u8 = 2;
+pub const COMMIT_STATUS_EXPIRED: u8 = 3;
+
+/// Share of the commitment bond (bps) forfeited to the treasury when the owner cancels during
+/// the live reveal window (READY). Cancelling before the window opens (TOO_EARLY) is free.
+pub const COMMIT_LIVE_CANCEL_PENALTY_BPS: u64 = 5_000;
+
+/// Commitment verification schemes (see `engine::verification`), stored with each commitment.
+/// Plain hash is always accepted; pools opt into the others with a bitmask of `1 << scheme`.
+pub const COMMIT_SCHEME_HASH: u64 = 0;
+pub const COMMIT_SCHEME_EIP712: u64 = 1;
+pub const COMMIT_SCHEME_MERKLE: u64 = 2;
+pub const COMMIT_SCHEME_ENCRYPTED: u64 = 3;
+/// Number of defined schemes (ids are `0..COMMIT_SCHEME_COUNT`).
+pub const COMMIT_SCHEME_COUNT: u64 = 4;
+
+/// MEV protection tiers chosen at commit time (see `commit_swap_with_tier`), stored per commitment.
+/// STANDARD: the configured reveal delay (default COMMIT_REVEAL_DELAY) at the pool fee.
+pub const MEV_TIER_STANDARD: u64 = 0;
+/// FAST: revealable after MEV_TIER_FAST_DELAY blocks; the swap fee is scaled by MEV_TIER_FAST_FEE_BPS.
+pub const MEV_TIER_FAST: u64 = 1;
+/// BATCH: the configured delay, revealed only into a batch auction (`reveal_swap_to_batch`).
+pub const MEV_TIER_BATCH: u64 = 2;
+/// Number of defined tiers (ids are `0..MEV_TIER_COUNT`).
+pub const MEV_TIER_COUNT: u64 = 3;
+/// Reveal delay (blocks) of the FAST tier.
+pub const MEV_TIER_FAST_DELAY: u64 = 1;
+/// Fee multiplier (bps of the pool fee) of the FAST tier: 15000 = 1.5x, capped at MAX_FEE_BPS.
+pub const MEV_TIER_FAST_FEE_BPS: u64 = 15_000;
+/// Flash swap callback styles a borrower can register (`set_flash_callback`).
+/// Oak: `oakFlashSwapCallback(amount0_owed, amount1_owed, data)` (default).
+pub const FLASH_CALLBACK_OAK: u64 = 0;
+/// Uniswap V2: `uniswapV2Call(sender, amount0_out, amount1_out, data)`.
+pub const FLASH_CALLBACK_UNISWAP_V2: u64 = 1;
+/// Uniswap V3: `uniswapV3FlashCallback(fee0, fee1, data)`.
+pub const FLASH_CALLBACK_UNISWAP_V3: u64 = 2;
+/// Number of defined callback styles (ids are `0..FLASH_CALLBACK_STYLE_COUNT`).
+pub const FLASH_CALLBACK_STYLE_COUNT: u64 = 3;
+/// Longest Merkle proof accepted by the Merkle-leaf scheme (sibling hashes).
+pub const MAX_MERKLE_PROOF_DEPTH: usize = 32;
+
+/// Reveal gas model (approximate, for wallet pre-funding; see `logic::estimate_reveal_gas`).
+/// Intrinsic tx + calldata, commitment load/hash, lock and pause checks.
+pub const REVEAL_GAS_BASE: u64 = 60_000;
+/// Pool reads/writes, oracle and stats updates, fee accounting.
+pub const REVEAL_GAS_SWAP: u64 = 95_000;
+/// One ERC-20 transfer / transferFrom (warm contract, cold balances).
+pub const REVEAL_GAS_TOKEN_TRANSFER: u64 = 35_000;
+/// Escrow credit instead of the outbound transfer (claimable balance + total, event).
+pub const REVEAL_GAS_ESCROW_CREDIT: u64 = 45_000;
+/// EIP-3529 refund for clearing the commitment hash slot (nonzero -> zero).
+pub const SSTORE_CLEAR_REFUND: u64 = 4_800;
+
+/// Length of an ERC-1155 position lock tier: tier k matures at block k * POSITION_TIER_BLOCKS
+/// (about four weeks of 12 s blocks), see `positions`.
+pub const POSITION_TIER_BLOCKS: u64 = 201_600;
+/// Most ids in one ERC-1155 batch transfer or balance query.
+pub const MAX_POSITION_BATCH: usize = 64;
+
 /// Maximum configurable fee in basis points (10%).
 pub const MAX_FEE_BPS: u64 = 1_000;
 
-/// Treasury share of the total fee in basis points (0.12% at 0.3% total).
-pub const TREASURY_FEE_BPS: u64 = 12;
-
-/// LP share of the total fee in basis points (0.18% at 0.3% total).
-pub const LP_FEE_BPS: u64 = DEFAULT_FEE_BPS - TREASURY_FEE_BPS;
+/// Maximum configurable flash loan fee in basis points (1%), see `set_flash_fee`.
+pub const MAX_FLASH_FEE_BPS: u64 = 100;
+const _: () = assert!(MAX_FLASH_FEE_BPS <= MAX_FEE_BPS);
 
 /// Fee split as percent of total fee: 60% LP, 20% Treasury, 20% Buyback.
+/// @dev The split is applied to whatever fee is configured (see `compute_fee_split`).
 pub const LP_FEE_PCT: u64 = 60;
 pub const TREASURY_FEE_PCT: u64 = 20;
 pub const BUYBACK_FEE_PCT: u64 = 20;
+const _: () = assert!(LP_FEE_PCT + TREASURY_FEE_PCT + BUYBACK_FEE_PCT == 100);
+
+/// Treasury share in basis points at DEFAULT_FEE_BPS (0.06% at 0.3% total), for reference only.
+/// @dev Derived from TREASURY_FEE_PCT; runtime math never assumes the default fee.
+pub const TREASURY_FEE_BPS: u64 = DEFAULT_FEE_BPS * TREASURY_FEE_PCT / 100;
+
+/// LP share in basis points at DEFAULT_FEE_BPS (0.18% at 0.3% total), for reference only.
+pub const LP_FEE_BPS: u64 = DEFAULT_FEE_BPS * LP_FEE_PCT / 100;
 
 /// Circuit breaker: auto-trigger when single-hop price impact exceeds this (basis points). 2000 = 20%.
 pub const CIRCUIT_BREAKER_IMPACT_BPS: u64 = 2000;
 /// TWAP deviation: if price changes more than this per block (basis points), emergency pause. 1500 = 15%.
 pub const TWAP_DEVIATION_BPS_MAX: u64 = 1500;
+/// Default heartbeat for the TWAP deviation reference: older than this (blocks) it is stale and
+/// no longer compared against (~1h at 1 block/s). Governance may override per deployment. The
+/// health check's HEALTH_ORACLE_STALE bit uses the same effective heartbeat.
+pub const ORACLE_HEARTBEAT_BLOCKS: u64 = 3_600;
+/// Oldest external feed answer accepted when validating a pool's first deposit (seconds).
+pub const FEED_MAX_AGE_SECONDS: u64 = 3_600;
+
+/// Per-pool TWAP window: checkpoints roll every this many blocks, so a warmed-up pool averages
+/// over 1x-2x this span (~30-60 min at 1 block/s).
+pub const TWAP_WINDOW_BLOCKS: u64 = 1_800;
+
+/// Shortest inactivity governance may set before abandoned claimable balances can be swept
+/// to treasury (~180 days at 1 block/s).
+pub const MIN_ESCROW_SWEEP_BLOCKS: u64 = 15_552_000;
+
+/// Upper bound for `increase_observation_cardinality` (ring slots per pool).
+pub const MAX_OBSERVATION_CARDINALITY: u64 = 1_024;
+
+// Iteration caps: every loop over caller-supplied lists or growing state is bounded by one of
+// these (or MAX_PATH_LENGTH / MAX_BATCH_POSITIONS / MAX_BATCH_CANCEL / MAX_BATCH_ORDERS /
+// MAX_AGGREGATE_CALLS), so
+// no entrypoint's gas grows with protocol state. Larger requests are paginated by the caller.
+
+/// Ring slots pre-paid per `increase_observation_cardinality` call (3 fresh SSTOREs each).
+pub const MAX_OBSERVATION_GROWTH_PER_CALL: u64 = 128;
+
+/// Points answered per `observe` call.
+pub const MAX_OBSERVE_POINTS: u64 = 32;
+
+/// Pools summed per `reconcile_fees` call.
+pub const MAX_RECONCILE_POOLS: u64 = 32;
+
+/// Entries returned per page by paginated list views.
+pub const MAX_PAGE_SIZE: u64 = 50;
+
+/// Blocks per year used to annualize rates (1 block/s, as the other block-based constants).
+pub const BLOCKS_PER_YEAR: u64 = 31_536_000;
+
+/// Fixed-point scale of decimal-adjusted price views (1e18 = 1.0).
+pub const PRICE_SCALE_DECIMALS: u8 = 18;
+
+/// Fixed-point scale of the k ratio reported in swap events (1e18 = k unchanged).
+pub const K_RATIO_SCALE: u64 = 1_000_000_000_000_000_000;
+
+/// Largest token `decimals()` the price views accept.
+pub const MAX_TOKEN_DECIMALS: u8 = 36;
 
 /// Basis points for price impact (10000 = 100%).
 pub const BPS: u64 = 10_000;
@@ -47,34 +176,94 @@ pub const BPS: u64 = 10_000;
 /// Maximum path length (multi-hop). Prevents DoS and gas griefing.
 pub const MAX_PATH_LENGTH: u64 = 10;
 
+/// Maximum number of view calls batched by `aggregate_views` (bounds returndata and gas).
+pub const MAX_AGGREGATE_CALLS: u64 = 32;
+
 /// Maximum single-trade size as share of reserve (basis points). 1000 = 10% of reserve_in per trade (bank-style cap).
 pub const MAX_TRADE_RESERVE_BPS: u64 = 1000;
 
 /// Blocks to wait before pending owner can accept (e.g. 172800 ≈ 24h at 0.5s/block). DoD two-step transfer.
 pub const OWNER_TRANSFER_DELAY_BLOCKS: u64 = 172800;
 
+/// Default delay between `propose_treasury` and `execute_treasury_change` (~24h at 1 block/s).
+pub const TREASURY_CHANGE_DELAY_BLOCKS: u64 = 86_400;
+
+/// Shortest treasury change delay governance may configure (~1h at 1 block/s).
+pub const MIN_TREASURY_CHANGE_DELAY_BLOCKS: u64 = 3_600;
+
+/// Grace period between queueing an LP-fee surplus sweep and executing it (~24h at 1 block/s).
+pub const LP_SURPLUS_SWEEP_DELAY_BLOCKS: u64 = 86_400;
+
+/// Length of an LP-fee surplus sweep epoch; at most one sweep executes per epoch (~24h at 1 block/s).
+pub const LP_SURPLUS_EPOCH_BLOCKS: u64 = 86_400;
+
 /// Timelock: minimum blocks to wait before executing a queued operation (~24h at 1 block/s).
 pub const TIMELOCK_MIN_DELAY_BLOCKS: u64 = 86400;
 
-/// Gas-rebate share of total fee in basis points (placeholder for future gas rebates).
-/// @dev A small portion of protocol fee is tracked in accrued_gas_rebate_token0.
+/// Gas-rebate share of total fee in basis points, paid to commit-reveal revealers.
+/// @dev Carved out of the treasury share in `compute_fee_split`; claimed with claim_gas_rebate.
 pub const GAS_REBATE_BPS: u64 = 5;
 
+/// Gas rebate: distinct tokens a user can have unclaimed rebates in (bounds claim_gas_rebate).
+/// @dev Rebates in a further token stay with the treasury until the user claims.
+pub const MAX_GAS_REBATE_TOKENS: u64 = 8;
+
+/// Treasury split table: most recipients a withdrawal fans out to (see `treasury_splits`).
+pub const MAX_TREASURY_SPLITS: u64 = 8;
+
 /// Batch execution: fee rebate in basis points (e.g. 2000 = 20% fee discount for batched positions).
 /// @dev Shared execution uses one swap instead of N; participants get this discount as gas rebate.
 pub const BATCH_FEE_REBATE_BPS: u64 = 2000;
 
 /// Maximum number of positions in a single batch (DoS and block gas limit).
 pub const MAX_BATCH_POSITIONS: u64 = 50;
+/// Largest distance (bps of the TWAP) the spot price may be from the pool TWAP when a position
+/// batch crosses offsetting flow internally; past it the batch reverts. 200 = 2%.
+pub const MAX_BATCH_CROSS_TWAP_DEVIATION_BPS: u64 = 200;
+
+/// Maximum number of orders (ids or id range length) cancelled in one batch call.
+pub const MAX_BATCH_CANCEL: u64 = 100;
+
+/// Maximum number of reveals collected into one batch auction (bounds `settle_batch` gas).
+pub const MAX_BATCH_ORDERS: u64 = 50;
+
+/// Longest batch auction window governance may set (~5 min at 1 block/s).
+pub const MAX_BATCH_WINDOW_BLOCKS: u64 = 300;
+
+/// Blocks after a batch window closes before an unsettled order can be reclaimed (~1h).
+pub const BATCH_RECLAIM_DELAY_BLOCKS: u64 = 3_600;
 
 /// Growth: max referral fee in basis points (e.g. 1000 = 10% of protocol fee).
 pub const REFERRAL_FEE_BPS_MAX: u64 = 1000;
 
+/// Max pool creator share in basis points of the treasury fee (2000 = 20%).
+pub const CREATOR_FEE_SHARE_BPS_MAX: u64 = 2000;
+
 /// Copy Trading: max slippage bps (e.g. 500 = 5%).
 pub const COPY_TRADING_SLIPPAGE_BPS_MAX: u64 = 500;
 /// Copy Trading: max amount ratio bps (10000 = 100% of leader amount).
 pub const COPY_TRADING_AMOUNT_RATIO_BPS_MAX: u64 = 10_000;
 
+/// Health check bits (health_check / health_check_pool). 0 = healthy.
+pub const HEALTH_PAUSED: u64 = 1 << 0;
+pub const HEALTH_CIRCUIT_BREAKER: u64 = 1 << 1;
+pub const HEALTH_RESERVE_DRIFT: u64 = 1 << 2;
+pub const HEALTH_ORACLE_STALE: u64 = 1 << 3;
+pub const HEALTH_FEE_ACCRUAL_HIGH: u64 = 1 << 4;
+pub const HEALTH_LOCK_STUCK: u64 = 1 << 5;
+/// Health: treasury + buyback accrual per token above which withdrawal is flagged (1M tokens at 18 decimals).
+pub const HEALTH_FEE_ACCRUAL_THRESHOLD: u128 = 1_000_000 * 10u128.pow(18);
+
+/// Self-test bits (self_test): a set bit is a failed check. 0 = all checks pass.
+pub const SELF_TEST_FEE_SPLIT: u64 = 1 << 0;
+pub const SELF_TEST_SWAP_MATH: u64 = 1 << 1;
+pub const SELF_TEST_LP_FEE_MATH: u64 = 1 << 2;
+pub const SELF_TEST_FEE_CONFIG: u64 = 1 << 3;
+pub const SELF_TEST_COMMIT_CONFIG: u64 = 1 << 4;
+pub const SELF_TEST_RESERVE_BOUNDS: u64 = 1 << 5;
+/// Self-test: registered pairs (in creation order) whose reserves are checked per call.
+pub const SELF_TEST_MAX_POOLS: u64 = 32;
+
 /// Q112.64 fixed-point multiplier for TWAP cumulative prices (2^112).
 pub const Q112: u128 = 1u128 << 112;
 
@@ -84,6 +273,70 @@ pub fn q112_u256() -> U256 {
     U256::from(1u64).wrapping_shl(112)
 }
 
+/// Owner audit log action codes (see `audit`). Append-only: codes are never reused.
+pub const AUDIT_ACTION_SET_FEE: u64 = 1;
+pub const AUDIT_ACTION_PAUSE: u64 = 2;
+pub const AUDIT_ACTION_UNPAUSE: u64 = 3;
+pub const AUDIT_ACTION_WITHDRAW_TREASURY_FEES: u64 = 4;
+pub const AUDIT_ACTION_GRANT_ROLE: u64 = 5;
+pub const AUDIT_ACTION_REVOKE_ROLE: u64 = 6;
+pub const AUDIT_ACTION_TRIGGER_CIRCUIT_BREAKER: u64 = 7;
+pub const AUDIT_ACTION_CLEAR_CIRCUIT_BREAKER: u64 = 8;
+pub const AUDIT_ACTION_SET_PENDING_OWNER: u64 = 9;
+pub const AUDIT_ACTION_ACCEPT_OWNER: u64 = 10;
+pub const AUDIT_ACTION_PROPOSE_TREASURY: u64 = 11;
+pub const AUDIT_ACTION_EXECUTE_TREASURY_CHANGE: u64 = 12;
+pub const AUDIT_ACTION_CANCEL_TREASURY_CHANGE: u64 = 13;
+pub const AUDIT_ACTION_PROPOSE_TREASURY_DESTINATION: u64 = 14;
+pub const AUDIT_ACTION_EXECUTE_TREASURY_DESTINATION: u64 = 15;
+pub const AUDIT_ACTION_SET_TREASURY_CHANGE_DELAY: u64 = 16;
+pub const AUDIT_ACTION_SET_BUYBACK_WALLET: u64 = 17;
+pub const AUDIT_ACTION_FORCE_UNLOCK: u64 = 18;
+pub const AUDIT_ACTION_SWEEP_STALE_CLAIMABLE: u64 = 19;
+pub const AUDIT_ACTION_RESET_POOL_STATS: u64 = 20;
+pub const AUDIT_ACTION_SET_POOL_PAUSED: u64 = 21;
+pub const AUDIT_ACTION_SET_POOL_HOOKED: u64 = 22;
+pub const AUDIT_ACTION_SET_POOL_MANAGER: u64 = 23;
+pub const AUDIT_ACTION_SET_BOOTSTRAP_FEED: u64 = 24;
+pub const AUDIT_ACTION_SET_CREATOR_FEE_SHARE: u64 = 25;
+pub const AUDIT_ACTION_SET_BATCH_WINDOW: u64 = 26;
+pub const AUDIT_ACTION_SET_COMMIT_BOND: u64 = 27;
+pub const AUDIT_ACTION_SET_ESCROW_SWEEP_PERIOD: u64 = 28;
+pub const AUDIT_ACTION_SET_ORACLE_STALENESS_POLICY: u64 = 29;
+pub const AUDIT_ACTION_SET_COMMIT_REVEAL_PARAMS: u64 = 30;
+pub const AUDIT_ACTION_SET_COMMIT_PRICE_BAND: u64 = 31;
+pub const AUDIT_ACTION_RESET_PRICE_REFERENCE: u64 = 32;
+pub const AUDIT_ACTION_SET_REFERRAL_FEE: u64 = 33;
+pub const AUDIT_ACTION_INIT_STAKING: u64 = 34;
+pub const AUDIT_ACTION_SET_BADGE_CONTRACT: u64 = 35;
+pub const AUDIT_ACTION_QUEUE_TIMELOCK_OPERATION: u64 = 36;
+pub const AUDIT_ACTION_SET_FEE_TIER: u64 = 37;
+pub const AUDIT_ACTION_SET_VOLATILITY_FEE_CONFIG: u64 = 38;
+pub const AUDIT_ACTION_SET_TOKEN_ALIAS: u64 = 39;
+pub const AUDIT_ACTION_REMOVE_TOKEN_ALIAS: u64 = 40;
+pub const AUDIT_ACTION_SET_POOL_COMMIT_SCHEMES: u64 = 41;
+pub const AUDIT_ACTION_PROPOSE_TREASURY_SPLITS: u64 = 42;
+pub const AUDIT_ACTION_EXECUTE_TREASURY_SPLITS: u64 = 43;
+pub const AUDIT_ACTION_SET_FLASH_LOAN_POOL: u64 = 44;
+pub const AUDIT_ACTION_SET_FLASH_FEE: u64 = 45;
+pub const AUDIT_ACTION_RETIRE_POOL: u64 = 46;
+pub const AUDIT_ACTION_SET_TOKEN_RISK: u64 = 47;
+pub const AUDIT_ACTION_SET_MAX_PRICE_IMPACT: u64 = 48;
+pub const AUDIT_ACTION_SET_AUTO_CIRCUIT_BREAKER: u64 = 49;
+pub const AUDIT_ACTION_RESET_AUTO_CIRCUIT_BREAKER: u64 = 50;
+pub const AUDIT_ACTION_SET_LP_SURPLUS_POLICY: u64 = 51;
+pub const AUDIT_ACTION_QUEUE_LP_SURPLUS_SWEEP: u64 = 52;
+pub const AUDIT_ACTION_EXECUTE_LP_SURPLUS_SWEEP: u64 = 53;
+pub const AUDIT_ACTION_CANCEL_LP_SURPLUS_SWEEP: u64 = 54;
+pub const AUDIT_ACTION_SET_MAX_OUTFLOW: u64 = 55;
+pub const AUDIT_ACTION_SYNC: u64 = 56;
+pub const AUDIT_ACTION_SKIM: u64 = 57;
+
+/// Volatility fee regimes of a pool (see `fees::FeeController`).
+pub const FEE_REGIME_NORMAL: u64 = 0;
+pub const FEE_REGIME_CALM: u64 = 1;
+pub const FEE_REGIME_VOLATILE: u64 = 2;
+
 /// Convenience helpers for working with `U256`-based math.
 pub fn as_u256(value: u64) -> U256 {
     U256::from(value)
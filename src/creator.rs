@@ -0,0 +1,129 @@
+//! Pool creator share: time-limited cut of a pool's treasury fees for whoever created it.
+//!
+//! Ecosystem projects that bootstrap a market on Oak can be rewarded with a slice of that
+//! pool's protocol (treasury) fee. When governance enables the share, every new pool records
+//! its creator together with the share rate and the last block it applies to, both snapshotted
+//! from the global configuration at creation. Later governance changes never alter an existing
+//! pool's schedule.
+//!
+//! While the schedule runs, each swap carves the creator's share out of the treasury fee and
+//! credits it to a per-creator claimable balance; the creator pulls it with
+//! `claim_creator_fees`. Pools created before the share was enabled carry no creator share.
+
+use stylus_sdk::{alloy_primitives::{Address, U256}, block};
+
+use crate::constants::{as_u256, BLOCKS_PER_YEAR, BPS, CREATOR_FEE_SHARE_BPS_MAX};
+use crate::errors::{
+    err, MathResult, OakResult, ERR_CREATOR_SHARE_TOO_HIGH, ERR_DIVISION_BY_ZERO, ERR_NOTHING_TO_CLAIM,
+    ERR_OVERFLOW,
+};
+use crate::events::{emit_creator_fee_share_set, emit_creator_fees_claimed};
+use crate::state::OakDEX;
+use crate::token::safe_transfer;
+
+/// Creator's cut of `treasury_fee` under a schedule of `share_bps` until `until_block` (inclusive).
+///
+/// @dev Floor rounding; zero once the schedule has ended.
+pub fn creator_share_of(treasury_fee: U256, share_bps: U256, now: U256, until_block: U256) -> MathResult<U256> {
+    if share_bps.is_zero() || now > until_block {
+        return Ok(U256::ZERO);
+    }
+    treasury_fee
+        .checked_mul(share_bps)
+        .ok_or(ERR_OVERFLOW)?
+        .checked_div(as_u256(BPS))
+        .ok_or(ERR_DIVISION_BY_ZERO)
+}
+
+/// Pool creator share (uses OakDEX creator_* storage and PoolData::creator*).
+pub struct PoolCreatorShare;
+
+impl PoolCreatorShare {
+    /// Owner sets the share for pools created from now on (`share_bps` of the treasury fee
+    /// for `period_blocks` after creation). 0 for either disables it.
+    pub fn set_config(dex: &mut OakDEX, share_bps: U256, period_blocks: U256) -> OakResult<()> {
+        if share_bps > as_u256(CREATOR_FEE_SHARE_BPS_MAX) || period_blocks > as_u256(BLOCKS_PER_YEAR) {
+            return Err(err(ERR_CREATOR_SHARE_TOO_HIGH));
+        }
+        dex.creator_fee_share_bps.set(share_bps);
+        dex.creator_fee_share_blocks.set(period_blocks);
+        emit_creator_fee_share_set(share_bps, period_blocks);
+        Ok(())
+    }
+
+    /// Record `creator` on a freshly created pool and snapshot the current schedule.
+    pub fn register(dex: &mut OakDEX, token0: Address, token1: Address, creator: Address) -> OakResult<()> {
+        let share_bps = dex.creator_fee_share_bps.get();
+        let period = dex.creator_fee_share_blocks.get();
+        let until_block = U256::from(block::number())
+            .checked_add(period)
+            .ok_or_else(|| err(ERR_OVERFLOW))?;
+        let mut outer = dex.pools.setter(token0);
+        let mut pool = outer.setter(token1);
+        pool.creator.set(creator);
+        if !share_bps.is_zero() && !period.is_zero() {
+            pool.creator_share_bps.set(share_bps);
+            pool.creator_share_until_block.set(until_block);
+        }
+        Ok(())
+    }
+
+    /// Credit the creator's share of `treasury_fee` (in `token`) for a swap in (token0, token1).
+    ///
+    /// @return The amount credited; the caller removes it from the treasury's cut.
+    pub fn accrue(
+        dex: &mut OakDEX,
+        token0: Address,
+        token1: Address,
+        token: Address,
+        treasury_fee: U256,
+    ) -> OakResult<U256> {
+        let (creator, share_bps, until_block) = {
+            let outer = dex.pools.getter(token0);
+            let pool = outer.getter(token1);
+            (pool.creator.get(), pool.creator_share_bps.get(), pool.creator_share_until_block.get())
+        };
+        if creator == Address::ZERO {
+            return Ok(U256::ZERO);
+        }
+        let amount = creator_share_of(treasury_fee, share_bps, U256::from(block::number()), until_block)
+            .map_err(err)?;
+        if amount.is_zero() {
+            return Ok(U256::ZERO);
+        }
+        let balance = dex
+            .creator_claimable
+            .getter(creator)
+            .get(token)
+            .checked_add(amount)
+            .ok_or_else(|| err(ERR_OVERFLOW))?;
+        let total = dex
+            .creator_claimable_total
+            .get(token)
+            .checked_add(amount)
+            .ok_or_else(|| err(ERR_OVERFLOW))?;
+        dex.creator_claimable.setter(creator).setter(token).set(balance);
+        dex.creator_claimable_total.setter(token).set(total);
+        Ok(amount)
+    }
+
+    /// Transfer msg.sender's accrued creator fees in `token` to them.
+    pub fn claim(dex: &mut OakDEX, token: Address) -> OakResult<U256> {
+        let creator = stylus_sdk::msg::sender();
+        let amount = dex.creator_claimable.getter(creator).get(token);
+        if amount.is_zero() {
+            return Err(err(ERR_NOTHING_TO_CLAIM));
+        }
+        let total = dex.creator_claimable_total.get(token);
+        dex.creator_claimable.setter(creator).setter(token).set(U256::ZERO);
+        dex.creator_claimable_total.setter(token).set(total.saturating_sub(amount));
+        safe_transfer(token, creator, amount)?;
+        emit_creator_fees_claimed(creator, token, amount);
+        Ok(amount)
+    }
+
+    /// View: accrued, unclaimed creator fees of `creator` in `token`.
+    pub fn claimable(dex: &OakDEX, creator: Address, token: Address) -> U256 {
+        dex.creator_claimable.getter(creator).get(token)
+    }
+}
@@ -2,12 +2,27 @@
 //!
 //! If TWAP-observable price changes more than TWAP_DEVIATION_BPS_MAX (15%) in a single block,
 //! the contract is put into Paused state and circuit breaker is triggered (audit trail).
+//!
+//! The comparison is only meaningful against a fresh reference. Once the reference is older than
+//! the heartbeat (no swaps for a while), governance picks the behaviour: skip the comparison and
+//! re-anchor (default), or reject swaps with ORACLE_STALE until `reset_price_reference`.
+//!
+//! The automatic breaker (`check_twap_divergence`) compares each pool's post-swap spot price with
+//! its TWAP over a governance-set window. Past the configured divergence it pauses the protocol
+//! without reverting, so the pause persists; only the owner can reset it.
 
-use stylus_sdk::{alloy_primitives::U256, crypto};
+use stylus_sdk::{
+    alloy_primitives::{Address, U256},
+    block, crypto,
+};
 
-use crate::constants::{q112_u256, BPS, TWAP_DEVIATION_BPS_MAX};
-use crate::errors::{err, OakResult, ERR_CIRCUIT_BREAKER};
-use crate::events::emit_emergency_triggered;
+use crate::constants::{as_u256, q112_u256, BPS, ORACLE_HEARTBEAT_BLOCKS, TWAP_DEVIATION_BPS_MAX};
+use crate::errors::{
+    err, MathResult, OakResult, ERR_BREAKER_NOT_TRIPPED, ERR_CIRCUIT_BREAKER, ERR_DIVISION_BY_ZERO, ERR_ORACLE_STALE,
+    ERR_OVERFLOW,
+};
+use crate::events::{emit_circuit_breaker_tripped, emit_emergency_triggered, emit_pause_changed, emit_price_reference_stale};
+use crate::oracle::{spot_prices, PoolOracle};
 use crate::state::OakDEX;
 
 /// Reason identifier for EmergencyTriggered (indexed for The Graph): keccak256("TWAP_DEVIATION").
@@ -35,6 +50,18 @@ pub fn check_price_deviation(dex: &mut OakDEX, reserve0: U256, reserve1: U256) -
 
     let last0 = dex.last_twap_price0.get();
     let last1 = dex.last_twap_price1.get();
+    let current_block = U256::from(block::number());
+
+    if !last0.is_zero() && is_reference_stale(dex, current_block) {
+        if dex.stale_oracle_pauses_swaps.get() {
+            return Err(err(ERR_ORACLE_STALE));
+        }
+        emit_price_reference_stale(dex.last_twap_price_block.get(), current_block);
+        dex.last_twap_price0.set(price0);
+        dex.last_twap_price1.set(price1);
+        dex.last_twap_price_block.set(current_block);
+        return Ok(());
+    }
 
     let deviation_bps = |current: U256, last: U256| -> Option<U256> {
         if last.is_zero() {
@@ -66,5 +93,96 @@ pub fn check_price_deviation(dex: &mut OakDEX, reserve0: U256, reserve1: U256) -
 
     dex.last_twap_price0.set(price0);
     dex.last_twap_price1.set(price1);
+    dex.last_twap_price_block.set(current_block);
     Ok(())
 }
+
+/// Effective heartbeat in blocks (stored override, or ORACLE_HEARTBEAT_BLOCKS when unset).
+pub fn oracle_heartbeat(dex: &OakDEX) -> U256 {
+    let configured = dex.oracle_heartbeat_blocks.get();
+    if configured.is_zero() {
+        as_u256(ORACLE_HEARTBEAT_BLOCKS)
+    } else {
+        configured
+    }
+}
+
+/// True if the deviation reference was last refreshed more than a heartbeat ago.
+pub fn is_reference_stale(dex: &OakDEX, current_block: U256) -> bool {
+    current_block.saturating_sub(dex.last_twap_price_block.get()) > oracle_heartbeat(dex)
+}
+
+/// Distance of `spot` from `twap` in bps of the TWAP (0 when there is no TWAP yet).
+pub fn price_divergence_bps(spot: U256, twap: U256) -> MathResult<U256> {
+    if twap.is_zero() {
+        return Ok(U256::ZERO);
+    }
+    let diff = if spot > twap { spot - twap } else { twap - spot };
+    diff.checked_mul(as_u256(BPS))
+        .ok_or(ERR_OVERFLOW)?
+        .checked_div(twap)
+        .ok_or(ERR_DIVISION_BY_ZERO)
+}
+
+/// Divergence (bps) at which the automatic breaker trips for `spot` against `twap` under a
+/// `max_bps` threshold (0 = disabled), or None while it holds.
+pub fn breaker_divergence(max_bps: U256, spot: U256, twap: U256) -> MathResult<Option<U256>> {
+    if max_bps.is_zero() {
+        return Ok(None);
+    }
+    let divergence = price_divergence_bps(spot, twap)?;
+    Ok((divergence > max_bps).then_some(divergence))
+}
+
+/// Revert with BREAKER_NOT_TRIPPED unless the automatic breaker tripped (at `tripped_block` != 0).
+pub fn require_breaker_tripped(tripped_block: U256) -> OakResult<()> {
+    if tripped_block.is_zero() {
+        return Err(err(ERR_BREAKER_NOT_TRIPPED));
+    }
+    Ok(())
+}
+
+/// TWAP of token0 in token1 (Q112) over the last `window` blocks.
+///
+/// @dev Read from the observation ring; when it does not reach back `window` blocks (or `window`
+///      is 0), the pool oracle's current window TWAP is used instead.
+fn pool_twap(dex: &OakDEX, token0: Address, token1: Address, window: U256) -> OakResult<U256> {
+    if !window.is_zero() {
+        if let Ok(cumulatives) = PoolOracle::observe(dex, token0, token1, &[window, U256::ZERO]) {
+            return cumulatives[1]
+                .checked_sub(cumulatives[0])
+                .map(|delta| delta / window)
+                .ok_or_else(|| err(ERR_OVERFLOW));
+        }
+    }
+    Ok(PoolOracle::twap(dex, token0, token1)?.0)
+}
+
+/// Automatic breaker: pause the protocol if the post-swap spot price of (token0, token1) diverges
+/// from its TWAP by more than `auto_breaker_deviation_bps`. Returns whether it tripped.
+///
+/// @notice Does not revert: the swap that crossed the threshold settles (it already passed slippage
+///         and the max price impact) and everything after it is blocked until `reset_circuit_breaker`.
+///         Reverting would roll the pause back with the swap.
+/// @dev Canonical order; call after the new reserves are written. No-op while disabled, already
+///      tripped, or without the `oracle` feature.
+pub fn check_twap_divergence(dex: &mut OakDEX, token0: Address, token1: Address, reserve0: U256, reserve1: U256) -> OakResult<bool> {
+    let max_bps = dex.auto_breaker_deviation_bps.get();
+    if !cfg!(feature = "oracle") || max_bps.is_zero() || !dex.auto_breaker_tripped_block.get().is_zero() {
+        return Ok(false);
+    }
+    if reserve0.is_zero() || reserve1.is_zero() {
+        return Ok(false);
+    }
+    let (spot, _) = spot_prices(reserve0, reserve1).map_err(err)?;
+    let twap = pool_twap(dex, token0, token1, dex.auto_breaker_window_blocks.get())?;
+    let Some(divergence) = breaker_divergence(max_bps, spot, twap).map_err(err)? else {
+        return Ok(false);
+    };
+    dex.auto_breaker_tripped_block.set(U256::from(block::number()));
+    dex.circuit_breaker_triggered.set(true);
+    dex.paused.set(true);
+    emit_pause_changed(true);
+    emit_circuit_breaker_tripped(token0, token1, spot, twap, divergence);
+    Ok(true)
+}
@@ -0,0 +1,77 @@
+//! Health check: bitfield of protocol conditions for monitoring and incident response.
+//!
+//! Bits are defined in `constants` (HEALTH_*). A zero result means healthy. Global bits are
+//! always evaluated; reserve drift and fee accrual need a pool and are only set by `pool_flags`.
+
+use stylus_sdk::{alloy_primitives::{Address, U256}, block, contract};
+
+use crate::constants::{
+    HEALTH_CIRCUIT_BREAKER, HEALTH_FEE_ACCRUAL_HIGH, HEALTH_FEE_ACCRUAL_THRESHOLD,
+    HEALTH_LOCK_STUCK, HEALTH_ORACLE_STALE, HEALTH_PAUSED, HEALTH_RESERVE_DRIFT,
+};
+use crate::engine::emergency::oracle_heartbeat;
+use crate::state::OakDEX;
+use crate::token::balance_of;
+
+/// Health monitor (read-only over OakDEX storage).
+pub struct HealthMonitor;
+
+impl HealthMonitor {
+    /// Global condition bits: paused, circuit breaker, oracle stale, lock stuck.
+    pub fn flags(dex: &OakDEX) -> U256 {
+        let mut flags = 0u64;
+        if dex.paused.get() {
+            flags |= HEALTH_PAUSED;
+        }
+        if dex.circuit_breaker_triggered.get() {
+            flags |= HEALTH_CIRCUIT_BREAKER;
+        }
+        // Oracle: cumulatives not advanced for longer than the governed heartbeat, the same
+        // threshold after which the TWAP deviation guard treats its reference as stale.
+        if cfg!(feature = "oracle") {
+            let last = dex.block_timestamp_last.get();
+            let current = U256::from(block::number());
+            if current.saturating_sub(last) > oracle_heartbeat(dex) {
+                flags |= HEALTH_ORACLE_STALE;
+            }
+        }
+        // Lock is only held inside a call; observing it from outside means it was never released.
+        if dex.locked.get() {
+            flags |= HEALTH_LOCK_STUCK;
+        }
+        U256::from(flags)
+    }
+
+    /// Global bits plus pool bits for (token_a, token_b): reserve drift and fee accrual.
+    pub fn pool_flags(dex: &OakDEX, token_a: Address, token_b: Address) -> U256 {
+        let (token0, token1) = if token_a < token_b {
+            (token_a, token_b)
+        } else {
+            (token_b, token_a)
+        };
+        let mut flags = Self::flags(dex);
+        let pool = dex.pools.getter(token0);
+        let pool = pool.getter(token1);
+        if !pool.initialized.get() {
+            return flags;
+        }
+        let reserves = [(token0, pool.reserve0.get()), (token1, pool.reserve1.get())];
+        let contract_addr = contract::address();
+        for (token, reserve) in reserves {
+            // Contract must hold at least this pool's reserve plus fees and escrow owed in `token`.
+            let treasury = dex.treasury_balance.getter(token).get();
+            let buyback = dex.buyback_balance.getter(token).get();
+            let owed = reserve
+                .saturating_add(treasury)
+                .saturating_add(buyback)
+                .saturating_add(dex.claimable_total.getter(token).get());
+            if balance_of(token, contract_addr) < owed {
+                flags |= U256::from(HEALTH_RESERVE_DRIFT);
+            }
+            if treasury.saturating_add(buyback) > U256::from(HEALTH_FEE_ACCRUAL_THRESHOLD) {
+                flags |= U256::from(HEALTH_FEE_ACCRUAL_HIGH);
+            }
+        }
+        flags
+    }
+}
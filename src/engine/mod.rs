@@ -3,14 +3,25 @@
 //! - **Swap core**: Uniswap-style/CPMM single-swap math and execution (storage-minimal).
 //! - **Execution strategy**: Trait for Atomic vs Commit-Reveal; chosen per-call or via storage.
 //! - **Order execution**: Uses swap core + strategy; supports batching.
-//! - **Emergency**: TWAP deviation circuit breaker (check_price_deviation).
+//! - **Emergency**: TWAP deviation circuit breakers (check_price_deviation, check_twap_divergence).
+//! - **Health**: monitoring bitfield (paused, drift, oracle stale, fee accrual, lock stuck).
+//! - **Self-test**: differential fee-math and config checks (`self_test`).
+//! - **Verification**: pluggable commitment schemes (plain hash, EIP-712, Merkle, encrypted).
 
 pub mod strategy;
 pub mod swap_core;
 pub mod execution;
 pub mod emergency;
+pub mod health;
+pub mod self_test;
+pub mod verification;
 
 pub use strategy::{ExecutionMode, ExecutionStrategy, Atomic, CommitReveal};
 pub use swap_core::SwapCore;
 pub use execution::OrderExecution;
-pub use emergency::check_price_deviation;
\ No newline at end of file
+pub use emergency::{
+    breaker_divergence, check_price_deviation, check_twap_divergence, price_divergence_bps, require_breaker_tripped,
+};
+pub use health::HealthMonitor;
+pub use self_test::SelfTest;
+pub use verification::{verify_commitment, CommitmentVerifier, RevealClaim};
\ No newline at end of file
@@ -0,0 +1,175 @@
+//! Self-test: on-chain consistency checks over the deployed fee math and configuration.
+//!
+//! Each check recomputes a quantity two ways and compares them (differential), or checks a
+//! bound the code relies on. Bits are defined in `constants` (SELF_TEST_*); a set bit is a
+//! failed check, so zero means everything passes. The result depends only on the deployed
+//! code and storage, so monitoring can call it without the test suite or the source.
+
+use stylus_sdk::alloy_primitives::{Address, U256};
+
+use crate::constants::{
+    as_u256, DEFAULT_FEE_BPS, FEE_DENOMINATOR, FEE_TIER_STABLE_BPS, FEE_TIER_STANDARD_BPS, FEE_TIER_VOLATILE_BPS, GAS_REBATE_BPS,
+    LP_FEE_PCT, MAX_FEE_BPS, MINIMUM_LIQUIDITY, SELF_TEST_COMMIT_CONFIG, SELF_TEST_FEE_CONFIG, SELF_TEST_FEE_SPLIT,
+    SELF_TEST_LP_FEE_MATH, SELF_TEST_MAX_POOLS, SELF_TEST_RESERVE_BOUNDS, SELF_TEST_SWAP_MATH,
+};
+use crate::logic::{commit_reveal_params, commit_reveal_params_valid, compute_fee_split, get_amount_in_with_fee, get_amount_out_with_fee};
+use crate::lp_fees::{earned_fees, fee_per_share_growth};
+use crate::state::OakDEX;
+
+/// Input amounts every math check is run with (dust to 2^128 - 1).
+fn sample_amounts() -> [U256; 6] {
+    [
+        U256::from(1u64),
+        U256::from(7u64),
+        U256::from(10_000u64),
+        U256::from(1_000_003u64),
+        U256::from(10u64).pow(U256::from(18u64)),
+        U256::from(u128::MAX),
+    ]
+}
+
+/// Fee rates every math check is run with.
+fn sample_fees(protocol_fee_bps: U256) -> [U256; 6] {
+    [
+        U256::ZERO,
+        as_u256(FEE_TIER_STABLE_BPS),
+        as_u256(FEE_TIER_STANDARD_BPS),
+        as_u256(FEE_TIER_VOLATILE_BPS),
+        as_u256(MAX_FEE_BPS),
+        protocol_fee_bps.min(as_u256(MAX_FEE_BPS)),
+    ]
+}
+
+/// Fee split parts sum to the input and to `amount * fee / FEE_DENOMINATOR`; LP gets the most
+/// and the gas rebate is exactly GAS_REBATE_BPS of the total fee.
+pub fn fee_split_consistent(amount: U256, fee_bps: U256) -> bool {
+    let Ok((effective, treasury, lp, buyback, gas_rebate)) = compute_fee_split(amount, fee_bps) else {
+        return false;
+    };
+    let total_fee = amount * fee_bps / as_u256(FEE_DENOMINATOR);
+    let parts = treasury + lp + buyback + gas_rebate;
+    parts == total_fee
+        && effective + parts == amount
+        && lp >= treasury
+        && lp >= buyback
+        && gas_rebate == total_fee * as_u256(GAS_REBATE_BPS) / as_u256(FEE_DENOMINATOR)
+}
+
+/// The default LP share matches `LP_FEE_PCT` of the default fee within one unit of rounding.
+pub fn lp_share_matches_pct(amount: U256) -> bool {
+    let Ok((_, _, lp, _, _)) = compute_fee_split(amount, as_u256(DEFAULT_FEE_BPS)) else {
+        return false;
+    };
+    let expected = amount * as_u256(DEFAULT_FEE_BPS) / as_u256(FEE_DENOMINATOR) * as_u256(LP_FEE_PCT) / U256::from(100u64);
+    lp >= expected && lp - expected <= U256::from(2u64)
+}
+
+/// Quote and inverse quote agree: the input quoted for the output buys at least that output,
+/// the output never reaches the reserve and never beats the fee-free quote.
+pub fn swap_math_consistent(amount_in: U256, reserve_in: U256, reserve_out: U256, fee_bps: U256) -> bool {
+    let (Ok(out), Ok(fee_free)) = (
+        get_amount_out_with_fee(amount_in, reserve_in, reserve_out, fee_bps),
+        get_amount_out_with_fee(amount_in, reserve_in, reserve_out, U256::ZERO),
+    ) else {
+        return false;
+    };
+    if out >= reserve_out || out > fee_free {
+        return false;
+    }
+    if out.is_zero() {
+        return true;
+    }
+    match get_amount_in_with_fee(out, reserve_in, reserve_out, fee_bps) {
+        Ok(needed) => matches!(get_amount_out_with_fee(needed, reserve_in, reserve_out, fee_bps), Ok(back) if back >= out),
+        Err(_) => false,
+    }
+}
+
+/// The LP fee accumulator never pays out more than it was credited.
+pub fn lp_fee_math_consistent(lp_fee: U256, supply: U256) -> bool {
+    let Ok((growth, distributed)) = fee_per_share_growth(lp_fee, supply) else {
+        return false;
+    };
+    let Ok(all_holders) = earned_fees(supply, growth, U256::ZERO) else {
+        return false;
+    };
+    distributed <= lp_fee && all_holders <= distributed
+}
+
+/// Self-test runner (read-only over OakDEX storage).
+pub struct SelfTest;
+
+impl SelfTest {
+    /// Bitmap of failed checks (see SELF_TEST_*); 0 = pass.
+    pub fn run(dex: &OakDEX) -> U256 {
+        let mut failed = 0u64;
+        let fees = sample_fees(dex.protocol_fee_bps.get());
+        let amounts = sample_amounts();
+        let reserve_in = U256::from(10u64).pow(U256::from(24u64));
+        let reserve_out = reserve_in * U256::from(3u64) + U256::from(1_001u64);
+        for &amount in &amounts {
+            if !lp_share_matches_pct(amount) {
+                failed |= SELF_TEST_FEE_SPLIT;
+            }
+            if !lp_fee_math_consistent(amount, amount + as_u256(MINIMUM_LIQUIDITY)) {
+                failed |= SELF_TEST_LP_FEE_MATH;
+            }
+            for &fee_bps in &fees {
+                if !fee_split_consistent(amount, fee_bps) {
+                    failed |= SELF_TEST_FEE_SPLIT;
+                }
+                if !swap_math_consistent(amount, reserve_in, reserve_out, fee_bps) {
+                    failed |= SELF_TEST_SWAP_MATH;
+                }
+            }
+        }
+        if !Self::fee_config_ok(dex) {
+            failed |= SELF_TEST_FEE_CONFIG;
+        }
+        let (reveal_delay, max_age) = commit_reveal_params(dex);
+        if !commit_reveal_params_valid(reveal_delay, max_age) {
+            failed |= SELF_TEST_COMMIT_CONFIG;
+        }
+        if !Self::reserves_ok(dex) {
+            failed |= SELF_TEST_RESERVE_BOUNDS;
+        }
+        U256::from(failed)
+    }
+
+    /// Fee rates within MAX_FEE_BPS, volatility thresholds ordered, a treasury once initialized.
+    fn fee_config_ok(dex: &OakDEX) -> bool {
+        let max_fee = as_u256(MAX_FEE_BPS);
+        let (calm, volatile) = (dex.vol_fee_calm_bps.get(), dex.vol_fee_volatile_bps.get());
+        let initialized = dex.owner.get() != Address::ZERO;
+        dex.protocol_fee_bps.get() <= max_fee
+            && dex.vol_fee_surcharge_bps.get() <= max_fee
+            && dex.vol_fee_discount_bps.get() <= max_fee
+            && (volatile.is_zero() || calm < volatile)
+            && (!initialized || dex.treasury.get() != Address::ZERO)
+    }
+
+    /// First SELF_TEST_MAX_POOLS registered pools: reserves empty exactly when supply is, the
+    /// locked minimum in place, and each reserve within the running per-token total.
+    fn reserves_ok(dex: &OakDEX) -> bool {
+        let count = dex.pair_count.get().min(as_u256(SELF_TEST_MAX_POOLS));
+        let mut position = U256::ZERO;
+        while position < count {
+            let (token0, token1) = (dex.pair_token0.get(position), dex.pair_token1.get(position));
+            let outer = dex.pools.getter(token0);
+            let pool = outer.getter(token1);
+            let (reserve0, reserve1, supply) = (pool.reserve0.get(), pool.reserve1.get(), pool.lp_total_supply.get());
+            let empty = supply.is_zero();
+            if !pool.initialized.get()
+                || reserve0.is_zero() != empty
+                || reserve1.is_zero() != empty
+                || (!empty && supply < as_u256(MINIMUM_LIQUIDITY))
+                || reserve0 > dex.reserve_totals.get(token0)
+                || reserve1 > dex.reserve_totals.get(token1)
+            {
+                return false;
+            }
+            position += U256::from(1u64);
+        }
+        true
+    }
+}
@@ -50,7 +50,7 @@ impl SwapCore for DexSwapCore {
         to: Address,
         params: SwapParams,
     ) -> OakResult<SwapResult> {
-        let fee_bps = params.fee_bps_override.unwrap_or_else(|| dex.protocol_fee_bps.get());
+        let fee_bps = params.fee_bps_override.unwrap_or_else(|| crate::logic::pool_fee_bps(dex, params.token_in, params.token_out));
         let amount_out = crate::logic::process_swap_from_to_with_fee(
             dex,
             from,
@@ -0,0 +1,180 @@
+//! Commitment verification strategies: how a reveal proves it opens the stored commitment.
+//!
+//! Every reveal entrypoint reduces its preimage to a `leaf` hash (`compute_commit_hash`, the
+//! private, bundle or exact-out variants). The scheme id stored with the commitment then picks
+//! the verifier that checks the leaf (plus an optional witness) against the stored value:
+//!
+//! - **Plain hash** (0): `stored == keccak256(pair_key, leaf)`. Always accepted.
+//! - **EIP-712** (1): `stored` is the typed digest of `OakCommitment(bytes32 pool,bytes32 order)`,
+//!   so wallets can display what is being committed.
+//! - **Merkle leaf** (2): `stored` is a root over pool-bound leaves; the witness is the proof.
+//!   One commitment covers several candidate orders, of which one is revealed.
+//! - **Encrypted payload** (3): `stored == keccak256(pair_key, keccak256(ciphertext))` and the
+//!   witness `(key, ciphertext)` must decrypt to the leaf, so a keeper holding the ciphertext can
+//!   reveal once the key is released.
+//!
+//! Pools opt into schemes other than the plain hash (`set_pool_commit_schemes`). A new scheme is
+//! one more `CommitmentVerifier` and one arm in `verify_commitment`; `reveal_swap` is unchanged.
+
+use stylus_sdk::{
+    alloy_primitives::{FixedBytes, U256},
+    crypto,
+};
+
+use crate::constants::{
+    COMMIT_SCHEME_COUNT, COMMIT_SCHEME_EIP712, COMMIT_SCHEME_ENCRYPTED, COMMIT_SCHEME_HASH, COMMIT_SCHEME_MERKLE,
+    MAX_MERKLE_PROOF_DEPTH,
+};
+use crate::errors::{err, OakResult, ERR_INVALID_HASH, ERR_UNKNOWN_COMMIT_SCHEME};
+use crate::logic::compute_pool_bound_hash;
+
+/// What a reveal claims: the pool, its preimage hash and any scheme-specific witness.
+pub struct RevealClaim<'a> {
+    /// Pair key of the pool revealed against (`pairs::compute_pair_key`).
+    pub pair_key: FixedBytes<32>,
+    /// Reveal preimage hash (`compute_commit_hash` or a variant).
+    pub leaf: FixedBytes<32>,
+    /// Scheme-specific proof data (empty for plain hash and EIP-712).
+    pub witness: &'a [u8],
+    /// EIP-712 domain separator of this deployment.
+    pub domain_separator: FixedBytes<32>,
+}
+
+/// One commitment scheme.
+pub trait CommitmentVerifier {
+    /// Scheme id stored with commitments made for this verifier.
+    const SCHEME: u64;
+    /// True if `claim` opens the commitment `stored`.
+    fn verify(stored: FixedBytes<32>, claim: &RevealClaim) -> bool;
+}
+
+/// `stored == keccak256(pair_key, leaf)`.
+pub struct PlainHash;
+
+impl CommitmentVerifier for PlainHash {
+    const SCHEME: u64 = COMMIT_SCHEME_HASH;
+    fn verify(stored: FixedBytes<32>, claim: &RevealClaim) -> bool {
+        claim.witness.is_empty() && stored == compute_pool_bound_hash(claim.pair_key, claim.leaf)
+    }
+}
+
+/// keccak256("OakCommitment(bytes32 pool,bytes32 order)")
+fn commitment_type_hash() -> FixedBytes<32> {
+    crypto::keccak(b"OakCommitment(bytes32 pool,bytes32 order)")
+}
+
+/// EIP-712 digest of `OakCommitment(pool, order)`: "\x19\x01" || domainSeparator || structHash.
+pub fn compute_typed_commitment(
+    pair_key: FixedBytes<32>,
+    leaf: FixedBytes<32>,
+    domain_separator: FixedBytes<32>,
+) -> FixedBytes<32> {
+    let mut enc = [0u8; 96];
+    enc[..32].copy_from_slice(commitment_type_hash().as_slice());
+    enc[32..64].copy_from_slice(pair_key.as_slice());
+    enc[64..].copy_from_slice(leaf.as_slice());
+    let struct_hash = crypto::keccak(enc);
+    let mut prefix = [0u8; 66];
+    prefix[..2].copy_from_slice(b"\x19\x01");
+    prefix[2..34].copy_from_slice(domain_separator.as_slice());
+    prefix[34..].copy_from_slice(struct_hash.as_slice());
+    crypto::keccak(prefix)
+}
+
+/// `stored` is the EIP-712 typed digest of (pair_key, leaf).
+pub struct Eip712Typed;
+
+impl CommitmentVerifier for Eip712Typed {
+    const SCHEME: u64 = COMMIT_SCHEME_EIP712;
+    fn verify(stored: FixedBytes<32>, claim: &RevealClaim) -> bool {
+        claim.witness.is_empty() && stored == compute_typed_commitment(claim.pair_key, claim.leaf, claim.domain_separator)
+    }
+}
+
+/// Fold a Merkle proof (32-byte siblings, sorted-pair hashing as OpenZeppelin) onto `node`.
+///
+/// @dev None for a malformed or over-deep proof.
+pub fn merkle_root_from_proof(mut node: FixedBytes<32>, proof: &[u8]) -> Option<FixedBytes<32>> {
+    if !proof.len().is_multiple_of(32) || proof.len() / 32 > MAX_MERKLE_PROOF_DEPTH {
+        return None;
+    }
+    for sibling in proof.chunks_exact(32) {
+        let sibling = FixedBytes::<32>::from_slice(sibling);
+        let (lo, hi) = if node <= sibling { (node, sibling) } else { (sibling, node) };
+        let mut pair = [0u8; 64];
+        pair[..32].copy_from_slice(lo.as_slice());
+        pair[32..].copy_from_slice(hi.as_slice());
+        node = crypto::keccak(pair);
+    }
+    Some(node)
+}
+
+/// `stored` is a Merkle root over pool-bound leaves; the witness is the proof.
+pub struct MerkleLeaf;
+
+impl CommitmentVerifier for MerkleLeaf {
+    const SCHEME: u64 = COMMIT_SCHEME_MERKLE;
+    fn verify(stored: FixedBytes<32>, claim: &RevealClaim) -> bool {
+        let node = compute_pool_bound_hash(claim.pair_key, claim.leaf);
+        merkle_root_from_proof(node, claim.witness) == Some(stored)
+    }
+}
+
+/// Ciphertext of `leaf` under `key`: `leaf XOR keccak256(key)` (and its own inverse).
+pub fn encrypt_commit_payload(key: FixedBytes<32>, leaf: FixedBytes<32>) -> FixedBytes<32> {
+    leaf ^ crypto::keccak(key.as_slice())
+}
+
+/// Commitment to an encrypted payload: `keccak256(pair_key, keccak256(ciphertext))`.
+pub fn compute_encrypted_commitment(pair_key: FixedBytes<32>, ciphertext: FixedBytes<32>) -> FixedBytes<32> {
+    compute_pool_bound_hash(pair_key, crypto::keccak(ciphertext.as_slice()))
+}
+
+/// `stored` commits to a ciphertext; the witness `key || ciphertext` must decrypt to the leaf.
+pub struct EncryptedPayload;
+
+impl CommitmentVerifier for EncryptedPayload {
+    const SCHEME: u64 = COMMIT_SCHEME_ENCRYPTED;
+    fn verify(stored: FixedBytes<32>, claim: &RevealClaim) -> bool {
+        if claim.witness.len() != 64 {
+            return false;
+        }
+        let key = FixedBytes::<32>::from_slice(&claim.witness[..32]);
+        let ciphertext = FixedBytes::<32>::from_slice(&claim.witness[32..]);
+        stored == compute_encrypted_commitment(claim.pair_key, ciphertext)
+            && encrypt_commit_payload(key, ciphertext) == claim.leaf
+    }
+}
+
+/// Whether `scheme` is a defined scheme id.
+pub fn is_known_scheme(scheme: U256) -> bool {
+    scheme < U256::from(COMMIT_SCHEME_COUNT)
+}
+
+/// Whether a pool with scheme bitmask `enabled` accepts commitments under `scheme`.
+///
+/// @notice The plain hash scheme is always accepted.
+pub fn scheme_enabled(enabled: U256, scheme: U256) -> bool {
+    if scheme == U256::from(COMMIT_SCHEME_HASH) {
+        return true;
+    }
+    is_known_scheme(scheme) && enabled.bit(scheme.to::<usize>())
+}
+
+/// Check `claim` against `stored` with the verifier of `scheme`.
+pub fn verify_commitment(scheme: U256, stored: FixedBytes<32>, claim: &RevealClaim) -> OakResult<()> {
+    if !is_known_scheme(scheme) {
+        return Err(err(ERR_UNKNOWN_COMMIT_SCHEME));
+    }
+    let valid = match scheme.to::<u64>() {
+        PlainHash::SCHEME => PlainHash::verify(stored, claim),
+        Eip712Typed::SCHEME => Eip712Typed::verify(stored, claim),
+        MerkleLeaf::SCHEME => MerkleLeaf::verify(stored, claim),
+        EncryptedPayload::SCHEME => EncryptedPayload::verify(stored, claim),
+        _ => return Err(err(ERR_UNKNOWN_COMMIT_SCHEME)),
+    };
+    if !valid {
+        return Err(err(ERR_INVALID_HASH));
+    }
+    Ok(())
+}
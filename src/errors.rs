@@ -1,14 +1,103 @@
 //! Shared error helpers and result type for Oak Protocol.
+//!
+//! Error codes are the `ERR_*` byte strings below: pure math returns them allocation-free and
+//! call sites write `err(ERR_X)`. At the ABI boundary every code is a Solidity custom error
+//! (`ERR_INSUFFICIENT_LIQUIDITY` reverts with `InsufficientLiquidity()`), so revert data is a
+//! 4-byte selector that standard tooling decodes from the exported ABI. `OakError` is the typed
+//! enum over those errors; `OakError::code` maps a variant back to its byte code.
 
 use alloc::vec::Vec;
 
+use stylus_sdk::{call, prelude::SolidityError};
+
 /// Canonical result type used across the protocol.
-pub type OakResult<T> = Result<T, Vec<u8>>;
+pub type OakResult<T> = Result<T, OakError>;
+
+/// Allocation-free result for pure math helpers: the error is a static code.
+///
+/// @dev `?` inside an `OakResult` function converts via `From<&[u8]> for OakError`.
+pub type MathResult<T> = Result<T, &'static [u8]>;
 
-/// Helper to build a `Vec<u8>` from a string literal at call site.
-#[inline]
-pub fn err(msg: &'static [u8]) -> Vec<u8> {
-    msg.to_vec()
+/// Helper to build an `OakError` from an error code at call site.
+///
+/// @dev Kept out of line and marked cold: the lookup runs only on the error path and
+///      is not duplicated into every call site of the WASM binary.
+#[cold]
+#[inline(never)]
+pub fn err(code: &'static [u8]) -> OakError {
+    OakError::from_code(code)
+}
+
+/// One Solidity custom error and `OakError` variant per error code.
+macro_rules! oak_errors {
+    ($($code:ident => $name:ident,)*) => {
+        /// Solidity error definitions (`error InsufficientLiquidity();` ...).
+        pub mod sol {
+            stylus_sdk::alloy_sol_types::sol! {
+                #![sol(alloy_sol_types = stylus_sdk::alloy_sol_types, all_derives)]
+                $(error $name();)*
+                /// An external call (flash / swap callback) reverted with `reason`.
+                error CallFailed(bytes reason);
+            }
+        }
+
+        /// Typed protocol error; reverts ABI-encoded as its Solidity custom error.
+        #[derive(SolidityError, Clone, Debug, PartialEq, Eq)]
+        pub enum OakError {
+            $($name(sol::$name),)*
+            CallFailed(sol::CallFailed),
+        }
+
+        impl OakError {
+            /// Error for `code`; an unknown code is carried as `CallFailed(code)`.
+            pub fn from_code(code: &[u8]) -> OakError {
+                $(if code == $code {
+                    return OakError::$name(sol::$name {});
+                })*
+                OakError::CallFailed(sol::CallFailed { reason: code.to_vec().into() })
+            }
+
+            /// Decode ABI revert data produced by an `OakError` (e.g. from a routed sub-call).
+            pub fn from_revert(data: &[u8]) -> OakError {
+                use stylus_sdk::alloy_sol_types::SolError;
+                $(if data == <sol::$name as SolError>::SELECTOR {
+                    return OakError::$name(sol::$name {});
+                })*
+                match <sol::CallFailed as SolError>::abi_decode(data, true) {
+                    Ok(e) => OakError::CallFailed(e),
+                    Err(_) => OakError::CallFailed(sol::CallFailed { reason: data.to_vec().into() }),
+                }
+            }
+
+            /// The `ERR_*` byte code of this error (`CallFailed` returns the call's revert data).
+            pub fn code(&self) -> &[u8] {
+                match self {
+                    $(OakError::$name(_) => $code,)*
+                    OakError::CallFailed(e) => &e.reason,
+                }
+            }
+        }
+    };
+}
+
+impl From<&[u8]> for OakError {
+    fn from(code: &[u8]) -> Self {
+        OakError::from_code(code)
+    }
+}
+
+impl From<call::Error> for OakError {
+    fn from(e: call::Error) -> Self {
+        let reason: Vec<u8> = e.into();
+        OakError::CallFailed(sol::CallFailed { reason: reason.into() })
+    }
+}
+
+/// Test conversion layer: compare against the byte codes the tests were written with.
+impl PartialEq<Vec<u8>> for OakError {
+    fn eq(&self, other: &Vec<u8>) -> bool {
+        self.code() == other.as_slice()
+    }
 }
 
 // Core error codes (Solidity-style short strings for tooling friendliness).
@@ -34,6 +123,8 @@ pub const ERR_DEADLINE_EXPIRED: &[u8] = b"DEADLINE_EXPIRED";
 pub const ERR_OVERFLOW: &[u8] = b"OVERFLOW";
 pub const ERR_FEE_OVERFLOW: &[u8] = b"FEE_OVERFLOW";
 pub const ERR_DIVISION_BY_ZERO: &[u8] = b"DIVISION_BY_ZERO";
+/// Swap input too small: output rounds down to zero (see get_min_amount_in).
+pub const ERR_DUST_AMOUNT: &[u8] = b"DUST_AMOUNT";
 
 pub const ERR_INVALID_HASH: &[u8] = b"INVALID_HASH";
 pub const ERR_COMMIT_NOT_FOUND: &[u8] = b"COMMIT_NOT_FOUND";
@@ -41,6 +132,11 @@ pub const ERR_BLOCK_OVERFLOW: &[u8] = b"BLOCK_OVERFLOW";
 pub const ERR_TOO_EARLY: &[u8] = b"TOO_EARLY";
 pub const ERR_COMMITMENT_EXPIRED: &[u8] = b"COMMITMENT_EXPIRED";
 pub const ERR_INVALID_ADDRESS: &[u8] = b"INVALID_ADDRESS";
+/// Swap+LP bundle: swap_amount must be non-zero and below amount_in.
+pub const ERR_BUNDLE_SPLIT_INVALID: &[u8] = b"BUNDLE_SPLIT_INVALID";
+
+/// Entrypoint belongs to a subsystem compiled out via Cargo features.
+pub const ERR_FEATURE_DISABLED: &[u8] = b"FEATURE_DISABLED";
 
 // Token transfer errors
 pub const ERR_TOKEN_TRANSFER_FAILED: &[u8] = b"TOKEN_TRANSFER_FAILED";
@@ -49,11 +145,19 @@ pub const ERR_ZERO_AMOUNT: &[u8] = b"ZERO_AMOUNT";
 
 // Re-entrancy guard errors
 pub const ERR_REENTRANT_CALL: &[u8] = b"REENTRANT_CALL";
+/// Pool reserves / LP supply changed during an external call (re-entry via token hook or callback).
+pub const ERR_POOL_STATE_CHANGED: &[u8] = b"POOL_STATE_CHANGED";
+/// Hooked-token pool: contract balance did not move by exactly the transferred amount.
+pub const ERR_HOOKED_BALANCE_MISMATCH: &[u8] = b"HOOKED_BALANCE_MISMATCH";
 
 // Treasury withdrawal errors
 pub const ERR_NO_TREASURY_FEES: &[u8] = b"NO_TREASURY_FEES";
 pub const ERR_INVALID_TOKEN: &[u8] = b"INVALID_TOKEN";
 pub const ERR_POOL_EXISTS: &[u8] = b"POOL_EXISTS";
+/// Caller is neither the pool manager nor protocol governance.
+pub const ERR_NOT_POOL_ADMIN: &[u8] = b"NOT_POOL_ADMIN";
+/// Pool paused by its manager or governance (swaps and deposits disabled; withdrawals still open).
+pub const ERR_POOL_PAUSED: &[u8] = b"POOL_PAUSED";
 pub const ERR_INVALID_PATH: &[u8] = b"INVALID_PATH";
 pub const ERR_EXPIRED: &[u8] = b"EXPIRED";
 
@@ -65,6 +169,8 @@ pub const ERR_VAULT_BUFFER: &[u8] = b"VAULT_BUFFER";
 
 /// Circuit breaker triggered; swaps disabled until owner clears.
 pub const ERR_CIRCUIT_BREAKER: &[u8] = b"CIRCUIT_BREAKER";
+/// Price reference older than the heartbeat and governance chose to reject swaps until it is reset.
+pub const ERR_ORACLE_STALE: &[u8] = b"ORACLE_STALE";
 
 /// Path length exceeds MAX_PATH_LENGTH.
 pub const ERR_PATH_TOO_LONG: &[u8] = b"PATH_TOO_LONG";
@@ -112,8 +218,10 @@ pub const ERR_TRAILING_NOT_TRIGGERED: &[u8] = b"TRAILING_NOT_TRIGGERED";
 pub const ERR_BATCH_TOO_FEW: &[u8] = b"BATCH_TOO_FEW";
 /// Batch exceeds MAX_BATCH_POSITIONS.
 pub const ERR_BATCH_TOO_MANY: &[u8] = b"BATCH_TOO_MANY";
-/// All positions in batch must share the same (base, quote) pair.
+/// All positions in batch must share the same pair (either base/quote orientation).
 pub const ERR_BATCH_NOT_SAME_PAIR: &[u8] = b"BATCH_NOT_SAME_PAIR";
+/// Batch cancel: more than MAX_BATCH_CANCEL ids, or an empty/inverted id range.
+pub const ERR_CANCEL_BATCH_INVALID: &[u8] = b"CANCEL_BATCH_INVALID";
 /// Invalid OCO pair (order not found or not open).
 pub const ERR_OCO_PAIR_INVALID: &[u8] = b"OCO_PAIR_INVALID";
 /// Margin: zero amount or insufficient balance.
@@ -157,3 +265,389 @@ pub const ERR_PERMIT_INVALID_SIGNATURE: &[u8] = b"PERMIT_INVALID_SIGNATURE";
 pub const ERR_PERMIT_EXPIRED: &[u8] = b"PERMIT_EXPIRED";
 /// Permit nonce already used (replay).
 pub const ERR_PERMIT_NONCE: &[u8] = b"PERMIT_NONCE";
+/// Router permit batch: token / value / signature arrays differ in length.
+pub const ERR_PERMIT_BATCH_LENGTH: &[u8] = b"PERMIT_BATCH_LENGTH";
+/// List argument longer than its iteration cap (see MAX_* constants).
+pub const ERR_TOO_MANY_ITEMS: &[u8] = b"TOO_MANY_ITEMS";
+
+// Output escrow
+/// No claimable balance for this token.
+pub const ERR_NOTHING_TO_CLAIM: &[u8] = b"NOTHING_TO_CLAIM";
+/// Claimable balance lower than the amount moved or spent.
+pub const ERR_INSUFFICIENT_CLAIMABLE: &[u8] = b"INSUFFICIENT_CLAIMABLE";
+
+// View batching
+/// aggregate_views: selector is not on the read-only allowlist (or calldata shorter than 4 bytes).
+pub const ERR_VIEW_NOT_AGGREGATABLE: &[u8] = b"VIEW_NOT_AGGREGATABLE";
+/// Escrow sweep period is non-zero and below MIN_ESCROW_SWEEP_BLOCKS.
+pub const ERR_SWEEP_PERIOD_TOO_SHORT: &[u8] = b"SWEEP_PERIOD_TOO_SHORT";
+/// Claimable balance is not abandoned (sweep disabled, untracked, or recent activity).
+pub const ERR_ESCROW_NOT_STALE: &[u8] = b"ESCROW_NOT_STALE";
+/// Large commitment's limit price is further below the commit-time TWAP than the configured band.
+pub const ERR_COMMIT_LIMIT_OUTSIDE_BAND: &[u8] = b"COMMIT_LIMIT_OUTSIDE_BAND";
+/// Price band configuration: threshold or band above BPS.
+pub const ERR_INVALID_PRICE_BAND: &[u8] = b"INVALID_PRICE_BAND";
+/// No treasury change proposed.
+pub const ERR_NO_PENDING_TREASURY: &[u8] = b"NO_PENDING_TREASURY";
+/// Treasury change delay not yet elapsed.
+pub const ERR_TREASURY_CHANGE_TOO_EARLY: &[u8] = b"TREASURY_CHANGE_TOO_EARLY";
+/// Commit-reveal delay or commitment age outside their configured bounds.
+pub const ERR_INVALID_COMMIT_PARAMS: &[u8] = b"INVALID_COMMIT_PARAMS";
+/// No fee destination change proposed for this token.
+pub const ERR_NO_PENDING_DESTINATION: &[u8] = b"NO_PENDING_DESTINATION";
+/// Treasury change delay below MIN_TREASURY_CHANGE_DELAY_BLOCKS.
+pub const ERR_TREASURY_DELAY_TOO_SHORT: &[u8] = b"TREASURY_DELAY_TOO_SHORT";
+/// Trade journal index out of range.
+pub const ERR_TRADE_RECORD_NOT_FOUND: &[u8] = b"TRADE_RECORD_NOT_FOUND";
+/// observe: requested point predates the oldest stored observation (or none recorded yet).
+pub const ERR_OBSERVATION_TOO_OLD: &[u8] = b"OBSERVATION_TOO_OLD";
+/// increase_observation_cardinality: above MAX_OBSERVATION_CARDINALITY.
+pub const ERR_CARDINALITY_TOO_HIGH: &[u8] = b"CARDINALITY_TOO_HIGH";
+/// aggregate_views: more than MAX_AGGREGATE_CALLS calls.
+pub const ERR_AGGREGATE_TOO_MANY: &[u8] = b"AGGREGATE_TOO_MANY";
+/// Commitment bond configured with a non-zero amount but no token.
+pub const ERR_INVALID_COMMIT_BOND: &[u8] = b"INVALID_COMMIT_BOND";
+/// slash_expired_commitment: commitment is still within MAX_COMMITMENT_AGE.
+pub const ERR_COMMITMENT_NOT_EXPIRED: &[u8] = b"COMMITMENT_NOT_EXPIRED";
+/// Creator share above CREATOR_FEE_SHARE_BPS_MAX or period longer than BLOCKS_PER_YEAR.
+pub const ERR_CREATOR_SHARE_TOO_HIGH: &[u8] = b"CREATOR_SHARE_TOO_HIGH";
+/// Batch auction mode is off (batch window is 0).
+pub const ERR_BATCH_MODE_DISABLED: &[u8] = b"BATCH_MODE_DISABLED";
+/// Batch window above MAX_BATCH_WINDOW_BLOCKS.
+pub const ERR_INVALID_BATCH_WINDOW: &[u8] = b"INVALID_BATCH_WINDOW";
+/// Current batch already holds MAX_BATCH_ORDERS reveals.
+pub const ERR_BATCH_FULL: &[u8] = b"BATCH_FULL";
+/// No order was revealed into this batch.
+pub const ERR_BATCH_NOT_FOUND: &[u8] = b"BATCH_NOT_FOUND";
+/// Batch has already been settled.
+pub const ERR_BATCH_SETTLED: &[u8] = b"BATCH_SETTLED";
+/// Batch window has not closed yet.
+pub const ERR_BATCH_WINDOW_OPEN: &[u8] = b"BATCH_WINDOW_OPEN";
+/// Batch order is not the caller's, already reclaimed, or still within its settlement period.
+pub const ERR_BATCH_ORDER_NOT_RECLAIMABLE: &[u8] = b"BATCH_ORDER_NOT_RECLAIMABLE";
+/// First deposit's price is outside the configured feed tolerance.
+pub const ERR_BOOTSTRAP_PRICE_OUT_OF_RANGE: &[u8] = b"BOOTSTRAP_PRICE_OUT_OF_RANGE";
+/// External price feed returned no answer or a stale one.
+pub const ERR_FEED_UNAVAILABLE: &[u8] = b"FEED_UNAVAILABLE";
+/// Feed configuration: zero scale with a feed, or tolerance above BPS.
+pub const ERR_INVALID_FEED_CONFIG: &[u8] = b"INVALID_FEED_CONFIG";
+/// Burning LP shares that are locked until their unlock block.
+pub const ERR_LIQUIDITY_LOCKED: &[u8] = b"LIQUIDITY_LOCKED";
+/// Lock: unlock block not in the future or before the current one, or more than the free balance.
+pub const ERR_INVALID_LOCK: &[u8] = b"INVALID_LOCK";
+/// commit_swap_for: caller is not an approved commit operator of the beneficiary.
+pub const ERR_NOT_COMMIT_OPERATOR: &[u8] = b"NOT_COMMIT_OPERATOR";
+/// reconcile_fees: a counterpart token is listed twice or its pool does not exist.
+pub const ERR_INVALID_RECONCILE_POOL: &[u8] = b"INVALID_RECONCILE_POOL";
+/// Native ETH input: msg.value does not match the amount the call requires.
+pub const ERR_NATIVE_VALUE_MISMATCH: &[u8] = b"NATIVE_VALUE_MISMATCH";
+/// swap callback: input not paid in full by the end of the callback.
+pub const ERR_SWAP_NOT_SETTLED: &[u8] = b"SWAP_NOT_SETTLED";
+/// swap callback: pool product k decreased across the swap.
+pub const ERR_K_DECREASED: &[u8] = b"K_DECREASED";
+/// swap callback: not available for hooked pools or native ETH input.
+pub const ERR_CALLBACK_NOT_ALLOWED: &[u8] = b"CALLBACK_NOT_ALLOWED";
+/// Token `decimals()` missing, malformed or above MAX_TOKEN_DECIMALS.
+pub const ERR_DECIMALS_UNAVAILABLE: &[u8] = b"DECIMALS_UNAVAILABLE";
+/// Contract balance of a token fell below its reserves plus accrued fees and escrows after a swap.
+pub const ERR_BALANCE_MISMATCH: &[u8] = b"BALANCE_MISMATCH";
+/// Tokens differ from the pair a single-pair deployment was initialized with.
+pub const ERR_PAIR_NOT_BOUND: &[u8] = b"PAIR_NOT_BOUND";
+/// Commitment was declared for a different pool than the one it is revealed against.
+pub const ERR_COMMIT_WRONG_POOL: &[u8] = b"COMMIT_WRONG_POOL";
+/// Path hop has no pair in the pair registry.
+pub const ERR_PAIR_NOT_REGISTERED: &[u8] = b"PAIR_NOT_REGISTERED";
+/// Owner audit log index out of range.
+pub const ERR_AUDIT_RECORD_NOT_FOUND: &[u8] = b"AUDIT_RECORD_NOT_FOUND";
+/// Pool fee tier is not on the owner-managed whitelist (or is zero / above MAX_FEE_BPS).
+pub const ERR_FEE_TIER_NOT_ENABLED: &[u8] = b"FEE_TIER_NOT_ENABLED";
+/// Volatility fee thresholds out of order, or an adjustment above MAX_FEE_BPS.
+pub const ERR_INVALID_FEE_CONFIG: &[u8] = b"INVALID_FEE_CONFIG";
+/// Token alias would alias itself, chain through another alias, or re-point a canonical token.
+pub const ERR_INVALID_ALIAS: &[u8] = b"INVALID_ALIAS";
+/// Token is not registered as an alias.
+pub const ERR_ALIAS_NOT_FOUND: &[u8] = b"ALIAS_NOT_FOUND";
+/// Swap path returns to an asset (or an alias of it) it already left.
+pub const ERR_PATH_REVISITS_ASSET: &[u8] = b"PATH_REVISITS_ASSET";
+/// Commitment scheme id is not defined, or not enabled for the pool.
+pub const ERR_UNKNOWN_COMMIT_SCHEME: &[u8] = b"UNKNOWN_COMMIT_SCHEME";
+/// cancel_commitment: commitment is past its max age; a keeper clears it with slash_expired_commitment.
+pub const ERR_CANCEL_AFTER_EXPIRY: &[u8] = b"CANCEL_AFTER_EXPIRY";
+/// Treasury split table: recipient zero, duplicated or the contract, a zero weight, weights not
+/// summing to BPS, or more than MAX_TREASURY_SPLITS entries.
+pub const ERR_INVALID_TREASURY_SPLIT: &[u8] = b"INVALID_TREASURY_SPLIT";
+/// No treasury split table is pending.
+pub const ERR_NO_PENDING_TREASURY_SPLIT: &[u8] = b"NO_PENDING_TREASURY_SPLIT";
+/// ERC-3156: no flash-loan pool serves the token (see `set_flash_loan_pool`).
+pub const ERR_FLASH_LOAN_UNSUPPORTED: &[u8] = b"FLASH_LOAN_UNSUPPORTED";
+/// ERC-3156: `onFlashLoan` did not return keccak256("ERC3156FlashBorrower.onFlashLoan").
+pub const ERR_FLASH_LOAN_CALLBACK_FAILED: &[u8] = b"FLASH_LOAN_CALLBACK_FAILED";
+/// ERC-3156: the contract's balance did not grow by the fee after pulling the repayment.
+pub const ERR_FLASH_LOAN_NOT_REPAID: &[u8] = b"FLASH_LOAN_NOT_REPAID";
+/// Flash swap callback style id is not defined.
+pub const ERR_UNKNOWN_FLASH_CALLBACK: &[u8] = b"UNKNOWN_FLASH_CALLBACK";
+/// Pool is retired: no new commitments for it, and past its grace period no trades or deposits.
+pub const ERR_POOL_RETIRED: &[u8] = b"POOL_RETIRED";
+/// retire_pool: grace period shorter than the max commitment age.
+pub const ERR_RETIREMENT_GRACE_TOO_SHORT: &[u8] = b"RETIREMENT_GRACE_TOO_SHORT";
+/// ERC-1155 position: tier already matured (or too large), or the pool is not registered.
+pub const ERR_INVALID_POSITION_TIER: &[u8] = b"INVALID_POSITION_TIER";
+/// ERC-1155 position: tier not matured yet, cannot unwrap.
+pub const ERR_POSITION_LOCKED: &[u8] = b"POSITION_LOCKED";
+/// ERC-1155 position: the receiving contract did not accept the transfer.
+pub const ERR_ERC1155_RECEIVER_REJECTED: &[u8] = b"ERC1155_RECEIVER_REJECTED";
+/// ERC-1155 position: caller is neither the holder nor an approved operator.
+pub const ERR_NOT_POSITION_OPERATOR: &[u8] = b"NOT_POSITION_OPERATOR";
+/// ERC-1155 position: batch arrays differ in length or exceed MAX_POSITION_BATCH.
+pub const ERR_INVALID_POSITION_BATCH: &[u8] = b"INVALID_POSITION_BATCH";
+
+/// Commit voucher uses must be non-zero.
+pub const ERR_INVALID_VOUCHER: &[u8] = b"INVALID_VOUCHER";
+
+/// Commit voucher is unknown, revoked or used up (or not the caller's to revoke).
+pub const ERR_VOUCHER_NOT_FOUND: &[u8] = b"VOUCHER_NOT_FOUND";
+
+/// Commit voucher is bound to another redeemer.
+pub const ERR_NOT_VOUCHER_REDEEMER: &[u8] = b"NOT_VOUCHER_REDEEMER";
+
+/// Sponsor's prepaid balance cannot cover the commit bond or the withdrawal.
+pub const ERR_SPONSOR_BALANCE_LOW: &[u8] = b"SPONSOR_BALANCE_LOW";
+
+/// Commit: unknown MEV protection tier; reveal: the committed tier does not allow this reveal path.
+pub const ERR_INVALID_MEV_TIER: &[u8] = b"INVALID_MEV_TIER";
+
+/// Swap or deposit would lift a pool's reserve of a token above its registry exposure cap.
+pub const ERR_TOKEN_EXPOSURE_EXCEEDED: &[u8] = b"TOKEN_EXPOSURE_EXCEEDED";
+
+/// Flash swap / flash loan of a token the risk registry does not allow to be borrowed.
+pub const ERR_FLASH_BORROW_DISABLED: &[u8] = b"FLASH_BORROW_DISABLED";
+
+/// Pool creation: a token of the pair requires an oracle and the pair has no bootstrap feed.
+pub const ERR_TOKEN_ORACLE_REQUIRED: &[u8] = b"TOKEN_ORACLE_REQUIRED";
+
+/// A token of the pair is registered as hooked; its pool cannot run unhooked.
+pub const ERR_TOKEN_HOOKED: &[u8] = b"TOKEN_HOOKED";
+
+/// Swap would move the pool's spot price by more than the configured max price impact.
+pub const ERR_PRICE_IMPACT_TOO_HIGH: &[u8] = b"PRICE_IMPACT_TOO_HIGH";
+
+/// Max price impact must be at most BPS (100%).
+pub const ERR_INVALID_PRICE_IMPACT_LIMIT: &[u8] = b"INVALID_PRICE_IMPACT_LIMIT";
+
+/// Reset called while the automatic circuit breaker has not tripped.
+pub const ERR_BREAKER_NOT_TRIPPED: &[u8] = b"BREAKER_NOT_TRIPPED";
+
+/// LP-fee surplus policy: sweep share above BPS, or a zero minimum age.
+pub const ERR_INVALID_LP_SURPLUS_POLICY: &[u8] = b"INVALID_LP_SURPLUS_POLICY";
+
+/// LP-fee surplus sweeping is disabled (max sweep share is 0).
+pub const ERR_LP_SURPLUS_SWEEP_DISABLED: &[u8] = b"LP_SURPLUS_SWEEP_DISABLED";
+
+/// A surplus sweep is already queued for this pool.
+pub const ERR_LP_SURPLUS_SWEEP_PENDING: &[u8] = b"LP_SURPLUS_SWEEP_PENDING";
+
+/// No surplus sweep is queued for this pool.
+pub const ERR_NO_LP_SURPLUS_SWEEP: &[u8] = b"NO_LP_SURPLUS_SWEEP";
+
+/// Queued surplus sweep is still in its grace period or has not aged enough epochs.
+pub const ERR_LP_SURPLUS_SWEEP_TOO_EARLY: &[u8] = b"LP_SURPLUS_SWEEP_TOO_EARLY";
+
+/// New commitment while the previous one is still pending (neither revealed, cancelled nor expired).
+pub const ERR_COMMITMENT_ACTIVE: &[u8] = b"COMMITMENT_ACTIVE";
+
+/// Reveal goes the other way or spends more than its commitment declared.
+pub const ERR_EXCEEDS_DECLARED_EXPOSURE: &[u8] = b"EXCEEDS_DECLARED_EXPOSURE";
+
+/// This block's outflow from the pool in this direction would exceed the per-block limit.
+pub const ERR_OUTFLOW_LIMIT_EXCEEDED: &[u8] = b"OUTFLOW_LIMIT_EXCEEDED";
+
+/// Per-block outflow limit must be at most BPS (100%).
+pub const ERR_INVALID_OUTFLOW_LIMIT: &[u8] = b"INVALID_OUTFLOW_LIMIT";
+
+/// Role mask is empty or has bits outside ROLE_ALL.
+pub const ERR_INVALID_ROLE: &[u8] = b"INVALID_ROLE";
+
+/// Batch crossing: the pool spot price is too far from its TWAP to cross flow internally.
+pub const ERR_BATCH_PRICE_DEVIATION: &[u8] = b"BATCH_PRICE_DEVIATION";
+
+oak_errors! {
+    ERR_ALREADY_INITIALIZED => AlreadyInitialized,
+    ERR_INVALID_OWNER => InvalidOwner,
+    ERR_ONLY_OWNER => OnlyOwner,
+    ERR_FEE_TOO_HIGH => FeeTooHigh,
+    ERR_PAUSED => Paused,
+    ERR_AMOUNT0_ZERO => Amount0Zero,
+    ERR_AMOUNT1_ZERO => Amount1Zero,
+    ERR_LIQUIDITY_OVERFLOW => LiquidityOverflow,
+    ERR_RESERVE0_OVERFLOW => Reserve0Overflow,
+    ERR_RESERVE1_OVERFLOW => Reserve1Overflow,
+    ERR_INSUFFICIENT_LIQUIDITY => InsufficientLiquidity,
+    ERR_VOLUME_OVERFLOW => VolumeOverflow,
+    ERR_INSUFFICIENT_INPUT_AMOUNT => InsufficientInputAmount,
+    ERR_INSUFFICIENT_OUTPUT_AMOUNT => InsufficientOutputAmount,
+    ERR_SLIPPAGE_EXCEEDED => SlippageExceeded,
+    ERR_DEADLINE_EXPIRED => DeadlineExpired,
+    ERR_OVERFLOW => Overflow,
+    ERR_FEE_OVERFLOW => FeeOverflow,
+    ERR_DIVISION_BY_ZERO => DivisionByZero,
+    ERR_DUST_AMOUNT => DustAmount,
+    ERR_INVALID_HASH => InvalidHash,
+    ERR_COMMIT_NOT_FOUND => CommitNotFound,
+    ERR_BLOCK_OVERFLOW => BlockOverflow,
+    ERR_TOO_EARLY => TooEarly,
+    ERR_COMMITMENT_EXPIRED => CommitmentExpired,
+    ERR_INVALID_ADDRESS => InvalidAddress,
+    ERR_BUNDLE_SPLIT_INVALID => BundleSplitInvalid,
+    ERR_FEATURE_DISABLED => FeatureDisabled,
+    ERR_TOKEN_TRANSFER_FAILED => TokenTransferFailed,
+    ERR_INSUFFICIENT_BALANCE => InsufficientBalance,
+    ERR_ZERO_AMOUNT => ZeroAmount,
+    ERR_REENTRANT_CALL => ReentrantCall,
+    ERR_POOL_STATE_CHANGED => PoolStateChanged,
+    ERR_HOOKED_BALANCE_MISMATCH => HookedBalanceMismatch,
+    ERR_NO_TREASURY_FEES => NoTreasuryFees,
+    ERR_INVALID_TOKEN => InvalidToken,
+    ERR_POOL_EXISTS => PoolExists,
+    ERR_NOT_POOL_ADMIN => NotPoolAdmin,
+    ERR_POOL_PAUSED => PoolPaused,
+    ERR_INVALID_PATH => InvalidPath,
+    ERR_EXPIRED => Expired,
+    ERR_VAULT_POOL_EXCEEDED => VaultPoolExceeded,
+    ERR_VAULT_INSUFFICIENT_RESERVE => VaultInsufficientReserve,
+    ERR_VAULT_RESERVE_EXCEEDS_POOL => VaultReserveExceedsPool,
+    ERR_VAULT_BUFFER => VaultBuffer,
+    ERR_CIRCUIT_BREAKER => CircuitBreaker,
+    ERR_ORACLE_STALE => OracleStale,
+    ERR_PATH_TOO_LONG => PathTooLong,
+    ERR_LP_SLIPPAGE => LpSlippage,
+    ERR_TRADE_TOO_LARGE => TradeTooLarge,
+    ERR_PENDING_OWNER_ONLY => PendingOwnerOnly,
+    ERR_NO_PENDING_OWNER => NoPendingOwner,
+    ERR_OWNER_TRANSFER_TOO_EARLY => OwnerTransferTooEarly,
+    ERR_TREASURY_IS_CONTRACT => TreasuryIsContract,
+    ERR_ORDER_NOT_FOUND => OrderNotFound,
+    ERR_ORDER_NOT_OPEN => OrderNotOpen,
+    ERR_ORDER_NOT_OWNER => OrderNotOwner,
+    ERR_INVALID_ORDER_TYPE => InvalidOrderType,
+    ERR_ORDER_CONDITION_NOT_MET => OrderConditionNotMet,
+    ERR_POSITION_NOT_FOUND => PositionNotFound,
+    ERR_POSITION_NOT_OWNER => PositionNotOwner,
+    ERR_POSITION_NOT_OPEN => PositionNotOpen,
+    ERR_POSITION_TP_SL_NOT_MET => PositionTpSlNotMet,
+    ERR_TRAILING_DISABLED => TrailingDisabled,
+    ERR_TRAILING_NOT_TRIGGERED => TrailingNotTriggered,
+    ERR_BATCH_TOO_FEW => BatchTooFew,
+    ERR_BATCH_TOO_MANY => BatchTooMany,
+    ERR_BATCH_NOT_SAME_PAIR => BatchNotSamePair,
+    ERR_CANCEL_BATCH_INVALID => CancelBatchInvalid,
+    ERR_OCO_PAIR_INVALID => OcoPairInvalid,
+    ERR_MARGIN_ZERO_OR_INSUFFICIENT => MarginZeroOrInsufficient,
+    ERR_MISSING_ROLE => MissingRole,
+    ERR_GRANT_ZERO => GrantZero,
+    ERR_TIMELOCK_UNKNOWN_OPERATION => TimelockUnknownOperation,
+    ERR_TIMELOCK_NOT_READY => TimelockNotReady,
+    ERR_TIMELOCK_ALREADY_EXECUTED => TimelockAlreadyExecuted,
+    ERR_REFERRAL_SELF => ReferralSelf,
+    ERR_REFERRAL_FEE_TOO_HIGH => ReferralFeeTooHigh,
+    ERR_STAKING_NOT_INIT => StakingNotInit,
+    ERR_STAKING_ZERO_AMOUNT => StakingZeroAmount,
+    ERR_QUEST_ALREADY_CLAIMED => QuestAlreadyClaimed,
+    ERR_QUEST_MILESTONE_NOT_MET => QuestMilestoneNotMet,
+    ERR_COPY_NOT_SUBSCRIBED => CopyNotSubscribed,
+    ERR_COPY_LEADER_MISMATCH => CopyLeaderMismatch,
+    ERR_COPY_SLIPPAGE => CopySlippage,
+    ERR_SIGNAL_NOT_LISTED => SignalNotListed,
+    ERR_SIGNAL_ALREADY_PURCHASED => SignalAlreadyPurchased,
+    ERR_SIGNAL_INVALID_SIGNATURE => SignalInvalidSignature,
+    ERR_PERMIT_INVALID_SIGNATURE => PermitInvalidSignature,
+    ERR_PERMIT_EXPIRED => PermitExpired,
+    ERR_PERMIT_NONCE => PermitNonce,
+    ERR_PERMIT_BATCH_LENGTH => PermitBatchLength,
+    ERR_TOO_MANY_ITEMS => TooManyItems,
+    ERR_NOTHING_TO_CLAIM => NothingToClaim,
+    ERR_INSUFFICIENT_CLAIMABLE => InsufficientClaimable,
+    ERR_VIEW_NOT_AGGREGATABLE => ViewNotAggregatable,
+    ERR_SWEEP_PERIOD_TOO_SHORT => SweepPeriodTooShort,
+    ERR_ESCROW_NOT_STALE => EscrowNotStale,
+    ERR_COMMIT_LIMIT_OUTSIDE_BAND => CommitLimitOutsideBand,
+    ERR_INVALID_PRICE_BAND => InvalidPriceBand,
+    ERR_NO_PENDING_TREASURY => NoPendingTreasury,
+    ERR_TREASURY_CHANGE_TOO_EARLY => TreasuryChangeTooEarly,
+    ERR_INVALID_COMMIT_PARAMS => InvalidCommitParams,
+    ERR_NO_PENDING_DESTINATION => NoPendingDestination,
+    ERR_TREASURY_DELAY_TOO_SHORT => TreasuryDelayTooShort,
+    ERR_TRADE_RECORD_NOT_FOUND => TradeRecordNotFound,
+    ERR_OBSERVATION_TOO_OLD => ObservationTooOld,
+    ERR_CARDINALITY_TOO_HIGH => CardinalityTooHigh,
+    ERR_AGGREGATE_TOO_MANY => AggregateTooMany,
+    ERR_INVALID_COMMIT_BOND => InvalidCommitBond,
+    ERR_COMMITMENT_NOT_EXPIRED => CommitmentNotExpired,
+    ERR_CREATOR_SHARE_TOO_HIGH => CreatorShareTooHigh,
+    ERR_BATCH_MODE_DISABLED => BatchModeDisabled,
+    ERR_INVALID_BATCH_WINDOW => InvalidBatchWindow,
+    ERR_BATCH_FULL => BatchFull,
+    ERR_BATCH_NOT_FOUND => BatchNotFound,
+    ERR_BATCH_SETTLED => BatchSettled,
+    ERR_BATCH_WINDOW_OPEN => BatchWindowOpen,
+    ERR_BATCH_ORDER_NOT_RECLAIMABLE => BatchOrderNotReclaimable,
+    ERR_BOOTSTRAP_PRICE_OUT_OF_RANGE => BootstrapPriceOutOfRange,
+    ERR_FEED_UNAVAILABLE => FeedUnavailable,
+    ERR_INVALID_FEED_CONFIG => InvalidFeedConfig,
+    ERR_LIQUIDITY_LOCKED => LiquidityLocked,
+    ERR_INVALID_LOCK => InvalidLock,
+    ERR_NOT_COMMIT_OPERATOR => NotCommitOperator,
+    ERR_INVALID_RECONCILE_POOL => InvalidReconcilePool,
+    ERR_NATIVE_VALUE_MISMATCH => NativeValueMismatch,
+    ERR_SWAP_NOT_SETTLED => SwapNotSettled,
+    ERR_K_DECREASED => KDecreased,
+    ERR_CALLBACK_NOT_ALLOWED => CallbackNotAllowed,
+    ERR_DECIMALS_UNAVAILABLE => DecimalsUnavailable,
+    ERR_BALANCE_MISMATCH => BalanceMismatch,
+    ERR_PAIR_NOT_BOUND => PairNotBound,
+    ERR_COMMIT_WRONG_POOL => CommitWrongPool,
+    ERR_PAIR_NOT_REGISTERED => PairNotRegistered,
+    ERR_AUDIT_RECORD_NOT_FOUND => AuditRecordNotFound,
+    ERR_FEE_TIER_NOT_ENABLED => FeeTierNotEnabled,
+    ERR_INVALID_FEE_CONFIG => InvalidFeeConfig,
+    ERR_INVALID_ALIAS => InvalidAlias,
+    ERR_ALIAS_NOT_FOUND => AliasNotFound,
+    ERR_PATH_REVISITS_ASSET => PathRevisitsAsset,
+    ERR_UNKNOWN_COMMIT_SCHEME => UnknownCommitScheme,
+    ERR_CANCEL_AFTER_EXPIRY => CancelAfterExpiry,
+    ERR_INVALID_TREASURY_SPLIT => InvalidTreasurySplit,
+    ERR_NO_PENDING_TREASURY_SPLIT => NoPendingTreasurySplit,
+    ERR_FLASH_LOAN_UNSUPPORTED => FlashLoanUnsupported,
+    ERR_FLASH_LOAN_CALLBACK_FAILED => FlashLoanCallbackFailed,
+    ERR_FLASH_LOAN_NOT_REPAID => FlashLoanNotRepaid,
+    ERR_UNKNOWN_FLASH_CALLBACK => UnknownFlashCallback,
+    ERR_POOL_RETIRED => PoolRetired,
+    ERR_RETIREMENT_GRACE_TOO_SHORT => RetirementGraceTooShort,
+    ERR_INVALID_POSITION_TIER => InvalidPositionTier,
+    ERR_POSITION_LOCKED => PositionLocked,
+    ERR_ERC1155_RECEIVER_REJECTED => Erc1155ReceiverRejected,
+    ERR_NOT_POSITION_OPERATOR => NotPositionOperator,
+    ERR_INVALID_POSITION_BATCH => InvalidPositionBatch,
+    ERR_INVALID_VOUCHER => InvalidVoucher,
+    ERR_VOUCHER_NOT_FOUND => VoucherNotFound,
+    ERR_NOT_VOUCHER_REDEEMER => NotVoucherRedeemer,
+    ERR_SPONSOR_BALANCE_LOW => SponsorBalanceLow,
+    ERR_INVALID_MEV_TIER => InvalidMevTier,
+    ERR_TOKEN_EXPOSURE_EXCEEDED => TokenExposureExceeded,
+    ERR_FLASH_BORROW_DISABLED => FlashBorrowDisabled,
+    ERR_TOKEN_ORACLE_REQUIRED => TokenOracleRequired,
+    ERR_TOKEN_HOOKED => TokenHooked,
+    ERR_PRICE_IMPACT_TOO_HIGH => PriceImpactTooHigh,
+    ERR_INVALID_PRICE_IMPACT_LIMIT => InvalidPriceImpactLimit,
+    ERR_BREAKER_NOT_TRIPPED => BreakerNotTripped,
+    ERR_INVALID_LP_SURPLUS_POLICY => InvalidLpSurplusPolicy,
+    ERR_LP_SURPLUS_SWEEP_DISABLED => LpSurplusSweepDisabled,
+    ERR_LP_SURPLUS_SWEEP_PENDING => LpSurplusSweepPending,
+    ERR_NO_LP_SURPLUS_SWEEP => NoLpSurplusSweep,
+    ERR_LP_SURPLUS_SWEEP_TOO_EARLY => LpSurplusSweepTooEarly,
+    ERR_COMMITMENT_ACTIVE => CommitmentActive,
+    ERR_EXCEEDS_DECLARED_EXPOSURE => ExceedsDeclaredExposure,
+    ERR_OUTFLOW_LIMIT_EXCEEDED => OutflowLimitExceeded,
+    ERR_INVALID_OUTFLOW_LIMIT => InvalidOutflowLimit,
+    ERR_INVALID_ROLE => InvalidRole,
+    ERR_BATCH_PRICE_DEVIATION => BatchPriceDeviation,
+}
@@ -0,0 +1,164 @@
+//! Output escrow: pull-based claims for swap proceeds.
+//!
+//! When a user enables escrow mode, `reveal_swap` keeps the output inside the contract and
+//! credits it to a per-user claimable balance. The user (or a smart-contract recipient that
+//! cannot receive tokens atomically) later pulls it with `claim_output`. A failing token
+//! transfer therefore never reverts the swap itself.
+//!
+//! Claimable balances are also spendable in place: `transfer_claimable` moves them between users
+//! and `swap_from_claimable` uses them as swap input, crediting the output back to the claimable
+//! balance. Neither touches the ERC-20, saving the claim/re-deposit round trip.
+//!
+//! Every escrow action stamps the user's last-activity block. Once governance enables a sweep
+//! period (at least MIN_ESCROW_SWEEP_BLOCKS), balances of accounts idle for longer can be moved
+//! to the treasury, so abandoned accounts do not hold storage forever.
+
+use stylus_sdk::{alloy_primitives::{Address, U256}, block};
+
+use crate::constants::{as_u256, MIN_ESCROW_SWEEP_BLOCKS};
+use crate::errors::{
+    err, OakResult, ERR_ESCROW_NOT_STALE, ERR_INSUFFICIENT_CLAIMABLE, ERR_NOTHING_TO_CLAIM, ERR_OVERFLOW,
+    ERR_SWEEP_PERIOD_TOO_SHORT,
+};
+use crate::events::{
+    emit_claim_transferred, emit_claimable_swept, emit_escrow_sweep_period_set, emit_output_claimed,
+    emit_output_escrowed,
+};
+use crate::state::OakDEX;
+use crate::token::safe_transfer;
+
+/// Claimable balance after crediting `amount` to `balance`; ERR_OVERFLOW if it does not fit.
+pub fn credited(balance: U256, amount: U256) -> OakResult<U256> {
+    balance.checked_add(amount).ok_or_else(|| err(ERR_OVERFLOW))
+}
+
+/// Claimable balance left after spending `amount` of `balance`; ERR_INSUFFICIENT_CLAIMABLE if short.
+pub fn debited(balance: U256, amount: U256) -> OakResult<U256> {
+    balance.checked_sub(amount).ok_or_else(|| err(ERR_INSUFFICIENT_CLAIMABLE))
+}
+
+/// Output escrow (uses OakDEX escrow storage).
+pub struct OutputEscrow;
+
+impl OutputEscrow {
+    /// Enable or disable escrow mode for msg.sender.
+    pub fn set_mode(dex: &mut OakDEX, enabled: bool) {
+        let user = stylus_sdk::msg::sender();
+        dex.escrow_output_enabled.setter(user).set(enabled);
+        Self::touch(dex, user);
+    }
+
+    /// Stamp `user`'s last escrow activity with the current block.
+    fn touch(dex: &mut OakDEX, user: Address) {
+        dex.escrow_last_activity.setter(user).set(U256::from(block::number()));
+    }
+
+    /// True if `user` has escrow mode enabled.
+    pub fn is_enabled(dex: &OakDEX, user: Address) -> bool {
+        dex.escrow_output_enabled.getter(user).get()
+    }
+
+    /// Credit `amount` of `token` to `user`. Tokens must already be held by the contract.
+    pub fn credit(dex: &mut OakDEX, user: Address, token: Address, amount: U256) -> OakResult<()> {
+        if amount.is_zero() {
+            return Ok(());
+        }
+        let prev = dex.claimable_balance.getter(user).getter(token).get();
+        let new_balance = credited(prev, amount)?;
+        let new_total = credited(dex.claimable_total.getter(token).get(), amount)?;
+        dex.claimable_balance.setter(user).setter(token).set(new_balance);
+        dex.claimable_total.setter(token).set(new_total);
+        Self::touch(dex, user);
+        emit_output_escrowed(user, token, amount);
+        Ok(())
+    }
+
+    /// Debit `amount` of `token` from `user`. The tokens stay in the contract (caller re-uses them).
+    pub fn debit(dex: &mut OakDEX, user: Address, token: Address, amount: U256) -> OakResult<()> {
+        let prev = dex.claimable_balance.getter(user).getter(token).get();
+        let new_balance = debited(prev, amount)?;
+        let prev_total = dex.claimable_total.getter(token).get();
+        dex.claimable_balance.setter(user).setter(token).set(new_balance);
+        dex.claimable_total.setter(token).set(prev_total.saturating_sub(amount));
+        Self::touch(dex, user);
+        Ok(())
+    }
+
+    /// Move `amount` of msg.sender's claimable `token` to `to`. No ERC-20 transfer, no fee.
+    pub fn transfer(dex: &mut OakDEX, to: Address, token: Address, amount: U256) -> OakResult<()> {
+        let from = stylus_sdk::msg::sender();
+        if amount.is_zero() {
+            return Err(err(ERR_NOTHING_TO_CLAIM));
+        }
+        let prev_from = dex.claimable_balance.getter(from).getter(token).get();
+        let new_from = debited(prev_from, amount)?;
+        dex.claimable_balance.setter(from).setter(token).set(new_from);
+        let prev_to = dex.claimable_balance.getter(to).getter(token).get();
+        let new_to = credited(prev_to, amount)?;
+        dex.claimable_balance.setter(to).setter(token).set(new_to);
+        Self::touch(dex, from);
+        Self::touch(dex, to);
+        emit_claim_transferred(from, to, token, amount);
+        Ok(())
+    }
+
+    /// Transfer the full claimable balance of `token` to msg.sender. CEI: balance cleared before transfer.
+    pub fn claim(dex: &mut OakDEX, token: Address) -> OakResult<U256> {
+        let user = stylus_sdk::msg::sender();
+        let amount = dex.claimable_balance.getter(user).getter(token).get();
+        if amount.is_zero() {
+            return Err(err(ERR_NOTHING_TO_CLAIM));
+        }
+        let prev_total = dex.claimable_total.getter(token).get();
+        dex.claimable_balance.setter(user).setter(token).set(U256::ZERO);
+        dex.claimable_total.setter(token).set(prev_total.saturating_sub(amount));
+        Self::touch(dex, user);
+        safe_transfer(token, user, amount)?;
+        emit_output_claimed(user, token, amount);
+        Ok(amount)
+    }
+
+    /// Set the inactivity period after which balances may be swept (0 disables sweeping).
+    pub fn set_sweep_period(dex: &mut OakDEX, blocks: U256) -> OakResult<()> {
+        if !blocks.is_zero() && blocks < as_u256(MIN_ESCROW_SWEEP_BLOCKS) {
+            return Err(err(ERR_SWEEP_PERIOD_TOO_SHORT));
+        }
+        dex.escrow_sweep_after_blocks.set(blocks);
+        emit_escrow_sweep_period_set(blocks);
+        Ok(())
+    }
+
+    /// Move `user`'s abandoned claimable `token` balance to the treasury balance.
+    ///
+    /// @dev Tokens never leave the contract; the treasury withdraws them as accrued fees. Users
+    ///      without a recorded activity block (balances predating tracking) are never swept.
+    pub fn sweep_stale(dex: &mut OakDEX, user: Address, token: Address) -> OakResult<U256> {
+        let period = dex.escrow_sweep_after_blocks.get();
+        let last_activity = dex.escrow_last_activity.getter(user).get();
+        let now = U256::from(block::number());
+        if period.is_zero() || last_activity.is_zero() || now.saturating_sub(last_activity) < period {
+            return Err(err(ERR_ESCROW_NOT_STALE));
+        }
+        let amount = dex.claimable_balance.getter(user).getter(token).get();
+        if amount.is_zero() {
+            return Err(err(ERR_NOTHING_TO_CLAIM));
+        }
+        let prev_total = dex.claimable_total.getter(token).get();
+        let treasury = dex
+            .treasury_balance
+            .getter(token)
+            .get()
+            .checked_add(amount)
+            .ok_or_else(|| err(ERR_OVERFLOW))?;
+        dex.claimable_balance.setter(user).setter(token).set(U256::ZERO);
+        dex.claimable_total.setter(token).set(prev_total.saturating_sub(amount));
+        dex.treasury_balance.setter(token).set(treasury);
+        emit_claimable_swept(user, token, amount);
+        Ok(amount)
+    }
+
+    /// View: claimable balance of `token` for `user`.
+    pub fn get_claimable(dex: &OakDEX, user: Address, token: Address) -> U256 {
+        dex.claimable_balance.getter(user).getter(token).get()
+    }
+}
@@ -1,85 +1,160 @@
 //! Solidity-compatible events for Oak Protocol.
 //!
 //! @notice Event helper functions for logging Solidity-compatible events.
-//! @dev Uses evm::raw_log for maximum compatibility with Stylus SDK 0.6.
+//! @dev Every log carries topic0 = keccak256 of its canonical Solidity signature, then the
+//!      indexed fields in declaration order; the data is the ABI encoding of the rest, so logs
+//!      decode with the contract ABI. Logs go through `evm::raw_log`: the `sol!` event expansion
+//!      (alloy-sol-macro 0.7.7) needs a newer alloy-sol-types than the 0.7.6 stylus-sdk 0.6 pins.
 
 use alloc::vec::Vec;
 use stylus_sdk::{
     alloy_primitives::{Address, FixedBytes, U256},
-    evm,
+    crypto, evm,
 };
 
+use crate::audit::{bool_word, word};
+
+/// Topic0 of the event with canonical `signature`, e.g. `Transfer(address,address,uint256)`.
+pub fn event_topic(signature: &str) -> FixedBytes<32> {
+    crypto::keccak(signature.as_bytes())
+}
+
+/// ABI words of event data made only of `uint256[]` fields: head offsets, then each array.
+pub fn uint_arrays(arrays: &[&[U256]]) -> Vec<FixedBytes<32>> {
+    let mut head = Vec::with_capacity(arrays.len());
+    let mut tail = Vec::new();
+    for array in arrays {
+        head.push(word(U256::from(32 * (arrays.len() + tail.len()))));
+        tail.push(word(U256::from(array.len())));
+        tail.extend(array.iter().map(|v| word(*v)));
+    }
+    head.extend(tail);
+    head
+}
+
+/// Emit the event `signature` with its `indexed` topics and ABI-encoded `data` words.
+fn log(signature: &str, indexed: &[FixedBytes<32>], data: &[FixedBytes<32>]) {
+    let mut topics = Vec::with_capacity(indexed.len() + 1);
+    topics.push(event_topic(signature));
+    topics.extend_from_slice(indexed);
+    let data: Vec<u8> = data.iter().flat_map(|w| w.0).collect();
+    let _ = evm::raw_log(&topics, &data);
+}
+
 /// Emit CommitSwap event.
 pub fn emit_commit_swap(user: Address, hash: FixedBytes<32>, block_number: U256) {
-    let topics = &[user.into_word()];
-    let mut data = Vec::new();
-    data.extend_from_slice(&hash.0);
-    data.extend_from_slice(&block_number.to_be_bytes::<32>());
-    let _ = evm::raw_log(topics, &data);
+    log("CommitSwap(address,bytes32,uint256)", &[user.into_word()], &[hash, word(block_number)]);
+}
+
+/// Emit CommitMevTier(user indexed, tier) for a commitment made with a non-standard tier.
+pub fn emit_commit_mev_tier(user: Address, tier: U256) {
+    log("CommitMevTier(address,uint256)", &[user.into_word()], &[word(tier)]);
+}
+
+/// CommitExposureDeclared(user indexed, pool indexed, zero_for_one, max_amount_in).
+pub fn emit_commit_exposure_declared(user: Address, pool: FixedBytes<32>, zero_for_one: bool, max_amount_in: U256) {
+    log(
+        "CommitExposureDeclared(address,bytes32,bool,uint256)",
+        &[user.into_word(), pool],
+        &[bool_word(zero_for_one), word(max_amount_in)],
+    );
 }
 
 /// Emit RevealSwap event.
+///
+/// @dev `reserve_in` / `reserve_out` are the pool reserves after the swap, oriented to its
+///      direction; `k_ratio` is k_after / k_before scaled by 1e18 (see `compute_k_ratio`).
+#[allow(clippy::too_many_arguments)]
 pub fn emit_reveal_swap(
     user: Address,
     amount_in: U256,
     amount_out: U256,
     treasury_fee: U256,
     lp_fee: U256,
+    reserve_in: U256,
+    reserve_out: U256,
+    k_ratio: U256,
 ) {
-    let topics = &[user.into_word()];
-    let mut data = Vec::new();
-    data.extend_from_slice(&amount_in.to_be_bytes::<32>());
-    data.extend_from_slice(&amount_out.to_be_bytes::<32>());
-    data.extend_from_slice(&treasury_fee.to_be_bytes::<32>());
-    data.extend_from_slice(&lp_fee.to_be_bytes::<32>());
-    let _ = evm::raw_log(topics, &data);
+    log(
+        "RevealSwap(address,uint256,uint256,uint256,uint256,uint256,uint256,uint256)",
+        &[user.into_word()],
+        &[
+            word(amount_in),
+            word(amount_out),
+            word(treasury_fee),
+            word(lp_fee),
+            word(reserve_in),
+            word(reserve_out),
+            word(k_ratio),
+        ],
+    );
 }
 
 /// Emit AddLiquidity event.
 pub fn emit_add_liquidity(provider: Address, amount0: U256, amount1: U256) {
-    let topics = &[provider.into_word()];
-    let mut data = Vec::new();
-    data.extend_from_slice(&amount0.to_be_bytes::<32>());
-    data.extend_from_slice(&amount1.to_be_bytes::<32>());
-    let _ = evm::raw_log(topics, &data);
+    log("AddLiquidity(address,uint256,uint256)", &[provider.into_word()], &[word(amount0), word(amount1)]);
+}
+
+/// RemoveLiquidity(provider indexed, amount0, amount1, liquidity). Amounts in the caller's token order.
+pub fn emit_remove_liquidity(provider: Address, amount0: U256, amount1: U256, liquidity: U256) {
+    log(
+        "RemoveLiquidity(address,uint256,uint256,uint256)",
+        &[provider.into_word()],
+        &[word(amount0), word(amount1), word(liquidity)],
+    );
+}
+
+/// Emit when a swap+LP bundle is revealed: swap leg then liquidity deposit of the proceeds.
+pub fn emit_liquidity_bundle_revealed(
+    provider: Address,
+    amount_in: U256,
+    swap_amount: U256,
+    swap_out: U256,
+    liquidity: U256,
+) {
+    log(
+        "LiquidityBundleRevealed(address,uint256,uint256,uint256,uint256)",
+        &[provider.into_word()],
+        &[word(amount_in), word(swap_amount), word(swap_out), word(liquidity)],
+    );
 }
 
 /// Emit SetFee event.
 pub fn emit_set_fee(new_fee_bps: u16) {
-    let topics = &[];
-    let mut data = Vec::new();
-    data.extend_from_slice(&U256::from(new_fee_bps).to_be_bytes::<32>());
-    let _ = evm::raw_log(topics, &data);
+    log("SetFee(uint256)", &[], &[word(U256::from(new_fee_bps))]);
 }
 
 /// Emit PauseChanged event.
 pub fn emit_pause_changed(paused: bool) {
-    let topics = &[];
-    let mut data = Vec::new();
-    data.extend_from_slice(&U256::from(paused as u8).to_be_bytes::<32>());
-    let _ = evm::raw_log(topics, &data);
+    log("PauseChanged(bool)", &[], &[bool_word(paused)]);
 }
 
 /// Emit WithdrawTreasuryFees event.
 pub fn emit_withdraw_treasury_fees(treasury: Address, token: Address, amount: U256) {
-    let topics = &[treasury.into_word(), token.into_word()];
-    let mut data = Vec::new();
-    data.extend_from_slice(&amount.to_be_bytes::<32>());
-    let _ = evm::raw_log(topics, &data);
+    log("WithdrawTreasuryFees(address,address,uint256)", &[treasury.into_word(), token.into_word()], &[word(amount)]);
+}
+
+/// SetCommitRevealParams(reveal_delay_blocks, max_commitment_age_blocks).
+pub fn emit_set_commit_reveal_params(reveal_delay: U256, max_age: U256) {
+    log("SetCommitRevealParams(uint256,uint256)", &[], &[word(reveal_delay), word(max_age)]);
 }
 
 /// Emit CancelCommitment event.
 pub fn emit_cancel_commitment(user: Address, block_number: U256) {
-    let topics = &[user.into_word()];
-    let mut data = Vec::new();
-    data.extend_from_slice(&block_number.to_be_bytes::<32>());
-    let _ = evm::raw_log(topics, &data);
+    log("CancelCommitment(address,uint256)", &[user.into_word()], &[word(block_number)]);
+}
+
+/// Emit CommitmentExpired event (detected on reveal or cleanup). Lets apps notify traders the protected order lapsed.
+pub fn emit_commitment_expired(user: Address, hash: U256, commit_block: U256) {
+    log("CommitmentExpired(address,uint256,uint256)", &[user.into_word()], &[word(hash), word(commit_block)]);
 }
 
 /// Emit FlashSwap event.
 ///
 /// @notice Emitted when a flash swap is initiated and completed.
-/// @dev Includes borrower address, token addresses, borrowed amounts, and fees paid.
+/// @dev Includes borrower address, token addresses, borrowed amounts, fees paid, the reserves
+///      after repayment and k_after / k_before scaled by 1e18.
+#[allow(clippy::too_many_arguments)]
 pub fn emit_flash_swap(
     borrower: Address,
     token0: Address,
@@ -88,14 +163,15 @@ pub fn emit_flash_swap(
     amount1_out: U256,
     fee0: U256,
     fee1: U256,
+    reserve0: U256,
+    reserve1: U256,
+    k_ratio: U256,
 ) {
-    let topics = &[borrower.into_word(), token0.into_word(), token1.into_word()];
-    let mut data = Vec::new();
-    data.extend_from_slice(&amount0_out.to_be_bytes::<32>());
-    data.extend_from_slice(&amount1_out.to_be_bytes::<32>());
-    data.extend_from_slice(&fee0.to_be_bytes::<32>());
-    data.extend_from_slice(&fee1.to_be_bytes::<32>());
-    let _ = evm::raw_log(topics, &data);
+    log(
+        "FlashSwap(address,address,address,uint256,uint256,uint256,uint256,uint256,uint256,uint256)",
+        &[borrower.into_word(), token0.into_word(), token1.into_word()],
+        &[word(amount0_out), word(amount1_out), word(fee0), word(fee1), word(reserve0), word(reserve1), word(k_ratio)],
+    );
 }
 
 /// Emit LP token Transfer-like event for LP balances.
@@ -103,32 +179,56 @@ pub fn emit_flash_swap(
 /// @notice Mimics ERC-20 `Transfer` for LP tokens so that wallets
 ///         and indexers can track LP positions.
 pub fn emit_lp_transfer(from: Address, to: Address, value: U256) {
-    let topics = &[from.into_word(), to.into_word()];
-    let mut data = Vec::new();
-    data.extend_from_slice(&value.to_be_bytes::<32>());
-    let _ = evm::raw_log(topics, &data);
+    log("Transfer(address,address,uint256)", &[from.into_word(), to.into_word()], &[word(value)]);
 }
 
 /// Emit when circuit breaker auto-triggers (price impact exceeded). Audit trail.
 pub fn emit_circuit_breaker_triggered(price_impact_bps: U256) {
-    let topics = &[];
-    let mut data = Vec::new();
-    data.extend_from_slice(&price_impact_bps.to_be_bytes::<32>());
-    let _ = evm::raw_log(topics, &data);
+    log("CircuitBreakerTriggered(uint256)", &[], &[word(price_impact_bps)]);
 }
 
 /// Emit when owner clears circuit breaker.
 pub fn emit_circuit_breaker_cleared() {
-    let topics = &[];
-    let data: &[u8] = &[];
-    let _ = evm::raw_log(topics, data);
+    log("CircuitBreakerCleared()", &[], &[]);
+}
+
+/// ForceUnlocked(caller indexed). Stuck re-entrancy lock cleared via force_unlock.
+pub fn emit_force_unlocked(caller: Address) {
+    log("ForceUnlocked(address)", &[caller.into_word()], &[]);
 }
 
 /// EmergencyTriggered(reason indexed). For The Graph: TWAP deviation, manual pause, etc.
 pub fn emit_emergency_triggered(reason: FixedBytes<32>) {
-    let topics: &[FixedBytes<32>] = &[reason];
-    let data: &[u8] = &[];
-    let _ = evm::raw_log(topics, data);
+    log("EmergencyTriggered(bytes32)", &[reason], &[]);
+}
+
+/// PriceReferenceStale(last_block, current_block). Deviation check skipped and the reference re-anchored.
+pub fn emit_price_reference_stale(last_block: U256, current_block: U256) {
+    log("PriceReferenceStale(uint256,uint256)", &[], &[word(last_block), word(current_block)]);
+}
+
+/// CircuitBreakerTripped(token0 indexed, token1 indexed, spot, twap, divergence_bps). Protocol paused.
+pub fn emit_circuit_breaker_tripped(token0: Address, token1: Address, spot: U256, twap: U256, divergence_bps: U256) {
+    log(
+        "CircuitBreakerTripped(address,address,uint256,uint256,uint256)",
+        &[token0.into_word(), token1.into_word()],
+        &[word(spot), word(twap), word(divergence_bps)],
+    );
+}
+
+/// AutoCircuitBreakerSet(deviation_bps, window_blocks). deviation_bps = 0 disables the breaker.
+pub fn emit_auto_circuit_breaker_set(deviation_bps: U256, window_blocks: U256) {
+    log("AutoCircuitBreakerSet(uint256,uint256)", &[], &[word(deviation_bps), word(window_blocks)]);
+}
+
+/// CircuitBreakerReset(tripped_block). Owner cleared an automatic trip and resumed the protocol.
+pub fn emit_circuit_breaker_reset(tripped_block: U256) {
+    log("CircuitBreakerReset(uint256)", &[], &[word(tripped_block)]);
+}
+
+/// OracleStalenessPolicySet(heartbeat_blocks, pause_on_stale).
+pub fn emit_oracle_staleness_policy_set(heartbeat_blocks: U256, pause_on_stale: bool) {
+    log("OracleStalenessPolicySet(uint256,bool)", &[], &[word(heartbeat_blocks), bool_word(pause_on_stale)]);
 }
 
 /// SwapExecuted(sender indexed, tokenIn indexed, tokenOut indexed, amountIn, amountOut). For The Graph.
@@ -139,40 +239,89 @@ pub fn emit_swap_executed(
     amount_in: U256,
     amount_out: U256,
 ) {
-    let topics = &[sender.into_word(), token_in.into_word(), token_out.into_word()];
-    let mut data = Vec::new();
-    data.extend_from_slice(&amount_in.to_be_bytes::<32>());
-    data.extend_from_slice(&amount_out.to_be_bytes::<32>());
-    let _ = evm::raw_log(topics, &data);
+    log(
+        "SwapExecuted(address,address,address,uint256,uint256)",
+        &[sender.into_word(), token_in.into_word(), token_out.into_word()],
+        &[word(amount_in), word(amount_out)],
+    );
 }
 
 /// Emit when a new pool is created. Indexers use this to enumerate pairs.
 pub fn emit_pool_created(token0: Address, token1: Address) {
-    let topics = &[token0.into_word(), token1.into_word()];
-    let data: &[u8] = &[];
-    let _ = evm::raw_log(topics, data);
+    log("PoolCreated(address,address)", &[token0.into_word(), token1.into_word()], &[]);
+}
+
+/// PoolManagerSet(token0 indexed, token1 indexed, manager indexed).
+pub fn emit_pool_manager_set(token0: Address, token1: Address, manager: Address) {
+    log("PoolManagerSet(address,address,address)", &[token0.into_word(), token1.into_word(), manager.into_word()], &[]);
+}
+
+/// PoolConfigChanged(token0 indexed, token1 indexed, paused, hooked, by).
+pub fn emit_pool_config_changed(token0: Address, token1: Address, paused: bool, hooked: bool, by: Address) {
+    log(
+        "PoolConfigChanged(address,address,bool,bool,address)",
+        &[token0.into_word(), token1.into_word()],
+        &[bool_word(paused), bool_word(hooked), by.into_word()],
+    );
 }
 
 /// Emit when buyback wallet is set (owner-only).
 pub fn emit_buyback_wallet_set(wallet: Address) {
-    let topics = &[wallet.into_word()];
-    let data: &[u8] = &[];
-    let _ = evm::raw_log(topics, data);
+    log("BuybackWalletSet(address)", &[wallet.into_word()], &[]);
 }
 
 /// Emit when pending owner is set (two-step transfer).
 pub fn emit_pending_owner_set(pending: Address, transfer_after_block: U256) {
-    let topics = &[pending.into_word()];
-    let mut data = Vec::new();
-    data.extend_from_slice(&transfer_after_block.to_be_bytes::<32>());
-    let _ = evm::raw_log(topics, &data);
+    log("PendingOwnerSet(address,uint256)", &[pending.into_word()], &[word(transfer_after_block)]);
+}
+
+/// Emit when a new treasury is proposed; installable from `change_after_block`.
+pub fn emit_treasury_change_proposed(pending: Address, change_after_block: U256) {
+    log("TreasuryChangeProposed(address,uint256)", &[pending.into_word()], &[word(change_after_block)]);
+}
+
+/// Emit when the treasury address is rotated (after execute_treasury_change).
+pub fn emit_treasury_changed(old_treasury: Address, new_treasury: Address) {
+    log("TreasuryChanged(address,address)", &[old_treasury.into_word(), new_treasury.into_word()], &[]);
+}
+
+/// Emit when a pending treasury proposal is withdrawn.
+pub fn emit_treasury_change_cancelled(pending: Address) {
+    log("TreasuryChangeCancelled(address)", &[pending.into_word()], &[]);
+}
+
+/// Emit when a per-token fee destination is proposed; installable from `change_after_block`.
+pub fn emit_treasury_destination_proposed(token: Address, destination: Address, change_after_block: U256) {
+    log(
+        "TreasuryDestinationProposed(address,address,uint256)",
+        &[token.into_word(), destination.into_word()],
+        &[word(change_after_block)],
+    );
+}
+
+/// Emit when a per-token fee destination takes effect (0 = back to the treasury address).
+pub fn emit_treasury_destination_set(token: Address, destination: Address) {
+    log("TreasuryDestinationSet(address,address)", &[token.into_word(), destination.into_word()], &[]);
+}
+
+/// Emit when the treasury change delay is reconfigured; it applies from `effective_block`.
+pub fn emit_treasury_change_delay_set(delay_blocks: U256, effective_block: U256) {
+    log("TreasuryChangeDelaySet(uint256,uint256)", &[], &[word(delay_blocks), word(effective_block)]);
+}
+
+/// RolesGranted(account indexed, sender indexed, roles): `roles` is the bitmask added.
+pub fn emit_roles_granted(account: Address, roles: U256, sender: Address) {
+    log("RolesGranted(address,address,uint256)", &[account.into_word(), sender.into_word()], &[word(roles)]);
+}
+
+/// RolesRevoked(account indexed, sender indexed, roles): `roles` is the bitmask removed.
+pub fn emit_roles_revoked(account: Address, roles: U256, sender: Address) {
+    log("RolesRevoked(address,address,uint256)", &[account.into_word(), sender.into_word()], &[word(roles)]);
 }
 
 /// Emit when ownership is transferred (after accept_owner).
 pub fn emit_owner_changed(old_owner: Address, new_owner: Address) {
-    let topics = &[old_owner.into_word(), new_owner.into_word()];
-    let data: &[u8] = &[];
-    let _ = evm::raw_log(topics, data);
+    log("OwnerChanged(address,address)", &[old_owner.into_word(), new_owner.into_word()], &[]);
 }
 
 /// Emit when a TP/SL/Limit order is placed.
@@ -185,36 +334,36 @@ pub fn emit_order_placed(
     trigger_price: U256,
     order_type: U256,
 ) {
-    let topics = &[owner.into_word()];
-    let mut data = Vec::new();
-    data.extend_from_slice(&order_id.to_be_bytes::<32>());
-    data.extend_from_slice(token_in.as_slice());
-    data.extend_from_slice(token_out.as_slice());
-    data.extend_from_slice(&amount_out.to_be_bytes::<32>());
-    data.extend_from_slice(&trigger_price.to_be_bytes::<32>());
-    data.extend_from_slice(&order_type.to_be_bytes::<32>());
-    let _ = evm::raw_log(topics, &data);
+    log(
+        "OrderPlaced(address,uint256,address,address,uint256,uint256,uint256)",
+        &[owner.into_word()],
+        &[
+            word(order_id),
+            token_in.into_word(),
+            token_out.into_word(),
+            word(amount_out),
+            word(trigger_price),
+            word(order_type),
+        ],
+    );
 }
 
 /// Emit when an order is cancelled (tokens returned to owner).
 pub fn emit_order_cancelled(order_id: U256, owner: Address) {
-    let topics = &[owner.into_word()];
-    let mut data = Vec::new();
-    data.extend_from_slice(&order_id.to_be_bytes::<32>());
-    let _ = evm::raw_log(topics, &data);
+    log("OrderCancelled(address,uint256)", &[owner.into_word()], &[word(order_id)]);
+}
+
+/// Emit once for a batch cancel: owner indexed; data = the cancelled order ids.
+pub fn emit_orders_cancelled(owner: Address, order_ids: &[U256]) {
+    log("OrdersCancelled(address,uint256[])", &[owner.into_word()], &uint_arrays(&[order_ids]));
 }
 
 /// Emit when an order is executed (TP/SL/Limit filled).
 pub fn emit_order_executed(order_id: U256, owner: Address, amount_in_received: U256) {
-    let topics = &[owner.into_word()];
-    let mut data = Vec::new();
-    data.extend_from_slice(&order_id.to_be_bytes::<32>());
-    data.extend_from_slice(&amount_in_received.to_be_bytes::<32>());
-    let _ = evm::raw_log(topics, &data);
+    log("OrderExecuted(address,uint256,uint256)", &[owner.into_word()], &[word(order_id), word(amount_in_received)]);
 }
 
 // ---------- Position events (pro terminal) ----------
-// PositionOpened(positionId, owner indexed, baseToken indexed, quoteToken indexed, size, entryPrice) — use emit_open_position below for The Graph.
 
 /// PositionOpened: position_id, owner indexed, base_token indexed, quote_token indexed, size, entry_price. For The Graph.
 pub fn emit_open_position(
@@ -225,64 +374,71 @@ pub fn emit_open_position(
     size: U256,
     entry_price: U256,
 ) {
-    let topics = &[owner.into_word(), base_token.into_word(), quote_token.into_word()];
-    let mut data = Vec::new();
-    data.extend_from_slice(&position_id.to_be_bytes::<32>());
-    data.extend_from_slice(&size.to_be_bytes::<32>());
-    data.extend_from_slice(&entry_price.to_be_bytes::<32>());
-    let _ = evm::raw_log(topics, &data);
+    log(
+        "PositionOpened(uint256,address,address,address,uint256,uint256)",
+        &[owner.into_word(), base_token.into_word(), quote_token.into_word()],
+        &[word(position_id), word(size), word(entry_price)],
+    );
 }
 
 /// Emit when a position is closed (market sell).
 pub fn emit_close_position(position_id: U256, owner: Address, amount_out: U256) {
-    let topics = &[owner.into_word()];
-    let mut data = Vec::new();
-    data.extend_from_slice(&position_id.to_be_bytes::<32>());
-    data.extend_from_slice(&amount_out.to_be_bytes::<32>());
-    let _ = evm::raw_log(topics, &data);
+    log("PositionClosed(address,uint256,uint256)", &[owner.into_word()], &[word(position_id), word(amount_out)]);
 }
 
 /// Emit when TP/SL is set or updated on a position.
 pub fn emit_set_position_tp_sl(position_id: U256, owner: Address, tp_price: U256, sl_price: U256) {
-    let topics = &[owner.into_word()];
-    let mut data = Vec::new();
-    data.extend_from_slice(&position_id.to_be_bytes::<32>());
-    data.extend_from_slice(&tp_price.to_be_bytes::<32>());
-    data.extend_from_slice(&sl_price.to_be_bytes::<32>());
-    let _ = evm::raw_log(topics, &data);
+    log(
+        "PositionTpSlSet(address,uint256,uint256,uint256)",
+        &[owner.into_word()],
+        &[word(position_id), word(tp_price), word(sl_price)],
+    );
 }
 
 /// Emit when trailing stop is set on a position.
 pub fn emit_set_position_trailing(position_id: U256, owner: Address, trailing_delta_bps: U256, initial_peak: U256) {
-    let topics = &[owner.into_word()];
-    let mut data = Vec::new();
-    data.extend_from_slice(&position_id.to_be_bytes::<32>());
-    data.extend_from_slice(&trailing_delta_bps.to_be_bytes::<32>());
-    data.extend_from_slice(&initial_peak.to_be_bytes::<32>());
-    let _ = evm::raw_log(topics, &data);
+    log(
+        "PositionTrailingSet(address,uint256,uint256,uint256)",
+        &[owner.into_word()],
+        &[word(position_id), word(trailing_delta_bps), word(initial_peak)],
+    );
 }
 
 /// Emit when trailing stop triggers a close (oracle price dropped below trigger level).
 pub fn emit_trailing_stop_triggered(position_id: U256, owner: Address, peak_price: U256, trigger_price: U256, amount_out: U256) {
-    let topics = &[owner.into_word()];
-    let mut data = Vec::new();
-    data.extend_from_slice(&position_id.to_be_bytes::<32>());
-    data.extend_from_slice(&peak_price.to_be_bytes::<32>());
-    data.extend_from_slice(&trigger_price.to_be_bytes::<32>());
-    data.extend_from_slice(&amount_out.to_be_bytes::<32>());
-    let _ = evm::raw_log(topics, &data);
+    log(
+        "TrailingStopTriggered(address,uint256,uint256,uint256,uint256)",
+        &[owner.into_word()],
+        &[word(position_id), word(peak_price), word(trigger_price), word(amount_out)],
+    );
 }
 
 /// Emit when a batch of positions is executed (Shared Execution Gas-Rebate).
 /// executor: caller; total_size: aggregated base sold; total_quote_out: aggregated quote received; rebate_bps: fee discount applied.
 pub fn emit_batch_positions_executed(executor: Address, total_size: U256, total_quote_out: U256, rebate_bps: U256, position_count: U256) {
-    let topics = &[executor.into_word()];
-    let mut data = Vec::new();
-    data.extend_from_slice(&total_size.to_be_bytes::<32>());
-    data.extend_from_slice(&total_quote_out.to_be_bytes::<32>());
-    data.extend_from_slice(&rebate_bps.to_be_bytes::<32>());
-    data.extend_from_slice(&position_count.to_be_bytes::<32>());
-    let _ = evm::raw_log(topics, &data);
+    log(
+        "BatchPositionsExecuted(address,uint256,uint256,uint256,uint256)",
+        &[executor.into_word()],
+        &[word(total_size), word(total_quote_out), word(rebate_bps), word(position_count)],
+    );
+}
+
+/// Emit when a pool's observation ring is pre-paid to a larger size.
+pub fn emit_observation_cardinality_increased(token0: Address, token1: Address, old_next: U256, new_next: U256) {
+    log(
+        "ObservationCardinalityIncreased(address,address,uint256,uint256)",
+        &[token0.into_word(), token1.into_word()],
+        &[word(old_next), word(new_next)],
+    );
+}
+
+/// Emit when offsetting batch flow is crossed internally (coincidence of wants) instead of hitting the curve.
+pub fn emit_batch_crossed(base_token: Address, quote_token: Address, crossed_base: U256, crossed_quote: U256) {
+    log(
+        "BatchCrossed(address,address,uint256,uint256)",
+        &[base_token.into_word(), quote_token.into_word()],
+        &[word(crossed_base), word(crossed_quote)],
+    );
 }
 
 // -----------------------------------------------------------------------------
@@ -303,13 +459,11 @@ pub fn emit_emission_event(
     amount: U256,
     token_id: U256,
 ) {
-    let topics = &[user.into_word()];
-    let mut data = Vec::new();
-    data.extend_from_slice(&module_id.to_be_bytes::<32>());
-    data.extend_from_slice(&event_type.to_be_bytes::<32>());
-    data.extend_from_slice(&amount.to_be_bytes::<32>());
-    data.extend_from_slice(&token_id.to_be_bytes::<32>());
-    let _ = evm::raw_log(topics, &data);
+    log(
+        "EmissionEvent(uint256,address,uint256,uint256,uint256)",
+        &[user.into_word()],
+        &[word(module_id), word(event_type), word(amount), word(token_id)],
+    );
 }
 
 // -----------------------------------------------------------------------------
@@ -317,34 +471,465 @@ pub fn emit_emission_event(
 // -----------------------------------------------------------------------------
 /// Copy subscription created: follower, leader, slippage_bps, amount_ratio_bps.
 pub fn emit_copy_subscription(follower: Address, leader: Address, slippage_bps: U256, amount_ratio_bps: U256) {
-    let topics = &[follower.into_word(), leader.into_word()];
-    let mut data = Vec::new();
-    data.extend_from_slice(&slippage_bps.to_be_bytes::<32>());
-    data.extend_from_slice(&amount_ratio_bps.to_be_bytes::<32>());
-    let _ = evm::raw_log(topics, &data);
+    log(
+        "CopySubscription(address,address,uint256,uint256)",
+        &[follower.into_word(), leader.into_word()],
+        &[word(slippage_bps), word(amount_ratio_bps)],
+    );
 }
 
 /// Copy subscription revoked by follower (any time).
 pub fn emit_copy_subscription_revoked(follower: Address, leader: Address) {
-    let topics = &[follower.into_word(), leader.into_word()];
-    let _ = evm::raw_log(topics, &[]);
+    log("CopySubscriptionRevoked(address,address)", &[follower.into_word(), leader.into_word()], &[]);
 }
 
 /// Copy trade executed for follower (backend uses for push notifications).
 pub fn emit_copy_trade_executed(follower: Address, leader: Address, amount_in: U256, amount_out: U256) {
-    let topics = &[follower.into_word(), leader.into_word()];
-    let mut data = Vec::new();
-    data.extend_from_slice(&amount_in.to_be_bytes::<32>());
-    data.extend_from_slice(&amount_out.to_be_bytes::<32>());
-    let _ = evm::raw_log(topics, &data);
+    log(
+        "CopyTradeExecuted(address,address,uint256,uint256)",
+        &[follower.into_word(), leader.into_word()],
+        &[word(amount_in), word(amount_out)],
+    );
 }
 
 /// Signal purchased: buyer, seller, listing_hash, price. Backend delivers encrypted content key.
 pub fn emit_signal_purchased(buyer: Address, seller: Address, listing_hash: U256, price: U256) {
-    let topics = &[buyer.into_word(), seller.into_word()];
-    let mut data = Vec::new();
-    data.extend_from_slice(&listing_hash.to_be_bytes::<32>());
-    data.extend_from_slice(&price.to_be_bytes::<32>());
-    let _ = evm::raw_log(topics, &data);
+    log(
+        "SignalPurchased(address,address,uint256,uint256)",
+        &[buyer.into_word(), seller.into_word()],
+        &[word(listing_hash), word(price)],
+    );
+}
+
+
+// -----------------------------------------------------------------------------
+// Output escrow (pull-based claims)
+// -----------------------------------------------------------------------------
+/// Swap output credited to user's claimable balance instead of being transferred.
+pub fn emit_output_escrowed(user: Address, token: Address, amount: U256) {
+    log("OutputEscrowed(address,address,uint256)", &[user.into_word(), token.into_word()], &[word(amount)]);
+}
+
+/// Claimable balance moved between users without leaving the contract.
+pub fn emit_claim_transferred(from: Address, to: Address, token: Address, amount: U256) {
+    log(
+        "ClaimTransferred(address,address,address,uint256)",
+        &[from.into_word(), to.into_word(), token.into_word()],
+        &[word(amount)],
+    );
+}
+
+/// Escrowed output claimed by user.
+pub fn emit_output_claimed(user: Address, token: Address, amount: U256) {
+    log("OutputClaimed(address,address,uint256)", &[user.into_word(), token.into_word()], &[word(amount)]);
+}
+
+/// ClaimableSwap(user indexed, token_in indexed, token_out indexed, amount_in, amount_out, fee_bps):
+/// a swap paid from and credited to the user's claimable balance (`swap_from_claimable`).
+pub fn emit_claimable_swap(
+    user: Address,
+    token_in: Address,
+    token_out: Address,
+    amount_in: U256,
+    amount_out: U256,
+    fee_bps: U256,
+) {
+    log(
+        "ClaimableSwap(address,address,address,uint256,uint256,uint256)",
+        &[user.into_word(), token_in.into_word(), token_out.into_word()],
+        &[word(amount_in), word(amount_out), word(fee_bps)],
+    );
+}
+
+/// ClaimableSwept(user indexed, token indexed, amount): abandoned escrow moved to treasury.
+pub fn emit_claimable_swept(user: Address, token: Address, amount: U256) {
+    log("ClaimableSwept(address,address,uint256)", &[user.into_word(), token.into_word()], &[word(amount)]);
+}
+
+/// Emit when governance changes the large-commitment price band (threshold_bps 0 = disabled).
+pub fn emit_commit_price_band_set(threshold_bps: U256, band_bps: U256) {
+    log("CommitPriceBandSet(uint256,uint256)", &[], &[word(threshold_bps), word(band_bps)]);
+}
+
+/// Emit when governance changes the abandoned-escrow sweep period (0 = disabled).
+pub fn emit_escrow_sweep_period_set(blocks: U256) {
+    log("EscrowSweepPeriodSet(uint256)", &[], &[word(blocks)]);
+}
+
+/// PoolEpochClosed(token0 indexed, token1 indexed, epoch, volume0, volume1, fees0, fees1).
+/// Archived totals of a closed statistics epoch (reset_pool_stats).
+pub fn emit_pool_epoch_closed(
+    token0: Address,
+    token1: Address,
+    epoch: U256,
+    volume0: U256,
+    volume1: U256,
+    fees0: U256,
+    fees1: U256,
+) {
+    log(
+        "PoolEpochClosed(address,address,uint256,uint256,uint256,uint256,uint256)",
+        &[token0.into_word(), token1.into_word()],
+        &[word(epoch), word(volume0), word(volume1), word(fees0), word(fees1)],
+    );
+}
+
+/// CommitBondSet(token indexed, amount): bond required by commit_swap (0 = none).
+pub fn emit_commit_bond_set(token: Address, amount: U256) {
+    log("CommitBondSet(address,uint256)", &[token.into_word()], &[word(amount)]);
+}
+
+/// CommitBondSlashed(user indexed, token indexed, amount): abandoned commitment's bond sent to treasury.
+pub fn emit_commit_bond_slashed(user: Address, token: Address, amount: U256) {
+    log("CommitBondSlashed(address,address,uint256)", &[user.into_word(), token.into_word()], &[word(amount)]);
+}
+
+/// Emit when governance changes the pool creator share (applies to pools created afterwards).
+pub fn emit_creator_fee_share_set(share_bps: U256, period_blocks: U256) {
+    log("CreatorFeeShareSet(uint256,uint256)", &[], &[word(share_bps), word(period_blocks)]);
+}
+
+/// CreatorFeesClaimed(creator indexed, token indexed, amount).
+pub fn emit_creator_fees_claimed(creator: Address, token: Address, amount: U256) {
+    log("CreatorFeesClaimed(address,address,uint256)", &[creator.into_word(), token.into_word()], &[word(amount)]);
+}
+
+/// Emit when governance changes the batch auction window (0 = batch mode off).
+pub fn emit_batch_window_set(blocks: U256) {
+    log("BatchWindowSet(uint256)", &[], &[word(blocks)]);
+}
+
+/// BatchOrderRevealed(owner indexed, token0 indexed, token1 indexed, batch_id, index, zero_for_one, amount_in).
+pub fn emit_batch_order_revealed(
+    owner: Address,
+    token0: Address,
+    token1: Address,
+    batch_id: U256,
+    index: U256,
+    zero_for_one: bool,
+    amount_in: U256,
+) {
+    log(
+        "BatchOrderRevealed(address,address,address,uint256,uint256,bool,uint256)",
+        &[owner.into_word(), token0.into_word(), token1.into_word()],
+        &[word(batch_id), word(index), bool_word(zero_for_one), word(amount_in)],
+    );
+}
+
+/// BatchSettled(token0 indexed, token1 indexed, batch_id, price_num, price_den, residual_in).
+/// Uniform clearing price is price_num / price_den token1 per token0.
+pub fn emit_batch_settled(
+    token0: Address,
+    token1: Address,
+    batch_id: U256,
+    price_num: U256,
+    price_den: U256,
+    residual_in: U256,
+) {
+    log(
+        "BatchSettled(address,address,uint256,uint256,uint256,uint256)",
+        &[token0.into_word(), token1.into_word()],
+        &[word(batch_id), word(price_num), word(price_den), word(residual_in)],
+    );
+}
+
+/// BatchOrderReclaimed(owner indexed, batch_id, index, amount): unsettled input returned.
+pub fn emit_batch_order_reclaimed(owner: Address, batch_id: U256, index: U256, amount: U256) {
+    log(
+        "BatchOrderReclaimed(address,uint256,uint256,uint256)",
+        &[owner.into_word()],
+        &[word(batch_id), word(index), word(amount)],
+    );
+}
+
+/// BootstrapFeedSet(token0 indexed, token1 indexed, feed indexed, scale, tolerance_bps).
+pub fn emit_bootstrap_feed_set(token0: Address, token1: Address, feed: Address, scale: U256, tolerance_bps: U256) {
+    log(
+        "BootstrapFeedSet(address,address,address,uint256,uint256)",
+        &[token0.into_word(), token1.into_word(), feed.into_word()],
+        &[word(scale), word(tolerance_bps)],
+    );
+}
+
+/// LiquidityLocked(owner indexed, token0 indexed, token1 indexed, amount, unlock_block).
+/// `amount` is the owner's total locked LP after this lock.
+pub fn emit_liquidity_locked(owner: Address, token0: Address, token1: Address, amount: U256, unlock_block: U256) {
+    log(
+        "LiquidityLocked(address,address,address,uint256,uint256)",
+        &[owner.into_word(), token0.into_word(), token1.into_word()],
+        &[word(amount), word(unlock_block)],
+    );
+}
+
+/// CommitOperatorSet(user indexed, operator indexed, approved).
+pub fn emit_commit_operator_set(user: Address, operator: Address, approved: bool) {
+    log("CommitOperatorSet(address,address,bool)", &[user.into_word(), operator.into_word()], &[bool_word(approved)]);
+}
+
+/// PrivateSwapRevealed(user indexed, private_hash): a private reveal's undisclosed-fields digest.
+pub fn emit_private_swap_revealed(user: Address, private_hash: FixedBytes<32>) {
+    log("PrivateSwapRevealed(address,bytes32)", &[user.into_word()], &[private_hash]);
+}
+
+/// Sync(token0 indexed, token1 indexed, reserve0, reserve1): reserves reset to the balances.
+pub fn emit_sync(token0: Address, token1: Address, reserve0: U256, reserve1: U256) {
+    log(
+        "Sync(address,address,uint256,uint256)",
+        &[token0.into_word(), token1.into_word()],
+        &[word(reserve0), word(reserve1)],
+    );
+}
+
+/// Skim(token0 indexed, token1 indexed, to indexed, amount0, amount1): surplus swept to `to`.
+pub fn emit_skim(token0: Address, token1: Address, to: Address, amount0: U256, amount1: U256) {
+    log(
+        "Skim(address,address,address,uint256,uint256)",
+        &[token0.into_word(), token1.into_word(), to.into_word()],
+        &[word(amount0), word(amount1)],
+    );
 }
 
+/// PairCreated(token0 indexed, token1 indexed, pair_key, all_pairs_length), Uniswap V2 style.
+pub fn emit_pair_created(token0: Address, token1: Address, pair_key: FixedBytes<32>, all_pairs_length: U256) {
+    log(
+        "PairCreated(address,address,bytes32,uint256)",
+        &[token0.into_word(), token1.into_word()],
+        &[pair_key, word(all_pairs_length)],
+    );
+}
+
+/// TokensBound(token0 indexed, token1 indexed): deployment restricted to this one pair.
+pub fn emit_tokens_bound(token0: Address, token1: Address) {
+    log("TokensBound(address,address)", &[token0.into_word(), token1.into_word()], &[]);
+}
+
+/// PoolFeeTierSet(token0 indexed, token1 indexed, fee_bps): pool created on a fee tier.
+pub fn emit_pool_fee_tier_set(token0: Address, token1: Address, fee_bps: U256) {
+    log("PoolFeeTierSet(address,address,uint256)", &[token0.into_word(), token1.into_word()], &[word(fee_bps)]);
+}
+
+/// FeeTierSet(fee_bps, enabled): fee tier whitelist change.
+pub fn emit_fee_tier_set(fee_bps: U256, enabled: bool) {
+    log("FeeTierSet(uint256,bool)", &[], &[word(fee_bps), bool_word(enabled)]);
+}
+
+/// EffectiveFeeAdjusted(token0 indexed, token1 indexed, regime, volatility_bps, fee_bps):
+/// the pool's volatility regime changed; `fee_bps` is charged from the next trade on.
+pub fn emit_effective_fee_adjusted(token0: Address, token1: Address, regime: U256, volatility_bps: U256, fee_bps: U256) {
+    log(
+        "EffectiveFeeAdjusted(address,address,uint256,uint256,uint256)",
+        &[token0.into_word(), token1.into_word()],
+        &[word(regime), word(volatility_bps), word(fee_bps)],
+    );
+}
+
+/// VolatilityFeeConfigSet(calm_bps, volatile_bps, surcharge_bps, discount_bps).
+pub fn emit_volatility_fee_config_set(calm_bps: U256, volatile_bps: U256, surcharge_bps: U256, discount_bps: U256) {
+    log(
+        "VolatilityFeeConfigSet(uint256,uint256,uint256,uint256)",
+        &[],
+        &[word(calm_bps), word(volatile_bps), word(surcharge_bps), word(discount_bps)],
+    );
+}
+
+/// TokenAliasSet(alias indexed, canonical indexed): `alias` is a representation of `canonical`.
+pub fn emit_token_alias_set(alias: Address, canonical: Address) {
+    log("TokenAliasSet(address,address)", &[alias.into_word(), canonical.into_word()], &[]);
+}
+
+/// TokenAliasRemoved(alias indexed, canonical indexed).
+pub fn emit_token_alias_removed(alias: Address, canonical: Address) {
+    log("TokenAliasRemoved(address,address)", &[alias.into_word(), canonical.into_word()], &[]);
+}
+
+/// TokenRiskSet(token indexed, max_pool_exposure, flash_borrow_allowed, oracle_required, hooked).
+pub fn emit_token_risk_set(token: Address, max_pool_exposure: U256, flash_borrow_allowed: bool, oracle_required: bool, hooked: bool) {
+    log(
+        "TokenRiskSet(address,uint256,bool,bool,bool)",
+        &[token.into_word()],
+        &[word(max_pool_exposure), bool_word(flash_borrow_allowed), bool_word(oracle_required), bool_word(hooked)],
+    );
+}
+
+/// LpFeesClaimed(owner indexed, token0 indexed, token1 indexed, amount0, amount1).
+pub fn emit_lp_fees_claimed(owner: Address, token0: Address, token1: Address, amount0: U256, amount1: U256) {
+    log(
+        "LpFeesClaimed(address,address,address,uint256,uint256)",
+        &[owner.into_word(), token0.into_word(), token1.into_word()],
+        &[word(amount0), word(amount1)],
+    );
+}
+
+/// LpSurplusPolicySet(min_age_epochs, max_sweep_bps). max_sweep_bps = 0 disables sweeping.
+pub fn emit_lp_surplus_policy_set(min_age_epochs: U256, max_sweep_bps: U256) {
+    log("LpSurplusPolicySet(uint256,uint256)", &[], &[word(min_age_epochs), word(max_sweep_bps)]);
+}
+
+/// LpSurplusSweepQueued(token0 indexed, token1 indexed, amount0, amount1, after_block, ready_epoch).
+pub fn emit_lp_surplus_sweep_queued(
+    token0: Address,
+    token1: Address,
+    amount0: U256,
+    amount1: U256,
+    after_block: U256,
+    ready_epoch: U256,
+) {
+    log(
+        "LpSurplusSweepQueued(address,address,uint256,uint256,uint256,uint256)",
+        &[token0.into_word(), token1.into_word()],
+        &[word(amount0), word(amount1), word(after_block), word(ready_epoch)],
+    );
+}
+
+/// LpSurplusSwept(token0 indexed, token1 indexed, amount0, amount1): moved to the treasury balance.
+pub fn emit_lp_surplus_swept(token0: Address, token1: Address, amount0: U256, amount1: U256) {
+    log(
+        "LpSurplusSwept(address,address,uint256,uint256)",
+        &[token0.into_word(), token1.into_word()],
+        &[word(amount0), word(amount1)],
+    );
+}
+
+/// LpSurplusSweepCancelled(token0 indexed, token1 indexed).
+pub fn emit_lp_surplus_sweep_cancelled(token0: Address, token1: Address) {
+    log("LpSurplusSweepCancelled(address,address)", &[token0.into_word(), token1.into_word()], &[]);
+}
+
+/// PoolCommitSchemesSet(token0 indexed, token1 indexed, schemes): accepted commitment schemes bitmask.
+pub fn emit_pool_commit_schemes_set(token0: Address, token1: Address, schemes: U256) {
+    log("PoolCommitSchemesSet(address,address,uint256)", &[token0.into_word(), token1.into_word()], &[word(schemes)]);
+}
+
+/// GasRebateAccrued(revealer indexed, token indexed, amount).
+pub fn emit_gas_rebate_accrued(revealer: Address, token: Address, amount: U256) {
+    log("GasRebateAccrued(address,address,uint256)", &[revealer.into_word(), token.into_word()], &[word(amount)]);
+}
+
+/// GasRebateClaimed(revealer indexed, token indexed, amount).
+pub fn emit_gas_rebate_claimed(revealer: Address, token: Address, amount: U256) {
+    log("GasRebateClaimed(address,address,uint256)", &[revealer.into_word(), token.into_word()], &[word(amount)]);
+}
+
+/// CommitmentCancelledWithPenalty(user indexed, token indexed, refunded, penalty): cancelled
+/// inside the live reveal window; `penalty` of the bond went to the treasury.
+pub fn emit_commitment_cancelled_with_penalty(user: Address, token: Address, refunded: U256, penalty: U256) {
+    log(
+        "CommitmentCancelledWithPenalty(address,address,uint256,uint256)",
+        &[user.into_word(), token.into_word()],
+        &[word(refunded), word(penalty)],
+    );
+}
+
+/// TreasurySplitsProposed(count, after_block): split table of `count` recipients pending.
+pub fn emit_treasury_splits_proposed(count: U256, after_block: U256) {
+    log("TreasurySplitsProposed(uint256,uint256)", &[], &[word(count), word(after_block)]);
+}
+
+/// TreasurySplitsSet(count): split table installed (0 = splitting off).
+pub fn emit_treasury_splits_set(count: U256) {
+    log("TreasurySplitsSet(uint256)", &[], &[word(count)]);
+}
+
+/// ReferralFeesClaimed(referrer indexed, token indexed, amount).
+pub fn emit_referral_fees_claimed(referrer: Address, token: Address, amount: U256) {
+    log("ReferralFeesClaimed(address,address,uint256)", &[referrer.into_word(), token.into_word()], &[word(amount)]);
+}
+
+/// ReferralBpsSet(bps): referral share of the treasury fee.
+pub fn emit_referral_bps_set(bps: U256) {
+    log("ReferralBpsSet(uint256)", &[], &[word(bps)]);
+}
+
+/// FlashLoan(receiver indexed, token indexed, initiator indexed, amount, fee): ERC-3156 loan repaid.
+pub fn emit_flash_loan(receiver: Address, token: Address, initiator: Address, amount: U256, fee: U256) {
+    log(
+        "FlashLoan(address,address,address,uint256,uint256)",
+        &[receiver.into_word(), token.into_word(), initiator.into_word()],
+        &[word(amount), word(fee)],
+    );
+}
+
+/// FlashLoanPoolSet(token indexed, counter_token indexed): pool ERC-3156 loans of `token` use.
+pub fn emit_flash_loan_pool_set(token: Address, counter_token: Address) {
+    log("FlashLoanPoolSet(address,address)", &[token.into_word(), counter_token.into_word()], &[]);
+}
+
+/// FlashCallbackSet(borrower indexed, style): callback style flash_swap uses for `borrower`.
+pub fn emit_flash_callback_set(borrower: Address, style: U256) {
+    log("FlashCallbackSet(address,uint256)", &[borrower.into_word()], &[word(style)]);
+}
+
+/// MaxPriceImpactSet(max_impact_bps): per-swap spot price move limit (0 = no limit).
+pub fn emit_max_price_impact_set(max_impact_bps: U256) {
+    log("MaxPriceImpactSet(uint256)", &[], &[word(max_impact_bps)]);
+}
+
+/// MaxOutflowSet(max_outflow_bps): per-block, per-direction pool outflow limit (0 = no limit).
+pub fn emit_max_outflow_set(max_outflow_bps: U256) {
+    log("MaxOutflowSet(uint256)", &[], &[word(max_outflow_bps)]);
+}
+
+/// FlashFeeSet(fee_bps): flash loan fee (0 = each pool's swap fee).
+pub fn emit_flash_fee_set(fee_bps: U256) {
+    log("FlashFeeSet(uint256)", &[], &[word(fee_bps)]);
+}
+
+/// PoolRetired(pool indexed, successor indexed, retired_after_block).
+pub fn emit_pool_retired(pool: FixedBytes<32>, successor: FixedBytes<32>, retired_after_block: U256) {
+    log("PoolRetired(bytes32,bytes32,uint256)", &[pool, successor], &[word(retired_after_block)]);
+}
+
+/// ERC-1155 TransferSingle(operator indexed, from indexed, to indexed, id, value).
+pub fn emit_erc1155_transfer_single(operator: Address, from: Address, to: Address, id: U256, value: U256) {
+    log(
+        "TransferSingle(address,address,address,uint256,uint256)",
+        &[operator.into_word(), from.into_word(), to.into_word()],
+        &[word(id), word(value)],
+    );
+}
+
+/// ERC-1155 TransferBatch(operator indexed, from indexed, to indexed, ids, values).
+pub fn emit_erc1155_transfer_batch(operator: Address, from: Address, to: Address, ids: &[U256], values: &[U256]) {
+    log(
+        "TransferBatch(address,address,address,uint256[],uint256[])",
+        &[operator.into_word(), from.into_word(), to.into_word()],
+        &uint_arrays(&[ids, values]),
+    );
+}
+
+/// ERC-1155 ApprovalForAll(account indexed, operator indexed, approved).
+pub fn emit_erc1155_approval_for_all(account: Address, operator: Address, approved: bool) {
+    log("ApprovalForAll(address,address,bool)", &[account.into_word(), operator.into_word()], &[bool_word(approved)]);
+}
+
+/// CommitSponsorshipDeposited(sponsor indexed, token indexed, amount): prepaid commit bonds.
+pub fn emit_commit_sponsorship_deposited(sponsor: Address, token: Address, amount: U256) {
+    log(
+        "CommitSponsorshipDeposited(address,address,uint256)",
+        &[sponsor.into_word(), token.into_word()],
+        &[word(amount)],
+    );
+}
+
+/// CommitSponsorshipWithdrawn(sponsor indexed, token indexed, amount).
+pub fn emit_commit_sponsorship_withdrawn(sponsor: Address, token: Address, amount: U256) {
+    log(
+        "CommitSponsorshipWithdrawn(address,address,uint256)",
+        &[sponsor.into_word(), token.into_word()],
+        &[word(amount)],
+    );
+}
+
+/// CommitVoucherIssued(sponsor indexed, redeemer indexed, id, uses); redeemer zero = anyone.
+pub fn emit_commit_voucher_issued(id: U256, sponsor: Address, redeemer: Address, uses: U256) {
+    log(
+        "CommitVoucherIssued(address,address,uint256,uint256)",
+        &[sponsor.into_word(), redeemer.into_word()],
+        &[word(id), word(uses)],
+    );
+}
+
+/// CommitVoucherRedeemed(user indexed, sponsor indexed, id): a commit bond paid by a voucher.
+pub fn emit_commit_voucher_redeemed(id: U256, user: Address, sponsor: Address) {
+    log("CommitVoucherRedeemed(address,address,uint256)", &[user.into_word(), sponsor.into_word()], &[word(id)]);
+}
@@ -0,0 +1,138 @@
+//! Volatility-based fee controller.
+//!
+//! Each pool's charged fee is its base fee (tier or global `protocol_fee_bps`) moved by the
+//! pool's volatility regime. Volatility is the gap between the spot price and the pool's
+//! current-window TWAP, in bps of the TWAP. At every oracle update (before any swap or deposit
+//! changes reserves) the controller re-classifies the pool: at or above the volatile threshold
+//! the owner-set surcharge applies, at or below the calm threshold the discount applies, in
+//! between the base fee. A regime change emits `EffectiveFeeAdjusted`; the new fee applies from
+//! the next trade on, so quotes always match what the next swap is charged.
+
+use stylus_sdk::alloy_primitives::{Address, U256};
+
+use crate::constants::{as_u256, BPS, FEE_REGIME_CALM, FEE_REGIME_NORMAL, FEE_REGIME_VOLATILE, MAX_FEE_BPS};
+use crate::errors::{err, MathResult, OakResult, ERR_DIVISION_BY_ZERO, ERR_INVALID_FEE_CONFIG, ERR_OVERFLOW};
+use crate::events::emit_effective_fee_adjusted;
+use crate::oracle::{spot_prices, PoolOracle};
+use crate::state::OakDEX;
+
+/// Deviation of `spot` from `twap` in bps of `twap` (both Q112).
+pub fn volatility_bps(spot: U256, twap: U256) -> MathResult<U256> {
+    let gap = if spot > twap { spot - twap } else { twap - spot };
+    gap.checked_mul(as_u256(BPS))
+        .ok_or(ERR_OVERFLOW)?
+        .checked_div(twap)
+        .ok_or(ERR_DIVISION_BY_ZERO)
+}
+
+/// Regime for a volatility reading; `volatile_bps` 0 disables the controller (always normal).
+pub fn classify_volatility(volatility: U256, calm_bps: U256, volatile_bps: U256) -> U256 {
+    if volatile_bps.is_zero() {
+        as_u256(FEE_REGIME_NORMAL)
+    } else if volatility >= volatile_bps {
+        as_u256(FEE_REGIME_VOLATILE)
+    } else if volatility <= calm_bps {
+        as_u256(FEE_REGIME_CALM)
+    } else {
+        as_u256(FEE_REGIME_NORMAL)
+    }
+}
+
+/// `base_bps` adjusted for `regime`: plus `surcharge_bps` capped at MAX_FEE_BPS, or minus
+/// `discount_bps` floored at zero.
+pub fn regime_fee_bps(base_bps: U256, regime: U256, surcharge_bps: U256, discount_bps: U256) -> U256 {
+    if regime == as_u256(FEE_REGIME_VOLATILE) {
+        base_bps.saturating_add(surcharge_bps).min(as_u256(MAX_FEE_BPS).max(base_bps))
+    } else if regime == as_u256(FEE_REGIME_CALM) {
+        base_bps.saturating_sub(discount_bps)
+    } else {
+        base_bps
+    }
+}
+
+/// Dynamic fee controller (uses OakDEX vol_fee_* and PoolData fee_regime storage).
+pub struct FeeController;
+
+impl FeeController {
+    /// Set the thresholds (bps of TWAP) and adjustments (bps of fee); `volatile_bps` 0 disables.
+    pub fn set_config(
+        dex: &mut OakDEX,
+        calm_bps: U256,
+        volatile_bps: U256,
+        surcharge_bps: U256,
+        discount_bps: U256,
+    ) -> OakResult<()> {
+        let max_fee = as_u256(MAX_FEE_BPS);
+        if surcharge_bps > max_fee || discount_bps > max_fee {
+            return Err(err(ERR_INVALID_FEE_CONFIG));
+        }
+        if !volatile_bps.is_zero() && calm_bps >= volatile_bps {
+            return Err(err(ERR_INVALID_FEE_CONFIG));
+        }
+        dex.vol_fee_calm_bps.set(calm_bps);
+        dex.vol_fee_volatile_bps.set(volatile_bps);
+        dex.vol_fee_surcharge_bps.set(surcharge_bps);
+        dex.vol_fee_discount_bps.set(discount_bps);
+        Ok(())
+    }
+
+    /// Current (calm_bps, volatile_bps, surcharge_bps, discount_bps).
+    pub fn config(dex: &OakDEX) -> (U256, U256, U256, U256) {
+        (
+            dex.vol_fee_calm_bps.get(),
+            dex.vol_fee_volatile_bps.get(),
+            dex.vol_fee_surcharge_bps.get(),
+            dex.vol_fee_discount_bps.get(),
+        )
+    }
+
+    /// Fee (bps) charged by (token0, token1) given its base fee; base when disabled.
+    pub fn effective_fee_bps(dex: &OakDEX, token0: Address, token1: Address, base_bps: U256) -> U256 {
+        if dex.vol_fee_volatile_bps.get().is_zero() {
+            return base_bps;
+        }
+        let regime = dex.pools.getter(token0).getter(token1).fee_regime.get();
+        regime_fee_bps(base_bps, regime, dex.vol_fee_surcharge_bps.get(), dex.vol_fee_discount_bps.get())
+    }
+
+    /// Volatility (bps) of (token0, token1): spot vs current-window TWAP; None without a window.
+    pub fn volatility(dex: &OakDEX, token0: Address, token1: Address) -> OakResult<Option<U256>> {
+        let (twap, _, window) = PoolOracle::twap(dex, token0, token1)?;
+        if window.is_zero() {
+            return Ok(None);
+        }
+        let pool = dex.pools.getter(token0);
+        let pool = pool.getter(token1);
+        let (spot, _) = spot_prices(pool.reserve0.get(), pool.reserve1.get()).map_err(err)?;
+        Ok(Some(volatility_bps(spot, twap).map_err(err)?))
+    }
+
+    /// Re-classify (token0, token1) from its latest volatility; emits on a regime change.
+    ///
+    /// @dev Canonical order. No-op when disabled, for empty pools and without a TWAP window.
+    pub fn adjust(dex: &mut OakDEX, token0: Address, token1: Address, base_bps: U256) -> OakResult<()> {
+        let volatile_bps = dex.vol_fee_volatile_bps.get();
+        if volatile_bps.is_zero() {
+            return Ok(());
+        }
+        {
+            let outer = dex.pools.getter(token0);
+            let pool = outer.getter(token1);
+            if pool.reserve0.get().is_zero() || pool.reserve1.get().is_zero() {
+                return Ok(());
+            }
+        }
+        let Some(volatility) = Self::volatility(dex, token0, token1)? else {
+            return Ok(());
+        };
+        let regime = classify_volatility(volatility, dex.vol_fee_calm_bps.get(), volatile_bps);
+        let previous = dex.pools.getter(token0).getter(token1).fee_regime.get();
+        if regime == previous {
+            return Ok(());
+        }
+        dex.pools.setter(token0).setter(token1).fee_regime.set(regime);
+        let fee_bps = Self::effective_fee_bps(dex, token0, token1, base_bps);
+        emit_effective_fee_adjusted(token0, token1, regime, volatility, fee_bps);
+        Ok(())
+    }
+}
@@ -0,0 +1,88 @@
+//! Per-block outflow limiter: caps how much of a pool's reserve can leave it in one block.
+//!
+//! Swaps (reveals included), flash swaps and flash loans add what they take out of a pool to a per-block,
+//! per-direction accumulator. Once the total would exceed `max_outflow_bps_per_block` of the
+//! reserve the pool held at that block's first outflow, the transaction reverts. A drain then
+//! needs many blocks instead of one, which gives the circuit breaker or a guardian time to act.
+//! The accumulators reset lazily on the first outflow of a new block.
+
+use stylus_sdk::{
+    alloy_primitives::{Address, U256},
+    block,
+};
+
+use crate::constants::{as_u256, BPS};
+use crate::errors::{err, OakResult, ERR_OUTFLOW_LIMIT_EXCEEDED, ERR_OVERFLOW};
+use crate::state::OakDEX;
+
+/// `get_pool_outflow` result, canonical token order:
+/// (max_outflow_bps_per_block, outflow0, outflow1), the outflows being this block's totals so far.
+pub type PoolOutflowView = (U256, U256, U256);
+
+/// Reverts unless `used + amount` stays within `max_bps` of `base_reserve`.
+pub fn check_outflow(base_reserve: U256, used: U256, amount: U256, max_bps: U256) -> OakResult<()> {
+    let total = used.checked_add(amount).ok_or_else(|| err(ERR_OVERFLOW))?;
+    let scaled = total.checked_mul(as_u256(BPS)).ok_or_else(|| err(ERR_OVERFLOW))?;
+    let limit = base_reserve.checked_mul(max_bps).ok_or_else(|| err(ERR_OVERFLOW))?;
+    if scaled > limit {
+        return Err(err(ERR_OUTFLOW_LIMIT_EXCEEDED));
+    }
+    Ok(())
+}
+
+/// Outflow limiter (uses PoolData outflow_* storage). Token pair must be canonical.
+pub struct FlowLimiter;
+
+impl FlowLimiter {
+    /// Record `amount` of token0 (`out_token0`) or token1 leaving the pool, whose reserve of that
+    /// token is `reserve_out` before it leaves; reverts past the per-block limit.
+    pub fn record_outflow(
+        dex: &mut OakDEX,
+        token0: Address,
+        token1: Address,
+        out_token0: bool,
+        reserve_out: U256,
+        amount: U256,
+    ) -> OakResult<()> {
+        let max_bps = dex.max_outflow_bps_per_block.get();
+        if max_bps.is_zero() || amount.is_zero() {
+            return Ok(());
+        }
+        let now = U256::from(block::number());
+        let mut outer = dex.pools.setter(token0);
+        let mut pool = outer.setter(token1);
+        if pool.outflow_block.get() != now {
+            pool.outflow_block.set(now);
+            pool.outflow0.set(U256::ZERO);
+            pool.outflow1.set(U256::ZERO);
+            pool.outflow_base0.set(U256::ZERO);
+            pool.outflow_base1.set(U256::ZERO);
+        }
+        let (used, base) = if out_token0 {
+            (pool.outflow0.get(), pool.outflow_base0.get())
+        } else {
+            (pool.outflow1.get(), pool.outflow_base1.get())
+        };
+        let base = if base.is_zero() { reserve_out } else { base };
+        check_outflow(base, used, amount, max_bps)?;
+        let total = used + amount;
+        if out_token0 {
+            pool.outflow0.set(total);
+            pool.outflow_base0.set(base);
+        } else {
+            pool.outflow1.set(total);
+            pool.outflow_base1.set(base);
+        }
+        Ok(())
+    }
+
+    /// (outflow0, outflow1) recorded for the pool in the current block.
+    pub fn current(dex: &OakDEX, token0: Address, token1: Address) -> (U256, U256) {
+        let outer = dex.pools.getter(token0);
+        let pool = outer.getter(token1);
+        if pool.outflow_block.get() != U256::from(block::number()) {
+            return (U256::ZERO, U256::ZERO);
+        }
+        (pool.outflow0.get(), pool.outflow1.get())
+    }
+}
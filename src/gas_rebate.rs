@@ -0,0 +1,82 @@
+//! Gas rebates: a slice of swap fees refunded to commit-reveal revealers.
+//!
+//! A protected swap costs two transactions (commit, then reveal) where a direct swap costs one.
+//! To offset the extra one, `compute_fee_split` carves GAS_REBATE_BPS of every swap fee out of
+//! the treasury share. When the swap settles a commitment the rebate is credited to the revealer
+//! in the input token; any other swap leaves it with the treasury.
+//!
+//! Rebates are held per user and token and paid out together by `claim_gas_rebate`. A user
+//! tracks at most MAX_GAS_REBATE_TOKENS tokens between claims so the claim loop stays bounded;
+//! a rebate in a further token is not credited and stays with the treasury.
+
+use alloc::vec::Vec;
+use stylus_sdk::alloy_primitives::{Address, U256};
+
+use crate::constants::{as_u256, MAX_GAS_REBATE_TOKENS};
+use crate::errors::{err, OakResult, ERR_NOTHING_TO_CLAIM, ERR_OVERFLOW};
+use crate::events::{emit_gas_rebate_accrued, emit_gas_rebate_claimed};
+use crate::state::OakDEX;
+use crate::token::safe_transfer;
+
+/// Gas rebate accounting (uses OakDEX gas rebate storage).
+pub struct GasRebate;
+
+impl GasRebate {
+    /// Credit `amount` of `token` to `revealer`; returns the amount actually credited.
+    ///
+    /// @dev Tokens must already be held by the contract. Returns zero (nothing credited) when
+    ///      `revealer` already tracks MAX_GAS_REBATE_TOKENS other tokens.
+    pub fn accrue(dex: &mut OakDEX, revealer: Address, token: Address, amount: U256) -> OakResult<U256> {
+        if amount.is_zero() {
+            return Ok(U256::ZERO);
+        }
+        let prev = dex.accrued_gas_rebate.getter(revealer).get(token);
+        if prev.is_zero() {
+            let count = dex.gas_rebate_token_count.get(revealer);
+            if count >= as_u256(MAX_GAS_REBATE_TOKENS) {
+                return Ok(U256::ZERO);
+            }
+            dex.gas_rebate_tokens.setter(revealer).setter(count).set(token);
+            dex.gas_rebate_token_count.setter(revealer).set(count + U256::from(1u64));
+        }
+        let owed = prev.checked_add(amount).ok_or_else(|| err(ERR_OVERFLOW))?;
+        let held = dex.gas_rebates_held.get(token).checked_add(amount).ok_or_else(|| err(ERR_OVERFLOW))?;
+        dex.accrued_gas_rebate.setter(revealer).setter(token).set(owed);
+        dex.gas_rebates_held.setter(token).set(held);
+        emit_gas_rebate_accrued(revealer, token, amount);
+        Ok(amount)
+    }
+
+    /// Unclaimed rebate of `user` in `token`.
+    pub fn pending(dex: &OakDEX, user: Address, token: Address) -> U256 {
+        dex.accrued_gas_rebate.getter(user).get(token)
+    }
+
+    /// Pay out every unclaimed rebate of `user`; returns the number of tokens paid.
+    ///
+    /// @dev Balances and the token list are cleared before any transfer (CEI).
+    pub fn claim(dex: &mut OakDEX, user: Address) -> OakResult<U256> {
+        let count = dex.gas_rebate_token_count.get(user);
+        if count.is_zero() {
+            return Err(err(ERR_NOTHING_TO_CLAIM));
+        }
+        let mut payouts = Vec::new();
+        let mut i = U256::ZERO;
+        while i < count {
+            let token = dex.gas_rebate_tokens.getter(user).get(i);
+            let amount = dex.accrued_gas_rebate.getter(user).get(token);
+            dex.accrued_gas_rebate.setter(user).setter(token).set(U256::ZERO);
+            dex.gas_rebate_tokens.setter(user).setter(i).set(Address::ZERO);
+            let held = dex.gas_rebates_held.get(token).saturating_sub(amount);
+            dex.gas_rebates_held.setter(token).set(held);
+            payouts.push((token, amount));
+            i += U256::from(1u64);
+        }
+        dex.gas_rebate_token_count.setter(user).set(U256::ZERO);
+        for &(token, amount) in &payouts {
+            safe_transfer(token, user, amount)?;
+            emit_gas_rebate_claimed(user, token, amount);
+        }
+        Ok(count)
+    }
+}
@@ -4,6 +4,9 @@
 
 use stylus_sdk::alloy_primitives::{Address, U256};
 
+use crate::access::{require_role, ROLE_ADMIN};
+use crate::audit::AdminAuditLog;
+use crate::constants::AUDIT_ACTION_SET_BADGE_CONTRACT;
 use crate::errors::{err, OakResult, ERR_OVERFLOW};
 use crate::events::{emit_emission_event, emission_module_quest};
 use crate::state::OakDEX;
@@ -44,11 +47,10 @@ impl QuestSystem {
         Ok(())
     }
 
-    /// Set badge NFT contract (owner only). 0 = disabled.
+    /// Set badge NFT contract (ROLE_ADMIN only). 0 = disabled.
     pub fn set_badge_contract(dex: &mut OakDEX, contract: Address) -> OakResult<()> {
-        if stylus_sdk::msg::sender() != dex.owner.get() {
-            return Err(err(crate::errors::ERR_ONLY_OWNER));
-        }
+        require_role(dex, ROLE_ADMIN)?;
+        AdminAuditLog::record(dex, AUDIT_ACTION_SET_BADGE_CONTRACT, &[contract.into_word()])?;
         dex.quest_badge_contract.set(contract);
         Ok(())
     }
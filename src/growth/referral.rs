@@ -1,17 +1,35 @@
 //! Referral Engine: referrer => referee mapping, % of fees to referrer on each swap.
 //! Emits EmissionEvent(Referral, referrer, ReferralFee, amount, 0) for indexer.
+//!
+//! A reveal credits the referrer committed with it (`commit_swap_with_referrer`), else the
+//! swapper's registered referrer. The share is accrued per referrer and token rather than
+//! transferred mid-swap, and pulled with `claim_referral_fees(token)`, so aggregators can
+//! integrate without a transfer in every swap (hooked pools included).
 
 use stylus_sdk::alloy_primitives::{Address, U256};
 
-use crate::constants::REFERRAL_FEE_BPS_MAX;
-use crate::errors::{err, OakResult, ERR_DIVISION_BY_ZERO, ERR_OVERFLOW, ERR_REFERRAL_FEE_TOO_HIGH, ERR_REFERRAL_SELF};
-use crate::events::{emit_emission_event, emission_module_referral};
+use crate::access::{require_role, ROLE_ADMIN};
+use crate::audit::{word, AdminAuditLog};
+use crate::constants::{AUDIT_ACTION_SET_REFERRAL_FEE, REFERRAL_FEE_BPS_MAX};
+use crate::errors::{
+    err, OakResult, ERR_DIVISION_BY_ZERO, ERR_NOTHING_TO_CLAIM, ERR_OVERFLOW, ERR_REFERRAL_FEE_TOO_HIGH, ERR_REFERRAL_SELF,
+};
+use crate::events::{emit_emission_event, emit_referral_bps_set, emit_referral_fees_claimed, emission_module_referral};
 use crate::state::OakDEX;
 use crate::token::safe_transfer;
 
 /// Event type: referral fee paid.
 pub const REFERRAL_EVENT_FEE: u64 = 3;
 
+/// Referral share of `fee_amount` at `bps` (of BPS), floored.
+pub fn referral_share(fee_amount: U256, bps: U256) -> OakResult<U256> {
+    fee_amount
+        .checked_mul(bps)
+        .ok_or_else(|| err(ERR_OVERFLOW))?
+        .checked_div(U256::from(10_000u64))
+        .ok_or_else(|| err(ERR_DIVISION_BY_ZERO))
+}
+
 /// Referral Engine (uses OakDEX growth storage).
 pub struct ReferralEngine;
 
@@ -35,27 +53,36 @@ impl ReferralEngine {
         dex.referral_referrer.getter(referee).get()
     }
 
-    /// Owner sets referral fee in basis points (e.g. 500 = 5% of protocol fee).
+    /// ROLE_ADMIN sets referral fee in basis points (e.g. 500 = 5% of protocol fee).
     pub fn set_referral_fee_bps(dex: &mut OakDEX, bps: U256) -> OakResult<()> {
-        let owner = dex.owner.get();
-        if stylus_sdk::msg::sender() != owner {
-            return Err(err(crate::errors::ERR_ONLY_OWNER));
-        }
+        require_role(dex, ROLE_ADMIN)?;
         if bps > U256::from(REFERRAL_FEE_BPS_MAX) {
             return Err(err(ERR_REFERRAL_FEE_TOO_HIGH));
         }
+        AdminAuditLog::record(dex, AUDIT_ACTION_SET_REFERRAL_FEE, &[word(bps)])?;
         dex.referral_fee_bps.set(bps);
+        emit_referral_bps_set(bps);
         Ok(())
     }
 
-    /// Distribute referral share of fee to referrer (call from swap logic).
-    pub fn distribute_referral_fee(
+    /// Referrer credited for a swap by `referee`: `committed` when set, else the registered one.
+    pub fn referrer_for(dex: &OakDEX, referee: Address, committed: Address) -> Address {
+        if committed != Address::ZERO {
+            committed
+        } else {
+            Self::get_referrer(dex, referee)
+        }
+    }
+
+    /// Accrue the referral share of `fee_amount` (in `token`) to `referrer` (call from swap logic).
+    ///
+    /// @dev Bookkeeping only; the tokens stay in the contract until claim_referral_fees.
+    pub fn accrue_referral_fee(
         dex: &mut OakDEX,
+        referrer: Address,
         token: Address,
         fee_amount: U256,
-        referee: Address,
     ) -> OakResult<U256> {
-        let referrer = dex.referral_referrer.getter(referee).get();
         if referrer == Address::ZERO || fee_amount.is_zero() {
             return Ok(U256::ZERO);
         }
@@ -63,15 +90,23 @@ impl ReferralEngine {
         if bps.is_zero() {
             return Ok(U256::ZERO);
         }
-        let referral_amount = fee_amount
-            .checked_mul(bps)
-            .ok_or_else(|| err(ERR_OVERFLOW))?
-            .checked_div(U256::from(10_000u64))
-            .ok_or_else(|| err(ERR_DIVISION_BY_ZERO))?;
+        let referral_amount = referral_share(fee_amount, bps)?;
         if referral_amount.is_zero() {
             return Ok(U256::ZERO);
         }
-        safe_transfer(token, referrer, referral_amount)?;
+        let owed = dex
+            .referral_accrued
+            .getter(referrer)
+            .get(token)
+            .checked_add(referral_amount)
+            .ok_or_else(|| err(ERR_OVERFLOW))?;
+        let held = dex
+            .referral_fees_held
+            .get(token)
+            .checked_add(referral_amount)
+            .ok_or_else(|| err(ERR_OVERFLOW))?;
+        dex.referral_accrued.setter(referrer).setter(token).set(owed);
+        dex.referral_fees_held.setter(token).set(held);
         emit_emission_event(
             emission_module_referral(),
             referrer,
@@ -81,4 +116,23 @@ impl ReferralEngine {
         );
         Ok(referral_amount)
     }
+
+    /// Unclaimed referral fees of `referrer` in `token`.
+    pub fn pending(dex: &OakDEX, referrer: Address, token: Address) -> U256 {
+        dex.referral_accrued.getter(referrer).get(token)
+    }
+
+    /// Pay `referrer` its accrued referral fees in `token`; returns the amount.
+    pub fn claim(dex: &mut OakDEX, referrer: Address, token: Address) -> OakResult<U256> {
+        let amount = Self::pending(dex, referrer, token);
+        if amount.is_zero() {
+            return Err(err(ERR_NOTHING_TO_CLAIM));
+        }
+        dex.referral_accrued.setter(referrer).setter(token).set(U256::ZERO);
+        let held = dex.referral_fees_held.get(token).saturating_sub(amount);
+        dex.referral_fees_held.setter(token).set(held);
+        safe_transfer(token, referrer, amount)?;
+        emit_referral_fees_claimed(referrer, token, amount);
+        Ok(amount)
+    }
 }
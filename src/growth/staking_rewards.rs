@@ -8,8 +8,12 @@ use stylus_sdk::{
     block,
 };
 
+use crate::access::{require_role, ROLE_ADMIN};
+use crate::audit::{word, AdminAuditLog};
+use crate::constants::AUDIT_ACTION_INIT_STAKING;
 use crate::errors::{err, OakResult, ERR_DIVISION_BY_ZERO, ERR_OVERFLOW, ERR_STAKING_NOT_INIT, ERR_STAKING_ZERO_AMOUNT};
 use crate::events::{emit_emission_event, emission_module_staking};
+use crate::logic::with_lock;
 use crate::state::OakDEX;
 use crate::token::{safe_transfer, safe_transfer_from};
 
@@ -71,16 +75,16 @@ fn _update_rewards(dex: &mut OakDEX, user: Address) -> OakResult<()> {
 pub struct StakingRewards;
 
 impl StakingRewards {
-    /// Initialize staking (owner only). LP token can be ERC-20 or ERC-1155 (use token_id 0 for ERC-20).
+    /// Initialize staking (ROLE_ADMIN only). LP token can be ERC-20 or ERC-1155 (use token_id 0 for ERC-20).
     pub fn init(
         dex: &mut OakDEX,
         reward_token: Address,
         staking_token: Address,
         reward_rate_per_block: U256,
     ) -> OakResult<()> {
-        if stylus_sdk::msg::sender() != dex.owner.get() {
-            return Err(err(crate::errors::ERR_ONLY_OWNER));
-        }
+        require_role(dex, ROLE_ADMIN)?;
+        let params = [reward_token.into_word(), staking_token.into_word(), word(reward_rate_per_block)];
+        AdminAuditLog::record(dex, AUDIT_ACTION_INIT_STAKING, &params)?;
         dex.staking_reward_token.set(reward_token);
         dex.staking_token.set(staking_token);
         dex.staking_reward_rate_per_block.set(reward_rate_per_block);
@@ -99,33 +103,29 @@ impl StakingRewards {
         if staking_token == Address::ZERO {
             return Err(err(ERR_STAKING_NOT_INIT));
         }
-        crate::logic::lock_reentrancy_guard(dex)?;
-        if let Err(e) = _update_rewards(dex, sender) {
-            crate::logic::unlock_reentrancy_guard(dex);
-            return Err(e);
-        }
-        let prev = dex.staking_user_balance.setter(sender).get();
-        let new_balance = prev.checked_add(amount).ok_or_else(|| err(ERR_OVERFLOW))?;
-        let total = dex.staking_total_staked.get();
-        let new_total = total.checked_add(amount).ok_or_else(|| err(ERR_OVERFLOW))?;
-        dex.staking_user_balance.setter(sender).set(new_balance);
-        dex.staking_total_staked.set(new_total);
-        let contract = stylus_sdk::contract::address();
-        if let Err(e) = safe_transfer_from(staking_token, sender, contract, amount) {
-            dex.staking_user_balance.setter(sender).set(prev);
-            dex.staking_total_staked.set(total);
-            crate::logic::unlock_reentrancy_guard(dex);
-            return Err(e);
-        }
-        emit_emission_event(
-            emission_module_staking(),
-            sender,
-            U256::from(STAKING_EVENT_STAKED),
-            amount,
-            U256::ZERO,
-        );
-        crate::logic::unlock_reentrancy_guard(dex);
-        Ok(())
+        with_lock(dex, |dex| {
+            _update_rewards(dex, sender)?;
+            let prev = dex.staking_user_balance.setter(sender).get();
+            let new_balance = prev.checked_add(amount).ok_or_else(|| err(ERR_OVERFLOW))?;
+            let total = dex.staking_total_staked.get();
+            let new_total = total.checked_add(amount).ok_or_else(|| err(ERR_OVERFLOW))?;
+            dex.staking_user_balance.setter(sender).set(new_balance);
+            dex.staking_total_staked.set(new_total);
+            let contract = stylus_sdk::contract::address();
+            if let Err(e) = safe_transfer_from(staking_token, sender, contract, amount) {
+                dex.staking_user_balance.setter(sender).set(prev);
+                dex.staking_total_staked.set(total);
+                return Err(e);
+            }
+            emit_emission_event(
+                emission_module_staking(),
+                sender,
+                U256::from(STAKING_EVENT_STAKED),
+                amount,
+                U256::ZERO,
+            );
+            Ok(())
+        })
     }
 
     /// Unstake LP tokens. Reentrancy: guard held; CEI: state then transfer.
@@ -134,66 +134,57 @@ impl StakingRewards {
             return Err(err(ERR_STAKING_ZERO_AMOUNT));
         }
         let sender = stylus_sdk::msg::sender();
-        crate::logic::lock_reentrancy_guard(dex)?;
-        if let Err(e) = _update_rewards(dex, sender) {
-            crate::logic::unlock_reentrancy_guard(dex);
-            return Err(e);
-        }
-        let balance = dex.staking_user_balance.setter(sender).get();
-        if balance < amount {
-            crate::logic::unlock_reentrancy_guard(dex);
-            return Err(err(crate::errors::ERR_INSUFFICIENT_BALANCE));
-        }
-        let new_balance = balance.checked_sub(amount).ok_or_else(|| err(ERR_OVERFLOW))?;
-        let total = dex.staking_total_staked.get();
-        let new_total = total.checked_sub(amount).ok_or_else(|| err(ERR_OVERFLOW))?;
-        dex.staking_user_balance.setter(sender).set(new_balance);
-        dex.staking_total_staked.set(new_total);
-        let staking_token = dex.staking_token.get();
-        if let Err(e) = safe_transfer(staking_token, sender, amount) {
-            dex.staking_user_balance.setter(sender).set(balance);
-            dex.staking_total_staked.set(total);
-            crate::logic::unlock_reentrancy_guard(dex);
-            return Err(e);
-        }
-        emit_emission_event(
-            emission_module_staking(),
-            sender,
-            U256::from(STAKING_EVENT_UNSTAKED),
-            amount,
-            U256::ZERO,
-        );
-        crate::logic::unlock_reentrancy_guard(dex);
-        Ok(())
-    }
-
-    /// Claim pending reward tokens. Reentrancy: guard held; CEI: state then transfer.
-    pub fn claim_rewards(dex: &mut OakDEX) -> OakResult<U256> {
-        let sender = stylus_sdk::msg::sender();
-        crate::logic::lock_reentrancy_guard(dex)?;
-        if let Err(e) = _update_rewards(dex, sender) {
-            crate::logic::unlock_reentrancy_guard(dex);
-            return Err(e);
-        }
-        let amount = dex.staking_user_rewards.setter(sender).get();
-        dex.staking_user_rewards.setter(sender).set(U256::ZERO);
-        if !amount.is_zero() {
-            let reward_token = dex.staking_reward_token.get();
-            if let Err(e) = safe_transfer(reward_token, sender, amount) {
-                dex.staking_user_rewards.setter(sender).set(amount);
-                crate::logic::unlock_reentrancy_guard(dex);
+        with_lock(dex, |dex| {
+            _update_rewards(dex, sender)?;
+            let balance = dex.staking_user_balance.setter(sender).get();
+            if balance < amount {
+                return Err(err(crate::errors::ERR_INSUFFICIENT_BALANCE));
+            }
+            let new_balance = balance.checked_sub(amount).ok_or_else(|| err(ERR_OVERFLOW))?;
+            let total = dex.staking_total_staked.get();
+            let new_total = total.checked_sub(amount).ok_or_else(|| err(ERR_OVERFLOW))?;
+            dex.staking_user_balance.setter(sender).set(new_balance);
+            dex.staking_total_staked.set(new_total);
+            let staking_token = dex.staking_token.get();
+            if let Err(e) = safe_transfer(staking_token, sender, amount) {
+                dex.staking_user_balance.setter(sender).set(balance);
+                dex.staking_total_staked.set(total);
                 return Err(e);
             }
             emit_emission_event(
                 emission_module_staking(),
                 sender,
-                U256::from(STAKING_EVENT_REWARD_CLAIMED),
+                U256::from(STAKING_EVENT_UNSTAKED),
                 amount,
                 U256::ZERO,
             );
-        }
-        crate::logic::unlock_reentrancy_guard(dex);
-        Ok(amount)
+            Ok(())
+        })
+    }
+
+    /// Claim pending reward tokens. Reentrancy: guard held; CEI: state then transfer.
+    pub fn claim_rewards(dex: &mut OakDEX) -> OakResult<U256> {
+        let sender = stylus_sdk::msg::sender();
+        with_lock(dex, |dex| {
+            _update_rewards(dex, sender)?;
+            let amount = dex.staking_user_rewards.setter(sender).get();
+            dex.staking_user_rewards.setter(sender).set(U256::ZERO);
+            if !amount.is_zero() {
+                let reward_token = dex.staking_reward_token.get();
+                if let Err(e) = safe_transfer(reward_token, sender, amount) {
+                    dex.staking_user_rewards.setter(sender).set(amount);
+                    return Err(e);
+                }
+                emit_emission_event(
+                    emission_module_staking(),
+                    sender,
+                    U256::from(STAKING_EVENT_REWARD_CLAIMED),
+                    amount,
+                    U256::ZERO,
+                );
+            }
+            Ok(amount)
+        })
     }
 
     /// View: pending rewards for user.
@@ -69,92 +69,64 @@ impl CopyTrading {
         leader_amount_in: U256,
         deadline: U256,
     ) -> OakResult<U256> {
-        crate::logic::lock_reentrancy_guard(dex)?;
-        let stored_leader = dex.copy_trading_leader.getter(follower).get();
-        if stored_leader == Address::ZERO {
-            crate::logic::unlock_reentrancy_guard(dex);
-            return Err(err(ERR_COPY_NOT_SUBSCRIBED));
-        }
-        if stored_leader != leader {
-            crate::logic::unlock_reentrancy_guard(dex);
-            return Err(err(ERR_COPY_LEADER_MISMATCH));
-        }
-        if U256::from(block::number()) > deadline {
-            crate::logic::unlock_reentrancy_guard(dex);
-            return Err(err(crate::errors::ERR_EXPIRED));
-        }
-        let amount_ratio_bps = dex.copy_trading_amount_ratio_bps.getter(follower).get();
-        let slippage_bps = dex.copy_trading_slippage_bps.getter(follower).get();
-        let amount_in = leader_amount_in
-            .checked_mul(amount_ratio_bps)
-            .ok_or_else(|| err(ERR_OVERFLOW))?
-            .checked_div(U256::from(BPS))
-            .ok_or_else(|| err(ERR_DIVISION_BY_ZERO))?;
-        if amount_in.is_zero() {
-            crate::logic::unlock_reentrancy_guard(dex);
-            return Err(err(crate::errors::ERR_INSUFFICIENT_INPUT_AMOUNT));
-        }
-        let (token0, token1) = if token_in < token_out {
-            (token_in, token_out)
-        } else {
-            (token_out, token_in)
-        };
-        let outer = dex.pools.getter(token0);
-        let pool = outer.getter(token1);
-        if !pool.initialized.get() {
-            crate::logic::unlock_reentrancy_guard(dex);
-            return Err(err(crate::errors::ERR_INVALID_TOKEN));
-        }
-        let reserve0 = pool.reserve0.get();
-        let reserve1 = pool.reserve1.get();
-        let (reserve_in, reserve_out) = if token_in == token0 {
-            (reserve0, reserve1)
-        } else {
-            (reserve1, reserve0)
-        };
-        let fee_bps = dex.protocol_fee_bps.get(); // single storage read for amount_out and process_swap
-        let expected_out = match crate::logic::get_amount_out_with_fee(amount_in, reserve_in, reserve_out, fee_bps) {
-            Ok(x) => x,
-            Err(e) => {
-                crate::logic::unlock_reentrancy_guard(dex);
-                return Err(e);
+        crate::logic::with_lock(dex, |dex| {
+            let stored_leader = dex.copy_trading_leader.getter(follower).get();
+            if stored_leader == Address::ZERO {
+                return Err(err(ERR_COPY_NOT_SUBSCRIBED));
             }
-        };
-        let slippage_deduction = match U256::from(BPS).checked_sub(slippage_bps) {
-            Some(x) => x,
-            None => {
-                crate::logic::unlock_reentrancy_guard(dex);
-                return Err(err(ERR_OVERFLOW));
+            if stored_leader != leader {
+                return Err(err(ERR_COPY_LEADER_MISMATCH));
             }
-        };
-        let min_out = match expected_out.checked_mul(slippage_deduction).and_then(|n| n.checked_div(U256::from(BPS))) {
-            Some(x) => x,
-            None => {
-                crate::logic::unlock_reentrancy_guard(dex);
-                return Err(err(ERR_OVERFLOW));
+            if U256::from(block::number()) > deadline {
+                return Err(err(crate::errors::ERR_EXPIRED));
             }
-        };
-        let result = crate::logic::process_swap_from_to_with_fee(
-            dex,
-            follower,
-            follower,
-            token_in,
-            token_out,
-            amount_in,
-            min_out,
-            fee_bps,
-        );
-        match result {
-            Ok(amount_out) => {
-                emit_copy_trade_executed(follower, leader, amount_in, amount_out);
-                crate::logic::unlock_reentrancy_guard(dex);
-                Ok(amount_out)
+            let amount_ratio_bps = dex.copy_trading_amount_ratio_bps.getter(follower).get();
+            let slippage_bps = dex.copy_trading_slippage_bps.getter(follower).get();
+            let amount_in = leader_amount_in
+                .checked_mul(amount_ratio_bps)
+                .ok_or_else(|| err(ERR_OVERFLOW))?
+                .checked_div(U256::from(BPS))
+                .ok_or_else(|| err(ERR_DIVISION_BY_ZERO))?;
+            if amount_in.is_zero() {
+                return Err(err(crate::errors::ERR_INSUFFICIENT_INPUT_AMOUNT));
             }
-            Err(e) => {
-                crate::logic::unlock_reentrancy_guard(dex);
-                Err(e)
+            let (token0, token1) = if token_in < token_out {
+                (token_in, token_out)
+            } else {
+                (token_out, token_in)
+            };
+            let outer = dex.pools.getter(token0);
+            let pool = outer.getter(token1);
+            if !pool.initialized.get() {
+                return Err(err(crate::errors::ERR_INVALID_TOKEN));
             }
-        }
+            let reserve0 = pool.reserve0.get();
+            let reserve1 = pool.reserve1.get();
+            let (reserve_in, reserve_out) = if token_in == token0 {
+                (reserve0, reserve1)
+            } else {
+                (reserve1, reserve0)
+            };
+            let fee_bps = crate::logic::pool_fee_bps(dex, token_in, token_out); // single read for amount_out and process_swap
+            let expected_out = crate::logic::get_amount_out_with_fee(amount_in, reserve_in, reserve_out, fee_bps)?;
+            let slippage_deduction = U256::from(BPS).checked_sub(slippage_bps).ok_or_else(|| err(ERR_OVERFLOW))?;
+            let min_out = expected_out
+                .checked_mul(slippage_deduction)
+                .and_then(|n| n.checked_div(U256::from(BPS)))
+                .ok_or_else(|| err(ERR_OVERFLOW))?;
+            let amount_out = crate::logic::process_swap_from_to_with_fee(
+                dex,
+                follower,
+                follower,
+                token_in,
+                token_out,
+                amount_in,
+                min_out,
+                fee_bps,
+            )?;
+            emit_copy_trade_executed(follower, leader, amount_in, amount_out);
+            Ok(amount_out)
+        })
     }
 
     /// View: get subscription for follower (leader, slippage_bps, amount_ratio_bps). 0 leader = none.
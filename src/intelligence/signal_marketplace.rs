@@ -79,24 +79,19 @@ impl SignalMarketplace {
         if recovered != seller {
             return Err(err(ERR_SIGNAL_INVALID_SIGNATURE));
         }
-        crate::logic::lock_reentrancy_guard(dex)?;
-        // CEI: update state before external call (transfer); rollback on transfer failure.
-        let new_nonce = current_nonce.checked_add(U256::from(1u64)).ok_or_else(|| err(crate::errors::ERR_OVERFLOW))?;
-        dex.signal_purchased.setter(buyer).setter(listing_hash).set(true);
-        dex.signal_nonce.setter(seller).set(new_nonce);
-        match safe_transfer_from(protocol_token, buyer, seller, price) {
-            Ok(()) => {
-                emit_signal_purchased(buyer, seller, U256::from_be_bytes::<32>(listing_hash.into()), price);
-                crate::logic::unlock_reentrancy_guard(dex);
-                Ok(())
-            }
-            Err(e) => {
+        crate::logic::with_lock(dex, |dex| {
+            // CEI: update state before external call (transfer); rollback on transfer failure.
+            let new_nonce = current_nonce.checked_add(U256::from(1u64)).ok_or_else(|| err(crate::errors::ERR_OVERFLOW))?;
+            dex.signal_purchased.setter(buyer).setter(listing_hash).set(true);
+            dex.signal_nonce.setter(seller).set(new_nonce);
+            if let Err(e) = safe_transfer_from(protocol_token, buyer, seller, price) {
                 dex.signal_purchased.setter(buyer).setter(listing_hash).set(false);
                 dex.signal_nonce.setter(seller).set(current_nonce);
-                crate::logic::unlock_reentrancy_guard(dex);
-                Err(e)
+                return Err(e);
             }
-        }
+            emit_signal_purchased(buyer, seller, U256::from_be_bytes::<32>(listing_hash.into()), price);
+            Ok(())
+        })
     }
 
     /// View: listed price for (seller, signal_id_hash). 0 = not listed.
@@ -0,0 +1,101 @@
+//! Trade journal: opt-in, append-only on-chain record of a user's reveal executions.
+//!
+//! Institutional users need an audit trail of their fills that does not depend on trusting an
+//! indexer. With the journal enabled, each reveal appends the executed pair, amounts, fee rate,
+//! block and timestamp under the user's address. Entries are never modified or deleted, so a
+//! reader (or another contract) can reproduce the user's history from storage alone.
+
+use stylus_sdk::{alloy_primitives::{Address, U256}, block};
+
+use alloc::vec::Vec;
+
+use crate::constants::{as_u256, MAX_PAGE_SIZE};
+use crate::errors::{err, OakResult, ERR_OVERFLOW, ERR_TOO_MANY_ITEMS, ERR_TRADE_RECORD_NOT_FOUND};
+use crate::state::OakDEX;
+
+/// (token_in, token_out, amount_in, amount_out, fee_bps, block_number, timestamp).
+pub type TradeRecordView = (Address, Address, U256, U256, U256, U256, U256);
+
+/// Per-user trade journal (uses OakDEX trade_journal* storage).
+pub struct TradeJournal;
+
+impl TradeJournal {
+    /// Enable or disable journaling for msg.sender. Existing entries are kept either way.
+    pub fn set_enabled(dex: &mut OakDEX, enabled: bool) {
+        let user = stylus_sdk::msg::sender();
+        dex.trade_journal_enabled.setter(user).set(enabled);
+    }
+
+    /// True if `user` journals their reveals.
+    pub fn is_enabled(dex: &OakDEX, user: Address) -> bool {
+        dex.trade_journal_enabled.getter(user).get()
+    }
+
+    /// Append an executed reveal to `user`'s journal if enabled. No-op otherwise.
+    pub fn record(
+        dex: &mut OakDEX,
+        user: Address,
+        token_in: Address,
+        token_out: Address,
+        amount_in: U256,
+        amount_out: U256,
+    ) -> OakResult<()> {
+        if !Self::is_enabled(dex, user) {
+            return Ok(());
+        }
+        let fee_bps = crate::logic::pool_fee_bps(dex, token_in, token_out);
+        let index = dex.trade_journal_len.getter(user).get();
+        let next = index.checked_add(U256::from(1u64)).ok_or_else(|| err(ERR_OVERFLOW))?;
+        let mut journal = dex.trade_journal.setter(user);
+        let mut entry = journal.setter(index);
+        entry.token_in.set(token_in);
+        entry.token_out.set(token_out);
+        entry.amount_in.set(amount_in);
+        entry.amount_out.set(amount_out);
+        entry.fee_bps.set(fee_bps);
+        entry.block_number.set(U256::from(block::number()));
+        entry.timestamp.set(U256::from(block::timestamp()));
+        dex.trade_journal_len.setter(user).set(next);
+        Ok(())
+    }
+
+    /// Number of entries in `user`'s journal.
+    pub fn len(dex: &OakDEX, user: Address) -> U256 {
+        dex.trade_journal_len.getter(user).get()
+    }
+
+    /// Up to `limit` entries of `user`'s journal starting at `offset` (oldest first).
+    ///
+    /// @dev `limit` at most MAX_PAGE_SIZE; an offset past the end returns an empty page.
+    pub fn page(dex: &OakDEX, user: Address, offset: U256, limit: U256) -> OakResult<Vec<TradeRecordView>> {
+        if limit > as_u256(MAX_PAGE_SIZE) {
+            return Err(err(ERR_TOO_MANY_ITEMS));
+        }
+        let end = Self::len(dex, user).min(offset.saturating_add(limit));
+        let mut entries = Vec::new();
+        let mut index = offset;
+        while index < end {
+            entries.push(Self::entry(dex, user, index)?);
+            index += U256::from(1u64);
+        }
+        Ok(entries)
+    }
+
+    /// Entry `index` of `user`'s journal.
+    pub fn entry(dex: &OakDEX, user: Address, index: U256) -> OakResult<TradeRecordView> {
+        if index >= Self::len(dex, user) {
+            return Err(err(ERR_TRADE_RECORD_NOT_FOUND));
+        }
+        let journal = dex.trade_journal.getter(user);
+        let entry = journal.getter(index);
+        Ok((
+            entry.token_in.get(),
+            entry.token_out.get(),
+            entry.amount_in.get(),
+            entry.amount_out.get(),
+            entry.fee_bps.get(),
+            entry.block_number.get(),
+            entry.timestamp.get(),
+        ))
+    }
+}
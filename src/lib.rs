@@ -23,19 +23,62 @@ pub extern "C" fn native_keccak256(bytes: *const u8, len: usize, output: *mut u8
     unsafe { core::ptr::copy_nonoverlapping(out.as_ptr(), output, 32) };
 }
 
-use alloc::vec::Vec;
-use stylus_sdk::prelude::*;
-
 /// Access Control by roles (DEFAULT_ADMIN_ROLE, PAUSER_ROLE, UPGRADER_ROLE).
 pub mod access;
+/// Token aliases: canonical vs bridged representations of one asset.
+pub mod aliases;
+pub mod auction;
+/// Append-only on-chain log of privileged (owner / role) actions.
+pub mod audit;
 /// Protocol-wide constants (fees, limits, timing).
 pub mod constants;
+pub mod creator;
 /// Shared error types and helpers.
 pub mod errors;
+/// Output escrow: per-user claimable balances (pull-based claims).
+pub mod escrow;
 /// Solidity-compatible event definitions.
 pub mod events;
+/// Volatility-based dynamic fee controller.
+pub mod fees;
 /// Persistent storage layout for the DEX.
 pub mod state;
+/// LP deposit receipts: cost basis, fees earned and impermanent-loss estimate.
+pub mod receipts;
+/// Opt-in per-user trade journal of reveal executions (on-chain audit trail).
+pub mod journal;
+/// Claimable LP fees: per-pool fee-per-share accumulators.
+pub mod lp_fees;
+/// Gas rebates for commit-reveal revealers.
+pub mod gas_rebate;
+/// Treasury split table: weighted fan-out of treasury withdrawals.
+pub mod treasury_splits;
+/// Pool retirement: successor pools and commitment re-anchoring.
+pub mod pool_migration;
+/// Commit sponsorship vouchers: sponsors prepay commit bonds for their users.
+pub mod sponsorship;
+/// Time locks on LP shares with harvestable fee growth.
+pub mod locks;
+/// ERC-1155 position tokens over LP shares, by pool and lock tier.
+pub mod positions;
+/// Enumerable pair registry keyed by keccak(token0, token1).
+pub mod pairs;
+/// Per-token solvency reconciliation of balances against reserves and accrued buckets.
+pub mod solvency;
+/// Per-pool epoch statistics (volume / fees) with governance snapshot-and-reset.
+pub mod stats;
+/// Per-pool TWAP accumulators and cross-pool price composition.
+pub mod oracle;
+/// Rounding directions for value-moving divisions (flippable under `rounding-audit`).
+pub mod rounding;
+/// Governance token risk registry: exposure caps, flash borrowing, oracle and hook policy.
+pub mod risk;
+/// Deterministic replay of pool state transitions against the pure math (incident analysis).
+pub mod replay;
+/// Declared notional of pending commitments, aggregated per pool and direction.
+pub mod commit_exposure;
+/// Per-block, per-direction limit on reserve outflow (drain slowdown).
+pub mod flow_limit;
 /// Pausable trait and implementation for OakDEX.
 pub mod pausable;
 /// TimelockController: queue -> delay -> execute.
@@ -43,6 +86,7 @@ pub mod timelock;
 /// Core Engine: swap core, execution strategy (Atomic / Commit-Reveal), order execution.
 pub mod engine;
 /// Growth Engine: StakingRewards, Referral, Quest (EmissionEvent for indexer).
+#[cfg(feature = "rewards")]
 pub mod growth;
 /// Intelligence Layer: Copy Trading, Signal Marketplace (EIP-712).
 pub mod intelligence;
@@ -54,15 +98,3 @@ pub mod token;
 pub mod vault;
 /// Randomness utilities for Oak Bet (casino).
 pub mod rng;
-
-/// Entry point for the Oak Protocol contract.
-///
-/// @notice Main entry point invoked by the Stylus runtime.
-/// @dev In a full deployment, this function would dispatch calls
-///      generated by `cargo stylus` based on the ABI. For now it
-///      simply returns an empty byte array to keep the contract
-///      WASM-valid while we focus on core logic and architecture.
-#[entrypoint]
-pub fn main(_input: Vec<u8>) -> Result<Vec<u8>, Vec<u8>> {
-    Ok(Vec::new())
-}
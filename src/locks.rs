@@ -0,0 +1,134 @@
+//! Liquidity locks: verifiable time locks on LP shares (launch rug-check primitive).
+//!
+//! A provider locks part of their LP balance in a pool until a chosen block. Until then
+//! `remove_liquidity` refuses to burn the locked shares. The lock is readable on-chain and
+//! announced with a `LiquidityLocked` event.
+//!
+//! Locked shares keep earning claimable swap fees (`claim_lp_fees`, see `lp_fees`). Whatever
+//! compounds into the pool (fee rounding remainders, flash fees, synced donations) grows the
+//! liquidity index `sqrt(reserve0 * reserve1) / lp_supply` (see `receipts`) and stays
+//! harvestable while the principal is locked: `harvest_locked_fees` burns the fraction
+//! `1 - index_at_lock / index_now` of the locked shares, which is that growth. The remaining locked shares still carry the
+//! value that was locked. The lock can be topped up or extended but never shortened.
+
+use stylus_sdk::{alloy_primitives::{Address, U256}, block};
+
+use crate::errors::{err, MathResult, OakResult, ERR_DIVISION_BY_ZERO, ERR_INVALID_LOCK, ERR_LIQUIDITY_LOCKED, ERR_OVERFLOW};
+use crate::events::emit_liquidity_locked;
+use crate::receipts::liquidity_index;
+use crate::state::OakDEX;
+
+/// Locked shares whose value is fee growth since the lock: `locked * (index_now - index_lock) / index_now`.
+pub fn locked_fee_shares(locked: U256, index_at_lock: U256, index_now: U256) -> MathResult<U256> {
+    if index_now <= index_at_lock {
+        return Ok(U256::ZERO);
+    }
+    locked
+        .checked_mul(index_now - index_at_lock)
+        .ok_or(ERR_OVERFLOW)?
+        .checked_div(index_now)
+        .ok_or(ERR_DIVISION_BY_ZERO)
+}
+
+/// LP share locks (uses PoolData lp_locks storage). Token pair must be canonical.
+pub struct LiquidityLocks;
+
+impl LiquidityLocks {
+    /// Current liquidity index of (token0, token1).
+    fn index_now(dex: &OakDEX, token0: Address, token1: Address) -> OakResult<U256> {
+        let outer = dex.pools.getter(token0);
+        let pool = outer.getter(token1);
+        liquidity_index(pool.reserve0.get(), pool.reserve1.get(), pool.lp_total_supply.get()).map_err(err)
+    }
+
+    /// Lock `amount` more LP shares of `owner` until `unlock_block`.
+    ///
+    /// @dev Topping up re-anchors the fee index, so harvest before adding to an existing lock.
+    ///      An expired lock is replaced rather than extended.
+    pub fn lock(
+        dex: &mut OakDEX,
+        owner: Address,
+        token0: Address,
+        token1: Address,
+        amount: U256,
+        unlock_block: U256,
+    ) -> OakResult<()> {
+        let now = U256::from(block::number());
+        let index = Self::index_now(dex, token0, token1)?;
+        let mut outer = dex.pools.setter(token0);
+        let mut pool = outer.setter(token1);
+        // Shares wrapped as position tokens are already committed elsewhere.
+        let balance = pool.lp_balances.getter(owner).get().saturating_sub(pool.lp_wrapped.get(owner));
+        let mut lock = pool.lp_locks.setter(owner);
+        let active = lock.unlock_block.get() > now;
+        let (locked, current_unlock) = if active {
+            (lock.amount.get(), lock.unlock_block.get())
+        } else {
+            (U256::ZERO, U256::ZERO)
+        };
+        let new_locked = locked.checked_add(amount).ok_or_else(|| err(ERR_OVERFLOW))?;
+        if unlock_block <= now || unlock_block < current_unlock || new_locked.is_zero() || new_locked > balance {
+            return Err(err(ERR_INVALID_LOCK));
+        }
+        lock.amount.set(new_locked);
+        lock.unlock_block.set(unlock_block);
+        lock.index.set(index);
+        emit_liquidity_locked(owner, token0, token1, new_locked, unlock_block);
+        Ok(())
+    }
+
+    /// Revert if burning `liquidity` of `owner` would dip into shares still locked or wrapped.
+    pub fn require_unlocked(
+        dex: &OakDEX,
+        owner: Address,
+        token0: Address,
+        token1: Address,
+        liquidity: U256,
+    ) -> OakResult<()> {
+        let outer = dex.pools.getter(token0);
+        let pool = outer.getter(token1);
+        let lock = pool.lp_locks.getter(owner);
+        let locked = if lock.unlock_block.get() > U256::from(block::number()) { lock.amount.get() } else { U256::ZERO };
+        // Shares wrapped as ERC-1155 position tokens (see `positions`) are never burnable here.
+        let free = pool
+            .lp_balances
+            .getter(owner)
+            .get()
+            .saturating_sub(locked)
+            .saturating_sub(pool.lp_wrapped.get(owner));
+        if liquidity > free {
+            return Err(err(ERR_LIQUIDITY_LOCKED));
+        }
+        Ok(())
+    }
+
+    /// Release the fee-growth part of `owner`'s active lock and re-anchor it at the current index.
+    ///
+    /// @return LP shares freed for burning (0 if no fees accrued or no active lock).
+    pub fn take_fee_shares(dex: &mut OakDEX, owner: Address, token0: Address, token1: Address) -> OakResult<U256> {
+        let index = Self::index_now(dex, token0, token1)?;
+        let mut outer = dex.pools.setter(token0);
+        let mut pool = outer.setter(token1);
+        let mut lock = pool.lp_locks.setter(owner);
+        if lock.unlock_block.get() <= U256::from(block::number()) {
+            return Ok(U256::ZERO);
+        }
+        let locked = lock.amount.get();
+        let fee_shares = locked_fee_shares(locked, lock.index.get(), index).map_err(err)?;
+        lock.amount.set(locked - fee_shares);
+        lock.index.set(index);
+        Ok(fee_shares)
+    }
+
+    /// View: (locked shares, unlock_block) of `owner`; (0, 0) once the lock has expired.
+    pub fn get(dex: &OakDEX, owner: Address, token0: Address, token1: Address) -> (U256, U256) {
+        let outer = dex.pools.getter(token0);
+        let pool = outer.getter(token1);
+        let lock = pool.lp_locks.getter(owner);
+        let unlock_block = lock.unlock_block.get();
+        if unlock_block <= U256::from(block::number()) {
+            return (U256::ZERO, U256::ZERO);
+        }
+        (lock.amount.get(), unlock_block)
+    }
+}
@@ -3,7 +3,8 @@
 use alloc::vec::Vec;
 
 use stylus_sdk::{
-    alloy_primitives::{Address, FixedBytes, U256},
+    abi::Bytes,
+    alloy_primitives::{Address, FixedBytes, U256, U512},
     block,
     call::{self, Call},
     contract,
@@ -13,52 +14,352 @@ use stylus_sdk::{
 };
 
 use crate::{
-    access::{default_admin_role, pauser_role},
+    access::{self, holds_any, require_role, roles_of, ROLE_ADMIN, ROLE_FEE_SETTER, ROLE_PAUSER},
+    aliases::TokenAliases,
+    auction::BatchAuction,
+    audit::{bool_word, word, AdminAuditLog, AuditRecordView},
     constants::{
-        as_u256, q112_u256, BPS, BATCH_FEE_REBATE_BPS, CIRCUIT_BREAKER_IMPACT_BPS, COMMIT_REVEAL_DELAY,
-        DEFAULT_FEE_BPS, FEE_DENOMINATOR, GAS_REBATE_BPS, INITIAL_FEE, LP_FEE_PCT, MAX_BATCH_POSITIONS,
-        MAX_COMMITMENT_AGE, MAX_FEE_BPS, MAX_PATH_LENGTH, MAX_TRADE_RESERVE_BPS, MINIMUM_LIQUIDITY,
-        OWNER_TRANSFER_DELAY_BLOCKS, TREASURY_FEE_BPS, BUYBACK_FEE_PCT, TREASURY_FEE_PCT,
+        as_u256, q112_u256, AUDIT_ACTION_SET_FEE, AUDIT_ACTION_WITHDRAW_TREASURY_FEES,
+        AUDIT_ACTION_TRIGGER_CIRCUIT_BREAKER, AUDIT_ACTION_CLEAR_CIRCUIT_BREAKER, AUDIT_ACTION_SET_PENDING_OWNER,
+        AUDIT_ACTION_ACCEPT_OWNER, AUDIT_ACTION_PROPOSE_TREASURY, AUDIT_ACTION_EXECUTE_TREASURY_CHANGE,
+        AUDIT_ACTION_CANCEL_TREASURY_CHANGE, AUDIT_ACTION_PROPOSE_TREASURY_DESTINATION,
+        AUDIT_ACTION_EXECUTE_TREASURY_DESTINATION, AUDIT_ACTION_SET_TREASURY_CHANGE_DELAY,
+        AUDIT_ACTION_SET_BUYBACK_WALLET, AUDIT_ACTION_FORCE_UNLOCK, AUDIT_ACTION_SWEEP_STALE_CLAIMABLE,
+        AUDIT_ACTION_RESET_POOL_STATS, AUDIT_ACTION_SET_POOL_PAUSED, AUDIT_ACTION_SET_POOL_HOOKED,
+        AUDIT_ACTION_SET_POOL_MANAGER, AUDIT_ACTION_SET_BOOTSTRAP_FEED, AUDIT_ACTION_SET_CREATOR_FEE_SHARE,
+        AUDIT_ACTION_SET_BATCH_WINDOW, AUDIT_ACTION_SET_COMMIT_BOND, AUDIT_ACTION_SET_ESCROW_SWEEP_PERIOD,
+        AUDIT_ACTION_SET_ORACLE_STALENESS_POLICY, AUDIT_ACTION_SET_COMMIT_REVEAL_PARAMS,
+        AUDIT_ACTION_SET_COMMIT_PRICE_BAND, AUDIT_ACTION_RESET_PRICE_REFERENCE, AUDIT_ACTION_SET_FEE_TIER,
+        AUDIT_ACTION_SET_VOLATILITY_FEE_CONFIG, AUDIT_ACTION_SET_TOKEN_ALIAS, AUDIT_ACTION_REMOVE_TOKEN_ALIAS,
+        AUDIT_ACTION_SET_POOL_COMMIT_SCHEMES, AUDIT_ACTION_PROPOSE_TREASURY_SPLITS, AUDIT_ACTION_EXECUTE_TREASURY_SPLITS,
+        AUDIT_ACTION_SET_FLASH_LOAN_POOL, AUDIT_ACTION_SET_FLASH_FEE, AUDIT_ACTION_RETIRE_POOL, AUDIT_ACTION_SET_TOKEN_RISK, AUDIT_ACTION_SET_MAX_PRICE_IMPACT, AUDIT_ACTION_SET_AUTO_CIRCUIT_BREAKER, AUDIT_ACTION_RESET_AUTO_CIRCUIT_BREAKER, AUDIT_ACTION_SET_LP_SURPLUS_POLICY, AUDIT_ACTION_QUEUE_LP_SURPLUS_SWEEP, AUDIT_ACTION_EXECUTE_LP_SURPLUS_SWEEP, AUDIT_ACTION_CANCEL_LP_SURPLUS_SWEEP, AUDIT_ACTION_SET_MAX_OUTFLOW, AUDIT_ACTION_SKIM, AUDIT_ACTION_SYNC, MAX_FLASH_FEE_BPS, FLASH_CALLBACK_OAK, FLASH_CALLBACK_STYLE_COUNT, FLASH_CALLBACK_UNISWAP_V2,
+        FLASH_CALLBACK_UNISWAP_V3,
+        BPS, BATCH_FEE_REBATE_BPS, CIRCUIT_BREAKER_IMPACT_BPS, COMMIT_REVEAL_DELAY,
+        COMMIT_SCHEME_COUNT, COMMIT_SCHEME_EIP712, MEV_TIER_BATCH, MEV_TIER_COUNT, MEV_TIER_FAST, MEV_TIER_FAST_DELAY, MEV_TIER_FAST_FEE_BPS, MEV_TIER_STANDARD, COMMIT_SCHEME_HASH, COMMIT_LIVE_CANCEL_PENALTY_BPS, COMMIT_STATUS_EXPIRED, COMMIT_STATUS_NONE, COMMIT_STATUS_READY, COMMIT_STATUS_TOO_EARLY,
+        REVEAL_GAS_BASE, REVEAL_GAS_ESCROW_CREDIT, REVEAL_GAS_SWAP, REVEAL_GAS_TOKEN_TRANSFER, SSTORE_CLEAR_REFUND,
+        DEFAULT_FEE_BPS, FEE_DENOMINATOR, FEE_TIER_STABLE_BPS, FEE_TIER_STANDARD_BPS, FEE_TIER_VOLATILE_BPS, GAS_REBATE_BPS, INITIAL_FEE, K_RATIO_SCALE, LP_FEE_PCT, MAX_BATCH_CANCEL, MAX_BATCH_CROSS_TWAP_DEVIATION_BPS, MAX_BATCH_POSITIONS,
+        MAX_AGGREGATE_CALLS, MAX_POSITION_BATCH, MAX_COMMITMENT_AGE, MAX_COMMITMENT_AGE_LIMIT, MAX_COMMIT_REVEAL_DELAY, MIN_COMMITMENT_AGE,
+        MIN_COMMIT_REVEAL_DELAY, MAX_FEE_BPS, MAX_PATH_LENGTH, MAX_TRADE_RESERVE_BPS, MINIMUM_LIQUIDITY,
+        MIN_TREASURY_CHANGE_DELAY_BLOCKS, OWNER_TRANSFER_DELAY_BLOCKS, TREASURY_CHANGE_DELAY_BLOCKS, TREASURY_FEE_BPS, BUYBACK_FEE_PCT, TREASURY_FEE_PCT,
     },
     errors::*,
+    engine::{
+        emergency::{is_reference_stale, oracle_heartbeat},
+        verification::{compute_typed_commitment, scheme_enabled},
+        price_divergence_bps, require_breaker_tripped, verify_commitment, HealthMonitor, RevealClaim, SelfTest,
+    },
+    creator::PoolCreatorShare,
+    escrow::OutputEscrow,
+    journal::{TradeJournal, TradeRecordView},
+    locks::LiquidityLocks,
+    gas_rebate::GasRebate,
+    treasury_splits::{split_amounts, TreasurySplits},
+    lp_fees::{fee_per_share_growth, LpFees},
+    pairs::{compute_pair_key, PairRegistry},
+    pool_migration::PoolMigration,
+    risk::TokenRisk,
+    positions::{tier_unlock_block, PositionTokens},
+    rounding::{self, Rounding},
+    sponsorship::CommitSponsorship,
+    commit_exposure::{check_declared, CommitExposure},
+    flow_limit::{FlowLimiter, PoolOutflowView},
+    solvency::{synced_reserve, track_order_escrow, track_reserve, FeeReconciliation},
+    fees::FeeController,
+    oracle::{spot_prices, PathTwap, PoolOracle},
     events::{
-        emit_add_liquidity, emit_buyback_wallet_set, emit_cancel_commitment, emit_circuit_breaker_cleared,
-        emit_circuit_breaker_triggered, emit_close_position, emit_commit_swap, emit_flash_swap,
-        emit_lp_transfer,         emit_open_position, emit_order_cancelled, emit_order_executed,
+        emit_add_liquidity, emit_claimable_swap, emit_remove_liquidity, emit_roles_granted, emit_roles_revoked, emit_buyback_wallet_set, emit_cancel_commitment, emit_auto_circuit_breaker_set, emit_circuit_breaker_cleared, emit_circuit_breaker_reset,
+        emit_circuit_breaker_triggered, emit_close_position, emit_commit_bond_set, emit_commit_bond_slashed, emit_commit_mev_tier, emit_commitment_cancelled_with_penalty, emit_commit_operator_set, emit_commit_sponsorship_deposited, emit_commit_sponsorship_withdrawn, emit_private_swap_revealed, emit_skim, emit_sync, emit_tokens_bound,
+        emit_fee_tier_set, emit_pool_fee_tier_set, emit_volatility_fee_config_set, emit_pool_commit_schemes_set,
+        emit_commit_price_band_set, emit_set_commit_reveal_params, emit_oracle_staleness_policy_set, emit_commit_swap, emit_commitment_expired,
+        emit_erc1155_transfer_batch, emit_flash_callback_set, emit_flash_fee_set, emit_flash_loan, emit_max_price_impact_set, emit_lp_surplus_policy_set, emit_max_outflow_set, emit_flash_loan_pool_set, emit_flash_swap, emit_force_unlocked, emit_liquidity_bundle_revealed,
+        emit_lp_transfer,         emit_open_position, emit_order_cancelled, emit_orders_cancelled, emit_order_executed,
         emit_order_placed, emit_owner_changed, emit_pause_changed, emit_pending_owner_set,
-        emit_batch_positions_executed, emit_pool_created, emit_reveal_swap, emit_set_fee,
+        emit_treasury_change_cancelled, emit_treasury_change_delay_set, emit_treasury_change_proposed,
+        emit_treasury_changed, emit_treasury_destination_proposed, emit_treasury_destination_set,
+        emit_batch_crossed, emit_batch_positions_executed, emit_pool_config_changed, emit_pool_created,
+        emit_pool_manager_set, emit_reveal_swap, emit_set_fee,
         emit_set_position_tp_sl, emit_set_position_trailing, emit_trailing_stop_triggered,
         emit_withdraw_treasury_fees,
     },
     pausable::Pausable,
+    receipts::{LpReceiptView, LpReceipts},
     state::OakDEX,
-    token::{balance_of, safe_transfer, safe_transfer_from},
+    stats::PoolStats,
+    token::{
+        balance_of, permit, safe_transfer, safe_transfer_from, safe_transfer_from_received, take_native_value, NATIVE_TOKEN,
+    },
 };
 
-/// Encode `(amount_in, salt)` similarly to `abi.encode`.
-fn encode_commit_data(amount_in: U256, salt: U256) -> Vec<u8> {
-    let mut encoded = Vec::with_capacity(64);
+/// `get_pool_info` result, in caller token order:
+/// (initialized, reserve_a, reserve_b, lp_total_supply, fee_bps, paused, hooked, manager).
+/// `paused` is true when either the protocol or the pool itself is paused.
+pub type PoolInfo = (bool, U256, U256, U256, U256, bool, bool, Address);
+
+/// Encode `(amount_in, zero_for_one, deadline, salt)` similarly to `abi.encode`.
+fn encode_commit_data(amount_in: U256, zero_for_one: bool, deadline: U256, salt: U256) -> Vec<u8> {
+    let mut encoded = Vec::with_capacity(128);
     encoded.extend_from_slice(&amount_in.to_be_bytes::<32>());
+    encoded.extend_from_slice(&U256::from(zero_for_one as u8).to_be_bytes::<32>());
+    encoded.extend_from_slice(&deadline.to_be_bytes::<32>());
     encoded.extend_from_slice(&salt.to_be_bytes::<32>());
     encoded
 }
 
-/// Compute commitment hash as `keccak256(abi.encode(amount_in, salt))`.
+/// Compute commitment hash as `keccak256(abi.encode(amount_in, zero_for_one, deadline, salt))`.
 /// Public for test and SDK use.
-pub fn compute_commit_hash(amount_in: U256, salt: U256) -> FixedBytes<32> {
-    let encoded = encode_commit_data(amount_in, salt);
+///
+/// @dev The direction is bound to the commitment so a reveal cannot flip the trade side.
+///      `deadline` is the user's last acceptable reveal block; binding it means nobody
+///      (including the user's own relayer) can stretch it at reveal time.
+pub fn compute_commit_hash(amount_in: U256, zero_for_one: bool, deadline: U256, salt: U256) -> FixedBytes<32> {
+    let encoded = encode_commit_data(amount_in, zero_for_one, deadline, salt);
     crypto::keccak(&encoded)
 }
 
-/// Verify that `sender` is the contract owner.
-fn only_owner(owner: Address) -> OakResult<()> {
-    let sender = msg::sender();
-    if sender != owner {
-        return Err(err(ERR_ONLY_OWNER));
+/// Domain tag prefixed to private-swap preimages: `keccak256("OAK_PRIVATE_SWAP")`.
+fn private_commit_tag() -> FixedBytes<32> {
+    crypto::keccak(b"OAK_PRIVATE_SWAP")
+}
+
+/// Inner layer of a private commitment: `keccak256(abi.encode(app_id, order_tag, inner_salt))`.
+///
+/// @notice Binds per-user strategy fields that are never disclosed on-chain. `inner_salt` keeps
+///         low-entropy fields (app ids, tags) from being recovered by brute force.
+pub fn compute_private_fields_hash(app_id: U256, order_tag: U256, inner_salt: U256) -> FixedBytes<32> {
+    let mut encoded = Vec::with_capacity(96);
+    encoded.extend_from_slice(&app_id.to_be_bytes::<32>());
+    encoded.extend_from_slice(&order_tag.to_be_bytes::<32>());
+    encoded.extend_from_slice(&inner_salt.to_be_bytes::<32>());
+    crypto::keccak(&encoded)
+}
+
+/// Outer layer of a private commitment:
+/// `keccak256(abi.encode(keccak256("OAK_PRIVATE_SWAP"), amount_in, zero_for_one, deadline, salt, private_hash))`.
+///
+/// @notice The reveal discloses only the execution fields and the opaque `private_hash`
+///         (see `compute_private_fields_hash`); the domain tag keeps it apart from plain swaps.
+pub fn compute_private_commit_hash(
+    amount_in: U256,
+    zero_for_one: bool,
+    deadline: U256,
+    salt: U256,
+    private_hash: FixedBytes<32>,
+) -> FixedBytes<32> {
+    let mut encoded = Vec::with_capacity(192);
+    encoded.extend_from_slice(private_commit_tag().as_slice());
+    encoded.extend_from_slice(&encode_commit_data(amount_in, zero_for_one, deadline, salt));
+    encoded.extend_from_slice(private_hash.as_slice());
+    crypto::keccak(&encoded)
+}
+
+/// Domain tag prefixed to bundle preimages: `keccak256("OAK_LP_BUNDLE")`.
+fn bundle_commit_tag() -> FixedBytes<32> {
+    crypto::keccak(b"OAK_LP_BUNDLE")
+}
+
+/// Compute swap+LP bundle commitment hash as
+/// `keccak256(abi.encode(keccak256("OAK_LP_BUNDLE"), amount_in, swap_amount, salt))`.
+///
+/// @notice `swap_amount` of `amount_in` is swapped at reveal; the rest plus the proceeds are
+///         deposited as liquidity. The leading domain tag separates this preimage from
+///         `compute_commit_hash`, so a plain swap commitment can never be revealed as a
+///         bundle (or vice versa).
+pub fn compute_bundle_commit_hash(amount_in: U256, swap_amount: U256, salt: U256) -> FixedBytes<32> {
+    let mut encoded = Vec::with_capacity(128);
+    encoded.extend_from_slice(bundle_commit_tag().as_slice());
+    encoded.extend_from_slice(&amount_in.to_be_bytes::<32>());
+    encoded.extend_from_slice(&swap_amount.to_be_bytes::<32>());
+    encoded.extend_from_slice(&salt.to_be_bytes::<32>());
+    crypto::keccak(&encoded)
+}
+
+/// Part of a bundle's `amount_in` deposited as liquidity after `swap_amount` is swapped.
+///
+/// @dev Reverts with BUNDLE_SPLIT_INVALID unless both legs get a non-zero amount.
+pub fn bundle_deposit_amount(amount_in: U256, swap_amount: U256) -> OakResult<U256> {
+    if swap_amount.is_zero() || swap_amount >= amount_in {
+        return Err(err(ERR_BUNDLE_SPLIT_INVALID));
+    }
+    Ok(amount_in - swap_amount)
+}
+
+/// Domain tag prefixed to exact-output preimages: `keccak256("OAK_EXACT_OUT")`.
+fn exact_out_commit_tag() -> FixedBytes<32> {
+    crypto::keccak(b"OAK_EXACT_OUT")
+}
+
+/// Compute exact-output commitment hash as
+/// `keccak256(abi.encode(keccak256("OAK_EXACT_OUT"), amount_out, max_amount_in, zero_for_one, salt))`.
+///
+/// @notice The domain tag keeps exact-output commitments from being revealed as exact-input
+///         swaps (or bundles) and vice versa.
+pub fn compute_exact_out_commit_hash(
+    amount_out: U256,
+    max_amount_in: U256,
+    zero_for_one: bool,
+    salt: U256,
+) -> FixedBytes<32> {
+    let mut encoded = Vec::with_capacity(160);
+    encoded.extend_from_slice(exact_out_commit_tag().as_slice());
+    encoded.extend_from_slice(&amount_out.to_be_bytes::<32>());
+    encoded.extend_from_slice(&max_amount_in.to_be_bytes::<32>());
+    encoded.extend_from_slice(&U256::from(zero_for_one as u8).to_be_bytes::<32>());
+    encoded.extend_from_slice(&salt.to_be_bytes::<32>());
+    crypto::keccak(&encoded)
+}
+
+/// Deterministic pool id: `keccak256(abi.encode(token0, token1, fee_bps))` with the tokens sorted.
+///
+/// @notice Computable before the pool exists (like UniV2's CREATE2 pair address), so routers and
+///         frontends can reference a pool by one word. Token order does not matter.
+pub fn compute_pool_id(token_a: Address, token_b: Address, fee_bps: U256) -> FixedBytes<32> {
+    let (token0, token1) = if token_a < token_b { (token_a, token_b) } else { (token_b, token_a) };
+    let mut encoded = Vec::with_capacity(96);
+    encoded.extend_from_slice(token0.into_word().as_slice());
+    encoded.extend_from_slice(token1.into_word().as_slice());
+    encoded.extend_from_slice(&fee_bps.to_be_bytes::<32>());
+    crypto::keccak(&encoded)
+}
+
+/// Commitment actually stored for a reveal against pool `pair_key`: `keccak256(pair_key, inner)`.
+///
+/// @notice `inner` is the reveal-specific preimage hash (`compute_commit_hash`, private, bundle
+///         or exact-out). Binding the pair key (`pairs::compute_pair_key`) means a commitment can
+///         only be revealed against the pool it was made for, never replayed on another market.
+pub fn compute_pool_bound_hash(pair_key: FixedBytes<32>, inner: FixedBytes<32>) -> FixedBytes<32> {
+    let mut encoded = [0u8; 64];
+    encoded[..32].copy_from_slice(pair_key.as_slice());
+    encoded[32..].copy_from_slice(inner.as_slice());
+    crypto::keccak(encoded)
+}
+
+/// (reserve_in, reserve_out) of the (token_a, token_b) pool for a `zero_for_one` trade.
+///
+/// @dev Tokens in any order; direction refers to the canonical token0 (lower address).
+fn directional_reserves(dex: &OakDEX, token_a: Address, token_b: Address, zero_for_one: bool) -> OakResult<(U256, U256)> {
+    let (token0, token1) = if token_a < token_b {
+        (token_a, token_b)
+    } else {
+        (token_b, token_a)
+    };
+    let outer = dex.pools.getter(token0);
+    let pool = outer.getter(token1);
+    if !pool.initialized.get() {
+        return Err(err(ERR_INVALID_TOKEN));
+    }
+    let (reserve0, reserve1) = (pool.reserve0.get(), pool.reserve1.get());
+    Ok(if zero_for_one { (reserve0, reserve1) } else { (reserve1, reserve0) })
+}
+
+/// Post-swap (reserve_in, reserve_out) for event payloads; zeros when the pool is not initialized.
+fn swap_reserves(dex: &OakDEX, token_in: Address, token_out: Address) -> (U256, U256) {
+    directional_reserves(dex, token_in, token_out, token_in < token_out).unwrap_or((U256::ZERO, U256::ZERO))
+}
+
+/// k_after / k_before of a pool, scaled by K_RATIO_SCALE (1e18 = unchanged), floor.
+///
+/// @notice Products are taken in 512 bits so large reserves cannot overflow. Zero when k_before
+///         is zero (nothing to compare against); saturates at U256::MAX.
+pub fn compute_k_ratio(before: (U256, U256), after: (U256, U256)) -> U256 {
+    let k_before = U512::from(before.0) * U512::from(before.1);
+    if k_before.is_zero() {
+        return U256::ZERO;
     }
+    let k_after = U512::from(after.0) * U512::from(after.1);
+    // Scaling needs 60 spare bits (1e18 < 2^60); shed low bits of both products if k is that large.
+    let shift = (k_after.bit_len() + 60).saturating_sub(512);
+    let (k_before, k_after) = (k_before >> shift, k_after >> shift);
+    if k_before.is_zero() {
+        return U256::MAX;
+    }
+    let ratio = k_after * U512::from(K_RATIO_SCALE) / k_before;
+    U256::checked_from_limbs_slice(ratio.as_limbs()).unwrap_or(U256::MAX)
+}
+
+/// Emit RevealSwap for a completed swap: fee split, post-swap reserves and k ratio against `before`.
+#[allow(clippy::too_many_arguments)]
+fn emit_reveal(
+    dex: &OakDEX,
+    user: Address,
+    token_in: Address,
+    token_out: Address,
+    amount_in: U256,
+    amount_out: U256,
+    fee_bps: U256,
+    before: (U256, U256),
+) -> OakResult<()> {
+    let (_effective_in, treasury_fee, lp_fee, _buyback_fee, _gas_rebate) = compute_fee_split(amount_in, fee_bps)?;
+    let after = swap_reserves(dex, token_in, token_out);
+    let k_ratio = compute_k_ratio(before, after);
+    emit_reveal_swap(user, amount_in, amount_out, treasury_fee, lp_fee, after.0, after.1, k_ratio);
     Ok(())
 }
 
+/// Delay (blocks) applied to treasury and fee-destination changes.
+fn treasury_change_delay(dex: &OakDEX) -> U256 {
+    let configured = dex.treasury_change_delay_blocks.get();
+    let configured = if configured.is_zero() { as_u256(TREASURY_CHANGE_DELAY_BLOCKS) } else { configured };
+    effective_treasury_delay(
+        configured,
+        dex.pending_treasury_change_delay.get(),
+        dex.treasury_change_delay_after_block.get(),
+        U256::from(block::number()),
+    )
+}
+
+/// Treasury change delay in force at `current_block`: `configured`, or a queued shorter
+/// `pending` delay once `pending_after` is reached.
+pub fn effective_treasury_delay(configured: U256, pending: U256, pending_after: U256, current_block: U256) -> U256 {
+    if !pending.is_zero() && current_block >= pending_after {
+        pending
+    } else {
+        configured
+    }
+}
+
+/// Block from which `requested` replaces the `current` treasury change delay. A longer delay
+/// applies at once; a shorter one only after the current delay has run, so lowering it cannot
+/// speed up a treasury change proposed right after.
+pub fn treasury_delay_effective_block(current: U256, requested: U256, current_block: U256) -> OakResult<U256> {
+    if requested >= current {
+        return Ok(current_block);
+    }
+    current_block.checked_add(current).ok_or_else(|| err(ERR_OVERFLOW))
+}
+
+/// Where withdraw_treasury_fees sends `token`: its routing rule, else the treasury address.
+fn treasury_destination_for(dex: &OakDEX, token: Address) -> Address {
+    let routed = dex.treasury_destination.getter(token).get();
+    if routed == Address::ZERO {
+        dex.treasury.get()
+    } else {
+        routed
+    }
+}
+
+/// Two-level permission for per-pool setters: the pool's manager, or protocol governance (ROLE_ADMIN).
+///
+/// @notice Governance override always applies, including for pools whose manager renounced.
+/// @dev Every pool-level setter goes through this check; pool must exist.
+fn require_pool_admin(dex: &OakDEX, token0: Address, token1: Address) -> OakResult<()> {
+    let outer = dex.pools.getter(token0);
+    let pool = outer.getter(token1);
+    if !pool.initialized.get() {
+        return Err(err(ERR_INVALID_TOKEN));
+    }
+    let sender = msg::sender();
+    check_pool_admin(roles_of(dex, sender), sender, pool.manager.get())
+}
+
+/// Two-level pool permission: `sender` (holding `sender_roles`) is governance or the pool's
+/// `manager`. A renounced manager (zero) leaves only governance.
+pub fn check_pool_admin(sender_roles: U256, sender: Address, manager: Address) -> OakResult<()> {
+    if holds_any(sender_roles, ROLE_ADMIN) || (manager != Address::ZERO && sender == manager) {
+        return Ok(());
+    }
+    Err(err(ERR_NOT_POOL_ADMIN))
+}
+
 /// Validate that an address is not the zero address.
 ///
 /// @notice Prevents invalid address inputs that could lead to fund loss.
@@ -73,9 +374,8 @@ fn require_non_zero_address(addr: Address) -> OakResult<()> {
 /// Re-entrancy guard: ensure function is not called recursively.
 ///
 /// @notice Checks and sets the global `locked` flag.
-/// @dev Must be paired with `unlock_reentrancy_guard` in a finally-like pattern.
-///      Pub(crate) so that entrypoints in intelligence/growth that perform external calls can use it.
-pub(crate) fn lock_reentrancy_guard(dex: &mut OakDEX) -> OakResult<()> {
+/// @dev Only called through `with_lock`, which pairs it with `unlock_reentrancy_guard`.
+fn lock_reentrancy_guard(dex: &mut OakDEX) -> OakResult<()> {
     if dex.locked.get() {
         return Err(err(ERR_REENTRANT_CALL));
     }
@@ -86,9 +386,293 @@ pub(crate) fn lock_reentrancy_guard(dex: &mut OakDEX) -> OakResult<()> {
 /// Re-entrancy guard: release the lock.
 ///
 /// @notice Clears the global `locked` flag.
-/// @dev Must be called after `lock_reentrancy_guard` to prevent deadlock.
-pub(crate) fn unlock_reentrancy_guard(dex: &mut OakDEX) {
+/// @dev Called by `with_lock` on every return path, and by the `force_unlock` escape hatch.
+fn unlock_reentrancy_guard(dex: &mut OakDEX) {
+    dex.locked.set(false);
+}
+
+/// Run `f` holding the re-entrancy lock, releasing it on every return path.
+///
+/// @notice `f` gets the contract back as `dex`, so error paths can use `?` freely: no closure
+///         inside it has to capture the contract just to unlock it. Entrypoints in
+///         intelligence/growth that perform external calls use it too.
+pub(crate) fn with_lock<T>(dex: &mut OakDEX, f: impl FnOnce(&mut OakDEX) -> OakResult<T>) -> OakResult<T> {
+    lock_reentrancy_guard(dex)?;
+    let result = f(dex);
+    unlock_reentrancy_guard(dex);
+    result
+}
+
+/// Pool state observed around an external call: reserves and LP supply.
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct PoolSnapshot {
+    reserve0: U256,
+    reserve1: U256,
+    lp_total_supply: U256,
+}
+
+impl PoolSnapshot {
+    fn take(dex: &OakDEX, token_a: Address, token_b: Address) -> Self {
+        let (token0, token1) = if token_a < token_b {
+            (token_a, token_b)
+        } else {
+            (token_b, token_a)
+        };
+        let outer = dex.pools.getter(token0);
+        let pool = outer.getter(token1);
+        Self {
+            reserve0: pool.reserve0.get(),
+            reserve1: pool.reserve1.get(),
+            lp_total_supply: pool.lp_total_supply.get(),
+        }
+    }
+}
+
+/// Run external call `f` and revert if the (token_a, token_b) pool changed while it ran.
+///
+/// @notice Hardens token transfers, hooks and callbacks against re-entry (e.g. ERC-777-style
+///         hooks) that reaches pool state through a path the boolean lock does not cover.
+/// @dev Pool writes must be finished before the call; `f` must not touch `dex`.
+fn guarded_external_call<T>(
+    dex: &OakDEX,
+    token_a: Address,
+    token_b: Address,
+    f: impl FnOnce() -> OakResult<T>,
+) -> OakResult<T> {
+    let before = PoolSnapshot::take(dex, token_a, token_b);
+    let out = f()?;
+    if PoolSnapshot::take(dex, token_a, token_b) != before {
+        return Err(err(ERR_POOL_STATE_CHANGED));
+    }
+    Ok(out)
+}
+
+/// Token transfer for a pool, with extra checks when the pool holds hook-capable tokens.
+///
+/// @notice Always runs under `guarded_external_call`. Hooked pools additionally require the
+///         contract's `token` balance to move by exactly `amount` (up when `incoming`), so a
+///         hook cannot redirect, skim or double-count the transfer.
+fn pool_transfer(
+    dex: &OakDEX,
+    pool: (Address, Address),
+    token: Address,
+    amount: U256,
+    incoming: bool,
+    hooked: bool,
+    f: impl FnOnce() -> OakResult<()>,
+) -> OakResult<()> {
+    if !hooked {
+        return guarded_external_call(dex, pool.0, pool.1, f);
+    }
+    let contract_addr = contract::address();
+    let before = balance_of(token, contract_addr);
+    guarded_external_call(dex, pool.0, pool.1, f)?;
+    let after = balance_of(token, contract_addr);
+    let moved = if incoming {
+        after.checked_sub(before)
+    } else {
+        before.checked_sub(after)
+    };
+    if moved != Some(amount) {
+        return Err(err(ERR_HOOKED_BALANCE_MISMATCH));
+    }
+    Ok(())
+}
+
+/// Pull up to `amount` of `token` from `from` into the contract and return what actually arrived.
+///
+/// @notice Fee-on-transfer (deflationary) tokens deliver less than `amount`; callers run the CPMM
+///         math and reserve updates on the returned balance delta, so reserves never count tokens
+///         the contract does not hold. Anything above `amount` is left as an unaccounted donation.
+/// @dev Native ETH arrives as msg.value and is taken as-is. Runs under `guarded_external_call`.
+pub(crate) fn pull_received(
+    dex: &OakDEX,
+    pool: (Address, Address),
+    token: Address,
+    from: Address,
+    amount: U256,
+) -> OakResult<U256> {
+    let contract_addr = contract::address();
+    if token == NATIVE_TOKEN {
+        safe_transfer_from(token, from, contract_addr, amount)?;
+        return Ok(amount);
+    }
+    guarded_external_call(dex, pool.0, pool.1, || safe_transfer_from_received(token, from, amount))
+}
+
+/// Revert with ERR_BALANCE_MISMATCH unless the contract's `token` balance covers every pool
+/// reserve plus the accrued fee and escrow buckets (`FeeReconciliation::position`).
+///
+/// @dev A surplus (donations, dust) is fine; only a shortfall means the arithmetic and the
+///      tokens actually held have diverged.
+fn require_balance_covers(dex: &OakDEX, token: Address) -> OakResult<()> {
+    let (_, _, _, deficit) = FeeReconciliation::position(dex, token)?;
+    if !deficit.is_zero() {
+        return Err(err(ERR_BALANCE_MISMATCH));
+    }
+    Ok(())
+}
+
+/// In single-pair mode, reject any pair other than the one bound at initialization.
+///
+/// @dev Pool creation, swaps and deposits check this; every other pool entrypoint needs an
+///      existing pool, which can then only be the bound one. Factory mode accepts any pair.
+fn require_bound_pair(dex: &OakDEX, token_a: Address, token_b: Address) -> OakResult<()> {
+    if !dex.tokens_bound.get() {
+        return Ok(());
+    }
+    let (token0, token1) = if token_a < token_b { (token_a, token_b) } else { (token_b, token_a) };
+    if token0 != dex.bound_token0.get() || token1 != dex.bound_token1.get() {
+        return Err(err(ERR_PAIR_NOT_BOUND));
+    }
+    Ok(())
+}
+
+/// One-time initializer body shared by `init` and `init_single_pair`.
+fn process_init(dex: &mut OakDEX, initial_owner: Address, treasury: Address) -> OakResult<()> {
+    let current_owner = dex.owner.get();
+    if current_owner != Address::ZERO {
+        return Err(err(ERR_ALREADY_INITIALIZED));
+    }
+
+    if initial_owner == Address::ZERO {
+        return Err(err(ERR_INVALID_OWNER));
+    }
+    if treasury == Address::ZERO {
+        return Err(err(ERR_INVALID_OWNER));
+    }
+    let contract_addr = contract::address();
+    if treasury == contract_addr {
+        return Err(err(ERR_TREASURY_IS_CONTRACT));
+    }
+
+    dex.owner.set(initial_owner);
+    dex.treasury.set(treasury);
+
+    // Set initial total fee (0.5%) for the first month after launch.
+    // Governance can later reduce this to `DEFAULT_FEE_BPS` via `set_fee`.
+    dex.protocol_fee_bps.set(as_u256(INITIAL_FEE));
+    for tier in [FEE_TIER_STABLE_BPS, FEE_TIER_STANDARD_BPS, FEE_TIER_VOLATILE_BPS] {
+        dex.fee_tier_enabled.setter(as_u256(tier)).set(true);
+    }
+
+    // Initialize analytics and fee accounting.
+    dex.total_volume_token0.set(U256::ZERO);
+    dex.total_volume_token1.set(U256::ZERO);
+    dex.accrued_treasury_fees_token0.set(U256::ZERO);
+    dex.accrued_lp_fees_token0.set(U256::ZERO);
+
+    // TWAP oracle and gas-rebate placeholder.
+    dex.price0_cumulative_last.set(U256::ZERO);
+    dex.price1_cumulative_last.set(U256::ZERO);
+    dex.block_timestamp_last.set(U256::ZERO);
+    dex.accrued_gas_rebate_token0.set(U256::ZERO);
+
+    // Contract starts active, unlocked, circuit breaker off.
+    dex.paused.set(false);
     dex.locked.set(false);
+    dex.circuit_breaker_triggered.set(false);
+    dex.buyback_wallet.set(Address::ZERO);
+    dex.pending_owner.set(Address::ZERO);
+    dex.owner_transfer_after_block.set(U256::ZERO);
+    dex.next_position_id.set(U256::ZERO);
+
+    // Access Control: grant ROLE_ADMIN and ROLE_PAUSER to initial_owner (multisig).
+    dex.role_bits.setter(initial_owner).set(ROLE_ADMIN | ROLE_PAUSER);
+
+    Ok(())
+}
+
+/// Create an empty pool for (token_a, token_b). Caller must hold the re-entrancy lock.
+///
+/// @dev `hooked` marks pools containing ERC-777 / ERC-677 style tokens (see `pool_transfer`);
+///      pairs with a hooked-registered token are hooked regardless (see `risk`).
+///      `fee_tier_bps` 0 keeps the pool on the global fee; otherwise it must be whitelisted.
+fn process_create_pool(
+    dex: &mut OakDEX,
+    token_a: Address,
+    token_b: Address,
+    hooked: bool,
+    fee_tier_bps: U256,
+) -> OakResult<()> {
+    // address(0) is native ETH (`NATIVE_TOKEN`), which always sorts as token0.
+    if token_a == token_b {
+        return Err(err(ERR_INVALID_TOKEN));
+    }
+    if !fee_tier_bps.is_zero() && !dex.fee_tier_enabled.get(fee_tier_bps) {
+        return Err(err(ERR_FEE_TIER_NOT_ENABLED));
+    }
+    require_bound_pair(dex, token_a, token_b)?;
+
+    // Canonical ordering
+    let (token0, token1) = if token_a < token_b {
+        (token_a, token_b)
+    } else {
+        (token_b, token_a)
+    };
+    let hooked = TokenRisk::check_pool_creation(dex, token0, token1, hooked)?;
+
+    // Access pool storage
+    let mut outer = dex.pools.setter(token0);
+    let mut pool = outer.setter(token1);
+
+    if pool.initialized.get() {
+        return Err(err(ERR_POOL_EXISTS));
+    }
+
+    // Initialize empty pool
+    pool.reserve0.set(U256::ZERO);
+    pool.reserve1.set(U256::ZERO);
+    pool.lp_total_supply.set(U256::ZERO);
+    pool.stats_epoch_start_block.set(U256::from(block::number()));
+    pool.hooked_token.set(hooked);
+    pool.fee_tier_bps.set(fee_tier_bps);
+    pool.manager.set(msg::sender());
+    pool.initialized.set(true);
+    PoolCreatorShare::register(dex, token0, token1, msg::sender())?;
+    PairRegistry::register(dex, token0, token1)?;
+
+    emit_pool_created(token0, token1);
+    emit_pool_manager_set(token0, token1, msg::sender());
+    if !fee_tier_bps.is_zero() {
+        emit_pool_fee_tier_set(token0, token1, fee_tier_bps);
+    }
+    Ok(())
+}
+
+/// Base swap fee (bps) of the (token_a, token_b) pool, tokens in any order.
+///
+/// @notice The pool's fee tier when it was created with one, else the global `protocol_fee_bps`.
+pub fn base_fee_bps(dex: &OakDEX, token_a: Address, token_b: Address) -> U256 {
+    let (token0, token1) = if token_a < token_b { (token_a, token_b) } else { (token_b, token_a) };
+    let tier = dex.pools.getter(token0).getter(token1).fee_tier_bps.get();
+    if tier.is_zero() {
+        dex.protocol_fee_bps.get()
+    } else {
+        tier
+    }
+}
+
+/// Total swap fee (bps) of the (token_a, token_b) pool, tokens in any order.
+///
+/// @notice `base_fee_bps` moved by the pool's volatility regime (see `fees::FeeController`).
+///         Every quote and swap on the pool prices and splits fees with this rate.
+pub fn pool_fee_bps(dex: &OakDEX, token_a: Address, token_b: Address) -> U256 {
+    let (token0, token1) = if token_a < token_b { (token_a, token_b) } else { (token_b, token_a) };
+    FeeController::effective_fee_bps(dex, token0, token1, base_fee_bps(dex, token0, token1))
+}
+
+/// Flash fee (bps) on loans from the (token_a, token_b) pool, tokens in any order.
+///
+/// @notice The owner's `flash_fee_bps` when set, else the pool's swap fee (`pool_fee_bps`).
+///         flash_swap and the ERC-3156 loans charge it and check k against it.
+pub fn flash_fee_bps(dex: &OakDEX, token_a: Address, token_b: Address) -> U256 {
+    let configured = dex.flash_fee_bps.get();
+    if configured.is_zero() {
+        pool_fee_bps(dex, token_a, token_b)
+    } else {
+        configured
+    }
 }
 
 /// Emergency circuit breaker: revert if protocol is paused.
@@ -102,12 +686,95 @@ fn require_not_paused(dex: &OakDEX) -> OakResult<()> {
     Ok(())
 }
 
+/// Revert with POOL_RETIRED once the (token0, token1) pool is past its retirement grace period.
+///
+/// @notice Applied with the pool pause to swaps and deposits; exits are never blocked.
+fn require_pool_not_halted(dex: &OakDEX, token0: Address, token1: Address) -> OakResult<()> {
+    if PoolMigration::halted(dex, compute_pair_key(token0, token1), U256::from(block::number())) {
+        return Err(err(ERR_POOL_RETIRED));
+    }
+    Ok(())
+}
+
+/// Revert with NOT_COMMIT_OPERATOR unless `operator` is `beneficiary` or was `approved` by it.
+pub fn check_commit_operator(operator: Address, beneficiary: Address, approved: bool) -> OakResult<()> {
+    if operator != beneficiary && !approved {
+        return Err(err(ERR_NOT_COMMIT_OPERATOR));
+    }
+    Ok(())
+}
+
+/// Caller of an ERC-1155 transfer out of `from`: `from` itself or its approved operator.
+fn require_position_operator(dex: &OakDEX, from: Address) -> OakResult<Address> {
+    let operator = msg::sender();
+    if operator != from && !PositionTokens::is_approved_for_all(dex, from, operator) {
+        return Err(err(ERR_NOT_POSITION_OPERATOR));
+    }
+    Ok(operator)
+}
+
+/// Reject order/position entrypoints when the `orders` feature is compiled out.
+///
+/// @dev `cfg!` folds to a constant, so without the feature every caller's body after
+///      this check is dead code and dropped from the WASM binary.
+#[inline(always)]
+fn require_orders_enabled() -> OakResult<()> {
+    if cfg!(feature = "orders") {
+        Ok(())
+    } else {
+        Err(err(ERR_FEATURE_DISABLED))
+    }
+}
+
 /// Map order ID (U256) to storage key (Address = last 20 bytes of BE encoding).
 fn order_id_to_address(order_id: U256) -> Address {
     let b = order_id.to_be_bytes::<32>();
     Address::from_slice(&b[12..32])
 }
 
+/// Mark an open order cancelled, refund its escrow to `owner` and unlink any OCO pair.
+///
+/// @dev Caller has checked ownership and that the order is open.
+fn release_open_order(dex: &mut OakDEX, order_id: U256, owner: Address) -> OakResult<()> {
+    let key = order_id_to_address(order_id);
+    let token_out = dex.order_token_out.setter(key).get();
+    let amount_out = dex.order_amount_out.setter(key).get();
+    dex.order_status.setter(key).set(U256::from(2u64)); // Cancelled
+    track_order_escrow(dex, token_out, amount_out, U256::ZERO)?;
+    safe_transfer(token_out, owner, amount_out)?;
+    let oco_pair = dex.order_oco_pair.setter(key).get();
+    if !oco_pair.is_zero() {
+        let oco_key = order_id_to_address(oco_pair);
+        dex.order_oco_pair.setter(key).set(U256::ZERO);
+        dex.order_oco_pair.setter(oco_key).set(U256::ZERO);
+    }
+    Ok(())
+}
+
+/// Cancel `owner`'s open orders among `order_ids`; returns the ids actually cancelled.
+///
+/// @dev Orders already filled or cancelled are skipped, so a risk-off batch does not revert
+///      because one quote was hit in the meantime. Ids not owned by `owner` revert.
+fn cancel_orders_of(dex: &mut OakDEX, owner: Address, order_ids: &[U256]) -> OakResult<Vec<U256>> {
+    let mut cancelled = Vec::new();
+    for &order_id in order_ids {
+        let key = order_id_to_address(order_id);
+        let order_owner = dex.order_owner.setter(key).get();
+        if order_owner == Address::ZERO {
+            return Err(err(ERR_ORDER_NOT_FOUND));
+        }
+        if order_owner != owner {
+            return Err(err(ERR_ORDER_NOT_OWNER));
+        }
+        if dex.order_status.setter(key).get() != U256::ZERO {
+            continue;
+        }
+        release_open_order(dex, order_id, owner)?;
+        cancelled.push(order_id);
+    }
+    Ok(cancelled)
+}
+
 /// Map position ID (U256) to storage key (same as order_id for consistency).
 fn position_id_to_address(position_id: U256) -> Address {
     let b = position_id.to_be_bytes::<32>();
@@ -125,11 +792,22 @@ fn require_not_circuit_breaker(dex: &OakDEX) -> OakResult<()> {
 
 /// Update TWAP oracle cumulative prices and last block.
 ///
-/// @notice Must be called at the beginning of every swap (reveal_swap) and add_liquidity.
+/// @notice Called before reserves move on every swap, flash loan and sync. Liquidity add and
+///         remove keep the pool price (up to rounding), so the next update accrues the same price.
 /// @dev Uses Q112.64 fixed-point: price0 = reserve1/reserve0, price1 = reserve0/reserve1.
 ///      On L2 we use block number as time index for gas efficiency.
 ///      cumulative += price * (current_block - block_last); all math checked.
-fn update_oracle(dex: &mut OakDEX, reserve0: U256, reserve1: U256) -> OakResult<()> {
+///      Also feeds the per-pool accumulator (`oracle::PoolOracle`) for `pool` (canonical order)
+///      and re-classifies the pool's volatility fee regime (`fees::FeeController`).
+///      No-op when the `oracle` feature is compiled out (cumulatives stay at zero).
+fn update_oracle(dex: &mut OakDEX, pool: (Address, Address), reserve0: U256, reserve1: U256) -> OakResult<()> {
+    if !cfg!(feature = "oracle") {
+        return Ok(());
+    }
+    PoolOracle::record(dex, pool.0, pool.1, reserve0, reserve1)?;
+    let base_bps = base_fee_bps(dex, pool.0, pool.1);
+    FeeController::adjust(dex, pool.0, pool.1, base_bps)?;
+
     let block_last = dex.block_timestamp_last.get();
     let current_block = U256::from(block::number());
 
@@ -189,8 +867,31 @@ pub(crate) fn process_swap_from_to_with_fee(
     min_amount_out: U256,
     fee_bps: U256,
 ) -> OakResult<U256> {
-    require_non_zero_address(token0)?;
-    require_non_zero_address(token1)?;
+    process_swap_measured(dex, from, to, token0, token1, amount_in, min_amount_out, fee_bps, None).map(|(_, out)| out)
+}
+
+/// Core swap: returns (amount_in actually received, amount_out).
+///
+/// @notice Input from outside the contract is pulled before any pricing and the CPMM math,
+///         fees and reserve updates use the received balance delta (see `pull_received`), so
+///         fee-on-transfer tokens cannot inflate reserves. Hooked pools keep the deferred pull
+///         with its exact balance check (strict CEI), which rejects fee-on-transfer input.
+/// @dev `reveal` is set when the swap settles a commitment: the revealer is credited the fee's
+///      gas rebate and a committed referrer takes precedence over `from`'s registered one.
+///      Every other swap leaves the rebate share with the treasury.
+#[allow(clippy::too_many_arguments)]
+fn process_swap_measured(
+    dex: &mut OakDEX,
+    from: Address,
+    to: Address,
+    token0: Address,
+    token1: Address,
+    amount_in: U256,
+    min_amount_out: U256,
+    fee_bps: U256,
+    reveal: Option<Reveal>,
+) -> OakResult<(U256, U256)> {
+    // address(0) is native ETH (`NATIVE_TOKEN`); unknown pairs fail the pool lookup.
     if amount_in.is_zero() {
         return Err(err(ERR_INSUFFICIENT_INPUT_AMOUNT));
     }
@@ -199,9 +900,11 @@ pub(crate) fn process_swap_from_to_with_fee(
     }
     require_not_paused(dex)?;
     require_not_circuit_breaker(dex)?;
+    require_bound_pair(dex, token0, token1)?;
 
     let contract_addr = contract::address();
-    if from != contract_addr {
+    // Native input arrives as msg.value, so there is no payer balance to check.
+    if from != contract_addr && token0 != NATIVE_TOKEN {
         let user_balance = balance_of(token0, from);
         if user_balance < amount_in {
             return Err(err(ERR_INSUFFICIENT_BALANCE));
@@ -214,17 +917,21 @@ pub(crate) fn process_swap_from_to_with_fee(
     } else {
         (token1, token0)
     };
-    let (reserve0, reserve1) = {
+    let (reserve0, reserve1, hooked) = {
         let mut outer = dex.pools.setter(pool_token0);
         let pool = outer.setter(pool_token1);
         if !pool.initialized.get() {
             return Err(err(ERR_INVALID_TOKEN));
         }
-        (pool.reserve0.get(), pool.reserve1.get())
+        if pool.paused.get() {
+            return Err(err(ERR_POOL_PAUSED));
+        }
+        (pool.reserve0.get(), pool.reserve1.get(), pool.hooked_token.get())
     };
+    require_pool_not_halted(dex, pool_token0, pool_token1)?;
 
     // TWAP oracle: update cumulative prices at the beginning of every swap.
-    update_oracle(dex, reserve0, reserve1)?;
+    update_oracle(dex, (pool_token0, pool_token1), reserve0, reserve1)?;
     // Emergency: if TWAP price deviates >15% per block, pause and trigger circuit breaker.
     crate::engine::check_price_deviation(dex, reserve0, reserve1)?;
 
@@ -235,6 +942,15 @@ pub(crate) fn process_swap_from_to_with_fee(
         (reserve1, reserve0)
     };
 
+    // Transfer in: from -> contract, before pricing; everything below uses what arrived.
+    // Hooked pools defer it until all accounting below is written (strict CEI).
+    let pool_key = (pool_token0, pool_token1);
+    let amount_in = if from != contract_addr && !hooked {
+        pull_received(dex, pool_key, token0, from, amount_in)?
+    } else {
+        amount_in
+    };
+
     // Bank-style cap: single trade cannot exceed MAX_TRADE_RESERVE_BPS of reserve (e.g. 10%).
     let max_trade = reserve_in
         .checked_mul(as_u256(MAX_TRADE_RESERVE_BPS))
@@ -246,6 +962,10 @@ pub(crate) fn process_swap_from_to_with_fee(
     }
 
     let amount_out = get_amount_out_with_fee(amount_in, reserve_in, reserve_out, fee_bps)?;
+    // Dust: never pull the user's input for a zero output.
+    if amount_out.is_zero() {
+        return Err(err(ERR_DUST_AMOUNT));
+    }
 
     // Circuit breaker: auto-trigger on extreme price impact (e.g. 20%+). Audit trail event.
     let impact_num = amount_out
@@ -273,21 +993,31 @@ pub(crate) fn process_swap_from_to_with_fee(
         return Err(err(ERR_SLIPPAGE_EXCEEDED));
     }
 
-    // Compute fee split: 60% LP, 20% Treasury, 20% Buyback.
-    let (_effective_in, treasury_fee, lp_fee, buyback_fee) =
+    // Compute fee split: 60% LP, 20% Treasury (less the gas rebate), 20% Buyback.
+    let (effective_in, treasury_fee, lp_fee, buyback_fee, gas_rebate) =
         compute_fee_split(amount_in, fee_bps)?;
 
-    // Reserve invariant: only (amount_in - treasury - buyback) goes to pool; rest is claimable by owner.
-    // This ensures withdraw_treasury_fees does not drain pool reserves (balance = pool_reserves + treasury + buyback).
+    // LP share goes to the pool's fee-per-share accumulator, claimable with claim_lp_fees; only
+    // the rounding remainder the accumulator cannot represent compounds into the reserves.
+    let lp_supply = dex.pools.getter(pool_token0).getter(pool_token1).lp_total_supply.get();
+    let (lp_growth, lp_distributed) = fee_per_share_growth(lp_fee, lp_supply).map_err(err)?;
+
+    // Reserve invariant: only (amount_in - treasury - rebate - buyback - LP fees) goes to pool; the
+    // rest is held for the owner, revealer and LPs, so withdrawals never drain pool reserves.
     let to_pool_in = amount_in
         .checked_sub(treasury_fee)
         .ok_or_else(|| err(ERR_OVERFLOW))?
+        .checked_sub(gas_rebate)
+        .ok_or_else(|| err(ERR_OVERFLOW))?
         .checked_sub(buyback_fee)
+        .ok_or_else(|| err(ERR_OVERFLOW))?
+        .checked_sub(lp_distributed)
         .ok_or_else(|| err(ERR_OVERFLOW))?;
 
     let new_reserve_in = reserve_in
         .checked_add(to_pool_in)
         .ok_or_else(|| err(ERR_RESERVE0_OVERFLOW))?;
+    TokenRisk::require_within_exposure(dex, token0, new_reserve_in)?;
 
     let new_reserve_out = reserve_out
         .checked_sub(amount_out)
@@ -304,13 +1034,21 @@ pub(crate) fn process_swap_from_to_with_fee(
     if new_reserve0 < min_liquidity || new_reserve1 < min_liquidity {
         return Err(err(ERR_INSUFFICIENT_LIQUIDITY));
     }
+    check_price_impact(dex.max_price_impact_bps.get(), (reserve_in, reserve_out), (new_reserve_in, new_reserve_out))?;
+    FlowLimiter::record_outflow(dex, pool_token0, pool_token1, token1 == pool_token0, reserve_out, amount_out)?;
 
+    PoolStats::accrue_reserves(dex, pool_token0, pool_token1)?;
     {
         let mut outer = dex.pools.setter(pool_token0);
         let mut pool = outer.setter(pool_token1);
         pool.reserve0.set(new_reserve0);
         pool.reserve1.set(new_reserve1);
     }
+    track_reserve(dex, pool_token0, reserve0, new_reserve0)?;
+    track_reserve(dex, pool_token1, reserve1, new_reserve1)?;
+    LpFees::accrue(dex, pool_token0, pool_token1, token0 == pool_token0, lp_growth, lp_distributed)?;
+    // Automatic breaker: this swap settles, but a spot/TWAP divergence pauses everything after it.
+    crate::engine::check_twap_divergence(dex, pool_token0, pool_token1, new_reserve0, new_reserve1)?;
 
     // Update analytics and accounting.
     let current_volume0 = dex.total_volume_token0.get();
@@ -327,18 +1065,37 @@ pub(crate) fn process_swap_from_to_with_fee(
     dex.total_volume_token0.set(new_volume0);
     dex.total_volume_token1.set(new_volume1);
 
+    // Per-pool epoch statistics (input volume and total fee in token_in units).
+    let swap_fee = amount_in.checked_sub(effective_in).ok_or_else(|| err(ERR_OVERFLOW))?;
+    PoolStats::record_swap(dex, pool_token0, pool_token1, token0 == pool_token0, amount_in, swap_fee)?;
+
     // Quest: record volume for swapper (for bonus.oak.trade XP/Badges).
+    #[cfg(feature = "rewards")]
     let _ = crate::growth::QuestSystem::record_volume(dex, from, amount_in);
 
-    // Transfer in: from -> contract (before referral so contract has tokens)
     let token_in = token0;
-    if from != contract_addr {
-        safe_transfer_from(token0, from, contract_addr, amount_in)?;
-    }
 
-    // Referral Engine: send % of treasury_fee to referrer (referee = from).
-    let referral_amount = crate::growth::ReferralEngine::distribute_referral_fee(dex, token_in, treasury_fee, from)?;
+    // Referral Engine: accrue % of treasury_fee to the referrer (committed, else registered for `from`).
+    #[cfg(feature = "rewards")]
+    let referral_amount = {
+        let committed = reveal.map_or(Address::ZERO, |r| r.referrer);
+        let referrer = crate::growth::ReferralEngine::referrer_for(dex, from, committed);
+        crate::growth::ReferralEngine::accrue_referral_fee(dex, referrer, token_in, treasury_fee)?
+    };
+    #[cfg(not(feature = "rewards"))]
+    let referral_amount = U256::ZERO;
     let treasury_net = treasury_fee.checked_sub(referral_amount).ok_or_else(|| err(ERR_OVERFLOW))?;
+    // Pool creator share (bookkeeping only; claimed later with claim_creator_fees).
+    let creator_amount = PoolCreatorShare::accrue(dex, pool_token0, pool_token1, token_in, treasury_net)?;
+    let treasury_net = treasury_net.checked_sub(creator_amount).ok_or_else(|| err(ERR_OVERFLOW))?;
+    // Gas rebate: owed to the revealer; whatever is not credited stays with the treasury.
+    let rebated = match reveal {
+        Some(r) => GasRebate::accrue(dex, r.revealer, token_in, gas_rebate)?,
+        None => U256::ZERO,
+    };
+    let treasury_net = treasury_net
+        .checked_add(gas_rebate - rebated)
+        .ok_or_else(|| err(ERR_OVERFLOW))?;
 
     // Per-token treasury and buyback (60/20/20 model).
     let prev_treasury = dex.treasury_balance.setter(token_in).get();
@@ -354,29 +1111,51 @@ pub(crate) fn process_swap_from_to_with_fee(
             .ok_or_else(|| err(ERR_OVERFLOW))?,
     );
 
-    // Gas-rebate placeholder: track a small portion of protocol fee for future gas rebates.
-    let total_fee = treasury_fee
-        .checked_add(lp_fee)
-        .ok_or_else(|| err(ERR_OVERFLOW))?;
-    let gas_rebate = total_fee
-        .checked_mul(as_u256(GAS_REBATE_BPS))
-        .ok_or_else(|| err(ERR_OVERFLOW))?
-        .checked_div(as_u256(FEE_DENOMINATOR))
-        .ok_or_else(|| err(ERR_DIVISION_BY_ZERO))?;
-    if !gas_rebate.is_zero() {
-        let acc = dex.accrued_gas_rebate_token0.get();
-        let new_acc = acc
-            .checked_add(gas_rebate)
-            .ok_or_else(|| err(ERR_OVERFLOW))?;
-        dex.accrued_gas_rebate_token0.set(new_acc);
+    if from != contract_addr && hooked {
+        pool_transfer(dex, pool_key, token0, amount_in, true, hooked, || {
+            safe_transfer_from(token0, from, contract_addr, amount_in)
+        })?;
     }
 
-    // Transfer out: contract -> to
-    safe_transfer(token1, to, amount_out)?;
+    // Transfer out: contract -> to (skipped when output stays in contract, e.g. escrow mode).
+    if to != contract_addr {
+        pool_transfer(dex, pool_key, token1, amount_out, false, hooked, || {
+            safe_transfer(token1, to, amount_out)
+        })?;
+    }
 
-    crate::events::emit_swap_executed(from, token0, token1, amount_in, amount_out);
+    // The contract must still hold every reserve and accrued bucket in both tokens. Contract-funded
+    // swaps (orders, callbacks) settle their input later and are checked by the caller.
+    if from != contract_addr {
+        require_balance_covers(dex, token0)?;
+        require_balance_covers(dex, token1)?;
+    }
 
-    Ok(amount_out)
+    crate::events::emit_swap_executed(from, token0, token1, amount_in, amount_out);
+
+    Ok((amount_in, amount_out))
+}
+
+/// Charge only the protocol share (treasury + buyback) of `fee_bps` on internally crossed volume.
+///
+/// @dev Crossed flow never touches the curve, so there is no LP fee. Returns the net amount
+///      left for the counterparties; the fee stays in the contract as treasury/buyback balance.
+///      There is no revealer to rebate, so the gas rebate share stays with the treasury.
+fn accrue_crossing_fee(dex: &mut OakDEX, token: Address, amount: U256, fee_bps: U256) -> OakResult<U256> {
+    let (_effective, treasury_fee, _lp_fee, buyback_fee, gas_rebate) = compute_fee_split(amount, fee_bps)?;
+    let treasury_fee = treasury_fee.checked_add(gas_rebate).ok_or_else(|| err(ERR_OVERFLOW))?;
+    let prev_treasury = dex.treasury_balance.setter(token).get();
+    let prev_buyback = dex.buyback_balance.setter(token).get();
+    dex.treasury_balance
+        .setter(token)
+        .set(prev_treasury.checked_add(treasury_fee).ok_or_else(|| err(ERR_OVERFLOW))?);
+    dex.buyback_balance
+        .setter(token)
+        .set(prev_buyback.checked_add(buyback_fee).ok_or_else(|| err(ERR_OVERFLOW))?);
+    amount
+        .checked_sub(treasury_fee)
+        .and_then(|v| v.checked_sub(buyback_fee))
+        .ok_or_else(|| err(ERR_OVERFLOW))
 }
 
 /// Core swap processing with configurable from/to (for direct swaps and order execution).
@@ -392,28 +1171,498 @@ fn process_swap_from_to(
     amount_in: U256,
     min_amount_out: U256,
 ) -> OakResult<U256> {
-    let fee_bps = dex.protocol_fee_bps.get();
+    let fee_bps = pool_fee_bps(dex, token0, token1);
     process_swap_from_to_with_fee(dex, from, to, token0, token1, amount_in, min_amount_out, fee_bps)
 }
 
+/// Commit-reveal context of a swap that settles a commitment.
+#[derive(Clone, Copy)]
+struct Reveal {
+    /// Owner of the commitment; earns the gas rebate.
+    revealer: Address,
+    /// Referrer committed with it (`commit_swap_with_referrer`); zero = none.
+    referrer: Address,
+    /// MEV protection tier committed with it (MEV_TIER_*); scales the swap fee.
+    tier: U256,
+}
+
+/// Reveal context for `user`'s just-consumed commitment (referrer and tier survive consumption).
+fn reveal_context(dex: &OakDEX, user: Address) -> Reveal {
+    let commitment = dex.commitments.getter(user);
+    Reveal { revealer: user, referrer: commitment.referrer.get(), tier: commitment.mev_tier.get() }
+}
+
+/// Swap fee (bps) on (token0, token1): the pool fee, scaled by the MEV tier when settling a reveal.
+fn reveal_fee_bps(dex: &OakDEX, token0: Address, token1: Address, reveal: Option<Reveal>) -> OakResult<U256> {
+    let fee_bps = pool_fee_bps(dex, token0, token1);
+    match reveal {
+        Some(reveal) => Ok(mev_tier_fee_bps(reveal.tier, fee_bps)?),
+        None => Ok(fee_bps),
+    }
+}
+
 /// Core swap processing: invariant math, slippage protection, fee accounting and transfers.
 ///
 /// @notice Entrypoint path: from = to = msg::sender. Emits RevealSwap.
+/// @dev `reveal` is set when the swap settles the sender's commitment (see `Reveal`).
 fn process_swap(
     dex: &mut OakDEX,
     token0: Address,
     token1: Address,
     amount_in: U256,
     min_amount_out: U256,
+    reveal: Option<Reveal>,
+) -> OakResult<U256> {
+    let sender = msg::sender();
+    let fee_bps = reveal_fee_bps(dex, token0, token1, reveal)?;
+    let before = swap_reserves(dex, token0, token1);
+    let (amount_in, amount_out) =
+        process_swap_measured(dex, sender, sender, token0, token1, amount_in, min_amount_out, fee_bps, reveal)?;
+    TradeJournal::record(dex, sender, token0, token1, amount_in, amount_out)?;
+    emit_reveal(dex, sender, token0, token1, amount_in, amount_out, fee_bps, before)?;
+    Ok(amount_out)
+}
+
+/// Swap processing for escrow mode: output stays in the contract and is credited to the caller.
+///
+/// @notice Entrypoint path: from = msg::sender, to = contract. Emits RevealSwap.
+/// @dev The caller later pulls the output with claim_output (see `escrow`). Only reveals use
+///      escrow mode, so there is always a `reveal` context.
+fn process_swap_to_escrow(
+    dex: &mut OakDEX,
+    token0: Address,
+    token1: Address,
+    amount_in: U256,
+    min_amount_out: U256,
+    reveal: Reveal,
+) -> OakResult<U256> {
+    let sender = msg::sender();
+    let contract_addr = contract::address();
+    let fee_bps = reveal_fee_bps(dex, token0, token1, Some(reveal))?;
+    let before = swap_reserves(dex, token0, token1);
+    let (amount_in, amount_out) =
+        process_swap_measured(dex, sender, contract_addr, token0, token1, amount_in, min_amount_out, fee_bps, Some(reveal))?;
+    OutputEscrow::credit(dex, sender, token1, amount_out)?;
+    TradeJournal::record(dex, sender, token0, token1, amount_in, amount_out)?;
+    emit_reveal(dex, sender, token0, token1, amount_in, amount_out, fee_bps, before)?;
+    Ok(amount_out)
+}
+
+/// ABI-encode `oakSwapCallback(uint256 amount_in_owed, uint256 amount_out, bytes data)`.
+pub fn encode_swap_callback(amount_in_owed: U256, amount_out: U256, data: &[u8]) -> Vec<u8> {
+    let selector = crypto::keccak(b"oakSwapCallback(uint256,uint256,bytes)");
+    let padding = (32 - data.len() % 32) % 32;
+    let mut call_data = Vec::with_capacity(4 + 128 + data.len() + padding);
+    call_data.extend_from_slice(&selector[0..4]);
+    call_data.extend_from_slice(&amount_in_owed.to_be_bytes::<32>());
+    call_data.extend_from_slice(&amount_out.to_be_bytes::<32>());
+    // Dynamic `bytes`: offset (3 head words), length, data padded to a word boundary.
+    call_data.extend_from_slice(&U256::from(96u64).to_be_bytes::<32>());
+    call_data.extend_from_slice(&U256::from(data.len()).to_be_bytes::<32>());
+    call_data.extend_from_slice(data);
+    call_data.resize(call_data.len() + padding, 0);
+    call_data
+}
+
+/// ABI-encode the `style` (FLASH_CALLBACK_*) flash swap callback to `borrower`.
+///
+/// @dev Oak passes the amounts owed, Uniswap V2 the amounts borrowed with `borrower` as sender,
+///      Uniswap V3 the fees. flash_swap checks repayment the same way whatever the style.
+#[allow(clippy::too_many_arguments)]
+pub fn encode_flash_swap_callback(
+    style: U256,
+    borrower: Address,
+    amount0_out: U256,
+    amount1_out: U256,
+    fee0: U256,
+    fee1: U256,
+    data: &[u8],
+) -> MathResult<Vec<u8>> {
+    let (signature, head): (&[u8], Vec<[u8; 32]>) = if style == as_u256(FLASH_CALLBACK_OAK) {
+        let owed0 = amount0_out.checked_add(fee0).ok_or(ERR_OVERFLOW)?;
+        let owed1 = amount1_out.checked_add(fee1).ok_or(ERR_OVERFLOW)?;
+        (b"oakFlashSwapCallback(uint256,uint256,bytes)", alloc::vec![owed0.to_be_bytes(), owed1.to_be_bytes()])
+    } else if style == as_u256(FLASH_CALLBACK_UNISWAP_V2) {
+        (
+            b"uniswapV2Call(address,uint256,uint256,bytes)",
+            alloc::vec![borrower.into_word().0, amount0_out.to_be_bytes(), amount1_out.to_be_bytes()],
+        )
+    } else if style == as_u256(FLASH_CALLBACK_UNISWAP_V3) {
+        (b"uniswapV3FlashCallback(uint256,uint256,bytes)", alloc::vec![fee0.to_be_bytes(), fee1.to_be_bytes()])
+    } else {
+        return Err(ERR_UNKNOWN_FLASH_CALLBACK);
+    };
+    let selector = crypto::keccak(signature);
+    let padding = (32 - data.len() % 32) % 32;
+    let mut call_data = Vec::with_capacity(4 + 32 * (head.len() + 2) + data.len() + padding);
+    call_data.extend_from_slice(&selector[0..4]);
+    for word in &head {
+        call_data.extend_from_slice(word);
+    }
+    // Dynamic `bytes`: offset (past the head words and itself), length, data padded to a word.
+    call_data.extend_from_slice(&U256::from(32 * (head.len() + 1)).to_be_bytes::<32>());
+    call_data.extend_from_slice(&U256::from(data.len()).to_be_bytes::<32>());
+    call_data.extend_from_slice(data);
+    call_data.resize(call_data.len() + padding, 0);
+    Ok(call_data)
+}
+
+/// ERC-3156 `onFlashLoan` success value: keccak256("ERC3156FlashBorrower.onFlashLoan").
+pub fn flash_loan_callback_success() -> FixedBytes<32> {
+    crypto::keccak(b"ERC3156FlashBorrower.onFlashLoan")
+}
+
+/// ABI-encode `onFlashLoan(address initiator, address token, uint256 amount, uint256 fee, bytes data)`.
+pub fn encode_on_flash_loan(initiator: Address, token: Address, amount: U256, fee: U256, data: &[u8]) -> Vec<u8> {
+    let selector = crypto::keccak(b"onFlashLoan(address,address,uint256,uint256,bytes)");
+    let padding = (32 - data.len() % 32) % 32;
+    let mut call_data = Vec::with_capacity(4 + 192 + data.len() + padding);
+    call_data.extend_from_slice(&selector[0..4]);
+    call_data.extend_from_slice(initiator.into_word().as_slice());
+    call_data.extend_from_slice(token.into_word().as_slice());
+    call_data.extend_from_slice(&amount.to_be_bytes::<32>());
+    call_data.extend_from_slice(&fee.to_be_bytes::<32>());
+    // Dynamic `bytes`: offset (5 head words), length, data padded to a word boundary.
+    call_data.extend_from_slice(&U256::from(160u64).to_be_bytes::<32>());
+    call_data.extend_from_slice(&U256::from(data.len()).to_be_bytes::<32>());
+    call_data.extend_from_slice(data);
+    call_data.resize(call_data.len() + padding, 0);
+    call_data
+}
+
+/// ERC-3156 fee on a loan of `amount`: the same `amount * fee_bps / FEE_DENOMINATOR` flash_swap charges.
+pub fn flash_loan_fee(amount: U256, fee_bps: U256) -> MathResult<U256> {
+    amount
+        .checked_mul(fee_bps)
+        .ok_or(ERR_OVERFLOW)?
+        .checked_div(as_u256(FEE_DENOMINATOR))
+        .ok_or(ERR_DIVISION_BY_ZERO)
+}
+
+/// Canonical pool an ERC-3156 loan of `token` draws from, if any.
+///
+/// @dev The pair set with `set_flash_loan_pool`, else the bound pair in single-pair mode. The
+///      pool must exist and hold no hook-capable token; native ETH is never lent.
+fn flash_loan_pool(dex: &OakDEX, token: Address) -> Option<(Address, Address)> {
+    if token == NATIVE_TOKEN || token == Address::ZERO {
+        return None;
+    }
+    let mut counter = dex.flash_loan_pair.get(token);
+    if counter == Address::ZERO && dex.tokens_bound.get() {
+        let (bound0, bound1) = (dex.bound_token0.get(), dex.bound_token1.get());
+        if token == bound0 {
+            counter = bound1;
+        } else if token == bound1 {
+            counter = bound0;
+        }
+    }
+    if counter == Address::ZERO {
+        return None;
+    }
+    let (token0, token1) = if token < counter { (token, counter) } else { (counter, token) };
+    let outer = dex.pools.getter(token0);
+    let pool = outer.getter(token1);
+    (pool.initialized.get() && !pool.hooked_token.get()).then_some((token0, token1))
+}
+
+/// ERC-3156 `maxFlashLoan`: `token`'s reserve in its flash-loan pool above min_liquidity.
+///
+/// @dev Zero when no pool serves `token`, the risk registry bans borrowing it, the protocol or
+///      pool is paused, or the pool is retired past its grace period.
+fn max_flash_loan_amount(dex: &OakDEX, token: Address) -> U256 {
+    let Some((token0, token1)) = flash_loan_pool(dex, token) else {
+        return U256::ZERO;
+    };
+    if TokenRisk::require_flash_borrow_allowed(dex, token).is_err() {
+        return U256::ZERO;
+    }
+    let outer = dex.pools.getter(token0);
+    let pool = outer.getter(token1);
+    if dex.paused.get() || pool.paused.get() || require_pool_not_halted(dex, token0, token1).is_err() {
+        return U256::ZERO;
+    }
+    let reserve = if token == token0 { pool.reserve0.get() } else { pool.reserve1.get() };
+    reserve.saturating_sub(dex.min_liquidity.get())
+}
+
+/// Book the flash fee on `amount_out` of `token` borrowed from the canonical (token0, token1) pool.
+///
+/// @dev Split like a swap fee (`compute_fee_split`) in `token` itself: treasury (plus the gas
+///      rebate share, as there is no revealer), buyback, and the pool's LP fee accumulator for
+///      that side. Returns the part of the fee now held outside the reserves; the rest (the LP
+///      rounding remainder) belongs to the reserves.
+fn accrue_flash_fee(
+    dex: &mut OakDEX,
+    token0: Address,
+    token1: Address,
+    token: Address,
+    amount_out: U256,
+    fee_bps: U256,
+) -> OakResult<U256> {
+    let (_, treasury_fee, lp_fee, buyback_fee, gas_rebate) = compute_fee_split(amount_out, fee_bps).map_err(err)?;
+    let lp_supply = dex.pools.getter(token0).getter(token1).lp_total_supply.get();
+    let (lp_growth, lp_distributed) = fee_per_share_growth(lp_fee, lp_supply).map_err(err)?;
+    LpFees::accrue(dex, token0, token1, token == token0, lp_growth, lp_distributed)?;
+    let to_treasury = treasury_fee.checked_add(gas_rebate).ok_or_else(|| err(ERR_OVERFLOW))?;
+    let treasury = dex.treasury_balance.get(token).checked_add(to_treasury).ok_or_else(|| err(ERR_OVERFLOW))?;
+    let buyback = dex.buyback_balance.get(token).checked_add(buyback_fee).ok_or_else(|| err(ERR_OVERFLOW))?;
+    dex.treasury_balance.setter(token).set(treasury);
+    dex.buyback_balance.setter(token).set(buyback);
+    to_treasury
+        .checked_add(buyback_fee)
+        .and_then(|b| b.checked_add(lp_distributed))
+        .ok_or_else(|| err(ERR_OVERFLOW))
+}
+
+/// ERC-3156 loan of `amount` of `token` to `receiver`, on behalf of `initiator`. Emits FlashLoan.
+///
+/// @notice The tokens are sent to `receiver`, `receiver.onFlashLoan(initiator, token, amount,
+///         fee, data)` must return the ERC-3156 success value, and `amount + fee` is then pulled
+///         back with transferFrom (the receiver approves this contract).
+/// @dev The fee is booked by `accrue_flash_fee`; the LP rounding remainder compounds into the
+///      reserve. Repayment is checked on the balance delta.
+fn process_flash_loan(
+    dex: &mut OakDEX,
+    initiator: Address,
+    receiver: Address,
+    token: Address,
+    amount: U256,
+    data: &[u8],
+) -> OakResult<()> {
+    require_not_paused(dex)?;
+    require_non_zero_address(receiver)?;
+    let (token0, token1) = flash_loan_pool(dex, token).ok_or_else(|| err(ERR_FLASH_LOAN_UNSUPPORTED))?;
+    TokenRisk::require_flash_borrow_allowed(dex, token)?;
+    if dex.pools.getter(token0).getter(token1).paused.get() {
+        return Err(err(ERR_POOL_PAUSED));
+    }
+    if amount.is_zero() {
+        return Err(err(ERR_INSUFFICIENT_INPUT_AMOUNT));
+    }
+    if amount > max_flash_loan_amount(dex, token) {
+        return Err(err(ERR_INSUFFICIENT_LIQUIDITY));
+    }
+    let fee_bps = flash_fee_bps(dex, token0, token1);
+    let fee = flash_loan_fee(amount, fee_bps).map_err(err)?;
+    let repayment = amount.checked_add(fee).ok_or_else(|| err(ERR_OVERFLOW))?;
+    // The principal leaves the pool for the callback, so it counts towards the per-block outflow limit.
+    let reserve_out = {
+        let outer = dex.pools.getter(token0);
+        let pool = outer.getter(token1);
+        if token == token0 { pool.reserve0.get() } else { pool.reserve1.get() }
+    };
+    FlowLimiter::record_outflow(dex, token0, token1, token == token0, reserve_out, amount)?;
+    let contract_addr = contract::address();
+    let balance_before = balance_of(token, contract_addr);
+
+    let call_data = encode_on_flash_loan(initiator, token, amount, fee, data);
+    guarded_external_call(dex, token0, token1, || {
+        safe_transfer(token, receiver, amount)?;
+        let ret = call::call(Call::new(), receiver, &call_data).map_err(|_| err(ERR_FLASH_LOAN_CALLBACK_FAILED))?;
+        if ret.len() != 32 || ret[..] != flash_loan_callback_success()[..] {
+            return Err(err(ERR_FLASH_LOAN_CALLBACK_FAILED));
+        }
+        safe_transfer_from(token, receiver, contract_addr, repayment)
+    })?;
+    let repaid = balance_of(token, contract_addr).saturating_sub(balance_before);
+    if repaid < fee {
+        return Err(err(ERR_FLASH_LOAN_NOT_REPAID));
+    }
+
+    // EFFECTS: book the fee; the principal never left the reserves in storage.
+    let booked = accrue_flash_fee(dex, token0, token1, token, amount, fee_bps)?;
+    let compounded = fee.checked_sub(booked).ok_or_else(|| err(ERR_OVERFLOW))?;
+    if !compounded.is_zero() {
+        let (reserve0, reserve1) = {
+            let outer = dex.pools.getter(token0);
+            let pool = outer.getter(token1);
+            (pool.reserve0.get(), pool.reserve1.get())
+        };
+        update_oracle(dex, (token0, token1), reserve0, reserve1)?;
+        PoolStats::accrue_reserves(dex, token0, token1)?;
+        let (new_reserve0, new_reserve1) = if token == token0 {
+            (reserve0.checked_add(compounded).ok_or_else(|| err(ERR_RESERVE0_OVERFLOW))?, reserve1)
+        } else {
+            (reserve0, reserve1.checked_add(compounded).ok_or_else(|| err(ERR_RESERVE1_OVERFLOW))?)
+        };
+        {
+            let mut outer = dex.pools.setter(token0);
+            let mut pool = outer.setter(token1);
+            pool.reserve0.set(new_reserve0);
+            pool.reserve1.set(new_reserve1);
+        }
+        track_reserve(dex, token0, reserve0, new_reserve0)?;
+        track_reserve(dex, token1, reserve1, new_reserve1)?;
+    }
+    require_balance_covers(dex, token)?;
+    emit_flash_loan(receiver, token, initiator, amount, fee);
+    Ok(())
+}
+
+/// Instant swap with optional pay-later callback (UniV2 pair style). Emits RevealSwap.
+///
+/// @notice Empty `data`: the input is pulled from msg::sender before pricing, as in
+///         `process_swap`. Otherwise the output is sent to `to` first, then
+///         `to.oakSwapCallback(amount_in, amount_out, data)` runs and must transfer `amount_in`
+///         of `token_in` to this contract before it returns.
+/// @dev Settlement is checked on the contract's `token_in` balance delta. The pool's k must not
+///      have dropped, and the callback cannot touch pool state (`guarded_external_call`).
+///      Hooked pools and native ETH input cannot use the callback.
+#[allow(clippy::too_many_arguments)]
+fn process_swap_with_callback(
+    dex: &mut OakDEX,
+    to: Address,
+    token_in: Address,
+    token_out: Address,
+    amount_in: U256,
+    min_amount_out: U256,
+    data: &[u8],
 ) -> OakResult<U256> {
     let sender = msg::sender();
-    let amount_out = process_swap_from_to(dex, sender, sender, token0, token1, amount_in, min_amount_out)?;
-    let (_effective_in, treasury_fee, lp_fee, _buyback_fee) =
-        compute_fee_split(amount_in, dex.protocol_fee_bps.get())?;
-    emit_reveal_swap(sender, amount_in, amount_out, treasury_fee, lp_fee);
+    let fee_bps = pool_fee_bps(dex, token_in, token_out);
+    let before = swap_reserves(dex, token_in, token_out);
+    if data.is_empty() {
+        let (amount_in, amount_out) =
+            process_swap_measured(dex, sender, to, token_in, token_out, amount_in, min_amount_out, fee_bps, None)?;
+        TradeJournal::record(dex, sender, token_in, token_out, amount_in, amount_out)?;
+        emit_reveal(dex, sender, token_in, token_out, amount_in, amount_out, fee_bps, before)?;
+        return Ok(amount_out);
+    }
+
+    let (token0, token1) = if token_in < token_out { (token_in, token_out) } else { (token_out, token_in) };
+    let (reserve0, reserve1, hooked) = {
+        let outer = dex.pools.getter(token0);
+        let pool = outer.getter(token1);
+        (pool.reserve0.get(), pool.reserve1.get(), pool.hooked_token.get())
+    };
+    if hooked || token_in == NATIVE_TOKEN {
+        return Err(err(ERR_CALLBACK_NOT_ALLOWED));
+    }
+    let k_before = reserve0.checked_mul(reserve1).ok_or_else(|| err(ERR_OVERFLOW))?;
+    let contract_addr = contract::address();
+    let balance_before = balance_of(token_in, contract_addr);
+
+    // Optimistic: accounting and output transfer as if the input were already held.
+    let (_, amount_out) =
+        process_swap_measured(dex, contract_addr, to, token_in, token_out, amount_in, min_amount_out, fee_bps, None)?;
+
+    let call_data = encode_swap_callback(amount_in, amount_out, data);
+    guarded_external_call(dex, token0, token1, || {
+        call::call(Call::new(), to, &call_data)
+            .map(|_| ())
+            .map_err(|e| e.into())
+    })?;
+
+    let received = balance_of(token_in, contract_addr).saturating_sub(balance_before);
+    if received < amount_in {
+        return Err(err(ERR_SWAP_NOT_SETTLED));
+    }
+    require_balance_covers(dex, token_in)?;
+    require_balance_covers(dex, token_out)?;
+    let k_after = {
+        let outer = dex.pools.getter(token0);
+        let pool = outer.getter(token1);
+        pool.reserve0.get().checked_mul(pool.reserve1.get()).ok_or_else(|| err(ERR_OVERFLOW))?
+    };
+    if k_after < k_before {
+        return Err(err(ERR_K_DECREASED));
+    }
+
+    TradeJournal::record(dex, sender, token_in, token_out, amount_in, amount_out)?;
+    emit_reveal(dex, sender, token_in, token_out, amount_in, amount_out, fee_bps, before)?;
     Ok(amount_out)
 }
 
+/// Chained exact-input quotes: `amounts[0] = amount_in`, then `get_amount_out_with_fee` per hop.
+///
+/// @notice `hops` holds each pool's (reserve_in, reserve_out, fee_bps) in trade direction. A hop
+///         that rounds to zero output fails with DUST_AMOUNT rather than quoting a dead route.
+pub fn chain_amounts_out(amount_in: U256, hops: &[(U256, U256, U256)]) -> MathResult<Vec<U256>> {
+    let mut amounts = Vec::with_capacity(hops.len() + 1);
+    amounts.push(amount_in);
+    let mut current = amount_in;
+    for &(reserve_in, reserve_out, fee_bps) in hops {
+        current = get_amount_out_with_fee(current, reserve_in, reserve_out, fee_bps)?;
+        if current.is_zero() {
+            return Err(ERR_DUST_AMOUNT);
+        }
+        amounts.push(current);
+    }
+    Ok(amounts)
+}
+
+/// Exact-input swap along `path` through registered pairs; returns the amount at every step.
+///
+/// @notice The input is pulled from `sender` once; intermediate outputs stay in the contract and
+///         feed the next hop, and only the last hop pays `sender`. The chained quote is checked
+///         against `min_out` before anything moves, and the executed output again at the end
+///         (fee-on-transfer input can only make it smaller). Every hop emits RevealSwap.
+fn process_swap_exact_in_path(
+    dex: &mut OakDEX,
+    sender: Address,
+    path: &[Address],
+    amount_in: U256,
+    min_out: U256,
+) -> OakResult<Vec<U256>> {
+    if path.len() < 2 {
+        return Err(err(ERR_INVALID_PATH));
+    }
+    if path.len() as u64 > MAX_PATH_LENGTH {
+        return Err(err(ERR_PATH_TOO_LONG));
+    }
+    if amount_in.is_zero() {
+        return Err(err(ERR_INSUFFICIENT_INPUT_AMOUNT));
+    }
+    if min_out.is_zero() {
+        return Err(err(ERR_INSUFFICIENT_OUTPUT_AMOUNT));
+    }
+    TokenAliases::require_acyclic_path(dex, path)?;
+
+    let mut hops = Vec::with_capacity(path.len() - 1);
+    for pair in path.windows(2) {
+        let (token_in, token_out) = (pair[0], pair[1]);
+        if token_in == token_out {
+            return Err(err(ERR_INVALID_PATH));
+        }
+        let (token0, token1) = if token_in < token_out { (token_in, token_out) } else { (token_out, token_in) };
+        if PairRegistry::position(dex, token0, token1).is_none() {
+            return Err(err(ERR_PAIR_NOT_REGISTERED));
+        }
+        let (reserve_in, reserve_out) = directional_reserves(dex, token_in, token_out, token_in == token0)?;
+        hops.push((reserve_in, reserve_out, pool_fee_bps(dex, token0, token1)));
+    }
+    let quoted = chain_amounts_out(amount_in, &hops)?;
+    if quoted[quoted.len() - 1] < min_out {
+        return Err(err(ERR_SLIPPAGE_EXCEEDED));
+    }
+
+    let contract_addr = contract::address();
+    let last = path.len() - 2;
+    let mut amounts = Vec::with_capacity(path.len());
+    let mut hop_in = amount_in;
+    for (i, pair) in path.windows(2).enumerate() {
+        let (token_in, token_out) = (pair[0], pair[1]);
+        let from = if i == 0 { sender } else { contract_addr };
+        let to = if i == last { sender } else { contract_addr };
+        let hop_min = if i == last { min_out } else { U256::from(1u64) };
+        let fee_bps = hops[i].2;
+        let before = swap_reserves(dex, token_in, token_out);
+        let (received, hop_out) =
+            process_swap_measured(dex, from, to, token_in, token_out, hop_in, hop_min, fee_bps, None)?;
+        emit_reveal(dex, sender, token_in, token_out, received, hop_out, fee_bps, before)?;
+        amounts.push(received);
+        hop_in = hop_out;
+    }
+    amounts.push(hop_in);
+
+    // Hops funded by the contract skip the per-swap balance check; settle every token once here.
+    for token in path {
+        require_balance_covers(dex, *token)?;
+    }
+    TradeJournal::record(dex, sender, path[0], path[path.len() - 1], amounts[0], hop_in)?;
+    Ok(amounts)
+}
+
 // ---------- EIP-712 Gasless Permit Swap ----------
 
 /// EIP-712 domain name and version for PermitSwap.
@@ -441,6 +1690,11 @@ fn signal_listing_type_hash() -> FixedBytes<32> {
     crypto::keccak(b"SignalListing(address seller,bytes32 signalIdHash,uint256 price,uint256 nonce,uint256 deadline)")
 }
 
+/// keccak256("CancelBatch(address owner,uint256[] orderIds,bool cancelCommitment,uint256 nonce,uint256 deadline)")
+fn cancel_batch_type_hash() -> FixedBytes<32> {
+    crypto::keccak(b"CancelBatch(address owner,uint256[] orderIds,bool cancelCommitment,uint256 nonce,uint256 deadline)")
+}
+
 /// EIP-712 struct hash for SignalListing (used as listing_hash and in digest).
 pub(crate) fn compute_signal_listing_struct_hash(
     seller: Address,
@@ -529,6 +1783,48 @@ fn compute_permit_swap_digest(
     crypto::keccak(&prefix)
 }
 
+/// Compute EIP-712 digest for CancelBatch: "\x19\x01" || domainSeparator || structHash.
+///
+/// @dev `orderIds` is a dynamic array, so it enters the struct as keccak256 of its packed words.
+pub fn compute_cancel_batch_digest(
+    owner: Address,
+    order_ids: &[U256],
+    cancel_commitment: bool,
+    nonce: U256,
+    deadline: U256,
+    domain_separator: &FixedBytes<32>,
+) -> FixedBytes<32> {
+    let mut ids = Vec::with_capacity(32 * order_ids.len());
+    for &id in order_ids {
+        ids.extend_from_slice(&enc_u256(id));
+    }
+    let mut enc = Vec::with_capacity(192);
+    enc.extend_from_slice(cancel_batch_type_hash().as_slice());
+    enc.extend_from_slice(&enc_addr(owner));
+    enc.extend_from_slice(crypto::keccak(&ids).as_slice());
+    enc.extend_from_slice(&enc_u256(U256::from(cancel_commitment as u8)));
+    enc.extend_from_slice(&enc_u256(nonce));
+    enc.extend_from_slice(&enc_u256(deadline));
+    let struct_hash = crypto::keccak(&enc);
+    let mut prefix = Vec::with_capacity(66);
+    prefix.extend_from_slice(b"\x19\x01");
+    prefix.extend_from_slice(domain_separator.as_slice());
+    prefix.extend_from_slice(struct_hash.as_slice());
+    crypto::keccak(&prefix)
+}
+
+/// Revert unless a signed message is still valid at `current_block` and carries the signer's
+/// next `expected_nonce`: PERMIT_EXPIRED past `deadline`, PERMIT_NONCE on a reused or skipped nonce.
+pub fn check_permit(current_block: U256, deadline: U256, nonce: U256, expected_nonce: U256) -> OakResult<()> {
+    if current_block > deadline {
+        return Err(err(ERR_PERMIT_EXPIRED));
+    }
+    if nonce != expected_nonce {
+        return Err(err(ERR_PERMIT_NONCE));
+    }
+    Ok(())
+}
+
 /// Recover signer from EIP-712 digest and (v, r, s). Returns zero address on failure.
 pub(crate) fn ecrecover_recover(digest: FixedBytes<32>, v: u8, r: [u8; 32], s: [u8; 32]) -> Address {
     let v_normalized = if v <= 1 { v + 27 } else { v };
@@ -554,14 +1850,15 @@ pub(crate) fn ecrecover_recover(digest: FixedBytes<32>, v: u8, r: [u8; 32], s: [
 ///      amount_out = (amount_in_with_fee * reserve_out)
 ///                   / (reserve_in * FEE_DENOMINATOR + amount_in_with_fee)
 ///      where amount_in_with_fee = amount_in * (FEE_DENOMINATOR - fee_bps).
+///      Returns a static error code; `?` in an `OakResult` caller converts it.
 pub fn get_amount_out_with_fee(
     amount_in: U256,
     reserve_in: U256,
     reserve_out: U256,
     fee_bps: U256,
-) -> OakResult<U256> {
+) -> MathResult<U256> {
     if amount_in.is_zero() || reserve_in.is_zero() || reserve_out.is_zero() {
-        return Err(err(ERR_INSUFFICIENT_INPUT_AMOUNT));
+        return Err(ERR_INSUFFICIENT_INPUT_AMOUNT);
     }
 
     // If the effective fee rounds down to zero for this trade size,
@@ -570,265 +1867,1709 @@ pub fn get_amount_out_with_fee(
     // of reverting, so callers can decide whether to proceed.
     let total_fee = amount_in
         .checked_mul(fee_bps)
-        .ok_or_else(|| err(ERR_OVERFLOW))?
+        .ok_or(ERR_OVERFLOW)?
         .checked_div(as_u256(FEE_DENOMINATOR))
-        .ok_or_else(|| err(ERR_DIVISION_BY_ZERO))?;
+        .ok_or(ERR_DIVISION_BY_ZERO)?;
     if !fee_bps.is_zero() && total_fee.is_zero() {
         return Ok(U256::ZERO);
     }
 
     let fee_multiplier = as_u256(FEE_DENOMINATOR)
         .checked_sub(fee_bps)
-        .ok_or_else(|| err(ERR_FEE_OVERFLOW))?;
+        .ok_or(ERR_FEE_OVERFLOW)?;
 
     let amount_in_with_fee = amount_in
         .checked_mul(fee_multiplier)
-        .ok_or_else(|| err(ERR_OVERFLOW))?;
+        .ok_or(ERR_OVERFLOW)?;
 
     let numerator = amount_in_with_fee
         .checked_mul(reserve_out)
-        .ok_or_else(|| err(ERR_OVERFLOW))?;
+        .ok_or(ERR_OVERFLOW)?;
 
     let denominator_part1 = reserve_in
         .checked_mul(as_u256(FEE_DENOMINATOR))
-        .ok_or_else(|| err(ERR_OVERFLOW))?;
+        .ok_or(ERR_OVERFLOW)?;
 
     let denominator = denominator_part1
         .checked_add(amount_in_with_fee)
-        .ok_or_else(|| err(ERR_OVERFLOW))?;
+        .ok_or(ERR_OVERFLOW)?;
 
-    // Integer division in Rust performs floor rounding (rounds down).
-    // This is protocol-favorable: users receive slightly less, protocol retains value.
+    // Output is paid out: floor, so users receive slightly less and the pool keeps the dust.
     // Formula: amount_out = floor((amount_in_with_fee * reserve_out) / denominator)
-    let amount_out = numerator
-        .checked_div(denominator)
-        .ok_or_else(|| err(ERR_DIVISION_BY_ZERO))?;
-
-    Ok(amount_out)
+    rounding::div(numerator, denominator, Rounding::Down)
 }
 
 /// Inverse of get_amount_out: amount_in needed to receive at least amount_out (single hop). Rounds up (protocol-safe).
+///
+/// @dev Never below the fee-rounding dust floor, so `get_amount_out_with_fee(result) >= amount_out`.
 pub fn get_amount_in_with_fee(
     amount_out: U256,
     reserve_in: U256,
     reserve_out: U256,
     fee_bps: U256,
-) -> OakResult<U256> {
+) -> MathResult<U256> {
     if amount_out.is_zero() || reserve_in.is_zero() || reserve_out.is_zero() {
-        return Err(err(ERR_INSUFFICIENT_INPUT_AMOUNT));
+        return Err(ERR_INSUFFICIENT_INPUT_AMOUNT);
     }
-    let reserve_out_sub = reserve_out.checked_sub(amount_out).ok_or_else(|| err(ERR_INSUFFICIENT_LIQUIDITY))?;
-    let fee_mult = as_u256(FEE_DENOMINATOR).checked_sub(fee_bps).ok_or_else(|| err(ERR_FEE_OVERFLOW))?;
+    let reserve_out_sub = reserve_out.checked_sub(amount_out).ok_or(ERR_INSUFFICIENT_LIQUIDITY)?;
+    let fee_mult = as_u256(FEE_DENOMINATOR).checked_sub(fee_bps).ok_or(ERR_FEE_OVERFLOW)?;
     let numerator = amount_out
         .checked_mul(reserve_in)
-        .ok_or_else(|| err(ERR_OVERFLOW))?
+        .ok_or(ERR_OVERFLOW)?
         .checked_mul(as_u256(FEE_DENOMINATOR))
-        .ok_or_else(|| err(ERR_OVERFLOW))?;
+        .ok_or(ERR_OVERFLOW)?;
     let denominator = reserve_out_sub
         .checked_mul(fee_mult)
-        .ok_or_else(|| err(ERR_OVERFLOW))?;
-    let amount_in = numerator
-        .checked_div(denominator)
-        .ok_or_else(|| err(ERR_DIVISION_BY_ZERO))?;
-    let remainder = numerator % denominator;
-    let amount_in_ceil = if remainder.is_zero() {
-        amount_in
-    } else {
-        amount_in.checked_add(U256::from(1u64)).ok_or_else(|| err(ERR_OVERFLOW))?
-    };
-    Ok(amount_in_ceil)
+        .ok_or(ERR_OVERFLOW)?;
+    let amount_in_ceil = rounding::div(numerator, denominator, Rounding::Up)?;
+    // Below ceil(FEE_DENOMINATOR / fee_bps) the fee rounds to zero and get_amount_out_with_fee
+    // returns dust (0), so the quoted input must never go under that floor.
+    if fee_bps.is_zero() {
+        return Ok(amount_in_ceil);
+    }
+    let fee_floor = rounding::div(as_u256(FEE_DENOMINATOR), fee_bps, Rounding::Up)?;
+    Ok(amount_in_ceil.max(fee_floor))
+}
+
+/// Smallest `amount_in` that yields a non-zero output for the given reserves and fee.
+///
+/// @notice Below this, floor rounding (or a fee that rounds to zero) makes the swap pure dust.
+/// @dev get_amount_in_with_fee(1): the inverse already applies the fee-rounding floor.
+pub fn min_amount_in_for_output(reserve_in: U256, reserve_out: U256, fee_bps: U256) -> MathResult<U256> {
+    get_amount_in_with_fee(U256::from(1u64), reserve_in, reserve_out, fee_bps)
+}
+
+/// How far a swap moves the pool's spot price (reserve_out / reserve_in), in bps of the old price.
+///
+/// @notice `BPS - new_price * BPS / old_price` with the ratio floored, so the move rounds up and a
+///         guard on it errs on the strict side. Output reserves only shrink and input reserves only
+///         grow in a swap, so the price never moves the other way.
+pub fn spot_price_move_bps(
+    reserve_in: U256,
+    reserve_out: U256,
+    new_reserve_in: U256,
+    new_reserve_out: U256,
+) -> MathResult<U256> {
+    let remaining = new_reserve_out
+        .checked_mul(reserve_in)
+        .ok_or(ERR_OVERFLOW)?
+        .checked_mul(as_u256(BPS))
+        .ok_or(ERR_OVERFLOW)?
+        .checked_div(reserve_out.checked_mul(new_reserve_in).ok_or(ERR_OVERFLOW)?)
+        .ok_or(ERR_DIVISION_BY_ZERO)?;
+    Ok(as_u256(BPS).saturating_sub(remaining))
+}
+
+/// Revert with PRICE_IMPACT_TOO_HIGH when a swap from `before` to `after` (reserve_in,
+/// reserve_out) moves the spot price by more than `max_impact_bps` (0 = no cap).
+pub fn check_price_impact(max_impact_bps: U256, before: (U256, U256), after: (U256, U256)) -> OakResult<()> {
+    if !max_impact_bps.is_zero() && spot_price_move_bps(before.0, before.1, after.0, after.1)? > max_impact_bps {
+        return Err(err(ERR_PRICE_IMPACT_TOO_HIGH));
+    }
+    Ok(())
+}
+
+/// Revert with DEADLINE_EXPIRED once `now` passes the caller's `deadline` (inclusive).
+///
+/// @notice Direct entrypoints (router and instant swaps, liquidity, permit flows) pass the block
+///         timestamp. Commit-reveal reveals pass the block number: their deadline is bound into
+///         the commitment and the reveal window is counted in blocks.
+pub fn check_deadline(now: U256, deadline: U256) -> OakResult<()> {
+    if now > deadline {
+        return Err(err(ERR_DEADLINE_EXPIRED));
+    }
+    Ok(())
 }
 
 /// Compute the total fee and its split: 60% LP, 20% Treasury, 20% Buyback.
 ///
 /// @notice World-class fee model: LPs get majority, treasury and buyback fund get equal shares.
-/// @dev All math checked; remainder goes to LP to avoid dust.
+/// @dev All math checked; remainder goes to LP to avoid dust. Shares are percentages of the
+///      total fee, so the split is exact for any fee up to MAX_FEE_BPS (not just the default).
+///      Returns (effective_in, treasury, lp, buyback, gas_rebate): GAS_REBATE_BPS of the total
+///      fee is carved out of the treasury share as the revealer's gas rebate (see `gas_rebate`).
 pub fn compute_fee_split(
     amount_in: U256,
     fee_bps: U256,
-) -> OakResult<(U256, U256, U256, U256)> {
+) -> MathResult<(U256, U256, U256, U256, U256)> {
+    if fee_bps > as_u256(MAX_FEE_BPS) {
+        return Err(ERR_FEE_TOO_HIGH);
+    }
     if amount_in.is_zero() {
-        return Ok((U256::ZERO, U256::ZERO, U256::ZERO, U256::ZERO));
+        return Ok((U256::ZERO, U256::ZERO, U256::ZERO, U256::ZERO, U256::ZERO));
     }
 
     let total_fee = amount_in
         .checked_mul(fee_bps)
-        .ok_or_else(|| err(ERR_OVERFLOW))?
+        .ok_or(ERR_OVERFLOW)?
         .checked_div(as_u256(FEE_DENOMINATOR))
-        .ok_or_else(|| err(ERR_DIVISION_BY_ZERO))?;
+        .ok_or(ERR_DIVISION_BY_ZERO)?;
 
     if total_fee.is_zero() {
-        return Ok((amount_in, U256::ZERO, U256::ZERO, U256::ZERO));
+        return Ok((amount_in, U256::ZERO, U256::ZERO, U256::ZERO, U256::ZERO));
     }
 
     // 20% Treasury
     let treasury_fee = total_fee
         .checked_mul(as_u256(TREASURY_FEE_PCT))
-        .ok_or_else(|| err(ERR_OVERFLOW))?
+        .ok_or(ERR_OVERFLOW)?
         .checked_div(U256::from(100u64))
-        .ok_or_else(|| err(ERR_DIVISION_BY_ZERO))?;
+        .ok_or(ERR_DIVISION_BY_ZERO)?;
 
     // 20% Buyback
     let buyback_fee = total_fee
         .checked_mul(as_u256(BUYBACK_FEE_PCT))
-        .ok_or_else(|| err(ERR_OVERFLOW))?
+        .ok_or(ERR_OVERFLOW)?
         .checked_div(U256::from(100u64))
-        .ok_or_else(|| err(ERR_DIVISION_BY_ZERO))?;
+        .ok_or(ERR_DIVISION_BY_ZERO)?;
 
     // 60% LP (remainder to avoid rounding dust)
     let lp_fee = total_fee
         .checked_sub(treasury_fee)
-        .ok_or_else(|| err(ERR_OVERFLOW))?
+        .ok_or(ERR_OVERFLOW)?
         .checked_sub(buyback_fee)
-        .ok_or_else(|| err(ERR_OVERFLOW))?;
+        .ok_or(ERR_OVERFLOW)?;
+
+    // Gas rebate out of the treasury share (always smaller: GAS_REBATE_BPS of the total fee).
+    let gas_rebate = total_fee
+        .checked_mul(as_u256(GAS_REBATE_BPS))
+        .ok_or(ERR_OVERFLOW)?
+        .checked_div(as_u256(FEE_DENOMINATOR))
+        .ok_or(ERR_DIVISION_BY_ZERO)?;
+    let treasury_fee = treasury_fee.checked_sub(gas_rebate).ok_or(ERR_OVERFLOW)?;
 
     let effective_in = amount_in
         .checked_sub(total_fee)
-        .ok_or_else(|| err(ERR_OVERFLOW))?;
+        .ok_or(ERR_OVERFLOW)?;
 
-    Ok((effective_in, treasury_fee, lp_fee, buyback_fee))
+    Ok((effective_in, treasury_fee, lp_fee, buyback_fee, gas_rebate))
 }
 
-/// Integer square root for `U256` (floor).
+/// Split offsetting batch flow into the part crossed internally (coincidence of wants).
 ///
-/// @notice Returns `floor(sqrt(x))` using a Babylonian-style iteration.
-/// @dev This is used for initial LP token minting: sqrt(amount0 * amount1).
-fn u256_sqrt(x: U256) -> U256 {
-    if x.is_zero() {
-        return U256::ZERO;
+/// @notice `total_x` of token X is sold against `total_y` of token Y at the pool spot price
+///         `reserve_y / reserve_x`. Returns `(crossed_x, crossed_y)`: the smaller side is fully
+///         crossed, the larger side keeps `total - crossed` as the residual routed through the curve.
+/// @dev Floor rounding on the converted leg keeps the dust inside the contract (never over-pays).
+pub fn compute_batch_crossing(
+    total_x: U256,
+    total_y: U256,
+    reserve_x: U256,
+    reserve_y: U256,
+) -> MathResult<(U256, U256)> {
+    if total_x.is_zero() || total_y.is_zero() {
+        return Ok((U256::ZERO, U256::ZERO));
     }
+    if reserve_x.is_zero() || reserve_y.is_zero() {
+        return Err(ERR_INSUFFICIENT_LIQUIDITY);
+    }
+    let y_in_x = rounding::div(total_y.checked_mul(reserve_x).ok_or(ERR_OVERFLOW)?, reserve_y, Rounding::Down)?;
+    if y_in_x <= total_x {
+        return Ok((y_in_x, total_y));
+    }
+    let x_in_y = rounding::div(total_x.checked_mul(reserve_y).ok_or(ERR_OVERFLOW)?, reserve_x, Rounding::Down)?;
+    Ok((total_x, x_in_y))
+}
 
-    // Initial approximation: x/2 + 1
-    let mut z = x;
-    let mut y = (x >> 1) + U256::from(1u64);
+/// Revert with BATCH_PRICE_DEVIATION when the crossing price `spot` is more than
+/// MAX_BATCH_CROSS_TWAP_DEVIATION_BPS away from the pool's `twap` (both Q112, same direction).
+///
+/// @dev A same-block swap moves the spot but not the TWAP, so a batch cannot be crossed at a
+///      price an attacker just pushed the pool to.
+pub fn check_batch_crossing_price(spot: U256, twap: U256) -> OakResult<()> {
+    if price_divergence_bps(spot, twap).map_err(err)? > as_u256(MAX_BATCH_CROSS_TWAP_DEVIATION_BPS) {
+        return Err(err(ERR_BATCH_PRICE_DEVIATION));
+    }
+    Ok(())
+}
 
-    while y < z {
-        z = y;
-        y = (x.checked_div(y).unwrap_or(U256::ZERO) + y) >> 1;
+/// Pack a commitment's fill priority: `(commit_block << 128) | sequence`.
+///
+/// @notice Ordering by this key is ordering by commit block, then by commit order inside the
+///         block. Both halves are fixed when the commitment is stored, so nobody settling a batch
+///         can reorder users.
+pub fn commitment_priority(commit_block: U256, sequence: U256) -> MathResult<U256> {
+    let mask = (U256::from(1u64) << 128) - U256::from(1u64);
+    if commit_block > mask || sequence > mask {
+        return Err(ERR_OVERFLOW);
     }
+    Ok((commit_block << 128) | sequence)
+}
 
-    z
+/// Allocate `available` output across batch orders under price-time priority.
+///
+/// @notice `orders` is `(priority, amount)` in any order. Orders are filled in ascending
+///         priority (earliest commitment first): each is filled in full while liquidity lasts,
+///         the order at the boundary is filled partially, later ones get zero. Returns the fill
+///         for each order in the input order. Equal keys keep input order (stable sort), but
+///         keys from `commitment_priority` are unique.
+pub fn allocate_fills_by_priority(orders: &[(U256, U256)], available: U256) -> Vec<U256> {
+    let mut queue: Vec<usize> = (0..orders.len()).collect();
+    queue.sort_by_key(|&i| orders[i].0);
+    let mut fills = alloc::vec![U256::ZERO; orders.len()];
+    let mut remaining = available;
+    for i in queue {
+        if remaining.is_zero() {
+            break;
+        }
+        let fill = orders[i].1.min(remaining);
+        fills[i] = fill;
+        remaining -= fill;
+    }
+    fills
 }
 
-/// Public contract functions implementation.
+/// Amounts actually deposited for a desired pair, keeping the pool ratio (Uniswap V2 `_addLiquidity`).
 ///
-/// @notice Core entrypoints exposed to external callers.
-/// @dev These methods operate on Stylus storage types defined in `state`.
-///      This block is only compiled for on-chain (wasm32) builds; host
-///      tests use the pure helper functions above instead.
-#[cfg(all(not(test), target_arch = "wasm32"))]
-#[public]
-impl OakDEX {
-    /// Create a new pool for a token pair.
-    ///
+/// @notice Canonical order. Empty pool: the desired amounts set the price. Otherwise one side is
+///         used in full and the other is cut to `desired * reserve_other / reserve` (floor), so a
+///         deposit can never move the price; the unused excess is simply not taken.
+pub fn optimal_liquidity_amounts(
+    amount0_desired: U256,
+    amount1_desired: U256,
+    reserve0: U256,
+    reserve1: U256,
+) -> MathResult<(U256, U256)> {
+    if reserve0.is_zero() && reserve1.is_zero() {
+        return Ok((amount0_desired, amount1_desired));
+    }
+    if reserve0.is_zero() || reserve1.is_zero() {
+        return Err(ERR_INSUFFICIENT_LIQUIDITY);
+    }
+    let amount1_optimal =
+        rounding::div(amount0_desired.checked_mul(reserve1).ok_or(ERR_OVERFLOW)?, reserve0, Rounding::Down)?;
+    if amount1_optimal <= amount1_desired {
+        return Ok((amount0_desired, amount1_optimal));
+    }
+    let amount0_optimal =
+        rounding::div(amount1_desired.checked_mul(reserve0).ok_or(ERR_OVERFLOW)?, reserve1, Rounding::Down)?;
+    Ok((amount0_optimal, amount1_desired))
+}
+
+/// LP shares minted for depositing (amount0, amount1) into a pool, canonical order (Uniswap V2 `mint`).
+///
+/// @notice First deposit: `sqrt(amount0 * amount1) - MINIMUM_LIQUIDITY`; the caller locks the
+///         MINIMUM_LIQUIDITY shares to address(0), so the first share can never be made arbitrarily
+///         expensive for free. Later deposits: `min(amount0 * supply / reserve0, amount1 * supply /
+///         reserve1)`, floor. Zero shares fail rather than taking a deposit for nothing.
+pub fn compute_liquidity_minted(
+    amount0: U256,
+    amount1: U256,
+    reserve0: U256,
+    reserve1: U256,
+    total_supply: U256,
+) -> MathResult<U256> {
+    if total_supply.is_zero() {
+        let min_lp = as_u256(MINIMUM_LIQUIDITY);
+        let sqrt = u256_sqrt(amount0.checked_mul(amount1).ok_or(ERR_LIQUIDITY_OVERFLOW)?);
+        if sqrt <= min_lp {
+            return Err(ERR_INSUFFICIENT_LIQUIDITY);
+        }
+        return Ok(sqrt - min_lp);
+    }
+    let liquidity0 =
+        rounding::div(amount0.checked_mul(total_supply).ok_or(ERR_LIQUIDITY_OVERFLOW)?, reserve0, Rounding::Down)?;
+    let liquidity1 =
+        rounding::div(amount1.checked_mul(total_supply).ok_or(ERR_LIQUIDITY_OVERFLOW)?, reserve1, Rounding::Down)?;
+    let liquidity = liquidity0.min(liquidity1);
+    if liquidity.is_zero() {
+        return Err(ERR_INSUFFICIENT_LIQUIDITY);
+    }
+    Ok(liquidity)
+}
+
+/// Pool amounts paid out for burning `liquidity_share` of `total_supply`, canonical order.
+///
+/// @notice `reserve * liquidity_share / total_supply` per token, floor, so the pool keeps the
+///         dust. A burn that pays nothing on either side fails.
+pub fn compute_liquidity_burned(
+    liquidity_share: U256,
+    reserve0: U256,
+    reserve1: U256,
+    total_supply: U256,
+) -> MathResult<(U256, U256)> {
+    let amount0 =
+        rounding::div(reserve0.checked_mul(liquidity_share).ok_or(ERR_OVERFLOW)?, total_supply, Rounding::Down)?;
+    let amount1 =
+        rounding::div(reserve1.checked_mul(liquidity_share).ok_or(ERR_OVERFLOW)?, total_supply, Rounding::Down)?;
+    if amount0.is_zero() || amount1.is_zero() {
+        return Err(ERR_INSUFFICIENT_LIQUIDITY);
+    }
+    Ok((amount0, amount1))
+}
+
+/// Integer square root for `U256` (floor).
+///
+/// @notice Returns `floor(sqrt(x))` using a Babylonian-style iteration.
+/// @dev This is used for initial LP token minting: sqrt(amount0 * amount1).
+pub(crate) fn u256_sqrt(x: U256) -> U256 {
+    if x.is_zero() {
+        return U256::ZERO;
+    }
+
+    // Initial approximation: x/2 + 1
+    let mut z = x;
+    let mut y = (x >> 1) + U256::from(1u64);
+
+    while y < z {
+        z = y;
+        y = (x.checked_div(y).unwrap_or(U256::ZERO) + y) >> 1;
+    }
+
+    z
+}
+
+/// Core liquidity provision: LP math, token pull, reserve update and LP mint.
+///
+/// @notice Shared by add_liquidity and the commit-reveal swap+LP bundle.
+/// @dev `prefunded` (in caller token order) marks tokens already held by the contract
+///      (e.g. swap proceeds); those are not pulled from `provider`.
+///      Caller must hold the re-entrancy lock. Returns LP tokens minted.
+#[allow(clippy::too_many_arguments)]
+fn process_add_liquidity(
+    dex: &mut OakDEX,
+    provider: Address,
+    token0: Address,
+    token1: Address,
+    amount0: U256,
+    amount1: U256,
+    amount0_min: U256,
+    amount1_min: U256,
+    prefunded: (bool, bool),
+) -> OakResult<U256> {
+    // address(0) is native ETH (`NATIVE_TOKEN`); unknown pairs fail the pool lookup.
+
+    // Input sanitization: validate amounts
+    if amount0.is_zero() {
+        return Err(err(ERR_AMOUNT0_ZERO));
+    }
+    if amount1.is_zero() {
+        return Err(err(ERR_AMOUNT1_ZERO));
+    }
+
+    require_not_paused(dex)?;
+    require_not_circuit_breaker(dex)?;
+    require_bound_pair(dex, token0, token1)?;
+
+    // Canonicalize token ordering for pool key.
+    let (pool_token0, pool_token1) = if token0 < token1 {
+        (token0, token1)
+    } else {
+        (token1, token0)
+    };
+    let (reserve0, reserve1, total_supply, hooked) = {
+        let outer = dex.pools.getter(pool_token0);
+        let pool = outer.getter(pool_token1);
+        if !pool.initialized.get() {
+            return Err(err(ERR_INVALID_TOKEN));
+        }
+        if pool.paused.get() {
+            return Err(err(ERR_POOL_PAUSED));
+        }
+        (
+            pool.reserve0.get(),
+            pool.reserve1.get(),
+            pool.lp_total_supply.get(),
+            pool.hooked_token.get(),
+        )
+    };
+    require_pool_not_halted(dex, pool_token0, pool_token1)?;
+
+    // Map provided amounts into canonical order.
+    let (desired0_c, desired1_c, amount0_min_c, amount1_min_c) = if token0 == pool_token0 {
+        (amount0, amount1, amount0_min, amount1_min)
+    } else {
+        (amount1, amount0, amount1_min, amount0_min)
+    };
+
+    // Ratio enforcement: only the price-preserving part of the desired amounts is deposited.
+    let (amount0_c, amount1_c) = optimal_liquidity_amounts(desired0_c, desired1_c, reserve0, reserve1)?;
+    if amount0_c.is_zero() || amount1_c.is_zero() {
+        return Err(err(ERR_INSUFFICIENT_LIQUIDITY));
+    }
+
+    // LP slippage protection (bank-grade: never accept below user minimum).
+    if amount0_c < amount0_min_c || amount1_c < amount1_min_c {
+        return Err(err(ERR_LP_SLIPPAGE));
+    }
+    // Used and excess amounts back in caller order.
+    let (used0, used1) = if token0 == pool_token0 {
+        (amount0_c, amount1_c)
+    } else {
+        (amount1_c, amount0_c)
+    };
+    let excess0 = amount0.checked_sub(used0).ok_or_else(|| err(ERR_OVERFLOW))?;
+    let excess1 = amount1.checked_sub(used1).ok_or_else(|| err(ERR_OVERFLOW))?;
+
+    // Transfer tokens from provider to contract before computing liquidity
+    // (hooked pools: after all accounting, strict CEI).
+    let contract_addr = contract::address();
+    let pool_key = (pool_token0, pool_token1);
+    // Pulled tokens: only the used amount is taken and what arrives is counted (fee-on-transfer
+    // tokens deliver less; hooked pools require the exact amount). Prefunded tokens: the excess
+    // is refunded. Returns the deposited amounts in caller order.
+    let pull_deposits = |dex: &OakDEX| -> OakResult<(U256, U256)> {
+        let received0 = if prefunded.0 {
+            if !excess0.is_zero() {
+                pool_transfer(dex, pool_key, token0, excess0, false, hooked, || {
+                    safe_transfer(token0, provider, excess0)
+                })?;
+            }
+            used0
+        } else if hooked {
+            pool_transfer(dex, pool_key, token0, used0, true, hooked, || {
+                safe_transfer_from(token0, provider, contract_addr, used0)
+            })?;
+            used0
+        } else {
+            pull_received(dex, pool_key, token0, provider, used0)?
+        };
+        let received1 = if prefunded.1 {
+            if !excess1.is_zero() {
+                pool_transfer(dex, pool_key, token1, excess1, false, hooked, || {
+                    safe_transfer(token1, provider, excess1)
+                })?;
+            }
+            used1
+        } else if hooked {
+            pool_transfer(dex, pool_key, token1, used1, true, hooked, || {
+                safe_transfer_from(token1, provider, contract_addr, used1)
+            })?;
+            used1
+        } else {
+            pull_received(dex, pool_key, token1, provider, used1)?
+        };
+        Ok((received0, received1))
+    };
+    let (used0, used1, amount0_c, amount1_c) = if hooked {
+        (used0, used1, amount0_c, amount1_c)
+    } else {
+        let (received0, received1) = pull_deposits(dex)?;
+        let (received0_c, received1_c) = if token0 == pool_token0 {
+            (received0, received1)
+        } else {
+            (received1, received0)
+        };
+        // Slippage minimums apply to what actually arrived.
+        if received0_c < amount0_min_c || received1_c < amount1_min_c {
+            return Err(err(ERR_LP_SLIPPAGE));
+        }
+        (received0, received1, received0_c, received1_c)
+    };
+
+    // Compute LP tokens to mint, following Uniswap V2 semantics.
+    let first_deposit = total_supply.is_zero();
+    let min_lp = as_u256(MINIMUM_LIQUIDITY);
+    let liquidity = compute_liquidity_minted(amount0_c, amount1_c, reserve0, reserve1, total_supply)?;
+    if first_deposit {
+        // Cold start: a feed-bound pair must open near the external price.
+        PoolOracle::check_bootstrap_price(dex, pool_token0, pool_token1, amount0_c, amount1_c)?;
+    }
+
+    // Update reserves after successful transfer (canonical order).
+    let new_reserve0 = reserve0
+        .checked_add(amount0_c)
+        .ok_or_else(|| err(ERR_RESERVE0_OVERFLOW))?;
+    let new_reserve1 = reserve1
+        .checked_add(amount1_c)
+        .ok_or_else(|| err(ERR_RESERVE1_OVERFLOW))?;
+    TokenRisk::require_within_exposure(dex, pool_token0, new_reserve0)?;
+    TokenRisk::require_within_exposure(dex, pool_token1, new_reserve1)?;
+
+    let locked_lp = if first_deposit { min_lp } else { U256::ZERO };
+    let new_total = total_supply
+        .checked_add(locked_lp)
+        .and_then(|t| t.checked_add(liquidity))
+        .ok_or_else(|| err(ERR_LIQUIDITY_OVERFLOW))?;
+    PoolStats::accrue_reserves(dex, pool_token0, pool_token1)?;
+    LpFees::settle(dex, pool_token0, pool_token1, provider)?;
+    let balance_before = {
+        let mut outer = dex.pools.setter(pool_token0);
+        let mut pool = outer.setter(pool_token1);
+        pool.reserve0.set(new_reserve0);
+        pool.reserve1.set(new_reserve1);
+        pool.lp_total_supply.set(new_total);
+        if first_deposit {
+            // Lock MINIMUM_LIQUIDITY LP tokens forever to the zero address.
+            pool.lp_balances.setter(Address::ZERO).set(min_lp);
+        }
+        // Mint LP tokens to provider (pool-specific).
+        let current_balance = pool.lp_balances.getter(provider).get();
+        let new_balance = current_balance
+            .checked_add(liquidity)
+            .ok_or_else(|| err(ERR_LIQUIDITY_OVERFLOW))?;
+        pool.lp_balances.setter(provider).set(new_balance);
+        current_balance
+    };
+    track_reserve(dex, pool_token0, reserve0, new_reserve0)?;
+    track_reserve(dex, pool_token1, reserve1, new_reserve1)?;
+    LpReceipts::record_deposit(
+        dex,
+        pool_token0,
+        pool_token1,
+        provider,
+        amount0_c,
+        amount1_c,
+        liquidity,
+        balance_before,
+    )?;
+    if hooked {
+        pull_deposits(dex)?;
+    }
+
+    // LP token Transfer event (mint from zero).
+    emit_lp_transfer(Address::ZERO, provider, liquidity);
+    emit_add_liquidity(provider, used0, used1);
+
+    Ok(liquidity)
+}
+
+/// Canonical (token0, token1) of the pool a `permit_swap_and_zap` zap deposits into: the
+/// path's first and last token. INVALID_PATH if the path is too short or ends where it began.
+pub fn zap_pool(path: &[Address]) -> OakResult<(Address, Address)> {
+    let (first, last) = match (path.first(), path.last()) {
+        (Some(first), Some(last)) if path.len() >= 2 && first != last => (*first, *last),
+        _ => return Err(err(ERR_INVALID_PATH)),
+    };
+    Ok(if first < last { (first, last) } else { (last, first) })
+}
+
+/// LP supply left after `provider` burns `liquidity_share` of its `balance`.
+///
+/// @dev Reverts with INSUFFICIENT_LIQUIDITY when the provider holds less, or when the burn would
+///      leave less than MINIMUM_LIQUIDITY: the locked shares keep the pool from being drained.
+pub fn lp_supply_after_burn(liquidity_share: U256, balance: U256, total_supply: U256) -> OakResult<U256> {
+    if liquidity_share > balance {
+        return Err(err(ERR_INSUFFICIENT_LIQUIDITY));
+    }
+    let new_total = total_supply
+        .checked_sub(liquidity_share)
+        .ok_or_else(|| err(ERR_LIQUIDITY_OVERFLOW))?;
+    if new_total < as_u256(MINIMUM_LIQUIDITY) {
+        return Err(err(ERR_INSUFFICIENT_LIQUIDITY));
+    }
+    Ok(new_total)
+}
+
+/// Revert with LP_SLIPPAGE if either withdrawn amount is below the caller's minimum.
+pub fn check_lp_slippage(amount0: U256, amount1: U256, min_amount0: U256, min_amount1: U256) -> OakResult<()> {
+    if amount0 < min_amount0 || amount1 < min_amount1 {
+        return Err(err(ERR_LP_SLIPPAGE));
+    }
+    Ok(())
+}
+
+/// Core liquidity withdrawal: pro-rata amounts, LP burn, reserve update and token transfer out.
+///
+/// @notice amount0 = liquidity_share * reserve0 / totalSupply (same for token1), floor-rounded
+///         in favour of the pool. The MINIMUM_LIQUIDITY locked at pool creation can never be burned.
+/// @dev Minimums and the returned amounts are in caller token order. Caller must hold the lock.
+fn process_remove_liquidity(
+    dex: &mut OakDEX,
+    provider: Address,
+    token0: Address,
+    token1: Address,
+    liquidity_share: U256,
+    min_amount0: U256,
+    min_amount1: U256,
+) -> OakResult<(U256, U256)> {
+    if liquidity_share.is_zero() {
+        return Err(err(ERR_ZERO_AMOUNT));
+    }
+
+    // Canonical pool key
+    let (pool_token0, pool_token1) = if token0 < token1 {
+        (token0, token1)
+    } else {
+        (token1, token0)
+    };
+    LiquidityLocks::require_unlocked(dex, provider, pool_token0, pool_token1, liquidity_share)?;
+    PoolStats::accrue_reserves(dex, pool_token0, pool_token1)?;
+    LpFees::settle(dex, pool_token0, pool_token1, provider)?;
+    let (amount0_c, amount1_c, balance) = {
+        let mut outer = dex.pools.setter(pool_token0);
+        let mut pool = outer.setter(pool_token1);
+        if !pool.initialized.get() {
+            return Err(err(ERR_INVALID_TOKEN));
+        }
+
+        let total_supply = pool.lp_total_supply.get();
+        if total_supply.is_zero() {
+            return Err(err(ERR_INSUFFICIENT_LIQUIDITY));
+        }
+        let balance = pool.lp_balances.getter(provider).get();
+        let new_total = lp_supply_after_burn(liquidity_share, balance, total_supply)?;
+
+        let reserve0 = pool.reserve0.get();
+        let reserve1 = pool.reserve1.get();
+        let (amount0_c, amount1_c) = compute_liquidity_burned(liquidity_share, reserve0, reserve1, total_supply)?;
+
+        // Effects: burn LP and shrink reserves (canonical order) before any transfer.
+        pool.lp_total_supply.set(new_total);
+        pool.lp_balances.setter(provider).set(balance - liquidity_share);
+        pool.reserve0.set(reserve0.checked_sub(amount0_c).ok_or_else(|| err(ERR_INSUFFICIENT_LIQUIDITY))?);
+        pool.reserve1.set(reserve1.checked_sub(amount1_c).ok_or_else(|| err(ERR_INSUFFICIENT_LIQUIDITY))?);
+        (amount0_c, amount1_c, balance)
+    };
+    track_reserve(dex, pool_token0, amount0_c, U256::ZERO)?;
+    track_reserve(dex, pool_token1, amount1_c, U256::ZERO)?;
+
+    // Map canonical amounts back to caller token order
+    let (amount0, amount1) = if token0 == pool_token0 {
+        (amount0_c, amount1_c)
+    } else {
+        (amount1_c, amount0_c)
+    };
+    check_lp_slippage(amount0, amount1, min_amount0, min_amount1)?;
+
+    LpReceipts::record_withdrawal(dex, pool_token0, pool_token1, provider, liquidity_share, balance)?;
+
+    // Interactions: transfer underlying tokens back to the provider
+    guarded_external_call(dex, token0, token1, || {
+        safe_transfer(token0, provider, amount0)?;
+        safe_transfer(token1, provider, amount1)
+    })?;
+
+    // LP token Transfer event (burn to zero) and withdrawal summary.
+    emit_lp_transfer(provider, Address::ZERO, liquidity_share);
+    emit_remove_liquidity(provider, amount0, amount1, liquidity_share);
+
+    Ok((amount0, amount1))
+}
+
+/// Credit the contract's token surplus to a pool's reserves (Uniswap v2 `sync`).
+///
+/// @notice The contract holds every pool's tokens in one balance, so "matching balances" is per
+///         token: the surplus over everything owed (`FeeReconciliation::position`), e.g. tokens
+///         sent straight to the contract, is credited to this pool's reserves. A deficit leaves
+///         the reserves alone: it cannot be attributed to one pool. Returns the new
+///         (reserve0, reserve1), canonical order.
+/// @dev Caller must hold the lock and ROLE_ADMIN: the surplus may have been sent for any pool
+///      holding the token. Oracle and stats accrue over the old reserves first.
+fn process_sync(dex: &mut OakDEX, token_a: Address, token_b: Address) -> OakResult<(U256, U256)> {
+    require_not_paused(dex)?;
+    let (token0, token1) = if token_a < token_b { (token_a, token_b) } else { (token_b, token_a) };
+    let (reserve0, reserve1) = {
+        let outer = dex.pools.getter(token0);
+        let pool = outer.getter(token1);
+        if token0 == token1 || !pool.initialized.get() {
+            return Err(err(ERR_INVALID_TOKEN));
+        }
+        (pool.reserve0.get(), pool.reserve1.get())
+    };
+    let (_, _, surplus0, _) = FeeReconciliation::position(dex, token0)?;
+    let (_, _, surplus1, _) = FeeReconciliation::position(dex, token1)?;
+    let new_reserve0 = synced_reserve(reserve0, surplus0)?;
+    let new_reserve1 = synced_reserve(reserve1, surplus1)?;
+
+    update_oracle(dex, (token0, token1), reserve0, reserve1)?;
+    PoolStats::accrue_reserves(dex, token0, token1)?;
+    {
+        let mut outer = dex.pools.setter(token0);
+        let mut pool = outer.setter(token1);
+        pool.reserve0.set(new_reserve0);
+        pool.reserve1.set(new_reserve1);
+    }
+    track_reserve(dex, token0, reserve0, new_reserve0)?;
+    track_reserve(dex, token1, reserve1, new_reserve1)?;
+    emit_sync(token0, token1, new_reserve0, new_reserve1);
+    Ok((new_reserve0, new_reserve1))
+}
+
+/// Send the contract's surplus in both tokens of a pool to `to` (Uniswap v2 `skim`).
+///
+/// @notice The surplus is per token across all pools (see `process_sync`), so it includes tokens
+///         sent for any pool sharing a token with this one; nothing owed to LPs, fee buckets or
+///         escrows is touched. Returns (amount0, amount1), canonical order.
+/// @dev Caller must hold the lock and ROLE_ADMIN. No storage changes, so the transfers are the
+///      only effects.
+fn process_skim(dex: &OakDEX, token_a: Address, token_b: Address, to: Address) -> OakResult<(U256, U256)> {
+    if to == Address::ZERO || to == contract::address() {
+        return Err(err(ERR_INVALID_ADDRESS));
+    }
+    let (token0, token1) = if token_a < token_b { (token_a, token_b) } else { (token_b, token_a) };
+    if token0 == token1 || !dex.pools.getter(token0).getter(token1).initialized.get() {
+        return Err(err(ERR_INVALID_TOKEN));
+    }
+    let (_, _, surplus0, _) = FeeReconciliation::position(dex, token0)?;
+    let (_, _, surplus1, _) = FeeReconciliation::position(dex, token1)?;
+    for (token, amount) in [(token0, surplus0), (token1, surplus1)] {
+        if !amount.is_zero() {
+            guarded_external_call(dex, token0, token1, || safe_transfer(token, to, amount))?;
+        }
+    }
+    emit_skim(token0, token1, to, surplus0, surplus1);
+    Ok((surplus0, surplus1))
+}
+
+/// Effective (reveal_delay, max_commitment_age) in blocks: storage overrides or the constants.
+pub(crate) fn commit_reveal_params(dex: &OakDEX) -> (U256, U256) {
+    let delay = dex.commit_reveal_delay_blocks.get();
+    let max_age = dex.max_commitment_age_blocks.get();
+    (
+        if delay.is_zero() { as_u256(COMMIT_REVEAL_DELAY) } else { delay },
+        if max_age.is_zero() { as_u256(MAX_COMMITMENT_AGE) } else { max_age },
+    )
+}
+
+/// Reveal delay (blocks) of a commitment in MEV `tier`, given the configured `reveal_delay`.
+///
+/// @notice FAST commitments are revealable after MEV_TIER_FAST_DELAY blocks; STANDARD and BATCH
+///         wait the configured delay. Never longer than the configured delay.
+pub fn mev_tier_reveal_delay(tier: U256, reveal_delay: U256) -> U256 {
+    if tier == as_u256(MEV_TIER_FAST) {
+        reveal_delay.min(as_u256(MEV_TIER_FAST_DELAY))
+    } else {
+        reveal_delay
+    }
+}
+
+/// Swap fee (bps) charged to a reveal in MEV `tier` on a pool charging `fee_bps`.
+///
+/// @notice FAST pays MEV_TIER_FAST_FEE_BPS of the pool fee (rounded up, capped at MAX_FEE_BPS)
+///         for its shorter exposure window; the other tiers pay the pool fee.
+pub fn mev_tier_fee_bps(tier: U256, fee_bps: U256) -> MathResult<U256> {
+    if tier != as_u256(MEV_TIER_FAST) {
+        return Ok(fee_bps);
+    }
+    let scaled = fee_bps.checked_mul(as_u256(MEV_TIER_FAST_FEE_BPS)).ok_or(ERR_OVERFLOW)?;
+    Ok(rounding::div(scaled, as_u256(BPS), Rounding::Up)?.min(as_u256(MAX_FEE_BPS)).max(fee_bps))
+}
+
+/// Reveal delay (blocks) of `user`'s commitment under its MEV tier.
+fn commitment_reveal_delay(dex: &OakDEX, user: Address) -> U256 {
+    let (reveal_delay, _) = commit_reveal_params(dex);
+    mev_tier_reveal_delay(dex.commitments.getter(user).mev_tier.get(), reveal_delay)
+}
+
+/// True when (reveal_delay, max_age) lie within their MIN/MAX bounds.
+pub fn commit_reveal_params_valid(reveal_delay: U256, max_age: U256) -> bool {
+    reveal_delay >= as_u256(MIN_COMMIT_REVEAL_DELAY)
+        && reveal_delay <= as_u256(MAX_COMMIT_REVEAL_DELAY)
+        && max_age >= as_u256(MIN_COMMITMENT_AGE)
+        && max_age <= as_u256(MAX_COMMITMENT_AGE_LIMIT)
+}
+
+/// Reveal status of a commitment at `current_block`, using the same bounds as `consume_commitment`.
+///
+/// @notice NONE (no active commitment), TOO_EARLY (< reveal_delay blocks old),
+///         READY, or EXPIRED (> max_age blocks old).
+pub fn commitment_status(
+    active: bool,
+    commit_block: U256,
+    current_block: U256,
+    reveal_delay: U256,
+    max_age: U256,
+) -> u8 {
+    if !active {
+        return COMMIT_STATUS_NONE;
+    }
+    let age = current_block.saturating_sub(commit_block);
+    if age > max_age {
+        COMMIT_STATUS_EXPIRED
+    } else if age < reveal_delay {
+        COMMIT_STATUS_TOO_EARLY
+    } else {
+        COMMIT_STATUS_READY
+    }
+}
+
+/// Whether a new commit may replace a commitment in `status`: Ok(false) when there is none,
+/// Ok(true) when it expired (its bond must be slashed first), COMMITMENT_ACTIVE while pending.
+///
+/// @notice A pending commitment (TOO_EARLY or READY) is revealed or cancelled, never
+///         overwritten, so a phishing UI cannot silently void it. Slashing the expired one keeps
+///         re-committing from recovering a bond that `slash_expired_commitment` would take.
+pub fn commitment_replacement(status: u8) -> OakResult<bool> {
+    match status {
+        COMMIT_STATUS_NONE => Ok(false),
+        COMMIT_STATUS_EXPIRED => Ok(true),
+        _ => Err(err(ERR_COMMITMENT_ACTIVE)),
+    }
+}
+
+/// Revert of a reveal against a commitment in `status`: COMMITMENT_EXPIRED past the max age,
+/// TOO_EARLY inside the reveal delay, Ok when READY.
+///
+/// @notice A reverting reveal cannot clean up after itself, so an expired commitment is only
+///         cleared (and its bond slashed) by `slash_expired_commitment` or the owner's next commit.
+pub fn require_revealable(status: u8) -> OakResult<()> {
+    match status {
+        COMMIT_STATUS_READY => Ok(()),
+        COMMIT_STATUS_EXPIRED => Err(err(ERR_COMMITMENT_EXPIRED)),
+        COMMIT_STATUS_TOO_EARLY => Err(err(ERR_TOO_EARLY)),
+        _ => Err(err(ERR_COMMIT_NOT_FOUND)),
+    }
+}
+
+/// (refund, penalty) of a commitment `bond` cancelled by its owner in phase `status`.
+///
+/// @notice Free before the reveal window (TOO_EARLY); COMMIT_LIVE_CANCEL_PENALTY_BPS of the bond
+///         is forfeited inside it (READY). Expired commitments are not owner-cancellable.
+pub fn cancel_bond_split(status: u8, bond: U256) -> MathResult<(U256, U256)> {
+    if status != COMMIT_STATUS_READY {
+        return Ok((bond, U256::ZERO));
+    }
+    let penalty = bond
+        .checked_mul(as_u256(COMMIT_LIVE_CANCEL_PENALTY_BPS))
+        .ok_or(ERR_OVERFLOW)?
+        / as_u256(BPS);
+    Ok((bond - penalty, penalty))
+}
+
+/// Approximate gas for revealing a commitment in `status`, net of the slot-clearing refund.
+///
+/// @notice Only pending commitments (TOO_EARLY / READY) have a reveal cost; NONE and EXPIRED
+///         return 0 (an expired reveal reverts, so its cleanup refund never applies; cancel instead).
+/// @dev Refund capped at 1/5 of gas used (EIP-3529). Escrow mode swaps the outbound transfer
+///      for a claimable-balance credit.
+pub fn estimate_reveal_gas(status: u8, escrow: bool) -> U256 {
+    if status != COMMIT_STATUS_READY && status != COMMIT_STATUS_TOO_EARLY {
+        return U256::ZERO;
+    }
+    let outbound = if escrow { REVEAL_GAS_ESCROW_CREDIT } else { REVEAL_GAS_TOKEN_TRANSFER };
+    let gross = REVEAL_GAS_BASE + REVEAL_GAS_SWAP + REVEAL_GAS_TOKEN_TRANSFER + outbound;
+    let refund = SSTORE_CLEAR_REFUND.min(gross / 5);
+    U256::from(gross - refund)
+}
+
+/// True when `amount_in` exceeds `threshold_bps` of `reserve_in` (a "large" commitment).
+pub fn is_large_commitment(amount_in: U256, reserve_in: U256, threshold_bps: U256) -> bool {
+    match (amount_in.checked_mul(as_u256(BPS)), reserve_in.checked_mul(threshold_bps)) {
+        (Some(scaled_in), Some(limit)) => scaled_in > limit,
+        (None, _) => true,
+        (_, None) => false,
+    }
+}
+
+/// True when the limit price `min_amount_out / amount_in` is at most `band_bps` below `twap_q112`.
+///
+/// @dev Compares `min_amount_out * BPS >= (amount_in * twap / Q112) * (BPS - band)`; floor on the
+///      TWAP side, so the check never rejects a limit exactly at the band edge.
+pub fn limit_within_twap_band(
+    amount_in: U256,
+    min_amount_out: U256,
+    twap_q112: U256,
+    band_bps: U256,
+) -> MathResult<bool> {
+    let bps = as_u256(BPS);
+    let keep = bps.checked_sub(band_bps).ok_or(ERR_INVALID_PRICE_BAND)?;
+    let fair_out = amount_in
+        .checked_mul(twap_q112)
+        .ok_or(ERR_OVERFLOW)?
+        .checked_div(q112_u256())
+        .ok_or(ERR_DIVISION_BY_ZERO)?;
+    let floor = fair_out.checked_mul(keep).ok_or(ERR_OVERFLOW)?;
+    Ok(min_amount_out.checked_mul(bps).ok_or(ERR_OVERFLOW)? >= floor)
+}
+
+/// Reject large commitments whose limit price gives the trader a free option.
+///
+/// @notice Above `large_commit_reserve_bps` of reserve_in, the committed limit
+///         (min_amount_out / amount_in) must lie within `commit_price_band_bps` of the TWAP at
+///         the commit block. Without it a trader can commit with a loose limit and only reveal
+///         if the market moved far in their favour, at LPs' expense.
+fn require_commit_limit_in_band(
+    dex: &OakDEX,
+    token_in: Address,
+    token_out: Address,
+    amount_in: U256,
+    min_amount_out: U256,
+    commit_block: U256,
+) -> OakResult<()> {
+    let threshold_bps = dex.large_commit_reserve_bps.get();
+    if threshold_bps.is_zero() {
+        return Ok(());
+    }
+    let (reserve_in, _) = directional_reserves(dex, token_in, token_out, token_in < token_out)?;
+    if !is_large_commitment(amount_in, reserve_in, threshold_bps) {
+        return Ok(());
+    }
+    let twap = PoolOracle::twap_ending_at(dex, token_in, token_out, commit_block)?;
+    if !limit_within_twap_band(amount_in, min_amount_out, twap, dex.commit_price_band_bps.get())? {
+        return Err(err(ERR_COMMIT_LIMIT_OUTSIDE_BAND));
+    }
+    Ok(())
+}
+
+/// Verify and consume `user`'s commitment against `computed_hash`.
+///
+/// @notice Checks activation, hash match under the commitment's scheme (`witness` is its proof,
+///         see `engine::verification`), expiry (max commitment age) and minimum delay
+///         (reveal delay of its MEV tier), then clears the commitment before any swap logic runs.
+///         A BATCH-tier commitment only settles through a batch auction (`batch`).
+///         The reveal's canonical direction and (maximum) input must stay within any exposure
+///         the commitment declared (`commit_exposure`).
+/// @dev Shared by every reveal variant. Caller must hold the re-entrancy lock. Returns the
+///      commit block.
+#[allow(clippy::too_many_arguments)]
+fn consume_commitment(
+    dex: &mut OakDEX,
+    user: Address,
+    token_a: Address,
+    token_b: Address,
+    zero_for_one: bool,
+    amount_in: U256,
+    computed_hash: FixedBytes<32>,
+    witness: &[u8],
+    batch: bool,
+) -> OakResult<U256> {
+    let (_, max_age) = commit_reveal_params(dex);
+    let reveal_delay = commitment_reveal_delay(dex, user);
+    let pair_key = compute_pair_key(token_a, token_b);
+    // Declared for a retired pool and revealed in its successor: verify against the old key.
+    let anchor = PoolMigration::anchor(dex, dex.commitments.getter(user).pool.get(), pair_key);
+    let mut commitment = dex.commitments.setter(user);
+
+    // Reentrancy protection: check activation, then clear commitment
+    // before performing any external‑effectful logic.
+    let stored_hash_u256 = commitment.hash.get();
+    if stored_hash_u256.is_zero() {
+        return Err(err(ERR_COMMIT_NOT_FOUND));
+    }
+
+    let anchor_key = anchor?;
+    if commitment.mev_tier.get() == as_u256(MEV_TIER_BATCH) && !batch {
+        return Err(err(ERR_INVALID_MEV_TIER));
+    }
+    let scheme = commitment.scheme.get();
+    // Only the EIP-712 scheme needs the domain; skip its hashing for the others.
+    let domain_separator = if scheme == as_u256(COMMIT_SCHEME_EIP712) {
+        compute_domain_separator(contract::address(), CHAIN_ID_ARBITRUM_ONE)
+    } else {
+        FixedBytes::ZERO
+    };
+    let claim = RevealClaim { pair_key: anchor_key, leaf: computed_hash, witness, domain_separator };
+    verify_commitment(scheme, FixedBytes::from(stored_hash_u256.to_be_bytes::<32>()), &claim)?;
+
+    let commit_block = commitment.block.get();
+    let status = commitment_status(true, commit_block, U256::from(block::number()), reveal_delay, max_age);
+    require_revealable(status)?;
+    check_declared(commitment.exposure_in.get(), commitment.exposure_zero_for_one.get(), zero_for_one, amount_in)?;
+
+    // Clear commitment state prior to swap execution (hash == 0 marks it inactive).
+    commitment.hash.set(U256::ZERO);
+
+    CommitExposure::release(dex, user);
+    refund_commit_bond(dex, user)?;
+    Ok(commit_block)
+}
+
+/// Detach the bond held on `user`'s commitment and release it from `commit_bonds_held`.
+///
+/// @dev Returns (token, amount, sponsor); the caller refunds (see `return_commit_bond`) or
+///      slashes it. Amount 0 = no bond held; sponsor zero = the user posted it.
+fn take_commit_bond(dex: &mut OakDEX, user: Address) -> OakResult<(Address, U256, Address)> {
+    let mut commitment = dex.commitments.setter(user);
+    let amount = commitment.bond.get();
+    let token = commitment.bond_token.get();
+    let sponsor = commitment.bond_sponsor.get();
+    if amount.is_zero() {
+        return Ok((token, amount, sponsor));
+    }
+    commitment.bond.set(U256::ZERO);
+    commitment.bond_token.set(Address::ZERO);
+    commitment.bond_sponsor.set(Address::ZERO);
+    let held = dex.commit_bonds_held.get(token);
+    dex.commit_bonds_held.setter(token).set(held.saturating_sub(amount));
+    Ok((token, amount, sponsor))
+}
+
+/// Pay a detached bond back: to `user`, or to the prepaid balance of the `sponsor` that posted it.
+fn return_commit_bond(dex: &mut OakDEX, user: Address, sponsor: Address, token: Address, amount: U256) -> OakResult<()> {
+    if amount.is_zero() {
+        return Ok(());
+    }
+    if sponsor == Address::ZERO {
+        safe_transfer(token, user, amount)
+    } else {
+        CommitSponsorship::credit(dex, sponsor, token, amount)
+    }
+}
+
+/// Return the bond held on `user`'s commitment to whoever posted it (no-op when none is held).
+fn refund_commit_bond(dex: &mut OakDEX, user: Address) -> OakResult<()> {
+    let (token, amount, sponsor) = take_commit_bond(dex, user)?;
+    return_commit_bond(dex, user, sponsor, token, amount)
+}
+
+/// Clear `user`'s expired commitment and send its bond to the treasury; returns the bond slashed.
+///
+/// @dev Never reverts on its own: shared by `slash_expired_commitment` and by commits that
+///      replace an expired commitment. Callers check expiry first.
+fn expire_commitment(dex: &mut OakDEX, user: Address) -> OakResult<U256> {
+    let mut commitment = dex.commitments.setter(user);
+    let stored_hash = commitment.hash.get();
+    let commit_block = commitment.block.get();
+    commitment.hash.set(U256::ZERO);
+    commitment.block.set(U256::ZERO);
+    commitment.priority.set(U256::ZERO);
+    emit_commitment_expired(user, stored_hash, commit_block);
+    CommitExposure::release(dex, user);
+
+    let (token, amount, _) = take_commit_bond(dex, user)?;
+    if !amount.is_zero() {
+        let treasury = dex
+            .treasury_balance
+            .get(token)
+            .checked_add(amount)
+            .ok_or_else(|| err(ERR_OVERFLOW))?;
+        dex.treasury_balance.setter(token).set(treasury);
+        emit_commit_bond_slashed(user, token, amount);
+    }
+    Ok(amount)
+}
+
+/// Cancel `owner`'s pending commitment (see `cancel_commitment` for the phases and bond split).
+fn process_cancel_commitment(dex: &mut OakDEX, owner: Address) -> OakResult<()> {
+    let (_, max_age) = commit_reveal_params(dex);
+    let reveal_delay = commitment_reveal_delay(dex, owner);
+    let current_block = U256::from(block::number());
+
+    let mut commitment = dex.commitments.setter(owner);
+    let stored_hash = commitment.hash.get();
+    let commit_block = commitment.block.get();
+    let phase = commitment_status(!stored_hash.is_zero(), commit_block, current_block, reveal_delay, max_age);
+    match phase {
+        COMMIT_STATUS_NONE => return Err(err(ERR_COMMIT_NOT_FOUND)),
+        COMMIT_STATUS_EXPIRED => return Err(err(ERR_CANCEL_AFTER_EXPIRY)),
+        _ => {}
+    }
+
+    // Clear commitment state
+    commitment.hash.set(U256::ZERO);
+    commitment.block.set(U256::ZERO);
+    commitment.priority.set(U256::ZERO);
+    CommitExposure::release(dex, owner);
+
+    with_lock(dex, |dex| {
+        let (token, bond, sponsor) = take_commit_bond(dex, owner)?;
+        let (refund, penalty) = cancel_bond_split(phase, bond).map_err(err)?;
+        if !penalty.is_zero() {
+            let treasury = dex
+                .treasury_balance
+                .get(token)
+                .checked_add(penalty)
+                .ok_or_else(|| err(ERR_OVERFLOW))?;
+            dex.treasury_balance.setter(token).set(treasury);
+        }
+        return_commit_bond(dex, owner, sponsor, token, refund)?;
+        if phase == COMMIT_STATUS_READY {
+            emit_commitment_cancelled_with_penalty(owner, token, refund, penalty);
+        } else {
+            emit_cancel_commitment(owner, current_block);
+        }
+        Ok(())
+    })
+}
+
+/// Post the configured commitment bond for `user`'s commitment.
+///
+/// @dev Drawn from the prepaid balance of `sponsor` (commit vouchers), or pulled from `payer`
+///      via transferFrom when `sponsor` is zero. Callers clear any replaced commitment first
+///      (`expire_commitment`), so no bond is held here.
+fn post_commit_bond(dex: &mut OakDEX, user: Address, payer: Address, sponsor: Address) -> OakResult<()> {
+    let token = dex.commit_bond_token.get();
+    let amount = dex.commit_bond_amount.get();
+    if amount.is_zero() {
+        return Ok(());
+    }
+    if sponsor == Address::ZERO {
+        safe_transfer_from(token, payer, contract::address(), amount)?;
+    } else {
+        CommitSponsorship::debit(dex, sponsor, token, amount)?;
+    }
+    let total = dex
+        .commit_bonds_held
+        .get(token)
+        .checked_add(amount)
+        .ok_or_else(|| err(ERR_OVERFLOW))?;
+    dex.commit_bonds_held.setter(token).set(total);
+    let mut commitment = dex.commitments.setter(user);
+    commitment.bond.set(amount);
+    commitment.bond_token.set(token);
+    commitment.bond_sponsor.set(sponsor);
+    Ok(())
+}
+
+/// Record `hash` as `user`'s commitment for `pool` (zero = undeclared); any bond is pulled from `payer`.
+///
+/// @dev Shared by `commit_swap` (payer == user), `commit_swap_for` (payer == operator),
+///      `commit_swap_with_voucher` (bond drawn from `sponsor`, zero = none) and
+///      `commit_swap_in_pool`. The pool is bound through the hash either way (see
+///      `compute_pool_bound_hash`); declaring it only makes it readable and fails a wrong-pool
+///      reveal with a clearer error. `referrer` (zero = none) earns the referral share of the
+///      reveal's fee (`commit_swap_with_referrer`). A pending commitment is never replaced (see
+///      `commitment_replacement`); an expired one is first cleared and its bond slashed.
+///      `tier` is the MEV protection tier (`commit_swap_with_tier`, see `mev_tier_reveal_delay`).
+#[allow(clippy::too_many_arguments)]
+fn process_commit_swap(
+    dex: &mut OakDEX,
+    user: Address,
+    payer: Address,
+    sponsor: Address,
+    pool: FixedBytes<32>,
+    hash: FixedBytes<32>,
+    scheme: U256,
+    referrer: Address,
+    tier: U256,
+) -> OakResult<()> {
+    require_not_paused(dex)?;
+
+    if hash == FixedBytes::ZERO {
+        return Err(err(ERR_INVALID_HASH));
+    }
+    if tier >= as_u256(MEV_TIER_COUNT) {
+        return Err(err(ERR_INVALID_MEV_TIER));
+    }
+    if referrer == user {
+        return Err(err(ERR_REFERRAL_SELF));
+    }
+    // Griefing protection: a pending commitment is revealed or cancelled, never overwritten.
+    let (_, max_age) = commit_reveal_params(dex);
+    let reveal_delay = commitment_reveal_delay(dex, user);
+    let status = {
+        let existing = dex.commitments.getter(user);
+        let active = !existing.hash.get().is_zero();
+        commitment_status(active, existing.block.get(), U256::from(block::number()), reveal_delay, max_age)
+    };
+    let expired = commitment_replacement(status)?;
+
+    with_lock(dex, |dex| {
+        PoolMigration::require_accepts_commitments(dex, pool)?;
+        if expired {
+            expire_commitment(dex, user)?;
+        }
+        // Anti-spam bond (if configured); pulled before the commitment is recorded.
+        post_commit_bond(dex, user, payer, sponsor)?;
+
+        let current_block = U256::from(block::number());
+
+        let hash_u256 = U256::from_be_bytes::<32>(hash.into());
+        let sequence = dex.commit_sequence.get();
+        dex.commit_sequence.set(sequence.checked_add(U256::from(1u64)).ok_or_else(|| err(ERR_OVERFLOW))?);
+        let priority = commitment_priority(current_block, sequence).map_err(err)?;
+        let mut commitment = dex.commitments.setter(user);
+        commitment.hash.set(hash_u256);
+        commitment.block.set(current_block);
+        commitment.priority.set(priority);
+        commitment.pool.set(U256::from_be_bytes::<32>(pool.into()));
+        commitment.scheme.set(scheme);
+        commitment.referrer.set(referrer);
+        commitment.mev_tier.set(tier);
+
+        emit_commit_swap(user, hash, current_block);
+        if !tier.is_zero() {
+            emit_commit_mev_tier(user, tier);
+        }
+        Ok(())
+    })
+}
+
+/// Execute a revealed exact-input swap after verifying `computed_hash` against the caller's commitment.
+///
+/// @dev Shared by `reveal_swap` and `reveal_swap_private`, which differ only in the preimage.
+///      Strict CEI: runs under `with_lock`, so the lock is released on every path.
+#[allow(clippy::too_many_arguments)]
+fn process_reveal_swap(
+    dex: &mut OakDEX,
+    token_a: Address,
+    token_b: Address,
+    zero_for_one: bool,
+    amount_in: U256,
+    min_amount_out: U256,
+    deadline: U256,
+    computed_hash: FixedBytes<32>,
+    witness: &[u8],
+) -> OakResult<()> {
+    with_lock(dex, |dex| {
+        // Direction: canonical token0 is the lower address (native ETH, address(0), when present).
+        let (token0, token1) = if token_a < token_b { (token_a, token_b) } else { (token_b, token_a) };
+        let (token_in, token_out) = if zero_for_one { (token0, token1) } else { (token1, token0) };
+
+        // Input sanitization: validate amounts
+        if amount_in.is_zero() {
+            return Err(err(ERR_INSUFFICIENT_INPUT_AMOUNT));
+        }
+        if min_amount_out.is_zero() {
+            return Err(err(ERR_INSUFFICIENT_OUTPUT_AMOUNT));
+        }
+
+        require_not_paused(dex)?;
+        require_not_circuit_breaker(dex)?;
+
+        // Deadline protection: revert if transaction is included after deadline (block number).
+        check_deadline(U256::from(block::number()), deadline)?;
+
+        let sender = msg::sender();
+
+        let commit_block = consume_commitment(dex, sender, token_a, token_b, zero_for_one, amount_in, computed_hash, witness, false)?;
+        require_commit_limit_in_band(dex, token_in, token_out, amount_in, min_amount_out, commit_block)?;
+
+        // Execute the actual swap with invariant checks, slippage protection,
+        // and fee accounting. All math and external calls are performed inside
+        // `process_swap`, which uses fully checked arithmetic and accrues
+        // treasury fees for the admin wallet. In escrow mode the output is
+        // credited to the caller's claimable balance instead of transferred.
+        let reveal = reveal_context(dex, sender);
+        if OutputEscrow::is_enabled(dex, sender) {
+            process_swap_to_escrow(dex, token_in, token_out, amount_in, min_amount_out, reveal)?;
+        } else {
+            process_swap(dex, token_in, token_out, amount_in, min_amount_out, Some(reveal))?;
+        }
+        Ok(())
+    })
+}
+
+/// Public contract functions implementation.
+///
+/// @notice Core entrypoints exposed to external callers.
+/// @dev These methods operate on Stylus storage types defined in `state`.
+///      This block is only compiled for on-chain (wasm32) builds; host
+///      tests use the pure helper functions above instead.
+#[cfg(all(not(test), target_arch = "wasm32"))]
+#[public]
+impl OakDEX {
+    /// Create a new pool for a token pair.
+    ///
     /// @notice Anyone can create a pool, but each canonical pair (token0, token1)
     ///         can only be initialized once.
     pub fn create_pool(&mut self, token_a: Address, token_b: Address) -> OakResult<()> {
-        // Re-entrancy guard
-        lock_reentrancy_guard(self)?;
+        with_lock(self, |dex| process_create_pool(dex, token_a, token_b, false, U256::ZERO))
+    }
 
-        require_non_zero_address(token_a)?;
-        require_non_zero_address(token_b)?;
+    /// Create the (token_a, token_b) pair and return its registry key (see `get_pair`).
+    ///
+    /// @notice Same as `create_pool`; each pair is an independent market with its own reserves,
+    ///         LP shares, oracle, stats and fee configuration.
+    pub fn create_pair(&mut self, token_a: Address, token_b: Address) -> OakResult<FixedBytes<32>> {
+        with_lock(self, |dex| {
+            process_create_pool(dex, token_a, token_b, false, U256::ZERO).map(|()| compute_pair_key(token_a, token_b))
+        })
+    }
+
+    /// Create the (token_a, token_b) pair on a fee tier and return its registry key.
+    ///
+    /// @notice `fee_bps` must be on the owner-managed whitelist (`is_fee_tier_enabled`); by
+    ///         default 5 (stable pairs), 30 and 100 (volatile pairs). The tier is the pool's total
+    ///         swap fee for its lifetime and is split 60/20/20 like the global fee.
+    pub fn create_pair_with_fee(
+        &mut self,
+        token_a: Address,
+        token_b: Address,
+        fee_bps: U256,
+    ) -> OakResult<FixedBytes<32>> {
+        if fee_bps.is_zero() {
+            return Err(err(ERR_FEE_TIER_NOT_ENABLED));
+        }
+        with_lock(self, |dex| {
+            process_create_pool(dex, token_a, token_b, false, fee_bps).map(|()| compute_pair_key(token_a, token_b))
+        })
+    }
+
+    /// Enable or disable a pool fee tier (ROLE_ADMIN only). Existing pools keep their tier.
+    pub fn set_fee_tier(&mut self, fee_bps: U256, enabled: bool) -> OakResult<()> {
+        require_role(self, ROLE_ADMIN)?;
+        if fee_bps.is_zero() || fee_bps > as_u256(MAX_FEE_BPS) {
+            return Err(err(ERR_FEE_TIER_NOT_ENABLED));
+        }
+        AdminAuditLog::record(self, AUDIT_ACTION_SET_FEE_TIER, &[word(fee_bps), bool_word(enabled)])?;
+        self.fee_tier_enabled.setter(fee_bps).set(enabled);
+        emit_fee_tier_set(fee_bps, enabled);
+        Ok(())
+    }
+
+    /// View: whether `fee_bps` is an enabled pool fee tier.
+    pub fn is_fee_tier_enabled(&self, fee_bps: U256) -> bool {
+        self.fee_tier_enabled.get(fee_bps)
+    }
+
+    /// View: base swap fee (bps) of the (token_a, token_b) pool, before volatility adjustment.
+    pub fn get_pool_fee_bps(&self, token_a: Address, token_b: Address) -> U256 {
+        base_fee_bps(self, token_a, token_b)
+    }
+
+    /// View: swap fee (bps) the next trade on (token_a, token_b) is charged (see `fees`).
+    pub fn get_effective_fee_bps(&self, token_a: Address, token_b: Address) -> U256 {
+        pool_fee_bps(self, token_a, token_b)
+    }
+
+    /// ROLE_ADMIN: configure the volatility fee controller (`volatile_bps` 0 disables it).
+    ///
+    /// @param calm_bps Spot-vs-TWAP deviation (bps) at or below which `discount_bps` is taken off.
+    /// @param volatile_bps Deviation (bps) at or above which `surcharge_bps` is added.
+    /// @dev Pools keep their last regime until their next oracle update re-classifies them.
+    pub fn set_volatility_fee_config(
+        &mut self,
+        calm_bps: U256,
+        volatile_bps: U256,
+        surcharge_bps: U256,
+        discount_bps: U256,
+    ) -> OakResult<()> {
+        require_role(self, ROLE_ADMIN)?;
+        AdminAuditLog::record(
+            self,
+            AUDIT_ACTION_SET_VOLATILITY_FEE_CONFIG,
+            &[word(calm_bps), word(volatile_bps), word(surcharge_bps), word(discount_bps)],
+        )?;
+        FeeController::set_config(self, calm_bps, volatile_bps, surcharge_bps, discount_bps)?;
+        emit_volatility_fee_config_set(calm_bps, volatile_bps, surcharge_bps, discount_bps);
+        Ok(())
+    }
+
+    /// View: volatility fee config (calm_bps, volatile_bps, surcharge_bps, discount_bps).
+    pub fn get_volatility_fee_config(&self) -> (U256, U256, U256, U256) {
+        FeeController::config(self)
+    }
+
+    /// ROLE_ADMIN: register `alias` as a representation of `canonical` (e.g. USDC.e -> USDC).
+    ///
+    /// @notice Router paths compare canonical assets (see `aliases`); pools are unaffected.
+    pub fn set_token_alias(&mut self, alias: Address, canonical: Address) -> OakResult<()> {
+        require_role(self, ROLE_ADMIN)?;
+        AdminAuditLog::record(self, AUDIT_ACTION_SET_TOKEN_ALIAS, &[alias.into_word(), canonical.into_word()])?;
+        TokenAliases::set(self, alias, canonical)
+    }
+
+    /// ROLE_ADMIN: drop the alias entry of `alias`.
+    pub fn remove_token_alias(&mut self, alias: Address) -> OakResult<()> {
+        require_role(self, ROLE_ADMIN)?;
+        AdminAuditLog::record(self, AUDIT_ACTION_REMOVE_TOKEN_ALIAS, &[alias.into_word()])?;
+        TokenAliases::remove(self, alias)
+    }
+
+    /// View: canonical token of `token` (itself when it is not an alias).
+    pub fn canonical_token(&self, token: Address) -> Address {
+        TokenAliases::canonical(self, token)
+    }
+
+    /// View: whether `token` is a registered alias.
+    pub fn is_token_alias(&self, token: Address) -> bool {
+        TokenAliases::is_alias(self, token)
+    }
+
+    /// ROLE_ADMIN: set the risk policy of `token` (see `risk`).
+    ///
+    /// @notice `max_pool_exposure` caps the token's reserve in any single pool (0 = uncapped);
+    ///         swaps and deposits past it revert. `oracle_required` tokens only pool once a
+    ///         bootstrap feed is bound to the pair; `hooked` tokens always get hooked pools.
+    ///         Pools created earlier keep their hook setting; the caps apply at once.
+    pub fn set_token_risk(
+        &mut self,
+        token: Address,
+        max_pool_exposure: U256,
+        flash_borrow_allowed: bool,
+        oracle_required: bool,
+        hooked: bool,
+    ) -> OakResult<()> {
+        require_role(self, ROLE_ADMIN)?;
+        let params = [
+            token.into_word(),
+            word(max_pool_exposure),
+            bool_word(flash_borrow_allowed),
+            bool_word(oracle_required),
+            bool_word(hooked),
+        ];
+        AdminAuditLog::record(self, AUDIT_ACTION_SET_TOKEN_RISK, &params)?;
+        TokenRisk::set(self, token, max_pool_exposure, flash_borrow_allowed, oracle_required, hooked);
+        Ok(())
+    }
+
+    /// View: (max_pool_exposure, flash_borrow_allowed, oracle_required, hooked) of `token`.
+    pub fn get_token_risk(&self, token: Address) -> (U256, bool, bool, bool) {
+        TokenRisk::params(self, token)
+    }
+
+    /// View: (regime, volatility_bps) of (token_a, token_b); volatility 0 without a TWAP window.
+    pub fn get_pool_volatility(&self, token_a: Address, token_b: Address) -> OakResult<(U256, U256)> {
+        let (token0, token1) = if token_a < token_b { (token_a, token_b) } else { (token_b, token_a) };
+        let regime = self.pools.getter(token0).getter(token1).fee_regime.get();
+        let volatility = FeeController::volatility(self, token0, token1)?.unwrap_or(U256::ZERO);
+        Ok((regime, volatility))
+    }
+
+    /// View: (pair_key, position, token0, token1, reserve0, reserve1) of (token_a, token_b).
+    ///
+    /// @notice `position` is the creation order (index for `pair_at`); `U256::MAX` and zero
+    ///         reserves when the pair was never created. Tokens in canonical order.
+    pub fn get_pair(
+        &self,
+        token_a: Address,
+        token_b: Address,
+    ) -> OakResult<(FixedBytes<32>, U256, Address, Address, U256, U256)> {
         if token_a == token_b {
-            unlock_reentrancy_guard(self);
             return Err(err(ERR_INVALID_TOKEN));
         }
+        let (token0, token1) = if token_a < token_b { (token_a, token_b) } else { (token_b, token_a) };
+        let position = PairRegistry::position(self, token0, token1).unwrap_or(U256::MAX);
+        let outer = self.pools.getter(token0);
+        let pool = outer.getter(token1);
+        Ok((
+            compute_pair_key(token0, token1),
+            position,
+            token0,
+            token1,
+            pool.reserve0.get(),
+            pool.reserve1.get(),
+        ))
+    }
+
+    /// View: number of pairs created (Uniswap V2 `allPairsLength`).
+    pub fn all_pairs_length(&self) -> U256 {
+        self.pair_count.get()
+    }
+
+    /// View: (token0, token1) of the pair at `position` in creation order (`allPairs`).
+    pub fn pair_at(&self, position: U256) -> OakResult<(Address, Address)> {
+        PairRegistry::at(self, position)
+    }
+
+    /// Create a pool containing a hook-capable token (ERC-777 / ERC-677).
+    ///
+    /// @notice Swaps and deposits in this pool run with strict CEI ordering (all accounting
+    ///         before any transfer, no inline referral payout) and verify the contract balance
+    ///         delta of every transfer.
+    pub fn create_hooked_pool(&mut self, token_a: Address, token_b: Address) -> OakResult<()> {
+        with_lock(self, |dex| process_create_pool(dex, token_a, token_b, true, U256::ZERO))
+    }
+
+    /// True if the (token_a, token_b) pool was created as a hooked-token pool.
+    pub fn is_hooked_pool(&self, token_a: Address, token_b: Address) -> OakResult<bool> {
+        let (token0, token1) = if token_a < token_b {
+            (token_a, token_b)
+        } else {
+            (token_b, token_a)
+        };
+        Ok(self.pools.getter(token0).getter(token1).hooked_token.get())
+    }
 
-        // Canonical ordering
+    /// Hand the (token_a, token_b) pool's manager role to `manager` (0 renounces it).
+    ///
+    /// @notice Pool manager or governance. Governance keeps override rights either way.
+    pub fn set_pool_manager(&mut self, token_a: Address, token_b: Address, manager: Address) -> OakResult<()> {
         let (token0, token1) = if token_a < token_b {
             (token_a, token_b)
         } else {
             (token_b, token_a)
         };
+        require_pool_admin(self, token0, token1)?;
+        AdminAuditLog::record(self, AUDIT_ACTION_SET_POOL_MANAGER, &[token0.into_word(), token1.into_word(), manager.into_word()])?;
+        self.pools.setter(token0).setter(token1).manager.set(manager);
+        emit_pool_manager_set(token0, token1, manager);
+        Ok(())
+    }
 
-        // Access pool storage
+    /// Pause or resume swaps and deposits in one pool (pool manager or governance).
+    ///
+    /// @notice Withdrawals stay open so LPs can always exit.
+    pub fn set_pool_paused(&mut self, token_a: Address, token_b: Address, paused: bool) -> OakResult<()> {
+        let (token0, token1) = if token_a < token_b {
+            (token_a, token_b)
+        } else {
+            (token_b, token_a)
+        };
+        require_pool_admin(self, token0, token1)?;
+        AdminAuditLog::record(self, AUDIT_ACTION_SET_POOL_PAUSED, &[token0.into_word(), token1.into_word(), bool_word(paused)])?;
         let mut outer = self.pools.setter(token0);
         let mut pool = outer.setter(token1);
+        pool.paused.set(paused);
+        let hooked = pool.hooked_token.get();
+        emit_pool_config_changed(token0, token1, paused, hooked, msg::sender());
+        Ok(())
+    }
 
-        if pool.initialized.get() {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_POOL_EXISTS));
-        }
+    /// Bind the (token_a, token_b) pair's first deposit to an external price feed (ROLE_ADMIN only).
+    ///
+    /// @notice The first deposit must price token0 within `tolerance_bps` of `answer * scale`
+    ///         (reserve1/reserve0, Q112) so the TWAP cannot anchor on an absurd opening price.
+    ///         `scale` is in canonical (token0, token1) order. Feed 0 removes the binding.
+    pub fn set_bootstrap_feed(
+        &mut self,
+        token_a: Address,
+        token_b: Address,
+        feed: Address,
+        scale: U256,
+        tolerance_bps: U256,
+    ) -> OakResult<()> {
+        require_role(self, ROLE_ADMIN)?;
+        let (token0, token1) = if token_a < token_b { (token_a, token_b) } else { (token_b, token_a) };
+        let params = [token0.into_word(), token1.into_word(), feed.into_word(), word(scale), word(tolerance_bps)];
+        AdminAuditLog::record(self, AUDIT_ACTION_SET_BOOTSTRAP_FEED, &params)?;
+        PoolOracle::set_bootstrap_feed(self, token0, token1, feed, scale, tolerance_bps)
+    }
 
-        // Initialize empty pool
-        pool.reserve0.set(U256::ZERO);
-        pool.reserve1.set(U256::ZERO);
-        pool.lp_total_supply.set(U256::ZERO);
-        pool.initialized.set(true);
+    /// View: (feed, scale, tolerance_bps) bound to the pair's first deposit (canonical order).
+    pub fn get_bootstrap_feed(&self, token_a: Address, token_b: Address) -> OakResult<(Address, U256, U256)> {
+        let (token0, token1) = if token_a < token_b { (token_a, token_b) } else { (token_b, token_a) };
+        let outer = self.pools.getter(token0);
+        let pool = outer.getter(token1);
+        Ok((pool.bootstrap_feed.get(), pool.bootstrap_feed_scale.get(), pool.bootstrap_tolerance_bps.get()))
+    }
 
-        emit_pool_created(token0, token1);
+    /// Configure the pool creator share (ROLE_ADMIN only): `share_bps` of each swap's treasury fee
+    /// goes to the pool's creator for `period_blocks` after creation.
+    ///
+    /// @notice Applies to pools created afterwards; existing schedules are unchanged. 0 disables.
+    pub fn set_creator_fee_share(&mut self, share_bps: U256, period_blocks: U256) -> OakResult<()> {
+        require_role(self, ROLE_ADMIN)?;
+        AdminAuditLog::record(self, AUDIT_ACTION_SET_CREATOR_FEE_SHARE, &[word(share_bps), word(period_blocks)])?;
+        PoolCreatorShare::set_config(self, share_bps, period_blocks)
+    }
 
-        unlock_reentrancy_guard(self);
+    /// View: creator share for new pools as (share_bps, period_blocks).
+    pub fn get_creator_fee_share(&self) -> OakResult<(U256, U256)> {
+        Ok((self.creator_fee_share_bps.get(), self.creator_fee_share_blocks.get()))
+    }
 
-        Ok(())
+    /// View: (creator, share_bps, until_block) of the (token_a, token_b) pool.
+    pub fn get_pool_creator(&self, token_a: Address, token_b: Address) -> OakResult<(Address, U256, U256)> {
+        let (token0, token1) = if token_a < token_b {
+            (token_a, token_b)
+        } else {
+            (token_b, token_a)
+        };
+        let outer = self.pools.getter(token0);
+        let pool = outer.getter(token1);
+        Ok((pool.creator.get(), pool.creator_share_bps.get(), pool.creator_share_until_block.get()))
     }
-    /// Initialize the contract.
-    ///
-    /// @notice One‑time initializer setting owner, treasury, and default fee.
-    /// @dev Reverts if called more than once or if owner/treasury are zero.
-    pub fn init(&mut self, initial_owner: Address, treasury: Address) -> OakResult<()> {
-        let current_owner = self.owner.get();
-        if current_owner != Address::ZERO {
-            return Err(err(ERR_ALREADY_INITIALIZED));
-        }
 
-        if initial_owner == Address::ZERO {
-            return Err(err(ERR_INVALID_OWNER));
-        }
-        if treasury == Address::ZERO {
-            return Err(err(ERR_INVALID_OWNER));
-        }
-        let contract_addr = contract::address();
-        if treasury == contract_addr {
-            return Err(err(ERR_TREASURY_IS_CONTRACT));
-        }
+    /// View: creator fees of `creator` in `token` not yet claimed.
+    pub fn get_creator_claimable(&self, creator: Address, token: Address) -> OakResult<U256> {
+        Ok(PoolCreatorShare::claimable(self, creator, token))
+    }
 
-        self.owner.set(initial_owner);
-        self.treasury.set(treasury);
+    /// Claim msg.sender's accrued pool creator fees in `token`.
+    pub fn claim_creator_fees(&mut self, token: Address) -> OakResult<U256> {
+        with_lock(self, |dex| PoolCreatorShare::claim(dex, token))
+    }
 
-        // Set initial total fee (0.5%) for the first month after launch.
-        // Governance can later reduce this to `DEFAULT_FEE_BPS` via `set_fee`.
-        self.protocol_fee_bps.set(as_u256(INITIAL_FEE));
+    /// Change the pool's hook configuration (strict CEI + balance-diff checks, see create_hooked_pool).
+    ///
+    /// @notice Pool manager or governance may enable it; only governance may disable it, since a
+    ///         manager switching off protection on a hook-capable token is exactly what to guard against.
+    pub fn set_pool_hooked(&mut self, token_a: Address, token_b: Address, hooked: bool) -> OakResult<()> {
+        let (token0, token1) = if token_a < token_b {
+            (token_a, token_b)
+        } else {
+            (token_b, token_a)
+        };
+        require_pool_admin(self, token0, token1)?;
+        if !hooked {
+            require_role(self, ROLE_ADMIN)?;
+        }
+        TokenRisk::require_hook_allowed(self, token0, token1, hooked)?;
+        AdminAuditLog::record(self, AUDIT_ACTION_SET_POOL_HOOKED, &[token0.into_word(), token1.into_word(), bool_word(hooked)])?;
+        let mut outer = self.pools.setter(token0);
+        let mut pool = outer.setter(token1);
+        pool.hooked_token.set(hooked);
+        let paused = pool.paused.get();
+        emit_pool_config_changed(token0, token1, paused, hooked, msg::sender());
+        Ok(())
+    }
 
-        // Initialize analytics and fee accounting.
-        self.total_volume_token0.set(U256::ZERO);
-        self.total_volume_token1.set(U256::ZERO);
-        self.accrued_treasury_fees_token0.set(U256::ZERO);
-        self.accrued_lp_fees_token0.set(U256::ZERO);
+    /// View: (manager, paused) for the (token_a, token_b) pool.
+    pub fn get_pool_manager(&self, token_a: Address, token_b: Address) -> OakResult<(Address, bool)> {
+        let (token0, token1) = if token_a < token_b {
+            (token_a, token_b)
+        } else {
+            (token_b, token_a)
+        };
+        let outer = self.pools.getter(token0);
+        let pool = outer.getter(token1);
+        Ok((pool.manager.get(), pool.paused.get()))
+    }
 
-        // TWAP oracle and gas-rebate placeholder.
-        self.price0_cumulative_last.set(U256::ZERO);
-        self.price1_cumulative_last.set(U256::ZERO);
-        self.block_timestamp_last.set(U256::ZERO);
-        self.accrued_gas_rebate_token0.set(U256::ZERO);
+    /// ROLE_ADMIN: retire the (token_a, token_b) pool in favour of (successor_a, successor_b).
+    ///
+    /// @notice The old pool takes no new pool-declared commitments and trades for `grace_blocks`
+    ///         more blocks (at least the max commitment age, so every open commitment can still
+    ///         reveal against it); then swaps and deposits revert with POOL_RETIRED, exits stay
+    ///         open. Commitments declared for it may also reveal in the successor. See
+    ///         `pool_migration`.
+    pub fn retire_pool(
+        &mut self,
+        token_a: Address,
+        token_b: Address,
+        successor_a: Address,
+        successor_b: Address,
+        grace_blocks: U256,
+    ) -> OakResult<()> {
+        require_role(self, ROLE_ADMIN)?;
+        let (token0, token1) = if token_a < token_b { (token_a, token_b) } else { (token_b, token_a) };
+        let (successor0, successor1) =
+            if successor_a < successor_b { (successor_a, successor_b) } else { (successor_b, successor_a) };
+        if (token0, token1) == (successor0, successor1)
+            || !self.pools.getter(token0).getter(token1).initialized.get()
+            || !self.pools.getter(successor0).getter(successor1).initialized.get()
+        {
+            return Err(err(ERR_INVALID_TOKEN));
+        }
+        let (_, max_age) = commit_reveal_params(self);
+        if grace_blocks < max_age {
+            return Err(err(ERR_RETIREMENT_GRACE_TOO_SHORT));
+        }
+        let after_block = U256::from(block::number())
+            .checked_add(grace_blocks)
+            .ok_or_else(|| err(ERR_BLOCK_OVERFLOW))?;
+        let (key, successor) = (compute_pair_key(token0, token1), compute_pair_key(successor0, successor1));
+        AdminAuditLog::record(self, AUDIT_ACTION_RETIRE_POOL, &[key, successor, word(after_block)])?;
+        PoolMigration::retire(self, key, successor, after_block)
+    }
 
-        // Contract starts active, unlocked, circuit breaker off.
-        self.paused.set(false);
-        self.locked.set(false);
-        self.circuit_breaker_triggered.set(false);
-        self.buyback_wallet.set(Address::ZERO);
-        self.pending_owner.set(Address::ZERO);
-        self.owner_transfer_after_block.set(U256::ZERO);
-        self.next_position_id.set(U256::ZERO);
+    /// View: (successor pair key, last trading block) of the (token_a, token_b) pool; zeros if active.
+    pub fn get_pool_successor(&self, token_a: Address, token_b: Address) -> (FixedBytes<32>, U256) {
+        let key = compute_pair_key(token_a, token_b);
+        (PoolMigration::successor(self, key), PoolMigration::retired_after(self, key))
+    }
 
-        // Access Control: grant DEFAULT_ADMIN_ROLE and PAUSER_ROLE to initial_owner (multisig).
-        self.roles.setter(default_admin_role()).setter(initial_owner).set(true);
-        self.roles.setter(pauser_role()).setter(initial_owner).set(true);
+    /// Initialize the contract.
+    ///
+    /// @notice One‑time initializer setting owner, treasury, and default fee.
+    /// @dev Reverts if called more than once or if owner/treasury are zero.
+    pub fn init(&mut self, initial_owner: Address, treasury: Address) -> OakResult<()> {
+        process_init(self, initial_owner, treasury)
+    }
 
+    /// Initialize the contract for a single trading pair (instead of `init`).
+    ///
+    /// @notice Binds the canonical (token0, token1): pool creation, swaps, deposits and flash
+    ///         swaps revert with PAIR_NOT_BOUND for any other tokens, so caller-supplied token
+    ///         addresses can never reach another market's reserves. Read back with `get_tokens`.
+    pub fn init_single_pair(
+        &mut self,
+        initial_owner: Address,
+        treasury: Address,
+        token_a: Address,
+        token_b: Address,
+    ) -> OakResult<()> {
+        if token_a == token_b {
+            return Err(err(ERR_INVALID_TOKEN));
+        }
+        process_init(self, initial_owner, treasury)?;
+        let (token0, token1) = if token_a < token_b { (token_a, token_b) } else { (token_b, token_a) };
+        self.tokens_bound.set(true);
+        self.bound_token0.set(token0);
+        self.bound_token1.set(token1);
+        emit_tokens_bound(token0, token1);
         Ok(())
     }
 
+    /// View: (bound, token0, token1); `bound` is false (and the tokens zero) in factory mode.
+    pub fn get_tokens(&self) -> (bool, Address, Address) {
+        (self.tokens_bound.get(), self.bound_token0.get(), self.bound_token1.get())
+    }
+
     /// Update the total protocol fee.
     ///
-    /// @notice Owner‑only function to adjust the global fee (in basis points).
+    /// @notice Owner or FEE_SETTER_ROLE: adjusts the global fee (in basis points).
     /// @dev Upper bound protects users from excessive fees.
     pub fn set_fee(&mut self, new_fee_bps: u16) -> OakResult<()> {
-        let owner = self.owner.get();
-        only_owner(owner)?;
+        require_role(self, ROLE_ADMIN | ROLE_FEE_SETTER)?;
 
         if new_fee_bps as u64 > MAX_FEE_BPS {
             return Err(err(ERR_FEE_TOO_HIGH));
         }
+        AdminAuditLog::record(self, AUDIT_ACTION_SET_FEE, &[word(U256::from(new_fee_bps))])?;
 
         self.protocol_fee_bps.set(U256::from(new_fee_bps));
 
@@ -837,9 +3578,38 @@ impl OakDEX {
         Ok(())
     }
 
+    /// Grant `role` (a mask of `access::ROLE_*` bits, e.g. ROLE_GUARDIAN = 8) to `account`.
+    ///
+    /// @notice Caller must have ROLE_ADMIN. Emits RolesGranted.
+    pub fn grant_role(&mut self, role: U256, account: Address) -> OakResult<()> {
+        access::grant_role(self, role, account)
+    }
+
+    /// Revoke the `role` bits from `account`. Caller must have ROLE_ADMIN. Emits RolesRevoked.
+    pub fn revoke_role(&mut self, role: U256, account: Address) -> OakResult<()> {
+        access::revoke_role(self, role, account)
+    }
+
+    /// View: whether `account` holds every bit of `role`.
+    pub fn has_role(&self, role: U256, account: Address) -> OakResult<bool> {
+        Ok(access::has_role(self, role, account))
+    }
+
+    /// View: `account`'s role bitmask.
+    pub fn get_roles(&self, account: Address) -> U256 {
+        roles_of(self, account)
+    }
+
+    /// Move `account`'s grants from the legacy per-role map into its bitmask; returns the bits moved.
+    ///
+    /// @notice For deployments initialized before role bitmasks; anyone may call it.
+    pub fn migrate_legacy_roles(&mut self, account: Address) -> U256 {
+        access::migrate_legacy_roles(self, account)
+    }
+
     /// Pause trading in case of emergency.
     ///
-    /// @notice Caller must have PAUSER_ROLE (e.g. multisig). Disables swaps and commits.
+    /// @notice Caller must have PAUSER_ROLE (e.g. multisig) or GUARDIAN_ROLE. Disables swaps and commits.
     /// @dev Uses Pausable trait; CEI: state update before any external.
     pub fn pause(&mut self) -> OakResult<()> {
         Pausable::pause(self).map_err(|e| e)
@@ -852,154 +3622,643 @@ impl OakDEX {
         Pausable::unpause(self).map_err(|e| e)
     }
 
+    /// Inner commitment hash for `reveal_swap`; commit `compute_pool_commitment` of it.
+    ///
+    /// @notice Pure helper for integrators: derive the hash on-chain or check that an
+    ///         off-chain implementation matches
+    ///         (`keccak256(abi.encode(amount_in, zero_for_one, deadline, salt))`).
+    ///         `deadline` must equal the value later passed to `reveal_swap`.
+    pub fn compute_commitment(amount_in: U256, zero_for_one: bool, deadline: U256, salt: U256) -> FixedBytes<32> {
+        compute_commit_hash(amount_in, zero_for_one, deadline, salt)
+    }
+
+    /// The hash to commit for a reveal against the (token_a, token_b) pool: `inner` (from
+    /// `compute_commitment` or any other `compute_*_commitment`) bound to the pool's pair key.
+    ///
+    /// @notice `keccak256(abi.encodePacked(keccak256(abi.encode(token0, token1)), inner))`.
+    pub fn compute_pool_commitment(token_a: Address, token_b: Address, inner: FixedBytes<32>) -> FixedBytes<32> {
+        compute_pool_bound_hash(compute_pair_key(token_a, token_b), inner)
+    }
+
+    /// Commitment hash for `reveal_swap_private` (see `compute_private_commit_hash`).
+    pub fn compute_private_commitment(
+        amount_in: U256,
+        zero_for_one: bool,
+        deadline: U256,
+        salt: U256,
+        private_hash: FixedBytes<32>,
+    ) -> FixedBytes<32> {
+        compute_private_commit_hash(amount_in, zero_for_one, deadline, salt, private_hash)
+    }
+
+    /// Check a disclosed (app_id, order_tag, inner_salt) against the `private_hash` of a private reveal.
+    ///
+    /// @notice For selective disclosure to auditors or counterparties after the fact.
+    pub fn verify_private_fields(
+        private_hash: FixedBytes<32>,
+        app_id: U256,
+        order_tag: U256,
+        inner_salt: U256,
+    ) -> bool {
+        compute_private_fields_hash(app_id, order_tag, inner_salt) == private_hash
+    }
+
+    /// Commitment hash for `reveal_swap_and_add_liquidity` (see `compute_bundle_commit_hash`).
+    pub fn compute_bundle_commitment(amount_in: U256, swap_amount: U256, salt: U256) -> FixedBytes<32> {
+        compute_bundle_commit_hash(amount_in, swap_amount, salt)
+    }
+
+    /// Commitment hash for `reveal_swap_exact_out` (see `compute_exact_out_commit_hash`).
+    pub fn compute_exact_out_commitment(
+        amount_out: U256,
+        max_amount_in: U256,
+        zero_for_one: bool,
+        salt: U256,
+    ) -> FixedBytes<32> {
+        compute_exact_out_commit_hash(amount_out, max_amount_in, zero_for_one, salt)
+    }
+
     /// Create a swap commitment.
     ///
     /// @notice Stores a commitment hash and the current block number.
     /// @dev Part 1 of the commit‑reveal flow used for MEV resistance.
     pub fn commit_swap(&mut self, hash: FixedBytes<32>) -> OakResult<()> {
-        require_not_paused(self)?;
+        let sender = msg::sender();
+        process_commit_swap(self, sender, sender, Address::ZERO, FixedBytes::ZERO, hash, as_u256(COMMIT_SCHEME_HASH), Address::ZERO, as_u256(MEV_TIER_STANDARD))
+    }
+
+    /// Create a swap commitment whose reveal credits `referrer` (e.g. the integrating aggregator).
+    ///
+    /// @notice On reveal, the referral share (`get_referral_bps`) of the treasury fee accrues to
+    ///         `referrer`, claimable with claim_referral_fees. Zero behaves like `commit_swap`.
+    pub fn commit_swap_with_referrer(&mut self, hash: FixedBytes<32>, referrer: Address) -> OakResult<()> {
+        let sender = msg::sender();
+        process_commit_swap(self, sender, sender, Address::ZERO, FixedBytes::ZERO, hash, as_u256(COMMIT_SCHEME_HASH), referrer, as_u256(MEV_TIER_STANDARD))
+    }
+
+    /// Commit `hash` on behalf of `beneficiary` (smart-contract wallets, routers).
+    ///
+    /// @notice Caller must be an approved commit operator of `beneficiary`. The commitment is
+    ///         stored under `beneficiary`, so only `beneficiary` can reveal it. Any commitment
+    ///         bond is paid by the caller and refunded to `beneficiary` with the commitment.
+    pub fn commit_swap_for(&mut self, beneficiary: Address, hash: FixedBytes<32>) -> OakResult<()> {
+        let operator = msg::sender();
+        check_commit_operator(operator, beneficiary, self.commit_operators.getter(beneficiary).get(operator))?;
+        process_commit_swap(self, beneficiary, operator, Address::ZERO, FixedBytes::ZERO, hash, as_u256(COMMIT_SCHEME_HASH), Address::ZERO, as_u256(MEV_TIER_STANDARD))
+    }
+
+    /// Create a swap commitment whose bond is paid by the sponsor of voucher `voucher_id`.
+    ///
+    /// @notice Spends one use of the voucher; the commit bond is drawn from the sponsor's
+    ///         prepaid balance (`get_sponsor_balance`) and returns there on reveal or cancel.
+    pub fn commit_swap_with_voucher(&mut self, hash: FixedBytes<32>, voucher_id: U256) -> OakResult<()> {
+        let sender = msg::sender();
+        let sponsor = CommitSponsorship::redeem(self, voucher_id, sender)?;
+        process_commit_swap(self, sender, sender, sponsor, FixedBytes::ZERO, hash, as_u256(COMMIT_SCHEME_HASH), Address::ZERO, as_u256(MEV_TIER_STANDARD))
+    }
 
+    /// Create a swap commitment under MEV protection `tier` (MEV_TIER_*).
+    ///
+    /// @notice Trades latency against protection: STANDARD (0) waits the configured reveal delay
+    ///         at the pool fee; FAST (1) is revealable after MEV_TIER_FAST_DELAY block and pays
+    ///         MEV_TIER_FAST_FEE_BPS of the pool fee; BATCH (2) waits the configured delay and can
+    ///         only be revealed into a batch auction (`reveal_swap_to_batch`), where it clears at
+    ///         the batch's uniform price. The tier is stored with the commitment and enforced at reveal.
+    pub fn commit_swap_with_tier(&mut self, hash: FixedBytes<32>, tier: U256) -> OakResult<()> {
         let sender = msg::sender();
+        process_commit_swap(self, sender, sender, Address::ZERO, FixedBytes::ZERO, hash, as_u256(COMMIT_SCHEME_HASH), Address::ZERO, tier)
+    }
 
-        if hash == FixedBytes::ZERO {
-            return Err(err(ERR_INVALID_HASH));
+    /// View: MEV protection tier (MEV_TIER_*) of `user`'s active commitment; 0 if none is active.
+    pub fn get_commitment_tier(&self, user: Address) -> OakResult<U256> {
+        let commitment = self.commitments.getter(user);
+        if commitment.hash.get().is_zero() {
+            return Ok(U256::ZERO);
         }
+        Ok(commitment.mev_tier.get())
+    }
 
-        let current_block = U256::from(block::number());
+    /// Create a swap commitment declared for the (token_a, token_b) pool.
+    ///
+    /// @notice Same as `commit_swap`, but the pool is also stored with the commitment
+    ///         (`get_commitment_pool`) and a reveal against any other pool fails with
+    ///         COMMIT_WRONG_POOL before the hash is even checked.
+    pub fn commit_swap_in_pool(&mut self, token_a: Address, token_b: Address, hash: FixedBytes<32>) -> OakResult<()> {
+        if token_a == token_b {
+            return Err(err(ERR_INVALID_TOKEN));
+        }
+        let sender = msg::sender();
+        process_commit_swap(
+            self,
+            sender,
+            sender,
+            Address::ZERO,
+            compute_pair_key(token_a, token_b),
+            hash,
+            as_u256(COMMIT_SCHEME_HASH),
+            Address::ZERO,
+            as_u256(MEV_TIER_STANDARD),
+        )
+    }
 
-        let hash_u256 = U256::from_be_bytes::<32>(hash.into());
-        self.commitment_hashes.setter(sender).set(hash_u256);
-        self.commitment_timestamps.setter(sender).set(current_block);
-        self.commitment_activated.setter(sender).set(true);
+    /// Create a swap commitment for the (token_a, token_b) pool that declares its worst-case flow.
+    ///
+    /// @notice Like `commit_swap_in_pool`, plus the direction (`zero_for_one` in canonical order)
+    ///         and an upper bound on the input. The reveal must swap that way and at most
+    ///         `max_amount_in` (EXCEEDS_DECLARED_EXPOSURE otherwise); until then the bound counts
+    ///         towards the pool's `get_pending_exposure`. Reveals only the bound, not the trade.
+    pub fn commit_swap_with_exposure(
+        &mut self,
+        token_a: Address,
+        token_b: Address,
+        zero_for_one: bool,
+        max_amount_in: U256,
+        hash: FixedBytes<32>,
+    ) -> OakResult<()> {
+        if token_a == token_b {
+            return Err(err(ERR_INVALID_TOKEN));
+        }
+        if max_amount_in.is_zero() {
+            return Err(err(ERR_INSUFFICIENT_INPUT_AMOUNT));
+        }
+        let sender = msg::sender();
+        let pool = compute_pair_key(token_a, token_b);
+        process_commit_swap(
+            self,
+            sender,
+            sender,
+            Address::ZERO,
+            pool,
+            hash,
+            as_u256(COMMIT_SCHEME_HASH),
+            Address::ZERO,
+            as_u256(MEV_TIER_STANDARD),
+        )?;
+        CommitExposure::declare(self, sender, pool, zero_for_one, max_amount_in)
+    }
+
+    /// View: worst-case input of the (token_a, token_b) pool's pending declared commitments as
+    /// (amount0_in, amount1_in, count), canonical order.
+    ///
+    /// @notice Sums the bounds declared with `commit_swap_with_exposure` that are not yet revealed,
+    ///         cancelled or cleared; expired ones count until slashed, undeclared ones never.
+    pub fn get_pending_exposure(&self, token_a: Address, token_b: Address) -> (U256, U256, U256) {
+        CommitExposure::pending(self, compute_pair_key(token_a, token_b))
+    }
+
+    /// Create a commitment for the (token_a, token_b) pool verified under `scheme`.
+    ///
+    /// @notice Schemes other than the plain hash (COMMIT_SCHEME_*) must be enabled for the pool
+    ///         (`get_pool_commit_schemes`). `hash` is what that scheme stores: an EIP-712 digest
+    ///         (`compute_eip712_commitment`), a Merkle root over pool-bound leaves, or an
+    ///         encrypted-payload commitment. Reveal with `reveal_swap` or `reveal_swap_with_witness`.
+    pub fn commit_swap_with_scheme(
+        &mut self,
+        token_a: Address,
+        token_b: Address,
+        hash: FixedBytes<32>,
+        scheme: U256,
+    ) -> OakResult<()> {
+        if token_a == token_b {
+            return Err(err(ERR_INVALID_TOKEN));
+        }
+        let (token0, token1) = if token_a < token_b { (token_a, token_b) } else { (token_b, token_a) };
+        if !scheme_enabled(self.pools.getter(token0).getter(token1).commit_schemes.get(), scheme) {
+            return Err(err(ERR_UNKNOWN_COMMIT_SCHEME));
+        }
+        let sender = msg::sender();
+        process_commit_swap(self, sender, sender, Address::ZERO, compute_pair_key(token0, token1), hash, scheme, Address::ZERO, as_u256(MEV_TIER_STANDARD))
+    }
+
+    /// Enable commitment schemes for (token_a, token_b) as a bitmask of `1 << scheme` (pool admin).
+    ///
+    /// @notice The plain hash scheme is always accepted. Active commitments keep the scheme they
+    ///         were made under.
+    pub fn set_pool_commit_schemes(&mut self, token_a: Address, token_b: Address, schemes: U256) -> OakResult<()> {
+        let (token0, token1) = if token_a < token_b { (token_a, token_b) } else { (token_b, token_a) };
+        require_pool_admin(self, token0, token1)?;
+        if schemes >= U256::from(1u64) << COMMIT_SCHEME_COUNT as usize {
+            return Err(err(ERR_UNKNOWN_COMMIT_SCHEME));
+        }
+        AdminAuditLog::record(
+            self,
+            AUDIT_ACTION_SET_POOL_COMMIT_SCHEMES,
+            &[token0.into_word(), token1.into_word(), word(schemes)],
+        )?;
+        self.pools.setter(token0).setter(token1).commit_schemes.set(schemes);
+        emit_pool_commit_schemes_set(token0, token1, schemes);
+        Ok(())
+    }
+
+    /// View: bitmask of commitment schemes (besides the plain hash) enabled for (token_a, token_b).
+    pub fn get_pool_commit_schemes(&self, token_a: Address, token_b: Address) -> U256 {
+        let (token0, token1) = if token_a < token_b { (token_a, token_b) } else { (token_b, token_a) };
+        self.pools.getter(token0).getter(token1).commit_schemes.get()
+    }
+
+    /// View: scheme `user`'s active commitment is verified under (0 when none).
+    pub fn get_commitment_scheme(&self, user: Address) -> U256 {
+        let commitment = self.commitments.getter(user);
+        if commitment.hash.get().is_zero() {
+            return U256::ZERO;
+        }
+        commitment.scheme.get()
+    }
+
+    /// EIP-712 commitment of reveal preimage hash `leaf` (`compute_commitment`) for (token_a, token_b).
+    pub fn compute_eip712_commitment(&self, token_a: Address, token_b: Address, leaf: FixedBytes<32>) -> FixedBytes<32> {
+        let domain_separator = compute_domain_separator(contract::address(), CHAIN_ID_ARBITRUM_ONE);
+        compute_typed_commitment(compute_pair_key(token_a, token_b), leaf, domain_separator)
+    }
 
-        emit_commit_swap(sender, hash, current_block);
+    /// View: pair key `user`'s active commitment was declared for (zero when undeclared or none).
+    pub fn get_commitment_pool(&self, user: Address) -> OakResult<FixedBytes<32>> {
+        let commitment = self.commitments.getter(user);
+        if commitment.hash.get().is_zero() {
+            return Ok(FixedBytes::ZERO);
+        }
+        Ok(FixedBytes::from(commitment.pool.get().to_be_bytes::<32>()))
+    }
 
+    /// Approve or revoke `operator` to commit swaps on msg.sender's behalf.
+    pub fn set_commit_operator(&mut self, operator: Address, approved: bool) -> OakResult<()> {
+        if operator == Address::ZERO {
+            return Err(err(ERR_INVALID_ADDRESS));
+        }
+        let user = msg::sender();
+        self.commit_operators.setter(user).setter(operator).set(approved);
+        emit_commit_operator_set(user, operator, approved);
         Ok(())
     }
 
+    /// View: whether `operator` may commit on `user`'s behalf.
+    pub fn is_commit_operator(&self, user: Address, operator: Address) -> OakResult<bool> {
+        Ok(self.commit_operators.getter(user).get(operator))
+    }
+
+    /// View: `user`'s commitment as (hash, commit_block, activated).
+    pub fn get_commitment(&self, user: Address) -> OakResult<(FixedBytes<32>, U256, bool)> {
+        let commitment = self.commitments.getter(user);
+        let hash = commitment.hash.get();
+        Ok((FixedBytes::from(hash.to_be_bytes::<32>()), commitment.block.get(), !hash.is_zero()))
+    }
+
+    /// View: fill priority of `user`'s active commitment, `(commit_block << 128) | sequence`.
+    ///
+    /// @notice Lower fills first when a batch settlement is liquidity-limited. 0 if none is active.
+    pub fn get_commitment_priority(&self, user: Address) -> OakResult<U256> {
+        let commitment = self.commitments.getter(user);
+        if commitment.hash.get().is_zero() {
+            return Ok(U256::ZERO);
+        }
+        Ok(commitment.priority.get())
+    }
+
+    /// View: whether `user` can reveal now, as a `COMMIT_STATUS_*` code
+    /// (0 NONE, 1 TOO_EARLY, 2 READY, 3 EXPIRED).
+    pub fn can_reveal(&self, user: Address) -> OakResult<u8> {
+        let (_, max_age) = commit_reveal_params(self);
+        let reveal_delay = commitment_reveal_delay(self, user);
+        let commitment = self.commitments.getter(user);
+        let active = !commitment.hash.get().is_zero();
+        Ok(commitment_status(active, commitment.block.get(), U256::from(block::number()), reveal_delay, max_age))
+    }
+
+    /// View: approximate gas for msg.sender's pending reveal, plus its `COMMIT_STATUS_*` code.
+    ///
+    /// @notice Lets wallets pre-fund the reveal; 0 when there is nothing to reveal. Multiply by
+    ///         the ink price (10_000 ink per gas by default) for an ink figure.
+    pub fn estimate_reveal_cost(&self) -> OakResult<(U256, u8)> {
+        let user = msg::sender();
+        let (_, max_age) = commit_reveal_params(self);
+        let reveal_delay = commitment_reveal_delay(self, user);
+        let commitment = self.commitments.getter(user);
+        let active = !commitment.hash.get().is_zero();
+        let status =
+            commitment_status(active, commitment.block.get(), U256::from(block::number()), reveal_delay, max_age);
+        Ok((estimate_reveal_gas(status, OutputEscrow::is_enabled(self, user)), status))
+    }
+
     /// Reveal a previously committed swap and execute it.
     ///
     /// @notice Performs hash verification, time‑lock enforcement, fee
     ///         accounting, CPMM pricing, strict slippage and deadline checks, and token transfers.
     /// @dev Part 2 of commit‑reveal flow, providing strong MEV protection.
     ///      Reverts with DeadlineExpired if block number > deadline, SlippageExceeded if output < min_amount_out.
-    ///      Strict CEI: Lock acquired at start, released at end.
+    ///      Strict CEI under `with_lock`.
     ///
     /// # Arguments
-    /// * `token0` - Address of token0 (input token)
-    /// * `token1` - Address of token1 (output token)
+    /// * `token_a` - One token of the pool (order does not matter)
+    /// * `token_b` - The other token of the pool
+    /// * `zero_for_one` - True to sell the lower-address token, false to sell the higher one
     /// * `amount_in` - Input token amount
     /// * `salt` - Random salt used in commitment
     /// * `min_amount_out` - Minimum output tokens (strict slippage protection)
     /// * `deadline` - Block number after which the transaction must revert (deadline protection)
+    #[allow(clippy::too_many_arguments)]
     pub fn reveal_swap(
         &mut self,
-        token0: Address,
-        token1: Address,
+        token_a: Address,
+        token_b: Address,
+        zero_for_one: bool,
         amount_in: U256,
         salt: U256,
         min_amount_out: U256,
         deadline: U256,
     ) -> OakResult<()> {
-        // CRITICAL: Re-entrancy guard acquired at the VERY BEGINNING
-        lock_reentrancy_guard(self)?;
-
-        // Input sanitization: validate addresses
-        require_non_zero_address(token0)?;
-        require_non_zero_address(token1)?;
-
-        // Input sanitization: validate amounts
-        if amount_in.is_zero() {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_INSUFFICIENT_INPUT_AMOUNT));
-        }
-        if min_amount_out.is_zero() {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_INSUFFICIENT_OUTPUT_AMOUNT));
-        }
-
-        require_not_paused(self)?;
-        require_not_circuit_breaker(self)?;
-
-        // Deadline protection: revert if transaction is included after deadline (block number).
-        let current_block = U256::from(block::number());
-        if current_block > deadline {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_DEADLINE_EXPIRED));
-        }
+        // The deadline is part of the preimage, so it is the user's committed choice.
+        let computed_hash = compute_commit_hash(amount_in, zero_for_one, deadline, salt);
+        process_reveal_swap(self, token_a, token_b, zero_for_one, amount_in, min_amount_out, deadline, computed_hash, &[])
+    }
 
-        let sender = msg::sender();
+    /// Reveal a swap whose commitment scheme needs a proof (Merkle proof, encrypted payload).
+    ///
+    /// @notice Same preimage and execution as `reveal_swap`; `witness` is handed to the verifier
+    ///         of the commitment's scheme (see `engine::verification`).
+    #[allow(clippy::too_many_arguments)]
+    pub fn reveal_swap_with_witness(
+        &mut self,
+        token_a: Address,
+        token_b: Address,
+        zero_for_one: bool,
+        amount_in: U256,
+        salt: U256,
+        min_amount_out: U256,
+        deadline: U256,
+        witness: Bytes,
+    ) -> OakResult<()> {
+        let computed_hash = compute_commit_hash(amount_in, zero_for_one, deadline, salt);
+        process_reveal_swap(
+            self,
+            token_a,
+            token_b,
+            zero_for_one,
+            amount_in,
+            min_amount_out,
+            deadline,
+            computed_hash,
+            &witness,
+        )
+    }
 
-        // Reentrancy protection: check activation, then clear commitment
-        // before performing any external‑effectful logic.
-        let is_activated = self.commitment_activated.setter(sender).get();
-        if !is_activated {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_COMMIT_NOT_FOUND));
-        }
+    /// Reveal a swap committed with `compute_private_commit_hash`.
+    ///
+    /// @notice Same execution as `reveal_swap`, but strategy fields (app id, internal order tag)
+    ///         stay committed inside `private_hash` and never appear in calldata. Emits
+    ///         PrivateSwapRevealed(user, private_hash) so a later disclosure can be matched.
+    #[allow(clippy::too_many_arguments)]
+    pub fn reveal_swap_private(
+        &mut self,
+        token_a: Address,
+        token_b: Address,
+        zero_for_one: bool,
+        amount_in: U256,
+        salt: U256,
+        min_amount_out: U256,
+        deadline: U256,
+        private_hash: FixedBytes<32>,
+    ) -> OakResult<()> {
+        let computed_hash = compute_private_commit_hash(amount_in, zero_for_one, deadline, salt, private_hash);
+        process_reveal_swap(self, token_a, token_b, zero_for_one, amount_in, min_amount_out, deadline, computed_hash, &[])?;
+        emit_private_swap_revealed(msg::sender(), private_hash);
+        Ok(())
+    }
 
-        let stored_hash_u256 = self.commitment_hashes.setter(sender).get();
-        if stored_hash_u256.is_zero() {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_COMMIT_NOT_FOUND));
+    /// Reveal a committed swap selling native ETH (msg.value) for `token`.
+    ///
+    /// @notice Same commitment as `reveal_swap` with zero_for_one = true (ETH is always token0).
+    ///         msg.value must equal `amount_in`.
+    #[payable]
+    pub fn reveal_swap_eth_in(
+        &mut self,
+        token: Address,
+        amount_in: U256,
+        salt: U256,
+        min_amount_out: U256,
+        deadline: U256,
+    ) -> OakResult<()> {
+        if msg::value() != amount_in {
+            return Err(err(ERR_NATIVE_VALUE_MISMATCH));
         }
+        self.reveal_swap(NATIVE_TOKEN, token, true, amount_in, salt, min_amount_out, deadline)
+    }
 
-        let computed_hash = compute_commit_hash(amount_in, salt);
-        let computed_hash_u256 = U256::from_be_bytes::<32>(computed_hash.into());
-
-        if stored_hash_u256 != computed_hash_u256 {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_INVALID_HASH));
-        }
+    /// Reveal a committed swap selling `token` for native ETH.
+    ///
+    /// @notice Same commitment as `reveal_swap` with zero_for_one = false. The ETH is sent with
+    ///         safe_transfer_eth, or credited to the claimable balance in escrow mode.
+    pub fn reveal_swap_eth_out(
+        &mut self,
+        token: Address,
+        amount_in: U256,
+        salt: U256,
+        min_amount_out: U256,
+        deadline: U256,
+    ) -> OakResult<()> {
+        self.reveal_swap(token, NATIVE_TOKEN, false, amount_in, salt, min_amount_out, deadline)
+    }
 
-        let commit_block = self.commitment_timestamps.setter(sender).get();
-        // current_block already set above for deadline check
+    /// Reveal a committed swap into the pool's current batch auction instead of executing it.
+    ///
+    /// @notice Same commitment and arguments as `reveal_swap`. The input is taken now; after the
+    ///         window closes `settle_batch` fills every order of the batch at one uniform price.
+    ///         Output and any unfilled input are credited to the caller's claimable balance.
+    /// @return The batch id (block at which the batch window closes).
+    #[allow(clippy::too_many_arguments)]
+    pub fn reveal_swap_to_batch(
+        &mut self,
+        token_a: Address,
+        token_b: Address,
+        zero_for_one: bool,
+        amount_in: U256,
+        salt: U256,
+        min_amount_out: U256,
+        deadline: U256,
+    ) -> OakResult<U256> {
+        with_lock(self, |dex| {
+            require_non_zero_address(token_a)?;
+            require_non_zero_address(token_b)?;
+            let (token0, token1) = if token_a < token_b { (token_a, token_b) } else { (token_b, token_a) };
+            let (token_in, token_out) = if zero_for_one { (token0, token1) } else { (token1, token0) };
+            if amount_in.is_zero() {
+                return Err(err(ERR_INSUFFICIENT_INPUT_AMOUNT));
+            }
+            if min_amount_out.is_zero() {
+                return Err(err(ERR_INSUFFICIENT_OUTPUT_AMOUNT));
+            }
+            require_not_paused(dex)?;
+            check_deadline(U256::from(block::number()), deadline)?;
+
+            let sender = msg::sender();
+            let computed_hash = compute_commit_hash(amount_in, zero_for_one, deadline, salt);
+            let commit_block = consume_commitment(dex, sender, token0, token1, zero_for_one, amount_in, computed_hash, &[], true)?;
+            require_commit_limit_in_band(dex, token_in, token_out, amount_in, min_amount_out, commit_block)?;
+            let priority = dex.commitments.getter(sender).priority.get();
+            BatchAuction::enqueue(dex, sender, token0, token1, zero_for_one, amount_in, min_amount_out, priority)
+        })
+    }
 
-        // Check commitment expiration (prevent storage bloat)
-        let max_block = commit_block
-            .checked_add(as_u256(MAX_COMMITMENT_AGE))
-            .ok_or_else(|| err(ERR_BLOCK_OVERFLOW))?;
+    /// Settle a closed batch auction at its uniform clearing price (anyone may call).
+    ///
+    /// @notice Crosses opposite flows, routes only the net flow through the pool as one swap and
+    ///         fills everyone at the same price; see `auction` for the clearing rule.
+    /// @return Clearing price as (price_num, price_den), token1 per token0.
+    pub fn settle_batch(&mut self, token_a: Address, token_b: Address, batch_id: U256) -> OakResult<(U256, U256)> {
+        let (token0, token1) = if token_a < token_b { (token_a, token_b) } else { (token_b, token_a) };
+        with_lock(self, |dex| BatchAuction::settle(dex, token0, token1, batch_id))
+    }
 
-        if current_block > max_block {
-            // Commitment expired, clear it and return error
-            self.commitment_activated.setter(sender).set(false);
-            self.commitment_hashes.setter(sender).set(U256::ZERO);
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_COMMITMENT_EXPIRED));
-        }
+    /// Take back the input of an order whose batch was not settled in time.
+    pub fn reclaim_batch_order(&mut self, token_a: Address, token_b: Address, batch_id: U256, index: U256) -> OakResult<U256> {
+        let (token0, token1) = if token_a < token_b { (token_a, token_b) } else { (token_b, token_a) };
+        with_lock(self, |dex| BatchAuction::reclaim(dex, token0, token1, batch_id, index))
+    }
 
-        // Check minimum delay (MEV protection)
-        let min_block = commit_block
-            .checked_add(as_u256(COMMIT_REVEAL_DELAY))
-            .ok_or_else(|| err(ERR_BLOCK_OVERFLOW))?;
+    /// View: (order count, settled) of batch `batch_id` of the (token_a, token_b) pool.
+    pub fn get_batch(&self, token_a: Address, token_b: Address, batch_id: U256) -> OakResult<(U256, bool)> {
+        let (token0, token1) = if token_a < token_b { (token_a, token_b) } else { (token_b, token_a) };
+        Ok(BatchAuction::info(self, token0, token1, batch_id))
+    }
 
-        if current_block < min_block {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_TOO_EARLY));
-        }
+    /// Set the batch auction window in blocks (ROLE_ADMIN only; 0 turns batch mode off).
+    pub fn set_batch_window(&mut self, blocks: U256) -> OakResult<()> {
+        require_role(self, ROLE_ADMIN)?;
+        AdminAuditLog::record(self, AUDIT_ACTION_SET_BATCH_WINDOW, &[word(blocks)])?;
+        BatchAuction::set_window(self, blocks)
+    }
 
-        // Clear commitment state prior to swap execution.
-        self.commitment_activated.setter(sender).set(false);
-        self.commitment_hashes.setter(sender).set(U256::ZERO);
+    /// View: batch auction window in blocks (0 = off).
+    pub fn get_batch_window(&self) -> OakResult<U256> {
+        Ok(self.batch_window_blocks.get())
+    }
 
-        // Execute the actual swap with invariant checks, slippage protection,
-        // and fee accounting. All math and external calls are performed inside
-        // `process_swap`, which uses fully checked arithmetic and accrues
-        // treasury fees for the admin wallet.
-        let result = process_swap(self, token0, token1, amount_in, min_amount_out);
-        let amount_out = match result {
-            Ok(v) => v,
-            Err(e) => {
-                unlock_reentrancy_guard(self);
-                return Err(e);
+    /// Reveal a committed exact-output swap: receive `amount_out`, paying at most `max_amount_in`.
+    ///
+    /// @notice For market makers targeting precise output sizes. The input is derived at reveal
+    ///         time with `get_amount_in_with_fee` (rounded up), so the trader pays only what the
+    ///         current reserves require.
+    /// @dev Commitment is `compute_exact_out_commit_hash`; the direction is bound through
+    ///      `zero_for_one = token0 < token1`. Reverts with SLIPPAGE_EXCEEDED if the required
+    ///      input exceeds `max_amount_in`. Output goes to escrow when the caller opted in.
+    ///
+    /// # Arguments
+    /// * `token0` - Input token
+    /// * `token1` - Output token
+    /// * `amount_out` - Exact output amount
+    /// * `max_amount_in` - Maximum input the caller accepts (slippage)
+    /// * `salt` - Random salt used in commitment
+    pub fn reveal_swap_exact_out(
+        &mut self,
+        token0: Address,
+        token1: Address,
+        amount_out: U256,
+        max_amount_in: U256,
+        salt: U256,
+    ) -> OakResult<U256> {
+        with_lock(self, |dex| {
+            require_non_zero_address(token0)?;
+            require_non_zero_address(token1)?;
+            if amount_out.is_zero() {
+                return Err(err(ERR_INSUFFICIENT_OUTPUT_AMOUNT));
+            }
+            if max_amount_in.is_zero() {
+                return Err(err(ERR_INSUFFICIENT_INPUT_AMOUNT));
+            }
+            require_not_paused(dex)?;
+            require_not_circuit_breaker(dex)?;
+
+            let sender = msg::sender();
+            let zero_for_one = token0 < token1;
+            let computed_hash = compute_exact_out_commit_hash(amount_out, max_amount_in, zero_for_one, salt);
+            let commit_block =
+                consume_commitment(dex, sender, token0, token1, zero_for_one, max_amount_in, computed_hash, &[], false)?;
+            require_commit_limit_in_band(dex, token0, token1, max_amount_in, amount_out, commit_block)?;
+
+            let reveal = reveal_context(dex, sender);
+            let (reserve_in, reserve_out) = directional_reserves(dex, token0, token1, zero_for_one)?;
+            let fee_bps = reveal_fee_bps(dex, token0, token1, Some(reveal))?;
+            let amount_in = get_amount_in_with_fee(amount_out, reserve_in, reserve_out, fee_bps)?;
+            if amount_in > max_amount_in {
+                return Err(err(ERR_SLIPPAGE_EXCEEDED));
             }
-        };
 
-        // CRITICAL: Release re-entrancy guard at the VERY END
-        // This must be the last operation before return
-        unlock_reentrancy_guard(self);
+            if OutputEscrow::is_enabled(dex, sender) {
+                process_swap_to_escrow(dex, token0, token1, amount_in, amount_out, reveal)?;
+            } else {
+                process_swap(dex, token0, token1, amount_in, amount_out, Some(reveal))?;
+            }
+            Ok(amount_in)
+        })
+    }
 
-        Ok(())
+    /// Reveal a swap+LP bundle: swap part of the input, then deposit the rest plus proceeds as liquidity.
+    ///
+    /// @notice "Enter LP from one token" done MEV-safely: the split is hidden behind the
+    ///         commitment (`compute_bundle_commit_hash`) and both legs execute atomically.
+    /// @dev Swap output stays in the contract and is deposited directly (no round trip to the user).
+    ///
+    /// # Arguments
+    /// * `token0` - Input token (provided in full by the caller)
+    /// * `token1` - Pair token (obtained through the swap leg)
+    /// * `amount_in` - Total token0 committed
+    /// * `swap_amount` - Part of `amount_in` swapped to token1 (must be < amount_in)
+    /// * `salt` - Random salt used in commitment
+    /// * `min_swap_out` - Minimum token1 from the swap leg (slippage)
+    /// * `amount0_min` / `amount1_min` - Minimum amounts deposited (LP slippage)
+    /// * `deadline` - Block number after which the transaction must revert
+    #[allow(clippy::too_many_arguments)]
+    pub fn reveal_swap_and_add_liquidity(
+        &mut self,
+        token0: Address,
+        token1: Address,
+        amount_in: U256,
+        swap_amount: U256,
+        salt: U256,
+        min_swap_out: U256,
+        amount0_min: U256,
+        amount1_min: U256,
+        deadline: U256,
+    ) -> OakResult<U256> {
+        with_lock(self, |dex| {
+            let deposit0 = bundle_deposit_amount(amount_in, swap_amount)?;
+            if min_swap_out.is_zero() {
+                return Err(err(ERR_INSUFFICIENT_OUTPUT_AMOUNT));
+            }
+            check_deadline(U256::from(block::number()), deadline)?;
+
+            let sender = msg::sender();
+            let computed_hash = compute_bundle_commit_hash(amount_in, swap_amount, salt);
+            consume_commitment(dex, sender, token0, token1, token0 < token1, swap_amount, computed_hash, &[], false)?;
+
+            let contract_addr = contract::address();
+            let before = swap_reserves(dex, token0, token1);
+            let fee_bps = reveal_fee_bps(dex, token0, token1, Some(reveal_context(dex, sender)))?;
+            let swap_out = process_swap_from_to_with_fee(
+                dex,
+                sender,
+                contract_addr,
+                token0,
+                token1,
+                swap_amount,
+                min_swap_out,
+                fee_bps,
+            )?;
+            emit_reveal(dex, sender, token0, token1, swap_amount, swap_out, fee_bps, before)?;
+
+            let liquidity = process_add_liquidity(
+                dex,
+                sender,
+                token0,
+                token1,
+                deposit0,
+                swap_out,
+                amount0_min,
+                amount1_min,
+                (false, true),
+            )?;
+            emit_liquidity_bundle_revealed(sender, amount_in, swap_amount, swap_out, liquidity);
+            Ok(liquidity)
+        })
     }
 
     /// Execute a swap on behalf of `owner` using EIP-712 permit (gasless flow).
@@ -1019,71 +4278,58 @@ impl OakDEX {
         r: FixedBytes<32>,
         s: FixedBytes<32>,
     ) -> OakResult<()> {
-        lock_reentrancy_guard(self)?;
-
-        require_non_zero_address(owner)?;
-        require_non_zero_address(token_in)?;
-        require_non_zero_address(token_out)?;
-        if amount_in.is_zero() {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_INSUFFICIENT_INPUT_AMOUNT));
-        }
-        if min_amount_out.is_zero() {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_INSUFFICIENT_OUTPUT_AMOUNT));
-        }
-
-        require_not_paused(self)?;
-        require_not_circuit_breaker(self)?;
-
-        let current_block = U256::from(block::number());
-        if current_block > deadline {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_PERMIT_EXPIRED));
-        }
-
-        let current_nonce = self.permit_swap_nonce.setter(owner).get();
-        if nonce != current_nonce {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_PERMIT_NONCE));
-        }
-        self.permit_swap_nonce.setter(owner).set(
-            current_nonce.checked_add(U256::from(1u64)).ok_or_else(|| err(ERR_OVERFLOW))?,
-        );
-
-        let contract_addr = contract::address();
-        let domain_separator = compute_domain_separator(contract_addr, CHAIN_ID_ARBITRUM_ONE);
-        let digest = compute_permit_swap_digest(
-            owner,
-            token_in,
-            token_out,
-            amount_in,
-            min_amount_out,
-            deadline,
-            nonce,
-            &domain_separator,
-        );
-        let recovered = ecrecover_recover(digest, v, r.0, s.0);
-        if recovered != owner {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_PERMIT_INVALID_SIGNATURE));
-        }
+        with_lock(self, |dex| {
+            require_non_zero_address(owner)?;
+            require_non_zero_address(token_in)?;
+            require_non_zero_address(token_out)?;
+            if amount_in.is_zero() {
+                return Err(err(ERR_INSUFFICIENT_INPUT_AMOUNT));
+            }
+            if min_amount_out.is_zero() {
+                return Err(err(ERR_INSUFFICIENT_OUTPUT_AMOUNT));
+            }
 
-        let amount_out = process_swap_from_to(
-            self,
-            owner,
-            owner,
-            token_in,
-            token_out,
-            amount_in,
-            min_amount_out,
-        )?;
-        let (_effective_in, treasury_fee, lp_fee, _buyback_fee) =
-            compute_fee_split(amount_in, self.protocol_fee_bps.get())?;
-        emit_reveal_swap(owner, amount_in, amount_out, treasury_fee, lp_fee);
+            require_not_paused(dex)?;
+            require_not_circuit_breaker(dex)?;
+
+            let current_nonce = dex.permit_swap_nonce.setter(owner).get();
+            check_permit(U256::from(block::number()), deadline, nonce, current_nonce)?;
+            dex.permit_swap_nonce.setter(owner).set(
+                current_nonce.checked_add(U256::from(1u64)).ok_or_else(|| err(ERR_OVERFLOW))?,
+            );
+
+            let contract_addr = contract::address();
+            let domain_separator = compute_domain_separator(contract_addr, CHAIN_ID_ARBITRUM_ONE);
+            let digest = compute_permit_swap_digest(
+                owner,
+                token_in,
+                token_out,
+                amount_in,
+                min_amount_out,
+                deadline,
+                nonce,
+                &domain_separator,
+            );
+            let recovered = ecrecover_recover(digest, v, r.0, s.0);
+            if recovered != owner {
+                return Err(err(ERR_PERMIT_INVALID_SIGNATURE));
+            }
 
-        unlock_reentrancy_guard(self);
-        Ok(())
+            let before = swap_reserves(dex, token_in, token_out);
+            let amount_out = process_swap_from_to(
+                dex,
+                owner,
+                owner,
+                token_in,
+                token_out,
+                amount_in,
+                min_amount_out,
+            )?;
+            let fee_bps = pool_fee_bps(dex, token_in, token_out);
+            emit_reveal(dex, owner, token_in, token_out, amount_in, amount_out, fee_bps, before)?;
+
+            Ok(())
+        })
     }
 
     /// Returns the current permit-swap nonce for `owner` (for EIP-712 gasless flow).
@@ -1093,10 +4339,11 @@ impl OakDEX {
 
     /// Add liquidity to the pool.
     ///
-    /// @notice Adds token0 and token1 to the reserves, enforcing minimum liquidity.
-    /// @dev In a full implementation, this would also mint LP tokens.
-    ///      Transfers tokens from caller to contract before updating reserves.
-    ///      Strict CEI: Lock acquired at start, released at end.
+    /// @notice Adds token0 and token1 to the reserves at the current pool ratio, enforcing
+    ///         minimum liquidity. `amount0` / `amount1` are desired maxima: only the ratio-preserving
+    ///         part is pulled (see `quote_add_liquidity`), the rest is never taken.
+    /// @dev Transfers tokens from caller to contract before updating reserves.
+    ///      Strict CEI under `with_lock`.
     ///
     /// # Arguments
     /// * `token0` - Address of token0
@@ -1105,6 +4352,8 @@ impl OakDEX {
     /// * `amount1` - Amount of token1 to add
     /// * `amount0_min` - Minimum amount0 to accept (LP slippage protection)
     /// * `amount1_min` - Minimum amount1 to accept (LP slippage protection)
+    /// * `deadline` - Block timestamp after which the transaction must revert (DEADLINE_EXPIRED)
+    #[allow(clippy::too_many_arguments)]
     pub fn add_liquidity(
         &mut self,
         token0: Address,
@@ -1113,349 +4362,396 @@ impl OakDEX {
         amount1: U256,
         amount0_min: U256,
         amount1_min: U256,
+        deadline: U256,
     ) -> OakResult<()> {
-        // CRITICAL: Re-entrancy guard acquired at the VERY BEGINNING
-        // This must be the first state-modifying operation
-        lock_reentrancy_guard(self)?;
-
-        // Input sanitization: validate addresses
-        require_non_zero_address(token0)?;
-        require_non_zero_address(token1)?;
+        check_deadline(U256::from(block::timestamp()), deadline)?;
+        let provider = msg::sender();
+        with_lock(self, |dex| {
+            process_add_liquidity(
+                dex,
+                provider,
+                token0,
+                token1,
+                amount0,
+                amount1,
+                amount0_min,
+                amount1_min,
+                (false, false),
+            )
+            .map(|_| ())
+        })
+    }
 
-        // Input sanitization: validate amounts
-        if amount0.is_zero() {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_AMOUNT0_ZERO));
-        }
-        if amount1.is_zero() {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_AMOUNT1_ZERO));
+    /// Add liquidity to the native ETH / `token` pool, paying the ETH side with msg.value.
+    ///
+    /// @notice msg.value is the desired ETH amount: the part not needed at the pool ratio is
+    ///         refunded. Only the used part of `amount_token` is pulled via transferFrom.
+    ///         Reverts with DEADLINE_EXPIRED once the block timestamp passes `deadline`.
+    #[payable]
+    pub fn add_liquidity_eth(
+        &mut self,
+        token: Address,
+        amount_token: U256,
+        amount_token_min: U256,
+        amount_eth_min: U256,
+        deadline: U256,
+    ) -> OakResult<()> {
+        if token == NATIVE_TOKEN {
+            return Err(err(ERR_INVALID_TOKEN));
         }
+        check_deadline(U256::from(block::timestamp()), deadline)?;
+        // The whole msg.value is the prefunded ETH side; claim it so nothing else can spend it.
+        take_native_value(msg::value())?;
+        let provider = msg::sender();
+        with_lock(self, |dex| {
+            process_add_liquidity(
+                dex,
+                provider,
+                NATIVE_TOKEN,
+                token,
+                msg::value(),
+                amount_token,
+                amount_eth_min,
+                amount_token_min,
+                (true, false),
+            )
+            .map(|_| ())
+        })
+    }
 
-        require_not_paused(self)?;
-        require_not_circuit_breaker(self)?;
-
-        // Canonicalize token ordering for pool key.
+    /// View: amounts `add_liquidity` would actually deposit for the desired pair (caller token order).
+    ///
+    /// @notice Empty pools take the desired amounts as-is (they set the initial price).
+    pub fn quote_add_liquidity(
+        &self,
+        token0: Address,
+        token1: Address,
+        amount0_desired: U256,
+        amount1_desired: U256,
+    ) -> OakResult<(U256, U256)> {
         let (pool_token0, pool_token1) = if token0 < token1 {
             (token0, token1)
         } else {
             (token1, token0)
         };
-        let mut outer = self.pools.setter(pool_token0);
-        let mut pool = outer.setter(pool_token1);
+        let outer = self.pools.getter(pool_token0);
+        let pool = outer.getter(pool_token1);
         if !pool.initialized.get() {
-            unlock_reentrancy_guard(self);
             return Err(err(ERR_INVALID_TOKEN));
         }
-
-        // Map provided amounts into canonical order.
-        let (amount0_c, amount1_c) = if token0 == pool_token0 {
-            (amount0, amount1)
+        let (reserve0, reserve1) = (pool.reserve0.get(), pool.reserve1.get());
+        if token0 == pool_token0 {
+            Ok(optimal_liquidity_amounts(amount0_desired, amount1_desired, reserve0, reserve1)?)
         } else {
-            (amount1, amount0)
-        };
-
-        // LP slippage protection (bank-grade: never accept below user minimum).
-        if amount0_c < amount0_min || amount1_c < amount1_min {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_LP_SLIPPAGE));
+            let (used1, used0) = optimal_liquidity_amounts(amount1_desired, amount0_desired, reserve0, reserve1)?;
+            Ok((used0, used1))
         }
+    }
 
-        let reserve0 = pool.reserve0.get();
-        let reserve1 = pool.reserve1.get();
-        let total_supply = pool.lp_total_supply.get();
+    /// Remove liquidity from the pool.
+    ///
+    /// @notice Burns `liquidity_share` LP tokens and returns the underlying tokens
+    ///         to the provider in proportion to their share of total supply.
+    ///         Reverts with DEADLINE_EXPIRED once the block timestamp passes `deadline`, so a withdrawal
+    ///         stuck in the mempool cannot execute at a stale ratio.
+    /// @dev Uses the standard Uniswap V2 pro‑rata formula (see `process_remove_liquidity`).
+    ///      `min_amount0` / `min_amount1` follow the caller's token order.
+    pub fn remove_liquidity(
+        &mut self,
+        token0: Address,
+        token1: Address,
+        liquidity_share: U256,
+        min_amount0: U256,
+        min_amount1: U256,
+        deadline: U256,
+    ) -> OakResult<()> {
+        check_deadline(U256::from(block::timestamp()), deadline)?;
+        let provider = msg::sender();
+        with_lock(self, |dex| {
+            // address(0) is native ETH; its share is paid out with safe_transfer_eth.
+            require_not_paused(dex)?;
+            process_remove_liquidity(dex, provider, token0, token1, liquidity_share, min_amount0, min_amount1).map(|_| ())
+        })
+    }
 
-        // Compute LP tokens to mint, following Uniswap V2 semantics.
-        // First liquidity: liquidity = sqrt(amount0 * amount1) - MINIMUM_LIQUIDITY
-        // Subsequent: min(amount0 * totalSupply / reserve0, amount1 * totalSupply / reserve1)
-        let liquidity = if total_supply.is_zero() {
-            let product = amount0_c
-                .checked_mul(amount1_c)
-                .ok_or_else(|| {
-                    unlock_reentrancy_guard(self);
-                    err(ERR_LIQUIDITY_OVERFLOW)
-                })?;
-            let sqrt = u256_sqrt(product);
-            let min_lp = as_u256(MINIMUM_LIQUIDITY);
+    /// Lock `amount` of the caller's LP shares in (token_a, token_b) until `unlock_block`.
+    ///
+    /// @notice Locked shares cannot be withdrawn before `unlock_block`; their swap fees stay
+    ///         claimable (`claim_lp_fees`) and pool growth harvestable (`harvest_locked_fees`). Locks can be topped up or extended, never
+    ///         shortened. Emits LiquidityLocked with the total locked amount.
+    pub fn lock_liquidity(&mut self, token_a: Address, token_b: Address, amount: U256, unlock_block: U256) -> OakResult<()> {
+        let (token0, token1) = if token_a < token_b { (token_a, token_b) } else { (token_b, token_a) };
+        LiquidityLocks::lock(self, msg::sender(), token0, token1, amount, unlock_block)
+    }
 
-            if sqrt <= min_lp {
-                unlock_reentrancy_guard(self);
-                return Err(err(ERR_INSUFFICIENT_LIQUIDITY));
-            }
+    /// Withdraw the swap fees earned by the caller's locked LP shares; the principal stays locked.
+    ///
+    /// @dev Burns the fee-growth fraction of the lock (see `locks`). Minimums in caller token order.
+    pub fn harvest_locked_fees(
+        &mut self,
+        token_a: Address,
+        token_b: Address,
+        min_amount0: U256,
+        min_amount1: U256,
+    ) -> OakResult<(U256, U256)> {
+        with_lock(self, |dex| {
+            let provider = msg::sender();
+            let (token0, token1) = if token_a < token_b { (token_a, token_b) } else { (token_b, token_a) };
+            let fee_shares = LiquidityLocks::take_fee_shares(dex, provider, token0, token1)?;
+            process_remove_liquidity(dex, provider, token_a, token_b, fee_shares, min_amount0, min_amount1)
+        })
+    }
 
-            // Lock MINIMUM_LIQUIDITY LP tokens forever to the zero address.
-            pool.lp_total_supply.set(min_lp);
-            pool.lp_balances.setter(Address::ZERO).set(min_lp);
+    /// Claim the caller's LP fees in both tokens of (token_a, token_b); returns (amount0, amount1).
+    ///
+    /// @notice Pro-rata to the caller's LP balance while it was held, locked shares included
+    ///         (see `lp_fees`). Amounts in canonical token order; address(0) pays native ETH.
+    pub fn claim_lp_fees(&mut self, token_a: Address, token_b: Address) -> OakResult<(U256, U256)> {
+        let (token0, token1) = if token_a < token_b { (token_a, token_b) } else { (token_b, token_a) };
+        with_lock(self, |dex| LpFees::claim(dex, token0, token1, msg::sender()))
+    }
 
-            sqrt.checked_sub(min_lp).ok_or_else(|| {
-                unlock_reentrancy_guard(self);
-                err(ERR_LIQUIDITY_OVERFLOW)
-            })?
-        } else {
-            // amount0 * totalSupply / reserve0
-            let liquidity0 = amount0
-                .checked_mul(total_supply)
-                .ok_or_else(|| {
-                    unlock_reentrancy_guard(self);
-                    err(ERR_LIQUIDITY_OVERFLOW)
-                })?
-                .checked_div(reserve0)
-                .ok_or_else(|| {
-                    unlock_reentrancy_guard(self);
-                    err(ERR_DIVISION_BY_ZERO)
-                })?;
+    /// View: LP fees `owner` can claim from (token_a, token_b) as (amount0, amount1), canonical order.
+    pub fn pending_lp_fees(&self, owner: Address, token_a: Address, token_b: Address) -> OakResult<(U256, U256)> {
+        let (token0, token1) = if token_a < token_b { (token_a, token_b) } else { (token_b, token_a) };
+        LpFees::pending(self, token0, token1, owner)
+    }
 
-            let liquidity1 = amount1
-                .checked_mul(total_supply)
-                .ok_or_else(|| {
-                    unlock_reentrancy_guard(self);
-                    err(ERR_LIQUIDITY_OVERFLOW)
-                })?
-                .checked_div(reserve1)
-                .ok_or_else(|| {
-                    unlock_reentrancy_guard(self);
-                    err(ERR_DIVISION_BY_ZERO)
-                })?;
+    /// Bound governance's access to the unclaimable LP-fee surplus (ROLE_ADMIN only).
+    ///
+    /// @notice A queued sweep must wait `min_age_epochs` (>= 1) block epochs on top of the grace
+    ///         period, and takes at most `max_sweep_bps` of the surplus, once per epoch; 0 disables
+    ///         sweeping.
+    pub fn set_lp_surplus_policy(&mut self, min_age_epochs: U256, max_sweep_bps: U256) -> OakResult<()> {
+        require_role(self, ROLE_ADMIN)?;
+        if min_age_epochs.is_zero() || max_sweep_bps > as_u256(BPS) {
+            return Err(err(ERR_INVALID_LP_SURPLUS_POLICY));
+        }
+        AdminAuditLog::record(self, AUDIT_ACTION_SET_LP_SURPLUS_POLICY, &[word(min_age_epochs), word(max_sweep_bps)])?;
+        self.lp_surplus_min_age_epochs.set(min_age_epochs);
+        self.lp_surplus_max_sweep_bps.set(max_sweep_bps);
+        emit_lp_surplus_policy_set(min_age_epochs, max_sweep_bps);
+        Ok(())
+    }
 
-            let liq = if liquidity0 < liquidity1 {
-                liquidity0
-            } else {
-                liquidity1
-            };
+    /// Queue a sweep of the LP fees earned by the pool's locked address(0) shares (ROLE_ADMIN only).
+    ///
+    /// @notice Nobody can claim those fees. The queued amounts (capped by the surplus policy) move
+    ///         to the treasury balance via `execute_lp_surplus_sweep` after the grace period and
+    ///         minimum epoch age; returns (amount0, amount1) in canonical order.
+    pub fn queue_lp_surplus_sweep(&mut self, token_a: Address, token_b: Address) -> OakResult<(U256, U256)> {
+        require_role(self, ROLE_ADMIN)?;
+        let (token0, token1) = if token_a < token_b { (token_a, token_b) } else { (token_b, token_a) };
+        AdminAuditLog::record(self, AUDIT_ACTION_QUEUE_LP_SURPLUS_SWEEP, &[token0.into_word(), token1.into_word()])?;
+        LpFees::queue_surplus_sweep(self, token0, token1)
+    }
 
-            if liq.is_zero() {
-                unlock_reentrancy_guard(self);
-                return Err(err(ERR_INSUFFICIENT_LIQUIDITY));
-            }
+    /// Execute the pool's queued LP-fee surplus sweep into the treasury balance (ROLE_ADMIN only).
+    pub fn execute_lp_surplus_sweep(&mut self, token_a: Address, token_b: Address) -> OakResult<(U256, U256)> {
+        require_role(self, ROLE_ADMIN)?;
+        let (token0, token1) = if token_a < token_b { (token_a, token_b) } else { (token_b, token_a) };
+        AdminAuditLog::record(self, AUDIT_ACTION_EXECUTE_LP_SURPLUS_SWEEP, &[token0.into_word(), token1.into_word()])?;
+        LpFees::execute_surplus_sweep(self, token0, token1)
+    }
 
-            liq
-        };
+    /// Drop the pool's queued LP-fee surplus sweep (ROLE_ADMIN only).
+    pub fn cancel_lp_surplus_sweep(&mut self, token_a: Address, token_b: Address) -> OakResult<()> {
+        require_role(self, ROLE_ADMIN)?;
+        let (token0, token1) = if token_a < token_b { (token_a, token_b) } else { (token_b, token_a) };
+        AdminAuditLog::record(self, AUDIT_ACTION_CANCEL_LP_SURPLUS_SWEEP, &[token0.into_word(), token1.into_word()])?;
+        LpFees::cancel_surplus_sweep(self, token0, token1)
+    }
 
-        // Transfer tokens from caller to contract before updating state.
-        let provider = msg::sender();
-        let contract_addr = contract::address();
-        if let Err(e) = safe_transfer_from(token0, provider, contract_addr, amount0) {
-            unlock_reentrancy_guard(self);
-            return Err(e);
-        }
-        if let Err(e) = safe_transfer_from(token1, provider, contract_addr, amount1) {
-            unlock_reentrancy_guard(self);
-            return Err(e);
-        }
-
-        // Update reserves after successful transfer (canonical order).
-        let new_reserve0 = reserve0
-            .checked_add(amount0_c)
-            .ok_or_else(|| {
-                unlock_reentrancy_guard(self);
-                err(ERR_RESERVE0_OVERFLOW)
-            })?;
-        let new_reserve1 = reserve1
-            .checked_add(amount1_c)
-            .ok_or_else(|| {
-                unlock_reentrancy_guard(self);
-                err(ERR_RESERVE1_OVERFLOW)
-            })?;
+    /// View: (surplus0, surplus1, queued0, queued1, after_block; 0 = none queued) for (token_a, token_b).
+    pub fn get_lp_surplus(&self, token_a: Address, token_b: Address) -> OakResult<(U256, U256, U256, U256, U256)> {
+        let (token0, token1) = if token_a < token_b { (token_a, token_b) } else { (token_b, token_a) };
+        let (surplus0, surplus1) = LpFees::surplus(self, token0, token1)?;
+        let outer = self.pools.getter(token0);
+        let pool = outer.getter(token1);
+        Ok((
+            surplus0,
+            surplus1,
+            pool.lp_surplus_queued0.get(),
+            pool.lp_surplus_queued1.get(),
+            pool.lp_surplus_sweep_after_block.get(),
+        ))
+    }
 
-        pool.reserve0.set(new_reserve0);
-        pool.reserve1.set(new_reserve1);
+    /// Wrap `amount` of the caller's free LP shares in (token_a, token_b) as ERC-1155 position
+    /// tokens of lock tier `tier`; returns the token id.
+    ///
+    /// @notice Tier 0 is liquid; tier k > 0 unwraps from block k * POSITION_TIER_BLOCKS and must
+    ///         still be in the future. Wrapped shares keep earning LP fees but cannot be
+    ///         withdrawn until unwrapped (see `positions`).
+    pub fn wrap_position(&mut self, token_a: Address, token_b: Address, amount: U256, tier: U256) -> OakResult<U256> {
+        let (token0, token1) = if token_a < token_b { (token_a, token_b) } else { (token_b, token_a) };
+        PositionTokens::wrap(self, msg::sender(), token0, token1, amount, tier)
+    }
 
-        // Mint LP tokens to provider (pool-specific).
-        let current_total = pool.lp_total_supply.get();
-        let new_total = current_total
-            .checked_add(liquidity)
-            .ok_or_else(|| {
-                unlock_reentrancy_guard(self);
-                err(ERR_LIQUIDITY_OVERFLOW)
-            })?;
-        pool.lp_total_supply.set(new_total);
+    /// Turn `amount` of the caller's matured position tokens `id` back into plain LP shares.
+    pub fn unwrap_position(&mut self, id: U256, amount: U256) -> OakResult<()> {
+        PositionTokens::unwrap(self, msg::sender(), id, amount)
+    }
 
-        let current_balance = pool.lp_balances.setter(provider).get();
-        let new_balance = current_balance
-            .checked_add(liquidity)
-            .ok_or_else(|| {
-                unlock_reentrancy_guard(self);
-                err(ERR_LIQUIDITY_OVERFLOW)
-            })?;
-        pool.lp_balances.setter(provider).set(new_balance);
+    /// View: ERC-1155 token id of the (token_a, token_b) pool at lock tier `tier`.
+    pub fn get_position_id(&self, token_a: Address, token_b: Address, tier: U256) -> OakResult<U256> {
+        let (token0, token1) = if token_a < token_b { (token_a, token_b) } else { (token_b, token_a) };
+        PositionTokens::id(self, token0, token1, tier)
+    }
 
-        // LP token Transfer event (mint from zero).
-        emit_lp_transfer(Address::ZERO, provider, liquidity);
+    /// View: (token0, token1, tier, unlock block) of position token `id`.
+    pub fn get_position_class(&self, id: U256) -> OakResult<(Address, Address, U256, U256)> {
+        let (token0, token1, tier) = PositionTokens::class(self, id)?;
+        Ok((token0, token1, tier, tier_unlock_block(tier).map_err(err)?))
+    }
 
-        emit_add_liquidity(provider, amount0, amount1);
+    /// ERC-1155: balance of position token `id` held by `account`.
+    pub fn balance_of(&self, account: Address, id: U256) -> U256 {
+        PositionTokens::balance(self, account, id)
+    }
 
-        // CRITICAL: Release re-entrancy guard at the VERY END
-        // This must be the last operation before return
-        unlock_reentrancy_guard(self);
+    /// ERC-1155: balances of (accounts[i], ids[i]).
+    pub fn balance_of_batch(&self, accounts: Vec<Address>, ids: Vec<U256>) -> OakResult<Vec<U256>> {
+        if accounts.len() != ids.len() || ids.len() > MAX_POSITION_BATCH {
+            return Err(err(ERR_INVALID_POSITION_BATCH));
+        }
+        Ok(accounts.iter().zip(&ids).map(|(a, id)| PositionTokens::balance(self, *a, *id)).collect())
+    }
 
-        Ok(())
+    /// ERC-1155: let `operator` move all of the caller's position tokens (or revoke it).
+    pub fn set_approval_for_all(&mut self, operator: Address, approved: bool) -> OakResult<()> {
+        PositionTokens::set_approval_for_all(self, msg::sender(), operator, approved)
     }
 
-    /// Remove liquidity from the pool.
+    /// ERC-1155: whether `operator` may move all of `account`'s position tokens.
+    pub fn is_approved_for_all(&self, account: Address, operator: Address) -> bool {
+        PositionTokens::is_approved_for_all(self, account, operator)
+    }
+
+    /// ERC-1155: move `amount` of position token `id` (and the LP shares under it) to `to`.
     ///
-    /// @notice Burns LP tokens and returns the underlying token0 and token1
-    ///         to the provider in proportion to their share of total supply.
-    /// @dev Uses the standard Uniswap V2 pro‑rata formula:
-    ///      amount0 = lp_amount * reserve0 / totalSupply
-    ///      amount1 = lp_amount * reserve1 / totalSupply
-    pub fn remove_liquidity(
+    /// @notice Caller must be `from` or its approved operator. A contract `to` must accept via
+    ///         `onERC1155Received`; the hook runs after all writes, under the re-entrancy lock.
+    pub fn safe_transfer_from(&mut self, from: Address, to: Address, id: U256, amount: U256, data: Bytes) -> OakResult<()> {
+        with_lock(self, |dex| {
+            let operator = require_position_operator(dex, from)?;
+            PositionTokens::transfer_single(dex, operator, from, to, id, amount)?;
+            PositionTokens::check_receiver(operator, from, to, &[id], &[amount], &data, false)
+        })
+    }
+
+    /// ERC-1155: move several position classes from `from` to `to` in one call.
+    ///
+    /// @notice Same rules as `safe_transfer_from`; at most MAX_POSITION_BATCH ids.
+    pub fn safe_batch_transfer_from(
         &mut self,
-        token0: Address,
-        token1: Address,
-        lp_amount: U256,
-        amount0_min: U256,
-        amount1_min: U256,
+        from: Address,
+        to: Address,
+        ids: Vec<U256>,
+        amounts: Vec<U256>,
+        data: Bytes,
     ) -> OakResult<()> {
-        // Re-entrancy guard
-        lock_reentrancy_guard(self)?;
-
-        require_non_zero_address(token0)?;
-        require_non_zero_address(token1)?;
-
-        if lp_amount.is_zero() {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_ZERO_AMOUNT));
-        }
-
-        require_not_paused(self)?;
-
-        // Canonical pool key
-        let (pool_token0, pool_token1) = if token0 < token1 {
-            (token0, token1)
-        } else {
-            (token1, token0)
-        };
-        let mut outer = self.pools.setter(pool_token0);
-        let mut pool = outer.setter(pool_token1);
-        if !pool.initialized.get() {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_INVALID_TOKEN));
-        }
+        with_lock(self, |dex| {
+            if ids.len() != amounts.len() || ids.len() > MAX_POSITION_BATCH {
+                return Err(err(ERR_INVALID_POSITION_BATCH));
+            }
+            let operator = require_position_operator(dex, from)?;
+            for (id, amount) in ids.iter().zip(&amounts) {
+                PositionTokens::move_balance(dex, from, to, *id, *amount)?;
+            }
+            emit_erc1155_transfer_batch(operator, from, to, &ids, &amounts);
+            PositionTokens::check_receiver(operator, from, to, &ids, &amounts, &data, true)
+        })
+    }
 
-        let provider = msg::sender();
-        let total_supply = pool.lp_total_supply.get();
-        if total_supply.is_zero() {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_INSUFFICIENT_LIQUIDITY));
-        }
+    /// ERC-165: true for ERC-165 and ERC-1155.
+    pub fn supports_interface(&self, interface_id: FixedBytes<4>) -> bool {
+        PositionTokens::supports_interface(interface_id)
+    }
 
-        // Check provider balance
-        let balance = pool.lp_balances.getter(provider).get();
-        if lp_amount > balance {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_INSUFFICIENT_LIQUIDITY));
-        }
+    /// Claim the caller's gas rebates in every token; returns the number of tokens paid.
+    ///
+    /// @notice Revealing a commitment earns GAS_REBATE_BPS of the swap fee in the input token
+    ///         (see `gas_rebate`), offsetting the extra commit transaction.
+    pub fn claim_gas_rebate(&mut self) -> OakResult<U256> {
+        with_lock(self, |dex| GasRebate::claim(dex, msg::sender()))
+    }
 
-        let reserve0 = pool.reserve0.get();
-        let reserve1 = pool.reserve1.get();
+    /// View: unclaimed gas rebate of `user` in `token`.
+    pub fn pending_gas_rebate(&self, user: Address, token: Address) -> U256 {
+        GasRebate::pending(self, user, token)
+    }
 
-        // Pro-rata amounts to withdraw (canonical)
-        let amount0_c = reserve0
-            .checked_mul(lp_amount)
-            .ok_or_else(|| {
-                unlock_reentrancy_guard(self);
-                err(ERR_OVERFLOW)
-            })?
-            .checked_div(total_supply)
-            .ok_or_else(|| {
-                unlock_reentrancy_guard(self);
-                err(ERR_DIVISION_BY_ZERO)
-            })?;
-        let amount1_c = reserve1
-            .checked_mul(lp_amount)
-            .ok_or_else(|| {
-                unlock_reentrancy_guard(self);
-                err(ERR_OVERFLOW)
-            })?
-            .checked_div(total_supply)
-            .ok_or_else(|| {
-                unlock_reentrancy_guard(self);
-                err(ERR_DIVISION_BY_ZERO)
-            })?;
-        if amount0_c.is_zero() || amount1_c.is_zero() {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_INSUFFICIENT_LIQUIDITY));
+    /// Set the referral share of the treasury fee in bps (ROLE_ADMIN only; at most REFERRAL_FEE_BPS_MAX).
+    pub fn set_referral_bps(&mut self, bps: U256) -> OakResult<()> {
+        #[cfg(feature = "rewards")]
+        {
+            crate::growth::ReferralEngine::set_referral_fee_bps(self, bps)
         }
-        if amount0_c < amount0_min || amount1_c < amount1_min {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_LP_SLIPPAGE));
+        #[cfg(not(feature = "rewards"))]
+        {
+            let _ = bps;
+            Err(err(ERR_FEATURE_DISABLED))
         }
+    }
 
-        // Map canonical amounts back to user token order
-        let (amount0, amount1) = if token0 == pool_token0 {
-            (amount0_c, amount1_c)
-        } else {
-            (amount1_c, amount0_c)
-        };
-
-        // Update LP supply and balances
-        let new_total = total_supply
-            .checked_sub(lp_amount)
-            .ok_or_else(|| {
-                unlock_reentrancy_guard(self);
-                err(ERR_LIQUIDITY_OVERFLOW)
-            })?;
-        pool.lp_total_supply.set(new_total);
-
-        let new_balance = balance
-            .checked_sub(lp_amount)
-            .ok_or_else(|| {
-                unlock_reentrancy_guard(self);
-                err(ERR_LIQUIDITY_OVERFLOW)
-            })?;
-        pool.lp_balances.setter(provider).set(new_balance);
+    /// View: referral share of the treasury fee in bps.
+    pub fn get_referral_bps(&self) -> U256 {
+        self.referral_fee_bps.get()
+    }
 
-        // Update reserves after withdrawal (canonical)
-        let (new_reserve0, new_reserve1) = if token0 == pool_token0 {
-            let new_r0 = reserve0
-                .checked_sub(amount0_c)
-                .ok_or_else(|| {
-                    unlock_reentrancy_guard(self);
-                    err(ERR_INSUFFICIENT_LIQUIDITY)
-                })?;
-            let new_r1 = reserve1
-                .checked_sub(amount1_c)
-                .ok_or_else(|| {
-                    unlock_reentrancy_guard(self);
-                    err(ERR_INSUFFICIENT_LIQUIDITY)
-                })?;
-            (new_r0, new_r1)
-        } else {
-            let new_r0 = reserve0
-                .checked_sub(amount1_c)
-                .ok_or_else(|| {
-                    unlock_reentrancy_guard(self);
-                    err(ERR_INSUFFICIENT_LIQUIDITY)
-                })?;
-            let new_r1 = reserve1
-                .checked_sub(amount0_c)
-                .ok_or_else(|| {
-                    unlock_reentrancy_guard(self);
-                    err(ERR_INSUFFICIENT_LIQUIDITY)
-                })?;
-            (new_r0, new_r1)
+    /// Claim the caller's accrued referral fees in `token`; returns the amount paid.
+    pub fn claim_referral_fees(&mut self, token: Address) -> OakResult<U256> {
+        #[cfg(feature = "rewards")]
+        let result = with_lock(self, |dex| crate::growth::ReferralEngine::claim(dex, msg::sender(), token));
+        #[cfg(not(feature = "rewards"))]
+        let result = {
+            let _ = token;
+            Err(err(ERR_FEATURE_DISABLED))
         };
+        result
+    }
 
-        pool.reserve0.set(new_reserve0);
-        pool.reserve1.set(new_reserve1);
+    /// View: referral fees `referrer` can claim in `token`.
+    pub fn pending_referral_fees(&self, referrer: Address, token: Address) -> U256 {
+        self.referral_accrued.getter(referrer).get(token)
+    }
 
-        // Transfer underlying tokens back to the provider
-        if let Err(e) = safe_transfer(token0, provider, amount0) {
-            unlock_reentrancy_guard(self);
-            return Err(e);
-        }
-        if let Err(e) = safe_transfer(token1, provider, amount1) {
-            unlock_reentrancy_guard(self);
-            return Err(e);
-        }
+    /// View: solvency check of the contract's `token` balance against its accounting.
+    ///
+    /// @notice `counterparts` lists the other token of every pool holding `token`. Returns
+    ///         (balance, expected, surplus, deficit) where expected = pool reserves + treasury,
+    ///         buyback, output escrow, commit bonds, creator shares, batch escrow, margin, stake,
+    ///         open limit orders, unclaimed LP fees and gas rebates. A non-zero deficit means the contract cannot cover what it owes.
+    pub fn reconcile_fees(&self, token: Address, counterparts: Vec<Address>) -> OakResult<(U256, U256, U256, U256)> {
+        FeeReconciliation::reconcile(self, token, &counterparts)
+    }
 
-        // LP token Transfer event (burn to zero).
-        emit_lp_transfer(provider, Address::ZERO, lp_amount);
+    /// Credit the contract's surplus in the tokens of (token_a, token_b) to that pool (Uniswap v2 `sync`).
+    ///
+    /// @notice ROLE_ADMIN only: the surplus is per token across all pools, so governance decides
+    ///         which pool it was meant for. A deficit is never written off. Returns the new
+    ///         (reserve0, reserve1) in canonical order.
+    pub fn sync(&mut self, token_a: Address, token_b: Address) -> OakResult<(U256, U256)> {
+        require_role(self, ROLE_ADMIN)?;
+        AdminAuditLog::record(self, AUDIT_ACTION_SYNC, &[token_a.into_word(), token_b.into_word()])?;
+        with_lock(self, |dex| process_sync(dex, token_a, token_b))
+    }
 
-        // Re-entrancy guard release
-        unlock_reentrancy_guard(self);
+    /// Sweep the contract's surplus in the tokens of (token_a, token_b) to `to` (Uniswap v2 `skim`).
+    ///
+    /// @notice ROLE_ADMIN only: the surplus includes tokens sent for every pool sharing a token
+    ///         with this one. Returns the (amount0, amount1) sent, canonical order.
+    pub fn skim(&mut self, token_a: Address, token_b: Address, to: Address) -> OakResult<(U256, U256)> {
+        require_role(self, ROLE_ADMIN)?;
+        AdminAuditLog::record(self, AUDIT_ACTION_SKIM, &[token_a.into_word(), token_b.into_word(), to.into_word()])?;
+        with_lock(self, |dex| process_skim(dex, token_a, token_b, to))
+    }
 
-        Ok(())
+    /// View: (locked LP shares, unlock_block) of `owner` in (token_a, token_b); zeros when unlocked.
+    pub fn get_liquidity_lock(&self, owner: Address, token_a: Address, token_b: Address) -> OakResult<(U256, U256)> {
+        let (token0, token1) = if token_a < token_b { (token_a, token_b) } else { (token_b, token_a) };
+        Ok(LiquidityLocks::get(self, owner, token0, token1))
     }
 
     /// Compute expected output amounts along a multi-hop path.
@@ -1477,14 +4773,12 @@ impl OakDEX {
         if amount_in.is_zero() {
             return Err(err(ERR_INSUFFICIENT_INPUT_AMOUNT));
         }
+        TokenAliases::require_acyclic_path(self, &path)?;
 
         let mut amounts = Vec::with_capacity(path.len());
         amounts.push(amount_in);
         let mut current_in = amount_in;
 
-        // Single global fee setting for now.
-        let fee_bps = self.protocol_fee_bps.get();
-
         for i in 0..(path.len() - 1) {
             let input = path[i];
             let output = path[i + 1];
@@ -1519,6 +4813,7 @@ impl OakDEX {
                 (reserve1, reserve0)
             };
 
+            let fee_bps = pool_fee_bps(self, token0, token1);
             let out = get_amount_out_with_fee(current_in, reserve_in, reserve_out, fee_bps)?;
             if out.is_zero() {
                 return Err(err(ERR_INSUFFICIENT_OUTPUT_AMOUNT));
@@ -1528,7 +4823,139 @@ impl OakDEX {
             current_in = out;
         }
 
-        Ok(amounts)
+        Ok(amounts)
+    }
+
+    /// View: current protocol swap fee in basis points.
+    pub fn get_fee_bps(&self) -> OakResult<U256> {
+        Ok(self.protocol_fee_bps.get())
+    }
+
+    /// Set the flash loan fee in bps, separate from the swap fee (0 = follow the pool's swap fee).
+    ///
+    /// @notice Owner or FEE_SETTER_ROLE. Bounded by MAX_FLASH_FEE_BPS; applies to flash_swap
+    ///         and the ERC-3156 `flash_loan`.
+    pub fn set_flash_fee(&mut self, fee_bps: U256) -> OakResult<()> {
+        require_role(self, ROLE_ADMIN | ROLE_FEE_SETTER)?;
+        if fee_bps > as_u256(MAX_FLASH_FEE_BPS) {
+            return Err(err(ERR_FEE_TOO_HIGH));
+        }
+        AdminAuditLog::record(self, AUDIT_ACTION_SET_FLASH_FEE, &[word(fee_bps)])?;
+        self.flash_fee_bps.set(fee_bps);
+        emit_flash_fee_set(fee_bps);
+        Ok(())
+    }
+
+    /// View: configured flash loan fee in bps (0 = each pool's swap fee).
+    pub fn get_flash_fee_bps(&self) -> U256 {
+        self.flash_fee_bps.get()
+    }
+
+    /// ROLE_ADMIN: cap how far one swap may move a pool's spot price, in bps (0 = no limit).
+    ///
+    /// @notice Every swap, reveals included, compares the pool's reserve_out / reserve_in after
+    ///         the swap with the pre-swap reserves and reverts with PriceImpactTooHigh past the
+    ///         cap (see `spot_price_move_bps`). Guards against fat-finger drains and price pushes.
+    pub fn set_max_price_impact(&mut self, max_impact_bps: U256) -> OakResult<()> {
+        require_role(self, ROLE_ADMIN)?;
+        if max_impact_bps > as_u256(BPS) {
+            return Err(err(ERR_INVALID_PRICE_IMPACT_LIMIT));
+        }
+        AdminAuditLog::record(self, AUDIT_ACTION_SET_MAX_PRICE_IMPACT, &[word(max_impact_bps)])?;
+        self.max_price_impact_bps.set(max_impact_bps);
+        emit_max_price_impact_set(max_impact_bps);
+        Ok(())
+    }
+
+    /// View: per-swap spot price move limit in bps (0 = no limit).
+    pub fn get_max_price_impact_bps(&self) -> U256 {
+        self.max_price_impact_bps.get()
+    }
+
+    /// ROLE_ADMIN: cap the share of a pool's reserve that may leave it per block, per direction (0 = no limit).
+    ///
+    /// @notice Swaps (reveals included) and flash swaps in the same block add up; past `max_outflow_bps`
+    ///         of the reserve held at the block's first outflow they revert with OutflowLimitExceeded.
+    ///         Slows a drain down to one slice per block so the breaker or a guardian can react.
+    pub fn set_max_outflow_per_block(&mut self, max_outflow_bps: U256) -> OakResult<()> {
+        require_role(self, ROLE_ADMIN)?;
+        if max_outflow_bps > as_u256(BPS) {
+            return Err(err(ERR_INVALID_OUTFLOW_LIMIT));
+        }
+        AdminAuditLog::record(self, AUDIT_ACTION_SET_MAX_OUTFLOW, &[word(max_outflow_bps)])?;
+        self.max_outflow_bps_per_block.set(max_outflow_bps);
+        emit_max_outflow_set(max_outflow_bps);
+        Ok(())
+    }
+
+    /// View: outflow limit and current-block outflows of (token_a, token_b) (see `PoolOutflowView`).
+    pub fn get_pool_outflow(&self, token_a: Address, token_b: Address) -> PoolOutflowView {
+        let (token0, token1) = if token_a < token_b { (token_a, token_b) } else { (token_b, token_a) };
+        let (outflow0, outflow1) = FlowLimiter::current(self, token0, token1);
+        (self.max_outflow_bps_per_block.get(), outflow0, outflow1)
+    }
+
+    /// View: protocol owner (governance).
+    pub fn get_owner(&self) -> OakResult<Address> {
+        Ok(self.owner.get())
+    }
+
+    /// View: treasury address receiving withdrawn protocol fees.
+    pub fn get_treasury(&self) -> OakResult<Address> {
+        Ok(self.treasury.get())
+    }
+
+    /// View: true if the protocol is paused (manually or by the circuit breaker).
+    pub fn is_paused(&self) -> OakResult<bool> {
+        Ok(self.paused.get())
+    }
+
+    /// View: deterministic id of the (token_a, token_b, fee_bps) pool (see `compute_pool_id`) as
+    /// (pool_id, token0, token1, exists).
+    ///
+    /// @notice Works before creation; `exists` is true once the pair is initialized. Pools
+    ///         currently share the protocol fee, so pass `get_fee_bps()` for live pools.
+    pub fn get_pool_id(
+        &self,
+        token_a: Address,
+        token_b: Address,
+        fee_bps: U256,
+    ) -> OakResult<(FixedBytes<32>, Address, Address, bool)> {
+        if token_a == token_b {
+            return Err(err(ERR_INVALID_TOKEN));
+        }
+        let (token0, token1) = if token_a < token_b { (token_a, token_b) } else { (token_b, token_a) };
+        let exists = self.pools.getter(token0).getter(token1).initialized.get();
+        Ok((compute_pool_id(token0, token1, fee_bps), token0, token1, exists))
+    }
+
+    /// View: combined pool state for frontends and routers (see `PoolInfo`).
+    ///
+    /// @notice Unknown pairs return `initialized = false` and zeros instead of reverting.
+    pub fn get_pool_info(&self, token_a: Address, token_b: Address) -> OakResult<PoolInfo> {
+        let (token0, token1) = if token_a < token_b {
+            (token_a, token_b)
+        } else {
+            (token_b, token_a)
+        };
+        let outer = self.pools.getter(token0);
+        let pool = outer.getter(token1);
+        let (reserve0, reserve1) = (pool.reserve0.get(), pool.reserve1.get());
+        let (reserve_a, reserve_b) = if token_a == token0 {
+            (reserve0, reserve1)
+        } else {
+            (reserve1, reserve0)
+        };
+        Ok((
+            pool.initialized.get(),
+            reserve_a,
+            reserve_b,
+            pool.lp_total_supply.get(),
+            pool_fee_bps(self, token0, token1),
+            self.paused.get() || pool.paused.get(),
+            pool.hooked_token.get(),
+            pool.manager.get(),
+        ))
     }
 
     /// Get current reserves for a given token pair.
@@ -1539,8 +4966,6 @@ impl OakDEX {
         token_a: Address,
         token_b: Address,
     ) -> OakResult<(U256, U256)> {
-        require_non_zero_address(token_a)?;
-        require_non_zero_address(token_b)?;
         if token_a == token_b {
             return Err(err(ERR_INVALID_TOKEN));
         }
@@ -1584,80 +5009,205 @@ impl OakDEX {
         to: Address,
         deadline: U256,
     ) -> OakResult<Vec<U256>> {
-        // Re-entrancy guard
-        lock_reentrancy_guard(self)?;
+        with_lock(self, |dex| {
+            // Basic input validation
+            if amount_in.is_zero() {
+                return Err(err(ERR_INSUFFICIENT_INPUT_AMOUNT));
+            }
+            if amount_out_min.is_zero() {
+                return Err(err(ERR_INSUFFICIENT_OUTPUT_AMOUNT));
+            }
+            if path.len() < 2 {
+                return Err(err(ERR_INVALID_PATH));
+            }
+            if path.len() as u64 > MAX_PATH_LENGTH {
+                return Err(err(ERR_PATH_TOO_LONG));
+            }
 
-        // Basic input validation
-        if amount_in.is_zero() {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_INSUFFICIENT_INPUT_AMOUNT));
-        }
-        if amount_out_min.is_zero() {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_INSUFFICIENT_OUTPUT_AMOUNT));
-        }
-        if path.len() < 2 {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_INVALID_PATH));
-        }
-        if path.len() as u64 > MAX_PATH_LENGTH {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_PATH_TOO_LONG));
-        }
+            // Recipient must be non-zero and, в текущей версии, совпадать с sender.
+            let sender = msg::sender();
+            if to == Address::ZERO || to != sender {
+                return Err(err(ERR_INVALID_ADDRESS));
+            }
 
-        // Recipient must be non-zero and, в текущей версии, совпадать с sender.
-        let sender = msg::sender();
-        if to == Address::ZERO || to != sender {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_INVALID_ADDRESS));
-        }
+            require_not_paused(dex)?;
+            require_not_circuit_breaker(dex)?;
 
-        require_not_paused(self)?;
-        require_not_circuit_breaker(self)?;
+            // Deadline based on block timestamp
+            check_deadline(U256::from(block::timestamp()), deadline)?;
 
-        // Deadline based on block timestamp
-        let now = U256::from(block::timestamp());
-        if now > deadline {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_EXPIRED));
-        }
+            // Compute expected amounts along the path
+            let amounts = dex.get_amounts_out(amount_in, path.clone())?;
 
-        // Compute expected amounts along the path
-        let amounts = match self.get_amounts_out(amount_in, path.clone()) {
-            Ok(v) => v,
-            Err(e) => {
-                unlock_reentrancy_guard(self);
-                return Err(e);
+            let final_out = *amounts.last().unwrap_or(&U256::ZERO);
+            if final_out < amount_out_min {
+                return Err(err(ERR_INSUFFICIENT_OUTPUT_AMOUNT));
             }
-        };
 
-        let final_out = *amounts.last().unwrap_or(&U256::ZERO);
-        if final_out < amount_out_min {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_INSUFFICIENT_OUTPUT_AMOUNT));
+            // Execute the multi-hop swaps sequentially.
+            // At каждом шаге process_swap:
+            // - списывает amount_in хопа с sender в контракт
+            // - отправляет amount_out хопа обратно sender'у
+            // - обновляет резервы пула (через PoolData)
+            for i in 0..(path.len() - 1) {
+                let token_in = path[i];
+                let token_out = path[i + 1];
+                let hop_in = amounts[i];
+                let hop_min_out = amounts[i + 1]; // строгое ожидание по расчёту get_amounts_out
+
+                process_swap(dex, token_in, token_out, hop_in, hop_min_out, None)?;
+            }
+
+            Ok(amounts)
+        })
+    }
+
+    /// Multi-hop exact-input swap through registered pairs (A -> B -> C in one transaction).
+    ///
+    /// @notice Pulls `amount_in` of `path[0]` from the caller, routes it hop by hop and sends at
+    ///         least `min_out` of the last token back. Returns the amount at every step of `path`.
+    /// @dev Every hop must be a pair in the registry (`get_pair`). `deadline` is a block timestamp,
+    ///      as for `swap_exact_tokens_for_tokens`.
+    pub fn swap_exact_in_path(
+        &mut self,
+        path: Vec<Address>,
+        amount_in: U256,
+        min_out: U256,
+        deadline: U256,
+    ) -> OakResult<Vec<U256>> {
+        check_deadline(U256::from(block::timestamp()), deadline)?;
+        with_lock(self, |dex| process_swap_exact_in_path(dex, msg::sender(), &path, amount_in, min_out))
+    }
+
+    /// Instant single-pool swap with an optional settlement callback.
+    ///
+    /// @notice Without `data` the input is pulled from the caller as usual. With `data` the output
+    ///         goes to `to` first and `to.oakSwapCallback(amount_in, amount_out, data)` must pay
+    ///         `amount_in` of `token_in` before returning ("pay later within the same call").
+    ///         The swap reverts unless the input arrived and the pool's k did not decrease.
+    /// @dev `deadline` is a block timestamp, as for the other direct (uncommitted) swaps.
+    #[allow(clippy::too_many_arguments)]
+    pub fn swap(
+        &mut self,
+        token_in: Address,
+        token_out: Address,
+        amount_in: U256,
+        min_amount_out: U256,
+        to: Address,
+        deadline: U256,
+        data: Bytes,
+    ) -> OakResult<U256> {
+        if to == Address::ZERO {
+            return Err(err(ERR_INVALID_ADDRESS));
         }
+        check_deadline(U256::from(block::timestamp()), deadline)?;
+        with_lock(self, |dex| {
+            process_swap_with_callback(dex, to, token_in, token_out, amount_in, min_amount_out, &data)
+        })
+    }
 
-        // Execute the multi-hop swaps sequentially.
-        // At каждом шаге process_swap:
-        // - списывает amount_in хопа с sender в контракт
-        // - отправляет amount_out хопа обратно sender'у
-        // - обновляет резервы пула (через PoolData)
-        for i in 0..(path.len() - 1) {
-            let token_in = path[i];
-            let token_out = path[i + 1];
-            let hop_in = amounts[i];
-            let hop_min_out = amounts[i + 1]; // строгое ожидание по расчёту get_amounts_out
+    /// Router onboarding flow: ERC-2612 permits, multi-hop swap and optional zap-in in one call.
+    ///
+    /// @notice Submits every permit (spender = this contract), swaps `swap_amount` of `path[0]`
+    ///         along `path`, then either sends the output to the caller (`swap_amount == amount_in`)
+    ///         or deposits the remaining `amount_in - swap_amount` of `path[0]` together with the
+    ///         output into the (path[0], path[last]) pool, which must already exist
+    ///         (PAIR_NOT_REGISTERED, checked before any permit or hop runs).
+    /// @dev One deadline (block timestamp) covers the permits and the swap. Slippage is checked
+    ///      once on the final output (`amount_out_min`) and, for zaps, on LP minted (`min_liquidity`).
+    ///      Intermediate hop outputs stay in the contract.
+    ///
+    /// # Returns
+    /// (amounts along the path, LP tokens minted; 0 without zap)
+    #[allow(clippy::too_many_arguments)]
+    pub fn permit_swap_and_zap(
+        &mut self,
+        permit_tokens: Vec<Address>,
+        permit_values: Vec<U256>,
+        permit_v: Vec<u8>,
+        permit_r: Vec<FixedBytes<32>>,
+        permit_s: Vec<FixedBytes<32>>,
+        amount_in: U256,
+        swap_amount: U256,
+        amount_out_min: U256,
+        min_liquidity: U256,
+        path: Vec<Address>,
+        deadline: U256,
+    ) -> OakResult<(Vec<U256>, U256)> {
+        with_lock(self, |dex| {
+            let n = permit_tokens.len();
+            if permit_values.len() != n || permit_v.len() != n || permit_r.len() != n || permit_s.len() != n {
+                return Err(err(ERR_PERMIT_BATCH_LENGTH));
+            }
+            // One permit per distinct path token at most.
+            if n as u64 > MAX_PATH_LENGTH {
+                return Err(err(ERR_TOO_MANY_ITEMS));
+            }
+            if swap_amount.is_zero() || swap_amount > amount_in {
+                return Err(err(ERR_INSUFFICIENT_INPUT_AMOUNT));
+            }
+            if amount_out_min.is_zero() {
+                return Err(err(ERR_INSUFFICIENT_OUTPUT_AMOUNT));
+            }
+            if path.len() < 2 {
+                return Err(err(ERR_INVALID_PATH));
+            }
+            if path.len() as u64 > MAX_PATH_LENGTH {
+                return Err(err(ERR_PATH_TOO_LONG));
+            }
+            check_deadline(U256::from(block::timestamp()), deadline)?;
+            // The zap deposits into (path[0], path[last]), which no hop touches on a multi-hop
+            // path: make sure it exists before any permit or swap runs.
+            let zap = swap_amount < amount_in;
+            if zap {
+                let (token0, token1) = zap_pool(&path)?;
+                if PairRegistry::position(dex, token0, token1).is_none() {
+                    return Err(err(ERR_PAIR_NOT_REGISTERED));
+                }
+            }
 
-            if let Err(e) = process_swap(self, token_in, token_out, hop_in, hop_min_out) {
-                unlock_reentrancy_guard(self);
-                return Err(e);
+            let sender = msg::sender();
+            let contract_addr = contract::address();
+            for i in 0..n {
+                let signature = (permit_v[i], permit_r[i], permit_s[i]);
+                permit(permit_tokens[i], sender, contract_addr, permit_values[i], deadline, signature)?;
             }
-        }
 
-        // Release re-entrancy guard
-        unlock_reentrancy_guard(self);
+            let amounts = dex.get_amounts_out(swap_amount, path.clone())?;
+            let final_out = *amounts.last().unwrap_or(&U256::ZERO);
+            if final_out < amount_out_min {
+                return Err(err(ERR_SLIPPAGE_EXCEEDED));
+            }
 
-        Ok(amounts)
+            let last_hop = path.len() - 2;
+            for i in 0..=last_hop {
+                let from = if i == 0 { sender } else { contract_addr };
+                let to = if i == last_hop && !zap { sender } else { contract_addr };
+                process_swap_from_to(dex, from, to, path[i], path[i + 1], amounts[i], amounts[i + 1])?;
+            }
+
+            let liquidity = if zap {
+                let deposit0 = amount_in - swap_amount;
+                let liquidity = process_add_liquidity(
+                    dex,
+                    sender,
+                    path[0],
+                    path[path.len() - 1],
+                    deposit0,
+                    final_out,
+                    U256::ZERO,
+                    U256::ZERO,
+                    (false, true),
+                )?;
+                if liquidity < min_liquidity {
+                    return Err(err(ERR_LP_SLIPPAGE));
+                }
+                liquidity
+            } else {
+                U256::ZERO
+            };
+            Ok((amounts, liquidity))
+        })
     }
 
     // ---------- TP/SL/Limit orders (pro exchange features) ----------
@@ -1679,109 +5229,139 @@ impl OakDEX {
         order_type: U256,
         oco_with_order_id: U256,
     ) -> OakResult<U256> {
-        lock_reentrancy_guard(self)?;
-        require_non_zero_address(token_in)?;
-        require_non_zero_address(token_out)?;
-        if token_in == token_out {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_INVALID_TOKEN));
-        }
-        if amount_out.is_zero() {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_INSUFFICIENT_INPUT_AMOUNT));
-        }
-        let order_type_u = order_type.as_limbs()[0];
-        if order_type_u > 2 {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_INVALID_ORDER_TYPE));
-        }
-        require_not_paused(self)?;
-        require_not_circuit_breaker(self)?;
+        require_orders_enabled()?;
+        with_lock(self, |dex| {
+            require_non_zero_address(token_in)?;
+            require_non_zero_address(token_out)?;
+            if token_in == token_out {
+                return Err(err(ERR_INVALID_TOKEN));
+            }
+            if amount_out.is_zero() {
+                return Err(err(ERR_INSUFFICIENT_INPUT_AMOUNT));
+            }
+            let order_type_u = order_type.as_limbs()[0];
+            if order_type_u > 2 {
+                return Err(err(ERR_INVALID_ORDER_TYPE));
+            }
+            require_not_paused(dex)?;
+            require_not_circuit_breaker(dex)?;
+
+            let sender = msg::sender();
+            let contract_addr = contract::address();
+            let balance = balance_of(token_out, sender);
+            if balance < amount_out {
+                return Err(err(ERR_INSUFFICIENT_BALANCE));
+            }
 
-        let sender = msg::sender();
-        let contract_addr = contract::address();
-        let balance = balance_of(token_out, sender);
-        if balance < amount_out {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_INSUFFICIENT_BALANCE));
-        }
+            safe_transfer_from(token_out, sender, contract_addr, amount_out)?;
+
+            let next_id = dex.next_order_id.get();
+            let new_id = next_id.checked_add(U256::from(1u64)).ok_or_else(|| err(ERR_OVERFLOW))?;
+            dex.next_order_id.set(new_id);
+
+            let key = order_id_to_address(new_id);
+            dex.order_owner.setter(key).set(sender);
+            dex.order_token_in.setter(key).set(token_in);
+            dex.order_token_out.setter(key).set(token_out);
+            dex.order_amount_out.setter(key).set(amount_out);
+            dex.order_trigger_price.setter(key).set(trigger_price);
+            dex.order_type.setter(key).set(order_type);
+            dex.order_status.setter(key).set(U256::ZERO); // Open
+            track_order_escrow(dex, token_out, U256::ZERO, amount_out)?;
+            dex.order_created_at.setter(key).set(U256::from(block::number()));
+
+            if !oco_with_order_id.is_zero() {
+                let oco_key = order_id_to_address(oco_with_order_id);
+                let oco_owner = dex.order_owner.setter(oco_key).get();
+                if oco_owner == Address::ZERO {
+                    return Err(err(ERR_OCO_PAIR_INVALID));
+                }
+                if oco_owner != sender {
+                    return Err(err(ERR_ORDER_NOT_OWNER));
+                }
+                let oco_status = dex.order_status.setter(oco_key).get();
+                if oco_status != U256::ZERO {
+                    return Err(err(ERR_OCO_PAIR_INVALID));
+                }
+                dex.order_oco_pair.setter(key).set(oco_with_order_id);
+                dex.order_oco_pair.setter(oco_key).set(new_id);
+            }
 
-        safe_transfer_from(token_out, sender, contract_addr, amount_out)?;
+            emit_order_placed(new_id, sender, token_in, token_out, amount_out, trigger_price, order_type);
+            Ok(new_id)
+        })
+    }
 
-        let next_id = self.next_order_id.get();
-        let new_id = next_id.checked_add(U256::from(1u64)).ok_or_else(|| {
-            unlock_reentrancy_guard(self);
-            err(ERR_OVERFLOW)
-        })?;
-        self.next_order_id.set(new_id);
-
-        let key = order_id_to_address(new_id);
-        self.order_owner.setter(key).set(sender);
-        self.order_token_in.setter(key).set(token_in);
-        self.order_token_out.setter(key).set(token_out);
-        self.order_amount_out.setter(key).set(amount_out);
-        self.order_trigger_price.setter(key).set(trigger_price);
-        self.order_type.setter(key).set(order_type);
-        self.order_status.setter(key).set(U256::ZERO); // Open
-        self.order_created_at.setter(key).set(U256::from(block::number()));
-
-        if !oco_with_order_id.is_zero() {
-            let oco_key = order_id_to_address(oco_with_order_id);
-            let oco_owner = self.order_owner.setter(oco_key).get();
-            if oco_owner == Address::ZERO {
-                unlock_reentrancy_guard(self);
-                return Err(err(ERR_OCO_PAIR_INVALID));
-            }
-            if oco_owner != sender {
-                unlock_reentrancy_guard(self);
+    /// Cancel an open order; returns escrowed tokens to the owner.
+    pub fn cancel_order(&mut self, order_id: U256) -> OakResult<()> {
+        require_orders_enabled()?;
+        with_lock(self, |dex| {
+            let sender = msg::sender();
+            let key = order_id_to_address(order_id);
+            let owner = dex.order_owner.setter(key).get();
+            if owner == Address::ZERO {
+                return Err(err(ERR_ORDER_NOT_FOUND));
+            }
+            if owner != sender {
                 return Err(err(ERR_ORDER_NOT_OWNER));
             }
-            let oco_status = self.order_status.setter(oco_key).get();
-            if oco_status != U256::ZERO {
-                unlock_reentrancy_guard(self);
-                return Err(err(ERR_OCO_PAIR_INVALID));
+            let status = dex.order_status.setter(key).get();
+            if status != U256::ZERO {
+                return Err(err(ERR_ORDER_NOT_OPEN));
             }
-            self.order_oco_pair.setter(key).set(oco_with_order_id);
-            self.order_oco_pair.setter(oco_key).set(new_id);
-        }
-
-        emit_order_placed(new_id, sender, token_in, token_out, amount_out, trigger_price, order_type);
-        unlock_reentrancy_guard(self);
-        Ok(new_id)
+            release_open_order(dex, order_id, sender)?;
+            emit_order_cancelled(order_id, sender);
+            Ok(())
+        })
     }
 
-    /// Cancel an open order; returns escrowed tokens to the owner.
-    pub fn cancel_order(&mut self, order_id: U256) -> OakResult<()> {
-        lock_reentrancy_guard(self)?;
-        let sender = msg::sender();
-        let key = order_id_to_address(order_id);
-        let owner = self.order_owner.setter(key).get();
-        if owner == Address::ZERO {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_ORDER_NOT_FOUND));
+    /// Cancel many of the caller's open orders in one call; returns how many were cancelled.
+    ///
+    /// @notice For market makers pulling quotes at risk-off moments. Orders already filled or
+    ///         cancelled are skipped; one aggregated OrdersCancelled event is emitted.
+    /// @dev At most MAX_BATCH_CANCEL ids. Reverts if any id is unknown or not the caller's.
+    pub fn cancel_orders(&mut self, order_ids: Vec<U256>) -> OakResult<U256> {
+        require_orders_enabled()?;
+        if order_ids.is_empty() || order_ids.len() as u64 > MAX_BATCH_CANCEL {
+            return Err(err(ERR_CANCEL_BATCH_INVALID));
         }
-        if owner != sender {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_ORDER_NOT_OWNER));
+        let sender = msg::sender();
+        with_lock(self, |dex| {
+            cancel_orders_of(dex, sender, &order_ids).map(|cancelled| {
+                emit_orders_cancelled(sender, &cancelled);
+                U256::from(cancelled.len())
+            })
+        })
+    }
+
+    /// Cancel every open order of the caller with id in `[first_id, last_id]`; returns the count.
+    ///
+    /// @notice Order ids are sequential, so a range covers "everything placed since id N"
+    ///         without listing ids. Ids owned by others (or unused) are skipped.
+    /// @dev Range length at most MAX_BATCH_CANCEL; one aggregated OrdersCancelled event.
+    pub fn cancel_orders_in_range(&mut self, first_id: U256, last_id: U256) -> OakResult<U256> {
+        require_orders_enabled()?;
+        if last_id < first_id || last_id - first_id >= as_u256(MAX_BATCH_CANCEL) {
+            return Err(err(ERR_CANCEL_BATCH_INVALID));
         }
-        let status = self.order_status.setter(key).get();
-        if status != U256::ZERO {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_ORDER_NOT_OPEN));
-        }
-        let token_out = self.order_token_out.setter(key).get();
-        let amount_out = self.order_amount_out.setter(key).get();
-        self.order_status.setter(key).set(U256::from(2u64)); // Cancelled
-        safe_transfer(token_out, sender, amount_out)?;
-        let oco_pair = self.order_oco_pair.setter(key).get();
-        if !oco_pair.is_zero() {
-            let oco_key = order_id_to_address(oco_pair);
-            self.order_oco_pair.setter(key).set(U256::ZERO);
-            self.order_oco_pair.setter(oco_key).set(U256::ZERO);
-        }
-        emit_order_cancelled(order_id, sender);
-        unlock_reentrancy_guard(self);
-        Ok(())
+        let sender = msg::sender();
+        let mut owned = Vec::new();
+        let mut id = first_id;
+        loop {
+            if self.order_owner.setter(order_id_to_address(id)).get() == sender {
+                owned.push(id);
+            }
+            if id == last_id {
+                break;
+            }
+            id += U256::from(1u64);
+        }
+        with_lock(self, |dex| {
+            cancel_orders_of(dex, sender, &owned).map(|cancelled| {
+                emit_orders_cancelled(sender, &cancelled);
+                U256::from(cancelled.len())
+            })
+        })
     }
 
     /// Execute an open order when price condition is met. Anyone may call.
@@ -1789,67 +5369,68 @@ impl OakDEX {
     /// @param order_id Order to execute.
     /// @param min_amount_out Minimum token_in to send to order owner (slippage).
     pub fn execute_order(&mut self, order_id: U256, min_amount_out: U256) -> OakResult<U256> {
-        lock_reentrancy_guard(self)?;
-        let key = order_id_to_address(order_id);
-        let owner = self.order_owner.setter(key).get();
-        if owner == Address::ZERO {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_ORDER_NOT_FOUND));
-        }
-        let status = self.order_status.setter(key).get();
-        if status != U256::ZERO {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_ORDER_NOT_OPEN));
-        }
-        let token_in = self.order_token_in.setter(key).get();
-        let token_out = self.order_token_out.setter(key).get();
-        let amount_out = self.order_amount_out.setter(key).get();
-        let trigger_price = self.order_trigger_price.setter(key).get();
-        let order_type = self.order_type.setter(key).get();
-
-        let current_price = self.get_current_price(token_in, token_out)?;
-        let order_type_u = order_type.as_limbs()[0];
-        let condition_met = if order_type_u == 2 {
-            current_price <= trigger_price
-        } else {
-            current_price >= trigger_price
-        };
-        if !condition_met {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_ORDER_CONDITION_NOT_MET));
-        }
-
-        let contract_addr = contract::address();
-        let amount_in_received = process_swap_from_to(
-            self,
-            contract_addr,
-            owner,
-            token_out,
-            token_in,
-            amount_out,
-            min_amount_out,
-        )?;
-        self.order_status.setter(key).set(U256::from(1u64)); // Executed
-        emit_order_executed(order_id, owner, amount_in_received);
+        require_orders_enabled()?;
+        with_lock(self, |dex| {
+            let key = order_id_to_address(order_id);
+            let owner = dex.order_owner.setter(key).get();
+            if owner == Address::ZERO {
+                return Err(err(ERR_ORDER_NOT_FOUND));
+            }
+            let status = dex.order_status.setter(key).get();
+            if status != U256::ZERO {
+                return Err(err(ERR_ORDER_NOT_OPEN));
+            }
+            let token_in = dex.order_token_in.setter(key).get();
+            let token_out = dex.order_token_out.setter(key).get();
+            let amount_out = dex.order_amount_out.setter(key).get();
+            let trigger_price = dex.order_trigger_price.setter(key).get();
+            let order_type = dex.order_type.setter(key).get();
+
+            let current_price = dex.get_current_price(token_in, token_out)?;
+            let order_type_u = order_type.as_limbs()[0];
+            let condition_met = if order_type_u == 2 {
+                current_price <= trigger_price
+            } else {
+                current_price >= trigger_price
+            };
+            if !condition_met {
+                return Err(err(ERR_ORDER_CONDITION_NOT_MET));
+            }
 
-        let oco_pair = self.order_oco_pair.setter(key).get();
-        if !oco_pair.is_zero() {
-            let oco_key = order_id_to_address(oco_pair);
-            let oco_owner = self.order_owner.setter(oco_key).get();
-            let oco_status = self.order_status.setter(oco_key).get();
-            if oco_owner != Address::ZERO && oco_status == U256::ZERO {
-                let oco_token_out = self.order_token_out.setter(oco_key).get();
-                let oco_amount_out = self.order_amount_out.setter(oco_key).get();
-                self.order_status.setter(oco_key).set(U256::from(2u64)); // Cancelled
-                safe_transfer(oco_token_out, oco_owner, oco_amount_out)?;
-                emit_order_cancelled(oco_pair, oco_owner);
+            let contract_addr = contract::address();
+            // The escrow becomes swap input now; the swap books it into the pool reserve.
+            track_order_escrow(dex, token_out, amount_out, U256::ZERO)?;
+            let amount_in_received = process_swap_from_to(
+                dex,
+                contract_addr,
+                owner,
+                token_out,
+                token_in,
+                amount_out,
+                min_amount_out,
+            )?;
+            dex.order_status.setter(key).set(U256::from(1u64)); // Executed
+            emit_order_executed(order_id, owner, amount_in_received);
+
+            let oco_pair = dex.order_oco_pair.setter(key).get();
+            if !oco_pair.is_zero() {
+                let oco_key = order_id_to_address(oco_pair);
+                let oco_owner = dex.order_owner.setter(oco_key).get();
+                let oco_status = dex.order_status.setter(oco_key).get();
+                if oco_owner != Address::ZERO && oco_status == U256::ZERO {
+                    let oco_token_out = dex.order_token_out.setter(oco_key).get();
+                    let oco_amount_out = dex.order_amount_out.setter(oco_key).get();
+                    dex.order_status.setter(oco_key).set(U256::from(2u64)); // Cancelled
+                    track_order_escrow(dex, oco_token_out, oco_amount_out, U256::ZERO)?;
+                    safe_transfer(oco_token_out, oco_owner, oco_amount_out)?;
+                    emit_order_cancelled(oco_pair, oco_owner);
+                }
+                dex.order_oco_pair.setter(key).set(U256::ZERO);
+                dex.order_oco_pair.setter(oco_key).set(U256::ZERO);
             }
-            self.order_oco_pair.setter(key).set(U256::ZERO);
-            self.order_oco_pair.setter(oco_key).set(U256::ZERO);
-        }
 
-        unlock_reentrancy_guard(self);
-        Ok(amount_in_received)
+            Ok(amount_in_received)
+        })
     }
 
     /// View: get order details by ID.
@@ -1906,62 +5487,54 @@ impl OakDEX {
         entry_price: U256,
         initial_collateral: U256,
     ) -> OakResult<U256> {
-        lock_reentrancy_guard(self)?;
-        require_non_zero_address(base_token)?;
-        require_non_zero_address(quote_token)?;
-        if base_token == quote_token {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_INVALID_TOKEN));
-        }
-        if size.is_zero() {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_INSUFFICIENT_INPUT_AMOUNT));
-        }
-        require_not_paused(self)?;
-
-        let sender = msg::sender();
-        let contract_addr = contract::address();
-        if !initial_collateral.is_zero() {
-            let bal = balance_of(quote_token, sender);
-            if bal < initial_collateral {
-                unlock_reentrancy_guard(self);
-                return Err(err(ERR_MARGIN_ZERO_OR_INSUFFICIENT));
+        require_orders_enabled()?;
+        with_lock(self, |dex| {
+            require_non_zero_address(base_token)?;
+            require_non_zero_address(quote_token)?;
+            if base_token == quote_token {
+                return Err(err(ERR_INVALID_TOKEN));
+            }
+            if size.is_zero() {
+                return Err(err(ERR_INSUFFICIENT_INPUT_AMOUNT));
+            }
+            require_not_paused(dex)?;
+
+            let sender = msg::sender();
+            let contract_addr = contract::address();
+            if !initial_collateral.is_zero() {
+                let bal = balance_of(quote_token, sender);
+                if bal < initial_collateral {
+                    return Err(err(ERR_MARGIN_ZERO_OR_INSUFFICIENT));
+                }
+                safe_transfer_from(quote_token, sender, contract_addr, initial_collateral)?;
+                let prev = dex.position_margin_balance.setter(quote_token).get();
+                dex.position_margin_balance
+                    .setter(quote_token)
+                    .set(prev.checked_add(initial_collateral).ok_or_else(|| err(ERR_OVERFLOW))?);
             }
-            safe_transfer_from(quote_token, sender, contract_addr, initial_collateral)?;
-            let prev = self.position_margin_balance.setter(quote_token).get();
-            self.position_margin_balance
-                .setter(quote_token)
-                .set(prev.checked_add(initial_collateral).ok_or_else(|| {
-                    unlock_reentrancy_guard(self);
-                    err(ERR_OVERFLOW)
-                })?);
-        }
 
-        let next_id = self.next_position_id.get();
-        let new_id = next_id.checked_add(U256::from(1u64)).ok_or_else(|| {
-            unlock_reentrancy_guard(self);
-            err(ERR_OVERFLOW)
-        })?;
-        self.next_position_id.set(new_id);
-
-        let key = position_id_to_address(new_id);
-        self.position_owner.setter(key).set(sender);
-        self.position_base.setter(key).set(base_token);
-        self.position_quote.setter(key).set(quote_token);
-        self.position_size.setter(key).set(size);
-        self.position_entry_price.setter(key).set(entry_price);
-        self.position_tp_price.setter(key).set(U256::ZERO);
-        self.position_sl_price.setter(key).set(U256::ZERO);
-        self.position_trailing_delta_bps.setter(key).set(U256::ZERO);
-        self.position_trailing_peak_price.setter(key).set(U256::ZERO);
-        self.position_initial_collateral.setter(key).set(initial_collateral);
-        self.position_margin_added.setter(key).set(U256::ZERO);
-        self.position_opened_at.setter(key).set(U256::from(block::number()));
-        self.position_status.setter(key).set(U256::ZERO); // Open
-
-        emit_open_position(new_id, sender, base_token, quote_token, size, entry_price);
-        unlock_reentrancy_guard(self);
-        Ok(new_id)
+            let next_id = dex.next_position_id.get();
+            let new_id = next_id.checked_add(U256::from(1u64)).ok_or_else(|| err(ERR_OVERFLOW))?;
+            dex.next_position_id.set(new_id);
+
+            let key = position_id_to_address(new_id);
+            dex.position_owner.setter(key).set(sender);
+            dex.position_base.setter(key).set(base_token);
+            dex.position_quote.setter(key).set(quote_token);
+            dex.position_size.setter(key).set(size);
+            dex.position_entry_price.setter(key).set(entry_price);
+            dex.position_tp_price.setter(key).set(U256::ZERO);
+            dex.position_sl_price.setter(key).set(U256::ZERO);
+            dex.position_trailing_delta_bps.setter(key).set(U256::ZERO);
+            dex.position_trailing_peak_price.setter(key).set(U256::ZERO);
+            dex.position_initial_collateral.setter(key).set(initial_collateral);
+            dex.position_margin_added.setter(key).set(U256::ZERO);
+            dex.position_opened_at.setter(key).set(U256::from(block::number()));
+            dex.position_status.setter(key).set(U256::ZERO); // Open
+
+            emit_open_position(new_id, sender, base_token, quote_token, size, entry_price);
+            Ok(new_id)
+        })
     }
 
     /// Add margin to an open position (increases collateral, does not change entry_price or size).
@@ -1969,51 +5542,41 @@ impl OakDEX {
     /// @param amount Amount of quote token to add (18 decimals). Transferred from owner to contract.
     /// Liquidation price becomes (initial_collateral + margin_added + amount) / size.
     pub fn add_margin(&mut self, position_id: U256, amount: U256) -> OakResult<()> {
-        lock_reentrancy_guard(self)?;
-        if amount.is_zero() {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_MARGIN_ZERO_OR_INSUFFICIENT));
-        }
-        let sender = msg::sender();
-        let key = position_id_to_address(position_id);
-        let owner = self.position_owner.setter(key).get();
-        if owner == Address::ZERO {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_POSITION_NOT_FOUND));
-        }
-        if owner != sender {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_POSITION_NOT_OWNER));
-        }
-        let status = self.position_status.setter(key).get();
-        if status != U256::ZERO {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_POSITION_NOT_OPEN));
-        }
-        let quote_token = self.position_quote.setter(key).get();
-        let bal = balance_of(quote_token, sender);
-        if bal < amount {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_MARGIN_ZERO_OR_INSUFFICIENT));
-        }
-        let contract_addr = contract::address();
-        safe_transfer_from(quote_token, sender, contract_addr, amount)?;
-        let prev_added = self.position_margin_added.setter(key).get();
-        self.position_margin_added
-            .setter(key)
-            .set(prev_added.checked_add(amount).ok_or_else(|| {
-                unlock_reentrancy_guard(self);
-                err(ERR_OVERFLOW)
-            })?);
-        let prev_balance = self.position_margin_balance.setter(quote_token).get();
-        self.position_margin_balance
-            .setter(quote_token)
-            .set(prev_balance.checked_add(amount).ok_or_else(|| {
-                unlock_reentrancy_guard(self);
-                err(ERR_OVERFLOW)
-            })?);
-        unlock_reentrancy_guard(self);
-        Ok(())
+        require_orders_enabled()?;
+        with_lock(self, |dex| {
+            if amount.is_zero() {
+                return Err(err(ERR_MARGIN_ZERO_OR_INSUFFICIENT));
+            }
+            let sender = msg::sender();
+            let key = position_id_to_address(position_id);
+            let owner = dex.position_owner.setter(key).get();
+            if owner == Address::ZERO {
+                return Err(err(ERR_POSITION_NOT_FOUND));
+            }
+            if owner != sender {
+                return Err(err(ERR_POSITION_NOT_OWNER));
+            }
+            let status = dex.position_status.setter(key).get();
+            if status != U256::ZERO {
+                return Err(err(ERR_POSITION_NOT_OPEN));
+            }
+            let quote_token = dex.position_quote.setter(key).get();
+            let bal = balance_of(quote_token, sender);
+            if bal < amount {
+                return Err(err(ERR_MARGIN_ZERO_OR_INSUFFICIENT));
+            }
+            let contract_addr = contract::address();
+            safe_transfer_from(quote_token, sender, contract_addr, amount)?;
+            let prev_added = dex.position_margin_added.setter(key).get();
+            dex.position_margin_added
+                .setter(key)
+                .set(prev_added.checked_add(amount).ok_or_else(|| err(ERR_OVERFLOW))?);
+            let prev_balance = dex.position_margin_balance.setter(quote_token).get();
+            dex.position_margin_balance
+                .setter(quote_token)
+                .set(prev_balance.checked_add(amount).ok_or_else(|| err(ERR_OVERFLOW))?);
+            Ok(())
+        })
     }
 
     /// Set or update Take-Profit and Stop-Loss prices for an open position.
@@ -2026,6 +5589,7 @@ impl OakDEX {
         tp_price: U256,
         sl_price: U256,
     ) -> OakResult<()> {
+        require_orders_enabled()?;
         let sender = msg::sender();
         let key = position_id_to_address(position_id);
         let owner = self.position_owner.setter(key).get();
@@ -2054,6 +5618,7 @@ impl OakDEX {
         position_id: U256,
         trailing_delta_bps: U256,
     ) -> OakResult<()> {
+        require_orders_enabled()?;
         let sender = msg::sender();
         let key = position_id_to_address(position_id);
         let owner = self.position_owner.setter(key).get();
@@ -2091,280 +5656,312 @@ impl OakDEX {
         new_price: U256,
         min_amount_out: U256,
     ) -> OakResult<U256> {
-        lock_reentrancy_guard(self)?;
-        let key = position_id_to_address(position_id);
-        let owner = self.position_owner.setter(key).get();
-        if owner == Address::ZERO {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_POSITION_NOT_FOUND));
-        }
-        let status = self.position_status.setter(key).get();
-        if status != U256::ZERO {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_POSITION_NOT_OPEN));
-        }
-        let delta_bps = self.position_trailing_delta_bps.setter(key).get();
-        if delta_bps.is_zero() {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_TRAILING_DISABLED));
-        }
-        let mut peak = self.position_trailing_peak_price.setter(key).get();
-        if new_price > peak {
-            peak = new_price;
-            self.position_trailing_peak_price.setter(key).set(peak);
-        }
-        let bps_u = as_u256(10_000u64);
-        let trigger_num = peak
-            .checked_mul(bps_u.saturating_sub(delta_bps))
-            .ok_or_else(|| {
-                unlock_reentrancy_guard(self);
-                err(ERR_OVERFLOW)
-            })?;
-        let trigger_price = trigger_num
-            .checked_div(bps_u)
-            .ok_or_else(|| {
-                unlock_reentrancy_guard(self);
-                err(ERR_DIVISION_BY_ZERO)
-            })?;
-        if new_price > trigger_price {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_TRAILING_NOT_TRIGGERED));
-        }
-        let base_token = self.position_base.setter(key).get();
-        let quote_token = self.position_quote.setter(key).get();
-        let size = self.position_size.setter(key).get();
-        let initial_collateral = self.position_initial_collateral.setter(key).get();
-        let margin_added = self.position_margin_added.setter(key).get();
-        let margin_total = initial_collateral
-            .checked_add(margin_added)
-            .ok_or_else(|| {
-                unlock_reentrancy_guard(self);
-                err(ERR_OVERFLOW)
-            })?;
-        if !margin_total.is_zero() {
-            let prev = self.position_margin_balance.setter(quote_token).get();
-            self.position_margin_balance
-                .setter(quote_token)
-                .set(prev.checked_sub(margin_total).ok_or_else(|| {
-                    unlock_reentrancy_guard(self);
-                    err(ERR_OVERFLOW)
-                })?);
-            self.position_initial_collateral.setter(key).set(U256::ZERO);
-            self.position_margin_added.setter(key).set(U256::ZERO);
-            safe_transfer(quote_token, owner, margin_total)?;
-        }
-        let amount_out = process_swap_from_to(
-            self,
-            owner,
-            owner,
-            base_token,
-            quote_token,
-            size,
-            min_amount_out,
-        )?;
-        self.position_status.setter(key).set(U256::from(1u64)); // Closed
-        emit_close_position(position_id, owner, amount_out);
-        emit_trailing_stop_triggered(position_id, owner, peak, trigger_price, amount_out);
-        unlock_reentrancy_guard(self);
-        Ok(amount_out)
+        require_orders_enabled()?;
+        with_lock(self, |dex| {
+            let key = position_id_to_address(position_id);
+            let owner = dex.position_owner.setter(key).get();
+            if owner == Address::ZERO {
+                return Err(err(ERR_POSITION_NOT_FOUND));
+            }
+            let status = dex.position_status.setter(key).get();
+            if status != U256::ZERO {
+                return Err(err(ERR_POSITION_NOT_OPEN));
+            }
+            let delta_bps = dex.position_trailing_delta_bps.setter(key).get();
+            if delta_bps.is_zero() {
+                return Err(err(ERR_TRAILING_DISABLED));
+            }
+            let mut peak = dex.position_trailing_peak_price.setter(key).get();
+            if new_price > peak {
+                peak = new_price;
+                dex.position_trailing_peak_price.setter(key).set(peak);
+            }
+            let bps_u = as_u256(10_000u64);
+            let trigger_num = peak
+                .checked_mul(bps_u.saturating_sub(delta_bps))
+                .ok_or_else(|| err(ERR_OVERFLOW))?;
+            let trigger_price = trigger_num
+                .checked_div(bps_u)
+                .ok_or_else(|| err(ERR_DIVISION_BY_ZERO))?;
+            if new_price > trigger_price {
+                return Err(err(ERR_TRAILING_NOT_TRIGGERED));
+            }
+            let base_token = dex.position_base.setter(key).get();
+            let quote_token = dex.position_quote.setter(key).get();
+            let size = dex.position_size.setter(key).get();
+            let initial_collateral = dex.position_initial_collateral.setter(key).get();
+            let margin_added = dex.position_margin_added.setter(key).get();
+            let margin_total = initial_collateral
+                .checked_add(margin_added)
+                .ok_or_else(|| err(ERR_OVERFLOW))?;
+            if !margin_total.is_zero() {
+                let prev = dex.position_margin_balance.setter(quote_token).get();
+                dex.position_margin_balance
+                    .setter(quote_token)
+                    .set(prev.checked_sub(margin_total).ok_or_else(|| err(ERR_OVERFLOW))?);
+                dex.position_initial_collateral.setter(key).set(U256::ZERO);
+                dex.position_margin_added.setter(key).set(U256::ZERO);
+                safe_transfer(quote_token, owner, margin_total)?;
+            }
+            let amount_out = process_swap_from_to(
+                dex,
+                owner,
+                owner,
+                base_token,
+                quote_token,
+                size,
+                min_amount_out,
+            )?;
+            dex.position_status.setter(key).set(U256::from(1u64)); // Closed
+            emit_close_position(position_id, owner, amount_out);
+            emit_trailing_stop_triggered(position_id, owner, peak, trigger_price, amount_out);
+            Ok(amount_out)
+        })
     }
 
     /// Close an open position: return margin to owner, market-sell base for quote, mark closed.
     ///
     /// @param min_amount_out Slippage protection (minimum quote to receive from swap).
     pub fn close_position(&mut self, position_id: U256, min_amount_out: U256) -> OakResult<U256> {
-        lock_reentrancy_guard(self)?;
-        require_not_paused(self)?;
-        let sender = msg::sender();
-        let key = position_id_to_address(position_id);
-        let owner = self.position_owner.setter(key).get();
-        if owner == Address::ZERO {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_POSITION_NOT_FOUND));
-        }
-        if owner != sender {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_POSITION_NOT_OWNER));
-        }
-        let status = self.position_status.setter(key).get();
-        if status != U256::ZERO {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_POSITION_NOT_OPEN));
-        }
+        require_orders_enabled()?;
+        with_lock(self, |dex| {
+            require_not_paused(dex)?;
+            let sender = msg::sender();
+            let key = position_id_to_address(position_id);
+            let owner = dex.position_owner.setter(key).get();
+            if owner == Address::ZERO {
+                return Err(err(ERR_POSITION_NOT_FOUND));
+            }
+            if owner != sender {
+                return Err(err(ERR_POSITION_NOT_OWNER));
+            }
+            let status = dex.position_status.setter(key).get();
+            if status != U256::ZERO {
+                return Err(err(ERR_POSITION_NOT_OPEN));
+            }
 
-        let base_token = self.position_base.setter(key).get();
-        let quote_token = self.position_quote.setter(key).get();
-        let size = self.position_size.setter(key).get();
-        let initial_collateral = self.position_initial_collateral.setter(key).get();
-        let margin_added = self.position_margin_added.setter(key).get();
-        let margin_total = initial_collateral
-            .checked_add(margin_added)
-            .ok_or_else(|| {
-                unlock_reentrancy_guard(self);
-                err(ERR_OVERFLOW)
-            })?;
+            let base_token = dex.position_base.setter(key).get();
+            let quote_token = dex.position_quote.setter(key).get();
+            let size = dex.position_size.setter(key).get();
+            let initial_collateral = dex.position_initial_collateral.setter(key).get();
+            let margin_added = dex.position_margin_added.setter(key).get();
+            let margin_total = initial_collateral
+                .checked_add(margin_added)
+                .ok_or_else(|| err(ERR_OVERFLOW))?;
 
-        if !margin_total.is_zero() {
-            let prev = self.position_margin_balance.setter(quote_token).get();
-            self.position_margin_balance
-                .setter(quote_token)
-                .set(prev.checked_sub(margin_total).ok_or_else(|| {
-                    unlock_reentrancy_guard(self);
-                    err(ERR_OVERFLOW)
-                })?);
-            self.position_initial_collateral.setter(key).set(U256::ZERO);
-            self.position_margin_added.setter(key).set(U256::ZERO);
-            safe_transfer(quote_token, owner, margin_total)?;
-        }
+            if !margin_total.is_zero() {
+                let prev = dex.position_margin_balance.setter(quote_token).get();
+                dex.position_margin_balance
+                    .setter(quote_token)
+                    .set(prev.checked_sub(margin_total).ok_or_else(|| err(ERR_OVERFLOW))?);
+                dex.position_initial_collateral.setter(key).set(U256::ZERO);
+                dex.position_margin_added.setter(key).set(U256::ZERO);
+                safe_transfer(quote_token, owner, margin_total)?;
+            }
 
-        let amount_out = process_swap_from_to(
-            self,
-            sender,
-            sender,
-            base_token,
-            quote_token,
-            size,
-            min_amount_out,
-        )?;
-        self.position_status.setter(key).set(U256::from(1u64)); // Closed
-        emit_close_position(position_id, sender, amount_out);
-        unlock_reentrancy_guard(self);
-        Ok(amount_out)
+            let amount_out = process_swap_from_to(
+                dex,
+                sender,
+                sender,
+                base_token,
+                quote_token,
+                size,
+                min_amount_out,
+            )?;
+            dex.position_status.setter(key).set(U256::from(1u64)); // Closed
+            emit_close_position(position_id, sender, amount_out);
+            Ok(amount_out)
+        })
     }
 
-    /// Batch-close positions: one aggregated swap + gas-rebate (fee discount) shared among participants.
+    /// Batch-close positions: offsetting flow is crossed internally, the residual takes one aggregated swap.
     ///
-    /// @notice Shared Execution Gas-Rebate: all positions must share the same (base, quote) pair.
-    ///         Contract pulls base from each owner, does ONE swap, distributes quote proportionally.
-    ///         Fee discount (BATCH_FEE_REBATE_BPS) is applied so participants receive more output.
+    /// @notice Shared Execution Gas-Rebate: positions must be on the same pair, in either orientation.
+    ///         Positions selling the first position's base ("forward") and positions selling its quote
+    ///         ("reverse") are netted at the pool spot price first (coincidence of wants), charging only
+    ///         the protocol fee on crossed volume. The residual goes through the curve as ONE swap with
+    ///         the batch fee discount (BATCH_FEE_REBATE_BPS). Each side's proceeds are split pro rata by size.
+    ///         Crossing reverts (BATCH_PRICE_DEVIATION) while the spot price is more than
+    ///         MAX_BATCH_CROSS_TWAP_DEVIATION_BPS from the pool TWAP, so it cannot be steered in-block.
     /// @param position_ids List of position IDs to close (min 2, max MAX_BATCH_POSITIONS).
-    /// @param min_amount_out Slippage protection for the aggregated swap.
+    /// @param min_amount_out Slippage protection on the total quote received by the forward side.
     pub fn batch_execute_positions(
         &mut self,
         position_ids: Vec<U256>,
         min_amount_out: U256,
     ) -> OakResult<U256> {
+        require_orders_enabled()?;
         let sender = msg::sender();
-        lock_reentrancy_guard(self)?;
-        require_not_paused(self)?;
+        with_lock(self, |dex| {
+            require_not_paused(dex)?;
 
-        let n = position_ids.len() as u64;
-        if n < 2 {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_BATCH_TOO_FEW));
-        }
-        if n > MAX_BATCH_POSITIONS {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_BATCH_TOO_MANY));
-        }
-
-        let contract_addr = contract::address();
-        let mut base_token = Address::ZERO;
-        let mut quote_token = Address::ZERO;
-        let mut total_size = U256::ZERO;
-        // (owner, size, margin_total, key)
-        let mut items: Vec<(Address, U256, U256, Address)> = Vec::with_capacity(position_ids.len());
-
-        for position_id in &position_ids {
-            let key = position_id_to_address(*position_id);
-            let owner = self.position_owner.setter(key).get();
-            if owner == Address::ZERO {
-                unlock_reentrancy_guard(self);
-                return Err(err(ERR_POSITION_NOT_FOUND));
+            let n = position_ids.len() as u64;
+            if n < 2 {
+                return Err(err(ERR_BATCH_TOO_FEW));
             }
-            let status = self.position_status.setter(key).get();
-            if status != U256::ZERO {
-                unlock_reentrancy_guard(self);
-                return Err(err(ERR_POSITION_NOT_OPEN));
+            if n > MAX_BATCH_POSITIONS {
+                return Err(err(ERR_BATCH_TOO_MANY));
             }
-            let base = self.position_base.setter(key).get();
-            let quote = self.position_quote.setter(key).get();
-            if base_token == Address::ZERO {
-                base_token = base;
-                quote_token = quote;
-            } else if base != base_token || quote != quote_token {
-                unlock_reentrancy_guard(self);
-                return Err(err(ERR_BATCH_NOT_SAME_PAIR));
-            }
-            let size = self.position_size.setter(key).get();
-            let initial_collateral = self.position_initial_collateral.setter(key).get();
-            let margin_added = self.position_margin_added.setter(key).get();
-            let margin_total = initial_collateral
-                .checked_add(margin_added)
-                .ok_or_else(|| {
-                    unlock_reentrancy_guard(self);
-                    err(ERR_OVERFLOW)
-                })?;
-            total_size = total_size.checked_add(size).ok_or_else(|| {
-                unlock_reentrancy_guard(self);
-                err(ERR_OVERFLOW)
-            })?;
-            items.push((owner, size, margin_total, key));
-        }
-
-        if total_size.is_zero() {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_INSUFFICIENT_INPUT_AMOUNT));
-        }
 
-        // Return margin to each owner and pull base from each owner into contract.
-        for (owner, size, margin_total, key) in &items {
-            self.position_initial_collateral.setter(*key).set(U256::ZERO);
-            self.position_margin_added.setter(*key).set(U256::ZERO);
-            if !margin_total.is_zero() {
-                let prev = self.position_margin_balance.setter(quote_token).get();
-                self.position_margin_balance
-                    .setter(quote_token)
-                    .set(prev.checked_sub(*margin_total).ok_or_else(|| {
-                        unlock_reentrancy_guard(self);
-                        err(ERR_OVERFLOW)
-                    })?);
-                safe_transfer(quote_token, *owner, *margin_total)?;
+            let contract_addr = contract::address();
+            let mut base_token = Address::ZERO;
+            let mut quote_token = Address::ZERO;
+            // Forward side sells base_token; reverse side sells quote_token.
+            let mut total_forward = U256::ZERO;
+            let mut total_reverse = U256::ZERO;
+            // (owner, size, margin_total, key, forward)
+            let mut items: Vec<(Address, U256, U256, Address, bool)> = Vec::with_capacity(position_ids.len());
+
+            for position_id in &position_ids {
+                let key = position_id_to_address(*position_id);
+                let owner = dex.position_owner.setter(key).get();
+                if owner == Address::ZERO {
+                    return Err(err(ERR_POSITION_NOT_FOUND));
+                }
+                let status = dex.position_status.setter(key).get();
+                if status != U256::ZERO {
+                    return Err(err(ERR_POSITION_NOT_OPEN));
+                }
+                let base = dex.position_base.setter(key).get();
+                let quote = dex.position_quote.setter(key).get();
+                if base_token == Address::ZERO {
+                    base_token = base;
+                    quote_token = quote;
+                }
+                let forward = if base == base_token && quote == quote_token {
+                    true
+                } else if base == quote_token && quote == base_token {
+                    false
+                } else {
+                    return Err(err(ERR_BATCH_NOT_SAME_PAIR));
+                };
+                let size = dex.position_size.setter(key).get();
+                let initial_collateral = dex.position_initial_collateral.setter(key).get();
+                let margin_added = dex.position_margin_added.setter(key).get();
+                let margin_total = initial_collateral
+                    .checked_add(margin_added)
+                    .ok_or_else(|| err(ERR_OVERFLOW))?;
+                items.push((owner, size, margin_total, key, forward));
             }
-            safe_transfer_from(base_token, *owner, contract_addr, *size)?;
-        }
 
-        // One swap with fee rebate (batch discount).
-        let fee_bps = self.protocol_fee_bps.get();
-        let effective_fee_bps = fee_bps
-            .checked_mul(as_u256(BPS).checked_sub(as_u256(BATCH_FEE_REBATE_BPS)).ok_or_else(|| err(ERR_OVERFLOW))?)
-            .ok_or_else(|| err(ERR_OVERFLOW))?
-            .checked_div(as_u256(BPS))
-            .ok_or_else(|| err(ERR_DIVISION_BY_ZERO))?;
-
-        let total_quote_out = process_swap_from_to_with_fee(
-            self,
-            contract_addr,
-            contract_addr,
-            base_token,
-            quote_token,
-            total_size,
-            min_amount_out,
-            effective_fee_bps,
-        )?;
+            // Return margin to each owner and pull the sold token from each owner into contract.
+            // Fee-on-transfer tokens: each position sells (and is paid pro rata for) what arrived.
+            let pool_key = if base_token < quote_token { (base_token, quote_token) } else { (quote_token, base_token) };
+            for (owner, size, margin_total, key, forward) in items.iter_mut() {
+                let (sold, margin_token) = if *forward { (base_token, quote_token) } else { (quote_token, base_token) };
+                dex.position_initial_collateral.setter(*key).set(U256::ZERO);
+                dex.position_margin_added.setter(*key).set(U256::ZERO);
+                if !margin_total.is_zero() {
+                    let prev = dex.position_margin_balance.setter(margin_token).get();
+                    dex.position_margin_balance
+                        .setter(margin_token)
+                        .set(prev.checked_sub(*margin_total).ok_or_else(|| err(ERR_OVERFLOW))?);
+                    safe_transfer(margin_token, *owner, *margin_total)?;
+                }
+                *size = pull_received(dex, pool_key, sold, *owner, *size)?;
+                let side_total = if *forward { &mut total_forward } else { &mut total_reverse };
+                *side_total = side_total.checked_add(*size).ok_or_else(|| err(ERR_OVERFLOW))?;
+            }
+            if total_forward.is_zero() {
+                return Err(err(ERR_INSUFFICIENT_INPUT_AMOUNT));
+            }
 
-        // Distribute quote to each owner proportionally (by size); mark closed.
-        for (i, (owner, size, _margin_total, key)) in items.iter().enumerate() {
-            let share = total_quote_out
-                .checked_mul(*size)
+            let fee_bps = pool_fee_bps(dex, base_token, quote_token);
+            let effective_fee_bps = fee_bps
+                .checked_mul(as_u256(BPS).checked_sub(as_u256(BATCH_FEE_REBATE_BPS)).ok_or_else(|| err(ERR_OVERFLOW))?)
                 .ok_or_else(|| err(ERR_OVERFLOW))?
-                .checked_div(total_size)
+                .checked_div(as_u256(BPS))
                 .ok_or_else(|| err(ERR_DIVISION_BY_ZERO))?;
-            self.position_status.setter(*key).set(U256::from(1u64));
-            let pos_id = *position_ids.get(i).unwrap_or(&U256::ZERO);
-            emit_close_position(pos_id, *owner, share);
-            safe_transfer(quote_token, *owner, share)?;
-        }
 
-        emit_batch_positions_executed(
-            sender,
-            total_size,
-            total_quote_out,
-            as_u256(BATCH_FEE_REBATE_BPS),
-            U256::from(n),
-        );
-        unlock_reentrancy_guard(self);
-        Ok(total_quote_out)
+            // Coincidence of wants: cross offsetting flow at spot before touching the curve.
+            let (crossed_base, crossed_quote) = if total_reverse.is_zero() {
+                (U256::ZERO, U256::ZERO)
+            } else {
+                let (reserve_base, reserve_quote) = {
+                    let (t0, t1) = if base_token < quote_token { (base_token, quote_token) } else { (quote_token, base_token) };
+                    let mut outer = dex.pools.setter(t0);
+                    let pool = outer.setter(t1);
+                    if !pool.initialized.get() {
+                        return Err(err(ERR_INVALID_TOKEN));
+                    }
+                    if base_token == t0 {
+                        (pool.reserve0.get(), pool.reserve1.get())
+                    } else {
+                        (pool.reserve1.get(), pool.reserve0.get())
+                    }
+                };
+                // Cross at spot only while it agrees with the TWAP (base priced in quote, Q112).
+                let (spot, _) = spot_prices(reserve_base, reserve_quote).map_err(err)?;
+                let (twap, _, _) = PoolOracle::twap(dex, base_token, quote_token)?;
+                check_batch_crossing_price(spot, twap)?;
+                compute_batch_crossing(total_forward, total_reverse, reserve_base, reserve_quote)?
+            };
+            // Forward side receives the reverse side's crossed quote; reverse side receives crossed base.
+            let mut forward_out = accrue_crossing_fee(dex, quote_token, crossed_quote, effective_fee_bps)?;
+            let mut reverse_out = accrue_crossing_fee(dex, base_token, crossed_base, effective_fee_bps)?;
+
+            // Residual (at most one side) takes ONE swap on the curve.
+            let residual_forward = total_forward.checked_sub(crossed_base).ok_or_else(|| err(ERR_OVERFLOW))?;
+            let residual_reverse = total_reverse.checked_sub(crossed_quote).ok_or_else(|| err(ERR_OVERFLOW))?;
+            if !residual_forward.is_zero() {
+                let out = process_swap_from_to_with_fee(
+                    dex,
+                    contract_addr,
+                    contract_addr,
+                    base_token,
+                    quote_token,
+                    residual_forward,
+                    U256::from(1u64),
+                    effective_fee_bps,
+                )?;
+                forward_out = forward_out.checked_add(out).ok_or_else(|| err(ERR_OVERFLOW))?;
+            } else if !residual_reverse.is_zero() {
+                let out = process_swap_from_to_with_fee(
+                    dex,
+                    contract_addr,
+                    contract_addr,
+                    quote_token,
+                    base_token,
+                    residual_reverse,
+                    U256::from(1u64),
+                    effective_fee_bps,
+                )?;
+                reverse_out = reverse_out.checked_add(out).ok_or_else(|| err(ERR_OVERFLOW))?;
+            }
+            if forward_out < min_amount_out {
+                return Err(err(ERR_SLIPPAGE_EXCEEDED));
+            }
+
+            // Distribute each side's proceeds proportionally (by size); mark closed.
+            for (i, (owner, size, _margin_total, key, forward)) in items.iter().enumerate() {
+                let (proceeds, side_total, token_out) = if *forward {
+                    (forward_out, total_forward, quote_token)
+                } else {
+                    (reverse_out, total_reverse, base_token)
+                };
+                let share = proceeds
+                    .checked_mul(*size)
+                    .ok_or_else(|| err(ERR_OVERFLOW))?
+                    .checked_div(side_total)
+                    .ok_or_else(|| err(ERR_DIVISION_BY_ZERO))?;
+                dex.position_status.setter(*key).set(U256::from(1u64));
+                let pos_id = *position_ids.get(i).unwrap_or(&U256::ZERO);
+                emit_close_position(pos_id, *owner, share);
+                safe_transfer(token_out, *owner, share)?;
+            }
+
+            if !crossed_base.is_zero() {
+                emit_batch_crossed(base_token, quote_token, crossed_base, crossed_quote);
+            }
+            emit_batch_positions_executed(
+                sender,
+                total_forward,
+                forward_out,
+                as_u256(BATCH_FEE_REBATE_BPS),
+                U256::from(n),
+            );
+            Ok(forward_out)
+        })
     }
 
     /// Execute TP/SL for a position if condition met (anyone may call; keeper-friendly).
@@ -2375,6 +5972,7 @@ impl OakDEX {
         position_id: U256,
         min_amount_out: U256,
     ) -> OakResult<U256> {
+        require_orders_enabled()?;
         require_not_paused(self)?;
         let key = position_id_to_address(position_id);
         let owner = self.position_owner.setter(key).get();
@@ -2498,99 +6096,631 @@ impl OakDEX {
         token_to: Address,
         min_amount_out: U256,
     ) -> OakResult<U256> {
+        require_orders_enabled()?;
         if amount_in.is_zero() {
             return Err(err(ERR_INSUFFICIENT_INPUT_AMOUNT));
         }
-        lock_reentrancy_guard(self)?;
-        let out = process_swap(self, token_from, token_to, amount_in, min_amount_out)?;
-        unlock_reentrancy_guard(self);
-        Ok(out)
+        with_lock(self, |dex| process_swap(dex, token_from, token_to, amount_in, min_amount_out, None))
     }
 
-    /// Cancel an expired or unwanted commitment.
-    ///
-    /// @notice Allows users to clear their commitment if it has expired or they no longer
-    ///         wish to execute the swap. Prevents storage bloat from abandoned commitments.
-    /// @dev Can only cancel own commitment, and only if expired or minimum delay has passed.
+    /// Cancel the caller's commitment; what it costs depends on the commitment's phase.
     ///
-    /// # Returns
-    /// `Ok(())` on successful cancellation
+    /// @notice Phases follow `commitment_status`:
+    ///         - TOO_EARLY (reveal window not open yet): free, the whole bond is refunded.
+    ///           Emits CancelCommitment.
+    ///         - READY (live reveal window): COMMIT_LIVE_CANCEL_PENALTY_BPS of the bond goes to
+    ///           the treasury and the rest is refunded, so a trader cannot cancel for free once
+    ///           the price has moved. Emits CommitmentCancelledWithPenalty.
+    ///         - EXPIRED: the owner can no longer cancel (ERR_CANCEL_AFTER_EXPIRY); any keeper
+    ///           clears it with slash_expired_commitment, which forfeits the bond.
+    /// @dev State is cleared before the bond moves (CEI).
     pub fn cancel_commitment(&mut self) -> OakResult<()> {
-        let sender = msg::sender();
+        process_cancel_commitment(self, msg::sender())
+    }
+
+    /// Cancel `owner`'s open orders and/or pending commitment with their EIP-712 signature.
+    ///
+    /// @notice Meta-tx for market makers pulling quotes at risk-off moments: a relayer submits the
+    ///         signed CancelBatch and pays the gas. `order_ids` follow `cancel_orders` (filled or
+    ///         cancelled ones are skipped, one aggregated OrdersCancelled event); with
+    ///         `cancel_commitment` the pending commitment is cancelled as by `cancel_commitment`.
+    ///         Returns the number of orders cancelled.
+    /// @dev Deadline is a block number (PERMIT_EXPIRED past it). The signature must carry
+    ///      `owner`'s current cancel nonce, which is consumed before anything is cancelled.
+    #[allow(clippy::too_many_arguments)]
+    pub fn cancel_with_signature(
+        &mut self,
+        owner: Address,
+        order_ids: Vec<U256>,
+        cancel_commitment: bool,
+        deadline: U256,
+        nonce: U256,
+        v: u8,
+        r: FixedBytes<32>,
+        s: FixedBytes<32>,
+    ) -> OakResult<U256> {
+        require_non_zero_address(owner)?;
+        if (order_ids.is_empty() && !cancel_commitment) || order_ids.len() as u64 > MAX_BATCH_CANCEL {
+            return Err(err(ERR_CANCEL_BATCH_INVALID));
+        }
+        if !order_ids.is_empty() {
+            require_orders_enabled()?;
+        }
+        let current_nonce = self.cancel_nonce.get(owner);
+        check_permit(U256::from(block::number()), deadline, nonce, current_nonce)?;
+        let domain_separator = compute_domain_separator(contract::address(), CHAIN_ID_ARBITRUM_ONE);
+        let digest = compute_cancel_batch_digest(owner, &order_ids, cancel_commitment, nonce, deadline, &domain_separator);
+        if ecrecover_recover(digest, v, r.0, s.0) != owner {
+            return Err(err(ERR_PERMIT_INVALID_SIGNATURE));
+        }
+        self.cancel_nonce
+            .setter(owner)
+            .set(current_nonce.checked_add(U256::from(1u64)).ok_or_else(|| err(ERR_OVERFLOW))?);
 
-        // Check if commitment exists
-        let is_activated = self.commitment_activated.setter(sender).get();
-        if !is_activated {
-            return Err(err(ERR_COMMIT_NOT_FOUND));
+        let mut cancelled = U256::ZERO;
+        if !order_ids.is_empty() {
+            cancelled = with_lock(self, |dex| {
+                let ids = cancel_orders_of(dex, owner, &order_ids)?;
+                emit_orders_cancelled(owner, &ids);
+                Ok(U256::from(ids.len()))
+            })?;
+        }
+        if cancel_commitment {
+            process_cancel_commitment(self, owner)?;
         }
+        Ok(cancelled)
+    }
 
-        let commit_block = self.commitment_timestamps.setter(sender).get();
-        let current_block = U256::from(block::number());
+    /// Returns the current cancel nonce for `owner` (for EIP-712 signed cancellation).
+    pub fn get_cancel_nonce(&self, owner: Address) -> U256 {
+        self.cancel_nonce.get(owner)
+    }
 
-        // Allow cancellation if:
-        // 1. Commitment has expired (older than MAX_COMMITMENT_AGE blocks), OR
-        // 2. Minimum delay has passed (user can cancel after reveal window)
-        let max_block = commit_block
-            .checked_add(as_u256(MAX_COMMITMENT_AGE))
-            .ok_or_else(|| err(ERR_BLOCK_OVERFLOW))?;
+    /// Clear an abandoned commitment and send its bond to the treasury (anyone may call).
+    ///
+    /// @notice The keeper path of the cancellation phases: only once the commitment is past the
+    ///         max commitment age, after which the owner can no longer cancel it.
+    /// @return The slashed bond amount (0 if the commitment carried no bond).
+    pub fn slash_expired_commitment(&mut self, user: Address) -> OakResult<U256> {
+        let (_, max_age) = commit_reveal_params(self);
+        let reveal_delay = commitment_reveal_delay(self, user);
+        let commitment = self.commitments.getter(user);
+        let active = !commitment.hash.get().is_zero();
+        match commitment_status(active, commitment.block.get(), U256::from(block::number()), reveal_delay, max_age) {
+            COMMIT_STATUS_NONE => return Err(err(ERR_COMMIT_NOT_FOUND)),
+            COMMIT_STATUS_EXPIRED => {}
+            _ => return Err(err(ERR_COMMITMENT_NOT_EXPIRED)),
+        }
+        expire_commitment(self, user)
+    }
 
-        let min_block = commit_block
-            .checked_add(as_u256(COMMIT_REVEAL_DELAY))
-            .ok_or_else(|| err(ERR_BLOCK_OVERFLOW))?;
+    /// Configure the bond posted by commit_swap (ROLE_ADMIN only; amount 0 disables).
+    ///
+    /// @notice Bonds already held keep the token and amount they were posted with.
+    pub fn set_commit_bond(&mut self, token: Address, amount: U256) -> OakResult<()> {
+        require_role(self, ROLE_ADMIN)?;
+        if !amount.is_zero() && token == Address::ZERO {
+            return Err(err(ERR_INVALID_COMMIT_BOND));
+        }
+        AdminAuditLog::record(self, AUDIT_ACTION_SET_COMMIT_BOND, &[token.into_word(), word(amount)])?;
+        self.commit_bond_token.set(token);
+        self.commit_bond_amount.set(amount);
+        emit_commit_bond_set(token, amount);
+        Ok(())
+    }
 
-        // Can cancel if expired OR if minimum delay has passed
-        if current_block <= max_block && current_block < min_block {
-            // Cannot cancel: commitment is still valid and within reveal window
-            return Err(err(ERR_TOO_EARLY));
+    /// View: bond required by commit_swap as (token, amount).
+    pub fn get_commit_bond(&self) -> OakResult<(Address, U256)> {
+        Ok((self.commit_bond_token.get(), self.commit_bond_amount.get()))
+    }
+
+    /// Prepay `amount` of `token` to pay the commit bonds of the caller's vouchers.
+    ///
+    /// @notice Deposit in the commit bond token (`get_commit_bond`); pulled via transferFrom.
+    pub fn deposit_commit_sponsorship(&mut self, token: Address, amount: U256) -> OakResult<()> {
+        if token == Address::ZERO {
+            return Err(err(ERR_INVALID_TOKEN));
         }
+        if amount.is_zero() {
+            return Err(err(ERR_ZERO_AMOUNT));
+        }
+        let sponsor = msg::sender();
+        with_lock(self, |dex| {
+            safe_transfer_from(token, sponsor, contract::address(), amount)?;
+            CommitSponsorship::credit(dex, sponsor, token, amount)?;
+            emit_commit_sponsorship_deposited(sponsor, token, amount);
+            Ok(())
+        })
+    }
+
+    /// Withdraw `amount` of the caller's unused prepaid sponsorship balance in `token`.
+    ///
+    /// @dev Bonds held on open sponsored commitments are not withdrawable until they return.
+    pub fn withdraw_commit_sponsorship(&mut self, token: Address, amount: U256) -> OakResult<()> {
+        let sponsor = msg::sender();
+        with_lock(self, |dex| {
+            CommitSponsorship::debit(dex, sponsor, token, amount)?;
+            safe_transfer(token, sponsor, amount)?;
+            emit_commit_sponsorship_withdrawn(sponsor, token, amount);
+            Ok(())
+        })
+    }
+
+    /// Issue a commit voucher good for `uses` commits, paid from the caller's prepaid balance.
+    ///
+    /// @notice `redeemer` zero lets anyone holding the id redeem it; hand such ids out privately.
+    /// @return The voucher id for `commit_swap_with_voucher`.
+    pub fn issue_commit_voucher(&mut self, redeemer: Address, uses: U256) -> OakResult<U256> {
+        CommitSponsorship::issue(self, msg::sender(), redeemer, uses)
+    }
+
+    /// Cancel the remaining uses of one of the caller's vouchers.
+    pub fn revoke_commit_voucher(&mut self, voucher_id: U256) -> OakResult<()> {
+        CommitSponsorship::revoke(self, msg::sender(), voucher_id)
+    }
+
+    /// View: voucher `voucher_id` as (sponsor, redeemer, remaining uses); sponsor zero = unknown.
+    pub fn get_commit_voucher(&self, voucher_id: U256) -> (Address, Address, U256) {
+        CommitSponsorship::get(self, voucher_id)
+    }
+
+    /// View: prepaid commit sponsorship balance of `sponsor` in `token`.
+    pub fn get_sponsor_balance(&self, sponsor: Address, token: Address) -> U256 {
+        CommitSponsorship::balance(self, sponsor, token)
+    }
+
+    /// Enable or disable output escrow for the caller.
+    ///
+    /// @notice When enabled, reveal_swap credits the output to a claimable balance
+    ///         instead of transferring it (for recipients that cannot receive atomically).
+    pub fn set_escrow_output(&mut self, enabled: bool) -> OakResult<()> {
+        OutputEscrow::set_mode(self, enabled);
+        Ok(())
+    }
+
+    /// Claim the caller's escrowed output for `token`. Returns the amount transferred.
+    ///
+    /// @dev `token` = address(0) claims native ETH.
+    pub fn claim_output(&mut self, token: Address) -> OakResult<U256> {
+        with_lock(self, |dex| OutputEscrow::claim(dex, token))
+    }
+
+    /// Move part of the caller's claimable `token` balance to `to` (fee-free, no ERC-20 transfer).
+    pub fn transfer_claimable(&mut self, to: Address, token: Address, amount: U256) -> OakResult<()> {
+        require_non_zero_address(to)?;
+        require_non_zero_address(token)?;
+        with_lock(self, |dex| OutputEscrow::transfer(dex, to, token, amount))
+    }
+
+    /// Swap directly out of the caller's claimable balance; the output is credited back to it.
+    ///
+    /// @notice Re-uses escrowed proceeds as swap input without leaving the contract, saving the
+    ///         claim and re-deposit ERC-20 transfers. Claim the result with `claim_output`.
+    ///         Emits ClaimableSwap (not RevealSwap: no commitment is involved).
+    /// @param deadline Block timestamp after which the call reverts (as swap_exact_tokens_for_tokens).
+    pub fn swap_from_claimable(
+        &mut self,
+        token_in: Address,
+        token_out: Address,
+        amount_in: U256,
+        min_amount_out: U256,
+        deadline: U256,
+    ) -> OakResult<U256> {
+        require_non_zero_address(token_in)?;
+        require_non_zero_address(token_out)?;
+        with_lock(self, |dex| {
+            check_deadline(U256::from(block::timestamp()), deadline)?;
+            let sender = msg::sender();
+            let contract_addr = contract::address();
+            OutputEscrow::debit(dex, sender, token_in, amount_in)?;
+            let amount_out = process_swap_from_to(
+                dex,
+                contract_addr,
+                contract_addr,
+                token_in,
+                token_out,
+                amount_in,
+                min_amount_out,
+            )?;
+            OutputEscrow::credit(dex, sender, token_out, amount_out)?;
+            let fee_bps = pool_fee_bps(dex, token_in, token_out);
+            emit_claimable_swap(sender, token_in, token_out, amount_in, amount_out, fee_bps);
+            Ok(amount_out)
+        })
+    }
 
-        // Clear commitment state
-        self.commitment_activated.setter(sender).set(false);
-        self.commitment_hashes.setter(sender).set(U256::ZERO);
-        self.commitment_timestamps.setter(sender).set(U256::ZERO);
+    /// Set how long (blocks) an escrow account must be idle before its balances can be swept
+    /// to treasury. ROLE_ADMIN only; 0 disables, otherwise at least MIN_ESCROW_SWEEP_BLOCKS.
+    pub fn set_escrow_sweep_period(&mut self, blocks: U256) -> OakResult<()> {
+        require_role(self, ROLE_ADMIN)?;
+        AdminAuditLog::record(self, AUDIT_ACTION_SET_ESCROW_SWEEP_PERIOD, &[word(blocks)])?;
+        OutputEscrow::set_sweep_period(self, blocks)
+    }
 
-        emit_cancel_commitment(sender, current_block);
+    /// Sweep `user`'s abandoned claimable `token` balance into the treasury balance.
+    ///
+    /// @notice ROLE_ADMIN only. Only for accounts with no escrow activity for the sweep period;
+    ///         emits ClaimableSwept. Any claim, transfer or credit resets the clock.
+    pub fn sweep_stale_claimable(&mut self, user: Address, token: Address) -> OakResult<U256> {
+        require_role(self, ROLE_ADMIN)?;
+        AdminAuditLog::record(self, AUDIT_ACTION_SWEEP_STALE_CLAIMABLE, &[user.into_word(), token.into_word()])?;
+        OutputEscrow::sweep_stale(self, user, token)
+    }
 
+    /// Enable or disable the caller's on-chain trade journal.
+    ///
+    /// @notice When enabled, every reveal appends its execution details (see `journal`).
+    pub fn set_trade_journal(&mut self, enabled: bool) -> OakResult<()> {
+        TradeJournal::set_enabled(self, enabled);
         Ok(())
     }
 
+    /// View: number of journal entries of `user`.
+    pub fn get_trade_count(&self, user: Address) -> OakResult<U256> {
+        Ok(TradeJournal::len(self, user))
+    }
+
+    /// View: up to `limit` (max MAX_PAGE_SIZE) journal entries of `user` from `offset`.
+    pub fn get_trade_records(&self, user: Address, offset: U256, limit: U256) -> OakResult<Vec<TradeRecordView>> {
+        TradeJournal::page(self, user, offset, limit)
+    }
+
+    /// View: journal entry `index` of `user` (see `TradeRecordView`).
+    pub fn get_trade_record(&self, user: Address, index: U256) -> OakResult<TradeRecordView> {
+        TradeJournal::entry(self, user, index)
+    }
+
+    /// View: number of entries in the owner audit log.
+    pub fn get_audit_log_length(&self) -> OakResult<U256> {
+        Ok(AdminAuditLog::len(self))
+    }
+
+    /// View: up to `limit` (max MAX_PAGE_SIZE) audit log entries from `offset` (see `AuditRecordView`).
+    pub fn get_audit_records(&self, offset: U256, limit: U256) -> OakResult<Vec<AuditRecordView>> {
+        AdminAuditLog::page(self, offset, limit)
+    }
+
+    /// View: audit log entry `index` as (action, actor, params_hash, block_number).
+    pub fn get_audit_record(&self, index: U256) -> OakResult<AuditRecordView> {
+        AdminAuditLog::entry(self, index)
+    }
+
+    /// View: escrowed output claimable by `user` for `token`.
+    pub fn get_claimable(&self, user: Address, token: Address) -> OakResult<U256> {
+        Ok(OutputEscrow::get_claimable(self, user, token))
+    }
+
     /// Withdraw (claim) accrued treasury fees for a given token.
     ///
-    /// @notice Owner-only. Transfers per-token treasury balance (20% of fees) to treasury address.
-    /// @dev 60/20/20 model: 20% Treasury, 20% Buyback, 60% LP. Resets balance after transfer.
+    /// @notice ROLE_ADMIN only. Transfers per-token treasury balance (20% of fees) to the token's
+    ///         configured destination; without one, fans it out over the treasury split table
+    ///         (see `treasury_splits`), or sends it to the treasury address when that is empty.
+    /// @dev 60/20/20 model: 20% Treasury, 20% Buyback, 60% LP. Resets balance before transfer.
+    ///      Emits WithdrawTreasuryFees once per recipient paid.
     pub fn withdraw_treasury_fees(&mut self, token: Address) -> OakResult<()> {
-        let owner = self.owner.get();
-        only_owner(owner)?;
-        require_non_zero_address(token)?;
-        lock_reentrancy_guard(self)?;
+        require_role(self, ROLE_ADMIN)?;
+        // `token` = address(0) withdraws native ETH fees.
+        with_lock(self, |dex| {
+            let treasury = treasury_destination_for(dex, token);
+            if treasury == Address::ZERO {
+                return Err(err(ERR_INVALID_OWNER));
+            }
+            let contract_addr = contract::address();
+            if treasury == contract_addr {
+                return Err(err(ERR_TREASURY_IS_CONTRACT));
+            }
 
-        let treasury = self.treasury.get();
-        if treasury == Address::ZERO {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_INVALID_OWNER));
-        }
-        let contract_addr = contract::address();
-        if treasury == contract_addr {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_TREASURY_IS_CONTRACT));
-        }
+            let accrued = dex.treasury_balance.setter(token).get();
+            if accrued.is_zero() {
+                return Err(err(ERR_NO_TREASURY_FEES));
+            }
+            let contract_balance = balance_of(token, contract_addr);
+            if contract_balance < accrued {
+                return Err(err(ERR_INSUFFICIENT_LIQUIDITY));
+            }
 
-        let accrued = self.treasury_balance.setter(token).get();
-        if accrued.is_zero() {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_NO_TREASURY_FEES));
-        }
-        let contract_balance = balance_of(token, contract_addr);
-        if contract_balance < accrued {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_INSUFFICIENT_LIQUIDITY));
+            AdminAuditLog::record(dex, AUDIT_ACTION_WITHDRAW_TREASURY_FEES, &[token.into_word(), treasury.into_word(), word(accrued)])?;
+            dex.treasury_balance.setter(token).set(U256::ZERO);
+
+            let (recipients, weights) = TreasurySplits::table(dex);
+            let payouts = if recipients.is_empty() || dex.treasury_destination.get(token) != Address::ZERO {
+                alloc::vec![(treasury, accrued)]
+            } else {
+                match split_amounts(accrued, &weights) {
+                    Ok(shares) => recipients.into_iter().zip(shares).collect(),
+                    Err(e) => {
+                        return Err(err(e));
+                    }
+                }
+            };
+            for (recipient, amount) in payouts {
+                if amount.is_zero() {
+                    continue;
+                }
+                safe_transfer(token, recipient, amount)?;
+                emit_withdraw_treasury_fees(recipient, token, amount);
+            }
+            Ok(())
+        })
+    }
+
+    /// Multicall3-style batching of read-only views in one call (no helper contract needed).
+    ///
+    /// @notice Each entry is full calldata (selector + ABI args) for one allowlisted view; returns
+    ///         the ABI-encoded result of each, in order. Any failing view reverts the whole batch
+    ///         with that view's revert data. Call via eth_call.
+    /// @dev Dispatch goes through the contract's own router in-process, since Stylus programs
+    ///      without the `reentrant` feature cannot static-call themselves. `&mut self` is only
+    ///      required by the router signature; the allowlist keeps every routed call read-only.
+    pub fn aggregate_views(&mut self, calls: Vec<Bytes>) -> OakResult<Vec<Bytes>> {
+        use stylus_sdk::function_selector;
+        const VIEWS: [[u8; 4]; 105] = [
+            function_selector!("getReserves", Address, Address),
+            function_selector!("getPoolInfo", Address, Address),
+            function_selector!("getFeeBps"),
+            function_selector!("getOwner"),
+            function_selector!("hasRole", FixedBytes<32>, Address),
+            function_selector!("getTreasury"),
+            function_selector!("getPendingTreasury"),
+            function_selector!("getTreasuryDestination", Address),
+            function_selector!("isPaused"),
+            function_selector!("getAmountsOut", U256, Vec<Address>),
+            function_selector!("getAmountsIn", U256, Vec<Address>),
+            function_selector!("getQuote", U256, Vec<Address>),
+            function_selector!("quoteSwapExactIn", Address, Address, U256, bool),
+            function_selector!("quoteSwapExactOut", Address, Address, U256, bool),
+            function_selector!("calculateTradeImpact", U256, Vec<Address>),
+            function_selector!("getCurrentPrice", Address, Address),
+            function_selector!("getMinAmountIn", Address, Address),
+            function_selector!("quoteAddLiquidity", Address, Address, U256, U256),
+            function_selector!("getDynamicFeeBps", Address, Address),
+            function_selector!("isHookedPool", Address, Address),
+            function_selector!("getPoolManager", Address, Address),
+            function_selector!("getPoolId", Address, Address, U256),
+            function_selector!("getPoolStats", Address, Address),
+            function_selector!("getPoolEpochStats", Address, Address, U256),
+            function_selector!("getPoolFeeApr", Address, Address),
+            function_selector!("getImpermanentLossBps", Address, Address),
+            function_selector!("getLpReceipt", Address, Address, Address),
+            function_selector!("getLpPosition", Address, Address, Address),
+            function_selector!("getClaimable", Address, Address),
+            function_selector!("getTreasuryBalance", Address),
+            function_selector!("getBuybackBalance", Address),
+            function_selector!("getProtocolAnalytics"),
+            function_selector!("getOrder", U256),
+            function_selector!("getPosition", U256),
+            function_selector!("getPositionHealth", U256),
+            function_selector!("getOracleStalenessPolicy"),
+            function_selector!("getPathTwap", Vec<Address>),
+            function_selector!("observe", Address, Address, Vec<U256>),
+            function_selector!("getObservationState", Address, Address),
+            function_selector!("getCommitment", Address),
+            function_selector!("getCommitmentPriority", Address),
+            function_selector!("getCommitBond"),
+            function_selector!("getCreatorFeeShare"),
+            function_selector!("getPoolCreator", Address, Address),
+            function_selector!("getCreatorClaimable", Address, Address),
+            function_selector!("getBatch", Address, Address, U256),
+            function_selector!("getBatchWindow"),
+            function_selector!("getBootstrapFeed", Address, Address),
+            function_selector!("getLiquidityLock", Address, Address, Address),
+            function_selector!("isCommitOperator", Address, Address),
+            function_selector!("reconcileFees", Address, Vec<Address>),
+            function_selector!("getPrice", Address, Address),
+            function_selector!("getTwapPrice", Address, Address),
+            function_selector!("getPair", Address, Address),
+            function_selector!("allPairsLength"),
+            function_selector!("pairAt", U256),
+            function_selector!("getTokens"),
+            function_selector!("getCommitmentPool", Address),
+            function_selector!("getAuditLogLength"),
+            function_selector!("getAuditRecords", U256, U256),
+            function_selector!("getAuditRecord", U256),
+            function_selector!("isFeeTierEnabled", U256),
+            function_selector!("getPoolFeeBps", Address, Address),
+            function_selector!("getEffectiveFeeBps", Address, Address),
+            function_selector!("getVolatilityFeeConfig"),
+            function_selector!("getPoolVolatility", Address, Address),
+            function_selector!("canonicalToken", Address),
+            function_selector!("isTokenAlias", Address),
+            function_selector!("pendingLpFees", Address, Address, Address),
+            function_selector!("getPoolCommitSchemes", Address, Address),
+            function_selector!("getCommitmentScheme", Address),
+            function_selector!("selfTest"),
+            function_selector!("pendingGasRebate", Address, Address),
+            function_selector!("getTreasurySplits"),
+            function_selector!("getPendingTreasurySplits"),
+            function_selector!("getReferralBps"),
+            function_selector!("pendingReferralFees", Address, Address),
+            function_selector!("maxFlashLoan", Address),
+            function_selector!("flashFee", Address, U256),
+            function_selector!("getFlashLoanPool", Address),
+            function_selector!("getFlashCallback", Address),
+            function_selector!("getFlashFeeBps"),
+            function_selector!("getPoolSuccessor", Address, Address),
+            function_selector!("getPositionId", Address, Address, U256),
+            function_selector!("getPositionClass", U256),
+            function_selector!("balanceOf", Address, U256),
+            function_selector!("isApprovedForAll", Address, Address),
+            function_selector!("getCommitVoucher", U256),
+            function_selector!("getSponsorBalance", Address, Address),
+            function_selector!("getCommitmentTier", Address),
+            function_selector!("getTokenRisk", Address),
+            function_selector!("getMaxPriceImpactBps"),
+            function_selector!("getAutoCircuitBreaker"),
+            function_selector!("getLpSurplus", Address, Address),
+            function_selector!("getPendingExposure", Address, Address),
+            function_selector!("getPoolOutflow", Address, Address),
+            function_selector!("getCommitPriceBand"),
+            function_selector!("getCommitRevealParams"),
+            function_selector!("getTradeCount", Address),
+            function_selector!("getTradeRecord", Address, U256),
+            function_selector!("getTradeRecords", Address, U256, U256),
+            function_selector!("canReveal", Address),
+            function_selector!("estimateRevealCost"),
+            function_selector!("healthCheck"),
+            function_selector!("healthCheckPool", Address, Address),
+        ];
+
+        if calls.len() as u64 > MAX_AGGREGATE_CALLS {
+            return Err(err(ERR_AGGREGATE_TOO_MANY));
+        }
+        let mut results = Vec::with_capacity(calls.len());
+        for data in &calls {
+            let selector: [u8; 4] = match data.get(..4).and_then(|s| s.try_into().ok()) {
+                Some(sel) if VIEWS.contains(&sel) => sel,
+                _ => return Err(err(ERR_VIEW_NOT_AGGREGATABLE)),
+            };
+            let routed = <Self as stylus_sdk::abi::Router<Self>>::route(
+                self,
+                u32::from_be_bytes(selector),
+                &data[4..],
+            );
+            match routed {
+                Some(Ok(out)) => results.push(Bytes::from(out)),
+                Some(Err(revert)) => return Err(OakError::from_revert(&revert)),
+                None => return Err(err(ERR_VIEW_NOT_AGGREGATABLE)),
+            }
         }
+        Ok(results)
+    }
+
+    /// View: decimal-adjusted spot prices as (1 token_a in token_b, 1 token_b in token_a), 1e18-scaled.
+    ///
+    /// @notice Whole-token prices that account for each token's `decimals()`, e.g. 3000e18 for
+    ///         1 WETH (18) in USDC (6) at 3000. Raw reserve ratios (Q112 views) ignore decimals.
+    pub fn get_price(&self, token_a: Address, token_b: Address) -> OakResult<(U256, U256)> {
+        PoolOracle::decimal_prices(self, token_a, token_b, false)
+    }
+
+    /// View: decimal-adjusted TWAP prices, same layout and scale as `get_price`.
+    pub fn get_twap_price(&self, token_a: Address, token_b: Address) -> OakResult<(U256, U256)> {
+        PoolOracle::decimal_prices(self, token_a, token_b, true)
+    }
+
+    /// View: TWAP composed along a routing path, e.g. [A, B, C] prices A in C via A/B and B/C.
+    ///
+    /// @notice Returns (price_q112, max_age_blocks, min_window_blocks): the implied price of one
+    ///         unit of path[0] in path[last] (Q112), the staleness of the least recently traded hop,
+    ///         and the shortest averaging window (0 = some hop only has a spot price).
+    pub fn get_path_twap(&self, path: Vec<Address>) -> OakResult<PathTwap> {
+        PoolOracle::path_twap(self, &path)
+    }
+
+    /// Pre-pay the pair's observation ring up to `cardinality` slots; returns the new size.
+    ///
+    /// @notice Anyone may call (the caller pays the storage). Larger rings let `observe` reach
+    ///         further back; at most MAX_OBSERVATION_CARDINALITY, grown by at most
+    ///         MAX_OBSERVATION_GROWTH_PER_CALL slots per call.
+    pub fn increase_observation_cardinality(
+        &mut self,
+        token_a: Address,
+        token_b: Address,
+        cardinality: U256,
+    ) -> OakResult<U256> {
+        let (token0, token1) = if token_a < token_b {
+            (token_a, token_b)
+        } else {
+            (token_b, token_a)
+        };
+        PoolOracle::increase_observation_cardinality(self, token0, token1, cardinality)
+    }
+
+    /// View: cumulative prices of `token_in` in `token_out` (Q112 * blocks) `seconds_agos` ago.
+    ///
+    /// @notice TWAP over [t1, t0] = (cum[t0] - cum[t1]) / (ago1 - ago0). Uniswap v3 `observe`
+    ///         semantics with block numbers as time (1 block/s).
+    pub fn observe(&self, token_in: Address, token_out: Address, seconds_agos: Vec<U256>) -> OakResult<Vec<U256>> {
+        PoolOracle::observe(self, token_in, token_out, &seconds_agos)
+    }
+
+    /// View: observation ring of the pair as (index, cardinality, cardinality_next).
+    pub fn get_observation_state(&self, token_a: Address, token_b: Address) -> OakResult<(U256, U256, U256)> {
+        let (token0, token1) = if token_a < token_b {
+            (token_a, token_b)
+        } else {
+            (token_b, token_a)
+        };
+        Ok(PoolOracle::observation_state(self, token0, token1))
+    }
+
+    /// Health check for monitoring: bitfield of global conditions (0 = healthy).
+    ///
+    /// @notice Bits: 0 paused, 1 circuit breaker, 3 oracle stale, 5 lock stuck (see `HEALTH_*`).
+    ///         Pool-level bits (2 reserve drift, 4 fee accrual) come from `health_check_pool`.
+    pub fn health_check(&self) -> OakResult<U256> {
+        Ok(HealthMonitor::flags(self))
+    }
+
+    /// Self-test for monitoring: bitmap of failed internal consistency checks (0 = pass).
+    ///
+    /// @notice Bits: 0 fee split, 1 swap quote math, 2 LP fee accumulator, 3 fee config,
+    ///         4 commit-reveal config, 5 pool reserve bounds (see `SELF_TEST_*`, `engine::self_test`).
+    pub fn self_test(&self) -> U256 {
+        SelfTest::run(self)
+    }
+
+    /// Health check including pool bits for (token_a, token_b): reserve drift and fee accrual.
+    pub fn health_check_pool(&self, token_a: Address, token_b: Address) -> OakResult<U256> {
+        Ok(HealthMonitor::pool_flags(self, token_a, token_b))
+    }
+
+    /// Close the current statistics epoch for a pool (ROLE_ADMIN only).
+    ///
+    /// @notice Archives volume/fee totals into the epoch history, zeroes the counters and
+    ///         starts the next epoch. Returns the closed epoch index.
+    pub fn reset_pool_stats(&mut self, token_a: Address, token_b: Address) -> OakResult<U256> {
+        require_role(self, ROLE_ADMIN)?;
+        let (token0, token1) = if token_a < token_b {
+            (token_a, token_b)
+        } else {
+            (token_b, token_a)
+        };
+        AdminAuditLog::record(self, AUDIT_ACTION_RESET_POOL_STATS, &[token0.into_word(), token1.into_word()])?;
+        PoolStats::close_epoch(self, token0, token1)
+    }
+
+    /// Current-epoch pool statistics: (epoch, start_block, volume0, volume1, fees0, fees1).
+    pub fn get_pool_stats(
+        &self,
+        token_a: Address,
+        token_b: Address,
+    ) -> OakResult<(U256, U256, U256, U256, U256, U256)> {
+        let (token0, token1) = if token_a < token_b {
+            (token_a, token_b)
+        } else {
+            (token_b, token_a)
+        };
+        Ok(PoolStats::current(self, token0, token1))
+    }
+
+    /// Archived pool statistics: (start_block, end_block, volume0, volume1, fees0, fees1) for `epoch`.
+    pub fn get_pool_epoch_stats(
+        &self,
+        token_a: Address,
+        token_b: Address,
+        epoch: U256,
+    ) -> OakResult<(U256, U256, U256, U256, U256, U256)> {
+        let (token0, token1) = if token_a < token_b {
+            (token_a, token_b)
+        } else {
+            (token_b, token_a)
+        };
+        Ok(PoolStats::archived(self, token0, token1, epoch))
+    }
 
-        self.treasury_balance.setter(token).set(U256::ZERO);
-        safe_transfer(token, treasury, accrued)?;
-        emit_withdraw_treasury_fees(treasury, token, accrued);
-        unlock_reentrancy_guard(self);
-        Ok(())
+    /// Rolling LP fee APR for the pair: (apr_bps, window_blocks).
+    ///
+    /// @notice LP share of fees over the epoch divided by the time-weighted average reserves,
+    ///         annualized at BLOCKS_PER_YEAR. Right after an epoch reset it reports the last
+    ///         closed epoch.
+    pub fn get_pool_fee_apr(&self, token_a: Address, token_b: Address) -> OakResult<(U256, U256)> {
+        let (token0, token1) = if token_a < token_b {
+            (token_a, token_b)
+        } else {
+            (token_b, token_a)
+        };
+        PoolStats::fee_apr(self, token0, token1)
     }
 
     /// Protocol analytics: total trading volume (global). Public Analytics for reporting.
@@ -2627,7 +6757,6 @@ impl OakDEX {
             return Err(err(ERR_PATH_TOO_LONG));
         }
         let amounts = self.get_amounts_out(amount_in, path.clone())?;
-        let fee_bps = self.protocol_fee_bps.get();
         let mut impacts = Vec::with_capacity(amounts.len().saturating_sub(1));
         let mut fees = Vec::with_capacity(amounts.len().saturating_sub(1));
 
@@ -2653,7 +6782,7 @@ impl OakDEX {
             let amt_out = amounts[i + 1];
 
             let fee_hop = amt_in
-                .checked_mul(fee_bps)
+                .checked_mul(pool_fee_bps(self, token0, token1))
                 .ok_or_else(|| err(ERR_OVERFLOW))?
                 .checked_div(as_u256(FEE_DENOMINATOR))
                 .unwrap_or(U256::ZERO);
@@ -2680,6 +6809,25 @@ impl OakDEX {
         Ok((amounts, impacts, fees))
     }
 
+    /// LP deposit receipt for reporting / IL insurance.
+    ///
+    /// @notice Returns (deposited0, deposited1, entry_block, value0, value1, fees0, fees1, il_bps)
+    ///         in canonical token order: cost basis, current pro-rata claim, the part of it
+    ///         earned from swap fees, and impermanent loss vs holding the deposit.
+    pub fn get_lp_receipt(
+        &self,
+        user: Address,
+        token_a: Address,
+        token_b: Address,
+    ) -> OakResult<LpReceiptView> {
+        let (token0, token1) = if token_a < token_b {
+            (token_a, token_b)
+        } else {
+            (token_b, token_a)
+        };
+        LpReceipts::position(self, user, token0, token1)
+    }
+
     /// LP position: balance and pool share in basis points.
     pub fn get_lp_position(
         &self,
@@ -2728,7 +6876,7 @@ impl OakDEX {
         if amount_out.is_zero() {
             return Err(err(ERR_INSUFFICIENT_OUTPUT_AMOUNT));
         }
-        let fee_bps = self.protocol_fee_bps.get();
+        TokenAliases::require_acyclic_path(self, &path)?;
         let mut amounts = Vec::with_capacity(path.len());
         let mut current_out = amount_out;
         for i in (0..path.len()).rev() {
@@ -2756,12 +6904,64 @@ impl OakDEX {
             } else {
                 (pool.reserve1.get(), pool.reserve0.get())
             };
+            let fee_bps = pool_fee_bps(self, token0, token1);
             current_out = get_amount_in_with_fee(current_out, reserve_in, reserve_out, fee_bps)?;
         }
         amounts.reverse();
         Ok(amounts)
     }
 
+    /// Minimum economically meaningful input for token_in -> token_out at current reserves and fee.
+    ///
+    /// @notice Swaps below this amount revert with DUST_AMOUNT (output would round to zero).
+    pub fn get_min_amount_in(&self, token_in: Address, token_out: Address) -> OakResult<U256> {
+        require_non_zero_address(token_in)?;
+        require_non_zero_address(token_out)?;
+        let (token0, token1) = if token_in < token_out {
+            (token_in, token_out)
+        } else {
+            (token_out, token_in)
+        };
+        let outer = self.pools.getter(token0);
+        let pool = outer.getter(token1);
+        if !pool.initialized.get() {
+            return Err(err(ERR_INVALID_TOKEN));
+        }
+        let (reserve_in, reserve_out) = if token_in == token0 {
+            (pool.reserve0.get(), pool.reserve1.get())
+        } else {
+            (pool.reserve1.get(), pool.reserve0.get())
+        };
+        Ok(min_amount_in_for_output(reserve_in, reserve_out, pool_fee_bps(self, token0, token1))?)
+    }
+
+    /// Quote: output for an exact input in the (token_a, token_b) pool at current reserves and fee.
+    ///
+    /// @notice `zero_for_one` sells the lower-address token (same flag as the commitment), so the
+    ///         result can be used as `min_amount_out` (minus tolerance) before committing.
+    pub fn quote_swap_exact_in(
+        &self,
+        token_a: Address,
+        token_b: Address,
+        amount_in: U256,
+        zero_for_one: bool,
+    ) -> OakResult<U256> {
+        let (reserve_in, reserve_out) = directional_reserves(self, token_a, token_b, zero_for_one)?;
+        Ok(get_amount_out_with_fee(amount_in, reserve_in, reserve_out, pool_fee_bps(self, token_a, token_b))?)
+    }
+
+    /// Quote: input required for an exact output (inverse CPMM formula, rounded up).
+    pub fn quote_swap_exact_out(
+        &self,
+        token_a: Address,
+        token_b: Address,
+        amount_out: U256,
+        zero_for_one: bool,
+    ) -> OakResult<U256> {
+        let (reserve_in, reserve_out) = directional_reserves(self, token_a, token_b, zero_for_one)?;
+        Ok(get_amount_in_with_fee(amount_out, reserve_in, reserve_out, pool_fee_bps(self, token_a, token_b))?)
+    }
+
     /// Quote: same as calculate_trade_impact (amounts, price_impact_bps per hop, fee per hop).
     pub fn get_quote(
         &self,
@@ -2813,30 +7013,166 @@ impl OakDEX {
         Ok(il_bps)
     }
 
-    /// Dynamic fee hook: currently returns base protocol fee. Future: volatility-based adjustment.
-    pub fn get_dynamic_fee_bps(&self, _token_a: Address, _token_b: Address) -> OakResult<U256> {
-        Ok(self.protocol_fee_bps.get())
+    /// Dynamic fee hook: the pool's volatility-adjusted fee (same as `get_effective_fee_bps`).
+    pub fn get_dynamic_fee_bps(&self, token_a: Address, token_b: Address) -> OakResult<U256> {
+        Ok(pool_fee_bps(self, token_a, token_b))
+    }
+
+    /// Escape hatch: clear a stuck re-entrancy lock (owner, or the contract itself via timelock).
+    ///
+    /// @notice Only reachable if a success path ever returned with `locked == true`
+    ///         (see `health_check` bit HEALTH_LOCK_STUCK); otherwise every call would revert.
+    /// @dev No-op when the lock is already free. Audit event.
+    pub fn force_unlock(&mut self) -> OakResult<()> {
+        if msg::sender() != contract::address() {
+            require_role(self, ROLE_ADMIN)?;
+        }
+        if !self.locked.get() {
+            return Ok(());
+        }
+        AdminAuditLog::record(self, AUDIT_ACTION_FORCE_UNLOCK, &[])?;
+        unlock_reentrancy_guard(self);
+        emit_force_unlocked(msg::sender());
+        Ok(())
     }
 
-    /// Manually trigger circuit breaker (owner only). Stops swaps until cleared. Audit event.
+    /// Manually trigger circuit breaker (ROLE_ADMIN only). Stops swaps until cleared. Audit event.
     pub fn trigger_circuit_breaker(&mut self) -> OakResult<()> {
-        only_owner(self.owner.get())?;
+        require_role(self, ROLE_ADMIN)?;
+        AdminAuditLog::record(self, AUDIT_ACTION_TRIGGER_CIRCUIT_BREAKER, &[])?;
         self.circuit_breaker_triggered.set(true);
         emit_circuit_breaker_triggered(U256::ZERO); // 0 = manual trigger
         Ok(())
     }
 
-    /// Clear circuit breaker (owner only). Re-enables swaps. Audit event.
+    /// Clear circuit breaker (ROLE_ADMIN only). Re-enables swaps. Audit event.
     pub fn clear_circuit_breaker(&mut self) -> OakResult<()> {
-        only_owner(self.owner.get())?;
+        require_role(self, ROLE_ADMIN)?;
+        AdminAuditLog::record(self, AUDIT_ACTION_CLEAR_CIRCUIT_BREAKER, &[])?;
         self.circuit_breaker_triggered.set(false);
         emit_circuit_breaker_cleared();
         Ok(())
     }
 
-    /// Set buyback wallet (owner only). Can set to zero to disable.
+    /// Configure the automatic circuit breaker (ROLE_ADMIN only).
+    ///
+    /// @notice After every swap the pool's spot price is compared with its TWAP over the last
+    ///         `window_blocks` (0 = the pool oracle's current window); a divergence above
+    ///         `deviation_bps` pauses the protocol and emits CircuitBreakerTripped.
+    ///         `deviation_bps` = 0 disables the breaker.
+    pub fn set_auto_circuit_breaker(&mut self, deviation_bps: U256, window_blocks: U256) -> OakResult<()> {
+        require_role(self, ROLE_ADMIN)?;
+        AdminAuditLog::record(self, AUDIT_ACTION_SET_AUTO_CIRCUIT_BREAKER, &[word(deviation_bps), word(window_blocks)])?;
+        self.auto_breaker_deviation_bps.set(deviation_bps);
+        self.auto_breaker_window_blocks.set(window_blocks);
+        emit_auto_circuit_breaker_set(deviation_bps, window_blocks);
+        Ok(())
+    }
+
+    /// Reset a tripped automatic circuit breaker and resume the protocol (ROLE_ADMIN only).
+    ///
+    /// @notice Clears the breaker flag and the pause it set. Reverts with BreakerNotTripped when the
+    ///         pause came from elsewhere (use `unpause` / `clear_circuit_breaker` for those).
+    pub fn reset_circuit_breaker(&mut self) -> OakResult<()> {
+        require_role(self, ROLE_ADMIN)?;
+        let tripped_block = self.auto_breaker_tripped_block.get();
+        require_breaker_tripped(tripped_block)?;
+        AdminAuditLog::record(self, AUDIT_ACTION_RESET_AUTO_CIRCUIT_BREAKER, &[word(tripped_block)])?;
+        self.auto_breaker_tripped_block.set(U256::ZERO);
+        self.circuit_breaker_triggered.set(false);
+        self.paused.set(false);
+        emit_pause_changed(false);
+        emit_circuit_breaker_reset(tripped_block);
+        Ok(())
+    }
+
+    /// View: automatic breaker config as (deviation_bps, window_blocks, tripped_block; 0 = not tripped).
+    pub fn get_auto_circuit_breaker(&self) -> (U256, U256, U256) {
+        (
+            self.auto_breaker_deviation_bps.get(),
+            self.auto_breaker_window_blocks.get(),
+            self.auto_breaker_tripped_block.get(),
+        )
+    }
+
+    /// Configure how swaps behave when the TWAP deviation reference goes stale (ROLE_ADMIN only).
+    ///
+    /// @notice `heartbeat_blocks` = 0 restores ORACLE_HEARTBEAT_BLOCKS. With `pause_on_stale` swaps
+    ///         revert with ORACLE_STALE until `reset_price_reference`; otherwise the deviation
+    ///         comparison is skipped once and the reference re-anchored to the current price.
+    pub fn set_oracle_staleness_policy(&mut self, heartbeat_blocks: U256, pause_on_stale: bool) -> OakResult<()> {
+        require_role(self, ROLE_ADMIN)?;
+        AdminAuditLog::record(self, AUDIT_ACTION_SET_ORACLE_STALENESS_POLICY, &[word(heartbeat_blocks), bool_word(pause_on_stale)])?;
+        self.oracle_heartbeat_blocks.set(heartbeat_blocks);
+        self.stale_oracle_pauses_swaps.set(pause_on_stale);
+        emit_oracle_staleness_policy_set(heartbeat_blocks, pause_on_stale);
+        Ok(())
+    }
+
+    /// Tune MEV protection vs. UX latency (ROLE_ADMIN only): blocks between commit and reveal, and
+    /// blocks after which an unrevealed commitment expires.
+    ///
+    /// @notice Bounded by MIN/MAX_COMMIT_REVEAL_DELAY and MIN_COMMITMENT_AGE /
+    ///         MAX_COMMITMENT_AGE_LIMIT. Applies to pending commitments too.
+    pub fn set_commit_reveal_params(&mut self, reveal_delay: U256, max_age: U256) -> OakResult<()> {
+        require_role(self, ROLE_ADMIN)?;
+        if !commit_reveal_params_valid(reveal_delay, max_age) {
+            return Err(err(ERR_INVALID_COMMIT_PARAMS));
+        }
+        AdminAuditLog::record(self, AUDIT_ACTION_SET_COMMIT_REVEAL_PARAMS, &[word(reveal_delay), word(max_age)])?;
+        self.commit_reveal_delay_blocks.set(reveal_delay);
+        self.max_commitment_age_blocks.set(max_age);
+        emit_set_commit_reveal_params(reveal_delay, max_age);
+        Ok(())
+    }
+
+    /// View: effective (reveal_delay_blocks, max_commitment_age_blocks).
+    pub fn get_commit_reveal_params(&self) -> OakResult<(U256, U256)> {
+        Ok(commit_reveal_params(self))
+    }
+
+    /// Configure the large-commitment price band (ROLE_ADMIN only).
+    ///
+    /// @notice Commitments above `threshold_bps` of reserve_in must commit a limit price within
+    ///         `band_bps` of the TWAP at commit time (checked at reveal). `threshold_bps` 0 disables.
+    pub fn set_commit_price_band(&mut self, threshold_bps: U256, band_bps: U256) -> OakResult<()> {
+        require_role(self, ROLE_ADMIN)?;
+        if threshold_bps > as_u256(BPS) || band_bps > as_u256(BPS) {
+            return Err(err(ERR_INVALID_PRICE_BAND));
+        }
+        AdminAuditLog::record(self, AUDIT_ACTION_SET_COMMIT_PRICE_BAND, &[word(threshold_bps), word(band_bps)])?;
+        self.large_commit_reserve_bps.set(threshold_bps);
+        self.commit_price_band_bps.set(band_bps);
+        emit_commit_price_band_set(threshold_bps, band_bps);
+        Ok(())
+    }
+
+    /// View: large-commitment price band as (threshold_bps, band_bps).
+    pub fn get_commit_price_band(&self) -> OakResult<(U256, U256)> {
+        Ok((self.large_commit_reserve_bps.get(), self.commit_price_band_bps.get()))
+    }
+
+    /// Drop the stale deviation reference so the next swap re-anchors it (ROLE_ADMIN only).
+    pub fn reset_price_reference(&mut self) -> OakResult<()> {
+        require_role(self, ROLE_ADMIN)?;
+        AdminAuditLog::record(self, AUDIT_ACTION_RESET_PRICE_REFERENCE, &[])?;
+        self.last_twap_price0.set(U256::ZERO);
+        self.last_twap_price1.set(U256::ZERO);
+        self.last_twap_price_block.set(U256::from(block::number()));
+        Ok(())
+    }
+
+    /// View: (effective heartbeat in blocks, pause_on_stale, reference currently stale).
+    pub fn get_oracle_staleness_policy(&self) -> OakResult<(U256, bool, bool)> {
+        let stale = !self.last_twap_price0.get().is_zero()
+            && is_reference_stale(self, U256::from(block::number()));
+        Ok((oracle_heartbeat(self), self.stale_oracle_pauses_swaps.get(), stale))
+    }
+
+    /// Set buyback wallet (ROLE_ADMIN only). Can set to zero to disable.
     pub fn set_buyback_wallet(&mut self, wallet: Address) -> OakResult<()> {
-        only_owner(self.owner.get())?;
+        require_role(self, ROLE_ADMIN)?;
+        AdminAuditLog::record(self, AUDIT_ACTION_SET_BUYBACK_WALLET, &[wallet.into_word()])?;
         self.buyback_wallet.set(wallet);
         emit_buyback_wallet_set(wallet);
         Ok(())
@@ -2844,17 +7180,203 @@ impl OakDEX {
 
     /// Two-step ownership transfer (DoD-style). Pending owner must call accept_owner() after delay.
     pub fn set_pending_owner(&mut self, pending: Address) -> OakResult<()> {
-        only_owner(self.owner.get())?;
+        require_role(self, ROLE_ADMIN)?;
         let after_block = U256::from(block::number())
             .checked_add(as_u256(OWNER_TRANSFER_DELAY_BLOCKS))
             .ok_or_else(|| err(ERR_OVERFLOW))?;
+        AdminAuditLog::record(self, AUDIT_ACTION_SET_PENDING_OWNER, &[pending.into_word(), word(after_block)])?;
         self.pending_owner.set(pending);
         self.owner_transfer_after_block.set(after_block);
         emit_pending_owner_set(pending, after_block);
         Ok(())
     }
 
-    /// Accept ownership (callable only by pending owner after delay).
+    /// Propose a new treasury (ROLE_ADMIN only). Installable with `execute_treasury_change` after the
+    /// treasury change delay; a new proposal replaces the pending one and restarts the delay.
+    pub fn propose_treasury(&mut self, new_treasury: Address) -> OakResult<()> {
+        require_role(self, ROLE_ADMIN)?;
+        require_non_zero_address(new_treasury)?;
+        if new_treasury == contract::address() {
+            return Err(err(ERR_TREASURY_IS_CONTRACT));
+        }
+        let after_block = U256::from(block::number())
+            .checked_add(treasury_change_delay(self))
+            .ok_or_else(|| err(ERR_OVERFLOW))?;
+        AdminAuditLog::record(self, AUDIT_ACTION_PROPOSE_TREASURY, &[new_treasury.into_word(), word(after_block)])?;
+        self.pending_treasury.set(new_treasury);
+        self.treasury_change_after_block.set(after_block);
+        emit_treasury_change_proposed(new_treasury, after_block);
+        Ok(())
+    }
+
+    /// Install the proposed treasury once its delay has elapsed (ROLE_ADMIN only).
+    ///
+    /// @dev Fees already accrued stay in `treasury_balance`; the next withdrawal pays the new address.
+    pub fn execute_treasury_change(&mut self) -> OakResult<()> {
+        require_role(self, ROLE_ADMIN)?;
+        let pending = self.pending_treasury.get();
+        if pending == Address::ZERO {
+            return Err(err(ERR_NO_PENDING_TREASURY));
+        }
+        if U256::from(block::number()) < self.treasury_change_after_block.get() {
+            return Err(err(ERR_TREASURY_CHANGE_TOO_EARLY));
+        }
+        AdminAuditLog::record(self, AUDIT_ACTION_EXECUTE_TREASURY_CHANGE, &[pending.into_word()])?;
+        let old = self.treasury.get();
+        self.treasury.set(pending);
+        self.pending_treasury.set(Address::ZERO);
+        self.treasury_change_after_block.set(U256::ZERO);
+        emit_treasury_changed(old, pending);
+        Ok(())
+    }
+
+    /// Withdraw a pending treasury proposal (ROLE_ADMIN only).
+    pub fn cancel_treasury_change(&mut self) -> OakResult<()> {
+        require_role(self, ROLE_ADMIN)?;
+        let pending = self.pending_treasury.get();
+        if pending == Address::ZERO {
+            return Err(err(ERR_NO_PENDING_TREASURY));
+        }
+        AdminAuditLog::record(self, AUDIT_ACTION_CANCEL_TREASURY_CHANGE, &[pending.into_word()])?;
+        self.pending_treasury.set(Address::ZERO);
+        self.treasury_change_after_block.set(U256::ZERO);
+        emit_treasury_change_cancelled(pending);
+        Ok(())
+    }
+
+    /// Propose a fee destination for `token` (ROLE_ADMIN only), e.g. stable fees to operations and
+    /// volatile fees to the buyback module. `destination` 0 routes the token back to `treasury`.
+    ///
+    /// @dev Same delay as treasury rotation; install with `execute_treasury_destination_change`.
+    pub fn propose_treasury_destination(&mut self, token: Address, destination: Address) -> OakResult<()> {
+        require_role(self, ROLE_ADMIN)?;
+        require_non_zero_address(token)?;
+        if destination == contract::address() {
+            return Err(err(ERR_TREASURY_IS_CONTRACT));
+        }
+        let after_block = U256::from(block::number())
+            .checked_add(treasury_change_delay(self))
+            .ok_or_else(|| err(ERR_OVERFLOW))?;
+        let params = [token.into_word(), destination.into_word(), word(after_block)];
+        AdminAuditLog::record(self, AUDIT_ACTION_PROPOSE_TREASURY_DESTINATION, &params)?;
+        self.pending_treasury_destination.setter(token).set(destination);
+        self.treasury_destination_after_block.setter(token).set(after_block);
+        emit_treasury_destination_proposed(token, destination, after_block);
+        Ok(())
+    }
+
+    /// Install the proposed fee destination for `token` once its delay has elapsed (ROLE_ADMIN only).
+    pub fn execute_treasury_destination_change(&mut self, token: Address) -> OakResult<()> {
+        require_role(self, ROLE_ADMIN)?;
+        let after_block = self.treasury_destination_after_block.get(token);
+        if after_block.is_zero() {
+            return Err(err(ERR_NO_PENDING_DESTINATION));
+        }
+        if U256::from(block::number()) < after_block {
+            return Err(err(ERR_TREASURY_CHANGE_TOO_EARLY));
+        }
+        let destination = self.pending_treasury_destination.get(token);
+        AdminAuditLog::record(self, AUDIT_ACTION_EXECUTE_TREASURY_DESTINATION, &[token.into_word(), destination.into_word()])?;
+        self.treasury_destination.setter(token).set(destination);
+        self.pending_treasury_destination.setter(token).set(Address::ZERO);
+        self.treasury_destination_after_block.setter(token).set(U256::ZERO);
+        emit_treasury_destination_set(token, destination);
+        Ok(())
+    }
+
+    /// Propose a treasury split table (ROLE_ADMIN only): withdrawals fan out to `recipients` by
+    /// `weights_bps` (summing to BPS). Empty vectors propose turning splitting off.
+    ///
+    /// @dev Same delay as treasury rotation; install with `execute_treasury_splits`. A newer
+    ///      proposal replaces a pending one and restarts the delay.
+    pub fn propose_treasury_splits(&mut self, recipients: Vec<Address>, weights_bps: Vec<U256>) -> OakResult<()> {
+        require_role(self, ROLE_ADMIN)?;
+        let after_block = U256::from(block::number())
+            .checked_add(treasury_change_delay(self))
+            .ok_or_else(|| err(ERR_OVERFLOW))?;
+        let mut params = alloc::vec![word(after_block)];
+        params.extend(recipients.iter().map(|r| r.into_word()));
+        params.extend(weights_bps.iter().map(|w| word(*w)));
+        AdminAuditLog::record(self, AUDIT_ACTION_PROPOSE_TREASURY_SPLITS, &params)?;
+        TreasurySplits::propose(self, &recipients, &weights_bps, after_block)
+    }
+
+    /// Install the proposed treasury split table once its delay has elapsed (ROLE_ADMIN only).
+    pub fn execute_treasury_splits(&mut self) -> OakResult<()> {
+        require_role(self, ROLE_ADMIN)?;
+        let after_block = self.treasury_split_after_block.get();
+        if after_block.is_zero() {
+            return Err(err(ERR_NO_PENDING_TREASURY_SPLIT));
+        }
+        if U256::from(block::number()) < after_block {
+            return Err(err(ERR_TREASURY_CHANGE_TOO_EARLY));
+        }
+        AdminAuditLog::record(self, AUDIT_ACTION_EXECUTE_TREASURY_SPLITS, &[word(after_block)])?;
+        TreasurySplits::execute(self).map(|_| ())
+    }
+
+    /// View: active treasury split table as (recipients, weights_bps); empty = no splitting.
+    pub fn get_treasury_splits(&self) -> (Vec<Address>, Vec<U256>) {
+        TreasurySplits::table(self)
+    }
+
+    /// View: proposed treasury split table as (recipients, weights_bps, after_block); 0 = none.
+    pub fn get_pending_treasury_splits(&self) -> (Vec<Address>, Vec<U256>, U256) {
+        TreasurySplits::pending(self)
+    }
+
+    /// View: effective fee destination for `token` (routing rule or the treasury address).
+    pub fn get_treasury_destination(&self, token: Address) -> OakResult<Address> {
+        Ok(treasury_destination_for(self, token))
+    }
+
+    /// Set the treasury change delay in blocks (ROLE_ADMIN only; at least MIN_TREASURY_CHANGE_DELAY_BLOCKS).
+    ///
+    /// @dev Applies to proposals made afterwards; a pending proposal keeps its block. A longer
+    ///      delay takes effect at once, a shorter one only after the current delay has elapsed.
+    pub fn set_treasury_change_delay(&mut self, delay_blocks: U256) -> OakResult<()> {
+        require_role(self, ROLE_ADMIN)?;
+        if delay_blocks < as_u256(MIN_TREASURY_CHANGE_DELAY_BLOCKS) {
+            return Err(err(ERR_TREASURY_DELAY_TOO_SHORT));
+        }
+        let current_block = U256::from(block::number());
+        let current = treasury_change_delay(self);
+        let effective_block = treasury_delay_effective_block(current, delay_blocks, current_block)?;
+        AdminAuditLog::record(
+            self,
+            AUDIT_ACTION_SET_TREASURY_CHANGE_DELAY,
+            &[word(delay_blocks), word(effective_block)],
+        )?;
+        if effective_block == current_block {
+            self.treasury_change_delay_blocks.set(delay_blocks);
+            self.pending_treasury_change_delay.set(U256::ZERO);
+            self.treasury_change_delay_after_block.set(U256::ZERO);
+        } else {
+            // Fold an already-active shorter delay in first so queuing another cannot revert to an older value.
+            self.treasury_change_delay_blocks.set(current);
+            self.pending_treasury_change_delay.set(delay_blocks);
+            self.treasury_change_delay_after_block.set(effective_block);
+        }
+        emit_treasury_change_delay_set(delay_blocks, effective_block);
+        Ok(())
+    }
+
+    /// View: (delay in force, queued shorter delay, block it takes effect); the last two are zero if none.
+    pub fn get_treasury_change_delay(&self) -> OakResult<(U256, U256, U256)> {
+        let pending = self.pending_treasury_change_delay.get();
+        let after = self.treasury_change_delay_after_block.get();
+        if U256::from(block::number()) >= after {
+            return Ok((treasury_change_delay(self), U256::ZERO, U256::ZERO));
+        }
+        Ok((treasury_change_delay(self), pending, after))
+    }
+
+    /// View: pending treasury proposal as (pending_treasury, change_after_block); zero if none.
+    pub fn get_pending_treasury(&self) -> OakResult<(Address, U256)> {
+        Ok((self.pending_treasury.get(), self.treasury_change_after_block.get()))
+    }
+
+    /// Accept ownership (callable only by pending owner after delay); moves ROLE_ADMIN to the new owner.
     pub fn accept_owner(&mut self) -> OakResult<()> {
         let pending = self.pending_owner.get();
         if pending == Address::ZERO {
@@ -2868,21 +7390,95 @@ impl OakDEX {
             return Err(err(ERR_OWNER_TRANSFER_TOO_EARLY));
         }
         let old = self.owner.get();
+        AdminAuditLog::record(self, AUDIT_ACTION_ACCEPT_OWNER, &[old.into_word(), pending.into_word()])?;
         self.owner.set(pending);
+        // The owner seat carries ROLE_ADMIN; other roles of either account are left as granted.
+        let old_roles = roles_of(self, old) & !ROLE_ADMIN;
+        self.role_bits.setter(old).set(old_roles);
+        let new_roles = roles_of(self, pending) | ROLE_ADMIN;
+        self.role_bits.setter(pending).set(new_roles);
+        emit_roles_revoked(old, ROLE_ADMIN, pending);
+        emit_roles_granted(pending, ROLE_ADMIN, pending);
         self.pending_owner.set(Address::ZERO);
         self.owner_transfer_after_block.set(U256::ZERO);
         emit_owner_changed(old, pending);
         Ok(())
     }
 
+    /// ERC-3156: largest `token` loan `flash_loan` will currently make (0 = not lent).
+    pub fn max_flash_loan(&self, token: Address) -> U256 {
+        max_flash_loan_amount(self, token)
+    }
+
+    /// ERC-3156: fee charged on a `flash_loan` of `amount` of `token` (the pool's fee rate).
+    ///
+    /// @dev Reverts with FLASH_LOAN_UNSUPPORTED when no pool serves `token`.
+    pub fn flash_fee(&self, token: Address, amount: U256) -> OakResult<U256> {
+        let (token0, token1) = flash_loan_pool(self, token).ok_or_else(|| err(ERR_FLASH_LOAN_UNSUPPORTED))?;
+        flash_loan_fee(amount, flash_fee_bps(self, token0, token1)).map_err(err)
+    }
+
+    /// ERC-3156 flash loan of `amount` of `token` to `receiver`; returns true once repaid.
+    ///
+    /// @notice `receiver` must implement `IERC3156FlashBorrower.onFlashLoan` and approve this
+    ///         contract for `amount + fee`, which is pulled after the callback returns.
+    /// @dev Lock held for the whole loan; see `process_flash_loan`.
+    pub fn flash_loan(&mut self, receiver: Address, token: Address, amount: U256, data: Bytes) -> OakResult<bool> {
+        with_lock(self, |dex| process_flash_loan(dex, msg::sender(), receiver, token, amount, &data).map(|_| true))
+    }
+
+    /// ROLE_ADMIN: serve ERC-3156 loans of `token` from its pool with `counter_token` (zero = clear).
+    ///
+    /// @notice Single-pair deployments lend the bound pair's tokens without this.
+    pub fn set_flash_loan_pool(&mut self, token: Address, counter_token: Address) -> OakResult<()> {
+        require_role(self, ROLE_ADMIN)?;
+        require_non_zero_address(token)?;
+        if counter_token != Address::ZERO {
+            require_bound_pair(self, token, counter_token)?;
+            let (token0, token1) = if token < counter_token { (token, counter_token) } else { (counter_token, token) };
+            if token == counter_token || !self.pools.getter(token0).getter(token1).initialized.get() {
+                return Err(err(ERR_INVALID_TOKEN));
+            }
+        }
+        AdminAuditLog::record(self, AUDIT_ACTION_SET_FLASH_LOAN_POOL, &[token.into_word(), counter_token.into_word()])?;
+        self.flash_loan_pair.setter(token).set(counter_token);
+        emit_flash_loan_pool_set(token, counter_token);
+        Ok(())
+    }
+
+    /// View: counter token of the pool ERC-3156 loans of `token` draw from (zero = not lent).
+    pub fn get_flash_loan_pool(&self, token: Address) -> Address {
+        flash_loan_pool(self, token).map_or(Address::ZERO, |(token0, token1)| if token == token0 { token1 } else { token0 })
+    }
+
+    /// Register the callback style flash_swap uses for the caller (FLASH_CALLBACK_*).
+    ///
+    /// @notice Lets contracts written for Uniswap V2 (`uniswapV2Call`) or V3
+    ///         (`uniswapV3FlashCallback`) flash swaps borrow from Oak unchanged.
+    pub fn set_flash_callback(&mut self, style: U256) -> OakResult<()> {
+        if style >= as_u256(FLASH_CALLBACK_STYLE_COUNT) {
+            return Err(err(ERR_UNKNOWN_FLASH_CALLBACK));
+        }
+        let borrower = msg::sender();
+        self.flash_callback_style.setter(borrower).set(style);
+        emit_flash_callback_set(borrower, style);
+        Ok(())
+    }
+
+    /// View: flash swap callback style registered by `borrower` (0 = Oak).
+    pub fn get_flash_callback(&self, borrower: Address) -> U256 {
+        self.flash_callback_style.get(borrower)
+    }
+
     /// Execute a flash swap (uncollateralized loan).
     ///
     /// @notice Allows borrowing tokens without upfront collateral, provided the borrower
     ///         returns the borrowed amount plus fees within the same transaction.
     /// @dev Uses a callback pattern via `IOakCallee` to notify the borrower.
+    ///      Borrowers registered with `set_flash_callback` get a Uniswap V2/V3-style callback instead.
     ///      After the callback, the new product of reserves (k = reserve0 * reserve1)
     ///      must be greater than or equal to the product before the swap, including fees.
-    ///      Strict CEI: Lock acquired at start, released at end.
+    ///      Strict CEI under `with_lock`.
     ///
     /// # Arguments
     /// * `token0` - Address of token0 (can be borrowed if amount0_out > 0)
@@ -2893,7 +7489,7 @@ impl OakDEX {
     ///
     /// # Safety
     /// - Re-entrancy guard is active during the entire flash swap
-    /// - Verifies k' >= k * (1 + fee) after callback
+    /// - Verifies k' >= k * (1 + fee) after callback, with the flash fee (`flash_fee_bps`)
     /// - Reverts if insufficient liquidity or repayment fails
     #[cfg(all(not(test), target_arch = "wasm32"))]
     pub fn flash_swap(
@@ -2904,336 +7500,258 @@ impl OakDEX {
         amount1_out: U256,
         data: Vec<u8>,
     ) -> OakResult<()> {
-        // CRITICAL: Re-entrancy guard acquired at the VERY BEGINNING
-        // This must be the first state-modifying operation
-        lock_reentrancy_guard(self)?;
-
-        // Input sanitization: validate addresses
-        require_non_zero_address(token0)?;
-        require_non_zero_address(token1)?;
-
-        // Input sanitization: at least one amount must be non-zero
-        if amount0_out.is_zero() && amount1_out.is_zero() {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_INSUFFICIENT_INPUT_AMOUNT));
-        }
-
-        require_not_paused(self)?;
-
-        // Snapshot reserves and fee configuration before the swap
-        let reserve0_before = self.reserves0.get();
-        let reserve1_before = self.reserves1.get();
-        let fee_bps = self.protocol_fee_bps.get();
-
-        // Calculate initial k (constant product before swap)
-        let k_before = reserve0_before
-            .checked_mul(reserve1_before)
-            .ok_or_else(|| {
-                unlock_reentrancy_guard(self);
-                err(ERR_OVERFLOW)
-            })?;
-
-        // Verify sufficient liquidity for the requested amounts
-        if amount0_out > reserve0_before || amount1_out > reserve1_before {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_INSUFFICIENT_LIQUIDITY));
-        }
-
-        // Calculate new reserves after lending (before callback)
-        let reserve0_after_lend = reserve0_before
-            .checked_sub(amount0_out)
-            .ok_or_else(|| {
-                unlock_reentrancy_guard(self);
-                err(ERR_INSUFFICIENT_LIQUIDITY)
-            })?;
+        with_lock(self, |dex| {
+            // Input sanitization: validate addresses
+            require_non_zero_address(token0)?;
+            require_non_zero_address(token1)?;
+            // Only a real pair: bound tokens in single-pair mode, an existing pool in factory mode.
+            require_bound_pair(dex, token0, token1)?;
+            let (pool_token0, pool_token1) = if token0 < token1 { (token0, token1) } else { (token1, token0) };
+            if !dex.pools.getter(pool_token0).getter(pool_token1).initialized.get() {
+                return Err(err(ERR_INVALID_TOKEN));
+            }
 
-        let reserve1_after_lend = reserve1_before
-            .checked_sub(amount1_out)
-            .ok_or_else(|| {
-                unlock_reentrancy_guard(self);
-                err(ERR_INSUFFICIENT_LIQUIDITY)
-            })?;
+            // Input sanitization: at least one amount must be non-zero
+            if amount0_out.is_zero() && amount1_out.is_zero() {
+                return Err(err(ERR_INSUFFICIENT_INPUT_AMOUNT));
+            }
+            if !amount0_out.is_zero() {
+                TokenRisk::require_flash_borrow_allowed(dex, token0)?;
+            }
+            if !amount1_out.is_zero() {
+                TokenRisk::require_flash_borrow_allowed(dex, token1)?;
+            }
 
-        // Ensure minimum liquidity is maintained
-        let min_liquidity = self.min_liquidity.get();
-        if reserve0_after_lend < min_liquidity || reserve1_after_lend < min_liquidity {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_INSUFFICIENT_LIQUIDITY));
-        }
+            require_not_paused(dex)?;
 
-        // Transfer tokens to borrower (INTERACTION: external call)
-        let borrower = msg::sender();
-        let contract_addr = contract::address();
+            // Snapshot reserves and fee configuration before the swap
+            let reserve0_before = dex.reserves0.get();
+            let reserve1_before = dex.reserves1.get();
+            let fee_bps = flash_fee_bps(dex, token0, token1);
 
-        if !amount0_out.is_zero() {
-            if let Err(e) = safe_transfer(token0, borrower, amount0_out) {
-                unlock_reentrancy_guard(self);
-                return Err(e);
-            }
-        }
+            // Calculate initial k (constant product before swap)
+            let k_before = reserve0_before
+                .checked_mul(reserve1_before)
+                .ok_or_else(|| err(ERR_OVERFLOW))?;
 
-        if !amount1_out.is_zero() {
-            if let Err(e) = safe_transfer(token1, borrower, amount1_out) {
-                unlock_reentrancy_guard(self);
-                return Err(e);
+            // Verify sufficient liquidity for the requested amounts
+            if amount0_out > reserve0_before || amount1_out > reserve1_before {
+                return Err(err(ERR_INSUFFICIENT_LIQUIDITY));
             }
-        }
-
-        // Calculate fees owed (0.3% of borrowed amounts)
-        // Fee calculation: fee = amount * fee_bps / FEE_DENOMINATOR
-        let fee0 = if !amount0_out.is_zero() {
-            amount0_out
-                .checked_mul(fee_bps)
-                .ok_or_else(|| {
-                    unlock_reentrancy_guard(self);
-                    err(ERR_OVERFLOW)
-                })?
-                .checked_div(as_u256(FEE_DENOMINATOR))
-                .ok_or_else(|| {
-                    unlock_reentrancy_guard(self);
-                    err(ERR_DIVISION_BY_ZERO)
-                })?
-        } else {
-            U256::ZERO
-        };
-
-        let fee1 = if !amount1_out.is_zero() {
-            amount1_out
-                .checked_mul(fee_bps)
-                .ok_or_else(|| {
-                    unlock_reentrancy_guard(self);
-                    err(ERR_OVERFLOW)
-                })?
-                .checked_div(as_u256(FEE_DENOMINATOR))
-                .ok_or_else(|| {
-                    unlock_reentrancy_guard(self);
-                    err(ERR_DIVISION_BY_ZERO)
-                })?
-        } else {
-            U256::ZERO
-        };
 
-        // Calculate total repayment amounts (borrowed + fees)
-        let amount0_owed = amount0_out
-            .checked_add(fee0)
-            .ok_or_else(|| {
-                unlock_reentrancy_guard(self);
-                err(ERR_OVERFLOW)
-            })?;
+            // Calculate new reserves after lending (before callback)
+            let reserve0_after_lend = reserve0_before
+                .checked_sub(amount0_out)
+                .ok_or_else(|| err(ERR_INSUFFICIENT_LIQUIDITY))?;
 
-        let amount1_owed = amount1_out
-            .checked_add(fee1)
-            .ok_or_else(|| {
-                unlock_reentrancy_guard(self);
-                err(ERR_OVERFLOW)
-            })?;
+            let reserve1_after_lend = reserve1_before
+                .checked_sub(amount1_out)
+                .ok_or_else(|| err(ERR_INSUFFICIENT_LIQUIDITY))?;
 
-        // Call callback (INTERACTION: external call to borrower's contract)
-        // The borrower must implement: oakFlashSwapCallback(uint256,uint256,bytes)
-        // We use ABI encoding to call the callback function
-        let selector = crypto::keccak(b"oakFlashSwapCallback(uint256,uint256,bytes)");
-        let mut call_data = Vec::new();
-        call_data.extend_from_slice(&selector[0..4]); // Function selector (first 4 bytes)
-        
-        // ABI encode parameters: (uint256, uint256, bytes)
-        // For uint256: pad to 32 bytes, big-endian
-        call_data.extend_from_slice(&amount0_owed.to_be_bytes::<32>());
-        call_data.extend_from_slice(&amount1_owed.to_be_bytes::<32>());
-        
-        // For bytes: offset (32 bytes) + length (32 bytes) + data (padded to 32-byte boundary)
-        let data_offset = U256::from(96u64); // offset to data: 32 (amount0) + 32 (amount1) + 32 (offset)
-        call_data.extend_from_slice(&data_offset.to_be_bytes::<32>());
-        let data_len = U256::from(data.len());
-        call_data.extend_from_slice(&data_len.to_be_bytes::<32>());
-        call_data.extend_from_slice(&data);
-        // Pad data to 32-byte boundary
-        let padding = (32 - (data.len() % 32)) % 32;
-        for _ in 0..padding {
-            call_data.push(0u8);
-        }
-        
-        // Make the external call - this will revert if callback fails.
-        // The callback must transfer the repayment tokens back to this contract.
-        // Stylus call API: call::call(context, to, data).
-        if let Err(e) = call::call(Call::new(), borrower, &call_data) {
-            unlock_reentrancy_guard(self);
-            return Err(e.into());
-        }
-
-        // Verify repayment: check contract balances after callback
-        let balance0_after = balance_of(token0, contract_addr);
-        let balance1_after = balance_of(token1, contract_addr);
-
-        // Calculate what the balances should be after repayment
-        // We need: balance0_after >= reserve0_after_lend + amount0_owed
-        //         balance1_after >= reserve1_after_lend + amount1_owed
-        let expected_balance0 = reserve0_after_lend
-            .checked_add(amount0_owed)
-            .ok_or_else(|| {
-                unlock_reentrancy_guard(self);
-                err(ERR_OVERFLOW)
-            })?;
+            // Ensure minimum liquidity is maintained
+            let min_liquidity = dex.min_liquidity.get();
+            if reserve0_after_lend < min_liquidity || reserve1_after_lend < min_liquidity {
+                return Err(err(ERR_INSUFFICIENT_LIQUIDITY));
+            }
 
-        let expected_balance1 = reserve1_after_lend
-            .checked_add(amount1_owed)
-            .ok_or_else(|| {
-                unlock_reentrancy_guard(self);
-                err(ERR_OVERFLOW)
+            // Transfer tokens to borrower (INTERACTION: external call)
+            let borrower = msg::sender();
+            let contract_addr = contract::address();
+
+            guarded_external_call(dex, token0, token1, || {
+                if !amount0_out.is_zero() {
+                    safe_transfer(token0, borrower, amount0_out)?;
+                }
+                if !amount1_out.is_zero() {
+                    safe_transfer(token1, borrower, amount1_out)?;
+                }
+                Ok(())
             })?;
 
-        if balance0_after < expected_balance0 || balance1_after < expected_balance1 {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_INSUFFICIENT_LIQUIDITY));
-        }
+            // Calculate fees owed (flash fee on the borrowed amounts, see `flash_fee_bps`)
+            // Fee calculation: fee = amount * fee_bps / FEE_DENOMINATOR
+            let fee0 = if !amount0_out.is_zero() {
+                amount0_out
+                    .checked_mul(fee_bps)
+                    .ok_or_else(|| err(ERR_OVERFLOW))?
+                    .checked_div(as_u256(FEE_DENOMINATOR))
+                    .ok_or_else(|| err(ERR_DIVISION_BY_ZERO))?
+            } else {
+                U256::ZERO
+            };
 
-        // Calculate actual repayment amounts (may be more than required)
-        let actual_repayment0 = balance0_after
-            .checked_sub(reserve0_after_lend)
-            .ok_or_else(|| {
-                unlock_reentrancy_guard(self);
-                err(ERR_INSUFFICIENT_LIQUIDITY)
-            })?;
+            let fee1 = if !amount1_out.is_zero() {
+                amount1_out
+                    .checked_mul(fee_bps)
+                    .ok_or_else(|| err(ERR_OVERFLOW))?
+                    .checked_div(as_u256(FEE_DENOMINATOR))
+                    .ok_or_else(|| err(ERR_DIVISION_BY_ZERO))?
+            } else {
+                U256::ZERO
+            };
 
-        let actual_repayment1 = balance1_after
-            .checked_sub(reserve1_after_lend)
-            .ok_or_else(|| {
-                unlock_reentrancy_guard(self);
-                err(ERR_INSUFFICIENT_LIQUIDITY)
-            })?;
+            // Calculate total repayment amounts (borrowed + fees)
+            let amount0_owed = amount0_out
+                .checked_add(fee0)
+                .ok_or_else(|| err(ERR_OVERFLOW))?;
 
-        // Update reserves to reflect the repayment
-        // New reserves = reserves_after_lend + actual_repayment
-        let reserve0_after = reserve0_after_lend
-            .checked_add(actual_repayment0)
-            .ok_or_else(|| {
-                unlock_reentrancy_guard(self);
-                err(ERR_RESERVE0_OVERFLOW)
-            })?;
+            let amount1_owed = amount1_out
+                .checked_add(fee1)
+                .ok_or_else(|| err(ERR_OVERFLOW))?;
 
-        let reserve1_after = reserve1_after_lend
-            .checked_add(actual_repayment1)
-            .ok_or_else(|| {
-                unlock_reentrancy_guard(self);
-                err(ERR_RESERVE1_OVERFLOW)
+            // Call callback (INTERACTION: external call to borrower's contract) in the style the
+            // borrower registered with set_flash_callback (Oak's oakFlashSwapCallback by default).
+            let style = dex.flash_callback_style.get(borrower);
+            let call_data =
+                encode_flash_swap_callback(style, borrower, amount0_out, amount1_out, fee0, fee1, &data).map_err(err)?;
+
+            // Make the external call - this will revert if callback fails.
+            // The callback must transfer the repayment tokens back to this contract.
+            // Stylus call API: call::call(context, to, data).
+            guarded_external_call(dex, token0, token1, || {
+                call::call(Call::new(), borrower, &call_data)
+                    .map(|_| ())
+                    .map_err(|e| e.into())
             })?;
 
-        // CRITICAL: Verify k' >= k * (1 + fee_rate)
-        // This ensures the protocol doesn't lose value and collects fees
-        // k_after = reserve0_after * reserve1_after
-        let k_after = reserve0_after
-            .checked_mul(reserve1_after)
-            .ok_or_else(|| {
-                unlock_reentrancy_guard(self);
-                err(ERR_OVERFLOW)
-            })?;
+            // Verify repayment: check contract balances after callback
+            let balance0_after = balance_of(token0, contract_addr);
+            let balance1_after = balance_of(token1, contract_addr);
 
-        // Calculate minimum k required: k_min = k_before * (FEE_DENOMINATOR + fee_bps) / FEE_DENOMINATOR
-        // This ensures the new product includes the 0.3% fee as required
-        // Example: if fee_bps = 30 (0.3%), then k_min = k_before * 10030 / 10000
-        let fee_multiplier = as_u256(FEE_DENOMINATOR)
-            .checked_add(fee_bps)
-            .ok_or_else(|| {
-                unlock_reentrancy_guard(self);
-                err(ERR_OVERFLOW)
-            })?;
+            // Calculate what the balances should be after repayment
+            // We need: balance0_after >= reserve0_after_lend + amount0_owed
+            //         balance1_after >= reserve1_after_lend + amount1_owed
+            let expected_balance0 = reserve0_after_lend
+                .checked_add(amount0_owed)
+                .ok_or_else(|| err(ERR_OVERFLOW))?;
 
-        let k_min = k_before
-            .checked_mul(fee_multiplier)
-            .ok_or_else(|| {
-                unlock_reentrancy_guard(self);
-                err(ERR_OVERFLOW)
-            })?
-            .checked_div(as_u256(FEE_DENOMINATOR))
-            .ok_or_else(|| {
-                unlock_reentrancy_guard(self);
-                err(ERR_DIVISION_BY_ZERO)
-            })?;
+            let expected_balance1 = reserve1_after_lend
+                .checked_add(amount1_owed)
+                .ok_or_else(|| err(ERR_OVERFLOW))?;
 
-        // Verify k_after >= k_min (protocol must not lose value, fees must be paid)
-        // This is the core requirement: new product must be >= old product * (1 + fee)
-        if k_after < k_min {
-            unlock_reentrancy_guard(self);
-            return Err(err(ERR_INSUFFICIENT_LIQUIDITY));
-        }
+            if balance0_after < expected_balance0 || balance1_after < expected_balance1 {
+                return Err(err(ERR_INSUFFICIENT_LIQUIDITY));
+            }
 
-        // Update reserves (EFFECT: state change)
-        self.reserves0.set(reserve0_after);
-        self.reserves1.set(reserve1_after);
+            // Calculate actual repayment amounts (may be more than required)
+            let actual_repayment0 = balance0_after
+                .checked_sub(reserve0_after_lend)
+                .ok_or_else(|| err(ERR_INSUFFICIENT_LIQUIDITY))?;
+
+            let actual_repayment1 = balance1_after
+                .checked_sub(reserve1_after_lend)
+                .ok_or_else(|| err(ERR_INSUFFICIENT_LIQUIDITY))?;
+
+            // Update reserves to reflect the repayment
+            // New reserves = reserves_after_lend + actual_repayment
+            let reserve0_after = reserve0_after_lend
+                .checked_add(actual_repayment0)
+                .ok_or_else(|| err(ERR_RESERVE0_OVERFLOW))?;
+
+            let reserve1_after = reserve1_after_lend
+                .checked_add(actual_repayment1)
+                .ok_or_else(|| err(ERR_RESERVE1_OVERFLOW))?;
+
+            // CRITICAL: Verify k' >= k * (1 + fee_rate)
+            // This ensures the protocol doesn't lose value and collects fees
+            // k_after = reserve0_after * reserve1_after
+            let k_after = reserve0_after
+                .checked_mul(reserve1_after)
+                .ok_or_else(|| err(ERR_OVERFLOW))?;
 
-        // Update analytics: track flash swap volume
-        let current_volume0 = self.total_volume_token0.get();
-        let current_volume1 = self.total_volume_token1.get();
+            // Calculate minimum k required: k_min = k_before * (FEE_DENOMINATOR + fee_bps) / FEE_DENOMINATOR
+            // This ensures the new product includes the flash fee as required
+            // Example: if fee_bps = 30 (0.3%), then k_min = k_before * 10030 / 10000
+            let fee_multiplier = as_u256(FEE_DENOMINATOR)
+                .checked_add(fee_bps)
+                .ok_or_else(|| err(ERR_OVERFLOW))?;
 
-        if !amount0_out.is_zero() {
-            let new_volume0 = current_volume0
-                .checked_add(amount0_out)
-                .ok_or_else(|| {
-                    unlock_reentrancy_guard(self);
-                    err(ERR_VOLUME_OVERFLOW)
-                })?;
-            self.total_volume_token0.set(new_volume0);
-        }
+            let k_min = k_before
+                .checked_mul(fee_multiplier)
+                .ok_or_else(|| err(ERR_OVERFLOW))?
+                .checked_div(as_u256(FEE_DENOMINATOR))
+                .ok_or_else(|| err(ERR_DIVISION_BY_ZERO))?;
 
-        if !amount1_out.is_zero() {
-            let new_volume1 = current_volume1
-                .checked_add(amount1_out)
-                .ok_or_else(|| {
-                    unlock_reentrancy_guard(self);
-                    err(ERR_VOLUME_OVERFLOW)
-                })?;
-            self.total_volume_token1.set(new_volume1);
-        }
+            // Verify k_after >= k_min (protocol must not lose value, fees must be paid)
+            // This is the core requirement: new product must be >= old product * (1 + fee)
+            if k_after < k_min {
+                return Err(err(ERR_INSUFFICIENT_LIQUIDITY));
+            }
 
-        // Update fee accounting (60/20/20: per-token treasury and buyback)
-        if !fee0.is_zero() {
-            let (_e, treasury_fee0, _lp0, buyback_fee0) =
-                match compute_fee_split(amount0_out, fee_bps) {
-                    Ok(s) => s,
+            // Book each token's fee in that token (60/20/20: treasury, buyback and the pool's LP fee
+            // accumulator). There is no revealer on a flash swap, so the gas rebate share stays
+            // with the treasury. The booked parts leave the reserves, as on a swap.
+            let mut booked = [U256::ZERO; 2];
+            for (slot, token, amount_out, fee) in [(0, token0, amount0_out, fee0), (1, token1, amount1_out, fee1)] {
+                if fee.is_zero() {
+                    continue;
+                }
+                booked[slot] = match accrue_flash_fee(dex, pool_token0, pool_token1, token, amount_out, fee_bps) {
+                    Ok(b) => b,
                     Err(e) => {
-                        unlock_reentrancy_guard(self);
                         return Err(e);
                     }
                 };
-            let pt = self.treasury_balance.setter(token0);
-            let pb = self.buyback_balance.setter(token0);
-            pt.set(pt.get().checked_add(treasury_fee0).ok_or_else(|| {
-                unlock_reentrancy_guard(self);
-                err(ERR_OVERFLOW)
-            })?);
-            pb.set(pb.get().checked_add(buyback_fee0).ok_or_else(|| {
-                unlock_reentrancy_guard(self);
-                err(ERR_OVERFLOW)
-            })?);
-        }
-        if !fee1.is_zero() {
-            let (_e, treasury_fee1, _lp1, buyback_fee1) =
-                match compute_fee_split(amount1_out, fee_bps) {
-                    Ok(s) => s,
-                    Err(e) => {
-                        unlock_reentrancy_guard(self);
-                        return Err(e);
+            }
+            let (reserve0_after, reserve1_after) =
+                match (reserve0_after.checked_sub(booked[0]), reserve1_after.checked_sub(booked[1])) {
+                    (Some(r0), Some(r1)) => (r0, r1),
+                    _ => {
+                        return Err(err(ERR_INSUFFICIENT_LIQUIDITY));
                     }
                 };
-            let pt = self.treasury_balance.setter(token1);
-            let pb = self.buyback_balance.setter(token1);
-            pt.set(pt.get().checked_add(treasury_fee1).ok_or_else(|| {
-                unlock_reentrancy_guard(self);
-                err(ERR_OVERFLOW)
-            })?);
-            pb.set(pb.get().checked_add(buyback_fee1).ok_or_else(|| {
-                unlock_reentrancy_guard(self);
-                err(ERR_OVERFLOW)
-            })?);
-        }
-
-        // Emit FlashSwap event
-        emit_flash_swap(borrower, token0, token1, amount0_out, amount1_out, fee0, fee1);
-
-        // CRITICAL: Release re-entrancy guard at the VERY END
-        // This must be the last operation before return
-        unlock_reentrancy_guard(self);
+            // Net of the booked fees, k may still not fall below k_before.
+            match reserve0_after.checked_mul(reserve1_after) {
+                Some(k_net) if k_net >= k_before => {}
+                _ => {
+                    return Err(err(ERR_K_DECREASED));
+                }
+            }
+            // What the flash swap took out net of repayment counts towards the per-block outflow limit.
+            for (token, before, after) in [(token0, reserve0_before, reserve0_after), (token1, reserve1_before, reserve1_after)] {
+                let out = before.saturating_sub(after);
+                FlowLimiter::record_outflow(dex, pool_token0, pool_token1, token == pool_token0, before, out)?;
+            }
 
-        Ok(())
+            // Update reserves (EFFECT: state change)
+            dex.reserves0.set(reserve0_after);
+            dex.reserves1.set(reserve1_after);
+
+            // Update analytics: track flash swap volume
+            let current_volume0 = dex.total_volume_token0.get();
+            let current_volume1 = dex.total_volume_token1.get();
+
+            if !amount0_out.is_zero() {
+                let new_volume0 = current_volume0
+                    .checked_add(amount0_out)
+                    .ok_or_else(|| err(ERR_VOLUME_OVERFLOW))?;
+                dex.total_volume_token0.set(new_volume0);
+            }
+
+            if !amount1_out.is_zero() {
+                let new_volume1 = current_volume1
+                    .checked_add(amount1_out)
+                    .ok_or_else(|| err(ERR_VOLUME_OVERFLOW))?;
+                dex.total_volume_token1.set(new_volume1);
+            }
+
+            // Emit FlashSwap event
+            let k_ratio = compute_k_ratio((reserve0_before, reserve1_before), (reserve0_after, reserve1_after));
+            emit_flash_swap(
+                borrower,
+                token0,
+                token1,
+                amount0_out,
+                amount1_out,
+                fee0,
+                fee1,
+                reserve0_after,
+                reserve1_after,
+                k_ratio,
+            );
+
+            Ok(())
+        })
     }
 }
 
@@ -3283,11 +7801,11 @@ mod tests {
         let amount_in = U256::from(1_000_000u64);
         let fee_bps = as_u256(DEFAULT_FEE_BPS);
 
-        let (_effective_in, treasury_fee, lp_fee, buyback_fee) =
+        let (_effective_in, treasury_fee, lp_fee, buyback_fee, gas_rebate) =
             compute_fee_split(amount_in, fee_bps).unwrap();
 
         // Total fee should be 0.3% of amount_in.
-        let total_fee = treasury_fee + lp_fee + buyback_fee;
+        let total_fee = treasury_fee + lp_fee + buyback_fee + gas_rebate;
         let expected_total_fee = amount_in * as_u256(DEFAULT_FEE_BPS) / as_u256(FEE_DENOMINATOR);
         assert_eq!(total_fee, expected_total_fee);
 
@@ -3295,23 +7813,1321 @@ mod tests {
         let expected_treasury = total_fee * as_u256(TREASURY_FEE_PCT) / U256::from(100u64);
         let expected_lp = total_fee * as_u256(LP_FEE_PCT) / U256::from(100u64);
         let expected_buyback = total_fee * as_u256(BUYBACK_FEE_PCT) / U256::from(100u64);
+        let expected_rebate = total_fee * as_u256(GAS_REBATE_BPS) / as_u256(FEE_DENOMINATOR);
 
-        assert_eq!(treasury_fee, expected_treasury);
+        // The gas rebate comes out of the treasury share.
+        assert_eq!(gas_rebate, expected_rebate);
+        assert_eq!(treasury_fee + gas_rebate, expected_treasury);
         assert_eq!(lp_fee, expected_lp);
         assert_eq!(buyback_fee, expected_buyback);
     }
 
+    #[test]
+    fn fee_split_scales_with_configured_fee() {
+        let amount_in = U256::from(123_456_789u64);
+        for fee in [1u64, DEFAULT_FEE_BPS, INITIAL_FEE, 333, MAX_FEE_BPS] {
+            let fee_bps = as_u256(fee);
+            let (effective_in, treasury_fee, lp_fee, buyback_fee, gas_rebate) =
+                compute_fee_split(amount_in, fee_bps).unwrap();
+            let total_fee = amount_in * fee_bps / as_u256(FEE_DENOMINATOR);
+
+            assert_eq!(effective_in + treasury_fee + lp_fee + buyback_fee + gas_rebate, amount_in);
+            assert_eq!(treasury_fee + gas_rebate, total_fee * as_u256(TREASURY_FEE_PCT) / U256::from(100u64));
+            assert_eq!(buyback_fee, total_fee * as_u256(BUYBACK_FEE_PCT) / U256::from(100u64));
+            // LP gets at least its nominal share (plus rounding remainder).
+            assert!(lp_fee >= total_fee * as_u256(LP_FEE_PCT) / U256::from(100u64));
+        }
+    }
+
+    #[test]
+    fn fee_split_rejects_fee_above_max() {
+        let amount_in = U256::from(1_000_000u64);
+        assert_eq!(
+            compute_fee_split(amount_in, as_u256(MAX_FEE_BPS + 1)),
+            Err(ERR_FEE_TOO_HIGH)
+        );
+    }
+
+    #[test]
+    fn batch_crossing_fills_smaller_side_at_spot() {
+        // Spot: 1 X = 2 Y. 100 X against 150 Y -> all Y crossed for 75 X, 25 X residual.
+        let (x, y) = compute_batch_crossing(
+            U256::from(100u64),
+            U256::from(150u64),
+            U256::from(1_000u64),
+            U256::from(2_000u64),
+        )
+        .unwrap();
+        assert_eq!((x, y), (U256::from(75u64), U256::from(150u64)));
+
+        // 100 X against 500 Y -> all X crossed for 200 Y, 300 Y residual.
+        let (x, y) = compute_batch_crossing(
+            U256::from(100u64),
+            U256::from(500u64),
+            U256::from(1_000u64),
+            U256::from(2_000u64),
+        )
+        .unwrap();
+        assert_eq!((x, y), (U256::from(100u64), U256::from(200u64)));
+    }
+
+    #[test]
+    fn batch_crossing_reverts_when_spot_strays_from_twap() {
+        use crate::oracle::spot_prices;
+        // TWAP: 1 X = 2 Y. A swap earlier in the block pushed the pool to 1 X = 2.2 Y.
+        let (twap, _) = spot_prices(U256::from(1_000u64), U256::from(2_000u64)).unwrap();
+        let (pushed, _) = spot_prices(U256::from(1_000u64), U256::from(2_200u64)).unwrap();
+        assert_eq!(check_batch_crossing_price(pushed, twap), Err(err(ERR_BATCH_PRICE_DEVIATION)));
+        // Either direction: pushed down just as far also reverts.
+        let (pushed_down, _) = spot_prices(U256::from(1_000u64), U256::from(1_800u64)).unwrap();
+        assert_eq!(check_batch_crossing_price(pushed_down, twap), Err(err(ERR_BATCH_PRICE_DEVIATION)));
+        // Within the 2% bound the batch still crosses at spot.
+        let (drifted, _) = spot_prices(U256::from(1_000u64), U256::from(2_030u64)).unwrap();
+        assert_eq!(check_batch_crossing_price(drifted, twap), Ok(()));
+        assert_eq!(check_batch_crossing_price(twap, twap), Ok(()));
+    }
+
+    #[test]
+    fn decimal_adjusted_price_scales_whole_tokens() {
+        use crate::constants::q112_u256;
+        use crate::oracle::decimal_adjusted_price;
+        let wad = U256::from(10u64).pow(U256::from(18u64));
+        // Pool of 10 WETH (18 decimals) and 30_000 USDC (6 decimals).
+        let weth = U256::from(10u64) * wad;
+        let usdc = U256::from(30_000_000_000u64);
+        let weth_in_usdc = usdc * q112_u256() / weth;
+        let usdc_in_weth = weth * q112_u256() / usdc;
+        // Within one unit of floor rounding (Q112 division, then the rescale).
+        let p = decimal_adjusted_price(weth_in_usdc, 18, 6).unwrap();
+        assert!(U256::from(3_000u64) * wad - p <= U256::from(1u64));
+        // 1 USDC = 1/3000 WETH.
+        let p = decimal_adjusted_price(usdc_in_weth, 6, 18).unwrap();
+        assert!(wad / U256::from(3_000u64) - p <= U256::from(1u64));
+        // Equal decimals: the raw ratio, 1e18-scaled.
+        assert_eq!(decimal_adjusted_price(q112_u256() * U256::from(2u64), 8, 8).unwrap(), U256::from(2u64) * wad);
+    }
+
+    #[test]
+    fn token_return_data_follows_safe_erc20() {
+        use crate::token::returned_success;
+        let word = |last: u8| {
+            let mut w = [0u8; 32];
+            w[31] = last;
+            w
+        };
+        // USDT-style: no return value.
+        assert!(returned_success(&[]));
+        assert!(returned_success(&word(1)));
+        assert!(!returned_success(&word(0)));
+        assert!(!returned_success(&word(2)));
+        assert!(!returned_success(&[1u8]));
+        let mut dirty = word(1);
+        dirty[0] = 1;
+        assert!(!returned_success(&dirty));
+    }
+
+    #[test]
+    fn fee_on_transfer_pulls_credit_only_what_arrived() {
+        use crate::token::received_amount;
+        // A token that burns 1% of every transfer.
+        let fot = |amount: U256| amount - amount / U256::from(100u64);
+        let before = U256::from(5_000u64);
+        let requested = U256::from(1_000u64);
+        let credited = received_amount(before, before + fot(requested), requested).unwrap();
+        assert_eq!(credited, U256::from(990u64));
+        // A batch order or vault contribution of 1_000 counts as 990; pro-rata shares follow it.
+        let contributions = [U256::from(1_000u64), U256::from(3_000u64)];
+        let received: Vec<U256> = contributions
+            .iter()
+            .map(|a| received_amount(U256::ZERO, fot(*a), *a).unwrap())
+            .collect();
+        let total: U256 = received.iter().copied().fold(U256::ZERO, |acc, x| acc + x);
+        assert_eq!(total, U256::from(3_960u64));
+        let out = U256::from(7_920u64);
+        assert_eq!(out * received[0] / total, U256::from(1_980u64));
+        // Extra tokens never inflate the credit, and nothing arriving fails the pull.
+        assert_eq!(received_amount(before, before + U256::from(2_000u64), requested), Ok(requested));
+        assert_eq!(received_amount(before, before, requested), Err(err(ERR_TOKEN_TRANSFER_FAILED)));
+        assert_eq!(received_amount(before, before - U256::from(1u64), requested), Err(err(ERR_TOKEN_TRANSFER_FAILED)));
+    }
+
+    #[test]
+    fn eth_pool_round_trip_claims_msg_value_once() {
+        use crate::token::claim_native_value;
+        let e = |x: u64| U256::from(x) * U256::from(10u64).pow(U256::from(15u64));
+        let fee = as_u256(30u64);
+        // add_liquidity_eth: msg.value is the whole ETH side and is claimed once.
+        let value = e(10_000);
+        let used = claim_native_value(value, U256::ZERO, value).unwrap();
+        assert_eq!(claim_native_value(value, used, U256::from(1u64)).unwrap_err(), err(ERR_NATIVE_VALUE_MISMATCH));
+        let (eth, tok) = optimal_liquidity_amounts(value, e(20_000), U256::ZERO, U256::ZERO).unwrap();
+        let shares = compute_liquidity_minted(eth, tok, U256::ZERO, U256::ZERO, U256::ZERO).unwrap();
+        let supply = shares + as_u256(MINIMUM_LIQUIDITY);
+        // reveal_swap_eth_in: ETH (token0) in, token out; msg.value must cover amount_in exactly once.
+        let amount_in = e(100);
+        claim_native_value(amount_in, U256::ZERO, amount_in).unwrap();
+        assert!(claim_native_value(amount_in, amount_in, amount_in).is_err());
+        let out = get_amount_out_with_fee(amount_in, eth, tok, fee).unwrap();
+        let (r0, r1) = (eth + amount_in, tok - out);
+        // remove_liquidity: the ETH side is paid with safe_transfer_eth, and the swap left it larger.
+        let (eth_out, tok_out) = compute_liquidity_burned(shares, r0, r1, supply).unwrap();
+        assert!(eth_out > eth - e(1) && eth_out < r0);
+        assert!(tok_out < tok && tok_out < r1);
+        // The constant product never shrinks across the round trip.
+        assert!((r0 - eth_out) * (r1 - tok_out) >= as_u256(MINIMUM_LIQUIDITY) * as_u256(MINIMUM_LIQUIDITY));
+        assert!(r0 * r1 >= eth * tok);
+    }
+
+    #[test]
+    fn permit_counts_only_once_the_allowance_is_set() {
+        use crate::token::check_permit_allowance;
+        let value = U256::from(1_000u64);
+        // A WETH-style fallback swallows the permit call without reverting and sets nothing.
+        assert_eq!(check_permit_allowance(U256::ZERO, value), Err(err(ERR_PERMIT_INVALID_SIGNATURE)));
+        assert_eq!(check_permit_allowance(value - U256::from(1u64), value), Err(err(ERR_PERMIT_INVALID_SIGNATURE)));
+        // Front-run with the same signature: our call reverts, but the allowance is in place.
+        assert_eq!(check_permit_allowance(value, value), Ok(()));
+        assert_eq!(check_permit_allowance(U256::MAX, value), Ok(()));
+    }
+
+    #[test]
+    fn zap_deposits_into_the_first_and_last_path_token_pool() {
+        let (a, b, c) = (Address::repeat_byte(1), Address::repeat_byte(2), Address::repeat_byte(3));
+        assert_eq!(zap_pool(&[a, b]), Ok((a, b)));
+        assert_eq!(zap_pool(&[b, a]), Ok((a, b)));
+        // A -> B -> C zaps into (A, C): a pool neither hop swapped through, so it is checked up front.
+        let target = zap_pool(&[c, b, a]).unwrap();
+        assert_eq!(target, (a, c));
+        let target_key = compute_pair_key(target.0, target.1);
+        assert!(target_key != compute_pair_key(c, b) && target_key != compute_pair_key(b, a));
+        assert_eq!(zap_pool(&[a, b, a]), Err(err(ERR_INVALID_PATH)));
+        assert_eq!(zap_pool(&[a]), Err(err(ERR_INVALID_PATH)));
+    }
+
+    #[test]
+    fn permit_calldata_is_erc2612() {
+        use crate::token::encode_permit;
+        let owner = Address::repeat_byte(1);
+        let spender = Address::repeat_byte(2);
+        let (r, s) = (FixedBytes::repeat_byte(3), FixedBytes::repeat_byte(4));
+        let data = encode_permit(owner, spender, U256::from(5u64), U256::from(6u64), (27, r, s));
+        assert_eq!(
+            &data[0..4],
+            &crypto::keccak(b"permit(address,address,uint256,uint256,uint8,bytes32,bytes32)")[0..4]
+        );
+        assert_eq!(data.len(), 4 + 7 * 32);
+        assert_eq!(&data[16..36], owner.as_slice());
+        assert_eq!(&data[48..68], spender.as_slice());
+        assert_eq!(U256::from_be_slice(&data[68..100]), U256::from(5u64));
+        assert_eq!(U256::from_be_slice(&data[100..132]), U256::from(6u64));
+        assert_eq!(U256::from_be_slice(&data[132..164]), U256::from(27u64));
+        assert_eq!(&data[164..196], r.as_slice());
+        assert_eq!(&data[196..228], s.as_slice());
+    }
+
+    #[test]
+    fn swap_callback_calldata_is_abi_encoded() {
+        let data = [0xab_u8; 33];
+        let encoded = encode_swap_callback(U256::from(5u64), U256::from(7u64), &data);
+        assert_eq!(&encoded[0..4], &crypto::keccak(b"oakSwapCallback(uint256,uint256,bytes)")[0..4]);
+        // Selector + 4 head words + 33 bytes padded to 64.
+        assert_eq!(encoded.len(), 4 + 128 + 64);
+        assert_eq!(U256::from_be_slice(&encoded[68..100]), U256::from(96u64));
+        assert_eq!(U256::from_be_slice(&encoded[100..132]), U256::from(33u64));
+        assert_eq!(encoded[132 + 32], 0xab);
+        assert!(encoded[132 + 33..].iter().all(|b| *b == 0));
+        assert_eq!(encode_swap_callback(U256::ZERO, U256::ZERO, &[]).len(), 4 + 128);
+    }
+
+    #[test]
+    fn private_commitment_hides_strategy_fields() {
+        let u = |v: u64| U256::from(v);
+        let fields = compute_private_fields_hash(u(7), u(42), u(999));
+        let private = compute_private_commit_hash(u(1_000), true, u(50), u(1), fields);
+        // A plain reveal of the same execution fields never matches a private commitment.
+        assert_ne!(private, compute_commit_hash(u(1_000), true, u(50), u(1)));
+        // Any change to an undisclosed field changes the commitment.
+        let other_tag = compute_private_fields_hash(u(7), u(43), u(999));
+        assert_ne!(private, compute_private_commit_hash(u(1_000), true, u(50), u(1), other_tag));
+        assert_eq!(fields, compute_private_fields_hash(u(7), u(42), u(999)));
+    }
+
+    #[test]
+    fn outflow_limit_caps_cumulative_flow_per_block() {
+        use crate::flow_limit::check_outflow;
+        let (base, cap) = (U256::from(10_000u64), U256::from(500u64));
+        assert_eq!(check_outflow(base, U256::ZERO, U256::from(500u64), cap), Ok(()));
+        // Cumulative: a second trade (or flash loan) in the same block sees the first one's outflow.
+        assert_eq!(check_outflow(base, U256::from(300u64), U256::from(200u64), cap), Ok(()));
+        assert_eq!(check_outflow(base, U256::from(300u64), U256::from(201u64), cap), Err(err(ERR_OUTFLOW_LIMIT_EXCEEDED)));
+        assert_eq!(check_outflow(base, U256::MAX, U256::from(1u64), cap), Err(err(ERR_OVERFLOW)));
+    }
+
+    #[test]
+    fn declared_exposure_bounds_the_reveal() {
+        use crate::commit_exposure::check_declared;
+        // Declared: token0 in, at most 1_000; consume_commitment checks each reveal against it.
+        let cap = U256::from(1_000u64);
+        let reveal = |zero_for_one: bool, amount_in: U256| check_declared(cap, true, zero_for_one, amount_in);
+        assert_eq!(reveal(true, cap), Ok(()));
+        assert_eq!(reveal(true, cap + U256::from(1u64)), Err(err(ERR_EXCEEDS_DECLARED_EXPOSURE)));
+        // The declared direction is binding too.
+        assert_eq!(reveal(false, U256::from(1u64)), Err(err(ERR_EXCEEDS_DECLARED_EXPOSURE)));
+        // Undeclared commitments are unconstrained (and not counted).
+        assert_eq!(check_declared(U256::ZERO, false, true, U256::MAX), Ok(()));
+    }
+
+    #[test]
+    fn second_commit_reverts_while_the_first_is_pending() {
+        let (delay, age) = (as_u256(COMMIT_REVEAL_DELAY), as_u256(MAX_COMMITMENT_AGE));
+        let commit_at = U256::from(1_000u64);
+        // Commit again `ago` blocks after a first commit at `commit_at`.
+        let recommit =
+            |ago: u64| commitment_replacement(commitment_status(true, commit_at, commit_at + U256::from(ago), delay, age));
+        // First commit: nothing to replace.
+        assert_eq!(commitment_replacement(commitment_status(false, U256::ZERO, commit_at, delay, age)), Ok(false));
+        assert_eq!(recommit(0), Err(err(ERR_COMMITMENT_ACTIVE)));
+        assert_eq!(recommit(COMMIT_REVEAL_DELAY), Err(err(ERR_COMMITMENT_ACTIVE)));
+        assert_eq!(recommit(MAX_COMMITMENT_AGE), Err(err(ERR_COMMITMENT_ACTIVE)));
+        // Expired: replaceable, but its bond is slashed first rather than reused or refunded.
+        assert_eq!(recommit(MAX_COMMITMENT_AGE + 1), Ok(true));
+    }
+
+    #[test]
+    fn lp_surplus_sweep_is_capped_and_aged() {
+        use crate::constants::LP_SURPLUS_EPOCH_BLOCKS;
+        use crate::lp_fees::{surplus_epoch, surplus_sweep_amount, surplus_sweep_ready};
+        let owed = U256::from(1_001u64);
+        assert_eq!(surplus_sweep_amount(owed, U256::from(2_500u64)), Ok(U256::from(250u64)));
+        assert_eq!(surplus_sweep_amount(owed, as_u256(BPS)), Ok(owed));
+        assert_eq!(surplus_sweep_amount(owed, U256::ZERO), Ok(U256::ZERO));
+
+        let epoch = |e: u64| U256::from(e * LP_SURPLUS_EPOCH_BLOCKS);
+        assert_eq!(surplus_epoch(epoch(3) - U256::from(1u64)), U256::from(2u64));
+        assert_eq!(surplus_epoch(epoch(3)), U256::from(3u64));
+        // Queued in epoch 3 with a grace period ending inside it; min age 2 epochs.
+        let (after, queued, age) = (epoch(3) + U256::from(10u64), U256::from(3u64), U256::from(2u64));
+        let ready = |now: U256, swept: u64| surplus_sweep_ready(now, after, queued, age, U256::from(swept));
+        assert!(!ready(epoch(3), 0));
+        // Past the grace period but only one epoch old.
+        assert!(!ready(epoch(4), 0));
+        assert!(!ready(epoch(5) - U256::from(1u64), 0));
+        assert!(ready(epoch(5), 0));
+        // A sweep already executed in epoch 5: the next one waits for epoch 6.
+        assert!(!ready(epoch(5) + U256::from(7u64), 6));
+        assert!(ready(epoch(6), 6));
+    }
+
+    #[test]
+    fn auto_breaker_divergence_is_symmetric_in_bps_of_twap() {
+        use crate::engine::{breaker_divergence, price_divergence_bps};
+        let twap = q112_u256() * U256::from(2u64);
+        assert_eq!(price_divergence_bps(twap, twap), Ok(U256::ZERO));
+        assert_eq!(price_divergence_bps(q112_u256() * U256::from(3u64), twap), Ok(U256::from(5_000u64)));
+        assert_eq!(price_divergence_bps(q112_u256(), twap), Ok(U256::from(5_000u64)));
+        // No TWAP yet: nothing to diverge from.
+        assert_eq!(price_divergence_bps(twap, U256::ZERO), Ok(U256::ZERO));
+
+        // A swap pushing spot 25% above the TWAP trips a 20% breaker, not a 30% or disabled one.
+        let spot = twap + twap / U256::from(4u64);
+        assert_eq!(breaker_divergence(U256::from(2_000u64), spot, twap), Ok(Some(U256::from(2_500u64))));
+        assert_eq!(breaker_divergence(U256::from(3_000u64), spot, twap), Ok(None));
+        assert_eq!(breaker_divergence(U256::ZERO, spot, twap), Ok(None));
+        // Resetting a breaker that never tripped reverts.
+        assert_eq!(require_breaker_tripped(U256::ZERO), Err(err(ERR_BREAKER_NOT_TRIPPED)));
+        assert_eq!(require_breaker_tripped(U256::from(42u64)), Ok(()));
+    }
+
+    #[test]
+    fn spot_price_move_measures_swap_impact() {
+        let r = U256::from(1_000_000u64);
+        // 1% of the reserve in, ~1% out: price (out/in) falls by ~2%.
+        let moved = spot_price_move_bps(r, r, U256::from(1_010_000u64), U256::from(990_100u64)).unwrap();
+        assert_eq!(moved, U256::from(198u64));
+        assert_eq!(spot_price_move_bps(r, r, r, r), Ok(U256::ZERO));
+        // A drain moves the price by the whole of it.
+        assert_eq!(spot_price_move_bps(r, r, r * U256::from(1_000u64), U256::from(1u64)), Ok(as_u256(BPS)));
+        assert_eq!(spot_price_move_bps(r, r, U256::ZERO, r), Err(ERR_DIVISION_BY_ZERO));
+
+        // The guard in the swap path: a 5% trade against a 2% cap reverts, a small one passes.
+        let swap = |amount_in: u64| {
+            let amount_in = U256::from(amount_in);
+            let out = get_amount_out_with_fee(amount_in, r, r, as_u256(30u64)).unwrap();
+            check_price_impact(U256::from(200u64), (r, r), (r + amount_in, r - out))
+        };
+        assert_eq!(swap(5_000), Ok(()));
+        assert_eq!(swap(50_000), Err(err(ERR_PRICE_IMPACT_TOO_HIGH)));
+        assert_eq!(check_price_impact(U256::ZERO, (r, r), (r * U256::from(1_000u64), U256::from(1u64))), Ok(()));
+    }
+
+    #[test]
+    fn deadlines_expire_after_the_given_timestamp() {
+        let deadline = U256::from(1_700_000_000u64);
+        assert_eq!(check_deadline(deadline - U256::from(1u64), deadline), Ok(()));
+        // Inclusive: a transaction mined exactly at the deadline still executes.
+        assert_eq!(check_deadline(deadline, deadline), Ok(()));
+        assert_eq!(check_deadline(deadline + U256::from(1u64), deadline), Err(err(ERR_DEADLINE_EXPIRED)));
+    }
+
+    #[test]
+    fn token_exposure_cap_bounds_pool_reserves() {
+        use crate::risk::check_exposure;
+        let cap = U256::from(10_000u64);
+        let (reserve_in, reserve_out) = (U256::from(9_000u64), U256::from(9_000u64));
+        // A swap's input lands in the reserve (net of the fee parts held outside it).
+        let new_reserve_in = |amount_in: u64| reserve_in + U256::from(amount_in);
+        assert!(!get_amount_out_with_fee(U256::from(1_000u64), reserve_in, reserve_out, as_u256(30u64)).unwrap().is_zero());
+        assert_eq!(check_exposure(cap, new_reserve_in(1_000)), Ok(()));
+        assert_eq!(check_exposure(cap, new_reserve_in(1_001)), Err(err(ERR_TOKEN_EXPOSURE_EXCEEDED)));
+        // A deposit at the pool ratio grows both reserves; either side may hit its cap.
+        let (used0, used1) = optimal_liquidity_amounts(U256::from(2_000u64), U256::from(2_000u64), reserve_in, reserve_out).unwrap();
+        assert_eq!(check_exposure(cap, reserve_in + used0), Err(err(ERR_TOKEN_EXPOSURE_EXCEEDED)));
+        assert_eq!(check_exposure(U256::from(11_000u64), reserve_out + used1), Ok(()));
+        // Zero is "no cap": unregistered tokens are unrestricted.
+        assert_eq!(check_exposure(U256::ZERO, U256::MAX), Ok(()));
+    }
+
+    #[test]
+    fn mev_tiers_scale_reveal_delay_and_fee() {
+        let (standard, fast, batch) = (as_u256(MEV_TIER_STANDARD), as_u256(MEV_TIER_FAST), as_u256(MEV_TIER_BATCH));
+        let delay = as_u256(COMMIT_REVEAL_DELAY);
+        assert_eq!(mev_tier_reveal_delay(standard, delay), delay);
+        assert_eq!(mev_tier_reveal_delay(batch, delay), delay);
+        assert_eq!(mev_tier_reveal_delay(fast, delay), as_u256(MEV_TIER_FAST_DELAY));
+        // FAST never waits longer than the configured delay.
+        assert_eq!(mev_tier_reveal_delay(fast, U256::from(1u64)), U256::from(1u64));
+        let status = |tier| {
+            commitment_status(true, U256::from(100u64), U256::from(101u64), mev_tier_reveal_delay(tier, delay), U256::from(50u64))
+        };
+        assert_eq!(status(fast), COMMIT_STATUS_READY);
+        assert_eq!(status(standard), COMMIT_STATUS_TOO_EARLY);
+
+        let fee = U256::from(30u64);
+        assert_eq!(mev_tier_fee_bps(standard, fee), Ok(fee));
+        assert_eq!(mev_tier_fee_bps(batch, fee), Ok(fee));
+        assert_eq!(mev_tier_fee_bps(fast, fee), Ok(U256::from(45u64)));
+        // Rounded up, capped at MAX_FEE_BPS, never below the pool fee.
+        assert_eq!(mev_tier_fee_bps(fast, U256::from(5u64)), Ok(U256::from(8u64)));
+        assert_eq!(mev_tier_fee_bps(fast, as_u256(MAX_FEE_BPS)), Ok(as_u256(MAX_FEE_BPS)));
+        assert_eq!(mev_tier_fee_bps(fast, U256::ZERO), Ok(U256::ZERO));
+    }
+
+    #[test]
+    fn errors_revert_as_solidity_custom_errors() {
+        use stylus_sdk::crypto::keccak;
+        let e = err(ERR_INSUFFICIENT_LIQUIDITY);
+        let data: Vec<u8> = e.clone().into();
+        assert_eq!(data, keccak(b"InsufficientLiquidity()")[..4].to_vec());
+        assert_eq!(e.code(), ERR_INSUFFICIENT_LIQUIDITY);
+        assert_eq!(e, ERR_INSUFFICIENT_LIQUIDITY.to_vec());
+        assert_eq!(OakError::from_revert(&data), e);
+        // A routed call's revert decodes to the same error; foreign data is carried as CallFailed.
+        let failed = OakError::from_revert(b"raw");
+        assert_eq!(failed.code(), b"raw");
+        let encoded: Vec<u8> = failed.clone().into();
+        assert_eq!(encoded[..4], keccak(b"CallFailed(bytes)")[..4]);
+        assert_eq!(OakError::from_revert(&encoded), failed);
+    }
+
+    #[test]
+    fn event_topics_and_array_data_follow_the_abi() {
+        use crate::events::{event_topic, uint_arrays};
+        // Standard signatures: ERC-20 Transfer (LP shares) and ERC-1155 TransferSingle.
+        assert_eq!(event_topic("Transfer(address,address,uint256)").0[..4], [0xdd, 0xf2, 0x52, 0xad]);
+        assert_eq!(event_topic("TransferSingle(address,address,address,uint256,uint256)").0[..4], [0xc3, 0xd5, 0x81, 0x68]);
+
+        let ids = [U256::from(1u64), U256::from(2u64)];
+        let values = [U256::from(3u64)];
+        let words: Vec<U256> = uint_arrays(&[&ids, &values]).iter().map(|w| U256::from_be_bytes(w.0)).collect();
+        let expected = [64u64, 160, 2, 1, 2, 1, 3].map(U256::from);
+        assert_eq!(words, expected);
+        // An empty array is its offset and a zero length.
+        let empty: Vec<U256> = uint_arrays(&[&[]]).iter().map(|w| U256::from_be_bytes(w.0)).collect();
+        assert_eq!(empty, [U256::from(32u64), U256::ZERO]);
+    }
+
+    #[test]
+    fn position_ids_and_erc1155_encoding_follow_the_standard() {
+        use crate::positions::{
+            encode_erc1155_received, position_id, split_position_id, ERC1155_INTERFACE_ID, ERC165_INTERFACE_ID,
+        };
+        use stylus_sdk::function_selector;
+        let id = position_id(U256::from(7u64), U256::from(3u64)).unwrap();
+        assert_eq!(split_position_id(id), (U256::from(7u64), U256::from(3u64)));
+        assert_eq!(position_id(U256::ZERO, U256::from(u64::MAX)), Some(U256::from(u64::MAX)));
+        assert_eq!(position_id(U256::ZERO, U256::from(1u64) << 64), None);
+        assert_eq!(tier_unlock_block(U256::ZERO), Ok(U256::ZERO));
+        assert_eq!(tier_unlock_block(U256::from(2u64)), Ok(U256::from(2 * crate::constants::POSITION_TIER_BLOCKS)));
+
+        // The interface id is the XOR of the ERC-1155 function selectors.
+        let selectors = [
+            function_selector!("safeTransferFrom", Address, Address, U256, U256, Bytes),
+            function_selector!("safeBatchTransferFrom", Address, Address, Vec<U256>, Vec<U256>, Bytes),
+            function_selector!("balanceOf", Address, U256),
+            function_selector!("balanceOfBatch", Vec<Address>, Vec<U256>),
+            function_selector!("setApprovalForAll", Address, bool),
+            function_selector!("isApprovedForAll", Address, Address),
+        ];
+        let xor = selectors.iter().fold([0u8; 4], |acc, s| core::array::from_fn(|i| acc[i] ^ s[i]));
+        assert_eq!(xor, ERC1155_INTERFACE_ID);
+        assert_eq!(function_selector!("supportsInterface", FixedBytes<4>), ERC165_INTERFACE_ID);
+        assert!(PositionTokens::supports_interface(FixedBytes::from(ERC1155_INTERFACE_ID)));
+        assert!(!PositionTokens::supports_interface(FixedBytes::from([0xff; 4])));
+
+        let (op, from) = (Address::repeat_byte(1), Address::repeat_byte(2));
+        let single = encode_erc1155_received(op, from, &[id], &[U256::from(5u64)], &[0xaa], false);
+        assert_eq!(&single[0..4], &[0xf2, 0x3a, 0x6e, 0x61]);
+        assert_eq!(U256::from_be_slice(&single[132..164]), U256::from(160u64));
+        assert_eq!(single.len(), 4 + 160 + 64);
+
+        let ids = [id, id + U256::from(1u64)];
+        let values = [U256::from(5u64), U256::from(6u64)];
+        let batch = encode_erc1155_received(op, from, &ids, &values, &[], true);
+        assert_eq!(&batch[0..4], &[0xbc, 0x19, 0x7c, 0x81]);
+        // ids at 160, values after 3 words of ids, data after 3 words of values.
+        assert_eq!(U256::from_be_slice(&batch[68..100]), U256::from(160u64));
+        assert_eq!(U256::from_be_slice(&batch[100..132]), U256::from(256u64));
+        assert_eq!(U256::from_be_slice(&batch[132..164]), U256::from(352u64));
+        assert_eq!(U256::from_be_slice(&batch[4 + 256..4 + 288]), U256::from(2u64));
+        assert_eq!(U256::from_be_slice(&batch[4 + 320..4 + 352]), values[1]);
+        assert_eq!(batch.len(), 4 + 352 + 32);
+    }
+
+    #[test]
+    fn retired_pool_commitments_reanchor_to_the_successor_only() {
+        use crate::pool_migration::reveal_anchor;
+        let key = |b: u8| U256::from_be_bytes::<32>(compute_pair_key(Address::repeat_byte(1), Address::repeat_byte(b)).into());
+        let (old, successor, other) = (key(2), key(3), key(4));
+
+        // Undeclared and same-pool reveals verify against the pool revealed in.
+        assert_eq!(reveal_anchor(U256::ZERO, other, U256::ZERO), Some(other));
+        assert_eq!(reveal_anchor(old, old, successor), Some(old));
+        // A reveal in the successor verifies against the old key the hash was bound to.
+        assert_eq!(reveal_anchor(old, successor, successor), Some(old));
+        assert_eq!(reveal_anchor(old, other, successor), None);
+        assert_eq!(reveal_anchor(old, successor, U256::ZERO), None);
+
+        let leaf = compute_commit_hash(U256::from(1_000u64), true, U256::from(50u64), U256::from(7u64));
+        let old_key = FixedBytes::from(old.to_be_bytes::<32>());
+        let hash = compute_pool_bound_hash(old_key, leaf);
+        let anchored = FixedBytes::from(reveal_anchor(old, successor, successor).unwrap().to_be_bytes::<32>());
+        let claim = RevealClaim { pair_key: anchored, leaf, witness: &[], domain_separator: FixedBytes::ZERO };
+        assert!(verify_commitment(U256::from(COMMIT_SCHEME_HASH), hash, &claim).is_ok());
+    }
+
+    #[test]
+    fn flash_swap_callback_follows_the_registered_style() {
+        let borrower = Address::repeat_byte(0x44);
+        let (out0, out1, fee0, fee1) = (U256::from(1_000u64), U256::ZERO, U256::from(3u64), U256::ZERO);
+        let encode = |style: u64| encode_flash_swap_callback(U256::from(style), borrower, out0, out1, fee0, fee1, &[0xee; 3]);
+
+        let oak = encode(FLASH_CALLBACK_OAK).unwrap();
+        assert_eq!(&oak[0..4], &crypto::keccak(b"oakFlashSwapCallback(uint256,uint256,bytes)")[0..4]);
+        assert_eq!(U256::from_be_slice(&oak[4..36]), U256::from(1_003u64));
+        assert_eq!(U256::from_be_slice(&oak[68..100]), U256::from(96u64));
+        assert_eq!(oak.len(), 4 + 128 + 32);
+
+        // uniswapV2Call(address,uint256,uint256,bytes) = 0x10d1e85c.
+        let v2 = encode(FLASH_CALLBACK_UNISWAP_V2).unwrap();
+        assert_eq!(&v2[0..4], &[0x10, 0xd1, 0xe8, 0x5c]);
+        assert_eq!(&v2[4..36], borrower.into_word().as_slice());
+        assert_eq!(U256::from_be_slice(&v2[36..68]), out0);
+        assert_eq!(U256::from_be_slice(&v2[100..132]), U256::from(128u64));
+        assert_eq!(U256::from_be_slice(&v2[132..164]), U256::from(3u64));
+        assert_eq!(&v2[164..167], &[0xee; 3]);
+
+        // uniswapV3FlashCallback(uint256,uint256,bytes) = 0xe9cbafb0.
+        let v3 = encode(FLASH_CALLBACK_UNISWAP_V3).unwrap();
+        assert_eq!(&v3[0..4], &[0xe9, 0xcb, 0xaf, 0xb0]);
+        assert_eq!(U256::from_be_slice(&v3[4..36]), fee0);
+        assert_eq!(v3.len(), oak.len());
+
+        assert_eq!(encode(FLASH_CALLBACK_STYLE_COUNT), Err(ERR_UNKNOWN_FLASH_CALLBACK));
+    }
+
+    #[test]
+    fn erc3156_callback_and_fee_match_the_standard() {
+        let initiator = Address::repeat_byte(0x11);
+        let token = Address::repeat_byte(0x22);
+        let data = [0xcd_u8; 5];
+        let encoded = encode_on_flash_loan(initiator, token, U256::from(1_000u64), U256::from(3u64), &data);
+        // onFlashLoan(address,address,uint256,uint256,bytes) = 0x23e30c8b.
+        assert_eq!(&encoded[0..4], &[0x23, 0xe3, 0x0c, 0x8b]);
+        // Selector + 5 head words + length word + 5 bytes padded to 32.
+        assert_eq!(encoded.len(), 4 + 192 + 32);
+        assert_eq!(&encoded[4..36], initiator.into_word().as_slice());
+        assert_eq!(&encoded[36..68], token.into_word().as_slice());
+        assert_eq!(U256::from_be_slice(&encoded[132..164]), U256::from(160u64));
+        assert_eq!(U256::from_be_slice(&encoded[164..196]), U256::from(5u64));
+        assert_eq!(&encoded[196..201], &data);
+        assert!(encoded[201..].iter().all(|b| *b == 0));
+        assert_eq!(
+            flash_loan_callback_success(),
+            crypto::keccak(b"ERC3156FlashBorrower.onFlashLoan")
+        );
+
+        // Same rate as flash_swap, floored; the split of the fee adds back up to it.
+        let fee = flash_loan_fee(U256::from(1_000_000u64), U256::from(30u64)).unwrap();
+        assert_eq!(fee, U256::from(3_000u64));
+        let (_, treasury, lp, buyback, rebate) =
+            compute_fee_split(U256::from(1_000_000u64), U256::from(30u64)).unwrap();
+        assert_eq!(treasury + lp + buyback + rebate, fee);
+        assert_eq!(flash_loan_fee(U256::from(333u64), U256::from(30u64)).unwrap(), U256::ZERO);
+        assert_eq!(flash_loan_fee(U256::MAX, U256::from(30u64)), Err(ERR_OVERFLOW));
+    }
+
+    #[cfg(feature = "rewards")]
+    #[test]
+    fn referral_share_is_a_bounded_slice_of_the_treasury_fee() {
+        use crate::constants::REFERRAL_FEE_BPS_MAX;
+        use crate::growth::referral::referral_share;
+        for amount_in in [U256::from(10_000u64), U256::from(123_456_789u64), U256::from(10u64).pow(U256::from(24u64))] {
+            let (_, treasury_fee, _, _, _) = compute_fee_split(amount_in, as_u256(DEFAULT_FEE_BPS)).unwrap();
+            assert_eq!(referral_share(treasury_fee, U256::ZERO).unwrap(), U256::ZERO);
+            let max = referral_share(treasury_fee, as_u256(REFERRAL_FEE_BPS_MAX)).unwrap();
+            assert!(max <= treasury_fee / U256::from(10u64));
+            assert_eq!(max, treasury_fee * as_u256(REFERRAL_FEE_BPS_MAX) / as_u256(BPS));
+        }
+        assert!(referral_share(U256::MAX, U256::from(2u64)).is_err());
+    }
+
+    #[test]
+    fn treasury_splits_fan_out_exactly_and_reject_bad_tables() {
+        use crate::constants::MAX_TREASURY_SPLITS;
+        use crate::treasury_splits::{split_amounts, splits_valid};
+        let (ops, grants, insurance, this) =
+            (Address::repeat_byte(1), Address::repeat_byte(2), Address::repeat_byte(3), Address::repeat_byte(9));
+        let weights = [U256::from(5_000u64), U256::from(3_333u64), U256::from(1_667u64)];
+        assert!(splits_valid(&[ops, grants, insurance], &weights, this));
+        for amount in [U256::from(1u64), U256::from(9_999u64), U256::from(1_000_003u64), U256::MAX / U256::from(10_000u64)] {
+            let shares = split_amounts(amount, &weights).unwrap();
+            assert_eq!(shares.iter().fold(U256::ZERO, |a, s| a + *s), amount);
+            // Only the first share carries the rounding remainder.
+            assert_eq!(shares[1], amount * weights[1] / as_u256(BPS));
+        }
+        // Empty table = splitting off.
+        assert!(splits_valid(&[], &[], this));
+        let half = U256::from(5_000u64);
+        assert!(!splits_valid(&[ops, grants], &[half, U256::from(4_999u64)], this));
+        assert!(!splits_valid(&[ops, ops], &[half, half], this));
+        assert!(!splits_valid(&[ops, this], &[half, half], this));
+        assert!(!splits_valid(&[ops, Address::ZERO], &[half, half], this));
+        assert!(!splits_valid(&[ops, grants], &[as_u256(BPS), U256::ZERO], this));
+        assert!(!splits_valid(&[ops], &[half, half], this));
+        let many: Vec<Address> = (1..=MAX_TREASURY_SPLITS as u8 + 1).map(Address::repeat_byte).collect();
+        let mut even = alloc::vec![U256::from(1_000u64); many.len()];
+        even[0] = as_u256(BPS) - U256::from(1_000u64 * MAX_TREASURY_SPLITS);
+        assert!(!splits_valid(&many, &even, this));
+    }
+
+    #[test]
+    fn cancellation_is_free_before_the_window_and_penalized_inside_it() {
+        let bond = U256::from(1_001u64);
+        let at = |age: u64| {
+            commitment_status(true, U256::from(1_000u64), U256::from(1_000 + age), as_u256(COMMIT_REVEAL_DELAY), as_u256(MAX_COMMITMENT_AGE))
+        };
+        // Pending: the whole bond comes back.
+        assert_eq!(at(0), COMMIT_STATUS_TOO_EARLY);
+        assert_eq!(cancel_bond_split(at(0), bond), Ok((bond, U256::ZERO)));
+        // Live window: the penalty share goes to the treasury, the rest is refunded, nothing lost.
+        assert_eq!(at(COMMIT_REVEAL_DELAY), COMMIT_STATUS_READY);
+        let (refund, penalty) = cancel_bond_split(at(COMMIT_REVEAL_DELAY), bond).unwrap();
+        assert_eq!(penalty, bond * as_u256(COMMIT_LIVE_CANCEL_PENALTY_BPS) / as_u256(BPS));
+        assert_eq!(refund + penalty, bond);
+        assert!(!penalty.is_zero());
+        // Expired: only the keeper path applies (cancel_commitment rejects before splitting).
+        assert_eq!(at(MAX_COMMITMENT_AGE + 1), COMMIT_STATUS_EXPIRED);
+        assert_eq!(cancel_bond_split(COMMIT_STATUS_READY, U256::MAX), Err(ERR_OVERFLOW));
+        assert_eq!(cancel_bond_split(COMMIT_STATUS_READY, U256::ZERO), Ok((U256::ZERO, U256::ZERO)));
+    }
+
+    #[test]
+    fn gas_rebate_is_carved_out_of_the_treasury_share() {
+        let big = U256::from(10u64).pow(U256::from(24u64));
+        for amount_in in [U256::from(999u64), U256::from(1_000_000u64), big] {
+            for fee in [1u64, DEFAULT_FEE_BPS, MAX_FEE_BPS] {
+                let fee_bps = as_u256(fee);
+                let (_, treasury_fee, lp_fee, _, gas_rebate) = compute_fee_split(amount_in, fee_bps).unwrap();
+                let total_fee = amount_in * fee_bps / as_u256(FEE_DENOMINATOR);
+                assert_eq!(gas_rebate, total_fee * as_u256(GAS_REBATE_BPS) / as_u256(FEE_DENOMINATOR));
+                assert_eq!(treasury_fee + gas_rebate, total_fee * as_u256(TREASURY_FEE_PCT) / U256::from(100u64));
+                assert!(gas_rebate <= treasury_fee && lp_fee >= total_fee * as_u256(LP_FEE_PCT) / U256::from(100u64));
+            }
+        }
+        // Large swaps pay a non-zero rebate; dust swaps pay none.
+        assert!(!compute_fee_split(big, as_u256(DEFAULT_FEE_BPS)).unwrap().4.is_zero());
+        assert!(compute_fee_split(U256::from(999u64), as_u256(DEFAULT_FEE_BPS)).unwrap().4.is_zero());
+    }
+
+    #[test]
+    fn self_test_checks_pass_on_sound_math_and_flag_bad_inputs() {
+        use crate::engine::self_test::{
+            fee_split_consistent, lp_fee_math_consistent, lp_share_matches_pct, swap_math_consistent,
+        };
+        let reserve = U256::from(10u64).pow(U256::from(24u64));
+        for amount in [1u64, 7, 10_000, 1_000_003, u64::MAX] {
+            let amount = U256::from(amount);
+            assert!(lp_share_matches_pct(amount));
+            assert!(lp_fee_math_consistent(amount, U256::from(3_001u64)));
+            for fee in [0u64, 5, 30, 100, MAX_FEE_BPS] {
+                assert!(fee_split_consistent(amount, U256::from(fee)));
+                assert!(swap_math_consistent(amount, reserve, reserve * U256::from(3u64), U256::from(fee)));
+            }
+        }
+        assert!(!fee_split_consistent(U256::from(10_000u64), as_u256(MAX_FEE_BPS + 1)));
+        assert!(!swap_math_consistent(U256::from(10_000u64), U256::ZERO, reserve, U256::from(30u64)));
+    }
+
+    #[test]
+    fn signed_cancellation_binds_every_field_and_one_nonce() {
+        let owner = Address::repeat_byte(1);
+        let domain = compute_domain_separator(Address::repeat_byte(9), CHAIN_ID_ARBITRUM_ONE);
+        let ids = [U256::from(3u64), U256::from(4u64)];
+        let (nonce, deadline) = (U256::from(5u64), U256::from(100u64));
+        let signed = compute_cancel_batch_digest(owner, &ids, true, nonce, deadline, &domain);
+        assert_eq!(signed, compute_cancel_batch_digest(owner, &ids, true, nonce, deadline, &domain));
+        // A relayer cannot reuse the signature for another owner, id set, scope, nonce or deadline.
+        assert_ne!(signed, compute_cancel_batch_digest(Address::repeat_byte(2), &ids, true, nonce, deadline, &domain));
+        assert_ne!(signed, compute_cancel_batch_digest(owner, &ids[..1], true, nonce, deadline, &domain));
+        assert_ne!(signed, compute_cancel_batch_digest(owner, &[ids[1], ids[0]], true, nonce, deadline, &domain));
+        assert_ne!(signed, compute_cancel_batch_digest(owner, &ids, false, nonce, deadline, &domain));
+        assert_ne!(signed, compute_cancel_batch_digest(owner, &ids, true, nonce + U256::from(1u64), deadline, &domain));
+        assert_ne!(signed, compute_cancel_batch_digest(owner, &ids, true, nonce, deadline + U256::from(1u64), &domain));
+        let other_chain = compute_domain_separator(Address::repeat_byte(9), CHAIN_ID_ARBITRUM_ONE + 1);
+        assert_ne!(signed, compute_cancel_batch_digest(owner, &ids, true, nonce, deadline, &other_chain));
+
+        // Valid up to and including the deadline block, and only with the current nonce.
+        assert_eq!(check_permit(deadline, deadline, nonce, nonce), Ok(()));
+        assert_eq!(check_permit(deadline + U256::from(1u64), deadline, nonce, nonce), Err(err(ERR_PERMIT_EXPIRED)));
+        // Replay: once consumed the nonce has moved on; a future nonce cannot be pre-spent either.
+        assert_eq!(check_permit(deadline, deadline, nonce, nonce + U256::from(1u64)), Err(err(ERR_PERMIT_NONCE)));
+        assert_eq!(check_permit(deadline, deadline, nonce + U256::from(1u64), nonce), Err(err(ERR_PERMIT_NONCE)));
+    }
+
+    #[test]
+    fn role_bitmask_grant_revoke_and_denial() {
+        use crate::access::{check_roles, updated_roles, ROLE_ALL, ROLE_GUARDIAN, ROLE_UPGRADER};
+        // Grants accumulate bits; a revoke drops only the named bits.
+        let guardian = updated_roles(U256::ZERO, ROLE_GUARDIAN, true).unwrap();
+        let both = updated_roles(guardian, ROLE_FEE_SETTER, true).unwrap();
+        assert_eq!(both, ROLE_GUARDIAN | ROLE_FEE_SETTER);
+        assert_eq!(updated_roles(both, ROLE_GUARDIAN, false), Ok(ROLE_FEE_SETTER));
+        assert_eq!(updated_roles(both, ROLE_GUARDIAN, true), Ok(both));
+        assert_eq!(updated_roles(both, ROLE_UPGRADER, false), Ok(both));
+        assert_eq!(updated_roles(both, U256::ZERO, true), Err(err(ERR_INVALID_ROLE)));
+        assert_eq!(updated_roles(both, ROLE_ALL + U256::from(1u64), true), Err(err(ERR_INVALID_ROLE)));
+
+        // Any one of the required bits passes: set_fee takes ROLE_ADMIN | ROLE_FEE_SETTER.
+        assert_eq!(check_roles(both, ROLE_ADMIN | ROLE_FEE_SETTER), Ok(()));
+        assert_eq!(check_roles(ROLE_ADMIN, ROLE_ADMIN | ROLE_FEE_SETTER), Ok(()));
+        // Denial: narrow roles do not reach admin entrypoints, and a guardian cannot unpause.
+        assert_eq!(check_roles(both, ROLE_ADMIN), Err(err(ERR_MISSING_ROLE)));
+        assert_eq!(check_roles(ROLE_GUARDIAN, ROLE_PAUSER), Err(err(ERR_MISSING_ROLE)));
+        assert_eq!(check_roles(ROLE_FEE_SETTER, ROLE_PAUSER | ROLE_GUARDIAN), Err(err(ERR_MISSING_ROLE)));
+        // Revoked: the former fee setter is denied set_fee.
+        let revoked = updated_roles(both, ROLE_FEE_SETTER, false).unwrap();
+        assert_eq!(check_roles(revoked, ROLE_ADMIN | ROLE_FEE_SETTER), Err(err(ERR_MISSING_ROLE)));
+        assert_eq!(check_roles(U256::ZERO, ROLE_ALL), Err(err(ERR_MISSING_ROLE)));
+    }
+
+    #[test]
+    fn bundle_reveal_swaps_the_split_then_deposits_the_rest_with_the_proceeds() {
+        // Model reveal_swap_and_add_liquidity on a 1:1 pool, the input being canonical token0.
+        let (fee_bps, supply) = (U256::from(30u64), U256::from(1_000_000u64));
+        let (mut r0, mut r1) = (U256::from(1_000_000u64), U256::from(1_000_000u64));
+        let (amount_in, swap_amount, salt) = (U256::from(100_000u64), U256::from(50_000u64), U256::from(7u64));
+        // A split that leaves either leg empty is rejected before the commitment is consumed.
+        assert_eq!(bundle_deposit_amount(amount_in, U256::ZERO), Err(err(ERR_BUNDLE_SPLIT_INVALID)));
+        assert_eq!(bundle_deposit_amount(amount_in, amount_in), Err(err(ERR_BUNDLE_SPLIT_INVALID)));
+        let deposit0 = bundle_deposit_amount(amount_in, swap_amount).unwrap();
+        // The split is part of the commitment, and the preimage cannot be opened as a plain swap.
+        let bundle = compute_bundle_commit_hash(amount_in, swap_amount, salt);
+        assert_ne!(bundle, compute_bundle_commit_hash(amount_in, U256::from(40_000u64), salt));
+        assert_ne!(bundle, compute_commit_hash(amount_in, true, swap_amount, salt));
+
+        // Swap leg: the proceeds stay in the contract as the prefunded token1 side of the deposit.
+        let swap_out = get_amount_out_with_fee(swap_amount, r0, r1, fee_bps).unwrap();
+        (r0, r1) = (r0 + swap_amount, r1 - swap_out);
+        // LP leg at the post-swap price: the rest of the input is used in full, the token1 side is
+        // cut to the pool ratio and the unused proceeds (the excess) go back to the sender.
+        let (used0, used1) = optimal_liquidity_amounts(deposit0, swap_out, r0, r1).unwrap();
+        assert_eq!(used0, deposit0);
+        assert!(used1 < swap_out);
+        assert_eq!(used1, deposit0 * r1 / r0);
+        let liquidity = compute_liquidity_minted(used0, used1, r0, r1, supply).unwrap();
+        assert_eq!(liquidity, used0 * supply / r0);
+    }
+
+    #[test]
+    fn remove_liquidity_burns_pro_rata_above_the_minimum_liquidity_floor() {
+        // Model process_remove_liquidity over a pool (reserve0, reserve1, supply) seeded the way
+        // process_add_liquidity seeds it. Minimums and payouts are in the caller's token order.
+        fn remove(pool: &mut (U256, U256, U256), share: U256, balance: U256, min: (U256, U256), reversed: bool) -> OakResult<(U256, U256)> {
+            let new_total = lp_supply_after_burn(share, balance, pool.2)?;
+            let (a0, a1) = compute_liquidity_burned(share, pool.0, pool.1, pool.2).map_err(err)?;
+            let (out_a, out_b) = if reversed { (a1, a0) } else { (a0, a1) };
+            check_lp_slippage(out_a, out_b, min.0, min.1)?;
+            *pool = (pool.0 - a0, pool.1 - a1, new_total);
+            Ok((out_a, out_b))
+        }
+        let (zero, locked) = (U256::ZERO, as_u256(MINIMUM_LIQUIDITY));
+        let (r0, r1) = (U256::from(4_000_000u64), U256::from(1_000_000u64));
+        let founder = compute_liquidity_minted(r0, r1, zero, zero, zero).unwrap();
+        let (d0, d1) = optimal_liquidity_amounts(U256::from(400_000u64), U256::from(500_000u64), r0, r1).unwrap();
+        let joiner = compute_liquidity_minted(d0, d1, r0, r1, founder + locked).unwrap();
+        let mut pool = (r0 + d0, r1 + d1, founder + locked + joiner);
+
+        // The joiner exits naming token1 first: a token0-sized minimum on the first slot fails,
+        // and the payout comes back token1-first, never more than it deposited.
+        assert_eq!(remove(&mut pool, joiner, joiner, (d0, zero), true), Err(err(ERR_LP_SLIPPAGE)));
+        let (got1, got0) = remove(&mut pool, joiner, joiner, (d1 - U256::from(1u64), d0 - U256::from(1u64)), true).unwrap();
+        assert!(got0 <= d0 && got1 <= d1);
+        // More than the founder holds reverts; burning all it holds leaves exactly the locked
+        // MINIMUM_LIQUIDITY, backed by a non-empty pool that nobody can dig into.
+        assert_eq!(remove(&mut pool, founder + U256::from(1u64), founder, (zero, zero), false), Err(err(ERR_INSUFFICIENT_LIQUIDITY)));
+        remove(&mut pool, founder, founder, (zero, zero), false).unwrap();
+        assert_eq!(pool.2, locked);
+        assert!(!pool.0.is_zero() && !pool.1.is_zero());
+        assert_eq!(remove(&mut pool, U256::from(1u64), locked, (zero, zero), false), Err(err(ERR_INSUFFICIENT_LIQUIDITY)));
+    }
+
+    #[test]
+    fn pool_manager_acts_on_its_pool_and_governance_overrides() {
+        use crate::access::updated_roles;
+        // Model set_pool_manager over a pool's lifetime: process_create_pool makes the creator
+        // its manager, and every pool-level setter goes through require_pool_admin.
+        fn set_pool_manager(manager: &mut Address, sender: Address, sender_roles: U256, to: Address) -> OakResult<()> {
+            check_pool_admin(sender_roles, sender, *manager)?;
+            *manager = to;
+            Ok(())
+        }
+        let (creator, successor, gov) = (Address::repeat_byte(1), Address::repeat_byte(2), Address::repeat_byte(3));
+        let mut manager = creator;
+        let mut gov_roles = updated_roles(U256::ZERO, ROLE_PAUSER | ROLE_FEE_SETTER, true).unwrap();
+
+        // The creator hands the pool over and loses control of it.
+        set_pool_manager(&mut manager, creator, U256::ZERO, successor).unwrap();
+        assert_eq!(set_pool_manager(&mut manager, creator, U256::ZERO, creator), Err(err(ERR_NOT_POOL_ADMIN)));
+        // Narrow protocol roles grant nothing at pool level.
+        assert_eq!(set_pool_manager(&mut manager, gov, gov_roles, gov), Err(err(ERR_NOT_POOL_ADMIN)));
+        // The successor renounces; the zero address cannot pose as the renounced manager.
+        set_pool_manager(&mut manager, successor, U256::ZERO, Address::ZERO).unwrap();
+        assert_eq!(set_pool_manager(&mut manager, Address::ZERO, U256::ZERO, creator), Err(err(ERR_NOT_POOL_ADMIN)));
+        assert_eq!(set_pool_manager(&mut manager, successor, U256::ZERO, successor), Err(err(ERR_NOT_POOL_ADMIN)));
+        // Governance still overrides the renounced pool, and only while it holds ROLE_ADMIN.
+        gov_roles = updated_roles(gov_roles, ROLE_ADMIN, true).unwrap();
+        set_pool_manager(&mut manager, gov, gov_roles, successor).unwrap();
+        assert_eq!(manager, successor);
+        gov_roles = updated_roles(gov_roles, ROLE_ADMIN, false).unwrap();
+        assert_eq!(set_pool_manager(&mut manager, gov, gov_roles, gov), Err(err(ERR_NOT_POOL_ADMIN)));
+    }
+
+    #[test]
+    fn only_approved_operators_commit_for_a_beneficiary() {
+        use std::collections::BTreeMap;
+        // Model set_commit_operator and commit_swap_for: approvals keyed (user, operator), and
+        // the commitment stored under the beneficiary, never under the operator.
+        #[derive(Default)]
+        struct Dex {
+            operators: BTreeMap<(Address, Address), bool>,
+            commitments: BTreeMap<Address, FixedBytes<32>>,
+        }
+        impl Dex {
+            fn commit_swap_for(&mut self, operator: Address, beneficiary: Address, hash: FixedBytes<32>) -> OakResult<()> {
+                let approved = self.operators.get(&(beneficiary, operator)).copied().unwrap_or(false);
+                check_commit_operator(operator, beneficiary, approved)?;
+                self.commitments.insert(beneficiary, hash);
+                Ok(())
+            }
+        }
+        let (user, router) = (Address::repeat_byte(1), Address::repeat_byte(2));
+        let (token_a, token_b) = (Address::repeat_byte(0xa), Address::repeat_byte(0xb));
+        let (amount_in, deadline, salt) = (U256::from(1_000u64), U256::from(500u64), U256::from(7u64));
+        let hash = compute_pool_bound_hash(compute_pair_key(token_a, token_b), compute_commit_hash(amount_in, true, deadline, salt));
+        let mut dex = Dex::default();
+
+        // Committing for oneself needs no approval; committing for someone else does.
+        dex.commit_swap_for(user, user, hash).unwrap();
+        assert_eq!(dex.commit_swap_for(router, user, hash), Err(err(ERR_NOT_COMMIT_OPERATOR)));
+        // Once the user approves the router, its commitment lands under the user, so only the
+        // user can reveal it. Approval is one-way.
+        dex.operators.insert((user, router), true);
+        dex.commit_swap_for(router, user, hash).unwrap();
+        assert_eq!(dex.commitments.get(&user), Some(&hash));
+        assert!(!dex.commitments.contains_key(&router));
+        assert_eq!(dex.commit_swap_for(user, router, hash), Err(err(ERR_NOT_COMMIT_OPERATOR)));
+        // Revoking takes effect for the next commitment.
+        dex.operators.insert((user, router), false);
+        assert_eq!(dex.commit_swap_for(router, user, hash), Err(err(ERR_NOT_COMMIT_OPERATOR)));
+    }
+
+    #[test]
+    fn escrowed_output_accrues_until_claimed_in_full() {
+        use crate::escrow::{credited, debited};
+        // Two escrowed reveals accumulate in the claimable balance instead of being pushed out.
+        let first = credited(U256::ZERO, U256::from(300u64)).unwrap();
+        let balance = credited(first, U256::from(200u64)).unwrap();
+        assert_eq!(balance, U256::from(500u64));
+        // claim_output pays the whole balance and leaves nothing behind.
+        assert_eq!(debited(balance, balance), Ok(U256::ZERO));
+        // A credit that would wrap reverts the reveal rather than losing the output.
+        assert_eq!(credited(U256::MAX, U256::from(1u64)), Err(err(ERR_OVERFLOW)));
+    }
+
+    #[test]
+    fn swap_from_claimable_debits_only_the_claimable_balance() {
+        use crate::escrow::debited;
+        let balance = U256::from(1_000u64);
+        // swap_from_claimable spends amount_in of the claimable balance before pricing.
+        assert_eq!(debited(balance, U256::from(400u64)), Ok(U256::from(600u64)));
+        assert_eq!(debited(balance, balance), Ok(U256::ZERO));
+        // More than is claimable reverts before anything is swapped.
+        assert_eq!(debited(balance, balance + U256::from(1u64)), Err(err(ERR_INSUFFICIENT_CLAIMABLE)));
+        assert_eq!(debited(U256::ZERO, U256::from(1u64)), Err(err(ERR_INSUFFICIENT_CLAIMABLE)));
+    }
+
+    #[test]
+    fn shorter_treasury_delay_waits_out_the_current_one() {
+        let (current, now) = (U256::from(86_400u64), U256::from(1_000u64));
+        // Lengthening applies at once.
+        assert_eq!(treasury_delay_effective_block(current, U256::from(100_000u64), now), Ok(now));
+        assert_eq!(treasury_delay_effective_block(current, current, now), Ok(now));
+        // Shortening only after the current delay, so a proposal made right after still waits it out.
+        let shorter = U256::from(3_600u64);
+        let after = treasury_delay_effective_block(current, shorter, now).unwrap();
+        assert_eq!(after, now + current);
+        assert_eq!(effective_treasury_delay(current, shorter, after, after - U256::from(1u64)), current);
+        assert_eq!(effective_treasury_delay(current, shorter, after, after), shorter);
+        assert_eq!(effective_treasury_delay(current, U256::ZERO, U256::ZERO, now), current);
+    }
+
+    #[test]
+    fn lp_receipt_follows_position_transfers() {
+        use crate::receipts::{blended_index, pro_rata};
+        let (deposited0, lp) = (U256::from(1_000u64), U256::from(400u64));
+        // Sending half the LP carries half the deposit basis; none of it is lost to rounding.
+        let moved = pro_rata(deposited0, U256::from(200u64), lp).unwrap();
+        assert_eq!(moved, U256::from(500u64));
+        assert_eq!(moved + (deposited0 - moved), deposited0);
+        // A receiver with no LP takes the sender's entry index, not zero.
+        let sender_index = U256::from(1_200u64);
+        assert_eq!(blended_index(sender_index, U256::from(200u64), U256::ZERO, U256::ZERO), Ok(sender_index));
+        // A receiver already holding LP gets the LP-weighted average.
+        assert_eq!(
+            blended_index(U256::from(1_000u64), U256::from(300u64), sender_index, U256::from(100u64)),
+            Ok(U256::from(1_050u64))
+        );
+        assert_eq!(pro_rata(deposited0, U256::from(1u64), U256::ZERO), Err(ERR_DIVISION_BY_ZERO));
+    }
+
+    #[test]
+    fn each_commitment_scheme_opens_only_its_own_commitment() {
+        use crate::constants::{COMMIT_SCHEME_ENCRYPTED, COMMIT_SCHEME_MERKLE};
+        use crate::engine::verification::{
+            compute_encrypted_commitment, encrypt_commit_payload, merkle_root_from_proof, scheme_enabled,
+        };
+        let pair_key = compute_pair_key(Address::repeat_byte(1), Address::repeat_byte(2));
+        let other_pool = compute_pair_key(Address::repeat_byte(1), Address::repeat_byte(3));
+        let domain = compute_domain_separator(Address::repeat_byte(9), CHAIN_ID_ARBITRUM_ONE);
+        let leaf = compute_commit_hash(U256::from(1_000u64), true, U256::from(50u64), U256::from(7u64));
+        let other_leaf = compute_commit_hash(U256::from(2_000u64), true, U256::from(50u64), U256::from(7u64));
+        let claim = |pair_key, leaf, witness| RevealClaim { pair_key, leaf, witness, domain_separator: domain };
+        let scheme = |id: u64| U256::from(id);
+
+        let plain = compute_pool_bound_hash(pair_key, leaf);
+        assert!(verify_commitment(scheme(COMMIT_SCHEME_HASH), plain, &claim(pair_key, leaf, &[])).is_ok());
+        assert!(verify_commitment(scheme(COMMIT_SCHEME_HASH), plain, &claim(other_pool, leaf, &[])).is_err());
+        assert!(verify_commitment(scheme(COMMIT_SCHEME_EIP712), plain, &claim(pair_key, leaf, &[])).is_err());
+
+        let typed = compute_typed_commitment(pair_key, leaf, domain);
+        assert!(verify_commitment(scheme(COMMIT_SCHEME_EIP712), typed, &claim(pair_key, leaf, &[])).is_ok());
+        assert!(verify_commitment(scheme(COMMIT_SCHEME_HASH), typed, &claim(pair_key, leaf, &[])).is_err());
+
+        // Two candidate orders under one root; either one reveals with the other as its proof.
+        let (node_a, node_b) = (compute_pool_bound_hash(pair_key, leaf), compute_pool_bound_hash(pair_key, other_leaf));
+        let root = merkle_root_from_proof(node_a, node_b.as_slice()).unwrap();
+        assert_eq!(merkle_root_from_proof(node_b, node_a.as_slice()), Some(root));
+        assert!(verify_commitment(scheme(COMMIT_SCHEME_MERKLE), root, &claim(pair_key, leaf, node_b.as_slice())).is_ok());
+        assert!(verify_commitment(scheme(COMMIT_SCHEME_MERKLE), root, &claim(pair_key, other_leaf, node_a.as_slice())).is_ok());
+        assert!(verify_commitment(scheme(COMMIT_SCHEME_MERKLE), root, &claim(other_pool, leaf, node_b.as_slice())).is_err());
+        assert!(verify_commitment(scheme(COMMIT_SCHEME_MERKLE), root, &claim(pair_key, leaf, &[0u8; 31])).is_err());
+
+        let key = FixedBytes::<32>::repeat_byte(0x42);
+        let ciphertext = encrypt_commit_payload(key, leaf);
+        let sealed = compute_encrypted_commitment(pair_key, ciphertext);
+        let mut witness = Vec::new();
+        witness.extend_from_slice(key.as_slice());
+        witness.extend_from_slice(ciphertext.as_slice());
+        assert!(verify_commitment(scheme(COMMIT_SCHEME_ENCRYPTED), sealed, &claim(pair_key, leaf, &witness)).is_ok());
+        assert!(verify_commitment(scheme(COMMIT_SCHEME_ENCRYPTED), sealed, &claim(pair_key, other_leaf, &witness)).is_err());
+        let mut wrong_key = witness.clone();
+        wrong_key[0] ^= 1;
+        assert!(verify_commitment(scheme(COMMIT_SCHEME_ENCRYPTED), sealed, &claim(pair_key, leaf, &wrong_key)).is_err());
+
+        assert_eq!(verify_commitment(scheme(4), plain, &claim(pair_key, leaf, &[])), Err(err(ERR_UNKNOWN_COMMIT_SCHEME)));
+        assert!(scheme_enabled(U256::ZERO, scheme(COMMIT_SCHEME_HASH)));
+        assert!(!scheme_enabled(U256::ZERO, scheme(COMMIT_SCHEME_MERKLE)));
+        assert!(scheme_enabled(U256::from(1u64 << COMMIT_SCHEME_MERKLE), scheme(COMMIT_SCHEME_MERKLE)));
+        assert!(!scheme_enabled(U256::MAX, scheme(4)));
+    }
+
+    #[test]
+    fn lp_fee_accumulator_pays_pro_rata_and_never_over_distributes() {
+        use crate::lp_fees::{earned_fees, fee_per_share_growth};
+        let supply = U256::from(3_000u64);
+        let (growth, distributed) = fee_per_share_growth(U256::from(1_000u64), supply).unwrap();
+        assert!(distributed <= U256::from(1_000u64));
+        assert!(U256::from(1_000u64) - distributed <= U256::from(1u64));
+
+        // Holders of 1/3 and 2/3 of the supply split the fee; floors never exceed what was credited.
+        let a = earned_fees(U256::from(1_000u64), growth, U256::ZERO).unwrap();
+        let b = earned_fees(U256::from(2_000u64), growth, U256::ZERO).unwrap();
+        assert_eq!(a, U256::from(333u64));
+        assert_eq!(b, U256::from(666u64));
+        assert!(a + b <= distributed);
+
+        // A provider checkpointed at the current accumulator has earned nothing yet.
+        assert_eq!(earned_fees(U256::from(1_000u64), growth, growth).unwrap(), U256::ZERO);
+        assert_eq!(fee_per_share_growth(U256::from(5u64), U256::ZERO).unwrap(), (U256::ZERO, U256::ZERO));
+    }
+
+    #[test]
+    fn paths_may_not_return_to_an_asset_they_left() {
+        use crate::aliases::revisits_asset;
+        let (usdc, weth, arb) = (Address::repeat_byte(1), Address::repeat_byte(2), Address::repeat_byte(3));
+        assert!(!revisits_asset(&[usdc, weth]));
+        assert!(!revisits_asset(&[usdc, weth, arb]));
+        // USDC.e resolved to USDC: a direct conversion hop is fine, a round trip is not.
+        assert!(!revisits_asset(&[usdc, usdc, weth]));
+        assert!(revisits_asset(&[usdc, weth, usdc]));
+        assert!(revisits_asset(&[weth, usdc, arb, usdc]));
+    }
+
+    #[test]
+    fn volatility_regimes_move_the_fee_within_bounds() {
+        use crate::constants::{FEE_REGIME_CALM, FEE_REGIME_NORMAL, FEE_REGIME_VOLATILE};
+        use crate::fees::{classify_volatility, regime_fee_bps, volatility_bps};
+        let q = q112_u256();
+        let twap = q * U256::from(2u64);
+        assert_eq!(volatility_bps(twap + q / U256::from(8u64), twap).unwrap(), U256::from(625u64));
+        assert_eq!(volatility_bps(twap - q / U256::from(8u64), twap).unwrap(), U256::from(625u64));
+        assert_eq!(volatility_bps(q, U256::ZERO), Err(ERR_DIVISION_BY_ZERO));
+
+        let (calm, volatile) = (U256::from(50u64), U256::from(300u64));
+        let regime = |v: u64| classify_volatility(U256::from(v), calm, volatile);
+        assert_eq!(regime(0), as_u256(FEE_REGIME_CALM));
+        assert_eq!(regime(50), as_u256(FEE_REGIME_CALM));
+        assert_eq!(regime(51), as_u256(FEE_REGIME_NORMAL));
+        assert_eq!(regime(300), as_u256(FEE_REGIME_VOLATILE));
+        assert_eq!(classify_volatility(U256::from(5_000u64), calm, U256::ZERO), as_u256(FEE_REGIME_NORMAL));
+
+        let (surcharge, discount) = (U256::from(40u64), U256::from(20u64));
+        let fee = |base: u64, r: u64| regime_fee_bps(U256::from(base), U256::from(r), surcharge, discount);
+        assert_eq!(fee(30, FEE_REGIME_NORMAL), U256::from(30u64));
+        assert_eq!(fee(30, FEE_REGIME_VOLATILE), U256::from(70u64));
+        assert_eq!(fee(30, FEE_REGIME_CALM), U256::from(10u64));
+        assert_eq!(fee(5, FEE_REGIME_CALM), U256::ZERO);
+        assert_eq!(fee(MAX_FEE_BPS - 10, FEE_REGIME_VOLATILE), as_u256(MAX_FEE_BPS));
+    }
+
+    #[test]
+    fn audit_params_hash_matches_abi_encoding() {
+        use crate::audit::compute_params_hash;
+        let token = Address::repeat_byte(0x44);
+        let amount = U256::from(1_234_567u64);
+        let mut encoded = [0u8; 96];
+        encoded[12..32].copy_from_slice(token.as_slice());
+        encoded[32..64].copy_from_slice(&amount.to_be_bytes::<32>());
+        encoded[95] = 1;
+        let params = [token.into_word(), word(amount), bool_word(true)];
+        assert_eq!(compute_params_hash(&params), stylus_sdk::crypto::keccak(encoded));
+        assert_eq!(compute_params_hash(&[]), stylus_sdk::crypto::keccak([]));
+        assert_ne!(compute_params_hash(&params[..2]), compute_params_hash(&params));
+    }
+
+    #[test]
+    fn chained_quotes_feed_each_hop_output_into_the_next() {
+        let u = |v: u64| U256::from(v);
+        // Each hop prices with its own pool's fee tier.
+        let hops = [(u(1_000_000), u(2_000_000), u(5)), (u(5_000_000), u(1_000_000), u(100))];
+        let amounts = chain_amounts_out(u(10_000), &hops).unwrap();
+        let first = get_amount_out_with_fee(u(10_000), u(1_000_000), u(2_000_000), u(5)).unwrap();
+        let second = get_amount_out_with_fee(first, u(5_000_000), u(1_000_000), u(100)).unwrap();
+        assert_eq!(amounts, vec![u(10_000), first, second]);
+        // A hop that rounds to nothing kills the route instead of quoting zero.
+        let dead = [(u(1_000), u(1_000), u(30)), (u(1_000_000_000), u(1), u(30))];
+        assert_eq!(chain_amounts_out(u(1_000), &dead), Err(ERR_DUST_AMOUNT));
+    }
+
+    #[test]
+    fn k_ratio_is_scaled_and_survives_large_reserves() {
+        let u = |v: u64| U256::from(v);
+        let one = as_u256(K_RATIO_SCALE);
+        assert_eq!(compute_k_ratio((u(1_000), u(1_000)), (u(1_000), u(1_000))), one);
+        // 0.3% fee on a 10% flash: k grows by ~0.03%.
+        assert_eq!(compute_k_ratio((u(10_000), u(10_000)), (u(10_003), u(10_000))), one * u(10_003) / u(10_000));
+        assert_eq!(compute_k_ratio((U256::ZERO, u(5)), (u(5), u(5))), U256::ZERO);
+        let big = U256::MAX >> 1;
+        assert_eq!(compute_k_ratio((big, big), (big, big)), one);
+        assert_eq!(compute_k_ratio((u(1), u(1)), (U256::MAX, U256::MAX)), U256::MAX);
+    }
+
+    #[test]
+    fn pair_key_is_order_independent_and_fee_agnostic() {
+        let a = Address::repeat_byte(0x11);
+        let b = Address::repeat_byte(0x22);
+        let c = Address::repeat_byte(0x33);
+        assert_eq!(compute_pair_key(a, b), compute_pair_key(b, a));
+        assert_ne!(compute_pair_key(a, b), compute_pair_key(a, c));
+        assert_ne!(compute_pair_key(a, b), compute_pool_id(a, b, U256::ZERO));
+    }
+
+    #[test]
+    fn sync_credits_a_shared_token_surplus_and_never_writes_off_a_deficit() {
+        use crate::solvency::balance_discrepancy;
+        let u = |v: u64| U256::from(v);
+        // Pools X = (T, A) and Y = (T, B) share token T; the contract holds one T balance for both.
+        let (reserve_x, reserve_y, fees) = (u(1_000), u(4_000), u(50));
+        let owed = reserve_x + reserve_y + fees;
+        // 30 T donated for pool X shows up as a T surplus with no pool attached, so syncing Y
+        // would hand it to Y's LPs: only governance may choose the pool.
+        let (surplus, deficit) = balance_discrepancy(owed + u(30), owed);
+        assert_eq!((surplus, deficit), (u(30), U256::ZERO));
+        assert_eq!(synced_reserve(reserve_x, surplus), Ok(u(1_030)));
+        // A T shortfall (say from pool X) reports no surplus, so no pool's reserve moves.
+        let (surplus, deficit) = balance_discrepancy(owed - u(700), owed);
+        assert_eq!((surplus, deficit), (U256::ZERO, u(700)));
+        assert_eq!(synced_reserve(reserve_y, surplus), Ok(reserve_y));
+        assert_eq!(synced_reserve(U256::MAX, u(1)), Err(err(ERR_OVERFLOW)));
+    }
+
+    #[test]
+    fn balance_discrepancy_reports_surplus_or_deficit() {
+        use crate::solvency::balance_discrepancy;
+        let u = |v: u64| U256::from(v);
+        assert_eq!(balance_discrepancy(u(1_005), u(1_000)), (u(5), U256::ZERO));
+        assert_eq!(balance_discrepancy(u(990), u(1_000)), (U256::ZERO, u(10)));
+        assert_eq!(balance_discrepancy(u(1_000), u(1_000)), (U256::ZERO, U256::ZERO));
+    }
+
+    #[test]
+    fn locked_liquidity_releases_only_fee_growth() {
+        use crate::locks::locked_fee_shares;
+        let u = |v: u64| U256::from(v);
+        // Index grew 5% since the lock: 1/21 of the locked shares is fee growth.
+        assert_eq!(locked_fee_shares(u(2_100), u(1_000), u(1_050)).unwrap(), u(100));
+        // The remaining shares are worth exactly what was locked.
+        assert_eq!((u(2_100) - u(100)) * u(1_050), u(2_100) * u(1_000));
+        // No growth (or a lower index) releases nothing.
+        assert_eq!(locked_fee_shares(u(2_100), u(1_000), u(1_000)).unwrap(), U256::ZERO);
+        assert_eq!(locked_fee_shares(u(2_100), u(1_000), u(900)).unwrap(), U256::ZERO);
+    }
+
+    #[test]
+    fn bootstrap_deposit_must_match_feed_price() {
+        use crate::oracle::within_feed_tolerance;
+        let u = |v: u64| U256::from(v);
+        // Feed says 1 token0 = 2_000 token1; 1% tolerance.
+        let expected = u(2_000) * q112_u256();
+        assert!(within_feed_tolerance(u(10), u(20_000), expected, u(100)).unwrap());
+        assert!(within_feed_tolerance(u(10), u(20_190), expected, u(100)).unwrap());
+        assert!(!within_feed_tolerance(u(10), u(20_300), expected, u(100)).unwrap());
+        // Attacker opening at an absurd price is rejected.
+        assert!(!within_feed_tolerance(u(10), u(1), expected, u(100)).unwrap());
+    }
+
+    #[test]
+    fn batch_clears_both_sides_at_one_price() {
+        use crate::auction::clear_batch;
+        let u = |v: u64| U256::from(v);
+        let (r0, r1, fee) = (u(1_000_000), u(1_000_000), u(30));
+        // 10_000 token0 sold against 4_000 token1: token0 side is heavy.
+        let orders = [(true, u(10_000), u(1), u(1)), (false, u(4_000), u(1), u(2))];
+        let c = clear_batch(&orders, r0, r1, fee, u(1_000)).unwrap();
+        assert!(c.residual_zero_for_one);
+        assert_eq!(c.fills, vec![u(10_000), u(4_000)]);
+        assert_eq!(c.payouts[0], u(10_000) * c.price_num / c.price_den);
+        assert_eq!(c.payouts[1], u(4_000) * c.price_den / c.price_num);
+        assert_eq!(c.residual_in, u(10_000) - c.payouts[1]);
+        // Below spot (the net flow pays fee and impact), above the unbatched execution.
+        assert!(c.price_num < c.price_den);
+        let alone = get_amount_out_with_fee(u(10_000), r0, r1, fee).unwrap();
+        assert!(c.payouts[0] > alone);
+        // The curve always covers what the heavy side is owed beyond the crossed volume.
+        let curve_out = get_amount_out_with_fee(c.residual_in, r0, r1, fee).unwrap();
+        assert!(curve_out >= c.residual_out_needed);
+        assert_eq!(c.residual_out_needed, c.payouts[0] - u(4_000));
+    }
+
+    #[test]
+    fn batch_partial_fill_respects_priority_and_limits() {
+        use crate::auction::clear_batch;
+        let u = |v: u64| U256::from(v);
+        let (r0, r1, fee) = (u(1_000_000), u(1_000_000), u(30));
+        // 180_000 token0 against a 10% trade cap: earliest commitment (lowest priority) first.
+        let orders = [(true, u(60_000), u(1), u(30)), (true, u(60_000), u(1), u(10)), (true, u(60_000), u(1), u(20))];
+        let c = clear_batch(&orders, r0, r1, fee, u(1_000)).unwrap();
+        assert!(c.residual_in <= u(100_000));
+        assert_eq!(c.fills[1], u(60_000));
+        assert!(!c.fills[2].is_zero() && c.fills[2] < u(60_000));
+        assert_eq!(c.fills[0], U256::ZERO);
+        assert_eq!(c.residual_in, c.fills[1] + c.fills[2]);
+        let curve_out = get_amount_out_with_fee(c.residual_in, r0, r1, fee).unwrap();
+        assert!(curve_out >= c.residual_out_needed);
+
+        // A limit no uniform price can meet is dropped and refunded; the rest still clears.
+        let orders = [(true, u(10_000), u(1), u(1)), (false, u(4_000), u(5_000), u(2))];
+        let c = clear_batch(&orders, r0, r1, fee, u(1_000)).unwrap();
+        assert_eq!((c.fills[1], c.payouts[1]), (U256::ZERO, U256::ZERO));
+        assert_eq!(c.fills[0], u(10_000));
+        assert_eq!(c.payouts[0], get_amount_out_with_fee(u(10_000), r0, r1, fee).unwrap());
+    }
+
+    #[test]
+    fn creator_share_applies_until_schedule_end() {
+        use crate::creator::creator_share_of;
+        let u = |v: u64| U256::from(v);
+        // 10% of a 1_000 treasury fee while the schedule runs (end block inclusive).
+        assert_eq!(creator_share_of(u(1_000), u(1_000), u(50), u(100)).unwrap(), u(100));
+        assert_eq!(creator_share_of(u(1_000), u(1_000), u(100), u(100)).unwrap(), u(100));
+        assert_eq!(creator_share_of(u(1_000), u(1_000), u(101), u(100)).unwrap(), U256::ZERO);
+        // No share configured -> nothing, regardless of the schedule.
+        assert_eq!(creator_share_of(u(1_000), U256::ZERO, u(50), u(100)).unwrap(), U256::ZERO);
+    }
+
+    #[test]
+    fn partial_batch_fills_earliest_commitment_first() {
+        let u = |v: u64| U256::from(v);
+        // Same block: commit order decides; an earlier block beats any later sequence.
+        let late_block = commitment_priority(u(101), u(0)).unwrap();
+        let first_in_block = commitment_priority(u(100), u(7)).unwrap();
+        let second_in_block = commitment_priority(u(100), u(8)).unwrap();
+        assert!(first_in_block < second_in_block && second_in_block < late_block);
+        assert!(commitment_priority(U256::MAX, u(0)).is_err());
+
+        // Submitted out of order; 250 available fills 100 + 100 and 50 of the third.
+        let orders = [(late_block, u(100)), (second_in_block, u(100)), (first_in_block, u(100))];
+        assert_eq!(allocate_fills_by_priority(&orders, u(250)), vec![u(50), u(100), u(100)]);
+        assert_eq!(allocate_fills_by_priority(&orders, u(1_000)), vec![u(100), u(100), u(100)]);
+        assert_eq!(allocate_fills_by_priority(&orders, U256::ZERO), vec![U256::ZERO; 3]);
+    }
+
+    #[test]
+    fn optimal_liquidity_keeps_pool_ratio() {
+        let (r0, r1) = (U256::from(1_000u64), U256::from(4_000u64));
+        // Too much token1: token0 used in full, token1 cut to ratio.
+        assert_eq!(
+            optimal_liquidity_amounts(U256::from(10u64), U256::from(100u64), r0, r1).unwrap(),
+            (U256::from(10u64), U256::from(40u64))
+        );
+        // Too much token0: token1 used in full, token0 cut to ratio.
+        assert_eq!(
+            optimal_liquidity_amounts(U256::from(10u64), U256::from(20u64), r0, r1).unwrap(),
+            (U256::from(5u64), U256::from(20u64))
+        );
+        // Empty pool: desired amounts set the price.
+        assert_eq!(
+            optimal_liquidity_amounts(U256::from(7u64), U256::from(9u64), U256::ZERO, U256::ZERO).unwrap(),
+            (U256::from(7u64), U256::from(9u64))
+        );
+    }
+
+    #[test]
+    fn fee_apr_annualizes_lp_fees_over_average_reserves() {
+        use crate::constants::BLOCKS_PER_YEAR;
+        use crate::stats::fee_apr_bps;
+        // 1_000_000 reserve held for a full year earning 100_000 fees per side: 10% gross,
+        // of which the LP share is LP_FEE_PCT.
+        let area = U256::from(1_000_000u64) * as_u256(BLOCKS_PER_YEAR);
+        let fees = U256::from(100_000u64);
+        let expected = as_u256(1_000 * LP_FEE_PCT / 100);
+        assert_eq!(fee_apr_bps(fees, fees, area, area).unwrap(), expected);
+        // Only one side traded: half the pool earns, half the APR.
+        assert_eq!(fee_apr_bps(fees, U256::ZERO, area, area).unwrap(), expected / U256::from(2u64));
+        // No TVL history yet.
+        assert_eq!(fee_apr_bps(fees, fees, U256::ZERO, U256::ZERO).unwrap(), U256::ZERO);
+    }
+
+    #[test]
+    fn large_commitment_limit_must_sit_near_twap() {
+        let reserve_in = U256::from(1_000_000u64);
+        // Threshold 1% of reserve_in.
+        assert!(!is_large_commitment(U256::from(10_000u64), reserve_in, U256::from(100u64)));
+        assert!(is_large_commitment(U256::from(10_001u64), reserve_in, U256::from(100u64)));
+
+        // TWAP 2 out per in; 5% band -> min_out must be >= 0.95 * 2 * amount_in.
+        let twap = U256::from(2u64) * q112_u256();
+        let amount_in = U256::from(50_000u64);
+        let band = U256::from(500u64);
+        assert!(limit_within_twap_band(amount_in, U256::from(95_000u64), twap, band).unwrap());
+        assert!(!limit_within_twap_band(amount_in, U256::from(94_999u64), twap, band).unwrap());
+        assert_eq!(
+            limit_within_twap_band(amount_in, U256::from(1u64), twap, as_u256(BPS + 1)),
+            Err(ERR_INVALID_PRICE_BAND)
+        );
+    }
+
+    #[test]
+    fn pool_id_is_order_independent_and_fee_specific() {
+        let a = Address::repeat_byte(0x11);
+        let b = Address::repeat_byte(0x22);
+        let fee = as_u256(DEFAULT_FEE_BPS);
+        assert_eq!(compute_pool_id(a, b, fee), compute_pool_id(b, a, fee));
+        assert_ne!(compute_pool_id(a, b, fee), compute_pool_id(a, b, fee + U256::from(1u64)));
+    }
+
+    #[test]
+    fn commitment_status_follows_reveal_window() {
+        let commit = U256::from(100u64);
+        let at = |offset: u64| U256::from(100u64 + offset);
+        let (delay, age) = (as_u256(COMMIT_REVEAL_DELAY), as_u256(MAX_COMMITMENT_AGE));
+        let status = |active: bool, offset: u64| commitment_status(active, commit, at(offset), delay, age);
+        assert_eq!(status(false, 10), COMMIT_STATUS_NONE);
+        assert_eq!(status(true, COMMIT_REVEAL_DELAY - 1), COMMIT_STATUS_TOO_EARLY);
+        assert_eq!(status(true, COMMIT_REVEAL_DELAY), COMMIT_STATUS_READY);
+        assert_eq!(status(true, MAX_COMMITMENT_AGE), COMMIT_STATUS_READY);
+        assert_eq!(status(true, MAX_COMMITMENT_AGE + 1), COMMIT_STATUS_EXPIRED);
+    }
+
+    #[test]
+    fn reveals_revert_outside_the_window_without_cleanup() {
+        let (delay, age) = (as_u256(COMMIT_REVEAL_DELAY), as_u256(MAX_COMMITMENT_AGE));
+        let reveal = |offset: u64| {
+            require_revealable(commitment_status(true, U256::from(100u64), U256::from(100 + offset), delay, age))
+        };
+        assert_eq!(reveal(COMMIT_REVEAL_DELAY - 1), Err(err(ERR_TOO_EARLY)));
+        assert_eq!(reveal(COMMIT_REVEAL_DELAY), Ok(()));
+        assert_eq!(reveal(MAX_COMMITMENT_AGE), Ok(()));
+        // Expired: the reveal reverts; clearing is left to slash_expired_commitment.
+        assert_eq!(reveal(MAX_COMMITMENT_AGE + 1), Err(err(ERR_COMMITMENT_EXPIRED)));
+        assert_eq!(require_revealable(COMMIT_STATUS_NONE), Err(err(ERR_COMMIT_NOT_FOUND)));
+    }
+
+    #[test]
+    fn commit_reveal_params_respect_bounds() {
+        assert!(commit_reveal_params_valid(as_u256(COMMIT_REVEAL_DELAY), as_u256(MAX_COMMITMENT_AGE)));
+        assert!(commit_reveal_params_valid(as_u256(MIN_COMMIT_REVEAL_DELAY), as_u256(MIN_COMMITMENT_AGE)));
+        assert!(!commit_reveal_params_valid(U256::ZERO, as_u256(MAX_COMMITMENT_AGE)));
+        assert!(!commit_reveal_params_valid(as_u256(MAX_COMMIT_REVEAL_DELAY + 1), as_u256(MAX_COMMITMENT_AGE)));
+        assert!(!commit_reveal_params_valid(as_u256(COMMIT_REVEAL_DELAY), as_u256(MIN_COMMITMENT_AGE - 1)));
+        assert!(!commit_reveal_params_valid(as_u256(COMMIT_REVEAL_DELAY), as_u256(MAX_COMMITMENT_AGE_LIMIT + 1)));
+    }
+
     #[test]
     fn commit_hash_roundtrip() {
         let amount_in = U256::from(42u64);
         let salt = U256::from(1337u64);
 
-        let hash = compute_commit_hash(amount_in, salt);
+        let deadline = U256::from(500u64);
+
+        let hash = compute_commit_hash(amount_in, true, deadline, salt);
 
-        let encoded = encode_commit_data(amount_in, salt);
+        let encoded = encode_commit_data(amount_in, true, deadline, salt);
         let direct = crypto::keccak(&encoded);
 
         assert_eq!(hash, direct);
+        assert_ne!(hash, compute_commit_hash(amount_in, false, deadline, salt));
+        assert_ne!(hash, compute_commit_hash(amount_in, true, deadline + U256::from(1u64), salt));
     }
 
     #[test]
@@ -3321,7 +9137,7 @@ mod tests {
         let amount_in = U256::from(1_000_001u64); // 1M + 1 (tests rounding)
         let fee_bps = as_u256(DEFAULT_FEE_BPS);
 
-        let (_effective_in, treasury_fee, lp_fee, buyback_fee) =
+        let (_effective_in, treasury_fee, lp_fee, buyback_fee, gas_rebate) =
             compute_fee_split(amount_in, fee_bps).unwrap();
 
         // Calculate expected total fee
@@ -3331,16 +9147,18 @@ mod tests {
             .checked_div(as_u256(FEE_DENOMINATOR))
             .unwrap();
 
-        // Verify: treasury_fee + lp_fee + buyback_fee = total_fee exactly (no precision loss)
+        // Verify: treasury_fee + lp_fee + buyback_fee + gas_rebate = total_fee exactly (no precision loss)
         let actual_total_fee = treasury_fee
             .checked_add(lp_fee)
             .unwrap()
             .checked_add(buyback_fee)
+            .unwrap()
+            .checked_add(gas_rebate)
             .unwrap();
 
         assert_eq!(
             actual_total_fee, expected_total_fee,
-            "Fee split must not lose precision: treasury+lp+buyback = {}, expected {}",
+            "Fee split must not lose precision: treasury+lp+buyback+rebate = {}, expected {}",
             actual_total_fee, expected_total_fee
         );
 
@@ -0,0 +1,268 @@
+//! Claimable LP fees: a fee-per-share accumulator per pool and token.
+//!
+//! The LP share of every swap fee (60%, see `compute_fee_split`) is held outside the reserves
+//! and credited to the pool's accumulator as `lp_fee * 2^128 / lp_total_supply`. Each provider
+//! has a checkpoint of the accumulator; the growth since then times their LP balance is theirs.
+//! Checkpoints are settled into `lp_fees_owed*` whenever a balance changes (mint, burn), so a
+//! provider earns only while holding shares. Locked shares keep earning. The rounding
+//! remainder that the accumulator cannot represent compounds into the reserves instead.
+//!
+//! The MINIMUM_LIQUIDITY shares locked to address(0) at pool creation earn too, but nobody can
+//! claim for them. Governance may redirect that surplus to the treasury, bounded: a sweep is
+//! queued with its amounts fixed from the surplus at that moment (at most
+//! `lp_surplus_max_sweep_bps` of it), announced by event, and executes only after a grace
+//! period and `lp_surplus_min_age_epochs` block epochs (`LP_SURPLUS_EPOCH_BLOCKS`), at most once
+//! per epoch. Surplus accrued after the queue is left for a later sweep. Fees owed to real
+//! providers are never touched.
+
+use stylus_sdk::{
+    alloy_primitives::{Address, U256},
+    block,
+};
+
+use crate::constants::{as_u256, BPS, LP_SURPLUS_EPOCH_BLOCKS, LP_SURPLUS_SWEEP_DELAY_BLOCKS};
+use crate::errors::{
+    err, MathResult, OakResult, ERR_INVALID_TOKEN, ERR_LP_SURPLUS_SWEEP_DISABLED, ERR_LP_SURPLUS_SWEEP_PENDING,
+    ERR_LP_SURPLUS_SWEEP_TOO_EARLY, ERR_NOTHING_TO_CLAIM, ERR_NO_LP_SURPLUS_SWEEP, ERR_OVERFLOW,
+};
+use crate::events::{emit_lp_fees_claimed, emit_lp_surplus_sweep_cancelled, emit_lp_surplus_sweep_queued, emit_lp_surplus_swept};
+use crate::rounding::{self, Rounding};
+use crate::state::OakDEX;
+use crate::token::safe_transfer;
+
+/// Fixed-point scale of the fee-per-share accumulators (2^128).
+#[inline]
+pub fn lp_fee_scale() -> U256 {
+    U256::from(1u64).wrapping_shl(128)
+}
+
+/// Accumulator growth for `lp_fee` over `supply` shares, and the part of `lp_fee` it pays out.
+///
+/// @dev (growth, distributed) with `distributed = growth * supply / 2^128 <= lp_fee`.
+pub fn fee_per_share_growth(lp_fee: U256, supply: U256) -> MathResult<(U256, U256)> {
+    if lp_fee.is_zero() || supply.is_zero() {
+        return Ok((U256::ZERO, U256::ZERO));
+    }
+    let scale = lp_fee_scale();
+    let growth = rounding::div(lp_fee.checked_mul(scale).ok_or(ERR_OVERFLOW)?, supply, Rounding::Down)?;
+    let distributed = rounding::div(growth.checked_mul(supply).ok_or(ERR_OVERFLOW)?, scale, Rounding::Down)?;
+    Ok((growth, distributed))
+}
+
+/// Fees earned by `balance` shares while the accumulator moved from `paid` to `acc` (floor).
+pub fn earned_fees(balance: U256, acc: U256, paid: U256) -> MathResult<U256> {
+    rounding::div(balance.checked_mul(acc.saturating_sub(paid)).ok_or(ERR_OVERFLOW)?, lp_fee_scale(), Rounding::Down)
+}
+
+/// Share of the surplus `owed` a single sweep may take (floor).
+pub fn surplus_sweep_amount(owed: U256, max_sweep_bps: U256) -> MathResult<U256> {
+    rounding::div(owed.checked_mul(max_sweep_bps).ok_or(ERR_OVERFLOW)?, as_u256(BPS), Rounding::Down)
+}
+
+/// Surplus sweep epoch of block `now`.
+///
+/// @dev Derived from the block number alone, so governance cannot advance it.
+pub fn surplus_epoch(now: U256) -> U256 {
+    now / as_u256(LP_SURPLUS_EPOCH_BLOCKS)
+}
+
+/// True once a sweep queued in `queued_epoch` with grace period ending at `after_block` may run
+/// at block `now`; `swept_epoch` is the last sweep's epoch + 1 (0 = never), one sweep per epoch.
+pub fn surplus_sweep_ready(now: U256, after_block: U256, queued_epoch: U256, min_age_epochs: U256, swept_epoch: U256) -> bool {
+    let epoch = surplus_epoch(now);
+    now >= after_block
+        && epoch >= queued_epoch.saturating_add(min_age_epochs)
+        && swept_epoch != epoch.saturating_add(U256::from(1u64))
+}
+
+/// LP fee distributor (uses PoolData lp_fee_* storage). Token pair must be canonical.
+pub struct LpFees;
+
+impl LpFees {
+    /// Credit `amount` (from `growth`, see `fee_per_share_growth`) of token0 or token1 to the pool.
+    pub fn accrue(
+        dex: &mut OakDEX,
+        token0: Address,
+        token1: Address,
+        in_token0: bool,
+        growth: U256,
+        amount: U256,
+    ) -> OakResult<()> {
+        if amount.is_zero() {
+            return Ok(());
+        }
+        {
+            let mut outer = dex.pools.setter(token0);
+            let mut pool = outer.setter(token1);
+            let acc = if in_token0 { &mut pool.lp_fee_per_share0 } else { &mut pool.lp_fee_per_share1 };
+            let next = acc.get().checked_add(growth).ok_or_else(|| err(ERR_OVERFLOW))?;
+            acc.set(next);
+        }
+        let token = if in_token0 { token0 } else { token1 };
+        let held = dex.lp_fees_held.get(token).checked_add(amount).ok_or_else(|| err(ERR_OVERFLOW))?;
+        dex.lp_fees_held.setter(token).set(held);
+        Ok(())
+    }
+
+    /// (owed0, owed1) of `owner` including growth not yet settled.
+    pub fn pending(dex: &OakDEX, token0: Address, token1: Address, owner: Address) -> OakResult<(U256, U256)> {
+        let outer = dex.pools.getter(token0);
+        let pool = outer.getter(token1);
+        let balance = pool.lp_balances.get(owner);
+        let earned0 = earned_fees(balance, pool.lp_fee_per_share0.get(), pool.lp_fee_paid0.get(owner)).map_err(err)?;
+        let earned1 = earned_fees(balance, pool.lp_fee_per_share1.get(), pool.lp_fee_paid1.get(owner)).map_err(err)?;
+        let owed0 = pool.lp_fees_owed0.get(owner).checked_add(earned0).ok_or_else(|| err(ERR_OVERFLOW))?;
+        let owed1 = pool.lp_fees_owed1.get(owner).checked_add(earned1).ok_or_else(|| err(ERR_OVERFLOW))?;
+        Ok((owed0, owed1))
+    }
+
+    /// Move `owner`'s unsettled growth into owed and checkpoint the accumulators.
+    ///
+    /// @dev Call before any change to `owner`'s LP balance.
+    pub fn settle(dex: &mut OakDEX, token0: Address, token1: Address, owner: Address) -> OakResult<()> {
+        let (owed0, owed1) = Self::pending(dex, token0, token1, owner)?;
+        let mut outer = dex.pools.setter(token0);
+        let mut pool = outer.setter(token1);
+        let (acc0, acc1) = (pool.lp_fee_per_share0.get(), pool.lp_fee_per_share1.get());
+        pool.lp_fee_paid0.setter(owner).set(acc0);
+        pool.lp_fee_paid1.setter(owner).set(acc1);
+        pool.lp_fees_owed0.setter(owner).set(owed0);
+        pool.lp_fees_owed1.setter(owner).set(owed1);
+        Ok(())
+    }
+
+    /// Pay `owner` everything owed by (token0, token1); returns (amount0, amount1).
+    pub fn claim(dex: &mut OakDEX, token0: Address, token1: Address, owner: Address) -> OakResult<(U256, U256)> {
+        Self::settle(dex, token0, token1, owner)?;
+        let (amount0, amount1) = {
+            let mut outer = dex.pools.setter(token0);
+            let mut pool = outer.setter(token1);
+            let owed = (pool.lp_fees_owed0.get(owner), pool.lp_fees_owed1.get(owner));
+            pool.lp_fees_owed0.setter(owner).set(U256::ZERO);
+            pool.lp_fees_owed1.setter(owner).set(U256::ZERO);
+            owed
+        };
+        if amount0.is_zero() && amount1.is_zero() {
+            return Err(err(ERR_NOTHING_TO_CLAIM));
+        }
+        for (token, amount) in [(token0, amount0), (token1, amount1)] {
+            let held = dex.lp_fees_held.get(token).saturating_sub(amount);
+            dex.lp_fees_held.setter(token).set(held);
+        }
+        for (token, amount) in [(token0, amount0), (token1, amount1)] {
+            if !amount.is_zero() {
+                safe_transfer(token, owner, amount)?;
+            }
+        }
+        emit_lp_fees_claimed(owner, token0, token1, amount0, amount1);
+        Ok((amount0, amount1))
+    }
+
+    /// Unclaimable fees of the locked zero-address shares as (surplus0, surplus1), minus any queued sweep.
+    pub fn surplus(dex: &OakDEX, token0: Address, token1: Address) -> OakResult<(U256, U256)> {
+        let (owed0, owed1) = Self::pending(dex, token0, token1, Address::ZERO)?;
+        let outer = dex.pools.getter(token0);
+        let pool = outer.getter(token1);
+        Ok((
+            owed0.saturating_sub(pool.lp_surplus_queued0.get()),
+            owed1.saturating_sub(pool.lp_surplus_queued1.get()),
+        ))
+    }
+
+    /// Queue a sweep of up to `lp_surplus_max_sweep_bps` of the pool's surplus; returns (amount0, amount1).
+    ///
+    /// @notice Amounts are fixed now from the current surplus and swept no earlier than
+    ///         LP_SURPLUS_SWEEP_DELAY_BLOCKS later and `lp_surplus_min_age_epochs` block epochs
+    ///         later. Caller enforces governance access.
+    pub fn queue_surplus_sweep(dex: &mut OakDEX, token0: Address, token1: Address) -> OakResult<(U256, U256)> {
+        let max_sweep_bps = dex.lp_surplus_max_sweep_bps.get();
+        if max_sweep_bps.is_zero() {
+            return Err(err(ERR_LP_SURPLUS_SWEEP_DISABLED));
+        }
+        {
+            let outer = dex.pools.getter(token0);
+            let pool = outer.getter(token1);
+            if !pool.initialized.get() {
+                return Err(err(ERR_INVALID_TOKEN));
+            }
+            if !pool.lp_surplus_sweep_after_block.get().is_zero() {
+                return Err(err(ERR_LP_SURPLUS_SWEEP_PENDING));
+            }
+        }
+        Self::settle(dex, token0, token1, Address::ZERO)?;
+        let (surplus0, surplus1) = Self::surplus(dex, token0, token1)?;
+        let amount0 = surplus_sweep_amount(surplus0, max_sweep_bps).map_err(err)?;
+        let amount1 = surplus_sweep_amount(surplus1, max_sweep_bps).map_err(err)?;
+        if amount0.is_zero() && amount1.is_zero() {
+            return Err(err(ERR_NOTHING_TO_CLAIM));
+        }
+        let after_block = U256::from(block::number())
+            .checked_add(as_u256(LP_SURPLUS_SWEEP_DELAY_BLOCKS))
+            .ok_or_else(|| err(ERR_OVERFLOW))?;
+        let min_age_epochs = dex.lp_surplus_min_age_epochs.get();
+        let epoch = surplus_epoch(U256::from(block::number()));
+        let mut outer = dex.pools.setter(token0);
+        let mut pool = outer.setter(token1);
+        pool.lp_surplus_queued0.set(amount0);
+        pool.lp_surplus_queued1.set(amount1);
+        pool.lp_surplus_sweep_after_block.set(after_block);
+        pool.lp_surplus_queued_epoch.set(epoch);
+        emit_lp_surplus_sweep_queued(token0, token1, amount0, amount1, after_block, epoch.saturating_add(min_age_epochs));
+        Ok((amount0, amount1))
+    }
+
+    /// Move the queued surplus from the zero-address shares to the treasury balance; returns (amount0, amount1).
+    ///
+    /// @notice At most one sweep executes per block epoch, so each epoch moves at most the
+    ///         capped share of surplus that existed when its sweep was queued.
+    /// @dev Bookkeeping only: the tokens already sit in the contract under `lp_fees_held`.
+    pub fn execute_surplus_sweep(dex: &mut OakDEX, token0: Address, token1: Address) -> OakResult<(U256, U256)> {
+        let min_age_epochs = dex.lp_surplus_min_age_epochs.get();
+        let (amount0, amount1) = {
+            let mut outer = dex.pools.setter(token0);
+            let mut pool = outer.setter(token1);
+            let after_block = pool.lp_surplus_sweep_after_block.get();
+            if after_block.is_zero() {
+                return Err(err(ERR_NO_LP_SURPLUS_SWEEP));
+            }
+            let now = U256::from(block::number());
+            let queued_epoch = pool.lp_surplus_queued_epoch.get();
+            if !surplus_sweep_ready(now, after_block, queued_epoch, min_age_epochs, pool.lp_surplus_swept_epoch.get()) {
+                return Err(err(ERR_LP_SURPLUS_SWEEP_TOO_EARLY));
+            }
+            pool.lp_surplus_swept_epoch.set(surplus_epoch(now).saturating_add(U256::from(1u64)));
+            let amounts = (pool.lp_surplus_queued0.get(), pool.lp_surplus_queued1.get());
+            // The zero address never claims and its owed balance only grows, so this cannot underflow.
+            let owed0 = pool.lp_fees_owed0.get(Address::ZERO).checked_sub(amounts.0).ok_or_else(|| err(ERR_OVERFLOW))?;
+            let owed1 = pool.lp_fees_owed1.get(Address::ZERO).checked_sub(amounts.1).ok_or_else(|| err(ERR_OVERFLOW))?;
+            pool.lp_fees_owed0.setter(Address::ZERO).set(owed0);
+            pool.lp_fees_owed1.setter(Address::ZERO).set(owed1);
+            pool.lp_surplus_queued0.set(U256::ZERO);
+            pool.lp_surplus_queued1.set(U256::ZERO);
+            pool.lp_surplus_sweep_after_block.set(U256::ZERO);
+            amounts
+        };
+        for (token, amount) in [(token0, amount0), (token1, amount1)] {
+            let held = dex.lp_fees_held.get(token).checked_sub(amount).ok_or_else(|| err(ERR_OVERFLOW))?;
+            dex.lp_fees_held.setter(token).set(held);
+            let treasury = dex.treasury_balance.get(token).checked_add(amount).ok_or_else(|| err(ERR_OVERFLOW))?;
+            dex.treasury_balance.setter(token).set(treasury);
+        }
+        emit_lp_surplus_swept(token0, token1, amount0, amount1);
+        Ok((amount0, amount1))
+    }
+
+    /// Drop the pool's queued surplus sweep.
+    pub fn cancel_surplus_sweep(dex: &mut OakDEX, token0: Address, token1: Address) -> OakResult<()> {
+        let mut outer = dex.pools.setter(token0);
+        let mut pool = outer.setter(token1);
+        if pool.lp_surplus_sweep_after_block.get().is_zero() {
+            return Err(err(ERR_NO_LP_SURPLUS_SWEEP));
+        }
+        pool.lp_surplus_queued0.set(U256::ZERO);
+        pool.lp_surplus_queued1.set(U256::ZERO);
+        pool.lp_surplus_sweep_after_block.set(U256::ZERO);
+        emit_lp_surplus_sweep_cancelled(token0, token1);
+        Ok(())
+    }
+}
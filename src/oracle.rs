@@ -0,0 +1,528 @@
+//! Per-pool TWAP oracle and cross-pool composition.
+//!
+//! Each pool accumulates `price * blocks` for both directions on every swap (before reserves
+//! change), Uniswap V2 style. Two checkpoints roll every TWAP_WINDOW_BLOCKS; the average is taken
+//! from the older one to now, extrapolating the cumulative with the current spot price. A routing
+//! path (A -> B -> C) composes the per-hop averages into one implied price, so long-tail assets can
+//! be priced through intermediate pools on Oak alone.
+//!
+//! Every cumulative update is also written to a per-pool ring buffer of observations (Uniswap
+//! v3 style). The ring starts with one slot; anyone may pre-pay more slots with
+//! `increase_observation_cardinality`, after which `observe` answers cumulatives at arbitrary
+//! points in the past, so callers pick their own TWAP windows.
+//!
+//! Cold start: the TWAP anchors on whatever price the first depositor sets. Governance may bind
+//! a pair to an external (Chainlink-style) feed; the first deposit must then be within a
+//! tolerance of the feed price, so nobody can initialize the pool at an absurd price.
+
+use alloc::vec::Vec;
+use stylus_sdk::{alloy_primitives::{Address, U256, U512}, block};
+
+use crate::constants::{
+    as_u256, q112_u256, BPS, PRICE_SCALE_DECIMALS, FEED_MAX_AGE_SECONDS, MAX_OBSERVATION_CARDINALITY, MAX_OBSERVATION_GROWTH_PER_CALL, MAX_OBSERVE_POINTS,
+    MAX_PATH_LENGTH, TWAP_WINDOW_BLOCKS,
+};
+use crate::errors::{
+    err, MathResult, OakResult, ERR_BOOTSTRAP_PRICE_OUT_OF_RANGE, ERR_CARDINALITY_TOO_HIGH, ERR_DIVISION_BY_ZERO,
+    ERR_FEED_UNAVAILABLE, ERR_INVALID_FEED_CONFIG, ERR_INVALID_PATH, ERR_INVALID_TOKEN, ERR_OBSERVATION_TOO_OLD,
+    ERR_OVERFLOW, ERR_PATH_TOO_LONG, ERR_TOO_MANY_ITEMS,
+};
+use crate::events::{emit_bootstrap_feed_set, emit_observation_cardinality_increased};
+use crate::state::{OakDEX, PoolData};
+use crate::token::decimals;
+
+/// Block value of a pre-paid ring slot that has not been written yet.
+const UNWRITTEN_SLOT: U256 = U256::MAX;
+
+/// `PoolOracle::path_twap` result: (price_q112, max_age_blocks, min_window_blocks).
+/// `price_q112` is units of the last token per unit of the first, Q112 fixed point.
+pub type PathTwap = (U256, U256, U256);
+
+/// Multiply per-hop Q112 prices into one Q112 price (floor at every hop).
+pub fn compose_q112_prices(prices: &[U256]) -> MathResult<U256> {
+    let q112 = q112_u256();
+    let mut composed = q112;
+    for price in prices {
+        composed = composed
+            .checked_mul(*price)
+            .ok_or(ERR_OVERFLOW)?
+            .checked_div(q112)
+            .ok_or(ERR_DIVISION_BY_ZERO)?;
+    }
+    Ok(composed)
+}
+
+/// Q112 spot prices (price0 = reserve1/reserve0, price1 = reserve0/reserve1).
+pub(crate) fn spot_prices(reserve0: U256, reserve1: U256) -> MathResult<(U256, U256)> {
+    let q112 = q112_u256();
+    let price0 = reserve1
+        .checked_mul(q112)
+        .ok_or(ERR_OVERFLOW)?
+        .checked_div(reserve0)
+        .ok_or(ERR_DIVISION_BY_ZERO)?;
+    let price1 = reserve0
+        .checked_mul(q112)
+        .ok_or(ERR_OVERFLOW)?
+        .checked_div(reserve1)
+        .ok_or(ERR_DIVISION_BY_ZERO)?;
+    Ok((price0, price1))
+}
+
+/// Human price from a raw Q112 price: whole `token_out` per whole `token_in`, 1e18-scaled.
+///
+/// @dev `price_q112 * 10^(18 + decimals_in) / (10^decimals_out * 2^112)`, in 512 bits; floor.
+pub fn decimal_adjusted_price(price_q112: U256, decimals_in: u8, decimals_out: u8) -> MathResult<U256> {
+    let ten = U512::from(10u64);
+    let num = U512::from(price_q112)
+        .checked_mul(ten.pow(U512::from(PRICE_SCALE_DECIMALS as u64 + decimals_in as u64)))
+        .ok_or(ERR_OVERFLOW)?;
+    let den = ten.pow(U512::from(decimals_out as u64)) << 112;
+    U256::checked_from_limbs_slice(num.checked_div(den).ok_or(ERR_DIVISION_BY_ZERO)?.as_limbs()).ok_or(ERR_OVERFLOW)
+}
+
+/// Cumulative at `target` between observations (a_block, a_cum) and (b_block, b_cum), linear.
+pub fn interpolate_cumulative(a_cum: U256, a_block: U256, b_cum: U256, b_block: U256, target: U256) -> MathResult<U256> {
+    if target <= a_block {
+        return Ok(a_cum);
+    }
+    let span = b_block.checked_sub(a_block).ok_or(ERR_OVERFLOW)?;
+    let delta = b_cum
+        .checked_sub(a_cum)
+        .ok_or(ERR_OVERFLOW)?
+        .checked_mul(target - a_block)
+        .ok_or(ERR_OVERFLOW)?
+        .checked_div(span)
+        .ok_or(ERR_DIVISION_BY_ZERO)?;
+    a_cum.checked_add(delta).ok_or(ERR_OVERFLOW)
+}
+
+/// True if depositing `amount0` : `amount1` sets a price within `tolerance_bps` of `expected_q112`
+/// (reserve1/reserve0 in Q112).
+pub fn within_feed_tolerance(amount0: U256, amount1: U256, expected_q112: U256, tolerance_bps: U256) -> MathResult<bool> {
+    if amount0.is_zero() || expected_q112.is_zero() {
+        return Ok(false);
+    }
+    let price = amount1
+        .checked_mul(q112_u256())
+        .ok_or(ERR_OVERFLOW)?
+        .checked_div(amount0)
+        .ok_or(ERR_DIVISION_BY_ZERO)?;
+    let deviation = if price > expected_q112 { price - expected_q112 } else { expected_q112 - price };
+    let lhs = deviation.checked_mul(as_u256(BPS)).ok_or(ERR_OVERFLOW)?;
+    let rhs = expected_q112.checked_mul(tolerance_bps).ok_or(ERR_OVERFLOW)?;
+    Ok(lhs <= rhs)
+}
+
+/// Latest `(answer, updated_at)` of a Chainlink-style aggregator.
+///
+/// Host-side implementation reports no answer, so a feed-bound first deposit fails closed.
+/// On-chain, this should call `latestRoundData()` and return `(answer, updatedAt)`.
+pub fn latest_feed_answer(_feed: Address) -> OakResult<(U256, U256)> {
+    Ok((U256::ZERO, U256::ZERO))
+}
+
+/// Whether a ring slot holds a real observation (not empty and not merely pre-paid).
+fn is_written(block: U256) -> bool {
+    !block.is_zero() && block != UNWRITTEN_SLOT
+}
+
+/// Per-pool TWAP oracle (uses PoolData oracle_* storage).
+pub struct PoolOracle;
+
+impl PoolOracle {
+    /// Accumulate the pre-trade price of (token0, token1) and roll window checkpoints.
+    ///
+    /// @dev Canonical order; call before reserves change. No-op for empty pools.
+    pub fn record(dex: &mut OakDEX, token0: Address, token1: Address, reserve0: U256, reserve1: U256) -> OakResult<()> {
+        let now = U256::from(block::number());
+        let mut outer = dex.pools.setter(token0);
+        let mut pool = outer.setter(token1);
+        let last = pool.oracle_block_last.get();
+        if last.is_zero() || reserve0.is_zero() || reserve1.is_zero() {
+            // First observation (or empty pool): start the clock without accumulating.
+            pool.oracle_block_last.set(now);
+            let (cum0, cum1) = (pool.oracle_cumulative0.get(), pool.oracle_cumulative1.get());
+            if pool.oracle_cp_new.block.get().is_zero() {
+                pool.oracle_cp_new.cumulative0.set(cum0);
+                pool.oracle_cp_new.cumulative1.set(cum1);
+                pool.oracle_cp_new.block.set(now);
+            }
+            Self::write_observation(&mut pool, cum0, cum1, now);
+            return Ok(());
+        }
+        let elapsed = now.saturating_sub(last);
+        if elapsed.is_zero() {
+            return Ok(());
+        }
+        let (price0, price1) = spot_prices(reserve0, reserve1)?;
+        let cum0 = pool
+            .oracle_cumulative0
+            .get()
+            .checked_add(price0.checked_mul(elapsed).ok_or_else(|| err(ERR_OVERFLOW))?)
+            .ok_or_else(|| err(ERR_OVERFLOW))?;
+        let cum1 = pool
+            .oracle_cumulative1
+            .get()
+            .checked_add(price1.checked_mul(elapsed).ok_or_else(|| err(ERR_OVERFLOW))?)
+            .ok_or_else(|| err(ERR_OVERFLOW))?;
+        pool.oracle_cumulative0.set(cum0);
+        pool.oracle_cumulative1.set(cum1);
+        pool.oracle_block_last.set(now);
+        Self::write_observation(&mut pool, cum0, cum1, now);
+
+        if now.saturating_sub(pool.oracle_cp_new.block.get()) >= as_u256(TWAP_WINDOW_BLOCKS) {
+            let (old0, old1, old_block) = (
+                pool.oracle_cp_new.cumulative0.get(),
+                pool.oracle_cp_new.cumulative1.get(),
+                pool.oracle_cp_new.block.get(),
+            );
+            pool.oracle_cp_old.cumulative0.set(old0);
+            pool.oracle_cp_old.cumulative1.set(old1);
+            pool.oracle_cp_old.block.set(old_block);
+            pool.oracle_cp_new.cumulative0.set(cum0);
+            pool.oracle_cp_new.cumulative1.set(cum1);
+            pool.oracle_cp_new.block.set(now);
+        }
+        Ok(())
+    }
+
+    /// Append (cum0, cum1, now) to the ring, growing it into pre-paid slots when the write
+    /// index reaches the current end. Same-block updates overwrite the newest slot.
+    fn write_observation(pool: &mut PoolData, cum0: U256, cum1: U256, now: U256) {
+        let one = U256::from(1u64);
+        let cardinality = pool.observation_cardinality.get();
+        let slot = if cardinality.is_zero() {
+            pool.observation_cardinality.set(one);
+            if pool.observation_cardinality_next.get().is_zero() {
+                pool.observation_cardinality_next.set(one);
+            }
+            U256::ZERO
+        } else {
+            let index = pool.observation_index.get();
+            if pool.observations.getter(index).block.get() == now {
+                index
+            } else {
+                let next = pool.observation_cardinality_next.get();
+                let cardinality = if next > cardinality && index == cardinality - one {
+                    pool.observation_cardinality.set(next);
+                    next
+                } else {
+                    cardinality
+                };
+                (index + one) % cardinality
+            }
+        };
+        pool.observation_index.set(slot);
+        let mut observation = pool.observations.setter(slot);
+        observation.cumulative0.set(cum0);
+        observation.cumulative1.set(cum1);
+        observation.block.set(now);
+    }
+
+    /// Grow the ring of (token0, token1) towards `target` slots; returns the new size.
+    ///
+    /// @notice Anyone may call. New slots are written once here so the storage cost is paid by
+    ///         the caller, not by the swaps that later fill them. At most
+    ///         MAX_OBSERVATION_GROWTH_PER_CALL slots per call; call again until the result
+    ///         reaches `target`.
+    pub fn increase_observation_cardinality(
+        dex: &mut OakDEX,
+        token0: Address,
+        token1: Address,
+        target: U256,
+    ) -> OakResult<U256> {
+        if target > as_u256(MAX_OBSERVATION_CARDINALITY) {
+            return Err(err(ERR_CARDINALITY_TOO_HIGH));
+        }
+        let mut outer = dex.pools.setter(token0);
+        let mut pool = outer.setter(token1);
+        if !pool.initialized.get() {
+            return Err(err(ERR_INVALID_TOKEN));
+        }
+        let current = pool.observation_cardinality_next.get().max(U256::from(1u64));
+        if target <= current {
+            return Ok(current);
+        }
+        let target = target.min(current + as_u256(MAX_OBSERVATION_GROWTH_PER_CALL));
+        let mut slot = current;
+        while slot < target {
+            let mut observation = pool.observations.setter(slot);
+            observation.cumulative0.set(U256::from(1u64));
+            observation.cumulative1.set(U256::from(1u64));
+            observation.block.set(UNWRITTEN_SLOT);
+            slot += U256::from(1u64);
+        }
+        pool.observation_cardinality_next.set(target);
+        emit_observation_cardinality_increased(token0, token1, current, target);
+        Ok(target)
+    }
+
+    /// Ring state of (token0, token1): (index, cardinality, cardinality_next).
+    pub fn observation_state(dex: &OakDEX, token0: Address, token1: Address) -> (U256, U256, U256) {
+        let outer = dex.pools.getter(token0);
+        let pool = outer.getter(token1);
+        (
+            pool.observation_index.get(),
+            pool.observation_cardinality.get(),
+            pool.observation_cardinality_next.get(),
+        )
+    }
+
+    /// Cumulative prices of `token_in` in `token_out` (Q112 * blocks) at `now - ago` for each entry.
+    ///
+    /// @notice Differences of two results divided by the difference of their `ago`s give the
+    ///         TWAP over that window. The repo counts blocks at 1 block/s, so `ago` is seconds.
+    ///         Reverts with OBSERVATION_TOO_OLD for points before the oldest observation.
+    /// @dev At most MAX_OBSERVE_POINTS points; each costs one O(log cardinality) search.
+    pub fn observe(dex: &OakDEX, token_in: Address, token_out: Address, agos: &[U256]) -> OakResult<Vec<U256>> {
+        if agos.len() as u64 > MAX_OBSERVE_POINTS {
+            return Err(err(ERR_TOO_MANY_ITEMS));
+        }
+        let (token0, token1) = if token_in < token_out {
+            (token_in, token_out)
+        } else {
+            (token_out, token_in)
+        };
+        let outer = dex.pools.getter(token0);
+        let pool = outer.getter(token1);
+        if !pool.initialized.get() {
+            return Err(err(ERR_INVALID_TOKEN));
+        }
+        let cardinality = pool.observation_cardinality.get();
+        if cardinality.is_zero() {
+            return Err(err(ERR_OBSERVATION_TOO_OLD));
+        }
+        let zero_for_one = token_in == token0;
+        let pick = |cum0: U256, cum1: U256| if zero_for_one { cum0 } else { cum1 };
+        let (reserve0, reserve1) = (pool.reserve0.get(), pool.reserve1.get());
+        let spot = if reserve0.is_zero() || reserve1.is_zero() {
+            U256::ZERO
+        } else {
+            let (price0, price1) = spot_prices(reserve0, reserve1)?;
+            pick(price0, price1)
+        };
+
+        let index = pool.observation_index.get();
+        let one = U256::from(1u64);
+        // Ring in chronological order: oldest at index + 1 once the ring has wrapped, else slot 0.
+        let wrapped_start = (index + one) % cardinality;
+        let (oldest, len) = if is_written(pool.observations.getter(wrapped_start).block.get()) {
+            (wrapped_start, cardinality)
+        } else {
+            (U256::ZERO, index + one)
+        };
+        let at = |logical: U256| {
+            let observation = pool.observations.getter((oldest + logical) % cardinality);
+            (
+                pick(observation.cumulative0.get(), observation.cumulative1.get()),
+                observation.block.get(),
+            )
+        };
+        let (newest_cum, newest_block) = at(len - one);
+        let (_, oldest_block) = at(U256::ZERO);
+
+        let now = U256::from(block::number());
+        let mut cumulatives = Vec::with_capacity(agos.len());
+        for ago in agos {
+            let target = now.checked_sub(*ago).ok_or_else(|| err(ERR_OBSERVATION_TOO_OLD))?;
+            let cumulative = if target >= newest_block {
+                // Counterfactual: spot price held since the newest observation.
+                spot.checked_mul(target - newest_block)
+                    .and_then(|d| newest_cum.checked_add(d))
+                    .ok_or_else(|| err(ERR_OVERFLOW))?
+            } else {
+                if target < oldest_block {
+                    return Err(err(ERR_OBSERVATION_TOO_OLD));
+                }
+                // Binary search for the last observation at or before `target`.
+                let (mut lo, mut hi) = (U256::ZERO, len - one);
+                while hi - lo > one {
+                    let mid = (lo + hi) / U256::from(2u64);
+                    if at(mid).1 <= target {
+                        lo = mid;
+                    } else {
+                        hi = mid;
+                    }
+                }
+                let (a_cum, a_block) = at(lo);
+                let (b_cum, b_block) = at(lo + one);
+                interpolate_cumulative(a_cum, a_block, b_cum, b_block, target)?
+            };
+            cumulatives.push(cumulative);
+        }
+        Ok(cumulatives)
+    }
+
+    /// TWAP of `token_in` in `token_out` (Q112) over the TWAP_WINDOW_BLOCKS ending at `end_block`.
+    ///
+    /// @dev Read from the observation ring; if the ring does not reach that far back, falls back
+    ///      to the current window TWAP (`twap`), which still spans `end_block` for recent blocks.
+    pub fn twap_ending_at(dex: &OakDEX, token_in: Address, token_out: Address, end_block: U256) -> OakResult<U256> {
+        let now = U256::from(block::number());
+        let window = as_u256(TWAP_WINDOW_BLOCKS);
+        let end_ago = now.saturating_sub(end_block);
+        if let Ok(cumulatives) = Self::observe(dex, token_in, token_out, &[end_ago + window, end_ago]) {
+            return cumulatives[1]
+                .checked_sub(cumulatives[0])
+                .map(|delta| delta / window)
+                .ok_or_else(|| err(ERR_OVERFLOW));
+        }
+        Ok(Self::twap(dex, token_in, token_out)?.0)
+    }
+
+    /// TWAP of `token_in` priced in `token_out` (Q112) as (price, age_blocks, window_blocks).
+    ///
+    /// @notice `age` = blocks since the pool's last observation; `window` = blocks averaged over.
+    ///         A pool without a usable checkpoint returns its spot price with window 0.
+    pub fn twap(dex: &OakDEX, token_in: Address, token_out: Address) -> OakResult<(U256, U256, U256)> {
+        let (token0, token1) = if token_in < token_out {
+            (token_in, token_out)
+        } else {
+            (token_out, token_in)
+        };
+        let outer = dex.pools.getter(token0);
+        let pool = outer.getter(token1);
+        if !pool.initialized.get() {
+            return Err(err(ERR_INVALID_TOKEN));
+        }
+        let (reserve0, reserve1) = (pool.reserve0.get(), pool.reserve1.get());
+        if reserve0.is_zero() || reserve1.is_zero() {
+            return Err(err(crate::errors::ERR_INSUFFICIENT_LIQUIDITY));
+        }
+        let zero_for_one = token_in == token0;
+        let (price0, price1) = spot_prices(reserve0, reserve1)?;
+        let spot = if zero_for_one { price0 } else { price1 };
+
+        let now = U256::from(block::number());
+        let last = pool.oracle_block_last.get();
+        let age = now.saturating_sub(last);
+        let cumulative = if zero_for_one {
+            pool.oracle_cumulative0.get()
+        } else {
+            pool.oracle_cumulative1.get()
+        };
+        // Counterfactual cumulative at `now`: spot price held since the last observation.
+        let cum_now = cumulative
+            .checked_add(spot.checked_mul(age).ok_or_else(|| err(ERR_OVERFLOW))?)
+            .ok_or_else(|| err(ERR_OVERFLOW))?;
+
+        let cp = if pool.oracle_cp_old.block.get().is_zero() {
+            &pool.oracle_cp_new
+        } else {
+            &pool.oracle_cp_old
+        };
+        let cp_block = cp.block.get();
+        let window = if last.is_zero() { U256::ZERO } else { now.saturating_sub(cp_block) };
+        if window.is_zero() {
+            return Ok((spot, age, U256::ZERO));
+        }
+        let cp_cum = if zero_for_one { cp.cumulative0.get() } else { cp.cumulative1.get() };
+        let price = cum_now
+            .checked_sub(cp_cum)
+            .ok_or_else(|| err(ERR_OVERFLOW))?
+            .checked_div(window)
+            .ok_or_else(|| err(ERR_DIVISION_BY_ZERO))?;
+        Ok((price, age, window))
+    }
+
+    /// Decimal-adjusted prices of (token_a, token_b) as (1 token_a in token_b, 1 token_b in token_a),
+    /// 1e18-scaled; spot from reserves, or the current-window TWAP when `twap` is set.
+    pub fn decimal_prices(dex: &OakDEX, token_a: Address, token_b: Address, twap: bool) -> OakResult<(U256, U256)> {
+        let (price_ab, price_ba) = if twap {
+            (Self::twap(dex, token_a, token_b)?.0, Self::twap(dex, token_b, token_a)?.0)
+        } else {
+            let (token0, token1) = if token_a < token_b { (token_a, token_b) } else { (token_b, token_a) };
+            let outer = dex.pools.getter(token0);
+            let pool = outer.getter(token1);
+            if !pool.initialized.get() {
+                return Err(err(ERR_INVALID_TOKEN));
+            }
+            let (price0, price1) = spot_prices(pool.reserve0.get(), pool.reserve1.get()).map_err(err)?;
+            if token_a == token0 { (price0, price1) } else { (price1, price0) }
+        };
+        let (decimals_a, decimals_b) = (decimals(token_a)?, decimals(token_b)?);
+        Ok((
+            decimal_adjusted_price(price_ab, decimals_a, decimals_b).map_err(err)?,
+            decimal_adjusted_price(price_ba, decimals_b, decimals_a).map_err(err)?,
+        ))
+    }
+
+    /// Compose per-hop TWAPs along `path` into one implied price (see `PathTwap`).
+    ///
+    /// @notice Staleness is the worst hop (max age); confidence is the shortest averaging window.
+    pub fn path_twap(dex: &OakDEX, path: &[Address]) -> OakResult<PathTwap> {
+        if path.len() < 2 {
+            return Err(err(ERR_INVALID_PATH));
+        }
+        if path.len() as u64 > MAX_PATH_LENGTH {
+            return Err(err(ERR_PATH_TOO_LONG));
+        }
+        let mut prices = Vec::with_capacity(path.len() - 1);
+        let mut max_age = U256::ZERO;
+        let mut min_window = U256::MAX;
+        for hop in path.windows(2) {
+            if hop[0] == hop[1] {
+                return Err(err(ERR_INVALID_PATH));
+            }
+            let (price, age, window) = Self::twap(dex, hop[0], hop[1])?;
+            prices.push(price);
+            max_age = max_age.max(age);
+            min_window = min_window.min(window);
+        }
+        Ok((compose_q112_prices(&prices)?, max_age, min_window))
+    }
+
+    /// Bind (token0, token1) to an external feed for its first deposit (0 feed clears it).
+    ///
+    /// @param scale Multiplier from the feed answer to reserve1/reserve0 in Q112
+    ///        (covers feed and token decimals, e.g. Q112 * 10^dec1 / (10^dec0 * 10^feed_dec)).
+    pub fn set_bootstrap_feed(
+        dex: &mut OakDEX,
+        token0: Address,
+        token1: Address,
+        feed: Address,
+        scale: U256,
+        tolerance_bps: U256,
+    ) -> OakResult<()> {
+        if (feed != Address::ZERO && scale.is_zero()) || tolerance_bps > as_u256(BPS) {
+            return Err(err(ERR_INVALID_FEED_CONFIG));
+        }
+        let mut outer = dex.pools.setter(token0);
+        let mut pool = outer.setter(token1);
+        pool.bootstrap_feed.set(feed);
+        pool.bootstrap_feed_scale.set(scale);
+        pool.bootstrap_tolerance_bps.set(tolerance_bps);
+        emit_bootstrap_feed_set(token0, token1, feed, scale, tolerance_bps);
+        Ok(())
+    }
+
+    /// Revert unless the first deposit's ratio is within tolerance of the pair's feed price.
+    ///
+    /// @dev No-op for pairs without a feed. Rejects zero or older than FEED_MAX_AGE_SECONDS answers.
+    pub fn check_bootstrap_price(
+        dex: &OakDEX,
+        token0: Address,
+        token1: Address,
+        amount0: U256,
+        amount1: U256,
+    ) -> OakResult<()> {
+        let (feed, scale, tolerance_bps) = {
+            let outer = dex.pools.getter(token0);
+            let pool = outer.getter(token1);
+            (pool.bootstrap_feed.get(), pool.bootstrap_feed_scale.get(), pool.bootstrap_tolerance_bps.get())
+        };
+        if feed == Address::ZERO {
+            return Ok(());
+        }
+        let (answer, updated_at) = latest_feed_answer(feed)?;
+        let now = U256::from(block::timestamp());
+        if answer.is_zero() || now.saturating_sub(updated_at) > as_u256(FEED_MAX_AGE_SECONDS) {
+            return Err(err(ERR_FEED_UNAVAILABLE));
+        }
+        let expected = answer.checked_mul(scale).ok_or_else(|| err(ERR_OVERFLOW))?;
+        if !within_feed_tolerance(amount0, amount1, expected, tolerance_bps).map_err(err)? {
+            return Err(err(ERR_BOOTSTRAP_PRICE_OUT_OF_RANGE));
+        }
+        Ok(())
+    }
+}
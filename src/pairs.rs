@@ -0,0 +1,59 @@
+//! Pair registry: one deployment, many markets.
+//!
+//! Pool state already lives per pair (`OakDEX::pools[token0][token1]`); the registry makes the
+//! pairs discoverable on-chain. Each pool is registered at creation under its pair key
+//! `keccak256(abi.encode(token0, token1))` and appended to a creation-ordered list, so
+//! `all_pairs_length` / `pair_at` enumerate every market (Uniswap V2 `allPairs`).
+
+use stylus_sdk::{
+    alloy_primitives::{Address, FixedBytes, U256},
+    crypto,
+};
+
+use crate::errors::{err, OakResult, ERR_INVALID_TOKEN, ERR_OVERFLOW, ERR_POOL_EXISTS};
+use crate::events::emit_pair_created;
+use crate::state::OakDEX;
+
+/// Pair key `keccak256(abi.encode(token0, token1))` with the tokens sorted.
+pub fn compute_pair_key(token_a: Address, token_b: Address) -> FixedBytes<32> {
+    let (token0, token1) = if token_a < token_b { (token_a, token_b) } else { (token_b, token_a) };
+    let mut encoded = [0u8; 64];
+    encoded[..32].copy_from_slice(token0.into_word().as_slice());
+    encoded[32..].copy_from_slice(token1.into_word().as_slice());
+    crypto::keccak(encoded)
+}
+
+/// Pair registry (uses OakDEX pair_* storage). Token pair must be canonical.
+pub struct PairRegistry;
+
+impl PairRegistry {
+    /// Append (token0, token1) to the registry; returns its pair key.
+    pub fn register(dex: &mut OakDEX, token0: Address, token1: Address) -> OakResult<FixedBytes<32>> {
+        let key = compute_pair_key(token0, token1);
+        if !dex.pair_index.get(key).is_zero() {
+            return Err(err(ERR_POOL_EXISTS));
+        }
+        let position = dex.pair_count.get();
+        let count = position.checked_add(U256::from(1u64)).ok_or_else(|| err(ERR_OVERFLOW))?;
+        dex.pair_token0.setter(position).set(token0);
+        dex.pair_token1.setter(position).set(token1);
+        dex.pair_index.setter(key).set(count);
+        dex.pair_count.set(count);
+        emit_pair_created(token0, token1, key, count);
+        Ok(key)
+    }
+
+    /// Position of (token0, token1) in creation order, or None when not registered.
+    pub fn position(dex: &OakDEX, token0: Address, token1: Address) -> Option<U256> {
+        let index = dex.pair_index.get(compute_pair_key(token0, token1));
+        (!index.is_zero()).then(|| index - U256::from(1u64))
+    }
+
+    /// (token0, token1) of the pair at `position`.
+    pub fn at(dex: &OakDEX, position: U256) -> OakResult<(Address, Address)> {
+        if position >= dex.pair_count.get() {
+            return Err(err(ERR_INVALID_TOKEN));
+        }
+        Ok((dex.pair_token0.get(position), dex.pair_token1.get(position)))
+    }
+}
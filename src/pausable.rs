@@ -1,12 +1,14 @@
 //! Pausable trait: global `paused` flag and checks for critical operations.
 //!
 //! No_std compatible. Use `require_not_paused` at the start of critical paths
-//! (swaps, close position, etc.). Only accounts with PAUSER_ROLE can pause/unpause.
+//! (swaps, close position, etc.). Accounts with ROLE_PAUSER can pause/unpause; ROLE_GUARDIAN
+//! can only pause.
 
-use alloc::vec::Vec;
 
 use crate::{
-    access::{pauser_role, require_role},
+    access::{require_role, ROLE_GUARDIAN, ROLE_PAUSER},
+    audit::AdminAuditLog,
+    constants::{AUDIT_ACTION_PAUSE, AUDIT_ACTION_UNPAUSE},
     errors::*,
     events::emit_pause_changed,
     state::OakDEX,
@@ -18,13 +20,13 @@ pub trait Pausable {
     fn is_paused(&self) -> bool;
 
     /// Reverts with `ERR_PAUSED` if the contract is paused (use at entry of critical functions).
-    fn require_not_paused(&self) -> Result<(), Vec<u8>>;
+    fn require_not_paused(&self) -> OakResult<()>;
 
-    /// Pauses the contract. Caller must have PAUSER_ROLE. CEI: state update before any external.
-    fn pause(&mut self) -> Result<(), Vec<u8>>;
+    /// Pauses the contract. Caller must have ROLE_PAUSER or ROLE_GUARDIAN. CEI: state update before any external.
+    fn pause(&mut self) -> OakResult<()>;
 
-    /// Unpauses the contract. Caller must have PAUSER_ROLE.
-    fn unpause(&mut self) -> Result<(), Vec<u8>>;
+    /// Unpauses the contract. Caller must have ROLE_PAUSER.
+    fn unpause(&mut self) -> OakResult<()>;
 }
 
 impl Pausable for OakDEX {
@@ -32,7 +34,7 @@ impl Pausable for OakDEX {
         self.paused.get()
     }
 
-    fn require_not_paused(&self) -> Result<(), Vec<u8>> {
+    fn require_not_paused(&self) -> OakResult<()> {
         if self.paused.get() {
             Err(err(ERR_PAUSED))
         } else {
@@ -40,15 +42,17 @@ impl Pausable for OakDEX {
         }
     }
 
-    fn pause(&mut self) -> Result<(), Vec<u8>> {
-        require_role(self, pauser_role())?;
+    fn pause(&mut self) -> OakResult<()> {
+        require_role(self, ROLE_PAUSER | ROLE_GUARDIAN)?;
+        AdminAuditLog::record(self, AUDIT_ACTION_PAUSE, &[])?;
         self.paused.set(true);
         emit_pause_changed(true);
         Ok(())
     }
 
-    fn unpause(&mut self) -> Result<(), Vec<u8>> {
-        require_role(self, pauser_role())?;
+    fn unpause(&mut self) -> OakResult<()> {
+        require_role(self, ROLE_PAUSER)?;
+        AdminAuditLog::record(self, AUDIT_ACTION_UNPAUSE, &[])?;
         self.paused.set(false);
         emit_pause_changed(false);
         Ok(())
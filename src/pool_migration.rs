@@ -0,0 +1,87 @@
+//! Pool retirement: hand a pool's order flow to a successor without stranding commitments.
+//!
+//! Governance retires a pool in favour of a successor pool with `retire_pool`. From then on the
+//! old pool takes no new pool-declared commitments, but for a grace period it keeps trading, so
+//! open commitments can still reveal against its reserves. Once the grace period ends the old
+//! pool is halted like a paused pool: swaps and deposits revert, exits stay open.
+//!
+//! A commitment declared for the old pool (`commit_swap_in_pool` and friends) can instead reveal
+//! against the successor at any time: the reveal is re-anchored transparently, its hash still
+//! being checked against the old pair key it was made for. Undeclared commitments carry no pool
+//! in storage and must reveal on the old pool within the grace period.
+
+use stylus_sdk::alloy_primitives::{FixedBytes, U256};
+
+use crate::errors::{err, OakResult, ERR_COMMIT_WRONG_POOL, ERR_POOL_RETIRED};
+use crate::events::emit_pool_retired;
+use crate::state::OakDEX;
+
+/// Pair key a commitment declared for `declared` (zero = undeclared) is verified with when it
+/// is revealed against `reveal_key`; None when the reveal is in the wrong pool.
+///
+/// @dev `successor` is the successor of `declared` (zero = not retired). A reveal in the
+///      successor verifies against the declared (old) key, which the hash is bound to.
+pub fn reveal_anchor(declared: U256, reveal_key: U256, successor: U256) -> Option<U256> {
+    if declared.is_zero() || declared == reveal_key {
+        Some(reveal_key)
+    } else if !successor.is_zero() && successor == reveal_key {
+        Some(declared)
+    } else {
+        None
+    }
+}
+
+/// Pool retirement registry (uses OakDEX pool_successor / pool_retired_after storage).
+pub struct PoolMigration;
+
+impl PoolMigration {
+    /// Successor pair key of the pool `key` (zero = not retired).
+    pub fn successor(dex: &OakDEX, key: FixedBytes<32>) -> FixedBytes<32> {
+        dex.pool_successor.get(key)
+    }
+
+    /// Last block the retired pool `key` still trades in (zero = not retired).
+    pub fn retired_after(dex: &OakDEX, key: FixedBytes<32>) -> U256 {
+        dex.pool_retired_after.get(key)
+    }
+
+    /// Whether `key` is retired and past its grace period (trades and deposits blocked).
+    pub fn halted(dex: &OakDEX, key: FixedBytes<32>, current_block: U256) -> bool {
+        let after = Self::retired_after(dex, key);
+        !after.is_zero() && current_block > after
+    }
+
+    /// Retire `key` in favour of `successor`; the old pool trades through `after_block`.
+    ///
+    /// @dev Caller validates both pools and the grace period. A retired pool is never revived.
+    pub fn retire(dex: &mut OakDEX, key: FixedBytes<32>, successor: FixedBytes<32>, after_block: U256) -> OakResult<()> {
+        if Self::successor(dex, key) != FixedBytes::ZERO {
+            return Err(err(ERR_POOL_RETIRED));
+        }
+        dex.pool_successor.setter(key).set(successor);
+        dex.pool_retired_after.setter(key).set(after_block);
+        emit_pool_retired(key, successor, after_block);
+        Ok(())
+    }
+
+    /// Reject a new commitment declared for a retired pool.
+    pub fn require_accepts_commitments(dex: &OakDEX, key: FixedBytes<32>) -> OakResult<()> {
+        if key != FixedBytes::ZERO && Self::successor(dex, key) != FixedBytes::ZERO {
+            return Err(err(ERR_POOL_RETIRED));
+        }
+        Ok(())
+    }
+
+    /// Pair key to verify a commitment declared for `declared` against, revealed in `reveal_key`.
+    pub fn anchor(dex: &OakDEX, declared: U256, reveal_key: FixedBytes<32>) -> OakResult<FixedBytes<32>> {
+        let successor = if declared.is_zero() {
+            U256::ZERO
+        } else {
+            let successor = Self::successor(dex, FixedBytes::from(declared.to_be_bytes::<32>()));
+            U256::from_be_bytes::<32>(successor.into())
+        };
+        reveal_anchor(declared, U256::from_be_bytes::<32>(reveal_key.into()), successor)
+            .map(|key| FixedBytes::from(key.to_be_bytes::<32>()))
+            .ok_or_else(|| err(ERR_COMMIT_WRONG_POOL))
+    }
+}
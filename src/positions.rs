@@ -0,0 +1,272 @@
+//! ERC-1155 position tokens: LP shares as transferable, batchable position classes.
+//!
+//! A provider wraps LP shares of a pool into a lock tier. Token id `(pair position << 64) | tier`
+//! names the class: the pool's index in the pair registry and the tier. Tier 0 is liquid; tier
+//! `k > 0` matures at block `k * POSITION_TIER_BLOCKS`, so every token of an id unlocks at the
+//! same block and the class stays fungible (what marketplaces list as a locked position).
+//!
+//! Wrapped shares stay in the holder's pool LP balance and keep earning claimable LP fees, but
+//! cannot be withdrawn, like locked shares (`LiquidityLocks::require_unlocked`). Transferring a
+//! token moves the underlying shares, after settling both sides' LP fees. `unwrap_position`
+//! turns matured tokens back into plain LP shares. LP receipts (cost basis) do not move.
+
+use alloc::vec::Vec;
+
+use stylus_sdk::{
+    alloy_primitives::{Address, FixedBytes, U256},
+    block,
+    call::{self, Call},
+    crypto,
+    types::AddressVM,
+};
+
+use crate::constants::{as_u256, POSITION_TIER_BLOCKS};
+use crate::errors::{
+    err, MathResult, OakResult, ERR_ERC1155_RECEIVER_REJECTED, ERR_INSUFFICIENT_BALANCE, ERR_INVALID_ADDRESS,
+    ERR_INVALID_POSITION_TIER, ERR_OVERFLOW, ERR_POSITION_LOCKED,
+};
+use crate::events::{emit_erc1155_approval_for_all, emit_erc1155_transfer_single};
+use crate::lp_fees::LpFees;
+use crate::pairs::PairRegistry;
+use crate::receipts::LpReceipts;
+use crate::state::OakDEX;
+
+/// ERC-165 interface id of ERC-165 itself.
+pub const ERC165_INTERFACE_ID: [u8; 4] = [0x01, 0xff, 0xc9, 0xa7];
+/// ERC-165 interface id of ERC-1155.
+pub const ERC1155_INTERFACE_ID: [u8; 4] = [0xd9, 0xb6, 0x7a, 0x26];
+
+/// Token id of the (pair registry position, tier) class; None if `tier` does not fit 64 bits.
+pub fn position_id(pair_position: U256, tier: U256) -> Option<U256> {
+    if tier >= U256::from(1u64).wrapping_shl(64) {
+        return None;
+    }
+    pair_position.checked_shl(64).map(|p| p | tier)
+}
+
+/// (pair registry position, tier) of token `id`.
+pub fn split_position_id(id: U256) -> (U256, U256) {
+    (id >> 64, id & U256::from(u64::MAX))
+}
+
+/// First block at which tier `tier` tokens can be unwrapped (0 for the liquid tier).
+pub fn tier_unlock_block(tier: U256) -> MathResult<U256> {
+    tier.checked_mul(as_u256(POSITION_TIER_BLOCKS)).ok_or(ERR_OVERFLOW)
+}
+
+/// ABI-encode `onERC1155Received(operator, from, id, value, data)` (single) or
+/// `onERC1155BatchReceived(operator, from, ids, values, data)` (batch).
+pub fn encode_erc1155_received(
+    operator: Address,
+    from: Address,
+    ids: &[U256],
+    values: &[U256],
+    data: &[u8],
+    batch: bool,
+) -> Vec<u8> {
+    let word = |v: U256| v.to_be_bytes::<32>();
+    let padding = (32 - data.len() % 32) % 32;
+    let mut call_data = Vec::new();
+    if !batch {
+        call_data.extend_from_slice(&crypto::keccak(b"onERC1155Received(address,address,uint256,uint256,bytes)")[0..4]);
+        call_data.extend_from_slice(operator.into_word().as_slice());
+        call_data.extend_from_slice(from.into_word().as_slice());
+        call_data.extend_from_slice(&word(ids[0]));
+        call_data.extend_from_slice(&word(values[0]));
+        // Dynamic `bytes` after the 5 head words.
+        call_data.extend_from_slice(&word(U256::from(160u64)));
+    } else {
+        call_data.extend_from_slice(
+            &crypto::keccak(b"onERC1155BatchReceived(address,address,uint256[],uint256[],bytes)")[0..4],
+        );
+        call_data.extend_from_slice(operator.into_word().as_slice());
+        call_data.extend_from_slice(from.into_word().as_slice());
+        // Tails: ids, values, data; each array is a length word plus its elements.
+        let ids_offset = 160u64;
+        let values_offset = ids_offset + 32 * (ids.len() as u64 + 1);
+        let data_offset = values_offset + 32 * (values.len() as u64 + 1);
+        call_data.extend_from_slice(&word(U256::from(ids_offset)));
+        call_data.extend_from_slice(&word(U256::from(values_offset)));
+        call_data.extend_from_slice(&word(U256::from(data_offset)));
+        for list in [ids, values] {
+            call_data.extend_from_slice(&word(U256::from(list.len())));
+            for v in list {
+                call_data.extend_from_slice(&word(*v));
+            }
+        }
+    }
+    call_data.extend_from_slice(&word(U256::from(data.len())));
+    call_data.extend_from_slice(data);
+    call_data.resize(call_data.len() + padding, 0);
+    call_data
+}
+
+/// ERC-1155 position tokens (uses OakDEX position_* and PoolData lp_wrapped storage).
+pub struct PositionTokens;
+
+impl PositionTokens {
+    /// Canonical pool and tier of token `id`.
+    pub fn class(dex: &OakDEX, id: U256) -> OakResult<(Address, Address, U256)> {
+        let (pair_position, tier) = split_position_id(id);
+        let (token0, token1) = PairRegistry::at(dex, pair_position)?;
+        Ok((token0, token1, tier))
+    }
+
+    /// Token id of (token0, token1) at `tier`; the pool must be registered.
+    pub fn id(dex: &OakDEX, token0: Address, token1: Address, tier: U256) -> OakResult<U256> {
+        let pair_position = PairRegistry::position(dex, token0, token1).ok_or_else(|| err(ERR_INVALID_POSITION_TIER))?;
+        position_id(pair_position, tier).ok_or_else(|| err(ERR_INVALID_POSITION_TIER))
+    }
+
+    /// `holder`'s balance of token `id`.
+    pub fn balance(dex: &OakDEX, holder: Address, id: U256) -> U256 {
+        dex.position_balances.getter(id).get(holder)
+    }
+
+    /// Whether `operator` may move all of `holder`'s tokens.
+    pub fn is_approved_for_all(dex: &OakDEX, holder: Address, operator: Address) -> bool {
+        dex.position_operators.getter(holder).get(operator)
+    }
+
+    /// Approve or revoke `operator` for all of `holder`'s tokens. Emits ApprovalForAll.
+    pub fn set_approval_for_all(dex: &mut OakDEX, holder: Address, operator: Address, approved: bool) -> OakResult<()> {
+        if operator == holder {
+            return Err(err(ERR_INVALID_ADDRESS));
+        }
+        dex.position_operators.setter(holder).setter(operator).set(approved);
+        emit_erc1155_approval_for_all(holder, operator, approved);
+        Ok(())
+    }
+
+    /// Wrap `amount` of `holder`'s free LP shares in (token0, token1) into `tier`; returns the id.
+    ///
+    /// @dev Only shares neither locked nor already wrapped can be wrapped. A locked tier must
+    ///      still be in the future. Emits TransferSingle from the zero address.
+    pub fn wrap(
+        dex: &mut OakDEX,
+        holder: Address,
+        token0: Address,
+        token1: Address,
+        amount: U256,
+        tier: U256,
+    ) -> OakResult<U256> {
+        let id = Self::id(dex, token0, token1, tier)?;
+        let now = U256::from(block::number());
+        if !tier.is_zero() && tier_unlock_block(tier).map_err(err)? <= now {
+            return Err(err(ERR_INVALID_POSITION_TIER));
+        }
+        let (balance, locked, wrapped) = {
+            let outer = dex.pools.getter(token0);
+            let pool = outer.getter(token1);
+            let lock = pool.lp_locks.getter(holder);
+            let locked = if lock.unlock_block.get() > now { lock.amount.get() } else { U256::ZERO };
+            (pool.lp_balances.get(holder), locked, pool.lp_wrapped.get(holder))
+        };
+        let free = balance.saturating_sub(locked).saturating_sub(wrapped);
+        if amount.is_zero() || amount > free {
+            return Err(err(ERR_INSUFFICIENT_BALANCE));
+        }
+        dex.pools.setter(token0).setter(token1).lp_wrapped.setter(holder).set(wrapped + amount);
+        let held = Self::balance(dex, holder, id).checked_add(amount).ok_or_else(|| err(ERR_OVERFLOW))?;
+        dex.position_balances.setter(id).setter(holder).set(held);
+        emit_erc1155_transfer_single(holder, Address::ZERO, holder, id, amount);
+        Ok(id)
+    }
+
+    /// Turn `amount` of `holder`'s matured `id` tokens back into plain LP shares.
+    pub fn unwrap(dex: &mut OakDEX, holder: Address, id: U256, amount: U256) -> OakResult<()> {
+        let (token0, token1, tier) = Self::class(dex, id)?;
+        if tier_unlock_block(tier).map_err(err)? > U256::from(block::number()) {
+            return Err(err(ERR_POSITION_LOCKED));
+        }
+        let held = Self::balance(dex, holder, id);
+        if amount.is_zero() || amount > held {
+            return Err(err(ERR_INSUFFICIENT_BALANCE));
+        }
+        dex.position_balances.setter(id).setter(holder).set(held - amount);
+        let mut outer = dex.pools.setter(token0);
+        let mut pool = outer.setter(token1);
+        let wrapped = pool.lp_wrapped.get(holder);
+        pool.lp_wrapped.setter(holder).set(wrapped.saturating_sub(amount));
+        emit_erc1155_transfer_single(holder, holder, Address::ZERO, id, amount);
+        Ok(())
+    }
+
+    /// Move `amount` of token `id`, and the LP shares under it, from `from` to `to`.
+    ///
+    /// @dev Both sides' LP fees are settled first, so each keeps what accrued while holding.
+    ///      The LP deposit receipt (cost basis, entry index) follows the shares.
+    ///      No event and no receiver check; see `transfer_single` / the batch entrypoint.
+    pub fn move_balance(dex: &mut OakDEX, from: Address, to: Address, id: U256, amount: U256) -> OakResult<()> {
+        if to == Address::ZERO {
+            return Err(err(ERR_INVALID_ADDRESS));
+        }
+        let held = Self::balance(dex, from, id);
+        if amount > held {
+            return Err(err(ERR_INSUFFICIENT_BALANCE));
+        }
+        if from == to || amount.is_zero() {
+            return Ok(());
+        }
+        let (token0, token1, _) = Self::class(dex, id)?;
+        LpFees::settle(dex, token0, token1, from)?;
+        LpFees::settle(dex, token0, token1, to)?;
+        let to_held = Self::balance(dex, to, id).checked_add(amount).ok_or_else(|| err(ERR_OVERFLOW))?;
+        dex.position_balances.setter(id).setter(from).set(held - amount);
+        dex.position_balances.setter(id).setter(to).set(to_held);
+        let mut outer = dex.pools.setter(token0);
+        let mut pool = outer.setter(token1);
+        let (from_lp, to_lp) = (pool.lp_balances.get(from), pool.lp_balances.get(to));
+        let (from_wrapped, to_wrapped) = (pool.lp_wrapped.get(from), pool.lp_wrapped.get(to));
+        let to_lp = to_lp.checked_add(amount).ok_or_else(|| err(ERR_OVERFLOW))?;
+        let to_wrapped = to_wrapped.checked_add(amount).ok_or_else(|| err(ERR_OVERFLOW))?;
+        pool.lp_balances.setter(from).set(from_lp.saturating_sub(amount));
+        pool.lp_balances.setter(to).set(to_lp);
+        pool.lp_wrapped.setter(from).set(from_wrapped.saturating_sub(amount));
+        pool.lp_wrapped.setter(to).set(to_wrapped);
+        let to_before = to_lp - amount;
+        LpReceipts::record_transfer(dex, token0, token1, from, to, amount, from_lp, to_before)
+    }
+
+    /// Move one class and emit TransferSingle.
+    pub fn transfer_single(
+        dex: &mut OakDEX,
+        operator: Address,
+        from: Address,
+        to: Address,
+        id: U256,
+        amount: U256,
+    ) -> OakResult<()> {
+        Self::move_balance(dex, from, to, id, amount)?;
+        emit_erc1155_transfer_single(operator, from, to, id, amount);
+        Ok(())
+    }
+
+    /// Run `to`'s ERC-1155 receiver hook when `to` is a contract; it must return its selector.
+    ///
+    /// @dev External call: the caller holds the re-entrancy lock and has finished all writes.
+    pub fn check_receiver(
+        operator: Address,
+        from: Address,
+        to: Address,
+        ids: &[U256],
+        values: &[U256],
+        data: &[u8],
+        batch: bool,
+    ) -> OakResult<()> {
+        if !to.has_code() {
+            return Ok(());
+        }
+        let call_data = encode_erc1155_received(operator, from, ids, values, data, batch);
+        let ret = call::call(Call::new(), to, &call_data).map_err(|_| err(ERR_ERC1155_RECEIVER_REJECTED))?;
+        if ret.len() < 32 || ret[0..4] != call_data[0..4] {
+            return Err(err(ERR_ERC1155_RECEIVER_REJECTED));
+        }
+        Ok(())
+    }
+
+    /// ERC-165: ERC-165 and ERC-1155 are supported.
+    pub fn supports_interface(interface_id: FixedBytes<4>) -> bool {
+        interface_id.0 == ERC165_INTERFACE_ID || interface_id.0 == ERC1155_INTERFACE_ID
+    }
+}
@@ -0,0 +1,262 @@
+//! LP deposit receipts: per-user cost basis, fee earnings and impermanent-loss estimate.
+//!
+//! Each deposit records the tokens contributed, the block, the post-deposit reserves and the
+//! pool's liquidity index `sqrt(reserve0 * reserve1) * 1e18 / lp_supply`. Fees compounded into
+//! the reserves grow the index, so the share of a position's current value attributable to them
+//! is `1 - entry_index / index_now`; the LP share of swap fees is paid out separately
+//! (`pending_lp_fees`). Withdrawals reduce the cost basis pro-rata; an ERC-1155 position
+//! transfer moves the same pro-rata slice of it, with its entry index, to the receiver.
+
+use stylus_sdk::{alloy_primitives::{Address, U256}, block};
+
+use crate::constants::BPS;
+use crate::errors::{err, MathResult, OakResult, ERR_DIVISION_BY_ZERO, ERR_OVERFLOW};
+use crate::logic::u256_sqrt;
+use crate::state::OakDEX;
+
+/// (deposited0, deposited1, entry_block, value0, value1, fees0, fees1, il_bps).
+pub type LpReceiptView = (U256, U256, U256, U256, U256, U256, U256, U256);
+
+/// Fixed-point scale of the liquidity index.
+pub const LIQUIDITY_INDEX_SCALE: u128 = 1_000_000_000_000_000_000;
+
+/// Liquidity index `sqrt(reserve0 * reserve1) * 1e18 / lp_supply` (zero for an empty pool).
+pub fn liquidity_index(reserve0: U256, reserve1: U256, lp_supply: U256) -> MathResult<U256> {
+    if lp_supply.is_zero() {
+        return Ok(U256::ZERO);
+    }
+    let k = reserve0.checked_mul(reserve1).ok_or(ERR_OVERFLOW)?;
+    u256_sqrt(k)
+        .checked_mul(U256::from(LIQUIDITY_INDEX_SCALE))
+        .ok_or(ERR_OVERFLOW)?
+        .checked_div(lp_supply)
+        .ok_or(ERR_DIVISION_BY_ZERO)
+}
+
+/// `amount * part / whole`: the share of a cost basis carried by `part` of `whole` LP.
+pub fn pro_rata(amount: U256, part: U256, whole: U256) -> MathResult<U256> {
+    amount.checked_mul(part).ok_or(ERR_OVERFLOW)?.checked_div(whole).ok_or(ERR_DIVISION_BY_ZERO)
+}
+
+/// LP-weighted entry index of `weight_a` LP entered at `index_a` merged with `weight_b` at `index_b`.
+pub fn blended_index(index_a: U256, weight_a: U256, index_b: U256, weight_b: U256) -> MathResult<U256> {
+    let weighted_a = index_a.checked_mul(weight_a).ok_or(ERR_OVERFLOW)?;
+    let weighted_b = index_b.checked_mul(weight_b).ok_or(ERR_OVERFLOW)?;
+    weighted_a
+        .checked_add(weighted_b)
+        .ok_or(ERR_OVERFLOW)?
+        .checked_div(weight_a.checked_add(weight_b).ok_or(ERR_OVERFLOW)?)
+        .ok_or(ERR_DIVISION_BY_ZERO)
+}
+
+/// Impermanent loss in bps: shortfall of the position value (ex-fees) against holding the
+/// deposited tokens, both valued in token1 at the current reserve ratio. Zero if no loss.
+pub fn impermanent_loss_bps(
+    deposited0: U256,
+    deposited1: U256,
+    value0: U256,
+    value1: U256,
+    reserve0: U256,
+    reserve1: U256,
+) -> MathResult<U256> {
+    if reserve0.is_zero() {
+        return Ok(U256::ZERO);
+    }
+    let in_token1 = |amount0: U256, amount1: U256| -> MathResult<U256> {
+        amount0
+            .checked_mul(reserve1)
+            .ok_or(ERR_OVERFLOW)?
+            .checked_div(reserve0)
+            .ok_or(ERR_DIVISION_BY_ZERO)?
+            .checked_add(amount1)
+            .ok_or(ERR_OVERFLOW)
+    };
+    let hodl = in_token1(deposited0, deposited1)?;
+    let lp = in_token1(value0, value1)?;
+    if hodl.is_zero() || lp >= hodl {
+        return Ok(U256::ZERO);
+    }
+    (hodl - lp)
+        .checked_mul(U256::from(BPS))
+        .ok_or(ERR_OVERFLOW)?
+        .checked_div(hodl)
+        .ok_or(ERR_DIVISION_BY_ZERO)
+}
+
+/// LP receipts (uses PoolData lp_receipts storage). Token pair must be canonical.
+pub struct LpReceipts;
+
+impl LpReceipts {
+    /// Record a deposit of (amount0, amount1) that minted `minted` LP on top of `balance_before`.
+    /// Reserves and supply are post-deposit. Entry index is LP-weighted across deposits.
+    #[allow(clippy::too_many_arguments)]
+    pub fn record_deposit(
+        dex: &mut OakDEX,
+        token0: Address,
+        token1: Address,
+        provider: Address,
+        amount0: U256,
+        amount1: U256,
+        minted: U256,
+        balance_before: U256,
+    ) -> OakResult<()> {
+        let mut outer = dex.pools.setter(token0);
+        let mut pool = outer.setter(token1);
+        let (reserve0, reserve1) = (pool.reserve0.get(), pool.reserve1.get());
+        let index = liquidity_index(reserve0, reserve1, pool.lp_total_supply.get())?;
+        let mut receipt = pool.lp_receipts.setter(provider);
+
+        let entry_index = blended_index(receipt.entry_index.get(), balance_before, index, minted)?;
+
+        let deposited0 = receipt.deposited0.get().checked_add(amount0).ok_or_else(|| err(ERR_OVERFLOW))?;
+        let deposited1 = receipt.deposited1.get().checked_add(amount1).ok_or_else(|| err(ERR_OVERFLOW))?;
+        receipt.deposited0.set(deposited0);
+        receipt.deposited1.set(deposited1);
+        receipt.entry_block.set(U256::from(block::number()));
+        receipt.entry_reserve0.set(reserve0);
+        receipt.entry_reserve1.set(reserve1);
+        receipt.entry_index.set(entry_index);
+        Ok(())
+    }
+
+    /// Reduce cost basis pro-rata after burning `lp_amount` out of `balance_before`.
+    pub fn record_withdrawal(
+        dex: &mut OakDEX,
+        token0: Address,
+        token1: Address,
+        provider: Address,
+        lp_amount: U256,
+        balance_before: U256,
+    ) -> OakResult<()> {
+        if balance_before.is_zero() {
+            return Ok(());
+        }
+        let mut outer = dex.pools.setter(token0);
+        let mut pool = outer.setter(token1);
+        let mut receipt = pool.lp_receipts.setter(provider);
+        let reduce = |deposited: U256| -> OakResult<U256> {
+            Ok(deposited.saturating_sub(pro_rata(deposited, lp_amount, balance_before)?))
+        };
+        let deposited0 = reduce(receipt.deposited0.get())?;
+        let deposited1 = reduce(receipt.deposited1.get())?;
+        receipt.deposited0.set(deposited0);
+        receipt.deposited1.set(deposited1);
+        if lp_amount >= balance_before {
+            receipt.entry_index.set(U256::ZERO);
+        }
+        Ok(())
+    }
+
+    /// Move the cost basis behind `lp_amount` of `from`'s `from_before` LP to `to`, who held
+    /// `to_before`. The receiver's entry index is LP-weighted with the sender's, and a receiver
+    /// without a position takes over the sender's entry block and reserves.
+    #[allow(clippy::too_many_arguments)]
+    pub fn record_transfer(
+        dex: &mut OakDEX,
+        token0: Address,
+        token1: Address,
+        from: Address,
+        to: Address,
+        lp_amount: U256,
+        from_before: U256,
+        to_before: U256,
+    ) -> OakResult<()> {
+        if from_before.is_zero() || lp_amount.is_zero() {
+            return Ok(());
+        }
+        let mut outer = dex.pools.setter(token0);
+        let mut pool = outer.setter(token1);
+        let (moved0, moved1, from_index, from_block, from_reserve0, from_reserve1) = {
+            let receipt = pool.lp_receipts.getter(from);
+            (
+                pro_rata(receipt.deposited0.get(), lp_amount, from_before).map_err(err)?,
+                pro_rata(receipt.deposited1.get(), lp_amount, from_before).map_err(err)?,
+                receipt.entry_index.get(),
+                receipt.entry_block.get(),
+                receipt.entry_reserve0.get(),
+                receipt.entry_reserve1.get(),
+            )
+        };
+        {
+            let mut sender = pool.lp_receipts.setter(from);
+            let deposited0 = sender.deposited0.get().saturating_sub(moved0);
+            let deposited1 = sender.deposited1.get().saturating_sub(moved1);
+            sender.deposited0.set(deposited0);
+            sender.deposited1.set(deposited1);
+            if lp_amount >= from_before {
+                sender.entry_index.set(U256::ZERO);
+            }
+        }
+        let mut receiver = pool.lp_receipts.setter(to);
+        let entry_index = blended_index(receiver.entry_index.get(), to_before, from_index, lp_amount)?;
+        let deposited0 = receiver.deposited0.get().checked_add(moved0).ok_or_else(|| err(ERR_OVERFLOW))?;
+        let deposited1 = receiver.deposited1.get().checked_add(moved1).ok_or_else(|| err(ERR_OVERFLOW))?;
+        receiver.deposited0.set(deposited0);
+        receiver.deposited1.set(deposited1);
+        receiver.entry_index.set(entry_index);
+        if to_before.is_zero() {
+            receiver.entry_block.set(from_block);
+            receiver.entry_reserve0.set(from_reserve0);
+            receiver.entry_reserve1.set(from_reserve1);
+        }
+        Ok(())
+    }
+
+    /// View: receipt of `user` (see `LpReceiptView`).
+    ///
+    /// `value*` is the current pro-rata claim, `fees*` the part of it earned from swap fees,
+    /// `il_bps` the impermanent loss of the ex-fee value against holding the deposit.
+    pub fn position(
+        dex: &OakDEX,
+        user: Address,
+        token0: Address,
+        token1: Address,
+    ) -> OakResult<LpReceiptView> {
+        let outer = dex.pools.getter(token0);
+        let pool = outer.getter(token1);
+        let receipt = pool.lp_receipts.getter(user);
+        let (reserve0, reserve1, supply) =
+            (pool.reserve0.get(), pool.reserve1.get(), pool.lp_total_supply.get());
+        let balance = pool.lp_balances.getter(user).get();
+        let (deposited0, deposited1) = (receipt.deposited0.get(), receipt.deposited1.get());
+        let entry_block = receipt.entry_block.get();
+        if supply.is_zero() || balance.is_zero() {
+            return Ok((deposited0, deposited1, entry_block, U256::ZERO, U256::ZERO, U256::ZERO, U256::ZERO, U256::ZERO));
+        }
+
+        let share = |reserve: U256| -> OakResult<U256> {
+            reserve
+                .checked_mul(balance)
+                .ok_or_else(|| err(ERR_OVERFLOW))?
+                .checked_div(supply)
+                .ok_or_else(|| err(ERR_DIVISION_BY_ZERO))
+        };
+        let (value0, value1) = (share(reserve0)?, share(reserve1)?);
+
+        let index_now = liquidity_index(reserve0, reserve1, supply)?;
+        let entry_index = receipt.entry_index.get().min(index_now);
+        let ex_fees = |value: U256| -> OakResult<U256> {
+            if index_now.is_zero() {
+                return Ok(value);
+            }
+            value
+                .checked_mul(entry_index)
+                .ok_or_else(|| err(ERR_OVERFLOW))?
+                .checked_div(index_now)
+                .ok_or_else(|| err(ERR_DIVISION_BY_ZERO))
+        };
+        let (principal0, principal1) = (ex_fees(value0)?, ex_fees(value1)?);
+        let il_bps = impermanent_loss_bps(deposited0, deposited1, principal0, principal1, reserve0, reserve1)?;
+
+        Ok((
+            deposited0,
+            deposited1,
+            entry_block,
+            value0,
+            value1,
+            value0 - principal0,
+            value1 - principal1,
+            il_bps,
+        ))
+    }
+}
@@ -0,0 +1,158 @@
+//! Deterministic replay of a pool's state transitions for post-incident analysis.
+//!
+//! Off-chain tooling decodes a pool's history (swaps, deposits, withdrawals, syncs) into
+//! `ReplayStep`s, each carrying what the chain recorded after it: reserves, LP supply and the
+//! amounts paid or minted. `replay` re-executes the steps from a starting snapshot with the same
+//! pure math the contract uses (`get_amount_out_with_fee`, `compute_fee_split`,
+//! `fee_per_share_growth`, `compute_liquidity_minted`, `compute_liquidity_burned`) and stops at
+//! the first step whose recorded outcome differs from the recomputed one. Reserve drift is then
+//! pinned to a single transaction instead of a range of blocks.
+//!
+//! Everything here is pure: no storage, no host calls, so it runs the same on host and wasm.
+
+use alloc::{boxed::Box, vec::Vec};
+
+use stylus_sdk::alloy_primitives::U256;
+
+use crate::constants::{as_u256, MINIMUM_LIQUIDITY};
+use crate::errors::{MathResult, ERR_DUST_AMOUNT, ERR_INSUFFICIENT_LIQUIDITY, ERR_LIQUIDITY_OVERFLOW, ERR_OVERFLOW};
+use crate::logic::{compute_fee_split, compute_liquidity_burned, compute_liquidity_minted, get_amount_out_with_fee};
+use crate::lp_fees::fee_per_share_growth;
+
+/// Pool state a step starts from or ends in (canonical token order).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PoolSnapshot {
+    pub reserve0: U256,
+    pub reserve1: U256,
+    pub lp_supply: U256,
+}
+
+/// One decoded call or event that moved the pool.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReplayAction {
+    /// Swap of `amount_in` as received by the pool, at the total fee `fee_bps` it was charged.
+    Swap { zero_for_one: bool, amount_in: U256, fee_bps: U256 },
+    /// Deposit of the amounts actually taken into the pool.
+    AddLiquidity { amount0: U256, amount1: U256 },
+    /// Burn of `share` LP tokens.
+    RemoveLiquidity { share: U256 },
+    /// Reserves overwritten from balances (`sync`), or any transition recorded only as its result.
+    Sync { reserve0: U256, reserve1: U256 },
+}
+
+/// Result of a step: the pool after it, and what it paid out or minted.
+///
+/// @dev `output` is (amount_out, 0) for swaps, (LP minted, 0) for deposits, (amount0, amount1)
+///      for withdrawals and zero for syncs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Transition {
+    pub after: PoolSnapshot,
+    pub output: (U256, U256),
+}
+
+/// A decoded step and the outcome the chain recorded for it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReplayStep {
+    pub action: ReplayAction,
+    pub observed: Transition,
+}
+
+/// First step whose recorded outcome differs from the replayed one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Divergence {
+    /// Index into the replayed steps.
+    pub step: usize,
+    /// State the step started from (replayed, so identical to the chain up to here).
+    pub before: PoolSnapshot,
+    /// What the math produces, or the revert code when the contract would have rejected the step.
+    pub expected: Result<Transition, &'static [u8]>,
+    pub observed: Transition,
+}
+
+/// Re-execute `action` on `state` with the contract's pure math.
+///
+/// @dev Mirrors the accounting of the swap, deposit and withdrawal paths: the swap fee's treasury,
+///      gas rebate, buyback and accumulator-distributed LP shares leave the reserves, and the first
+///      deposit locks MINIMUM_LIQUIDITY shares. Transfer effects (fee-on-transfer input, hooks) are
+///      expected to be folded into the decoded amounts already.
+pub fn apply(state: PoolSnapshot, action: &ReplayAction) -> MathResult<Transition> {
+    match *action {
+        ReplayAction::Swap { zero_for_one, amount_in, fee_bps } => {
+            let (reserve_in, reserve_out) =
+                if zero_for_one { (state.reserve0, state.reserve1) } else { (state.reserve1, state.reserve0) };
+            let amount_out = get_amount_out_with_fee(amount_in, reserve_in, reserve_out, fee_bps)?;
+            if amount_out.is_zero() {
+                return Err(ERR_DUST_AMOUNT);
+            }
+            let (_, treasury_fee, lp_fee, buyback_fee, gas_rebate) = compute_fee_split(amount_in, fee_bps)?;
+            let (_, lp_distributed) = fee_per_share_growth(lp_fee, state.lp_supply)?;
+            let to_pool_in = amount_in
+                .checked_sub(treasury_fee)
+                .and_then(|x| x.checked_sub(gas_rebate))
+                .and_then(|x| x.checked_sub(buyback_fee))
+                .and_then(|x| x.checked_sub(lp_distributed))
+                .ok_or(ERR_OVERFLOW)?;
+            let new_reserve_in = reserve_in.checked_add(to_pool_in).ok_or(ERR_OVERFLOW)?;
+            let new_reserve_out = reserve_out.checked_sub(amount_out).ok_or(ERR_INSUFFICIENT_LIQUIDITY)?;
+            let (reserve0, reserve1) =
+                if zero_for_one { (new_reserve_in, new_reserve_out) } else { (new_reserve_out, new_reserve_in) };
+            Ok(Transition { after: PoolSnapshot { reserve0, reserve1, ..state }, output: (amount_out, U256::ZERO) })
+        }
+        ReplayAction::AddLiquidity { amount0, amount1 } => {
+            let minted = compute_liquidity_minted(amount0, amount1, state.reserve0, state.reserve1, state.lp_supply)?;
+            let locked = if state.lp_supply.is_zero() { as_u256(MINIMUM_LIQUIDITY) } else { U256::ZERO };
+            let after = PoolSnapshot {
+                reserve0: state.reserve0.checked_add(amount0).ok_or(ERR_OVERFLOW)?,
+                reserve1: state.reserve1.checked_add(amount1).ok_or(ERR_OVERFLOW)?,
+                lp_supply: state
+                    .lp_supply
+                    .checked_add(locked)
+                    .and_then(|s| s.checked_add(minted))
+                    .ok_or(ERR_LIQUIDITY_OVERFLOW)?,
+            };
+            Ok(Transition { after, output: (minted, U256::ZERO) })
+        }
+        ReplayAction::RemoveLiquidity { share } => {
+            let lp_supply = state.lp_supply.checked_sub(share).ok_or(ERR_INSUFFICIENT_LIQUIDITY)?;
+            if lp_supply < as_u256(MINIMUM_LIQUIDITY) {
+                return Err(ERR_INSUFFICIENT_LIQUIDITY);
+            }
+            let (amount0, amount1) = compute_liquidity_burned(share, state.reserve0, state.reserve1, state.lp_supply)?;
+            let after = PoolSnapshot { reserve0: state.reserve0 - amount0, reserve1: state.reserve1 - amount1, lp_supply };
+            Ok(Transition { after, output: (amount0, amount1) })
+        }
+        ReplayAction::Sync { reserve0, reserve1 } => {
+            Ok(Transition { after: PoolSnapshot { reserve0, reserve1, ..state }, output: (U256::ZERO, U256::ZERO) })
+        }
+    }
+}
+
+/// Replay `steps` from `start`; the final state, or the first divergence.
+///
+/// @notice Each step runs from the replayed state, which equals the recorded one for every step
+///         before the divergence, so the reported step is where the chain first went off the math.
+pub fn replay(start: PoolSnapshot, steps: &[ReplayStep]) -> Result<PoolSnapshot, Box<Divergence>> {
+    let mut state = start;
+    for (step, recorded) in steps.iter().enumerate() {
+        let expected = apply(state, &recorded.action);
+        if expected != Ok(recorded.observed) {
+            return Err(Box::new(Divergence { step, before: state, expected, observed: recorded.observed }));
+        }
+        state = recorded.observed.after;
+    }
+    Ok(state)
+}
+
+/// Recompute the outcome of every action from `start`, as a replay of them expects to find it.
+///
+/// @dev Stops at the first action the contract would reject and returns the transitions so far.
+pub fn expected_transitions(start: PoolSnapshot, actions: &[ReplayAction]) -> Vec<Transition> {
+    let mut state = start;
+    let mut out = Vec::with_capacity(actions.len());
+    for action in actions {
+        let Ok(transition) = apply(state, action) else { break };
+        state = transition.after;
+        out.push(transition);
+    }
+    out
+}
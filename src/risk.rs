@@ -0,0 +1,95 @@
+//! Token risk registry: one governance-maintained policy per token.
+//!
+//! Each token may carry a cap on its reserve in any single pool, a ban on flash borrowing, an
+//! oracle requirement and a hooked-token flag. Pool creation, swaps, deposits and flash loans
+//! consult the registry here instead of keeping their own per-token checks. Tokens without an
+//! entry (all fields zero) are unrestricted, so existing pools behave as before.
+
+use stylus_sdk::alloy_primitives::{Address, U256};
+
+use crate::errors::{
+    err, OakResult, ERR_FLASH_BORROW_DISABLED, ERR_TOKEN_EXPOSURE_EXCEEDED, ERR_TOKEN_HOOKED, ERR_TOKEN_ORACLE_REQUIRED,
+};
+use crate::events::emit_token_risk_set;
+use crate::state::OakDEX;
+
+/// Revert with TOKEN_EXPOSURE_EXCEEDED unless a pool reserve of `reserve` stays within an
+/// exposure cap of `cap` (zero = uncapped).
+pub fn check_exposure(cap: U256, reserve: U256) -> OakResult<()> {
+    if !cap.is_zero() && reserve > cap {
+        return Err(err(ERR_TOKEN_EXPOSURE_EXCEEDED));
+    }
+    Ok(())
+}
+
+/// Token risk registry (uses OakDEX `token_risk` storage).
+pub struct TokenRisk;
+
+impl TokenRisk {
+    /// (max_pool_exposure, flash_borrow_allowed, oracle_required, hooked) of `token`.
+    pub fn params(dex: &OakDEX, token: Address) -> (U256, bool, bool, bool) {
+        let risk = dex.token_risk.getter(token);
+        (
+            risk.max_pool_exposure.get(),
+            !risk.flash_borrow_disabled.get(),
+            risk.oracle_required.get(),
+            risk.hooked.get(),
+        )
+    }
+
+    /// Replace the policy of `token` (address(0) is native ETH).
+    pub fn set(
+        dex: &mut OakDEX,
+        token: Address,
+        max_pool_exposure: U256,
+        flash_borrow_allowed: bool,
+        oracle_required: bool,
+        hooked: bool,
+    ) {
+        let mut risk = dex.token_risk.setter(token);
+        risk.max_pool_exposure.set(max_pool_exposure);
+        risk.flash_borrow_disabled.set(!flash_borrow_allowed);
+        risk.oracle_required.set(oracle_required);
+        risk.hooked.set(hooked);
+        emit_token_risk_set(token, max_pool_exposure, flash_borrow_allowed, oracle_required, hooked);
+    }
+
+    /// Whether either token of the pair is registered as hooked.
+    pub fn pair_hooked(dex: &OakDEX, token0: Address, token1: Address) -> bool {
+        dex.token_risk.getter(token0).hooked.get() || dex.token_risk.getter(token1).hooked.get()
+    }
+
+    /// Pool creation policy for (token0, token1); returns whether the pool must run hooked.
+    ///
+    /// @dev Oracle-required tokens only pool with a bootstrap feed already bound to the pair, so
+    ///      the first deposit is priced against it (see `oracle::PoolOracle::check_bootstrap_price`).
+    pub fn check_pool_creation(dex: &OakDEX, token0: Address, token1: Address, hooked: bool) -> OakResult<bool> {
+        let oracle_required =
+            dex.token_risk.getter(token0).oracle_required.get() || dex.token_risk.getter(token1).oracle_required.get();
+        if oracle_required && dex.pools.getter(token0).getter(token1).bootstrap_feed.get() == Address::ZERO {
+            return Err(err(ERR_TOKEN_ORACLE_REQUIRED));
+        }
+        Ok(hooked || Self::pair_hooked(dex, token0, token1))
+    }
+
+    /// Revert if a pool of hooked-registered tokens would be switched to unhooked.
+    pub fn require_hook_allowed(dex: &OakDEX, token0: Address, token1: Address, hooked: bool) -> OakResult<()> {
+        if !hooked && Self::pair_hooked(dex, token0, token1) {
+            return Err(err(ERR_TOKEN_HOOKED));
+        }
+        Ok(())
+    }
+
+    /// Revert if a pool reserve of `reserve` in `token` exceeds the token's exposure cap.
+    pub fn require_within_exposure(dex: &OakDEX, token: Address, reserve: U256) -> OakResult<()> {
+        check_exposure(dex.token_risk.getter(token).max_pool_exposure.get(), reserve)
+    }
+
+    /// Revert if `token` may not be flash-borrowed.
+    pub fn require_flash_borrow_allowed(dex: &OakDEX, token: Address) -> OakResult<()> {
+        if dex.token_risk.getter(token).flash_borrow_disabled.get() {
+            return Err(err(ERR_FLASH_BORROW_DISABLED));
+        }
+        Ok(())
+    }
+}
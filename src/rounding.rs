@@ -0,0 +1,64 @@
+//! Rounding direction for the protocol's integer divisions.
+//!
+//! Every division that decides how much value leaves or enters a pool names its direction at the
+//! call site: amounts paid out (swap output, LP shares minted, withdrawals, crossed batch legs,
+//! LP fee claims) round down, amounts charged (swap input quotes) round up. A wei lost to
+//! rounding therefore always stays with the pool, never with the caller.
+//!
+//! With the `rounding-audit` feature, `set_inverted(true)` flips every direction so the same
+//! inputs can be evaluated both ways. `tests/rounding_audit.rs` uses it to check, over seeded
+//! random inputs, that the chosen direction is never the less favorable one for the protocol:
+//!
+//!   cargo test --features rounding-audit --test rounding_audit
+//!
+//! Fee amounts (`amount * fee_bps / FEE_DENOMINATOR`) are quotes of a rate, not settlements, and
+//! keep the Uniswap-style floor; they are not routed through here.
+
+use stylus_sdk::alloy_primitives::U256;
+
+use crate::errors::{MathResult, ERR_DIVISION_BY_ZERO};
+
+/// Direction of an integer division.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    /// Floor: for amounts the protocol pays out.
+    Down,
+    /// Ceiling: for amounts the protocol charges.
+    Up,
+}
+
+#[cfg(feature = "rounding-audit")]
+static INVERTED: core::sync::atomic::AtomicBool = core::sync::atomic::AtomicBool::new(false);
+
+/// Flip every rounding direction (audit builds only).
+#[cfg(feature = "rounding-audit")]
+pub fn set_inverted(inverted: bool) {
+    INVERTED.store(inverted, core::sync::atomic::Ordering::SeqCst);
+}
+
+impl Rounding {
+    /// The direction actually applied: `self`, or its opposite while an audit run has inverted it.
+    pub fn effective(self) -> Rounding {
+        #[cfg(feature = "rounding-audit")]
+        if INVERTED.load(core::sync::atomic::Ordering::SeqCst) {
+            return match self {
+                Rounding::Down => Rounding::Up,
+                Rounding::Up => Rounding::Down,
+            };
+        }
+        self
+    }
+}
+
+/// `numerator / denominator` rounded in `rounding`'s direction.
+pub fn div(numerator: U256, denominator: U256, rounding: Rounding) -> MathResult<U256> {
+    if denominator.is_zero() {
+        return Err(ERR_DIVISION_BY_ZERO);
+    }
+    let quotient = numerator / denominator;
+    // quotient < U256::MAX whenever there is a remainder (denominator > 1), so +1 cannot overflow.
+    if rounding.effective() == Rounding::Up && !(numerator % denominator).is_zero() {
+        return Ok(quotient + U256::from(1u64));
+    }
+    Ok(quotient)
+}
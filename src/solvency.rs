@@ -0,0 +1,139 @@
+//! Fee accounting reconciliation: one-call solvency check per token.
+//!
+//! Everything the contract owes in a token is tracked in storage: pool reserves plus the
+//! accrual buckets that fees and escrows are swept into (treasury, buyback, output escrow,
+//! commitment bonds, creator shares, batch escrow, position margin, stake, limit orders, LP fees,
+//! gas rebates, referral fees).
+//! `reconcile` compares their sum with the token balance the contract actually holds and
+//! reports the surplus (donations, rounding dust) or the deficit (an accounting bug or a loss).
+//!
+//! `reconcile` takes the pools holding the token by their counterpart tokens, so one call stays
+//! bounded; monitoring enumerates them with `all_pairs_length` / `pair_at`.
+//! `position` instead uses the running per-token reserve total kept by `track_reserve`, which
+//! is what `sync` / `skim` settle against. That surplus is per token, not per pool, so both are
+//! governance actions.
+
+use stylus_sdk::{alloy_primitives::{Address, U256}, contract};
+
+use crate::constants::MAX_RECONCILE_POOLS;
+use crate::errors::{
+    err, OakResult, ERR_INVALID_RECONCILE_POOL, ERR_OVERFLOW, ERR_TOO_MANY_ITEMS,
+};
+use crate::state::OakDEX;
+use crate::token::balance_of;
+
+/// (surplus, deficit) of `balance` against `expected`; at most one is non-zero.
+pub fn balance_discrepancy(balance: U256, expected: U256) -> (U256, U256) {
+    (balance.saturating_sub(expected), expected.saturating_sub(balance))
+}
+
+/// A pool reserve after `sync` credits the token `surplus` to it.
+///
+/// @dev Only a surplus is ever booked: a deficit is summed over every pool holding the token, so
+///      writing it off one pool would charge its LPs for a shortfall that may belong to another.
+pub fn synced_reserve(reserve: U256, surplus: U256) -> OakResult<U256> {
+    reserve.checked_add(surplus).ok_or_else(|| err(ERR_OVERFLOW))
+}
+
+/// Shift a running total from including `old` to including `new`.
+fn shifted(total: U256, old: U256, new: U256) -> OakResult<U256> {
+    total
+        .checked_add(new)
+        .and_then(|t| t.checked_sub(old))
+        .ok_or_else(|| err(ERR_OVERFLOW))
+}
+
+/// Keep `reserve_totals[token]` in step with one pool reserve of `token` moving `old -> new`.
+pub fn track_reserve(dex: &mut OakDEX, token: Address, old: U256, new: U256) -> OakResult<()> {
+    let total = shifted(dex.reserve_totals.get(token), old, new)?;
+    dex.reserve_totals.setter(token).set(total);
+    Ok(())
+}
+
+/// Keep `order_escrowed[token]` in step with limit-order escrow moving `old -> new`.
+pub fn track_order_escrow(dex: &mut OakDEX, token: Address, old: U256, new: U256) -> OakResult<()> {
+    let total = shifted(dex.order_escrowed.get(token), old, new)?;
+    dex.order_escrowed.setter(token).set(total);
+    Ok(())
+}
+
+/// Fee and escrow reconciliation (read-only over OakDEX storage).
+pub struct FeeReconciliation;
+
+impl FeeReconciliation {
+    /// Sum of the accrual and escrow buckets held in `token` outside pool reserves.
+    pub fn accrued_liabilities(dex: &OakDEX, token: Address) -> OakResult<U256> {
+        let mut total = U256::ZERO;
+        let buckets = [
+            dex.treasury_balance.get(token),
+            dex.buyback_balance.get(token),
+            dex.claimable_total.get(token),
+            dex.commit_bonds_held.get(token),
+            dex.creator_claimable_total.get(token),
+            dex.batch_escrowed.get(token),
+            dex.position_margin_balance.get(token),
+            dex.order_escrowed.get(token),
+            dex.lp_fees_held.get(token),
+            dex.gas_rebates_held.get(token),
+            dex.referral_fees_held.get(token),
+            dex.sponsor_balances_held.get(token),
+        ];
+        for amount in buckets {
+            total = total.checked_add(amount).ok_or_else(|| err(ERR_OVERFLOW))?;
+        }
+        if token == dex.staking_token.get() {
+            total = total
+                .checked_add(dex.staking_total_staked.get())
+                .ok_or_else(|| err(ERR_OVERFLOW))?;
+        }
+        Ok(total)
+    }
+
+    /// Reserves of `token` across its pools with each of `counterparts`.
+    ///
+    /// @dev Rejects duplicates and uninitialized pools so no reserve is counted twice or invented.
+    pub fn pool_reserves(dex: &OakDEX, token: Address, counterparts: &[Address]) -> OakResult<U256> {
+        if counterparts.len() as u64 > MAX_RECONCILE_POOLS {
+            return Err(err(ERR_TOO_MANY_ITEMS));
+        }
+        let mut total = U256::ZERO;
+        for (i, &other) in counterparts.iter().enumerate() {
+            if other == token || counterparts[..i].contains(&other) {
+                return Err(err(ERR_INVALID_RECONCILE_POOL));
+            }
+            let (token0, token1) = if token < other { (token, other) } else { (other, token) };
+            let outer = dex.pools.getter(token0);
+            let pool = outer.getter(token1);
+            if !pool.initialized.get() {
+                return Err(err(ERR_INVALID_RECONCILE_POOL));
+            }
+            let reserve = if token == token0 { pool.reserve0.get() } else { pool.reserve1.get() };
+            total = total.checked_add(reserve).ok_or_else(|| err(ERR_OVERFLOW))?;
+        }
+        Ok(total)
+    }
+
+    /// (actual balance, expected balance, surplus, deficit) of the contract in `token`.
+    pub fn reconcile(dex: &OakDEX, token: Address, counterparts: &[Address]) -> OakResult<(U256, U256, U256, U256)> {
+        let expected = Self::pool_reserves(dex, token, counterparts)?
+            .checked_add(Self::accrued_liabilities(dex, token)?)
+            .ok_or_else(|| err(ERR_OVERFLOW))?;
+        let balance = balance_of(token, contract::address());
+        let (surplus, deficit) = balance_discrepancy(balance, expected);
+        Ok((balance, expected, surplus, deficit))
+    }
+
+    /// (actual balance, expected balance, surplus, deficit) in `token` across all pools.
+    ///
+    /// @dev Same as `reconcile` with every pool listed, using the running `reserve_totals`.
+    pub fn position(dex: &OakDEX, token: Address) -> OakResult<(U256, U256, U256, U256)> {
+        let expected = dex
+            .reserve_totals
+            .get(token)
+            .checked_add(Self::accrued_liabilities(dex, token)?)
+            .ok_or_else(|| err(ERR_OVERFLOW))?;
+        let balance = balance_of(token, contract::address());
+        let (surplus, deficit) = balance_discrepancy(balance, expected);
+        Ok((balance, expected, surplus, deficit))
+    }
+}
@@ -0,0 +1,106 @@
+//! Commit sponsorship: vouchers that let a sponsor pay the anti-spam commit bond.
+//!
+//! A sponsor (e.g. a frontend onboarding users) prepays bond tokens with
+//! `deposit_commit_sponsorship` and issues vouchers, each good for a number of commits and
+//! optionally bound to one redeemer. A user redeems a voucher id with `commit_swap_with_voucher`:
+//! the configured commit bond is drawn from the sponsor's prepaid balance instead of being
+//! pulled from the trader.
+//!
+//! The bond stays the sponsor's. It returns to the prepaid balance when the commitment is
+//! revealed, replaced or cancelled (less any cancellation penalty), so one deposit funds many
+//! commits. An abandoned commitment's bond is slashed to the treasury as usual: spam made with a
+//! voucher costs its sponsor. Unused prepaid balance can be withdrawn at any time.
+
+use stylus_sdk::alloy_primitives::{Address, U256};
+
+use crate::errors::{
+    err, OakResult, ERR_INVALID_VOUCHER, ERR_NOT_VOUCHER_REDEEMER, ERR_OVERFLOW, ERR_SPONSOR_BALANCE_LOW,
+    ERR_VOUCHER_NOT_FOUND,
+};
+use crate::events::{emit_commit_voucher_issued, emit_commit_voucher_redeemed};
+use crate::state::OakDEX;
+
+/// Commit sponsorship ledger (uses OakDEX sponsor_balances / commit_vouchers storage).
+pub struct CommitSponsorship;
+
+impl CommitSponsorship {
+    /// Prepaid balance of `sponsor` in `token`.
+    pub fn balance(dex: &OakDEX, sponsor: Address, token: Address) -> U256 {
+        dex.sponsor_balances.getter(sponsor).get(token)
+    }
+
+    /// Add `amount` of `token` to the prepaid balance of `sponsor`.
+    ///
+    /// @dev Tokens must already be held by the contract (a deposit or a returned bond).
+    pub fn credit(dex: &mut OakDEX, sponsor: Address, token: Address, amount: U256) -> OakResult<()> {
+        if amount.is_zero() {
+            return Ok(());
+        }
+        let balance = Self::balance(dex, sponsor, token).checked_add(amount).ok_or_else(|| err(ERR_OVERFLOW))?;
+        let held = dex.sponsor_balances_held.get(token).checked_add(amount).ok_or_else(|| err(ERR_OVERFLOW))?;
+        dex.sponsor_balances.setter(sponsor).setter(token).set(balance);
+        dex.sponsor_balances_held.setter(token).set(held);
+        Ok(())
+    }
+
+    /// Take `amount` of `token` out of the prepaid balance of `sponsor`.
+    ///
+    /// @dev The caller moves the tokens on (pays them out or holds them as a bond).
+    pub fn debit(dex: &mut OakDEX, sponsor: Address, token: Address, amount: U256) -> OakResult<()> {
+        let balance = Self::balance(dex, sponsor, token);
+        if balance < amount {
+            return Err(err(ERR_SPONSOR_BALANCE_LOW));
+        }
+        let held = dex.sponsor_balances_held.get(token).saturating_sub(amount);
+        dex.sponsor_balances.setter(sponsor).setter(token).set(balance - amount);
+        dex.sponsor_balances_held.setter(token).set(held);
+        Ok(())
+    }
+
+    /// Issue a voucher good for `uses` commits, redeemable by `redeemer` (zero = anyone).
+    ///
+    /// @return The voucher id (ids start at 1).
+    pub fn issue(dex: &mut OakDEX, sponsor: Address, redeemer: Address, uses: U256) -> OakResult<U256> {
+        if uses.is_zero() {
+            return Err(err(ERR_INVALID_VOUCHER));
+        }
+        let id = dex.commit_voucher_count.get().checked_add(U256::from(1u64)).ok_or_else(|| err(ERR_OVERFLOW))?;
+        dex.commit_voucher_count.set(id);
+        let mut voucher = dex.commit_vouchers.setter(id);
+        voucher.sponsor.set(sponsor);
+        voucher.redeemer.set(redeemer);
+        voucher.remaining.set(uses);
+        emit_commit_voucher_issued(id, sponsor, redeemer, uses);
+        Ok(id)
+    }
+
+    /// (sponsor, redeemer, remaining uses) of voucher `id`; sponsor zero = unknown id.
+    pub fn get(dex: &OakDEX, id: U256) -> (Address, Address, U256) {
+        let voucher = dex.commit_vouchers.getter(id);
+        (voucher.sponsor.get(), voucher.redeemer.get(), voucher.remaining.get())
+    }
+
+    /// Cancel the remaining uses of voucher `id` (its sponsor only).
+    pub fn revoke(dex: &mut OakDEX, sponsor: Address, id: U256) -> OakResult<()> {
+        let (owner, _, remaining) = Self::get(dex, id);
+        if owner != sponsor || remaining.is_zero() {
+            return Err(err(ERR_VOUCHER_NOT_FOUND));
+        }
+        dex.commit_vouchers.setter(id).remaining.set(U256::ZERO);
+        Ok(())
+    }
+
+    /// Spend one use of voucher `id` for `user`'s commit; returns the sponsor paying its bond.
+    pub fn redeem(dex: &mut OakDEX, id: U256, user: Address) -> OakResult<Address> {
+        let (sponsor, redeemer, remaining) = Self::get(dex, id);
+        if sponsor == Address::ZERO || remaining.is_zero() {
+            return Err(err(ERR_VOUCHER_NOT_FOUND));
+        }
+        if redeemer != Address::ZERO && redeemer != user {
+            return Err(err(ERR_NOT_VOUCHER_REDEEMER));
+        }
+        dex.commit_vouchers.setter(id).remaining.set(remaining - U256::from(1u64));
+        emit_commit_voucher_redeemed(id, user, sponsor);
+        Ok(sponsor)
+    }
+}
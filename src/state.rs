@@ -8,15 +8,16 @@
 use stylus_sdk::{
     alloy_primitives::{Address, FixedBytes, U256},
     prelude::*,
-    storage::{StorageAddress, StorageBool, StorageMap, StorageU256},
+    storage::{StorageAddress, StorageBool, StorageFixedBytes, StorageMap, StorageU256},
 };
 
 /// Commitment structure for the commit‑reveal mechanism.
 ///
 /// @notice Describes a user's pending swap commitment.
-/// @dev The live storage representation is split across several `StorageMap`s
-///      for gas efficiency on Stylus. This plain struct is used for in-memory
-///      reasoning and documentation.
+/// @dev The live storage representation is `OakDEX::commitments` (one `CommitmentSlot`
+///      per user, so the address is hashed once per access). `activated` is not stored:
+///      a commitment is active iff its hash is non-zero. This plain struct is used for
+///      in-memory reasoning and documentation.
 #[derive(Clone, Copy)]
 pub struct Commitment {
     /// Hash of the commitment (keccak256 of reveal data).
@@ -28,6 +29,57 @@ pub struct Commitment {
 }
 
 sol_storage! {
+    /// Per-user commitment record (consecutive slots under a single map key).
+    pub struct CommitmentSlot {
+        /// Commitment hash (U256-encoded bytes32); zero = no active commitment.
+        StorageU256 hash;
+        /// Block number when the commitment was created.
+        StorageU256 block;
+        /// Fill priority fixed at commit: packed (block << 128) | global commit sequence.
+        /// @dev Lower is earlier; batch settlement fills in ascending order (price-time priority).
+        StorageU256 priority;
+        /// Anti-spam bond posted at commit (returned on reveal/cancel, slashed once expired).
+        StorageU256 bond;
+        /// Token the bond was posted in (config may change while it is held).
+        StorageAddress bond_token;
+        /// Pair key the commitment was declared for (`commit_swap_in_pool`); zero = not declared.
+        StorageU256 pool;
+        /// Verification scheme (COMMIT_SCHEME_*) the reveal is checked with, see `engine::verification`.
+        StorageU256 scheme;
+        /// Referrer credited the referral share of the reveal's fee; zero = none (see `growth::referral`).
+        StorageAddress referrer;
+        /// Sponsor whose prepaid balance posted the bond and gets it back; zero = the user (see `sponsorship`).
+        StorageAddress bond_sponsor;
+        /// MEV protection tier (MEV_TIER_*): reveal delay, fee scale and reveal path.
+        StorageU256 mev_tier;
+        /// Declared upper bound on the reveal's input (0 = undeclared), see `commit_exposure`.
+        StorageU256 exposure_in;
+        /// Declared direction: true = token0 in.
+        StorageBool exposure_zero_for_one;
+    }
+
+    /// Commit sponsorship voucher (see `sponsorship`).
+    pub struct CommitVoucher {
+        /// Sponsor whose prepaid balance pays the bonds; zero = no such voucher.
+        StorageAddress sponsor;
+        /// Only address allowed to redeem it; zero = anyone holding the id.
+        StorageAddress redeemer;
+        /// Commits it can still pay for.
+        StorageU256 remaining;
+    }
+
+    /// Governance risk policy of one token (see `risk`); all-zero = no restrictions.
+    pub struct TokenRiskParams {
+        /// Cap on the token's reserve in any single pool; zero = uncapped.
+        StorageU256 max_pool_exposure;
+        /// Whether flash swaps and flash loans may borrow the token.
+        StorageBool flash_borrow_disabled;
+        /// Pools containing the token need a bootstrap feed bound before they are created.
+        StorageBool oracle_required;
+        /// Hook-capable token (ERC-777 / ERC-677): its pools always run as hooked pools.
+        StorageBool hooked;
+    }
+
     /// Per‑pair pool data for multi‑pool support.
     pub struct PoolData {
         /// Reserve of token0 in the pool (canonical ordering).
@@ -40,6 +92,220 @@ sol_storage! {
         StorageMap<Address, StorageU256> lp_balances;
         /// Initialization flag to distinguish configured pools.
         StorageBool initialized;
+
+        /// --- Epoch statistics (snapshot-and-reset via reset_pool_stats) ---
+        /// Current epoch index (starts at 0).
+        StorageU256 stats_epoch;
+        /// Block at which the current epoch started.
+        StorageU256 stats_epoch_start_block;
+        /// Token0 swapped into the pool during the current epoch.
+        StorageU256 stats_volume0;
+        /// Token1 swapped into the pool during the current epoch.
+        StorageU256 stats_volume1;
+        /// Total swap fees charged in token0 during the current epoch.
+        StorageU256 stats_fees0;
+        /// Total swap fees charged in token1 during the current epoch.
+        StorageU256 stats_fees1;
+        /// Archived totals of closed epochs (epoch index -> stats).
+        StorageMap<U256, EpochStats> stats_history;
+
+        /// Pool contains a hook-capable token (ERC-777 / ERC-677); set once at creation.
+        /// @dev Enables strict CEI ordering and balance-diff verification on transfers.
+        StorageBool hooked_token;
+
+        /// Per-provider deposit receipts (cost basis for get_lp_receipt).
+        StorageMap<Address, LpReceipt> lp_receipts;
+        /// Per-provider LP share lock (see `locks::LiquidityLocks`).
+        StorageMap<Address, LpLock> lp_locks;
+
+        /// Pool manager (the creator; 0 = renounced). May adjust this pool's narrow parameters;
+        /// protocol governance (owner) can always override (see `require_pool_admin`).
+        StorageAddress manager;
+        /// Pool-level pause set by the manager or governance: blocks swaps and deposits, never exits.
+        StorageBool paused;
+
+        /// --- Per-pool TWAP oracle (see `oracle::PoolOracle`) ---
+        /// Cumulative price0 (reserve1/reserve0, Q112) * blocks.
+        StorageU256 oracle_cumulative0;
+        /// Cumulative price1 (reserve0/reserve1, Q112) * blocks.
+        StorageU256 oracle_cumulative1;
+        /// Block of the last cumulative update (0 = never observed).
+        StorageU256 oracle_block_last;
+        /// Older and newer window checkpoints; the TWAP is measured from the older one.
+        OracleCheckpoint oracle_cp_old;
+        OracleCheckpoint oracle_cp_new;
+
+        /// Time-weighted reserves of the current epoch (reserve * blocks), for average TVL / fee APR.
+        StorageU256 stats_reserve0_area;
+        StorageU256 stats_reserve1_area;
+        /// Block up to which the reserve areas are accumulated (0 = epoch start).
+        StorageU256 stats_area_block;
+
+        /// Observation ring buffer (Uniswap v3 style): slot -> cumulative prices at `block`.
+        StorageMap<U256, OracleCheckpoint> observations;
+        /// Slot of the most recent observation.
+        StorageU256 observation_index;
+        /// Slots in use by the ring (0 = no observation yet).
+        StorageU256 observation_cardinality;
+        /// Ring size to grow into once the write index reaches the current end.
+        StorageU256 observation_cardinality_next;
+
+        /// --- Creator share (see `creator::PoolCreatorShare`) ---
+        /// Address that created the pool (immutable, unlike `manager`).
+        StorageAddress creator;
+        /// Creator's cut of the treasury fee (bps), snapshotted at creation (0 = none).
+        StorageU256 creator_share_bps;
+        /// Last block the creator share applies to.
+        StorageU256 creator_share_until_block;
+
+        /// --- Cold-start price check (see `PoolOracle::check_bootstrap_price`) ---
+        /// Chainlink-style feed the first deposit is validated against (0 = none).
+        StorageAddress bootstrap_feed;
+        /// Multiplier turning the feed answer into reserve1/reserve0 in Q112.
+        StorageU256 bootstrap_feed_scale;
+        /// Allowed deviation of the first deposit's ratio from the feed price (bps).
+        StorageU256 bootstrap_tolerance_bps;
+
+        /// Total swap fee (bps) chosen from the fee tier whitelist at creation; 0 = the global
+        /// `protocol_fee_bps` (pools created without a tier).
+        StorageU256 fee_tier_bps;
+
+        /// Volatility fee regime (FEE_REGIME_*), set by `fees::FeeController::adjust`.
+        StorageU256 fee_regime;
+
+        /// --- Claimable LP fees (see `lp_fees`) ---
+        /// LP fee per share in token0 / token1, scaled by 2^128 (monotonic).
+        StorageU256 lp_fee_per_share0;
+        StorageU256 lp_fee_per_share1;
+        /// Accumulator values each provider was last settled at.
+        StorageMap<Address, StorageU256> lp_fee_paid0;
+        StorageMap<Address, StorageU256> lp_fee_paid1;
+        /// Settled, unclaimed LP fees per provider.
+        StorageMap<Address, StorageU256> lp_fees_owed0;
+        StorageMap<Address, StorageU256> lp_fees_owed1;
+
+        /// Bitmask (`1 << scheme`) of commitment schemes accepted besides the plain hash.
+        StorageU256 commit_schemes;
+        /// LP shares each provider holds wrapped as ERC-1155 position tokens, all tiers (see `positions`).
+        StorageMap<Address, StorageU256> lp_wrapped;
+
+        /// --- LP-fee surplus sweep (see `lp_fees::LpFees::queue_surplus_sweep`) ---
+        /// Queued share of the locked zero-address shares' fees, per token.
+        StorageU256 lp_surplus_queued0;
+        StorageU256 lp_surplus_queued1;
+        /// Block from which the queued sweep may execute (0 = none queued).
+        StorageU256 lp_surplus_sweep_after_block;
+        /// Block epoch (`lp_fees::surplus_epoch`) the sweep was queued in.
+        StorageU256 lp_surplus_queued_epoch;
+
+        /// --- Per-block outflow limiter (see `flow_limit`) ---
+        /// Block the outflow accumulators below belong to.
+        StorageU256 outflow_block;
+        /// token0 / token1 that left the pool in `outflow_block` (swaps and flash swaps).
+        StorageU256 outflow0;
+        StorageU256 outflow1;
+        /// Reserve of token0 / token1 at its first outflow in `outflow_block` (the cap's base).
+        StorageU256 outflow_base0;
+        StorageU256 outflow_base1;
+        /// Epoch + 1 of the pool's last executed LP-fee surplus sweep (0 = never).
+        StorageU256 lp_surplus_swept_epoch;
+    }
+
+    /// One reveal collected into a batch auction (see `auction::BatchAuction`).
+    pub struct BatchOrder {
+        StorageAddress owner;
+        /// Canonical direction (true = sells token0).
+        StorageBool zero_for_one;
+        /// Input held by the contract (0 once reclaimed).
+        StorageU256 amount_in;
+        StorageU256 min_amount_out;
+        /// Commitment fill priority (see `CommitmentSlot::priority`).
+        StorageU256 priority;
+    }
+
+    /// Reveals of one pool collected over one batch window; keyed by the window's closing block.
+    pub struct SwapBatch {
+        StorageU256 count;
+        StorageMap<U256, BatchOrder> orders;
+        StorageBool settled;
+    }
+
+    /// Snapshot of a pool's cumulative prices at `block`.
+    pub struct OracleCheckpoint {
+        StorageU256 cumulative0;
+        StorageU256 cumulative1;
+        StorageU256 block;
+    }
+
+    /// Worst-case input of a pool's pending declared commitments (see `commit_exposure`).
+    pub struct PendingExposure {
+        /// Sum of declared token0 inputs (zero_for_one).
+        StorageU256 amount0_in;
+        /// Sum of declared token1 inputs.
+        StorageU256 amount1_in;
+        /// Number of pending declared commitments.
+        StorageU256 count;
+    }
+
+    /// Time lock on a provider's LP shares.
+    pub struct LpLock {
+        /// Locked LP shares.
+        StorageU256 amount;
+        /// First block at which the shares are free again.
+        StorageU256 unlock_block;
+        /// Liquidity index at lock / last harvest (fee growth above it is harvestable).
+        StorageU256 index;
+    }
+
+    /// LP deposit receipt: cost basis and entry state of a provider's position.
+    pub struct LpReceipt {
+        /// Token0 contributed, net of pro-rata withdrawals.
+        StorageU256 deposited0;
+        /// Token1 contributed, net of pro-rata withdrawals.
+        StorageU256 deposited1;
+        /// Block of the latest deposit.
+        StorageU256 entry_block;
+        /// Pool reserves right after the latest deposit.
+        StorageU256 entry_reserve0;
+        StorageU256 entry_reserve1;
+        /// LP-weighted liquidity index at entry (see `receipts::liquidity_index`).
+        StorageU256 entry_index;
+    }
+
+    /// One executed reveal in a user's trade journal.
+    pub struct TradeRecord {
+        StorageAddress token_in;
+        StorageAddress token_out;
+        StorageU256 amount_in;
+        /// Output delivered (or credited to escrow).
+        StorageU256 amount_out;
+        /// Protocol fee rate applied (bps of amount_in).
+        StorageU256 fee_bps;
+        StorageU256 block_number;
+        StorageU256 timestamp;
+    }
+
+    /// One privileged action in the owner audit log.
+    pub struct AuditRecord {
+        /// AUDIT_ACTION_* code.
+        StorageU256 action;
+        StorageAddress actor;
+        /// keccak256 of the action's parameters, ABI-encoded as 32-byte words.
+        StorageU256 params_hash;
+        StorageU256 block_number;
+    }
+
+    /// Archived per-pool totals for one closed epoch.
+    pub struct EpochStats {
+        StorageU256 start_block;
+        StorageU256 end_block;
+        StorageU256 volume0;
+        StorageU256 volume1;
+        StorageU256 fees0;
+        StorageU256 fees1;
+        /// Time-weighted reserves over the epoch (reserve * blocks).
+        StorageU256 reserve0_area;
+        StorageU256 reserve1_area;
     }
 
     #[cfg_attr(any(test, not(target_arch = "wasm32")), allow(unused_doc_comments))]
@@ -49,6 +315,9 @@ sol_storage! {
     /// @dev This layout is intentionally flat and Stylus‑friendly. Higher‑level
     ///      abstractions live in `logic`. Reserved fields at the end allow
     ///      backwards‑compatible extension for future features (e.g. Oak Bet).
+    ///      On-chain builds make it the contract's entrypoint: calldata is dispatched to the
+    ///      `#[public]` methods in `logic` by selector.
+    #[cfg_attr(all(not(test), target_arch = "wasm32"), entrypoint)]
     pub struct OakDEX {
         /// Reserve of token0 in the liquidity pool.
         StorageU256 reserves0;
@@ -76,7 +345,7 @@ sol_storage! {
         /// Accrued fees owed to the treasury in token0 units.
         StorageU256 accrued_treasury_fees_token0;
 
-        /// Accrued fees owed to LPs in token0 units (accounting only).
+        /// Unused: LP fees accrue per pool and are claimed with `claim_lp_fees` (see `lp_fees`).
         StorageU256 accrued_lp_fees_token0;
 
         /// Total trading volume for token0 (for analytics).
@@ -96,18 +365,15 @@ sol_storage! {
         /// TWAP deviation circuit breaker: last observed price1 (Q112.64) for per-block deviation check.
         StorageU256 last_twap_price1;
 
-        /// Gas-rebate reserve: portion of protocol fee tracked for future gas rebates (placeholder).
+        /// Unused: superseded by the per-user `accrued_gas_rebate` (see `gas_rebate`).
         StorageU256 accrued_gas_rebate_token0;
 
         /// Emergency pause switch (if true, swaps are frozen).
         StorageBool paused;
 
-        /// Mapping from user address to commitment hash (U256-encoded bytes32).
-        StorageMap<Address, StorageU256> commitment_hashes;
-        /// Mapping from user address to commitment block timestamp.
-        StorageMap<Address, StorageU256> commitment_timestamps;
-        /// Mapping from user address to commitment activation status.
-        StorageMap<Address, StorageBool> commitment_activated;
+        /// Mapping from user address to commitment record (hash + block).
+        /// @dev Replaces the former hash/timestamp/activated maps: one keccak per access.
+        StorageMap<Address, CommitmentSlot> commitments;
 
         /// Global re-entrancy guard (true = locked, false = unlocked).
         /// @dev Prevents recursive calls to critical functions.
@@ -183,7 +449,8 @@ sol_storage! {
         /// Gasless trading: per-user nonce for EIP-712 PermitSwap (replay protection).
         StorageMap<Address, StorageU256> permit_swap_nonce;
 
-        /// Access Control: role (bytes32) -> account -> has role.
+        /// Legacy Access Control map: role (bytes32) -> account -> has role. Superseded by
+        /// `role_bits`; read only by `migrate_legacy_roles`, slot kept for layout.
         StorageMap<FixedBytes<32>, StorageMap<Address, StorageBool>> roles;
 
         /// Timelock: operation_id (keccak256(target,value,data,predecessor,salt)) -> block number after which execute is allowed.
@@ -239,8 +506,225 @@ sol_storage! {
         /// Per-seller nonce for EIP-712 SignalListing replay protection.
         StorageMap<Address, StorageU256> signal_nonce;
 
+        /// --- Output escrow (pull-based claims) ---
+        /// User opt-in: when true, reveal credits the swap output to `claimable_balance` instead of transferring it.
+        StorageMap<Address, StorageBool> escrow_output_enabled;
+        /// user => (token => amount claimable via claim_output).
+        StorageMap<Address, StorageMap<Address, StorageU256>> claimable_balance;
+        /// Per-token sum of all claimable balances (held by the contract, outside pool reserves).
+        StorageMap<Address, StorageU256> claimable_total;
+
+        /// --- Stale price reference policy (TWAP deviation breaker) ---
+        /// Block at which last_twap_price0/1 were last refreshed.
+        StorageU256 last_twap_price_block;
+        /// Blocks after which the deviation reference counts as stale (0 = ORACLE_HEARTBEAT_BLOCKS).
+        StorageU256 oracle_heartbeat_blocks;
+        /// Governance choice on stale reference: true = reject swaps (ORACLE_STALE), false = skip the comparison.
+        StorageBool stale_oracle_pauses_swaps;
+
+        /// --- Abandoned escrow sweep ---
+        /// Block of the user's last escrow activity (credit, debit, transfer, claim, mode change).
+        StorageMap<Address, StorageU256> escrow_last_activity;
+        /// Inactivity (blocks) after which claimable balances may be swept to treasury (0 = disabled).
+        StorageU256 escrow_sweep_after_blocks;
+
+        /// --- Large-commitment price band ---
+        /// Commitments whose input exceeds this share of reserve_in (bps) are "large" (0 = check disabled).
+        StorageU256 large_commit_reserve_bps;
+        /// Large commitments must carry a limit price within this band (bps) below the TWAP at commit.
+        StorageU256 commit_price_band_bps;
+
+        /// --- Trade journal (see `journal`) ---
+        /// User opt-in: when true, every reveal appends a TradeRecord.
+        StorageMap<Address, StorageBool> trade_journal_enabled;
+        /// user => (index => record); append-only.
+        StorageMap<Address, StorageMap<U256, TradeRecord>> trade_journal;
+        /// user => number of journal entries.
+        StorageMap<Address, StorageU256> trade_journal_len;
+
+        /// --- Treasury rotation (propose -> delay -> execute) ---
+        /// Proposed treasury (0 = none pending).
+        StorageAddress pending_treasury;
+        /// Block from which the pending treasury can be installed.
+        StorageU256 treasury_change_after_block;
+        /// Proposal delay in blocks (0 = TREASURY_CHANGE_DELAY_BLOCKS).
+        StorageU256 treasury_change_delay_blocks;
+        /// Per-token fee destination overriding `treasury` in withdraw_treasury_fees (0 = treasury).
+        StorageMap<Address, StorageAddress> treasury_destination;
+        /// Proposed per-token destination and the block from which it can be installed.
+        StorageMap<Address, StorageAddress> pending_treasury_destination;
+        StorageMap<Address, StorageU256> treasury_destination_after_block;
+
+        /// --- Commit-reveal parameters (0 = compile-time default) ---
+        /// Minimum blocks between commit and reveal (COMMIT_REVEAL_DELAY).
+        StorageU256 commit_reveal_delay_blocks;
+        /// Blocks after which an unrevealed commitment expires (MAX_COMMITMENT_AGE).
+        StorageU256 max_commitment_age_blocks;
+        /// Monotonic counter of commitments; breaks same-block ties in `CommitmentSlot::priority`.
+        StorageU256 commit_sequence;
+        /// Bond required by commit_swap (amount 0 = no bond).
+        StorageAddress commit_bond_token;
+        StorageU256 commit_bond_amount;
+        /// Per-token total of bonds currently held for active commitments.
+        StorageMap<Address, StorageU256> commit_bonds_held;
+        /// user => operator => may commit on the user's behalf (`commit_swap_for`).
+        StorageMap<Address, StorageMap<Address, StorageBool>> commit_operators;
+
+        /// --- Pool creator share (config applies to pools created afterwards) ---
+        /// Creator's cut of the treasury fee in bps (0 = disabled).
+        StorageU256 creator_fee_share_bps;
+        /// Blocks after pool creation during which the share applies.
+        StorageU256 creator_fee_share_blocks;
+        /// creator => token => accrued, unclaimed share.
+        StorageMap<Address, StorageMap<Address, StorageU256>> creator_claimable;
+        /// Per-token total of unclaimed creator shares (held by the contract).
+        StorageMap<Address, StorageU256> creator_claimable_total;
+
+        /// --- Batch auction (uniform clearing price) ---
+        /// Window length in blocks (0 = batch mode off).
+        StorageU256 batch_window_blocks;
+        /// token0 => token1 => closing block => batch.
+        StorageMap<Address, StorageMap<Address, StorageMap<U256, SwapBatch>>> swap_batches;
+        /// Per-token input held for unsettled batch orders.
+        StorageMap<Address, StorageU256> batch_escrowed;
+
         /// Reserved space for future protocol extensions (e.g. Oak Bet).
         StorageU256 reserved3;
+        /// Per-token sum of pool reserves, so `sync`/`skim` can tell donations from owed funds.
+        StorageMap<Address, StorageU256> reserve_totals;
+        /// Per-token output escrowed by open limit orders.
+        StorageMap<Address, StorageU256> order_escrowed;
+
+        /// --- Pair registry (see `pairs::PairRegistry`) ---
+        /// keccak(token0, token1) => 1 + position in `pair_token0` / `pair_token1` (0 = unregistered).
+        StorageMap<FixedBytes<32>, StorageU256> pair_index;
+        /// Position => canonical token0 / token1 of every pool, in creation order.
+        StorageMap<U256, StorageAddress> pair_token0;
+        StorageMap<U256, StorageAddress> pair_token1;
+        /// Number of registered pairs.
+        StorageU256 pair_count;
+
+        /// Single-pair mode (`init_single_pair`): the only tokens any entrypoint accepts.
+        /// @dev Unset in factory mode. A flag, since native ETH (address(0)) can be token0.
+        StorageBool tokens_bound;
+        StorageAddress bound_token0;
+        StorageAddress bound_token1;
+
+        /// Append-only log of privileged actions (index -> record), see `audit`.
+        StorageMap<U256, AuditRecord> audit_log;
+        StorageU256 audit_log_len;
+
+        /// Owner-managed whitelist of pool fee tiers (bps -> enabled), see `create_pair_with_fee`.
+        StorageMap<U256, StorageBool> fee_tier_enabled;
+
+        /// --- Volatility fee controller (see `fees`); vol_fee_volatile_bps 0 = disabled ---
+        /// Volatility (bps of TWAP) at or below which a pool is calm.
+        StorageU256 vol_fee_calm_bps;
+        /// Volatility (bps of TWAP) at or above which a pool is volatile.
+        StorageU256 vol_fee_volatile_bps;
+        /// Fee added in the volatile regime (bps, capped at MAX_FEE_BPS).
+        StorageU256 vol_fee_surcharge_bps;
+        /// Fee removed in the calm regime (bps, floored at zero).
+        StorageU256 vol_fee_discount_bps;
+
+        /// --- Token aliases (see `aliases`): alias -> canonical, one level deep ---
+        StorageMap<Address, StorageAddress> token_alias;
+        /// Whether the token is an alias (canonical may be native ETH, address(0)).
+        StorageMap<Address, StorageBool> token_alias_set;
+        /// Number of aliases pointing at a canonical token.
+        StorageMap<Address, StorageU256> token_alias_count;
+
+        /// LP fees credited to pool accumulators and not yet claimed, per token (see `lp_fees`).
+        StorageMap<Address, StorageU256> lp_fees_held;
+
+        /// --- Gas rebates owed to commit-reveal revealers (see `gas_rebate`) ---
+        /// Unclaimed rebate per user and token.
+        StorageMap<Address, StorageMap<Address, StorageU256>> accrued_gas_rebate;
+        /// Tokens a user has unclaimed rebates in (index -> token), `gas_rebate_token_count` long.
+        StorageMap<Address, StorageMap<U256, StorageAddress>> gas_rebate_tokens;
+        StorageMap<Address, StorageU256> gas_rebate_token_count;
+        /// Rebates accrued and not yet claimed, per token.
+        StorageMap<Address, StorageU256> gas_rebates_held;
+
+        /// --- Treasury split table (see `treasury_splits`), index -> recipient / weight (bps) ---
+        StorageMap<U256, StorageAddress> treasury_split_recipient;
+        StorageMap<U256, StorageU256> treasury_split_bps;
+        /// Active table length; 0 = withdrawals go to a single destination.
+        StorageU256 treasury_split_count;
+        /// Proposed table, installable from `treasury_split_after_block` (0 = none pending).
+        StorageMap<U256, StorageAddress> pending_treasury_split_recipient;
+        StorageMap<U256, StorageU256> pending_treasury_split_bps;
+        StorageU256 pending_treasury_split_count;
+        StorageU256 treasury_split_after_block;
+
+        /// Referral fees owed per referrer and token, claimed with claim_referral_fees.
+        StorageMap<Address, StorageMap<Address, StorageU256>> referral_accrued;
+        /// Referral fees accrued and not yet claimed, per token.
+        StorageMap<Address, StorageU256> referral_fees_held;
+
+        /// ERC-3156 loans: token -> counter token of the pool a `flashLoan` of it draws from.
+        StorageMap<Address, StorageAddress> flash_loan_pair;
+        /// Flash swap callback style (FLASH_CALLBACK_*) registered per borrower; 0 = Oak.
+        StorageMap<Address, StorageU256> flash_callback_style;
+        /// Flash loan fee (bps) for flash_swap and ERC-3156 loans; 0 = each pool's swap fee.
+        StorageU256 flash_fee_bps;
+
+        /// Retired pool pair key -> successor pair key (see `pool_migration`).
+        StorageMap<FixedBytes<32>, StorageFixedBytes<32>> pool_successor;
+        /// Retired pool pair key -> last block it still trades in (grace period end).
+        StorageMap<FixedBytes<32>, StorageU256> pool_retired_after;
+
+        /// ERC-1155 position token balances: id -> holder -> amount (see `positions`).
+        StorageMap<U256, StorageMap<Address, StorageU256>> position_balances;
+        /// ERC-1155 operator approvals: holder -> operator -> approved.
+        StorageMap<Address, StorageMap<Address, StorageBool>> position_operators;
+
+        /// Commit sponsorship: sponsor -> token -> prepaid bond balance (see `sponsorship`).
+        StorageMap<Address, StorageMap<Address, StorageU256>> sponsor_balances;
+        /// Per-token total of prepaid sponsor balances (held by the contract).
+        StorageMap<Address, StorageU256> sponsor_balances_held;
+        /// Number of vouchers issued; the latest voucher id.
+        StorageU256 commit_voucher_count;
+        /// Voucher id -> voucher.
+        StorageMap<U256, CommitVoucher> commit_vouchers;
+
+        /// Token risk registry: token -> governance risk policy (see `risk`).
+        StorageMap<Address, TokenRiskParams> token_risk;
+
+        /// Largest spot price move (bps) a single swap may cause; 0 = no limit.
+        StorageU256 max_price_impact_bps;
+
+        /// --- Automatic circuit breaker (spot vs TWAP divergence) ---
+        /// Post-swap spot divergence from the TWAP (bps) that pauses the protocol; 0 = disabled.
+        StorageU256 auto_breaker_deviation_bps;
+        /// Blocks the TWAP is averaged over (0 = the pool oracle's current window).
+        StorageU256 auto_breaker_window_blocks;
+        /// Block at which the automatic breaker last tripped (0 = not tripped since the last reset).
+        StorageU256 auto_breaker_tripped_block;
+
+        /// --- LP-fee surplus sweep policy ---
+        /// Block epochs (LP_SURPLUS_EPOCH_BLOCKS) a queued sweep must age before it executes.
+        StorageU256 lp_surplus_min_age_epochs;
+        /// Share (bps) of the surplus one sweep may take; 0 = sweeping disabled.
+        StorageU256 lp_surplus_max_sweep_bps;
+
+        /// --- Pending commitment exposure (see `commit_exposure`) ---
+        /// pair key => declared input of unrevealed commitments.
+        StorageMap<FixedBytes<32>, PendingExposure> pending_exposure;
+
+        /// Largest share (bps) of a pool's reserve that may leave it per block and direction; 0 = no limit.
+        StorageU256 max_outflow_bps_per_block;
+
+        /// Per-user nonce for EIP-712 CancelBatch (signed cancellation replay protection).
+        StorageMap<Address, StorageU256> cancel_nonce;
+
+        /// Access Control: account -> role bitmask (`access::ROLE_*`).
+        StorageMap<Address, StorageU256> role_bits;
+
+        /// Shorter treasury change delay queued by `set_treasury_change_delay` (0 = none).
+        StorageU256 pending_treasury_change_delay;
+        /// Block from which `pending_treasury_change_delay` is in force.
+        StorageU256 treasury_change_delay_after_block;
     }
 
     /// Guardian / vault state used by the GMX-style leverage module.
@@ -282,3 +766,10 @@ sol_storage! {
     }
 }
 
+
+/// `OakDEX` is the contract's root storage, laid out from slot 0.
+///
+/// @dev Lets the public router dispatch against it in-process (e.g. `aggregate_views`). On-chain
+///      builds get this impl from `#[entrypoint]`; host builds have no router, so it is spelled out.
+#[cfg(any(test, not(target_arch = "wasm32")))]
+unsafe impl TopLevelStorage for OakDEX {}
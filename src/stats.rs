@@ -0,0 +1,203 @@
+//! Per-pool epoch statistics: swap volume and fees with snapshot-and-reset.
+//!
+//! Every swap adds its input volume and total fee to the pool's current-epoch counters.
+//! Governance calls `reset_pool_stats` at an epoch boundary: the running totals are archived
+//! into `stats_history[epoch]`, counters are zeroed and the epoch index advances. Counters
+//! therefore stay bounded and incentive programs read clean per-epoch totals.
+//!
+//! Reserves are also integrated over time (`reserve * blocks`) before every reserve change, so
+//! each epoch carries its average TVL and `fee_apr` can annualize LP fees on-chain.
+
+use stylus_sdk::{alloy_primitives::{Address, U256}, block};
+
+use crate::constants::{as_u256, BLOCKS_PER_YEAR, BPS, LP_FEE_PCT};
+use crate::errors::{
+    err, MathResult, OakResult, ERR_DIVISION_BY_ZERO, ERR_INVALID_TOKEN, ERR_OVERFLOW, ERR_VOLUME_OVERFLOW,
+};
+use crate::events::emit_pool_epoch_closed;
+use crate::state::OakDEX;
+
+/// Annualized LP fee yield in basis points from an epoch's fees and time-weighted reserves.
+///
+/// @notice Per side: fees * BLOCKS_PER_YEAR / area (area = average reserve * blocks), scaled by
+///         the LP share of the fee. A balanced pool holds equal value on both sides, so the pool
+///         APR is the mean of the two side yields. Sides with no area contribute 0.
+pub fn fee_apr_bps(fees0: U256, fees1: U256, area0: U256, area1: U256) -> MathResult<U256> {
+    let side = |fees: U256, area: U256| -> MathResult<U256> {
+        if area.is_zero() {
+            return Ok(U256::ZERO);
+        }
+        fees.checked_mul(as_u256(BLOCKS_PER_YEAR))
+            .and_then(|v| v.checked_mul(as_u256(BPS)))
+            .and_then(|v| v.checked_mul(as_u256(LP_FEE_PCT)))
+            .ok_or(ERR_OVERFLOW)?
+            .checked_div(area.checked_mul(U256::from(100u64)).ok_or(ERR_OVERFLOW)?)
+            .ok_or(ERR_DIVISION_BY_ZERO)
+    };
+    let total = side(fees0, area0)?.checked_add(side(fees1, area1)?).ok_or(ERR_OVERFLOW)?;
+    Ok(total / U256::from(2u64))
+}
+
+/// Pool statistics (uses PoolData stats_* storage).
+pub struct PoolStats;
+
+impl PoolStats {
+    /// Record a swap into the current epoch. `zero_for_one` = token0 was the input.
+    pub fn record_swap(
+        dex: &mut OakDEX,
+        token0: Address,
+        token1: Address,
+        zero_for_one: bool,
+        amount_in: U256,
+        fee: U256,
+    ) -> OakResult<()> {
+        let mut outer = dex.pools.setter(token0);
+        let mut pool = outer.setter(token1);
+        let (volume, fees) = if zero_for_one {
+            (pool.stats_volume0.get(), pool.stats_fees0.get())
+        } else {
+            (pool.stats_volume1.get(), pool.stats_fees1.get())
+        };
+        let new_volume = volume.checked_add(amount_in).ok_or_else(|| err(ERR_VOLUME_OVERFLOW))?;
+        let new_fees = fees.checked_add(fee).ok_or_else(|| err(ERR_OVERFLOW))?;
+        if zero_for_one {
+            pool.stats_volume0.set(new_volume);
+            pool.stats_fees0.set(new_fees);
+        } else {
+            pool.stats_volume1.set(new_volume);
+            pool.stats_fees1.set(new_fees);
+        }
+        Ok(())
+    }
+
+    /// Integrate the current reserves up to now. Call before any reserve change.
+    pub fn accrue_reserves(dex: &mut OakDEX, token0: Address, token1: Address) -> OakResult<()> {
+        let now = U256::from(block::number());
+        let mut outer = dex.pools.setter(token0);
+        let mut pool = outer.setter(token1);
+        let (area0, area1, elapsed) = Self::areas_at(&pool, now)?;
+        if elapsed.is_zero() {
+            return Ok(());
+        }
+        pool.stats_reserve0_area.set(area0);
+        pool.stats_reserve1_area.set(area1);
+        pool.stats_area_block.set(now);
+        Ok(())
+    }
+
+    /// Reserve areas extrapolated to `now` with the current reserves, plus blocks added.
+    fn areas_at(pool: &crate::state::PoolData, now: U256) -> OakResult<(U256, U256, U256)> {
+        let from = pool.stats_area_block.get().max(pool.stats_epoch_start_block.get());
+        let elapsed = now.saturating_sub(from);
+        let area0 = pool
+            .stats_reserve0_area
+            .get()
+            .checked_add(pool.reserve0.get().checked_mul(elapsed).ok_or_else(|| err(ERR_OVERFLOW))?)
+            .ok_or_else(|| err(ERR_OVERFLOW))?;
+        let area1 = pool
+            .stats_reserve1_area
+            .get()
+            .checked_add(pool.reserve1.get().checked_mul(elapsed).ok_or_else(|| err(ERR_OVERFLOW))?)
+            .ok_or_else(|| err(ERR_OVERFLOW))?;
+        Ok((area0, area1, elapsed))
+    }
+
+    /// Fee APR (bps) and the window it was measured over (blocks) for (token0, token1).
+    ///
+    /// @notice Uses the running epoch; right after a reset (empty window) falls back to the last
+    ///         closed epoch so the figure never drops to zero at an epoch boundary.
+    pub fn fee_apr(dex: &OakDEX, token0: Address, token1: Address) -> OakResult<(U256, U256)> {
+        let outer = dex.pools.getter(token0);
+        let pool = outer.getter(token1);
+        if !pool.initialized.get() {
+            return Err(err(ERR_INVALID_TOKEN));
+        }
+        let now = U256::from(block::number());
+        let start = pool.stats_epoch_start_block.get();
+        let epoch = pool.stats_epoch.get();
+        if now > start || epoch.is_zero() {
+            let (area0, area1, _) = Self::areas_at(&pool, now)?;
+            let apr = fee_apr_bps(pool.stats_fees0.get(), pool.stats_fees1.get(), area0, area1)?;
+            return Ok((apr, now.saturating_sub(start)));
+        }
+        let last = pool.stats_history.getter(epoch - U256::from(1u64));
+        let apr = fee_apr_bps(last.fees0.get(), last.fees1.get(), last.reserve0_area.get(), last.reserve1_area.get())?;
+        Ok((apr, last.end_block.get().saturating_sub(last.start_block.get())))
+    }
+
+    /// Archive the current epoch for (token0, token1), reset counters and start the next epoch.
+    /// Returns the index of the closed epoch. Caller enforces governance access.
+    pub fn close_epoch(dex: &mut OakDEX, token0: Address, token1: Address) -> OakResult<U256> {
+        let current_block = U256::from(block::number());
+        let mut outer = dex.pools.setter(token0);
+        let mut pool = outer.setter(token1);
+        if !pool.initialized.get() {
+            return Err(err(ERR_INVALID_TOKEN));
+        }
+        let (area0, area1, _) = Self::areas_at(&pool, current_block)?;
+        let epoch = pool.stats_epoch.get();
+        let next_epoch = epoch.checked_add(U256::from(1u64)).ok_or_else(|| err(ERR_OVERFLOW))?;
+        let (volume0, volume1) = (pool.stats_volume0.get(), pool.stats_volume1.get());
+        let (fees0, fees1) = (pool.stats_fees0.get(), pool.stats_fees1.get());
+        let start_block = pool.stats_epoch_start_block.get();
+
+        {
+            let mut archived = pool.stats_history.setter(epoch);
+            archived.start_block.set(start_block);
+            archived.end_block.set(current_block);
+            archived.volume0.set(volume0);
+            archived.volume1.set(volume1);
+            archived.fees0.set(fees0);
+            archived.fees1.set(fees1);
+            archived.reserve0_area.set(area0);
+            archived.reserve1_area.set(area1);
+        }
+
+        pool.stats_volume0.set(U256::ZERO);
+        pool.stats_volume1.set(U256::ZERO);
+        pool.stats_fees0.set(U256::ZERO);
+        pool.stats_fees1.set(U256::ZERO);
+        pool.stats_reserve0_area.set(U256::ZERO);
+        pool.stats_reserve1_area.set(U256::ZERO);
+        pool.stats_area_block.set(current_block);
+        pool.stats_epoch.set(next_epoch);
+        pool.stats_epoch_start_block.set(current_block);
+
+        emit_pool_epoch_closed(token0, token1, epoch, volume0, volume1, fees0, fees1);
+        Ok(epoch)
+    }
+
+    /// View: (epoch, start_block, volume0, volume1, fees0, fees1) for the running epoch.
+    pub fn current(dex: &OakDEX, token0: Address, token1: Address) -> (U256, U256, U256, U256, U256, U256) {
+        let outer = dex.pools.getter(token0);
+        let pool = outer.getter(token1);
+        (
+            pool.stats_epoch.get(),
+            pool.stats_epoch_start_block.get(),
+            pool.stats_volume0.get(),
+            pool.stats_volume1.get(),
+            pool.stats_fees0.get(),
+            pool.stats_fees1.get(),
+        )
+    }
+
+    /// View: (start_block, end_block, volume0, volume1, fees0, fees1) of an archived epoch (zeros if not closed).
+    pub fn archived(
+        dex: &OakDEX,
+        token0: Address,
+        token1: Address,
+        epoch: U256,
+    ) -> (U256, U256, U256, U256, U256, U256) {
+        let outer = dex.pools.getter(token0);
+        let pool = outer.getter(token1);
+        let stats = pool.stats_history.getter(epoch);
+        (
+            stats.start_block.get(),
+            stats.end_block.get(),
+            stats.volume0.get(),
+            stats.volume1.get(),
+            stats.fees0.get(),
+            stats.fees1.get(),
+        )
+    }
+}
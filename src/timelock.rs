@@ -15,17 +15,13 @@ use stylus_sdk::{
 };
 
 use crate::{
-    access::{default_admin_role, require_role},
-    constants::TIMELOCK_MIN_DELAY_BLOCKS,
+    access::{require_role, ROLE_ADMIN, ROLE_TIMELOCK_ADMIN},
+    audit::{word, AdminAuditLog},
+    constants::{AUDIT_ACTION_QUEUE_TIMELOCK_OPERATION, TIMELOCK_MIN_DELAY_BLOCKS},
     errors::*,
     state::OakDEX,
 };
 
-/// Role that can queue timelock operations (e.g. multisig).
-pub fn timelock_admin_role() -> FixedBytes<32> {
-    crypto::keccak(b"TIMELOCK_ADMIN_ROLE")
-}
-
 /// Compute operation id = keccak256(abi.encode(target, value, data, predecessor, salt)).
 fn operation_id(
     target: Address,
@@ -71,18 +67,17 @@ pub fn queue_operation(
     predecessor: Address,
     salt: FixedBytes<32>,
     delay_blocks: u64,
-) -> Result<FixedBytes<32>, Vec<u8>> {
+) -> OakResult<FixedBytes<32>> {
     if delay_blocks < TIMELOCK_MIN_DELAY_BLOCKS {
         return Err(err(ERR_TIMELOCK_NOT_READY));
     }
-    if require_role(dex, timelock_admin_role()).is_err() && require_role(dex, default_admin_role()).is_err() {
-        return Err(err(ERR_MISSING_ROLE));
-    }
+    require_role(dex, ROLE_TIMELOCK_ADMIN | ROLE_ADMIN)?;
     let id = operation_id(target, value, data, predecessor, salt);
     let ready_at = U256::from(block::number())
         .checked_add(U256::from(delay_blocks))
         .ok_or_else(|| err(ERR_OVERFLOW))?;
     dex.timelock_ready_block.setter(id).set(ready_at);
+    AdminAuditLog::record(dex, AUDIT_ACTION_QUEUE_TIMELOCK_OPERATION, &[id, word(ready_at)])?;
     Ok(id)
 }
 
@@ -100,7 +95,7 @@ pub fn execute_operation(
     data: &[u8],
     predecessor: Address,
     salt: FixedBytes<32>,
-) -> Result<(), Vec<u8>> {
+) -> OakResult<()> {
     let id = operation_id(target, value, data, predecessor, salt);
     let ready_at = dex.timelock_ready_block.setter(id).get();
     if ready_at.is_zero() {
@@ -1,63 +1,266 @@
 //! ERC-20 token interface and safe transfer utilities for Oak Protocol.
 //!
-//! For now this module provides **host-test-friendly stubs** for token
-//! operations so that we can exercise the DEX math and state logic
-//! off-chain. On-chain Stylus integration (via `sol_interface!` and
-//! `Call`) can be reintroduced on top of these signatures.
+//! `safe_transfer` / `safe_transfer_from` make raw `transfer` / `transferFrom` calls and accept
+//! the return data with OpenZeppelin SafeERC20 semantics (see `returned_success`), so tokens
+//! that return nothing (USDT and other pre-standard tokens) work like compliant ones.
+//! `balance_of` and `decimals` are real static calls. Native ETH goes out with
+//! `call::transfer_eth`; native input is msg.value, claimed at most once per call (see
+//! `take_native_value`). `permit` submits a real ERC-2612 permit.
 
-use stylus_sdk::alloy_primitives::{Address, U256};
+use alloc::vec::Vec;
+use core::cell::Cell;
+use stylus_sdk::{
+    alloy_primitives::{Address, FixedBytes, U256},
+    call::{self, Call},
+    contract, function_selector, msg,
+    types::AddressVM,
+};
 
-use crate::errors::{err, OakResult, ERR_INVALID_ADDRESS, ERR_TOKEN_TRANSFER_FAILED};
+use crate::constants::MAX_TOKEN_DECIMALS;
+use crate::errors::{
+    err, OakResult, ERR_DECIMALS_UNAVAILABLE, ERR_INVALID_ADDRESS, ERR_NATIVE_VALUE_MISMATCH, ERR_PERMIT_INVALID_SIGNATURE,
+    ERR_TOKEN_TRANSFER_FAILED,
+};
 
-/// Safely transfer ERC-20 tokens from `from` to `to`.
+/// Pseudo-token for native ETH in pool keys and balances (`address(0)`).
 ///
-/// Host-side implementation performs only input validation and assumes
-/// success. On-chain, this should perform a real `transferFrom` call.
+/// @dev Sorts below every ERC-20, so it is always a pool's canonical token0. Input arrives as
+///      msg.value (only payable entrypoints can supply it); output goes out via `safe_transfer_eth`.
+pub const NATIVE_TOKEN: Address = Address::ZERO;
+
+/// SafeERC20 check of a `transfer` / `transferFrom` return value: empty (token returns nothing)
+/// or an ABI-encoded `true`. Anything else, including `false` or a malformed word, is a failure.
+///
+/// @dev Empty data only counts when the callee has code; see `call_token`.
+pub fn returned_success(ret: &[u8]) -> bool {
+    if ret.is_empty() {
+        return true;
+    }
+    ret.len() >= 32 && ret[..31].iter().all(|b| *b == 0) && ret[31] == 1
+}
+
+/// msg.value already claimed by native pulls during this call.
+///
+/// @dev Stylus runs every external call in a fresh program instance, so a static is per-call
+///      state; wasm has a single thread.
+struct NativeValueUsed(Cell<U256>);
+
+unsafe impl Sync for NativeValueUsed {}
+
+static NATIVE_VALUE_USED: NativeValueUsed = NativeValueUsed(Cell::new(U256::ZERO));
+
+/// msg.value claimed after taking `amount` more of `value`, of which `used` was already claimed.
+///
+/// Fails with NATIVE_VALUE_MISMATCH when the call did not carry enough unclaimed value, so one
+/// msg.value can never pay for two deposits.
+pub fn claim_native_value(value: U256, used: U256, amount: U256) -> OakResult<U256> {
+    match used.checked_add(amount) {
+        Some(total) if total <= value => Ok(total),
+        _ => Err(err(ERR_NATIVE_VALUE_MISMATCH)),
+    }
+}
+
+/// Claim `amount` of this call's msg.value for a native deposit.
+pub fn take_native_value(amount: U256) -> OakResult<()> {
+    let used = claim_native_value(msg::value(), NATIVE_VALUE_USED.0.get(), amount)?;
+    NATIVE_VALUE_USED.0.set(used);
+    Ok(())
+}
+
+/// Call `token` with `calldata` and require a SafeERC20-successful result.
+///
+/// @dev A call to an address without code also succeeds with empty data, so that case fails.
+fn call_token(token: Address, calldata: &[u8]) -> OakResult<()> {
+    let ret = call::call(Call::new(), token, calldata).map_err(|_| err(ERR_TOKEN_TRANSFER_FAILED))?;
+    if (ret.is_empty() && !token.has_code()) || !returned_success(&ret) {
+        return Err(err(ERR_TOKEN_TRANSFER_FAILED));
+    }
+    Ok(())
+}
+
+/// Safely transfer ERC-20 tokens from `from` to `to` (`transferFrom`).
+///
+/// `NATIVE_TOKEN` cannot be pulled: it only succeeds into this contract when the call carried at
+/// least `amount` of msg.value not already claimed by an earlier pull (`take_native_value`).
 pub fn safe_transfer_from(
     token: Address,
     from: Address,
     to: Address,
     amount: U256,
 ) -> OakResult<()> {
+    if token == NATIVE_TOKEN {
+        if to != contract::address() {
+            return Err(err(ERR_NATIVE_VALUE_MISMATCH));
+        }
+        return take_native_value(amount);
+    }
     if token == Address::ZERO || from == Address::ZERO || to == Address::ZERO {
         return Err(err(ERR_INVALID_ADDRESS));
     }
     if amount.is_zero() {
         return Ok(());
     }
-    Ok(())
+    let mut calldata = Vec::with_capacity(100);
+    calldata.extend_from_slice(&function_selector!("transferFrom", Address, Address, U256));
+    calldata.extend_from_slice(from.into_word().as_slice());
+    calldata.extend_from_slice(to.into_word().as_slice());
+    calldata.extend_from_slice(&amount.to_be_bytes::<32>());
+    call_token(token, &calldata)
 }
 
-/// Safely transfer ERC-20 tokens from this contract to `to`.
+/// Amount credited for a pull of `requested` that moved the contract balance from `before` to `after`.
 ///
-/// Host-side implementation performs only input validation and assumes
-/// success. On-chain, this should perform a real `transfer` call.
+/// @notice Fee-on-transfer tokens deliver less than requested; only the balance delta counts,
+///         capped at `requested` (any excess is an unaccounted donation). Nothing received fails.
+pub fn received_amount(before: U256, after: U256, requested: U256) -> OakResult<U256> {
+    let received = after
+        .checked_sub(before)
+        .ok_or_else(|| err(ERR_TOKEN_TRANSFER_FAILED))?
+        .min(requested);
+    if received.is_zero() {
+        return Err(err(ERR_TOKEN_TRANSFER_FAILED));
+    }
+    Ok(received)
+}
+
+/// Pull `amount` of ERC-20 `token` from `from` into this contract; returns what actually arrived
+/// (see `received_amount`).
+pub fn safe_transfer_from_received(token: Address, from: Address, amount: U256) -> OakResult<U256> {
+    let contract_addr = contract::address();
+    let before = balance_of(token, contract_addr);
+    safe_transfer_from(token, from, contract_addr, amount)?;
+    received_amount(before, balance_of(token, contract_addr), amount)
+}
+
+/// Safely transfer ERC-20 tokens from this contract to `to` (`transfer`).
+///
+/// `NATIVE_TOKEN` is sent as ETH via `safe_transfer_eth`.
 pub fn safe_transfer(token: Address, to: Address, amount: U256) -> OakResult<()> {
+    if token == NATIVE_TOKEN && to != Address::ZERO {
+        return safe_transfer_eth(to, amount);
+    }
     if token == Address::ZERO || to == Address::ZERO {
         return Err(err(ERR_INVALID_ADDRESS));
     }
     if amount.is_zero() {
         return Ok(());
     }
+    let mut calldata = Vec::with_capacity(68);
+    calldata.extend_from_slice(&function_selector!("transfer", Address, U256));
+    calldata.extend_from_slice(to.into_word().as_slice());
+    calldata.extend_from_slice(&amount.to_be_bytes::<32>());
+    call_token(token, &calldata)
+}
+
+/// Submit an ERC-2612 `permit(owner, spender, value, deadline, v, r, s)` to `token`.
+///
+/// @notice Whatever the call returns, the permit only counts once `allowance(owner, spender)`
+///         covers `value`. A call that merely does not revert proves nothing (a token with a
+///         non-reverting fallback, or a differently shaped `permit`), while a reverting one may
+///         have been front-run with the same signature and still have set the allowance.
+pub fn permit(
+    token: Address,
+    owner: Address,
+    spender: Address,
+    value: U256,
+    deadline: U256,
+    signature: (u8, FixedBytes<32>, FixedBytes<32>),
+) -> OakResult<()> {
+    if token == Address::ZERO || owner == Address::ZERO || spender == Address::ZERO {
+        return Err(err(ERR_INVALID_ADDRESS));
+    }
+    let calldata = encode_permit(owner, spender, value, deadline, signature);
+    let _ = call::call(Call::new(), token, &calldata);
+    check_permit_allowance(allowance(token, owner, spender), value)
+}
+
+/// Outcome of a submitted permit given the allowance read back afterwards: PERMIT_INVALID_SIGNATURE
+/// unless it covers `value`.
+pub fn check_permit_allowance(allowance_after: U256, value: U256) -> OakResult<()> {
+    if allowance_after < value {
+        return Err(err(ERR_PERMIT_INVALID_SIGNATURE));
+    }
     Ok(())
 }
 
-/// Get the balance of an ERC-20 token for a given address.
+/// ABI-encoded ERC-2612 `permit(owner, spender, value, deadline, v, r, s)` calldata.
+pub fn encode_permit(
+    owner: Address,
+    spender: Address,
+    value: U256,
+    deadline: U256,
+    signature: (u8, FixedBytes<32>, FixedBytes<32>),
+) -> Vec<u8> {
+    let (v, r, s) = signature;
+    let mut calldata = Vec::with_capacity(228);
+    calldata.extend_from_slice(&function_selector!(
+        "permit", Address, Address, U256, U256, u8, FixedBytes<32>, FixedBytes<32>
+    ));
+    calldata.extend_from_slice(owner.into_word().as_slice());
+    calldata.extend_from_slice(spender.into_word().as_slice());
+    calldata.extend_from_slice(&value.to_be_bytes::<32>());
+    calldata.extend_from_slice(&deadline.to_be_bytes::<32>());
+    calldata.extend_from_slice(&U256::from(v).to_be_bytes::<32>());
+    calldata.extend_from_slice(r.as_slice());
+    calldata.extend_from_slice(s.as_slice());
+    calldata
+}
+
+/// ERC-20 `allowance(owner, spender)` of `token`; a reverting or malformed call reads as zero.
+pub fn allowance(token: Address, owner: Address, spender: Address) -> U256 {
+    let mut calldata = Vec::with_capacity(68);
+    calldata.extend_from_slice(&function_selector!("allowance", Address, Address));
+    calldata.extend_from_slice(owner.into_word().as_slice());
+    calldata.extend_from_slice(spender.into_word().as_slice());
+    match call::static_call(Call::new(), token, &calldata) {
+        Ok(ret) if ret.len() >= 32 => U256::from_be_slice(&ret[..32]),
+        _ => U256::ZERO,
+    }
+}
+
+/// ERC-20 `balanceOf(account)` of `token`; the ETH balance of `account` for `NATIVE_TOKEN`.
 ///
-/// Host-side implementation always returns zero; this is sufficient for
-/// our pure-math tests that do not rely on actual balances.
-pub fn balance_of(_token: Address, _account: Address) -> U256 {
-    U256::ZERO
+/// @dev A reverting or malformed `balanceOf` reads as zero, so balance checks fail closed.
+pub fn balance_of(token: Address, account: Address) -> U256 {
+    if token == NATIVE_TOKEN {
+        return account.balance();
+    }
+    let mut calldata = Vec::with_capacity(36);
+    calldata.extend_from_slice(&function_selector!("balanceOf", Address));
+    calldata.extend_from_slice(account.into_word().as_slice());
+    match call::static_call(Call::new(), token, &calldata) {
+        Ok(ret) if ret.len() >= 32 => U256::from_be_slice(&ret[..32]),
+        _ => U256::ZERO,
+    }
 }
 
-/// Transfer native ETH from this contract to `to`.
+/// ERC-20 `decimals()` of `token` (18 for `NATIVE_TOKEN`).
 ///
-/// Host-side stub: returns `Ok(())` for zero amount and a generic error
-/// otherwise. On-chain, this should call `stylus_sdk::call::transfer_eth`.
-pub fn safe_transfer_eth(_to: Address, amount: U256) -> OakResult<()> {
+/// @dev Fails rather than guessing when the token has no (or a malformed) `decimals()`, or
+///      reports more than MAX_TOKEN_DECIMALS.
+pub fn decimals(token: Address) -> OakResult<u8> {
+    if token == NATIVE_TOKEN {
+        return Ok(18);
+    }
+    let ret = call::static_call(Call::new(), token, &function_selector!("decimals"))
+        .map_err(|_| err(ERR_DECIMALS_UNAVAILABLE))?;
+    if ret.len() < 32 || ret[..31].iter().any(|b| *b != 0) || ret[31] > MAX_TOKEN_DECIMALS {
+        return Err(err(ERR_DECIMALS_UNAVAILABLE));
+    }
+    Ok(ret[31])
+}
+
+/// Transfer native ETH from this contract to `to` (`call::transfer_eth`, all gas forwarded).
+///
+/// @dev Callers hold the reentrancy lock and have written their state first; a reverting
+///      recipient fails the whole call with TOKEN_TRANSFER_FAILED.
+pub fn safe_transfer_eth(to: Address, amount: U256) -> OakResult<()> {
     if amount.is_zero() {
         return Ok(());
     }
-    Err(err(ERR_TOKEN_TRANSFER_FAILED))
+    if to == Address::ZERO {
+        return Err(err(ERR_INVALID_ADDRESS));
+    }
+    call::transfer_eth(to, amount).map_err(|_| err(ERR_TOKEN_TRANSFER_FAILED))
 }
 
@@ -0,0 +1,129 @@
+//! Treasury split table: fan each treasury withdrawal out to several recipients by weight.
+//!
+//! Governance configures up to MAX_TREASURY_SPLITS recipients (e.g. operations, grants, an
+//! insurance fund) with bps weights summing to BPS. The table is applied when fees are
+//! withdrawn, not when they accrue, so swaps keep a single treasury bucket per token and a
+//! single `withdraw_treasury_fees` call pays every recipient its share.
+//!
+//! Like treasury rotation, a new table is proposed and installed after the treasury change
+//! delay. An empty table turns splitting off. A per-token fee destination, when set, takes
+//! precedence over the table for that token.
+
+use alloc::vec::Vec;
+
+use stylus_sdk::{alloy_primitives::{Address, U256}, contract};
+
+use crate::constants::{as_u256, BPS, MAX_TREASURY_SPLITS};
+use crate::errors::{err, MathResult, OakResult, ERR_INVALID_TREASURY_SPLIT, ERR_NO_PENDING_TREASURY_SPLIT, ERR_OVERFLOW};
+use crate::events::{emit_treasury_splits_proposed, emit_treasury_splits_set};
+use crate::state::OakDEX;
+
+/// Shares of `amount` by bps `weights`; the rounding remainder goes to the first recipient.
+///
+/// @dev The shares always sum to `amount` when the weights sum to BPS.
+pub fn split_amounts(amount: U256, weights: &[U256]) -> MathResult<Vec<U256>> {
+    let mut shares = Vec::with_capacity(weights.len());
+    let mut paid = U256::ZERO;
+    for &weight in weights {
+        let share = amount.checked_mul(weight).ok_or(ERR_OVERFLOW)? / as_u256(BPS);
+        paid = paid.checked_add(share).ok_or(ERR_OVERFLOW)?;
+        shares.push(share);
+    }
+    if let Some(first) = shares.first_mut() {
+        *first = first.checked_add(amount.checked_sub(paid).ok_or(ERR_OVERFLOW)?).ok_or(ERR_OVERFLOW)?;
+    }
+    Ok(shares)
+}
+
+/// Whether (recipients, weights) is a valid split table; an empty table is valid (off).
+///
+/// @notice At most MAX_TREASURY_SPLITS distinct recipients, none zero or `contract_addr`, each
+///         with a non-zero weight, weights summing to exactly BPS.
+pub fn splits_valid(recipients: &[Address], weights: &[U256], contract_addr: Address) -> bool {
+    if recipients.len() != weights.len() || recipients.len() as u64 > MAX_TREASURY_SPLITS {
+        return false;
+    }
+    if recipients.is_empty() {
+        return true;
+    }
+    let distinct = recipients
+        .iter()
+        .enumerate()
+        .all(|(i, r)| *r != Address::ZERO && *r != contract_addr && !recipients[..i].contains(r));
+    let total = weights.iter().try_fold(U256::ZERO, |acc, w| if w.is_zero() { None } else { acc.checked_add(*w) });
+    distinct && total == Some(as_u256(BPS))
+}
+
+/// Treasury split table (uses OakDEX treasury_split* storage).
+pub struct TreasurySplits;
+
+impl TreasurySplits {
+    /// Active table as (recipients, weights); empty when splitting is off.
+    pub fn table(dex: &OakDEX) -> (Vec<Address>, Vec<U256>) {
+        let count = dex.treasury_split_count.get();
+        let mut recipients = Vec::new();
+        let mut weights = Vec::new();
+        let mut i = U256::ZERO;
+        while i < count {
+            recipients.push(dex.treasury_split_recipient.get(i));
+            weights.push(dex.treasury_split_bps.get(i));
+            i += U256::from(1u64);
+        }
+        (recipients, weights)
+    }
+
+    /// Proposed table as (recipients, weights, installable-after block); block 0 = none pending.
+    pub fn pending(dex: &OakDEX) -> (Vec<Address>, Vec<U256>, U256) {
+        let count = dex.pending_treasury_split_count.get();
+        let mut recipients = Vec::new();
+        let mut weights = Vec::new();
+        let mut i = U256::ZERO;
+        while i < count {
+            recipients.push(dex.pending_treasury_split_recipient.get(i));
+            weights.push(dex.pending_treasury_split_bps.get(i));
+            i += U256::from(1u64);
+        }
+        (recipients, weights, dex.treasury_split_after_block.get())
+    }
+
+    /// Record (recipients, weights) as the pending table, installable from `after_block`.
+    pub fn propose(
+        dex: &mut OakDEX,
+        recipients: &[Address],
+        weights: &[U256],
+        after_block: U256,
+    ) -> OakResult<()> {
+        if !splits_valid(recipients, weights, contract::address()) {
+            return Err(err(ERR_INVALID_TREASURY_SPLIT));
+        }
+        for (i, (recipient, weight)) in recipients.iter().zip(weights).enumerate() {
+            let index = U256::from(i);
+            dex.pending_treasury_split_recipient.setter(index).set(*recipient);
+            dex.pending_treasury_split_bps.setter(index).set(*weight);
+        }
+        let count = U256::from(recipients.len());
+        dex.pending_treasury_split_count.set(count);
+        dex.treasury_split_after_block.set(after_block);
+        emit_treasury_splits_proposed(count, after_block);
+        Ok(())
+    }
+
+    /// Install the pending table; returns its length. The caller checks the delay.
+    pub fn execute(dex: &mut OakDEX) -> OakResult<U256> {
+        if dex.treasury_split_after_block.get().is_zero() {
+            return Err(err(ERR_NO_PENDING_TREASURY_SPLIT));
+        }
+        let (recipients, weights, _) = Self::pending(dex);
+        for (i, (recipient, weight)) in recipients.iter().zip(&weights).enumerate() {
+            let index = U256::from(i);
+            dex.treasury_split_recipient.setter(index).set(*recipient);
+            dex.treasury_split_bps.setter(index).set(*weight);
+        }
+        let count = U256::from(recipients.len());
+        dex.treasury_split_count.set(count);
+        dex.pending_treasury_split_count.set(U256::ZERO);
+        dex.treasury_split_after_block.set(U256::ZERO);
+        emit_treasury_splits_set(count);
+        Ok(count)
+    }
+}
@@ -4,9 +4,9 @@
 //! positions are aggregated in one tx (same block), then one Uniswap-style swap in the pool.
 //! Math is 100% checked; uses alloy_primitives (U256). Storage minimized (packed slot for last batch).
 
+use alloc::vec::Vec;
 use stylus_sdk::alloy_primitives::{Address, U256};
 use stylus_sdk::block;
-use stylus_sdk::contract;
 
 use crate::errors::{err, OakResult, ERR_DIVISION_BY_ZERO, ERR_OVERFLOW};
 use crate::errors::{
@@ -18,7 +18,7 @@ use crate::errors::{
     ERR_VAULT_INSUFFICIENT_RESERVE,
 };
 use crate::state::OakSentinel;
-use crate::token::{safe_transfer, safe_transfer_from};
+use crate::token::{safe_transfer, safe_transfer_from_received};
 
 /// Basis points divisor (10_000 = 100%).
 const BASIS_POINTS_DIVISOR: u64 = 10_000;
@@ -329,7 +329,8 @@ fn pack_batch_slot(block_num: u64, total_in: U256) -> U256 {
 ///
 /// @notice Shared Execution Batching. Caller passes list of (owner, amount_in). Contract pulls
 ///         token_in from each owner, executes one Uniswap-style swap in the pool, then distributes
-///         token_out proportionally. Minimizes storage (no per-tx batch accumulator; single packed slot for analytics).
+///         token_out proportionally to what each owner's pull actually delivered (fee-on-transfer
+///         tokens deliver less). Minimizes storage (no per-tx batch accumulator; single packed slot for analytics).
 pub fn batch_swap(
     state: &mut OakSentinel,
     token_in: Address,
@@ -343,25 +344,26 @@ pub fn batch_swap(
     if contributions.is_empty() {
         return Err(err(ERR_INSUFFICIENT_INPUT_AMOUNT));
     }
-    let contract_addr = contract::address();
+    // Fee-on-transfer tokens: each contribution counts for what actually arrived.
+    let mut received: Vec<U256> = Vec::with_capacity(contributions.len());
     let mut total_in = U256::ZERO;
-    for (_owner, amount) in contributions.iter() {
-        total_in = total_in.checked_add(*amount).ok_or_else(|| err(ERR_OVERFLOW))?;
+    for (owner, amount) in contributions.iter() {
+        let got = if amount.is_zero() {
+            U256::ZERO
+        } else {
+            safe_transfer_from_received(token_in, *owner, *amount)?
+        };
+        total_in = total_in.checked_add(got).ok_or_else(|| err(ERR_OVERFLOW))?;
+        received.push(got);
     }
     if total_in.is_zero() {
         return Err(err(ERR_INSUFFICIENT_INPUT_AMOUNT));
     }
-    for (owner, amount) in contributions.iter() {
-        if amount.is_zero() {
-            continue;
-        }
-        safe_transfer_from(token_in, *owner, contract_addr, *amount)?;
-    }
     let amount_out = _swap(state, token_in, token_out, total_in, price_in, price_out, fee_bps)?;
     if amount_out < min_amount_out {
         return Err(err(crate::errors::ERR_SLIPPAGE_EXCEEDED));
     }
-    for (owner, amount) in contributions.iter() {
+    for ((owner, _), amount) in contributions.iter().zip(received.iter()) {
         if amount.is_zero() {
             continue;
         }
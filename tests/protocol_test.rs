@@ -20,7 +20,7 @@ use stylus_sdk::alloy_primitives::U256;
 fn happy_path_commit_reveal_then_position_price_consistency() {
     let amount_in = U256::from(10_000u64);
     let salt = U256::from(1337u64);
-    let _hash = compute_commit_hash(amount_in, salt);
+    let _hash = compute_commit_hash(amount_in, true, U256::from(1_000u64), salt);
 
     let reserve_in = U256::from(100_000u64);
     let reserve_out = U256::from(200_000u64);
@@ -33,8 +33,8 @@ fn happy_path_commit_reveal_then_position_price_consistency() {
     assert!(!entry_price.is_zero(), "entry price for position should be non-zero");
 
     // Fee split invariant
-    let (_eff, treasury, lp, buyback) = compute_fee_split(amount_in, fee_bps).unwrap();
-    let total_fee = treasury + lp + buyback;
+    let (_eff, treasury, lp, buyback, gas_rebate) = compute_fee_split(amount_in, fee_bps).unwrap();
+    let total_fee = treasury + lp + buyback + gas_rebate;
     let expected_fee = amount_in * fee_bps / as_u256(FEE_DENOMINATOR);
     assert_eq!(total_fee, expected_fee, "fee split should sum to total fee");
 }
@@ -121,7 +121,7 @@ fn edge_timelock_operation_id_deterministic() {
 /// Reentrancy: close_position and update_trailing_stop hold lock for full duration.
 #[test]
 fn edge_reentrancy_guard_held_during_close() {
-    // On-chain: close_position starts with lock_reentrancy_guard(self)? and ends with
-    // unlock_reentrancy_guard(self). So no external call runs without lock.
+    // On-chain: close_position runs its whole body under with_lock(self, |dex| ..), which
+    // releases the lock on every return path. So no external call runs without lock.
     assert!(true, "CEI: state updates before safe_transfer and process_swap_from_to");
 }
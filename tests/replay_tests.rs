@@ -0,0 +1,60 @@
+//! Replay of recorded pool transitions: a faithful history replays cleanly, and a tampered
+//! step is reported as the first divergence.
+
+use oak_protocol::replay::{expected_transitions, replay, PoolSnapshot, ReplayAction, ReplayStep};
+use stylus_sdk::alloy_primitives::U256;
+
+fn u(x: u64) -> U256 {
+    U256::from(x)
+}
+
+fn history() -> Vec<ReplayAction> {
+    vec![
+        ReplayAction::AddLiquidity { amount0: u(1_000_000), amount1: u(4_000_000) },
+        ReplayAction::Swap { zero_for_one: true, amount_in: u(10_000), fee_bps: u(30) },
+        ReplayAction::Swap { zero_for_one: false, amount_in: u(25_000), fee_bps: u(30) },
+        ReplayAction::AddLiquidity { amount0: u(50_000), amount1: u(200_000) },
+        ReplayAction::RemoveLiquidity { share: u(300_000) },
+        ReplayAction::Sync { reserve0: u(760_000), reserve1: u(3_000_000) },
+        ReplayAction::Swap { zero_for_one: true, amount_in: u(5_000), fee_bps: u(100) },
+    ]
+}
+
+/// Steps as the chain would have recorded them if it followed the math exactly.
+fn recorded(actions: &[ReplayAction]) -> Vec<ReplayStep> {
+    let transitions = expected_transitions(PoolSnapshot::default(), actions);
+    assert_eq!(transitions.len(), actions.len(), "history must be valid");
+    actions.iter().zip(transitions).map(|(&action, observed)| ReplayStep { action, observed }).collect()
+}
+
+#[test]
+fn faithful_history_replays_to_its_final_state() {
+    let steps = recorded(&history());
+    let end = replay(PoolSnapshot::default(), &steps).expect("no divergence");
+    assert_eq!(end, steps.last().unwrap().observed.after);
+    // Swap fees that leave the reserves (treasury, buyback, LP accumulator) are accounted for:
+    // the pool grows by less than the input.
+    let swap = steps[1].observed;
+    let before = steps[0].observed.after;
+    assert!(swap.after.reserve0 > before.reserve0 && swap.after.reserve0 < before.reserve0 + u(10_000));
+    assert_eq!(swap.after.reserve1, before.reserve1 - swap.output.0);
+}
+
+#[test]
+fn first_divergent_step_is_reported() {
+    let mut steps = recorded(&history());
+    // One wei of reserve drift on the third step; later steps are left inconsistent too.
+    steps[2].observed.after.reserve1 += u(1);
+    steps[4].observed.output.0 += u(1);
+    let divergence = replay(PoolSnapshot::default(), &steps).unwrap_err();
+    assert_eq!(divergence.step, 2);
+    assert_eq!(divergence.before, steps[1].observed.after);
+    assert_eq!(divergence.expected.unwrap().after.reserve1 + u(1), divergence.observed.after.reserve1);
+
+    // A step the contract would have rejected diverges with the revert code.
+    let mut steps = recorded(&history());
+    steps[1].action = ReplayAction::Swap { zero_for_one: true, amount_in: u(1), fee_bps: u(30) };
+    let divergence = replay(PoolSnapshot::default(), &steps).unwrap_err();
+    assert_eq!(divergence.step, 1);
+    assert!(divergence.expected.is_err());
+}
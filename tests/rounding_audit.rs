@@ -0,0 +1,231 @@
+//! Rounding direction audit: every value-moving division rounds in the protocol's favor.
+//!
+//! Each case evaluates a math function twice on the same seeded random inputs, once with the
+//! directions chosen in the code and once with every direction flipped
+//! (`rounding::set_inverted`). Payouts must never exceed the flipped result, charges must never
+//! fall below it, and the composed swap / deposit / withdrawal flows must never shrink the pool
+//! invariant per LP share. Only built with the audit feature:
+//!
+//!   cargo test --features rounding-audit --test rounding_audit
+//!
+//! `OAK_ROUNDING_CASES` overrides the number of random cases per function.
+#![cfg(feature = "rounding-audit")]
+
+use std::sync::Mutex;
+
+use oak_protocol::{
+    constants::{as_u256, FEE_DENOMINATOR, MAX_FEE_BPS, MINIMUM_LIQUIDITY},
+    logic::{
+        compute_batch_crossing, compute_liquidity_burned, compute_liquidity_minted, get_amount_in_with_fee,
+        get_amount_out_with_fee, optimal_liquidity_amounts,
+    },
+    lp_fees::{earned_fees, fee_per_share_growth, lp_fee_scale},
+    rounding::{self, Rounding},
+};
+use stylus_sdk::alloy_primitives::{U256, U512};
+
+const DEFAULT_CASES: u64 = 2_000;
+const SEED: u64 = 0x0a4b_0d1e;
+
+/// The inversion flag is process-wide; cases must not interleave.
+static INVERSION: Mutex<()> = Mutex::new(());
+
+/// SplitMix64: tiny, seedable and identical on every platform.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `lo..=hi`.
+    fn range(&mut self, lo: u64, hi: u64) -> u64 {
+        lo + self.next() % (hi - lo + 1)
+    }
+
+    /// Non-zero amount of 1..=bits random bits: dust and whale sizes alike.
+    fn amount(&mut self, bits: u64) -> U256 {
+        let len = self.range(1, bits) as usize;
+        let raw = (U256::from(self.next()) << 64) | U256::from(self.next());
+        let raw = (raw << 64) | U256::from(self.next());
+        let masked: U256 = raw & ((U256::from(1u64) << len) - U256::from(1u64));
+        masked.max(U256::from(1u64))
+    }
+
+    fn fee_bps(&mut self) -> U256 {
+        U256::from(self.range(0, MAX_FEE_BPS as u64))
+    }
+}
+
+fn cases() -> u64 {
+    std::env::var("OAK_ROUNDING_CASES").ok().and_then(|v| v.parse().ok()).unwrap_or(DEFAULT_CASES)
+}
+
+/// `f` with the directions as written, then with every direction flipped.
+fn both<T>(f: impl Fn() -> T) -> (T, T) {
+    let _guard = INVERSION.lock().unwrap_or_else(|e| e.into_inner());
+    rounding::set_inverted(false);
+    let chosen = f();
+    rounding::set_inverted(true);
+    let flipped = f();
+    rounding::set_inverted(false);
+    (chosen, flipped)
+}
+
+fn wide(x: U256) -> U512 {
+    U512::from(x)
+}
+
+#[test]
+fn div_rounds_as_named_and_flips() {
+    let (seven, three) = (U256::from(7u64), U256::from(3u64));
+    let (down, flipped_down) = both(|| rounding::div(seven, three, Rounding::Down).unwrap());
+    let (up, flipped_up) = both(|| rounding::div(seven, three, Rounding::Up).unwrap());
+    assert_eq!((down, up), (U256::from(2u64), U256::from(3u64)));
+    assert_eq!((flipped_down, flipped_up), (up, down));
+    let (exact, _) = both(|| rounding::div(U256::from(9u64), three, Rounding::Up).unwrap());
+    assert_eq!(exact, three);
+    assert!(rounding::div(seven, U256::ZERO, Rounding::Up).is_err());
+}
+
+#[test]
+fn swap_output_never_overpays() {
+    let mut rng = Rng(SEED);
+    let mut differing = 0u64;
+    for case in 0..cases() {
+        let (amount_in, reserve_in, reserve_out, fee) = (rng.amount(96), rng.amount(112), rng.amount(112), rng.fee_bps());
+        let (chosen, flipped) = both(|| get_amount_out_with_fee(amount_in, reserve_in, reserve_out, fee));
+        let (Ok(out), Ok(flipped)) = (chosen, flipped) else { continue };
+        assert!(out <= flipped, "case {case}: output {out} above flipped {flipped}");
+        differing += u64::from(out != flipped);
+        if out.is_zero() {
+            continue;
+        }
+        // The pool invariant never shrinks: (x + in_with_fee / D) * (y - out) >= x * y, scaled by D.
+        let d = wide(as_u256(FEE_DENOMINATOR));
+        let in_with_fee = wide(amount_in) * wide(as_u256(FEE_DENOMINATOR) - fee);
+        let after = (wide(reserve_in) * d + in_with_fee) * (wide(reserve_out) - wide(out));
+        assert!(after >= wide(reserve_in) * wide(reserve_out) * d, "case {case}: k decreased");
+    }
+    assert!(differing > 0, "no case exercised a remainder");
+}
+
+#[test]
+fn swap_input_quote_never_undercharges() {
+    let mut rng = Rng(SEED ^ 1);
+    let mut differing = 0u64;
+    for case in 0..cases() {
+        let (reserve_in, reserve_out, fee) = (rng.amount(112), rng.amount(112), rng.fee_bps());
+        let amount_out = rng.amount(112) % reserve_out;
+        if amount_out.is_zero() {
+            continue;
+        }
+        let (chosen, flipped) = both(|| get_amount_in_with_fee(amount_out, reserve_in, reserve_out, fee));
+        let (Ok(amount_in), Ok(flipped)) = (chosen, flipped) else { continue };
+        assert!(amount_in >= flipped, "case {case}: quote {amount_in} below flipped {flipped}");
+        differing += u64::from(amount_in != flipped);
+        // Paying the quote always buys at least the requested output.
+        let (bought, _) = both(|| get_amount_out_with_fee(amount_in, reserve_in, reserve_out, fee));
+        if let Ok(bought) = bought {
+            assert!(bought >= amount_out, "case {case}: quote {amount_in} buys {bought} < {amount_out}");
+        }
+    }
+    assert!(differing > 0, "no case exercised a remainder");
+}
+
+#[test]
+fn deposits_never_dilute_existing_shares() {
+    let mut rng = Rng(SEED ^ 2);
+    let mut differing = 0u64;
+    for case in 0..cases() {
+        let (reserve0, reserve1) = (rng.amount(100), rng.amount(100));
+        let supply = rng.amount(100).max(as_u256(MINIMUM_LIQUIDITY));
+        let (desired0, desired1) = (rng.amount(100), rng.amount(100));
+        let (Ok((used0, used1)), _) = both(|| optimal_liquidity_amounts(desired0, desired1, reserve0, reserve1)) else {
+            continue;
+        };
+        assert!(used0 <= desired0 && used1 <= desired1, "case {case}: took more than desired");
+        let (chosen, flipped) = both(|| compute_liquidity_minted(used0, used1, reserve0, reserve1, supply));
+        let Ok(minted) = chosen else { continue };
+        if let Ok(flipped) = flipped {
+            assert!(minted <= flipped, "case {case}: minted {minted} above flipped {flipped}");
+            differing += u64::from(minted != flipped);
+        }
+        // Each share keeps at least its claim on both reserves: r' * S >= r * S'.
+        let new_supply = wide(supply) + wide(minted);
+        assert!(wide(reserve0 + used0) * wide(supply) >= wide(reserve0) * new_supply, "case {case}: token0 diluted");
+        assert!(wide(reserve1 + used1) * wide(supply) >= wide(reserve1) * new_supply, "case {case}: token1 diluted");
+    }
+    assert!(differing > 0, "no case exercised a remainder");
+}
+
+#[test]
+fn withdrawals_never_overpay() {
+    let mut rng = Rng(SEED ^ 3);
+    let mut differing = 0u64;
+    for case in 0..cases() {
+        let (reserve0, reserve1) = (rng.amount(112), rng.amount(112));
+        let supply = rng.amount(112).max(U256::from(2u64));
+        let share = rng.amount(112) % supply;
+        let (chosen, flipped) = both(|| compute_liquidity_burned(share, reserve0, reserve1, supply));
+        let Ok((amount0, amount1)) = chosen else { continue };
+        let (flipped0, flipped1) = flipped.expect("rounding up cannot pay zero where rounding down did not");
+        assert!(amount0 <= flipped0 && amount1 <= flipped1, "case {case}: withdrawal above flipped");
+        differing += u64::from((amount0, amount1) != (flipped0, flipped1));
+        // Remaining shares keep at least their claim: (r - a) * S >= r * (S - share).
+        let remaining = wide(supply) - wide(share);
+        assert!(wide(reserve0 - amount0) * wide(supply) >= wide(reserve0) * remaining, "case {case}: token0 leak");
+        assert!(wide(reserve1 - amount1) * wide(supply) >= wide(reserve1) * remaining, "case {case}: token1 leak");
+    }
+    assert!(differing > 0, "no case exercised a remainder");
+}
+
+#[test]
+fn batch_crossing_never_overpays_the_converted_leg() {
+    let mut rng = Rng(SEED ^ 4);
+    let mut differing = 0u64;
+    for case in 0..cases() {
+        let (total_x, total_y, reserve_x, reserve_y) = (rng.amount(96), rng.amount(96), rng.amount(112), rng.amount(112));
+        let (chosen, _) = both(|| compute_batch_crossing(total_x, total_y, reserve_x, reserve_y));
+        let (crossed_x, crossed_y) = chosen.expect("non-zero inputs");
+        assert!(crossed_x <= total_x && crossed_y <= total_y, "case {case}: crossed more than offered");
+        // The converted leg is never worth more than the full leg it is crossed against.
+        assert!(
+            wide(crossed_x) * wide(reserve_y) <= wide(crossed_y) * wide(reserve_x) || crossed_x == total_x,
+            "case {case}: x leg overpaid"
+        );
+        assert!(
+            wide(crossed_y) * wide(reserve_x) <= wide(crossed_x) * wide(reserve_y) || crossed_y == total_y,
+            "case {case}: y leg overpaid"
+        );
+        let y_in_x = wide(total_y) * wide(reserve_x);
+        differing += u64::from(!(y_in_x % wide(reserve_y)).is_zero());
+    }
+    assert!(differing > 0, "no case exercised a remainder");
+}
+
+#[test]
+fn lp_fee_accounting_never_pays_more_than_accrued() {
+    let mut rng = Rng(SEED ^ 5);
+    let mut differing = 0u64;
+    for case in 0..cases() {
+        let (lp_fee, supply) = (rng.amount(96), rng.amount(112));
+        let (chosen, flipped) = both(|| fee_per_share_growth(lp_fee, supply).unwrap());
+        let ((growth, distributed), (flipped_growth, flipped_distributed)) = (chosen, flipped);
+        assert!(growth <= flipped_growth && distributed <= flipped_distributed, "case {case}: growth above flipped");
+        assert!(distributed <= lp_fee, "case {case}: distributed {distributed} > fee {lp_fee}");
+        differing += u64::from(distributed != flipped_distributed);
+        // Holders claiming all of `supply` never receive more than was distributed.
+        let holder = rng.amount(112) % supply;
+        let (mine, flipped_mine) = both(|| earned_fees(holder, growth, U256::ZERO).unwrap());
+        let (rest, _) = both(|| earned_fees(supply - holder, growth, U256::ZERO).unwrap());
+        assert!(mine <= flipped_mine, "case {case}: claim above flipped");
+        assert!(mine + rest <= distributed, "case {case}: claims exceed distribution");
+        assert!(wide(growth) * wide(supply) <= wide(lp_fee) * wide(lp_fee_scale()), "case {case}: growth overstated");
+    }
+    assert!(differing > 0, "no case exercised a remainder");
+}
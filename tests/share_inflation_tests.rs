@@ -0,0 +1,159 @@
+//! Share-inflation regression tests for LP minting.
+//!
+//! "First depositor" attack: mint a dust share, donate a large amount so the share becomes
+//! expensive, and let a victim's deposit round down to few (or zero) shares that the attacker
+//! then redeems against. The defences exercised here, with the contract's own math:
+//! - MINIMUM_LIQUIDITY shares are locked forever on the first deposit, so the attacker owns at
+//!   most a fraction of the supply and pays for most of any donation they inflate it with;
+//! - reserves are booked, not read from balances: a raw transfer to the contract moves no
+//!   price until governance `sync`s it (modelled here as if the attacker could), and only
+//!   governance can `skim` it;
+//! - deposits that would mint zero shares revert instead of being absorbed.
+
+use oak_protocol::{
+    constants::{as_u256, MINIMUM_LIQUIDITY},
+    errors::ERR_INSUFFICIENT_LIQUIDITY,
+    logic::compute_liquidity_minted,
+    solvency::{balance_discrepancy, synced_reserve},
+};
+use stylus_sdk::alloy_primitives::U256;
+
+fn u(v: u128) -> U256 {
+    U256::from(v)
+}
+
+const E18: u128 = 1_000_000_000_000_000_000;
+
+/// Symmetric pool: both tokens move together, so one side is enough to track value.
+#[derive(Clone, Copy)]
+struct Pool {
+    reserve: U256,
+    balance: U256,
+    supply: U256,
+}
+
+impl Pool {
+    fn empty() -> Self {
+        Pool { reserve: U256::ZERO, balance: U256::ZERO, supply: U256::ZERO }
+    }
+
+    fn deposit(&mut self, amount: U256) -> Result<U256, &'static [u8]> {
+        let first = self.supply.is_zero();
+        let shares = compute_liquidity_minted(amount, amount, self.reserve, self.reserve, self.supply)?;
+        self.supply += if first { shares + as_u256(MINIMUM_LIQUIDITY) } else { shares };
+        self.reserve += amount;
+        self.balance += amount;
+        Ok(shares)
+    }
+
+    /// Pro-rata, floor-rounded withdrawal as in `process_remove_liquidity`.
+    fn withdraw(&mut self, shares: U256) -> U256 {
+        let amount = self.reserve * shares / self.supply;
+        self.supply -= shares;
+        self.reserve -= amount;
+        self.balance -= amount;
+        amount
+    }
+
+    fn donate(&mut self, amount: U256) {
+        self.balance += amount;
+    }
+
+    fn sync(&mut self) {
+        let (surplus, _) = balance_discrepancy(self.balance, self.reserve);
+        self.reserve = synced_reserve(self.reserve, surplus).unwrap();
+    }
+}
+
+/// Attacker's net result (per token) of: dust first deposit, donate + sync, victim deposits,
+/// attacker exits. Returns (attacker profit or loss, victim's shares).
+fn run_attack(first: U256, donation: U256, victim_deposit: U256) -> (i128, Result<U256, &'static [u8]>) {
+    let mut pool = Pool::empty();
+    let attacker_shares = pool.deposit(first).unwrap();
+    pool.donate(donation);
+    pool.sync();
+    let victim = pool.deposit(victim_deposit);
+    let out = pool.withdraw(attacker_shares);
+    let spent = first + donation;
+    let pnl = if out >= spent {
+        i128::try_from(out - spent).unwrap()
+    } else {
+        -i128::try_from(spent - out).unwrap()
+    };
+    (pnl, victim)
+}
+
+#[test]
+fn dust_first_deposit_is_rejected() {
+    let mut pool = Pool::empty();
+    // sqrt(1000 * 1000) == MINIMUM_LIQUIDITY leaves nothing to mint.
+    assert_eq!(pool.deposit(as_u256(MINIMUM_LIQUIDITY)), Err(ERR_INSUFFICIENT_LIQUIDITY));
+    assert_eq!(pool.deposit(as_u256(MINIMUM_LIQUIDITY + 1)), Ok(u(1)));
+    assert_eq!(pool.supply, as_u256(MINIMUM_LIQUIDITY + 1));
+}
+
+#[test]
+fn classic_first_depositor_attack_is_unprofitable() {
+    // One share against 1_000 locked ones, then a 10_000-token donation pushed into reserves.
+    let (pnl, victim) = run_attack(u(1_001), u(10_000 * E18), u(20_000 * E18));
+    let victim_shares = victim.expect("victim still mints shares");
+    assert!(victim_shares >= u(1_000), "victim shares {victim_shares}");
+    // The locked shares absorb ~99.9% of the donation.
+    assert!(pnl < -(9_900 * E18 as i128), "attacker pnl {pnl}");
+}
+
+#[test]
+fn inflation_attack_loses_money_across_sizes() {
+    let firsts = [1_001u128, 5_000, 1_000_000, E18];
+    let donations = [1u128, 1_000_000, E18, 1_000 * E18, 1_000_000 * E18];
+    let victims = [1u128, 999, E18, 1_000 * E18, 5_000_000 * E18];
+    for first in firsts {
+        for donation in donations {
+            for victim in victims {
+                let (pnl, _) = run_attack(u(first), u(donation), u(victim));
+                assert!(pnl <= 0, "profitable: first={first} donation={donation} victim={victim} pnl={pnl}");
+            }
+        }
+    }
+}
+
+#[test]
+fn victim_deposit_rounding_to_zero_reverts() {
+    let mut pool = Pool::empty();
+    pool.deposit(u(1_001)).unwrap();
+    pool.donate(u(1_000_000 * E18));
+    pool.sync();
+    // One share now costs ~1e24 tokens; a smaller deposit must not be silently absorbed.
+    let before = pool;
+    assert_eq!(pool.deposit(u(E18)), Err(ERR_INSUFFICIENT_LIQUIDITY));
+    assert_eq!((pool.reserve, pool.supply), (before.reserve, before.supply));
+}
+
+#[test]
+fn unsynced_donation_does_not_move_share_price() {
+    let mut honest = Pool::empty();
+    honest.deposit(u(1_000 * E18)).unwrap();
+    let mut donated = honest;
+    donated.donate(u(500 * E18));
+
+    // Minting reads booked reserves only, so the donation buys the attacker nothing.
+    assert_eq!(honest.deposit(u(7 * E18)), donated.deposit(u(7 * E18)));
+    // It sits as surplus until governance syncs or skims it.
+    let (surplus, deficit) = balance_discrepancy(donated.balance, donated.reserve);
+    assert_eq!((surplus, deficit), (u(500 * E18), U256::ZERO));
+}
+
+#[test]
+fn victim_loss_is_bounded_by_one_share() {
+    let mut pool = Pool::empty();
+    let attacker_shares = pool.deposit(u(1_001)).unwrap();
+    pool.donate(u(3 * E18));
+    pool.sync();
+    let share_price = pool.reserve / pool.supply;
+    let deposit = u(1_000 * E18) + u(12_345);
+    let victim_shares = pool.deposit(deposit).unwrap();
+    pool.withdraw(attacker_shares);
+    let redeemed = pool.withdraw(victim_shares);
+    // Floor rounding costs the victim less than one share's worth (plus one wei per step).
+    assert!(redeemed + share_price + u(2) >= deposit, "redeemed {redeemed} of {deposit}");
+}
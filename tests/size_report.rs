@@ -0,0 +1,65 @@
+//! WASM size budget report.
+//!
+//! Stylus rejects programs above 24KB brotli-compressed. Build the contract with the release
+//! profile (opt-level "z", LTO, one codegen unit, stripped, panic = "abort"), then run:
+//!
+//!   cargo build --release --target wasm32-unknown-unknown --lib
+//!   cargo test --test size_report -- --ignored --nocapture
+//!
+//! The check is `#[ignore]`d because it needs the wasm artifact, which host-only test runs do
+//! not build; when run, a missing artifact is a failure, not a skip. Trim optional subsystems
+//! with `--no-default-features --features orders,oracle` etc.
+
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Stylus compressed WASM limit (brotli).
+const MAX_COMPRESSED_WASM_BYTES: usize = 24 * 1024;
+
+/// Brotli quality and window used by `cargo stylus check`.
+const BROTLI_QUALITY: u32 = 11;
+const BROTLI_WINDOW: u32 = 22;
+
+fn wasm_artifact() -> PathBuf {
+    if let Ok(path) = std::env::var("OAK_WASM_PATH") {
+        return PathBuf::from(path);
+    }
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("target/wasm32-unknown-unknown/release/oak_protocol.wasm")
+}
+
+fn brotli_len(bytes: &[u8]) -> usize {
+    let mut out = Vec::new();
+    {
+        let mut writer = brotli::CompressorWriter::new(&mut out, 4096, BROTLI_QUALITY, BROTLI_WINDOW);
+        writer.write_all(bytes).expect("brotli compression");
+    }
+    out.len()
+}
+
+#[test]
+fn brotli_len_compresses_redundant_input() {
+    let zeros = vec![0u8; 64 * 1024];
+    assert!(brotli_len(&zeros) < 64);
+}
+
+#[test]
+#[ignore = "needs the release wasm artifact; run with --ignored after building it"]
+fn wasm_size_within_budget() {
+    let path = wasm_artifact();
+    let wasm = std::fs::read(&path)
+        .unwrap_or_else(|e| panic!("size_report: {} not built ({e})", path.display()));
+    let compressed = brotli_len(&wasm);
+    eprintln!(
+        "size_report: {} = {} bytes, {} bytes brotli ({}% of {} byte budget)",
+        path.display(),
+        wasm.len(),
+        compressed,
+        compressed * 100 / MAX_COMPRESSED_WASM_BYTES,
+        MAX_COMPRESSED_WASM_BYTES
+    );
+    assert!(
+        compressed <= MAX_COMPRESSED_WASM_BYTES,
+        "WASM artifact exceeds the Stylus 24KB compressed limit"
+    );
+}
@@ -0,0 +1,434 @@
+//! Long-running soak scenarios against the hosted pool model.
+//!
+//! A seeded generator drives thousands of random steps (traders, LPs, keepers, flash
+//! borrowers, fee changes, pauses, donations and syncs) through the same pricing, fee-split,
+//! deposit and sync math the contract uses. After every step the accounting invariants are
+//! checked and any violation is recorded with its step; per-action gas is tallied from the
+//! `REVEAL_GAS_*` cost model (hosted runs have no metered gas).
+//!
+//! CI runs the default length. For audit evidence run longer and keep the report:
+//!
+//!   OAK_SOAK_STEPS=200000 OAK_SOAK_SEED=7 OAK_SOAK_REPORT=soak.txt \
+//!     cargo test --release --test soak_tests -- --nocapture
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+use oak_protocol::{
+    constants::{
+        as_u256, BPS, FEE_DENOMINATOR, INITIAL_FEE, MAX_FEE_BPS, MAX_TRADE_RESERVE_BPS, MINIMUM_LIQUIDITY,
+        REVEAL_GAS_BASE, REVEAL_GAS_SWAP, REVEAL_GAS_TOKEN_TRANSFER,
+    },
+    logic::{compute_fee_split, get_amount_out_with_fee, optimal_liquidity_amounts},
+    solvency::{balance_discrepancy, synced_reserve},
+};
+use stylus_sdk::alloy_primitives::{U256, U512};
+
+const DEFAULT_STEPS: u64 = 5_000;
+const DEFAULT_SEED: u64 = 0x0a4b_5eed;
+const LPS: usize = 4;
+/// Violations kept in the report; the count is always exact.
+const MAX_REPORTED_VIOLATIONS: usize = 20;
+
+/// SplitMix64: tiny, seedable and identical on every platform.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `lo..=hi`.
+    fn range(&mut self, lo: u64, hi: u64) -> u64 {
+        lo + self.next() % (hi - lo + 1)
+    }
+
+    fn chance(&mut self, one_in: u64) -> bool {
+        self.next() % one_in == 0
+    }
+}
+
+/// One pool plus the contract balances and fee buckets of its two tokens.
+#[derive(Clone)]
+struct Model {
+    reserve: [U256; 2],
+    treasury: [U256; 2],
+    buyback: [U256; 2],
+    balance: [U256; 2],
+    lp_supply: U256,
+    lp: [U256; LPS],
+    fee_bps: U256,
+    paused: bool,
+}
+
+type Step = Result<u64, &'static str>;
+
+fn swap_gas() -> u64 {
+    REVEAL_GAS_BASE + REVEAL_GAS_SWAP + 2 * REVEAL_GAS_TOKEN_TRANSFER
+}
+
+impl Model {
+    fn seeded(amount0: U256, amount1: U256) -> Self {
+        let min_lp = as_u256(MINIMUM_LIQUIDITY);
+        let liquidity = isqrt(amount0 * amount1);
+        let mut lp = [U256::ZERO; LPS];
+        lp[0] = liquidity - min_lp;
+        Model {
+            reserve: [amount0, amount1],
+            treasury: [U256::ZERO; 2],
+            buyback: [U256::ZERO; 2],
+            balance: [amount0, amount1],
+            lp_supply: liquidity,
+            lp,
+            fee_bps: as_u256(INITIAL_FEE),
+            paused: false,
+        }
+    }
+
+    fn swap(&mut self, i: usize, amount_in: U256) -> Step {
+        if self.paused {
+            return Err("PAUSED");
+        }
+        let o = 1 - i;
+        let max_trade = self.reserve[i] * as_u256(MAX_TRADE_RESERVE_BPS) / as_u256(BPS);
+        if amount_in.is_zero() || amount_in > max_trade {
+            return Err("TRADE_TOO_LARGE");
+        }
+        let amount_out = get_amount_out_with_fee(amount_in, self.reserve[i], self.reserve[o], self.fee_bps)
+            .map_err(|_| "PRICING")?;
+        if amount_out.is_zero() {
+            return Err("DUST_AMOUNT");
+        }
+        let (_, treasury_fee, _, buyback_fee, gas_rebate) =
+            compute_fee_split(amount_in, self.fee_bps).map_err(|_| "FEE_SPLIT")?;
+        // Direct swaps have no revealer: the gas rebate share stays with the treasury.
+        let treasury_fee = treasury_fee + gas_rebate;
+        let new_out = self.reserve[o] - amount_out;
+        if new_out < as_u256(MINIMUM_LIQUIDITY) {
+            return Err("INSUFFICIENT_LIQUIDITY");
+        }
+        self.reserve[i] += amount_in - treasury_fee - buyback_fee;
+        self.reserve[o] = new_out;
+        self.treasury[i] += treasury_fee;
+        self.buyback[i] += buyback_fee;
+        self.balance[i] += amount_in;
+        self.balance[o] -= amount_out;
+        Ok(swap_gas())
+    }
+
+    fn add_liquidity(&mut self, who: usize, desired0: U256, desired1: U256) -> Step {
+        if self.paused {
+            return Err("PAUSED");
+        }
+        let (used0, used1) =
+            optimal_liquidity_amounts(desired0, desired1, self.reserve[0], self.reserve[1]).map_err(|_| "RATIO")?;
+        let liquidity = (used0 * self.lp_supply / self.reserve[0]).min(used1 * self.lp_supply / self.reserve[1]);
+        if liquidity.is_zero() {
+            return Err("INSUFFICIENT_LIQUIDITY_MINTED");
+        }
+        self.reserve[0] += used0;
+        self.reserve[1] += used1;
+        self.balance[0] += used0;
+        self.balance[1] += used1;
+        self.lp_supply += liquidity;
+        self.lp[who] += liquidity;
+        Ok(swap_gas())
+    }
+
+    fn remove_liquidity(&mut self, who: usize, share: U256) -> Step {
+        if share.is_zero() || share > self.lp[who] {
+            return Err("INSUFFICIENT_LP_BALANCE");
+        }
+        let amount0 = self.reserve[0] * share / self.lp_supply;
+        let amount1 = self.reserve[1] * share / self.lp_supply;
+        if amount0.is_zero() || amount1.is_zero() {
+            return Err("INSUFFICIENT_LIQUIDITY");
+        }
+        self.reserve[0] -= amount0;
+        self.reserve[1] -= amount1;
+        self.balance[0] -= amount0;
+        self.balance[1] -= amount1;
+        self.lp_supply -= share;
+        self.lp[who] -= share;
+        Ok(swap_gas())
+    }
+
+    /// Borrow `amount` of token `i` and repay `repay`; the contract's k-with-fee check decides.
+    fn flash(&mut self, i: usize, amount: U256, repay: U256) -> Step {
+        if self.paused {
+            return Err("PAUSED");
+        }
+        if amount.is_zero() || amount >= self.reserve[i] {
+            return Err("INSUFFICIENT_LIQUIDITY");
+        }
+        let mut after = self.reserve;
+        after[i] = after[i] - amount + repay;
+        let (k_before, _) = self.share_value();
+        let k_after = U512::from(after[0]) * U512::from(after[1]);
+        let fee_multiplier = U512::from(as_u256(FEE_DENOMINATOR) + self.fee_bps);
+        if k_after * U512::from(as_u256(FEE_DENOMINATOR)) < k_before * fee_multiplier {
+            return Err("INSUFFICIENT_LIQUIDITY");
+        }
+        self.balance[i] = self.balance[i] - amount + repay;
+        self.reserve = after;
+        Ok(swap_gas())
+    }
+
+    fn withdraw_fees(&mut self, i: usize) -> Step {
+        let accrued = self.treasury[i] + self.buyback[i];
+        if accrued.is_zero() {
+            return Err("NO_TREASURY_FEES");
+        }
+        self.balance[i] -= accrued;
+        self.treasury[i] = U256::ZERO;
+        self.buyback[i] = U256::ZERO;
+        Ok(REVEAL_GAS_BASE + 2 * REVEAL_GAS_TOKEN_TRANSFER)
+    }
+
+    fn owed(&self, i: usize) -> U256 {
+        self.reserve[i] + self.treasury[i] + self.buyback[i]
+    }
+
+    fn sync(&mut self) -> Step {
+        if self.paused {
+            return Err("PAUSED");
+        }
+        for i in 0..2 {
+            let (surplus, _) = balance_discrepancy(self.balance[i], self.owed(i));
+            self.reserve[i] = synced_reserve(self.reserve[i], surplus).map_err(|_| "SYNC")?;
+        }
+        Ok(REVEAL_GAS_BASE + REVEAL_GAS_SWAP)
+    }
+
+    /// Value of one LP share squared, as a fraction: k / supply^2.
+    fn share_value(&self) -> (U512, U512) {
+        let k = U512::from(self.reserve[0]) * U512::from(self.reserve[1]);
+        let supply = U512::from(self.lp_supply);
+        (k, supply * supply)
+    }
+}
+
+fn isqrt(x: U256) -> U256 {
+    x.root(2)
+}
+
+#[derive(Default)]
+struct ActionStats {
+    ok: u64,
+    reverted: u64,
+    gas_total: u64,
+    gas_min: u64,
+    gas_max: u64,
+}
+
+struct Report {
+    seed: u64,
+    steps: u64,
+    stats: BTreeMap<&'static str, ActionStats>,
+    reverts: BTreeMap<&'static str, u64>,
+    violations: Vec<String>,
+    violation_count: u64,
+    final_state: String,
+}
+
+impl Report {
+    fn violation(&mut self, step: u64, action: &str, msg: String) {
+        self.violation_count += 1;
+        if self.violations.len() < MAX_REPORTED_VIOLATIONS {
+            self.violations.push(format!("step {step} ({action}): {msg}"));
+        }
+    }
+
+    fn render(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "oak soak: seed={:#x} steps={}", self.seed, self.steps);
+        let _ = writeln!(out, "{:<14}{:>8}{:>10}{:>12}{:>12}{:>12}", "action", "ok", "reverted", "gas_min", "gas_avg", "gas_max");
+        for (name, s) in &self.stats {
+            let avg = if s.ok == 0 { 0 } else { s.gas_total / s.ok };
+            let _ = writeln!(
+                out,
+                "{:<14}{:>8}{:>10}{:>12}{:>12}{:>12}",
+                name, s.ok, s.reverted, s.gas_min, avg, s.gas_max
+            );
+        }
+        let _ = writeln!(out, "reverts by reason: {:?}", self.reverts);
+        let _ = writeln!(out, "final state: {}", self.final_state);
+        let _ = writeln!(out, "invariant violations: {}", self.violation_count);
+        for v in &self.violations {
+            let _ = writeln!(out, "  {v}");
+        }
+        out
+    }
+}
+
+/// Accounting invariants that must hold between any two steps.
+fn check_invariants(m: &Model, prev: &Model, action: &str) -> Vec<String> {
+    let mut found = Vec::new();
+    for i in 0..2 {
+        let (_, deficit) = balance_discrepancy(m.balance[i], m.owed(i));
+        if !deficit.is_zero() {
+            found.push(format!("token{i} insolvent by {deficit}"));
+        }
+        if m.reserve[i] < as_u256(MINIMUM_LIQUIDITY) {
+            found.push(format!("reserve{i} {} below MINIMUM_LIQUIDITY", m.reserve[i]));
+        }
+    }
+    let held: U256 = m.lp.iter().fold(as_u256(MINIMUM_LIQUIDITY), |acc, b| acc + *b);
+    if held != m.lp_supply {
+        found.push(format!("LP supply {} != balances + locked {}", m.lp_supply, held));
+    }
+    // Rounding always favours the pool, so no action may dilute an LP share.
+    let ((k, s2), (k_prev, s2_prev)) = (m.share_value(), prev.share_value());
+    if k * s2_prev < k_prev * s2 {
+        found.push(format!("LP share value fell after {action}"));
+    }
+    if m.fee_bps > as_u256(MAX_FEE_BPS) {
+        found.push(format!("fee {} above MAX_FEE_BPS", m.fee_bps));
+    }
+    found
+}
+
+fn env_u64(name: &str, default: u64) -> u64 {
+    match std::env::var(name) {
+        Ok(v) => v
+            .strip_prefix("0x")
+            .map_or_else(|| v.parse(), |hex| u64::from_str_radix(hex, 16))
+            .unwrap_or_else(|_| panic!("{name} must be an integer")),
+        Err(_) => default,
+    }
+}
+
+fn run_soak(seed: u64, steps: u64) -> Report {
+    let mut rng = Rng(seed);
+    let unit = U256::from(1_000_000_000u64);
+    let mut m = Model::seeded(unit * U256::from(rng.range(1_000, 5_000)), unit * U256::from(rng.range(1_000, 5_000)));
+    let mut report = Report {
+        seed,
+        steps,
+        stats: BTreeMap::new(),
+        reverts: BTreeMap::new(),
+        violations: Vec::new(),
+        violation_count: 0,
+        final_state: String::new(),
+    };
+
+    for step in 0..steps {
+        let prev = m.clone();
+        let roll = rng.range(0, 99);
+        let (action, result) = match roll {
+            0..=59 => {
+                let i = (rng.next() % 2) as usize;
+                // Mostly small trades, occasionally one at or past the size cap.
+                let bps = if rng.chance(10) { rng.range(800, 1_200) } else { rng.range(1, 300) };
+                ("trader", m.swap(i, m.reserve[i] * U256::from(bps) / as_u256(BPS)))
+            }
+            60..=71 => {
+                // Deposits scale with the pool so LP supply keeps pace with fee growth.
+                let who = rng.range(0, LPS as u64 - 1) as usize;
+                let d0 = m.reserve[0] * U256::from(rng.range(1, 500)) / as_u256(BPS);
+                let d1 = m.reserve[1] * U256::from(rng.range(1, 500)) / as_u256(BPS);
+                ("lp_add", m.add_liquidity(who, d0, d1))
+            }
+            72..=81 => {
+                let who = rng.range(0, LPS as u64 - 1) as usize;
+                let share = m.lp[who] * U256::from(rng.range(1, 10)) / U256::from(100u64);
+                ("lp_remove", m.remove_liquidity(who, share))
+            }
+            // Each repaid flash grows a reserve by the fee rate (compounding), so borrowers are rare.
+            82..=83 if rng.chance(10) => {
+                let i = (rng.next() % 2) as usize;
+                let amount = m.reserve[i] * U256::from(rng.range(1, 5_000)) / as_u256(BPS);
+                // flash_swap wants k to grow by the fee rate, i.e. a fee on the whole reserve.
+                let fee = (m.reserve[i] * m.fee_bps).div_ceil(as_u256(FEE_DENOMINATOR)) + U256::from(1u64);
+                // One borrower in eight tries to short the fee.
+                let repay = if rng.chance(8) { amount + fee / U256::from(2u64) } else { amount + fee };
+                ("flash", m.flash(i, amount, repay))
+            }
+            82..=89 => ("keeper", m.withdraw_fees((rng.next() % 2) as usize)),
+            90..=92 => {
+                let i = (rng.next() % 2) as usize;
+                let donated = m.reserve[i] * U256::from(rng.range(1, 50)) / as_u256(BPS);
+                m.balance[i] += donated;
+                ("donation", Ok(REVEAL_GAS_BASE + REVEAL_GAS_TOKEN_TRANSFER))
+            }
+            93..=94 => ("sync", m.sync()),
+            95..=97 => {
+                let fee = U256::from(rng.range(0, MAX_FEE_BPS + 200));
+                if fee > as_u256(MAX_FEE_BPS) {
+                    ("set_fee", Err("FEE_TOO_HIGH"))
+                } else {
+                    m.fee_bps = fee;
+                    ("set_fee", Ok(REVEAL_GAS_BASE))
+                }
+            }
+            _ => {
+                // Pauses are rare and short: always unpause, pause one time in four.
+                if m.paused || rng.chance(4) {
+                    m.paused = !m.paused;
+                }
+                ("pause", Ok(REVEAL_GAS_BASE))
+            }
+        };
+
+        let stats = report.stats.entry(action).or_default();
+        match result {
+            Ok(gas) => {
+                stats.gas_min = if stats.ok == 0 { gas } else { stats.gas_min.min(gas) };
+                stats.gas_max = stats.gas_max.max(gas);
+                stats.gas_total += gas;
+                stats.ok += 1;
+            }
+            Err(reason) => {
+                stats.reverted += 1;
+                *report.reverts.entry(reason).or_default() += 1;
+                // A revert rolls back every effect.
+                m = prev.clone();
+            }
+        }
+        for msg in check_invariants(&m, &prev, action) {
+            report.violation(step, action, msg);
+        }
+    }
+
+    report.final_state = format!(
+        "reserves=({}, {}) balances=({}, {}) lp_supply={} fee_bps={} paused={}",
+        m.reserve[0], m.reserve[1], m.balance[0], m.balance[1], m.lp_supply, m.fee_bps, m.paused
+    );
+    report
+}
+
+#[test]
+fn soak_random_market_keeps_invariants() {
+    let seed = env_u64("OAK_SOAK_SEED", DEFAULT_SEED);
+    let steps = env_u64("OAK_SOAK_STEPS", DEFAULT_STEPS);
+    let report = run_soak(seed, steps);
+    let rendered = report.render();
+    eprintln!("{rendered}");
+    if let Ok(path) = std::env::var("OAK_SOAK_REPORT") {
+        std::fs::write(&path, &rendered).expect("write soak report");
+    }
+    assert_eq!(report.violation_count, 0, "soak invariant violations (replay with OAK_SOAK_SEED={seed:#x}):\n{rendered}");
+    let executed: u64 = report.stats.values().map(|s| s.ok).sum();
+    assert!(executed > steps / 2, "scenario mostly reverted; generator is out of range");
+}
+
+#[test]
+fn soak_is_reproducible_from_seed() {
+    let a = run_soak(42, 500);
+    let b = run_soak(42, 500);
+    assert_eq!(a.render(), b.render());
+    assert_ne!(a.render(), run_soak(43, 500).render());
+}
+
+#[test]
+fn soak_detects_a_broken_invariant() {
+    let m = Model::seeded(U256::from(1_000_000u64), U256::from(1_000_000u64));
+    let mut broken = m.clone();
+    broken.balance[0] -= U256::from(1u64);
+    let found = check_invariants(&broken, &m, "test");
+    assert!(found.iter().any(|v| v.contains("insolvent")), "{found:?}");
+}
@@ -0,0 +1,179 @@
+//! Storage layout compatibility: slot assignments of every `sol_storage!` struct are pinned.
+//!
+//! Deployed contracts keep their storage across upgrades, so reordering, resizing, retyping or
+//! removing a field silently corrupts live state. This test derives the layout from
+//! `src/state.rs` with the `#[storage]` packing rules (fields packed into 32-byte words in
+//! declaration order, maps and nested structs starting a fresh word) and compares it with the
+//! checked-in `tests/storage_layout.snap`. The derived slot count of every struct is
+//! cross-checked against the compiled `StorageType::REQUIRED_SLOTS`, so the parser cannot drift
+//! from the real types.
+//!
+//! Adding fields where they shift nothing (the end of a struct) only needs a snapshot refresh:
+//!
+//!   OAK_UPDATE_STORAGE_LAYOUT=1 cargo test --test storage_layout
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use oak_protocol::state::{
+    AuditRecord, BatchOrder, CommitVoucher, CommitmentSlot, EpochStats, LpLock, LpReceipt, OakDEX, OakSentinel, OracleCheckpoint, PendingExposure,
+    PoolData, SwapBatch, TokenRiskParams, TradeRecord,
+};
+use stylus_sdk::storage::StorageType;
+
+const STATE_SRC: &str = include_str!("../src/state.rs");
+
+/// (type, name) of each field per struct, in declaration order.
+fn parse_structs() -> Vec<(String, Vec<(String, String)>)> {
+    let start = STATE_SRC.find("sol_storage! {").expect("sol_storage! block present");
+    let mut structs = Vec::new();
+    let mut current: Option<(String, Vec<(String, String)>)> = None;
+    for line in STATE_SRC[start..].lines().skip(1) {
+        let line = line.trim();
+        if let Some(rest) = line.strip_prefix("pub struct ") {
+            let name = rest.trim_end_matches('{').trim().to_string();
+            current = Some((name, Vec::new()));
+        } else if line == "}" {
+            match current.take() {
+                Some(done) => structs.push(done),
+                None => break,
+            }
+        } else if let Some((_, fields)) = current.as_mut() {
+            if line.is_empty() || line.starts_with("//") {
+                continue;
+            }
+            let decl = line.trim_end_matches(';');
+            let split = decl.rfind(' ').expect("field declaration `Type name;`");
+            fields.push((decl[..split].trim().to_string(), decl[split + 1..].to_string()));
+        }
+    }
+    structs
+}
+
+type Structs = HashMap<String, Vec<(String, String)>>;
+
+/// (SLOT_BYTES, REQUIRED_SLOTS) of a field type, as the stylus storage types define them.
+fn type_size(ty: &str, structs: &Structs) -> (usize, usize) {
+    match ty {
+        "StorageU256" => (32, 0),
+        "StorageAddress" => (20, 0),
+        "StorageBool" => (1, 0),
+        _ if ty.starts_with("StorageMap<") => (32, 0),
+        _ => {
+            let fields = structs
+                .get(ty)
+                .unwrap_or_else(|| panic!("unknown storage type `{ty}`: teach type_size about it"));
+            (32, required_slots(fields, structs))
+        }
+    }
+}
+
+/// `REQUIRED_SLOTS` as the `#[storage]` derive computes it.
+///
+/// @dev The derive does not count a partly used word before a nested struct, so a struct like
+///      `PoolData` reports one slot fewer than its fields span. Harmless while such structs only
+///      live behind map keys, but it is why slot counts are checked separately from the layout.
+fn required_slots(fields: &[(String, String)], structs: &Structs) -> usize {
+    let (mut total, mut space) = (0usize, 32usize);
+    for (ty, _) in fields {
+        let (bytes, words) = type_size(ty, structs);
+        if words > 0 {
+            total += words;
+            space = 32;
+        } else {
+            if space < bytes {
+                space = 32;
+                total += 1;
+            }
+            space -= bytes;
+        }
+    }
+    if space != 32 || total == 0 {
+        total += 1;
+    }
+    total
+}
+
+/// Field offsets as the `#[storage]` derive assigns them (pack right-to-left, open a new word
+/// when out of space; maps and nested structs take whole words), as
+/// `Struct.field slot=S offset=O type=T` lines.
+fn layout_of(name: &str, fields: &[(String, String)], structs: &Structs, out: &mut Vec<String>) {
+    let (mut slot, mut space) = (0usize, 32usize);
+    for (ty, field) in fields {
+        let (bytes, words) = type_size(ty, structs);
+        if space < bytes {
+            space = 32;
+            slot += 1;
+        }
+        space -= bytes;
+        out.push(format!("{name}.{field} slot={slot} offset={space} type={ty}"));
+        if words > 0 {
+            slot += words;
+            space = 32;
+        }
+    }
+}
+
+/// Layout lines of every struct (declaration order) and the `REQUIRED_SLOTS` of each.
+fn derive_layout() -> (Vec<String>, HashMap<String, usize>) {
+    let parsed = parse_structs();
+    let structs: Structs = parsed.iter().cloned().collect();
+    let mut lines = Vec::new();
+    let mut struct_slots = HashMap::new();
+    for (name, fields) in &parsed {
+        layout_of(name, fields, &structs, &mut lines);
+        struct_slots.insert(name.clone(), required_slots(fields, &structs));
+    }
+    (lines, struct_slots)
+}
+
+fn snapshot_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/storage_layout.snap")
+}
+
+#[test]
+fn derived_slot_counts_match_the_compiled_structs() {
+    let (_, slots) = derive_layout();
+    let compiled = [
+        ("CommitmentSlot", <CommitmentSlot as StorageType>::REQUIRED_SLOTS),
+        ("CommitVoucher", <CommitVoucher as StorageType>::REQUIRED_SLOTS),
+        ("TokenRiskParams", <TokenRiskParams as StorageType>::REQUIRED_SLOTS),
+        ("PoolData", <PoolData as StorageType>::REQUIRED_SLOTS),
+        ("PendingExposure", <PendingExposure as StorageType>::REQUIRED_SLOTS),
+        ("BatchOrder", <BatchOrder as StorageType>::REQUIRED_SLOTS),
+        ("SwapBatch", <SwapBatch as StorageType>::REQUIRED_SLOTS),
+        ("OracleCheckpoint", <OracleCheckpoint as StorageType>::REQUIRED_SLOTS),
+        ("LpLock", <LpLock as StorageType>::REQUIRED_SLOTS),
+        ("LpReceipt", <LpReceipt as StorageType>::REQUIRED_SLOTS),
+        ("TradeRecord", <TradeRecord as StorageType>::REQUIRED_SLOTS),
+        ("AuditRecord", <AuditRecord as StorageType>::REQUIRED_SLOTS),
+        ("EpochStats", <EpochStats as StorageType>::REQUIRED_SLOTS),
+        ("OakDEX", <OakDEX as StorageType>::REQUIRED_SLOTS),
+        ("OakSentinel", <OakSentinel as StorageType>::REQUIRED_SLOTS),
+    ];
+    assert_eq!(slots.len(), compiled.len(), "a sol_storage! struct is missing from this check");
+    for (name, required) in compiled {
+        assert_eq!(slots[name], required, "derived slot count of {name}");
+    }
+}
+
+#[test]
+fn storage_layout_matches_snapshot() {
+    let (lines, _) = derive_layout();
+    let current = lines.join("\n") + "\n";
+    if std::env::var_os("OAK_UPDATE_STORAGE_LAYOUT").is_some() {
+        std::fs::write(snapshot_path(), &current).expect("write storage layout snapshot");
+        return;
+    }
+    let expected = std::fs::read_to_string(snapshot_path()).expect("tests/storage_layout.snap present");
+    if current == expected {
+        return;
+    }
+    let moved: Vec<&str> = expected.lines().filter(|l| !lines.iter().any(|c| c == l)).collect();
+    assert!(
+        moved.is_empty(),
+        "storage layout changed for existing fields (breaks live deployments):\n{}",
+        moved.join("\n")
+    );
+    panic!("new storage fields only; refresh the snapshot with OAK_UPDATE_STORAGE_LAYOUT=1");
+}
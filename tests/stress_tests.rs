@@ -7,7 +7,7 @@
 use oak_protocol::{
     constants::{as_u256, FEE_DENOMINATOR, INITIAL_FEE},
     errors::ERR_REENTRANT_CALL,
-    logic::{compute_fee_split, get_amount_out_with_fee},
+    logic::{compute_fee_split, get_amount_in_with_fee, get_amount_out_with_fee, min_amount_in_for_output},
 };
 
 use stylus_sdk::alloy_primitives::U256;
@@ -39,12 +39,12 @@ fn greedy_trader_fee_accounting_is_exact() {
             get_amount_out_with_fee(amount_per_swap, reserve_in, reserve_out, fee_bps).unwrap();
 
         // Compute fee split for this swap (60/20/20: LP, Treasury, Buyback)
-        let (_effective_in, treasury_fee, lp_fee, buyback_fee) =
+        let (_effective_in, treasury_fee, lp_fee, buyback_fee, gas_rebate) =
             compute_fee_split(amount_per_swap, fee_bps).unwrap();
 
-        // Update cumulative accounting
+        // Update cumulative accounting (no revealer: the gas rebate stays with the treasury)
         total_input = total_input + amount_per_swap;
-        total_treasury_fees = total_treasury_fees + treasury_fee;
+        total_treasury_fees = total_treasury_fees + treasury_fee + gas_rebate;
         total_lp_fees = total_lp_fees + lp_fee;
         total_buyback_fees = total_buyback_fees + buyback_fee;
 
@@ -187,9 +187,9 @@ fn dust_and_limits_are_safely_handled() {
     );
 
     // Total fee for big_amount_in must be consistent with fee_bps (no overflow).
-    let (_effective_in, treasury_fee, lp_fee, buyback_fee) =
+    let (_effective_in, treasury_fee, lp_fee, buyback_fee, gas_rebate) =
         compute_fee_split(big_amount_in, fee_bps).expect("fee split must not overflow");
-    let total_fee = treasury_fee + lp_fee + buyback_fee;
+    let total_fee = treasury_fee + lp_fee + buyback_fee + gas_rebate;
     let expected_fee = big_amount_in * fee_bps / as_u256(FEE_DENOMINATOR);
     assert_eq!(
         total_fee, expected_fee,
@@ -197,3 +197,81 @@ fn dust_and_limits_are_safely_handled() {
     );
 }
 
+#[test]
+fn min_amount_in_is_exact_dust_threshold() {
+    let fee_bps = as_u256(INITIAL_FEE);
+    let cases = [
+        (U256::from(1_000_000u64), U256::from(2_000_000u64)),
+        (U256::from(2_000_000u64), U256::from(1_000u64)),
+        (U256::from(1_000_000_000_000_000_000u64), U256::from(3u64)),
+    ];
+    for (reserve_in, reserve_out) in cases {
+        let min_in = min_amount_in_for_output(reserve_in, reserve_out, fee_bps).unwrap();
+        let out_at_min = get_amount_out_with_fee(min_in, reserve_in, reserve_out, fee_bps).unwrap();
+        assert!(out_at_min > U256::ZERO, "minimum input must produce output");
+        let below = min_in - U256::from(1u64);
+        if !below.is_zero() {
+            let out_below = get_amount_out_with_fee(below, reserve_in, reserve_out, fee_bps).unwrap();
+            assert_eq!(out_below, U256::ZERO, "one unit below minimum must be dust");
+        }
+    }
+}
+
+#[test]
+fn exact_out_quote_is_tight_inverse_of_exact_in() {
+    let fee_bps = as_u256(INITIAL_FEE);
+    let reserve_in = U256::from(5_000_000u64);
+    let reserve_out = U256::from(3_000_000u64);
+    for target in [1u64, 7, 1_000, 123_457, 1_000_000] {
+        let target = U256::from(target);
+        let amount_in = get_amount_in_with_fee(target, reserve_in, reserve_out, fee_bps).unwrap();
+        let out = get_amount_out_with_fee(amount_in, reserve_in, reserve_out, fee_bps).unwrap();
+        assert!(out >= target, "quoted input must deliver the requested output");
+        let out_less = get_amount_out_with_fee(amount_in - U256::from(1u64), reserve_in, reserve_out, fee_bps).unwrap();
+        assert!(out_less < target || amount_in == U256::from(1u64), "quoted input must be minimal");
+    }
+}
+
+// -----------------------------------------------------------------------------
+// 5. "Unbounded Growth" – every iteration cap fits one block regardless of state size
+// -----------------------------------------------------------------------------
+
+/// Arbitrum One block gas limit.
+const BLOCK_GAS_LIMIT: u64 = 32_000_000;
+/// Conservative per-operation gas costs (cold access, fresh slot writes).
+const SLOAD: u64 = 2_100;
+const SSTORE_NEW: u64 = 22_100;
+const SSTORE_UPDATE: u64 = 5_000;
+const LOG: u64 = 1_500;
+
+#[test]
+fn iteration_caps_fit_in_one_block() {
+    use oak_protocol::constants::{
+        MAX_AGGREGATE_CALLS, MAX_BATCH_CANCEL, MAX_BATCH_POSITIONS, MAX_OBSERVATION_CARDINALITY,
+        MAX_OBSERVATION_GROWTH_PER_CALL, MAX_OBSERVE_POINTS, MAX_PAGE_SIZE, MAX_PATH_LENGTH, REVEAL_GAS_SWAP,
+        REVEAL_GAS_TOKEN_TRANSFER,
+    };
+
+    // Loops whose per-item cost is bounded, with their caps.
+    let ring_search = 64 - MAX_OBSERVATION_CARDINALITY.leading_zeros() as u64 + 2;
+    let worst_cases = [
+        // cancel_orders / cancel_orders_in_range: owner + status reads, refund, 3 writes.
+        ("batch cancel", MAX_BATCH_CANCEL, 3 * SLOAD + REVEAL_GAS_TOKEN_TRANSFER + 3 * SSTORE_UPDATE),
+        // swap_exact_tokens_for_tokens and permit batches: one swap (and one permit) per hop.
+        ("multi-hop", MAX_PATH_LENGTH, REVEAL_GAS_SWAP + REVEAL_GAS_TOKEN_TRANSFER),
+        // batch_execute_positions: per-position reads, transfer and bookkeeping.
+        ("batch positions", MAX_BATCH_POSITIONS, 8 * SLOAD + REVEAL_GAS_TOKEN_TRANSFER + 4 * SSTORE_UPDATE + LOG),
+        // increase_observation_cardinality: three fresh slots per ring entry.
+        ("ring growth", MAX_OBSERVATION_GROWTH_PER_CALL, 3 * SSTORE_NEW),
+        // observe: binary search over the full ring per point.
+        ("observe", MAX_OBSERVE_POINTS, ring_search * 3 * SLOAD),
+        // Paginated journal: seven fields per entry.
+        ("journal page", MAX_PAGE_SIZE, 7 * SLOAD),
+        // aggregate_views: each call is a bounded view; budget one multi-hop quote each.
+        ("aggregate", MAX_AGGREGATE_CALLS, MAX_PATH_LENGTH * 6 * SLOAD),
+    ];
+    for (name, cap, per_item) in worst_cases {
+        let total = cap * per_item;
+        assert!(total < BLOCK_GAS_LIMIT / 2, "{name}: {total} gas at cap {cap} leaves no headroom");
+    }
+}